@@ -85,11 +85,11 @@ async fn main() {
                 "message": message,
             }))
         })
-        // sub route support - api version health check
-        .sub(
+        // nested router support - api version health check
+        .nest(
             "/api",
             Router::new()
-                .sub(
+                .nest(
                     "/v1",
                     Router::new().get("/status", async || {
                         Json(json!({
@@ -97,7 +97,7 @@ async fn main() {
                         }))
                     }),
                 )
-                .sub(
+                .nest(
                     "/v2",
                     Router::new().get("/status", async || {
                         Json(json!({
@@ -22,17 +22,16 @@ use rama::{
         service::web::{Router, response::Html},
         ws::{
             Message, ProtocolError,
-            handshake::server::{ServerWebSocket, WebSocketAcceptor},
+            handshake::server::{ServerWebSocket, websocket},
         },
     },
     layer::AddExtensionLayer,
-    service::service_fn,
     tcp::server::TcpListener,
     telemetry::tracing::{debug, error, info, level_filters::LevelFilter, warn},
 };
 
 use serde::{Deserialize, Serialize};
-use std::time::Duration;
+use std::{convert::Infallible, time::Duration};
 use tokio::sync::broadcast;
 use tracing_subscriber::{EnvFilter, fmt, layer::SubscriberExt, util::SubscriberInitExt};
 
@@ -52,65 +51,63 @@ async fn main() {
     graceful.spawn_task_fn(async |guard| {
         let server = HttpServer::http1().service(Router::new().get("/", Html(INDEX)).get(
             "/chat",
-            WebSocketAcceptor::new().into_service(service_fn(
-                async |ctx: Context, mut ws: ServerWebSocket| {
-                    let state = ctx.get::<State>().unwrap().clone();
-                    let mut handler = WsHandler {
-                        nickname: None,
-                        broadcast_tx: state.broadcast_tx,
-                    };
-                    let mut broadcast_rx = state.broadcast_rx;
+            websocket(async |ctx: Context, mut ws: ServerWebSocket| {
+                let state = ctx.get::<State>().unwrap().clone();
+                let mut handler = WsHandler {
+                    nickname: None,
+                    broadcast_tx: state.broadcast_tx,
+                };
+                let mut broadcast_rx = state.broadcast_rx;
 
-                    loop {
-                        tokio::select! {
-                            result = ws.recv_message() => {
-                                if handler.handle_inc_ws_message(result).await {
-                                    return Ok(());
-                                }
+                loop {
+                    tokio::select! {
+                        result = ws.recv_message() => {
+                            if handler.handle_inc_ws_message(result).await {
+                                return Ok::<_, Infallible>(());
                             }
-                            result = broadcast_rx.recv() => {
-                                match result {
-                                    Ok(BroadcastMessage::User { name, message }) => {
-                                        match serde_json::to_string(&ChatMessage {
-                                            r#type: "user",
-                                            name: Some(name.as_ref()),
-                                            message: Some(message.as_ref()),
-                                        }) {
-                                            Ok(text) => {
-                                                if let Err(err) = ws.send_message(text.into()).await {
-                                                    warn!("failed to send user message via WS socket: {err}");
-                                                }
-                                            }
-                                            Err(err) => {
-                                                warn!("failed to json serialize user message: {err}");
+                        }
+                        result = broadcast_rx.recv() => {
+                            match result {
+                                Ok(BroadcastMessage::User { name, message }) => {
+                                    match serde_json::to_string(&ChatMessage {
+                                        r#type: "user",
+                                        name: Some(name.as_ref()),
+                                        message: Some(message.as_ref()),
+                                    }) {
+                                        Ok(text) => {
+                                            if let Err(err) = ws.send_message(text.into()).await {
+                                                warn!("failed to send user message via WS socket: {err}");
                                             }
                                         }
+                                        Err(err) => {
+                                            warn!("failed to json serialize user message: {err}");
+                                        }
                                     }
-                                    Ok(BroadcastMessage::System(message)) => {
-                                        match serde_json::to_string(&ChatMessage {
-                                            r#type: "system",
-                                            name: None,
-                                            message: Some(message.as_ref()),
-                                        }) {
-                                            Ok(text) => {
-                                                if let Err(err) = ws.send_message(text.into()).await {
-                                                    warn!("failed to send system message via WS socket: {err}");
-                                                }
-                                            }
-                                            Err(err) => {
-                                                warn!("failed to json serialize system message: {err}");
+                                }
+                                Ok(BroadcastMessage::System(message)) => {
+                                    match serde_json::to_string(&ChatMessage {
+                                        r#type: "system",
+                                        name: None,
+                                        message: Some(message.as_ref()),
+                                    }) {
+                                        Ok(text) => {
+                                            if let Err(err) = ws.send_message(text.into()).await {
+                                                warn!("failed to send system message via WS socket: {err}");
                                             }
                                         }
-                                    }
-                                    Err(err) => {
-                                        warn!("failed to receive broadcast message: {err}");
+                                        Err(err) => {
+                                            warn!("failed to json serialize system message: {err}");
+                                        }
                                     }
                                 }
+                                Err(err) => {
+                                    warn!("failed to receive broadcast message: {err}");
+                                }
                             }
                         }
                     }
-                },
-            )),
+                }
+            }),
         ));
         info!("open mini web chat @ http://127.0.0.1:62033");
         info!("or connect directly to ws://127.0.0.1:62033/chat (via 'rama ws')");
@@ -15,7 +15,7 @@ use rama::{
         Body, BodyExtractExt, Request, Response, StatusCode,
         proto::h2,
         service::web::{
-            extract::Path,
+            extract::{Form, FromRequest, Path},
             response::{self, IntoResponse, Json},
         },
         ws::{
@@ -483,10 +483,54 @@ pub(super) async fn post_api_xml_http_request_number(
 // endpoints: form
 //------------------------------------------
 
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum FormSource {
+    Web,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum TurtlesAnswer {
+    Yes,
+    No,
+    Maybe,
+}
+
+#[derive(Debug, Deserialize)]
+struct FormInput {
+    source: Option<FormSource>,
+    rating: Option<u8>,
+    turtles: Option<TurtlesAnswer>,
+}
+
+impl From<FormInput> for Table {
+    fn from(input: FormInput) -> Self {
+        let mut rows = Vec::with_capacity(3);
+        if let Some(source) = input.source {
+            rows.push(("Source".to_owned(), format!("{source:?}")));
+        }
+        if let Some(rating) = input.rating {
+            rows.push(("Rating".to_owned(), rating.to_string()));
+        }
+        if let Some(turtles) = input.turtles {
+            rows.push(("Likes Turtles".to_owned(), format!("{turtles:?}")));
+        }
+        Self {
+            title: "📝 Form Input".to_owned(),
+            rows,
+        }
+    }
+}
+
 pub(super) async fn form(mut ctx: Context, req: Request) -> Result<Html, Response> {
     let ja4h = get_ja4h_info(&req);
 
-    let (mut parts, _) = req.into_parts();
+    let (mut parts, body) = req.into_parts();
+
+    let Form(input) = Form::<FormInput>::from_request(Request::from_parts(parts.clone(), body))
+        .await
+        .map_err(IntoResponse::into_response)?;
 
     let user_agent_info = get_user_agent_info(&ctx).await;
 
@@ -536,6 +580,7 @@ pub(super) async fn form(mut ctx: Context, req: Request) -> Result<Html, Respons
         ctx.get::<Arc<State>>().unwrap().data_source.clone().into(),
         user_agent_info.into(),
         request_info.into(),
+        input.into(),
         Table {
             title: "🚗 Http Headers".to_owned(),
             rows: http_info.headers,
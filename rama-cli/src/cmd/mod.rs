@@ -5,6 +5,7 @@ pub mod fp;
 pub mod http;
 pub mod ip;
 pub mod proxy;
+pub mod run;
 pub mod serve;
 pub mod tls;
 pub mod ws;
@@ -0,0 +1,46 @@
+//! Run the servers and proxies described by a config file.
+
+use clap::Args;
+use rama::{
+    cli::config::Config,
+    error::{BoxError, ErrorContext},
+    telemetry::tracing::{self, level_filters::LevelFilter},
+};
+
+use std::{path::PathBuf, time::Duration};
+
+#[derive(Debug, Args)]
+/// rama run (runs the servers and proxies described by a config file)
+pub struct CliCommandRun {
+    /// The path to the config file
+    ///
+    /// Parsed as JSON in case of a `.json` extension, as YAML in case of
+    /// a `.yaml` or `.yml` extension, as TOML otherwise.
+    #[arg()]
+    config: PathBuf,
+
+    #[arg(long, default_value_t = 30)]
+    /// the time in seconds to wait for the connections to complete on shutdown
+    shutdown_timeout: u64,
+}
+
+/// run the servers and proxies described by the config file
+pub async fn run(cfg: CliCommandRun) -> Result<(), BoxError> {
+    crate::trace::init_tracing(LevelFilter::INFO);
+
+    let config = Config::from_path(&cfg.config).context("load config")?;
+    tracing::info!(
+        "starting {} listener(s) from config: {:?}",
+        config.listeners.len(),
+        cfg.config,
+    );
+
+    let graceful = rama::graceful::Shutdown::default();
+    config.spawn(&graceful).await?;
+
+    graceful
+        .shutdown_with_limit(Duration::from_secs(cfg.shutdown_timeout))
+        .await?;
+
+    Ok(())
+}
@@ -1,13 +1,9 @@
-use opentelemetry_otlp::{SpanExporter, WithHttpConfig};
 use rama::{
-    http::{client::EasyHttpWebClient, service::opentelemetry::OtelExporter},
+    error::{ErrorContext, OpaqueError},
+    http::client::EasyHttpWebClient,
     net::client::pool::http::HttpPooledConnectorConfig,
     telemetry::{
-        opentelemetry::{
-            KeyValue,
-            sdk::{Resource, trace::SdkTracerProvider},
-            trace::TracerProvider,
-        },
+        otlp::OtlpConfig,
         tracing::{self, layer},
     },
 };
@@ -17,13 +13,22 @@ use tracing_subscriber::{
 };
 
 pub fn init_tracing(default_directive: impl Into<Directive>) {
+    let default_directive = default_directive.into();
     if std::env::var("OTEL_EXPORTER_OTLP_ENDPOINT").is_ok() {
-        init_structured(default_directive);
-        tracing::trace!("structured (OTEL) tracing init complete");
-    } else {
-        init_default(default_directive);
-        tracing::trace!("default tracing init complete");
+        match init_structured(default_directive.clone()) {
+            Ok(()) => {
+                tracing::trace!("structured (OTEL) tracing init complete");
+                return;
+            }
+            Err(err) => {
+                init_default(default_directive);
+                tracing::warn!("failed to init structured (OTEL) tracing, using default: {err}");
+                return;
+            }
+        }
     }
+    init_default(default_directive);
+    tracing::trace!("default tracing init complete");
 }
 
 fn init_default(default_directive: impl Into<Directive>) {
@@ -37,34 +42,20 @@ fn init_default(default_directive: impl Into<Directive>) {
         .init();
 }
 
-fn init_structured(default_directive: impl Into<Directive>) {
-    let svc = EasyHttpWebClient::builder()
+fn init_structured(default_directive: impl Into<Directive>) -> Result<(), OpaqueError> {
+    let client = EasyHttpWebClient::builder()
         .with_default_transport_connector()
         .without_tls_proxy_support()
         .without_proxy_support()
         .with_tls_support_using_boringssl(None)
         .with_connection_pool(HttpPooledConnectorConfig::default())
-        .expect("build http exporter client service")
+        .context("build http exporter client service")?
         .build();
-    let client = OtelExporter::new(svc);
 
-    let exportor = SpanExporter::builder()
-        .with_http()
-        .with_http_client(client)
-        .build()
-        .unwrap();
-
-    let provider = SdkTracerProvider::builder()
-        .with_batch_exporter(exportor)
-        .with_resource(
-            Resource::builder()
-                .with_attribute(KeyValue::new("service.name", "rama"))
-                .build(),
-        )
-        .build();
-
-    let tracer = provider.tracer("rama-cli");
-    let telemetry = layer().with_tracer(tracer);
+    let telemetry = OtlpConfig::from_env()
+        .install_with_client(client)
+        .context("install OTLP exporters")?;
+    let telemetry = layer().with_tracer(telemetry.tracer("rama-cli"));
 
     tracing_subscriber::registry()
         .with(telemetry)
@@ -81,4 +72,5 @@ fn init_structured(default_directive: impl Into<Directive>) {
                 .from_env_lossy(),
         )
         .init();
+    Ok(())
 }
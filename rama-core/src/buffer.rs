@@ -0,0 +1,288 @@
+//! Pooling of the byte buffers used to copy, aggregate and (de)compress data.
+//!
+//! A [`BufferPool`] hands out [`PooledBuffer`]s, which return to the pool
+//! once dropped, such that busy proxies do not allocate (and free) a fresh
+//! buffer for every tunnel, body or compression stream they handle.
+//!
+//! Most users can rely on the [`BufferPool::global`] pool, which is used by default
+//! by rama itself, while a dedicated [`BufferPool`] can be created in case
+//! a different chunk size or limit is desired.
+
+use crate::bytes::BytesMut;
+use parking_lot::Mutex;
+use std::{
+    fmt,
+    ops::{Deref, DerefMut},
+    sync::{
+        Arc, LazyLock,
+        atomic::{AtomicU64, Ordering},
+    },
+};
+
+/// A pool of [`BytesMut`] buffers, all allocated with the same chunk size.
+///
+/// All clones share the same buffers and metrics.
+#[derive(Clone)]
+pub struct BufferPool(Arc<PoolInner>);
+
+struct PoolInner {
+    chunk_size: usize,
+    max_pooled: usize,
+    buffers: Mutex<Vec<BytesMut>>,
+    reused: AtomicU64,
+    allocated: AtomicU64,
+    returned: AtomicU64,
+    discarded: AtomicU64,
+}
+
+static GLOBAL_POOL: LazyLock<BufferPool> = LazyLock::new(BufferPool::default);
+
+impl BufferPool {
+    /// The chunk size used by the [`Default`] (and [`global`]) pool.
+    ///
+    /// [`global`]: Self::global
+    pub const DEFAULT_CHUNK_SIZE: usize = 16 * 1024;
+
+    /// The maximum amount of idle buffers kept by the [`Default`] (and [`global`]) pool.
+    ///
+    /// [`global`]: Self::global
+    pub const DEFAULT_MAX_POOLED: usize = 1024;
+
+    /// Create a new [`BufferPool`] handing out buffers of (at least) `chunk_size` bytes,
+    /// keeping at most `max_pooled` idle buffers around.
+    ///
+    /// A `chunk_size` of zero is replaced by [`Self::DEFAULT_CHUNK_SIZE`].
+    #[must_use]
+    pub fn new(chunk_size: usize, max_pooled: usize) -> Self {
+        let chunk_size = if chunk_size == 0 {
+            Self::DEFAULT_CHUNK_SIZE
+        } else {
+            chunk_size
+        };
+        Self(Arc::new(PoolInner {
+            chunk_size,
+            max_pooled,
+            buffers: Mutex::new(Vec::new()),
+            reused: AtomicU64::new(0),
+            allocated: AtomicU64::new(0),
+            returned: AtomicU64::new(0),
+            discarded: AtomicU64::new(0),
+        }))
+    }
+
+    /// Returns the process-wide [`BufferPool`], created with the default settings,
+    /// which is used by rama wherever no dedicated pool is configured.
+    #[must_use]
+    pub fn global() -> &'static Self {
+        &GLOBAL_POOL
+    }
+
+    /// Returns the size of the buffers handed out by this pool.
+    #[must_use]
+    pub fn chunk_size(&self) -> usize {
+        self.0.chunk_size
+    }
+
+    /// Returns the maximum amount of idle buffers kept by this pool.
+    #[must_use]
+    pub fn max_pooled(&self) -> usize {
+        self.0.max_pooled
+    }
+
+    /// Get an empty buffer, with a capacity of at least [`Self::chunk_size`] bytes,
+    /// from the pool, or allocate a new one in case no idle buffer is available.
+    #[must_use]
+    pub fn get(&self) -> PooledBuffer {
+        let buffer = self.0.buffers.lock().pop();
+        let buffer = match buffer {
+            Some(buffer) => {
+                self.0.reused.fetch_add(1, Ordering::Relaxed);
+                buffer
+            }
+            None => {
+                self.0.allocated.fetch_add(1, Ordering::Relaxed);
+                BytesMut::with_capacity(self.0.chunk_size)
+            }
+        };
+        PooledBuffer {
+            buffer,
+            pool: self.clone(),
+        }
+    }
+
+    /// Returns a snapshot of the metrics of this pool.
+    #[must_use]
+    pub fn metrics(&self) -> BufferPoolMetrics {
+        BufferPoolMetrics {
+            idle: self.0.buffers.lock().len(),
+            reused: self.0.reused.load(Ordering::Relaxed),
+            allocated: self.0.allocated.load(Ordering::Relaxed),
+            returned: self.0.returned.load(Ordering::Relaxed),
+            discarded: self.0.discarded.load(Ordering::Relaxed),
+        }
+    }
+
+    fn put_back(&self, mut buffer: BytesMut) {
+        buffer.clear();
+        // buffers which were split off (e.g. frozen into `Bytes`) below the chunk size
+        // are of no use anymore, while those that grew far beyond it would hoard memory
+        let capacity = buffer.capacity();
+        if capacity >= self.0.chunk_size && capacity <= self.0.chunk_size.saturating_mul(4) {
+            let mut buffers = self.0.buffers.lock();
+            if buffers.len() < self.0.max_pooled {
+                buffers.push(buffer);
+                self.0.returned.fetch_add(1, Ordering::Relaxed);
+                return;
+            }
+        }
+        self.0.discarded.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+impl Default for BufferPool {
+    fn default() -> Self {
+        Self::new(Self::DEFAULT_CHUNK_SIZE, Self::DEFAULT_MAX_POOLED)
+    }
+}
+
+impl fmt::Debug for BufferPool {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BufferPool")
+            .field("chunk_size", &self.0.chunk_size)
+            .field("max_pooled", &self.0.max_pooled)
+            .field("metrics", &self.metrics())
+            .finish()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// A snapshot of the metrics of a [`BufferPool`].
+pub struct BufferPoolMetrics {
+    idle: usize,
+    reused: u64,
+    allocated: u64,
+    returned: u64,
+    discarded: u64,
+}
+
+impl BufferPoolMetrics {
+    /// Returns the amount of idle buffers currently kept by the pool.
+    #[must_use]
+    pub fn idle(&self) -> usize {
+        self.idle
+    }
+
+    /// Returns the amount of buffers handed out by reusing an idle buffer.
+    #[must_use]
+    pub fn reused(&self) -> u64 {
+        self.reused
+    }
+
+    /// Returns the amount of buffers handed out by allocating a new buffer.
+    #[must_use]
+    pub fn allocated(&self) -> u64 {
+        self.allocated
+    }
+
+    /// Returns the amount of buffers returned to the pool once dropped.
+    #[must_use]
+    pub fn returned(&self) -> u64 {
+        self.returned
+    }
+
+    /// Returns the amount of buffers dropped instead of returned to the pool,
+    /// because the pool was full or their capacity no longer fit the chunk size.
+    #[must_use]
+    pub fn discarded(&self) -> u64 {
+        self.discarded
+    }
+}
+
+/// A [`BytesMut`] buffer taken from a [`BufferPool`], to which it returns once dropped.
+pub struct PooledBuffer {
+    buffer: BytesMut,
+    pool: BufferPool,
+}
+
+impl PooledBuffer {
+    /// Returns the [`BufferPool`] this buffer belongs to.
+    #[must_use]
+    pub fn pool(&self) -> &BufferPool {
+        &self.pool
+    }
+}
+
+impl fmt::Debug for PooledBuffer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PooledBuffer")
+            .field("len", &self.buffer.len())
+            .field("capacity", &self.buffer.capacity())
+            .finish()
+    }
+}
+
+impl Deref for PooledBuffer {
+    type Target = BytesMut;
+
+    fn deref(&self) -> &Self::Target {
+        &self.buffer
+    }
+}
+
+impl DerefMut for PooledBuffer {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.buffer
+    }
+}
+
+impl AsRef<[u8]> for PooledBuffer {
+    fn as_ref(&self) -> &[u8] {
+        &self.buffer
+    }
+}
+
+impl Drop for PooledBuffer {
+    fn drop(&mut self) {
+        let buffer = std::mem::take(&mut self.buffer);
+        self.pool.put_back(buffer);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_buffer_pool_reuses_buffers() {
+        let pool = BufferPool::new(64, 1);
+
+        let mut a = pool.get();
+        assert!(a.capacity() >= 64);
+        a.extend_from_slice(b"hello");
+        let b = pool.get();
+        drop(a);
+        drop(b);
+
+        let c = pool.get();
+        assert!(c.is_empty());
+        assert!(c.capacity() >= 64);
+
+        let mut d = pool.get();
+        // split below the chunk size, no longer worth pooling
+        d.extend_from_slice(&[0; 60]);
+        let _ = d.split();
+        drop(d);
+        drop(c);
+
+        assert_eq!(
+            pool.metrics(),
+            BufferPoolMetrics {
+                idle: 1,
+                reused: 1,
+                allocated: 3,
+                returned: 2,
+                discarded: 2,
+            }
+        );
+    }
+}
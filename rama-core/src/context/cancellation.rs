@@ -0,0 +1,107 @@
+use parking_lot::Mutex;
+use std::{
+    fmt,
+    sync::{
+        Arc, Weak,
+        atomic::{AtomicBool, Ordering},
+    },
+};
+use tokio::sync::Notify;
+
+#[derive(Clone, Default)]
+/// A token to cancel the work done within a [`Context`] and its child contexts.
+///
+/// Cancelling a [`Cancellation`] also cancels all its children,
+/// created using [`Cancellation::child`] or [`Context::child`],
+/// while cancelling a child does not affect its parent.
+///
+/// # Example
+///
+/// ```
+/// use rama_core::context::Cancellation;
+///
+/// let connection = Cancellation::new();
+/// let request = connection.child();
+///
+/// request.cancel();
+/// assert!(!connection.is_cancelled());
+///
+/// let request = connection.child();
+/// connection.cancel();
+/// assert!(request.is_cancelled());
+/// ```
+///
+/// [`Context`]: super::Context
+/// [`Context::child`]: super::Context::child
+pub struct Cancellation(Arc<Node>);
+
+#[derive(Default)]
+struct Node {
+    cancelled: AtomicBool,
+    notify: Notify,
+    children: Mutex<Vec<Weak<Node>>>,
+}
+
+impl Cancellation {
+    /// Create a new [`Cancellation`] which is not yet cancelled.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a child of this [`Cancellation`],
+    /// which is cancelled when this one is cancelled.
+    #[must_use]
+    pub fn child(&self) -> Self {
+        let child = Self::new();
+        let mut children = self.0.children.lock();
+        if self.is_cancelled() {
+            child.cancel();
+        } else {
+            children.retain(|child| child.strong_count() > 0);
+            children.push(Arc::downgrade(&child.0));
+        }
+        child
+    }
+
+    /// Cancel this [`Cancellation`] and all its children.
+    pub fn cancel(&self) {
+        cancel(&self.0);
+    }
+
+    /// Returns `true` in case this [`Cancellation`] has been cancelled.
+    #[must_use]
+    pub fn is_cancelled(&self) -> bool {
+        self.0.cancelled.load(Ordering::Acquire)
+    }
+
+    /// Wait until this [`Cancellation`] is cancelled.
+    pub async fn cancelled(&self) {
+        loop {
+            let notified = self.0.notify.notified();
+            if self.is_cancelled() {
+                return;
+            }
+            notified.await;
+        }
+    }
+}
+
+fn cancel(node: &Node) {
+    if node.cancelled.swap(true, Ordering::AcqRel) {
+        return;
+    }
+    node.notify.notify_waiters();
+    let children = std::mem::take(&mut *node.children.lock());
+    for child in children.iter().filter_map(Weak::upgrade) {
+        cancel(&child);
+    }
+}
+
+impl fmt::Debug for Cancellation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Cancellation")
+            .field("cancelled", &self.is_cancelled())
+            .finish()
+    }
+}
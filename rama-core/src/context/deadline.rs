@@ -0,0 +1,133 @@
+use std::time::{Duration, Instant};
+
+use super::Context;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+/// The point in time by which the processing of a request has to be completed.
+///
+/// A [`Deadline`] is inserted in the [`Context`] by timeout layers (e.g. [`Timeout`]),
+/// such that services further down the stack (e.g. client connectors and retry layers)
+/// can limit their own work to the time remaining, rather than outliving the caller's budget.
+///
+/// Nested deadlines can only ever shorten the budget, see [`Deadline::apply`].
+///
+/// [`Timeout`]: crate::layer::timeout::Timeout
+pub struct Deadline(Instant);
+
+impl Deadline {
+    /// Create a [`Deadline`] at the given point in time.
+    #[must_use]
+    pub const fn at(instant: Instant) -> Self {
+        Self(instant)
+    }
+
+    /// Create a [`Deadline`] which expires after the given duration from now.
+    #[must_use]
+    pub fn after(timeout: Duration) -> Self {
+        let now = Instant::now();
+        // saturate in case of an absurdly large duration (e.g. `Duration::MAX`)
+        Self(
+            now.checked_add(timeout)
+                .unwrap_or_else(|| now + Duration::from_secs(60 * 60 * 24 * 365 * 100)),
+        )
+    }
+
+    /// Returns the point in time at which this [`Deadline`] expires.
+    #[must_use]
+    pub const fn instant(&self) -> Instant {
+        self.0
+    }
+
+    /// Returns the time remaining until this [`Deadline`] expires,
+    /// which is zero once it has expired.
+    #[must_use]
+    pub fn remaining(&self) -> Duration {
+        self.0.saturating_duration_since(Instant::now())
+    }
+
+    /// Returns `true` in case this [`Deadline`] has expired.
+    #[must_use]
+    pub fn is_expired(&self) -> bool {
+        self.0 <= Instant::now()
+    }
+
+    /// Returns the [`Deadline`] found in the given [`Context`], if any.
+    #[must_use]
+    pub fn from_ctx(ctx: &Context) -> Option<Self> {
+        ctx.get().copied()
+    }
+
+    /// Insert this [`Deadline`] in the given [`Context`], unless
+    /// the [`Context`] already contains an earlier one.
+    ///
+    /// Returns the [`Deadline`] in effect.
+    pub fn apply(self, ctx: &mut Context) -> Self {
+        match Self::from_ctx(ctx) {
+            Some(existing) if existing <= self => existing,
+            _ => {
+                ctx.insert(self);
+                self
+            }
+        }
+    }
+
+    /// Limit the given timeout to the time remaining until
+    /// the [`Deadline`] found in the given [`Context`], if any.
+    #[must_use]
+    pub fn limit_timeout(ctx: &Context, timeout: Duration) -> Duration {
+        match Self::from_ctx(ctx) {
+            Some(deadline) => timeout.min(deadline.remaining()),
+            None => timeout,
+        }
+    }
+
+    /// Completes once this [`Deadline`] has expired.
+    pub async fn expired(self) {
+        tokio::time::sleep_until(tokio::time::Instant::from_std(self.0)).await
+    }
+}
+
+rama_utils::macros::error::static_str_error! {
+    #[doc = "deadline of the request exceeded"]
+    pub struct DeadlineExceeded;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_deadline_apply_only_shortens() {
+        let mut ctx = Context::default();
+        assert!(Deadline::from_ctx(&ctx).is_none());
+
+        let long = Deadline::after(Duration::from_secs(60));
+        assert_eq!(long.apply(&mut ctx), long);
+
+        let short = Deadline::after(Duration::from_secs(1));
+        assert_eq!(short.apply(&mut ctx), short);
+        assert_eq!(Deadline::from_ctx(&ctx), Some(short));
+
+        assert_eq!(
+            Deadline::after(Duration::from_secs(30)).apply(&mut ctx),
+            short
+        );
+        assert_eq!(Deadline::from_ctx(&ctx), Some(short));
+
+        assert!(Deadline::limit_timeout(&ctx, Duration::from_secs(30)) <= Duration::from_secs(1));
+        assert_eq!(
+            Deadline::limit_timeout(&Context::default(), Duration::from_secs(30)),
+            Duration::from_secs(30)
+        );
+    }
+
+    #[test]
+    fn test_deadline_expired() {
+        let deadline = Deadline::at(Instant::now());
+        assert!(deadline.is_expired());
+        assert_eq!(deadline.remaining(), Duration::ZERO);
+
+        let deadline = Deadline::after(Duration::MAX);
+        assert!(!deadline.is_expired());
+    }
+}
@@ -83,6 +83,33 @@ impl Extensions {
         }
     }
 
+    /// Returns true if the `Extensions` contains no extensions.
+    pub(super) fn is_empty(&self) -> bool {
+        self.map.as_ref().is_none_or(|map| map.is_empty())
+    }
+
+    /// Copy the extension of type `T` from the other `Extensions`, if it contains one.
+    pub(super) fn copy_from<T: 'static>(&mut self, other: &Self) {
+        if let Some(boxed) = other
+            .map
+            .as_ref()
+            .and_then(|map| map.get(&TypeId::of::<T>()))
+        {
+            self.map
+                .get_or_insert_with(Box::default)
+                .insert(TypeId::of::<T>(), boxed.clone());
+        }
+    }
+
+    /// Remove the extensions of the given types.
+    pub(super) fn remove_type_ids(&mut self, ids: &[TypeId]) {
+        if let Some(map) = self.map.as_mut() {
+            for id in ids {
+                map.remove(id);
+            }
+        }
+    }
+
     /// Returns true if the `Extensions` contains the given type.
     #[must_use]
     pub fn contains<T: Send + Sync + 'static>(&self) -> bool {
@@ -31,16 +31,49 @@
 //! ctx.insert(5i32);
 //! assert_eq!(ctx.get::<i32>(), Some(&5i32));
 //! ```
+//!
+//! ## Example: Child Scopes
+//!
+//! A child [`Context`] can be created using [`Context::child`], e.g. per request of a connection.
+//! It sees the extensions of its parent without cloning them, can shadow them with its own values,
+//! and is cancelled when its parent is cancelled.
+//!
+//! ```
+//! use rama_core::Context;
+//!
+//! let mut conn_ctx = Context::default();
+//! conn_ctx.insert("connection");
+//! conn_ctx.insert(1u8);
+//!
+//! let mut req_ctx = conn_ctx.child();
+//! req_ctx.insert("request");
+//! assert_eq!(req_ctx.get::<&str>(), Some(&"request"));
+//! assert_eq!(req_ctx.get::<u8>(), Some(&1));
+//! assert_eq!(conn_ctx.get::<&str>(), Some(&"connection"));
+//!
+//! conn_ctx.cancel();
+//! assert!(req_ctx.is_cancelled());
+//! ```
 
 use crate::graceful::ShutdownGuard;
 use crate::rt::Executor;
+use std::any::TypeId;
 use std::ops::{Deref, DerefMut};
+use std::sync::Arc;
 use tokio::task::JoinHandle;
 
 mod extensions;
 #[doc(inline)]
 pub use extensions::Extensions;
 
+mod cancellation;
+#[doc(inline)]
+pub use cancellation::Cancellation;
+
+mod deadline;
+#[doc(inline)]
+pub use deadline::{Deadline, DeadlineExceeded};
+
 #[derive(Debug, Clone)]
 /// Wrapper type that can be injected into the dynamic extensions of a "Response",
 /// in order to preserve the [`Context`]'s extensions of the _Request_
@@ -91,6 +124,40 @@ impl DerefMut for RequestContextExt {
 pub struct Context {
     executor: Executor,
     extensions: Extensions,
+    // types removed from this scope, while still present in a parent scope
+    removed: Vec<TypeId>,
+    scope: Option<Arc<Scope>>,
+    cancellation: Option<Cancellation>,
+}
+
+#[derive(Debug)]
+/// The frozen extensions of a parent [`Context`], shared by its children.
+struct Scope {
+    extensions: Extensions,
+    removed: Vec<TypeId>,
+    parent: Option<Arc<Self>>,
+}
+
+/// Returns the [`Extensions`] of the closest scope containing `T`,
+/// unless `T` was removed first.
+fn find_scoped<'a, T: Send + Sync + 'static>(
+    removed: &[TypeId],
+    mut scope: Option<&'a Scope>,
+) -> Option<&'a Extensions> {
+    let id = TypeId::of::<T>();
+    if removed.contains(&id) {
+        return None;
+    }
+    while let Some(current) = scope {
+        if current.extensions.contains::<T>() {
+            return Some(&current.extensions);
+        }
+        if current.removed.contains(&id) {
+            return None;
+        }
+        scope = current.parent.as_deref();
+    }
+    None
 }
 
 #[derive(Debug)]
@@ -106,7 +173,7 @@ impl Context {
     pub fn new(executor: Executor) -> Self {
         Self {
             executor,
-            extensions: Extensions::new(),
+            ..Default::default()
         }
     }
 
@@ -115,14 +182,108 @@ impl Context {
         Self {
             executor: parts.executor,
             extensions: parts.extensions,
+            ..Default::default()
         }
     }
 
     #[must_use]
+    /// Split the [`Context`] into its parts,
+    /// merging the extensions of its parent scopes into its own.
     pub fn into_parts(self) -> Parts {
+        let extensions = if self.scope.is_some() {
+            let mut scopes = Vec::new();
+            let mut scope = self.scope.as_deref();
+            while let Some(current) = scope {
+                scopes.push(current);
+                scope = current.parent.as_deref();
+            }
+
+            let mut extensions = Extensions::new();
+            for scope in scopes.into_iter().rev() {
+                extensions.remove_type_ids(&scope.removed);
+                extensions.extend(scope.extensions.clone());
+            }
+            extensions.remove_type_ids(&self.removed);
+            extensions.extend(self.extensions);
+            extensions
+        } else {
+            self.extensions
+        };
         Parts {
             executor: self.executor,
-            extensions: self.extensions,
+            extensions,
+        }
+    }
+
+    /// Create a child [`Context`], e.g. for a request served over the connection of this one.
+    ///
+    /// The child sees the extensions of this [`Context`], but those it inserts
+    /// shadow them rather than overwriting them. To do so without cloning them,
+    /// the extensions of this [`Context`] are moved into a scope shared with the child,
+    /// such that the [`Extensions`] returned by [`Context::extensions`]
+    /// only contain the ones inserted after this call.
+    ///
+    /// The child is cancelled when this [`Context`] is cancelled, see [`Context::cancel`].
+    pub fn child(&mut self) -> Self {
+        if !self.extensions.is_empty() || !self.removed.is_empty() {
+            self.scope = Some(Arc::new(Scope {
+                extensions: std::mem::take(&mut self.extensions),
+                removed: std::mem::take(&mut self.removed),
+                parent: self.scope.take(),
+            }));
+        }
+        let cancellation = self
+            .cancellation
+            .get_or_insert_with(Cancellation::new)
+            .child();
+        Self {
+            executor: self.executor.clone(),
+            extensions: Extensions::new(),
+            removed: Vec::new(),
+            scope: self.scope.clone(),
+            cancellation: Some(cancellation),
+        }
+    }
+
+    /// Returns the [`Cancellation`] of this [`Context`],
+    /// which can be used to cancel it or to wait until it is cancelled.
+    pub fn cancellation(&mut self) -> &Cancellation {
+        self.cancellation.get_or_insert_with(Cancellation::new)
+    }
+
+    /// Cancel this [`Context`] and all its children.
+    pub fn cancel(&mut self) {
+        self.cancellation().cancel();
+    }
+
+    #[must_use]
+    /// Returns `true` in case this [`Context`] or one of its parents has been cancelled.
+    pub fn is_cancelled(&self) -> bool {
+        self.cancellation
+            .as_ref()
+            .is_some_and(Cancellation::is_cancelled)
+    }
+
+    /// Wait until this [`Context`] or one of its parents is cancelled.
+    pub async fn cancelled(&mut self) {
+        self.cancellation().cancelled().await;
+    }
+
+    /// Copy `T` from a parent scope into the own extensions, if it is not yet present,
+    /// such that it can be mutated without affecting the parent.
+    fn localize<T: Send + Sync + 'static>(&mut self) {
+        if !self.extensions.contains::<T>()
+            && let Some(extensions) = find_scoped::<T>(&self.removed, self.scope.as_deref())
+        {
+            self.extensions.copy_from::<T>(extensions);
+        }
+    }
+
+    fn find_extensions<T: Send + Sync + 'static>(&self) -> Option<&Extensions> {
+        if self.extensions.contains::<T>() {
+            Some(&self.extensions)
+        } else {
+            find_scoped::<T>(&self.removed, self.scope.as_deref())
         }
     }
 
@@ -147,6 +308,11 @@ impl Context {
 
     /// Spawn a future on the current executor,
     /// this is spawned gracefully in case a shutdown guard has been registered.
+    ///
+    /// The task is registered in the [`Tasks`] registry of the executor, if any,
+    /// see [`Executor::with_tasks`] and [`Self::spawn_labeled`].
+    ///
+    /// [`Tasks`]: crate::rt::Tasks
     pub fn spawn<F>(&self, future: F) -> JoinHandle<F::Output>
     where
         F: Future<Output: Send + 'static> + Send + 'static,
@@ -154,13 +320,26 @@ impl Context {
         self.executor.spawn_task(future)
     }
 
+    /// Spawn a future on the current executor with the given label,
+    /// under which it is registered in the [`Tasks`] registry of the executor, if any.
+    ///
+    /// This is spawned gracefully in case a shutdown guard has been registered.
+    ///
+    /// [`Tasks`]: crate::rt::Tasks
+    pub fn spawn_labeled<F>(&self, label: &'static str, future: F) -> JoinHandle<F::Output>
+    where
+        F: Future<Output: Send + 'static> + Send + 'static,
+    {
+        self.executor.spawn_labeled_task(label, future)
+    }
+
     #[must_use]
     /// Returns true if the `Context` contains the given type.
     ///
     /// Use [`Self::get`] in case you want to have access to the type
     /// or [`Self::get_mut`] if you also need to mutate it.
     pub fn contains<T: Send + Sync + 'static>(&self) -> bool {
-        self.extensions.contains::<T>()
+        self.find_extensions::<T>().is_some()
     }
 
     #[must_use]
@@ -185,7 +364,7 @@ impl Context {
     /// assert_eq!(ctx.get::<i32>(), Some(&5i32));
     /// ```
     pub fn get<T: Send + Sync + 'static>(&self) -> Option<&T> {
-        self.extensions.get::<T>()
+        self.find_extensions::<T>()?.get::<T>()
     }
 
     /// Get an exclusive reference to an extension.
@@ -213,6 +392,7 @@ impl Context {
     /// assert_eq!(ctx.get::<i32>(), Some(&8i32));
     /// ```
     pub fn get_mut<T: Send + Sync + 'static>(&mut self) -> Option<&mut T> {
+        self.localize::<T>();
         self.extensions.get_mut::<T>()
     }
 
@@ -233,6 +413,7 @@ impl Context {
         &mut self,
         f: impl FnOnce() -> T,
     ) -> &mut T {
+        self.localize::<T>();
         self.extensions.get_or_insert_with(f)
     }
 
@@ -262,6 +443,7 @@ impl Context {
         &mut self,
         f: impl FnOnce(&Self) -> T,
     ) -> &mut T {
+        self.localize::<T>();
         if self.extensions.contains::<T>() {
             // NOTE: once <https://github.com/rust-lang/polonius>
             // is merged into rust we can use directly `if let Some(v) = self.extensions.get_mut()`,
@@ -281,6 +463,7 @@ impl Context {
         &mut self,
         f: impl FnOnce(&Self) -> Result<T, E>,
     ) -> Result<&mut T, E> {
+        self.localize::<T>();
         if self.extensions.contains::<T>() {
             // NOTE: once <https://github.com/rust-lang/polonius>
             // is merged into rust we can use directly `if let Some(v) = self.extensions.get_mut()`,
@@ -299,6 +482,7 @@ impl Context {
         T: Clone + Send + Sync + 'static,
         U: Into<T>,
     {
+        self.localize::<T>();
         self.extensions.get_or_insert_from(src)
     }
 
@@ -320,6 +504,7 @@ impl Context {
     /// assert_eq!(*ctx.get_or_insert::<f64>(2.5), 2.5);
     /// ```
     pub fn get_or_insert<T: Send + Sync + Clone + 'static>(&mut self, fallback: T) -> &mut T {
+        self.localize::<T>();
         self.extensions.get_or_insert(fallback)
     }
 
@@ -338,6 +523,7 @@ impl Context {
     /// assert_eq!(*ctx.get_or_insert_default::<f64>(), 0f64);
     /// ```
     pub fn get_or_insert_default<T: Clone + Default + Send + Sync + 'static>(&mut self) -> &mut T {
+        self.localize::<T>();
         self.extensions.get_or_insert_default()
     }
 
@@ -361,6 +547,7 @@ impl Context {
     /// assert_eq!(ctx.get::<i32>(), Some(&4i32));
     /// ```
     pub fn insert<T: Clone + Send + Sync + 'static>(&mut self, extension: T) -> Option<T> {
+        self.localize::<T>();
         self.extensions.insert(extension)
     }
 
@@ -371,7 +558,7 @@ impl Context {
         &mut self,
         extension: Option<T>,
     ) -> Option<T> {
-        self.extensions.maybe_insert(extension)
+        extension.and_then(|extension| self.insert(extension))
     }
 
     #[must_use]
@@ -380,6 +567,8 @@ impl Context {
     /// Useful only in case you have a function which works with [`Extensions`] rather
     /// then the [`Context`] itself. In case you want to have access to a specific dynamic state,
     /// it is more suitable to use [`Context::get`] directly.
+    ///
+    /// These do not include the extensions of the parent scopes, see [`Context::child`].
     pub fn extensions(&self) -> &Extensions {
         &self.extensions
     }
@@ -420,7 +609,8 @@ impl Context {
         self.extensions.extend(extensions);
     }
 
-    /// Clear the [`Context`] of all inserted [`Extensions`].
+    /// Clear the [`Context`] of all inserted [`Extensions`],
+    /// including the ones of its parent scopes.
     ///
     /// # Example
     ///
@@ -436,11 +626,21 @@ impl Context {
     /// ```
     pub fn clear(&mut self) {
         self.extensions.clear();
+        self.removed.clear();
+        self.scope = None;
     }
 
-    /// Remove an extension from this [`Context`]
+    /// Remove an extension from this [`Context`].
+    ///
+    /// An extension of a parent scope is hidden from this [`Context`],
+    /// without removing it from the parent.
     pub fn remove<T: Clone + Send + Sync + 'static>(&mut self) -> Option<T> {
-        self.extensions.remove()
+        self.localize::<T>();
+        let extension = self.extensions.remove();
+        if find_scoped::<T>(&self.removed, self.scope.as_deref()).is_some() {
+            self.removed.push(TypeId::of::<T>());
+        }
+        extension
     }
 
     #[must_use]
@@ -450,3 +650,64 @@ impl Context {
         self.executor.guard()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_context_child_scopes() {
+        let mut parent = Context::default();
+        parent.insert(1u8);
+        parent.insert(String::from("parent"));
+
+        let mut child = parent.child();
+        assert_eq!(child.get::<u8>(), Some(&1));
+        assert!(child.extensions().get::<u8>().is_none());
+
+        // shadowing and mutating does not affect the parent
+        child.insert(String::from("child"));
+        *child.get_mut::<u8>().unwrap() = 2;
+        assert_eq!(child.get::<String>().unwrap(), "child");
+        assert_eq!(child.get::<u8>(), Some(&2));
+        assert_eq!(parent.get::<String>().unwrap(), "parent");
+        assert_eq!(parent.get::<u8>(), Some(&1));
+
+        // removing hides the extension of the parent scope
+        let mut grandchild = child.child();
+        assert_eq!(grandchild.remove::<String>().unwrap(), "child");
+        assert!(grandchild.get::<String>().is_none());
+        assert!(!grandchild.contains::<String>());
+        assert_eq!(child.get::<String>().unwrap(), "child");
+
+        grandchild.insert(true);
+        let parts = grandchild.into_parts();
+        assert_eq!(parts.extensions.get::<u8>(), Some(&2));
+        assert_eq!(parts.extensions.get::<bool>(), Some(&true));
+        assert!(parts.extensions.get::<String>().is_none());
+
+        // inserting into the parent after creating the child is not visible to the child
+        parent.insert(3u16);
+        assert!(child.get::<u16>().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_context_cancellation() {
+        let mut parent = Context::default();
+        let mut child = parent.child();
+        let mut grandchild = child.child();
+        let mut sibling = parent.child();
+
+        sibling.cancel();
+        assert!(!parent.is_cancelled());
+        assert!(!child.is_cancelled());
+
+        let waiter = tokio::spawn(async move { grandchild.cancelled().await });
+        parent.cancel();
+        assert!(child.is_cancelled());
+        waiter.await.unwrap();
+
+        // children of a cancelled context are cancelled from the start
+        assert!(child.child().is_cancelled());
+    }
+}
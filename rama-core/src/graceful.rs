@@ -1,6 +1,342 @@
 //! Shutdown management for graceful shutdown of async-first applications.
+//!
+//! A single [`Shutdown`] is sufficient for most applications: pass its [`ShutdownGuard`]
+//! to the `serve_graceful` methods of the listeners (or the `HttpServer`) and await
+//! [`Shutdown::shutdown_with_limit`] at the end of your _main_ function.
+//!
+//! Use a [`PhasedShutdown`] in case different parts of the application have to shut down
+//! one after the other, each with their own deadline. E.g. a proxy first stops its listeners
+//! and drains its connections, prior to flushing its telemetry.
+
+use crate::telemetry::tracing;
+use std::{fmt, pin::Pin, time::Duration};
+use tokio::sync::oneshot;
 
 #[doc(inline)]
 pub use ::tokio_graceful::{
     Shutdown, ShutdownBuilder, ShutdownGuard, WeakShutdownGuard, default_signal,
 };
+
+type Signal = Pin<Box<dyn Future<Output = ()> + Send + 'static>>;
+
+/// A builder to create a [`PhasedShutdown`].
+///
+/// Created using [`PhasedShutdown::builder`].
+pub struct PhasedShutdownBuilder {
+    signal: Option<Signal>,
+    phases: Vec<(&'static str, Option<Duration>)>,
+}
+
+impl fmt::Debug for PhasedShutdownBuilder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PhasedShutdownBuilder")
+            .field("phases", &self.phases)
+            .finish()
+    }
+}
+
+impl PhasedShutdownBuilder {
+    /// Set the signal which triggers the shutdown,
+    /// instead of the [`default_signal`] (e.g. `SIGINT` or `SIGTERM` on unix).
+    #[must_use]
+    pub fn with_signal(mut self, signal: impl Future + Send + 'static) -> Self {
+        self.signal = Some(Box::pin(async move {
+            signal.await;
+        }));
+        self
+    }
+
+    /// Add a phase, which is given at most the given deadline to shut down,
+    /// once all previous phases finished (or exceeded their deadline).
+    #[must_use]
+    pub fn with_phase(mut self, name: &'static str, deadline: Duration) -> Self {
+        self.phases.push((name, Some(deadline)));
+        self
+    }
+
+    /// Add a phase without a deadline, which is awaited until all its guards are dropped,
+    /// once all previous phases finished (or exceeded their deadline).
+    #[must_use]
+    pub fn with_phase_without_deadline(mut self, name: &'static str) -> Self {
+        self.phases.push((name, None));
+        self
+    }
+
+    /// Build the [`PhasedShutdown`].
+    ///
+    /// # Panics
+    ///
+    /// Panics when called outside of a tokio runtime.
+    #[must_use]
+    pub fn build(self) -> PhasedShutdown {
+        let signal = self.signal.unwrap_or_else(|| Box::pin(default_signal()));
+        let phases = self
+            .phases
+            .into_iter()
+            .map(|(name, deadline)| {
+                let (trigger, rx) = oneshot::channel::<()>();
+                ShutdownPhase {
+                    name,
+                    deadline,
+                    shutdown: Shutdown::new(rx),
+                    trigger,
+                }
+            })
+            .collect();
+        PhasedShutdown { signal, phases }
+    }
+}
+
+struct ShutdownPhase {
+    name: &'static str,
+    deadline: Option<Duration>,
+    shutdown: Shutdown,
+    trigger: oneshot::Sender<()>,
+}
+
+/// Orchestrates the graceful shutdown of an application in phases,
+/// executed one after the other once the shutdown signal is received.
+///
+/// Each phase is a [`Shutdown`] of its own, with an optional deadline.
+/// Hand out its [`ShutdownGuard`]s using [`PhasedShutdown::guard`],
+/// e.g. to the `serve_graceful` methods of the (tcp, unix, quic) listeners
+/// or the `HttpServer`. A phase is only triggered once all previous phases are finished,
+/// such that its tasks can keep running while the previous phases shut down.
+///
+/// # Example
+///
+/// ```
+/// use rama_core::graceful::PhasedShutdown;
+/// use std::time::Duration;
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// let shutdown = PhasedShutdown::builder()
+///     // e.g. replaced by the default signal (SIGINT/SIGTERM) in production
+///     .with_signal(std::future::ready(()))
+///     .with_phase("proxy", Duration::from_secs(30))
+///     .with_phase("telemetry", Duration::from_secs(5))
+///     .build();
+///
+/// shutdown.spawn_task("proxy", async |guard| {
+///     // e.g. `TcpListener::serve_graceful(guard, service)`
+///     guard.cancelled().await;
+/// });
+///
+/// let report = shutdown.shutdown().await;
+/// assert!(report.is_graceful());
+/// # }
+/// ```
+pub struct PhasedShutdown {
+    signal: Signal,
+    phases: Vec<ShutdownPhase>,
+}
+
+impl fmt::Debug for PhasedShutdown {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PhasedShutdown")
+            .field(
+                "phases",
+                &self
+                    .phases
+                    .iter()
+                    .map(|phase| (phase.name, phase.deadline))
+                    .collect::<Vec<_>>(),
+            )
+            .finish()
+    }
+}
+
+impl PhasedShutdown {
+    /// Create a [`PhasedShutdownBuilder`], without any phases,
+    /// triggered by the [`default_signal`] unless defined otherwise.
+    #[must_use]
+    pub fn builder() -> PhasedShutdownBuilder {
+        PhasedShutdownBuilder {
+            signal: None,
+            phases: Vec::new(),
+        }
+    }
+
+    /// Returns a [`ShutdownGuard`] for the phase with the given name,
+    /// or `None` in case no such phase exists.
+    #[must_use]
+    pub fn guard(&self, phase: &str) -> Option<ShutdownGuard> {
+        self.phase(phase).map(|phase| phase.shutdown.guard())
+    }
+
+    /// Returns a [`WeakShutdownGuard`] for the phase with the given name,
+    /// or `None` in case no such phase exists.
+    #[must_use]
+    pub fn guard_weak(&self, phase: &str) -> Option<WeakShutdownGuard> {
+        self.phase(phase).map(|phase| phase.shutdown.guard_weak())
+    }
+
+    /// Spawn a task, registered as part of the phase with the given name,
+    /// which receives a [`ShutdownGuard`] of that phase.
+    ///
+    /// # Panics
+    ///
+    /// Panics in case no phase exists with the given name.
+    pub fn spawn_task<F, Fut>(&self, phase: &str, task: F) -> tokio::task::JoinHandle<Fut::Output>
+    where
+        F: FnOnce(ShutdownGuard) -> Fut + Send + 'static,
+        Fut: Future + Send + 'static,
+        Fut::Output: Send + 'static,
+    {
+        let Some(phase) = self.phase(phase) else {
+            panic!("phased shutdown: unknown phase: {phase}");
+        };
+        phase.shutdown.spawn_task_fn(task)
+    }
+
+    fn phase(&self, name: &str) -> Option<&ShutdownPhase> {
+        self.phases.iter().find(|phase| phase.name == name)
+    }
+
+    /// Wait for the shutdown signal, after which all phases are shut down in order.
+    ///
+    /// A phase which exceeds its deadline is reported as such,
+    /// after which the next phase is shut down regardless.
+    pub async fn shutdown(self) -> ShutdownReport {
+        self.signal.await;
+        tracing::info!(
+            "phased shutdown: signal received: shut down {} phase(s)",
+            self.phases.len()
+        );
+
+        let mut phases = Vec::with_capacity(self.phases.len());
+        for phase in self.phases {
+            let ShutdownPhase {
+                name,
+                deadline,
+                shutdown,
+                trigger,
+            } = phase;
+
+            tracing::debug!("phased shutdown: shut down phase {name} (deadline: {deadline:?})");
+            let _ = trigger.send(());
+            let (elapsed, timed_out) = match deadline {
+                Some(deadline) => match shutdown.shutdown_with_limit(deadline).await {
+                    Ok(elapsed) => (elapsed, false),
+                    Err(err) => {
+                        tracing::warn!("phased shutdown: phase {name} exceeded deadline: {err}");
+                        (deadline, true)
+                    }
+                },
+                None => (shutdown.shutdown().await, false),
+            };
+            phases.push(ShutdownPhaseReport {
+                name,
+                elapsed,
+                timed_out,
+            });
+        }
+
+        ShutdownReport { phases }
+    }
+}
+
+#[derive(Debug, Clone)]
+/// The outcome of a [`PhasedShutdown`].
+pub struct ShutdownReport {
+    phases: Vec<ShutdownPhaseReport>,
+}
+
+impl ShutdownReport {
+    /// Returns the outcome of each phase, in the order they were shut down.
+    #[must_use]
+    pub fn phases(&self) -> &[ShutdownPhaseReport] {
+        &self.phases
+    }
+
+    /// Returns `true` in case all phases shut down within their deadline.
+    #[must_use]
+    pub fn is_graceful(&self) -> bool {
+        self.phases.iter().all(|phase| !phase.timed_out)
+    }
+}
+
+#[derive(Debug, Clone)]
+/// The outcome of a single phase of a [`PhasedShutdown`].
+pub struct ShutdownPhaseReport {
+    name: &'static str,
+    elapsed: Duration,
+    timed_out: bool,
+}
+
+impl ShutdownPhaseReport {
+    /// Returns the name of the phase.
+    #[must_use]
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    /// Returns the time it took to shut down the phase.
+    #[must_use]
+    pub fn elapsed(&self) -> Duration {
+        self.elapsed
+    }
+
+    /// Returns `true` in case the phase exceeded its deadline.
+    #[must_use]
+    pub fn timed_out(&self) -> bool {
+        self.timed_out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    #[tokio::test]
+    async fn test_phased_shutdown_order() {
+        let (tx, rx) = oneshot::channel::<()>();
+        let shutdown = PhasedShutdown::builder()
+            .with_signal(rx)
+            .with_phase("first", Duration::from_secs(1))
+            .with_phase("second", Duration::from_secs(1))
+            .build();
+
+        let events = Arc::new(Mutex::new(Vec::new()));
+        for phase in ["second", "first"] {
+            let events = events.clone();
+            shutdown.spawn_task(phase, async move |guard| {
+                guard.cancelled().await;
+                events.lock().unwrap().push(phase);
+            });
+        }
+
+        tx.send(()).unwrap();
+        let report = shutdown.shutdown().await;
+
+        assert!(report.is_graceful());
+        assert_eq!(*events.lock().unwrap(), vec!["first", "second"]);
+        assert_eq!(
+            report
+                .phases()
+                .iter()
+                .map(|phase| phase.name())
+                .collect::<Vec<_>>(),
+            vec!["first", "second"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_phased_shutdown_deadline() {
+        let shutdown = PhasedShutdown::builder()
+            .with_signal(std::future::ready(()))
+            .with_phase("stuck", Duration::from_millis(20))
+            .with_phase("next", Duration::from_secs(1))
+            .build();
+
+        let _stuck_guard = shutdown.guard("stuck").unwrap();
+        assert!(shutdown.guard("unknown").is_none());
+
+        let report = shutdown.shutdown().await;
+        assert!(!report.is_graceful());
+        assert!(report.phases()[0].timed_out());
+        assert!(!report.phases()[1].timed_out());
+    }
+}
@@ -1,5 +1,6 @@
 //! Default Error type for Timeout middleware.
 
+use crate::error::ClassifiedError;
 use std::{error, fmt, time::Duration};
 
 /// The timeout elapsed.
@@ -20,3 +21,9 @@ impl fmt::Display for Elapsed {
 }
 
 impl error::Error for Elapsed {}
+
+impl From<Elapsed> for ClassifiedError {
+    fn from(error: Elapsed) -> Self {
+        Self::timeout(error)
+    }
+}
@@ -2,9 +2,12 @@
 //!
 //! If the response does not complete within the specified timeout, the response
 //! will be aborted.
+//!
+//! A [`Deadline`] is inserted in the [`Context`] for the inner service,
+//! such that nested calls can limit themselves to the time remaining.
 
 use super::{LayerErrorFn, LayerErrorStatic, MakeLayerError};
-use crate::{Context, Service};
+use crate::{Context, Service, context::Deadline};
 use rama_utils::macros::define_inner_service_accessors;
 use std::{fmt, time::Duration};
 
@@ -119,7 +122,13 @@ where
     type Response = T::Response;
     type Error = T::Error;
 
-    async fn serve(&self, ctx: Context, request: Request) -> Result<Self::Response, Self::Error> {
+    async fn serve(
+        &self,
+        mut ctx: Context,
+        request: Request,
+    ) -> Result<Self::Response, Self::Error> {
+        // let the inner stack know about the time it has left
+        Deadline::after(self.timeout).apply(&mut ctx);
         tokio::select! {
             res = self.inner.serve(ctx, request) => res,
             _ = tokio::time::sleep(self.timeout) => Err(self.into_error.make_layer_error().into()),
@@ -1,16 +1,21 @@
+use super::{Tasks, tasks::UNLABELED_TASK};
 use crate::graceful::ShutdownGuard;
 
 /// Future executor that utilises `tokio` threads.
 #[derive(Default, Debug, Clone)]
 pub struct Executor {
     guard: Option<ShutdownGuard>,
+    tasks: Option<Tasks>,
 }
 
 impl Executor {
     /// Create a new [`Executor`].
     #[must_use]
     pub const fn new() -> Self {
-        Self { guard: None }
+        Self {
+            guard: None,
+            tasks: None,
+        }
     }
 
     /// Create a new [`Executor`] with the given shutdown guard,
@@ -19,21 +24,59 @@ impl Executor {
     /// in case the shutdown guard is triggered.
     #[must_use]
     pub fn graceful(guard: ShutdownGuard) -> Self {
-        Self { guard: Some(guard) }
+        Self {
+            guard: Some(guard),
+            tasks: None,
+        }
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Register the tasks spawned by this [`Executor`] in the given [`Tasks`] registry,
+        /// such that they can be counted, awaited or aborted.
+        pub fn tasks(mut self, tasks: Option<Tasks>) -> Self {
+            self.tasks = tasks;
+            self
+        }
     }
 
     /// Spawn a future on the current executor,
     /// this is spawned gracefully in case a shutdown guard has been registered.
+    ///
+    /// The task is registered as an [`UNLABELED_TASK`](super::UNLABELED_TASK)
+    /// in case a [`Tasks`] registry is used, see [`Self::spawn_labeled_task`].
     pub fn spawn_task<F>(&self, future: F) -> tokio::task::JoinHandle<F::Output>
     where
         F: Future<Output: Send + 'static> + Send + 'static,
     {
-        match &self.guard {
-            Some(guard) => guard.spawn_task(future),
-            None => tokio::spawn(future),
+        self.spawn_labeled_task(UNLABELED_TASK, future)
+    }
+
+    /// Spawn a future on the current executor with the given label,
+    /// under which it is registered in case a [`Tasks`] registry is used.
+    ///
+    /// This is spawned gracefully in case a shutdown guard has been registered.
+    pub fn spawn_labeled_task<F>(
+        &self,
+        label: &'static str,
+        future: F,
+    ) -> tokio::task::JoinHandle<F::Output>
+    where
+        F: Future<Output: Send + 'static> + Send + 'static,
+    {
+        match (&self.tasks, &self.guard) {
+            (Some(tasks), guard) => tasks.spawn(guard.as_ref(), label, future),
+            (None, Some(guard)) => guard.spawn_task(future),
+            (None, None) => tokio::spawn(future),
         }
     }
 
+    /// Get a reference to the [`Tasks`] registry,
+    /// if the executor was created with one.
+    #[must_use]
+    pub fn tasks(&self) -> Option<&Tasks> {
+        self.tasks.as_ref()
+    }
+
     /// Get a reference to the shutdown guard,
     /// if and only if the executor was created with [`Self::graceful`].
     #[must_use]
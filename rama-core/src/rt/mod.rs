@@ -12,4 +12,8 @@ mod executor;
 #[doc(inline)]
 pub use executor::Executor;
 
+mod tasks;
+#[doc(inline)]
+pub use tasks::{Tasks, UNLABELED_TASK};
+
 pub mod future;
@@ -0,0 +1,246 @@
+use crate::graceful::ShutdownGuard;
+use parking_lot::Mutex;
+use std::{
+    collections::HashMap,
+    fmt,
+    sync::{
+        Arc,
+        atomic::{AtomicU64, Ordering},
+    },
+    time::Duration,
+};
+use tokio::{
+    sync::Notify,
+    task::{AbortHandle, JoinHandle},
+};
+
+/// The label of a task spawned without one, e.g. using [`Executor::spawn_task`].
+///
+/// [`Executor::spawn_task`]: super::Executor::spawn_task
+pub const UNLABELED_TASK: &str = "unlabeled";
+
+#[derive(Clone, Default)]
+/// A registry of the background tasks spawned by an [`Executor`],
+/// e.g. tunnels, keep-alive pumps and health checks.
+///
+/// It exposes the number of running tasks per label, and allows to wait for them
+/// or abort them on shutdown, rather than leaking detached tokio tasks.
+///
+/// Tasks are registered by spawning them using an [`Executor`] created
+/// with [`Executor::with_tasks`] (e.g. using [`Context::spawn`]),
+/// and are unregistered as soon as they complete, panic or are aborted.
+///
+/// # Example
+///
+/// ```
+/// use rama_core::rt::{Executor, Tasks};
+/// use std::time::Duration;
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// let tasks = Tasks::new();
+/// let executor = Executor::new().with_tasks(tasks.clone());
+///
+/// executor.spawn_labeled_task("health check", std::future::pending::<()>());
+/// assert_eq!(tasks.count_label("health check"), 1);
+///
+/// // give the tasks some time to complete, aborting the ones that do not
+/// let aborted = tasks.shutdown(Duration::from_millis(10)).await;
+/// assert_eq!(aborted, 1);
+/// assert!(tasks.is_empty());
+/// # }
+/// ```
+///
+/// [`Executor`]: super::Executor
+/// [`Executor::with_tasks`]: super::Executor::with_tasks
+/// [`Context::spawn`]: crate::Context::spawn
+pub struct Tasks(Arc<Inner>);
+
+#[derive(Default)]
+struct Inner {
+    next_id: AtomicU64,
+    tasks: Mutex<HashMap<u64, Task>>,
+    notify: Notify,
+}
+
+struct Task {
+    label: &'static str,
+    abort: Option<AbortHandle>,
+}
+
+impl Tasks {
+    /// Create a new empty [`Tasks`] registry.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Spawn the future as a task registered with the given label,
+    /// gracefully in case a shutdown guard is given.
+    pub(super) fn spawn<F>(
+        &self,
+        guard: Option<&ShutdownGuard>,
+        label: &'static str,
+        future: F,
+    ) -> JoinHandle<F::Output>
+    where
+        F: Future<Output: Send + 'static> + Send + 'static,
+    {
+        let id = self.0.next_id.fetch_add(1, Ordering::Relaxed);
+        self.0.tasks.lock().insert(id, Task { label, abort: None });
+
+        let registration = Registration {
+            tasks: self.0.clone(),
+            id,
+        };
+        let future = async move {
+            // unregisters the task once completed, panicked or aborted
+            let _registration = registration;
+            future.await
+        };
+        let handle = match guard {
+            Some(guard) => guard.spawn_task(future),
+            None => tokio::spawn(future),
+        };
+
+        // the task might already be unregistered in case it completed immediately
+        if let Some(task) = self.0.tasks.lock().get_mut(&id) {
+            task.abort = Some(handle.abort_handle());
+        }
+        handle
+    }
+
+    /// Returns the number of running tasks.
+    #[must_use]
+    pub fn count(&self) -> usize {
+        self.0.tasks.lock().len()
+    }
+
+    /// Returns the number of running tasks with the given label.
+    #[must_use]
+    pub fn count_label(&self, label: &str) -> usize {
+        self.0
+            .tasks
+            .lock()
+            .values()
+            .filter(|task| task.label == label)
+            .count()
+    }
+
+    /// Returns `true` in case no task is running.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.0.tasks.lock().is_empty()
+    }
+
+    /// Returns the labels of the running tasks with the number of tasks for each,
+    /// sorted by label.
+    #[must_use]
+    pub fn labels(&self) -> Vec<(&'static str, usize)> {
+        let mut labels: Vec<(&'static str, usize)> = Vec::new();
+        for task in self.0.tasks.lock().values() {
+            match labels.iter_mut().find(|(label, _)| *label == task.label) {
+                Some((_, count)) => *count += 1,
+                None => labels.push((task.label, 1)),
+            }
+        }
+        labels.sort_unstable();
+        labels
+    }
+
+    /// Abort all running tasks, returning the number of aborted tasks.
+    ///
+    /// Tasks are unregistered once they are effectively aborted,
+    /// which can be awaited using [`Tasks::wait`].
+    pub fn abort_all(&self) -> usize {
+        let tasks = self.0.tasks.lock();
+        for abort in tasks.values().filter_map(|task| task.abort.as_ref()) {
+            abort.abort();
+        }
+        tasks.len()
+    }
+
+    /// Wait until all running tasks are completed,
+    /// including the ones spawned while waiting.
+    pub async fn wait(&self) {
+        loop {
+            let notified = self.0.notify.notified();
+            if self.is_empty() {
+                return;
+            }
+            notified.await;
+        }
+    }
+
+    /// Wait at most the given timeout for all running tasks to complete,
+    /// after which the remaining ones are aborted and awaited.
+    ///
+    /// Returns the number of aborted tasks.
+    pub async fn shutdown(&self, timeout: Duration) -> usize {
+        if tokio::time::timeout(timeout, self.wait()).await.is_ok() {
+            return 0;
+        }
+        let aborted = self.abort_all();
+        self.wait().await;
+        aborted
+    }
+}
+
+/// Unregisters a task from its [`Tasks`] when dropped.
+struct Registration {
+    tasks: Arc<Inner>,
+    id: u64,
+}
+
+impl Drop for Registration {
+    fn drop(&mut self) {
+        self.tasks.tasks.lock().remove(&self.id);
+        self.tasks.notify.notify_waiters();
+    }
+}
+
+impl fmt::Debug for Tasks {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Tasks")
+            .field("labels", &self.labels())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rt::Executor;
+
+    #[tokio::test]
+    async fn test_tasks_registry() {
+        let tasks = Tasks::new();
+        let executor = Executor::new().with_tasks(tasks.clone());
+
+        let (tx, rx) = tokio::sync::oneshot::channel::<()>();
+        executor.spawn_labeled_task("tunnel", async move {
+            let _ = rx.await;
+        });
+        executor.spawn_labeled_task("tunnel", std::future::pending::<()>());
+        executor.spawn_labeled_task("keep-alive", std::future::pending::<()>());
+        executor.spawn_task(async {}).await.unwrap();
+
+        assert_eq!(tasks.count(), 3);
+        assert_eq!(tasks.count_label("tunnel"), 2);
+        assert_eq!(tasks.labels(), vec![("keep-alive", 1), ("tunnel", 2)]);
+
+        tx.send(()).unwrap();
+        tokio::time::timeout(Duration::from_secs(1), async {
+            while tasks.count() > 2 {
+                tokio::task::yield_now().await;
+            }
+        })
+        .await
+        .unwrap();
+        assert_eq!(tasks.count_label("tunnel"), 1);
+
+        assert_eq!(tasks.shutdown(Duration::from_millis(10)).await, 2);
+        assert!(tasks.is_empty());
+        assert!(tasks.labels().is_empty());
+    }
+}
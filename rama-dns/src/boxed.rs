@@ -123,3 +123,30 @@ where
         }
     }
 }
+
+#[derive(Debug, Clone)]
+/// Wrapper struct that can be used to overwrite
+/// the [`DnsResolver`] used for a request, by adding it to its Context.
+///
+/// This is supported by the official `rama`
+/// consumers such as the `TcpConnector`.
+pub struct DnsResolverOverwrite(BoxDnsResolver);
+
+impl DnsResolverOverwrite {
+    /// Create a new [`DnsResolverOverwrite`] for the given [`DnsResolver`].
+    pub fn new(resolver: impl DnsResolver) -> Self {
+        Self(resolver.boxed())
+    }
+
+    /// Returns a reference to the (boxed) [`DnsResolver`] to use instead.
+    #[must_use]
+    pub fn resolver(&self) -> &BoxDnsResolver {
+        &self.0
+    }
+}
+
+impl From<BoxDnsResolver> for DnsResolverOverwrite {
+    fn from(resolver: BoxDnsResolver) -> Self {
+        Self(resolver)
+    }
+}
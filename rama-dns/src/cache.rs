@@ -0,0 +1,315 @@
+use crate::{DnsRecordType, DnsResolver};
+use rama_core::error::{BoxError, OpaqueError};
+use rama_core::telemetry::tracing;
+use rama_net::address::Domain;
+use std::{
+    collections::HashMap,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr},
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+#[derive(Debug)]
+struct CacheEntry {
+    answers: Result<Vec<IpAddr>, Arc<str>>,
+    expires_at: Instant,
+}
+
+type Cache = HashMap<(Domain, DnsRecordType), CacheEntry>;
+
+/// A [`DnsResolver`] caching the answers of the wrapped [`DnsResolver`].
+///
+/// Successful lookups are cached for the positive ttl (60 seconds by default),
+/// while failed lookups are cached for the (shorter) negative ttl (5 seconds by default),
+/// such that a failing domain does not result in a lookup for every request.
+///
+/// All clones of a [`CachedDns`] share the same cache,
+/// which holds at most `max_entries` (10 000 by default) domains.
+///
+/// # Example
+///
+/// ```
+/// use rama_dns::{CachedDns, DnsResolver, InMemoryDns};
+/// use std::{net::Ipv4Addr, time::Duration};
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// let mut dns = InMemoryDns::new();
+/// dns.insert_address(&"example.com".parse().unwrap(), Ipv4Addr::LOCALHOST);
+///
+/// let dns = CachedDns::new(dns).with_positive_ttl(Duration::from_secs(300));
+/// let ips = dns.ipv4_lookup("example.com".parse().unwrap()).await.unwrap();
+/// assert_eq!(ips, vec![Ipv4Addr::LOCALHOST]);
+/// # }
+/// ```
+pub struct CachedDns<R> {
+    inner: R,
+    cache: Arc<Mutex<Cache>>,
+    positive_ttl: Duration,
+    negative_ttl: Duration,
+    max_entries: usize,
+}
+
+impl<R: std::fmt::Debug> std::fmt::Debug for CachedDns<R> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CachedDns")
+            .field("inner", &self.inner)
+            .field("positive_ttl", &self.positive_ttl)
+            .field("negative_ttl", &self.negative_ttl)
+            .field("max_entries", &self.max_entries)
+            .finish()
+    }
+}
+
+impl<R: Clone> Clone for CachedDns<R> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            cache: self.cache.clone(),
+            positive_ttl: self.positive_ttl,
+            negative_ttl: self.negative_ttl,
+            max_entries: self.max_entries,
+        }
+    }
+}
+
+impl<R> CachedDns<R> {
+    /// Create a new [`CachedDns`], caching the answers of the given [`DnsResolver`].
+    pub fn new(inner: R) -> Self {
+        Self {
+            inner,
+            cache: Default::default(),
+            positive_ttl: Duration::from_secs(60),
+            negative_ttl: Duration::from_secs(5),
+            max_entries: 10_000,
+        }
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Set the duration for which successful lookups are cached.
+        pub fn positive_ttl(mut self, ttl: Duration) -> Self {
+            self.positive_ttl = ttl;
+            self
+        }
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Set the duration for which failed lookups are cached.
+        ///
+        /// Use [`Duration::ZERO`] to disable negative caching.
+        pub fn negative_ttl(mut self, ttl: Duration) -> Self {
+            self.negative_ttl = ttl;
+            self
+        }
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Set the maximum amount of entries held by the cache.
+        pub fn max_entries(mut self, max: usize) -> Self {
+            self.max_entries = max;
+            self
+        }
+    }
+
+    /// Remove all entries from the cache.
+    pub fn clear(&self) {
+        self.cache
+            .lock()
+            .unwrap_or_else(|err| err.into_inner())
+            .clear();
+    }
+
+    fn get(
+        &self,
+        domain: &Domain,
+        record_type: DnsRecordType,
+    ) -> Option<Result<Vec<IpAddr>, Arc<str>>> {
+        let mut cache = self.cache.lock().unwrap_or_else(|err| err.into_inner());
+        let key = (domain.clone(), record_type);
+        let entry = cache.get(&key)?;
+        if entry.expires_at <= Instant::now() {
+            cache.remove(&key);
+            return None;
+        }
+        Some(entry.answers.clone())
+    }
+
+    fn insert(
+        &self,
+        domain: Domain,
+        record_type: DnsRecordType,
+        answers: Result<Vec<IpAddr>, Arc<str>>,
+    ) {
+        let ttl = if answers.is_ok() {
+            self.positive_ttl
+        } else {
+            self.negative_ttl
+        };
+        if ttl.is_zero() {
+            return;
+        }
+
+        let now = Instant::now();
+        let mut cache = self.cache.lock().unwrap_or_else(|err| err.into_inner());
+        if cache.len() >= self.max_entries {
+            cache.retain(|_, entry| entry.expires_at > now);
+            if cache.len() >= self.max_entries {
+                tracing::debug!(%domain, "dns cache is full: answer not cached");
+                return;
+            }
+        }
+        cache.insert(
+            (domain, record_type),
+            CacheEntry {
+                answers,
+                expires_at: now + ttl,
+            },
+        );
+    }
+}
+
+impl<R: DnsResolver> CachedDns<R> {
+    async fn lookup<T, F>(
+        &self,
+        domain: Domain,
+        record_type: DnsRecordType,
+        resolve: impl Future<Output = Result<Vec<T>, R::Error>>,
+        from_ip: F,
+    ) -> Result<Vec<T>, BoxError>
+    where
+        T: Into<IpAddr> + Copy,
+        F: Fn(IpAddr) -> Option<T>,
+    {
+        if let Some(answers) = self.get(&domain, record_type) {
+            tracing::trace!(%domain, %record_type, "dns cache hit");
+            return match answers {
+                Ok(ips) => Ok(ips.into_iter().filter_map(from_ip).collect()),
+                Err(err) => Err(OpaqueError::from_display(err).into()),
+            };
+        }
+
+        match resolve.await {
+            Ok(ips) => {
+                self.insert(
+                    domain,
+                    record_type,
+                    Ok(ips.iter().copied().map(Into::into).collect()),
+                );
+                Ok(ips)
+            }
+            Err(err) => {
+                let err = err.into();
+                self.insert(domain, record_type, Err(err.to_string().into()));
+                Err(err)
+            }
+        }
+    }
+}
+
+impl<R: DnsResolver> DnsResolver for CachedDns<R> {
+    type Error = BoxError;
+
+    async fn ipv4_lookup(&self, domain: Domain) -> Result<Vec<Ipv4Addr>, Self::Error> {
+        let resolve = self.inner.ipv4_lookup(domain.clone());
+        self.lookup(domain, DnsRecordType::A, resolve, |ip| match ip {
+            IpAddr::V4(ip) => Some(ip),
+            IpAddr::V6(_) => None,
+        })
+        .await
+    }
+
+    async fn ipv6_lookup(&self, domain: Domain) -> Result<Vec<Ipv6Addr>, Self::Error> {
+        let resolve = self.inner.ipv6_lookup(domain.clone());
+        self.lookup(domain, DnsRecordType::Aaaa, resolve, |ip| match ip {
+            IpAddr::V4(_) => None,
+            IpAddr::V6(ip) => Some(ip),
+        })
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[derive(Debug, Clone, Default)]
+    struct CountingDns(Arc<AtomicUsize>);
+
+    impl DnsResolver for CountingDns {
+        type Error = OpaqueError;
+
+        async fn ipv4_lookup(&self, domain: Domain) -> Result<Vec<Ipv4Addr>, Self::Error> {
+            self.0.fetch_add(1, Ordering::SeqCst);
+            if domain == "example.com" {
+                Ok(vec![Ipv4Addr::new(127, 0, 0, 1)])
+            } else {
+                Err(OpaqueError::from_display("not found"))
+            }
+        }
+
+        async fn ipv6_lookup(&self, _domain: Domain) -> Result<Vec<Ipv6Addr>, Self::Error> {
+            self.0.fetch_add(1, Ordering::SeqCst);
+            Ok(vec![Ipv6Addr::LOCALHOST])
+        }
+    }
+
+    #[tokio::test]
+    async fn test_cached_dns_positive_and_negative() {
+        let inner = CountingDns::default();
+        let dns = CachedDns::new(inner.clone());
+
+        for _ in 0..3 {
+            let ips = dns
+                .ipv4_lookup("example.com".parse().unwrap())
+                .await
+                .unwrap();
+            assert_eq!(ips, vec![Ipv4Addr::new(127, 0, 0, 1)]);
+        }
+        assert_eq!(inner.0.load(Ordering::SeqCst), 1);
+
+        for _ in 0..3 {
+            let err = dns
+                .ipv4_lookup("missing.example.com".parse().unwrap())
+                .await
+                .unwrap_err();
+            assert_eq!(err.to_string(), "not found");
+        }
+        assert_eq!(inner.0.load(Ordering::SeqCst), 2);
+
+        dns.ipv6_lookup("example.com".parse().unwrap())
+            .await
+            .unwrap();
+        assert_eq!(inner.0.load(Ordering::SeqCst), 3);
+
+        dns.clear();
+        dns.ipv4_lookup("example.com".parse().unwrap())
+            .await
+            .unwrap();
+        assert_eq!(inner.0.load(Ordering::SeqCst), 4);
+    }
+
+    #[tokio::test]
+    async fn test_cached_dns_expiry() {
+        let inner = CountingDns::default();
+        let dns = CachedDns::new(inner.clone())
+            .with_positive_ttl(Duration::from_millis(10))
+            .with_negative_ttl(Duration::ZERO);
+
+        dns.ipv4_lookup("example.com".parse().unwrap())
+            .await
+            .unwrap();
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        dns.ipv4_lookup("example.com".parse().unwrap())
+            .await
+            .unwrap();
+        assert_eq!(inner.0.load(Ordering::SeqCst), 2);
+
+        for _ in 0..2 {
+            let _ = dns
+                .ipv4_lookup("missing.example.com".parse().unwrap())
+                .await;
+        }
+        assert_eq!(inner.0.load(Ordering::SeqCst), 4);
+    }
+}
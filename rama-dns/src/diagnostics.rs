@@ -0,0 +1,161 @@
+use crate::DnsResolver;
+use rama_core::error::BoxError;
+use rama_net::address::Domain;
+use std::{
+    fmt,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr},
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+/// The type of dns record looked up by a [`DnsResolver`].
+pub enum DnsRecordType {
+    /// An `A` record, resolving into IPv4 addresses.
+    A,
+    /// An `AAAA` record, resolving into IPv6 addresses.
+    Aaaa,
+}
+
+impl fmt::Display for DnsRecordType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::A => f.write_str("A"),
+            Self::Aaaa => f.write_str("AAAA"),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+/// A single lookup recorded in [`DnsDiagnostics`].
+pub struct DnsLookup {
+    domain: Domain,
+    record_type: DnsRecordType,
+    answers: Result<Vec<IpAddr>, Arc<str>>,
+    elapsed: Duration,
+}
+
+impl DnsLookup {
+    /// Returns the [`Domain`] that was looked up.
+    #[must_use]
+    pub fn domain(&self) -> &Domain {
+        &self.domain
+    }
+
+    /// Returns the [`DnsRecordType`] that was looked up.
+    #[must_use]
+    pub fn record_type(&self) -> DnsRecordType {
+        self.record_type
+    }
+
+    /// Returns the addresses the domain resolved into,
+    /// which is empty in case the lookup failed.
+    #[must_use]
+    pub fn answers(&self) -> &[IpAddr] {
+        self.answers.as_deref().unwrap_or_default()
+    }
+
+    /// Returns the error message in case the lookup failed.
+    #[must_use]
+    pub fn error(&self) -> Option<&str> {
+        self.answers.as_ref().err().map(|err| &**err)
+    }
+
+    /// Returns the time it took to resolve the domain.
+    #[must_use]
+    pub fn elapsed(&self) -> Duration {
+        self.elapsed
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+/// Shared handle to record the dns lookups made on behalf of a request,
+/// e.g. to expose the resolution timing and answers for diagnostics.
+///
+/// Insert it in the [`Context`] to have it used by the official `rama`
+/// consumers such as the `TcpConnector`, or wrap any [`DnsResolver`]
+/// using [`DnsDiagnostics::resolver`]. All clones of a handle share the same lookups.
+///
+/// [`Context`]: rama_core::Context
+pub struct DnsDiagnostics(Arc<Mutex<Vec<DnsLookup>>>);
+
+impl DnsDiagnostics {
+    /// Create a new [`DnsDiagnostics`] handle, without any lookups.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the lookups recorded so far, in the order they completed.
+    #[must_use]
+    pub fn lookups(&self) -> Vec<DnsLookup> {
+        self.0.lock().unwrap_or_else(|err| err.into_inner()).clone()
+    }
+
+    /// Wrap the given [`DnsResolver`] such that its lookups are recorded in this handle.
+    pub fn resolver<R>(&self, inner: R) -> DiagnosticsDns<R> {
+        DiagnosticsDns {
+            inner,
+            diagnostics: self.clone(),
+        }
+    }
+
+    fn record<T: Into<IpAddr> + Copy>(
+        &self,
+        domain: Domain,
+        record_type: DnsRecordType,
+        result: &Result<Vec<T>, BoxError>,
+        elapsed: Duration,
+    ) {
+        let answers = match result {
+            Ok(ips) => Ok(ips.iter().copied().map(Into::into).collect()),
+            Err(err) => Err(err.to_string().into()),
+        };
+        self.0
+            .lock()
+            .unwrap_or_else(|err| err.into_inner())
+            .push(DnsLookup {
+                domain,
+                record_type,
+                answers,
+                elapsed,
+            });
+    }
+}
+
+#[derive(Debug, Clone)]
+/// A [`DnsResolver`] recording all its lookups in [`DnsDiagnostics`].
+///
+/// Created using [`DnsDiagnostics::resolver`].
+pub struct DiagnosticsDns<R> {
+    inner: R,
+    diagnostics: DnsDiagnostics,
+}
+
+impl<R: DnsResolver> DnsResolver for DiagnosticsDns<R> {
+    type Error = BoxError;
+
+    async fn ipv4_lookup(&self, domain: Domain) -> Result<Vec<Ipv4Addr>, Self::Error> {
+        let start = Instant::now();
+        let result = self
+            .inner
+            .ipv4_lookup(domain.clone())
+            .await
+            .map_err(Into::into);
+        self.diagnostics
+            .record(domain, DnsRecordType::A, &result, start.elapsed());
+        result
+    }
+
+    async fn ipv6_lookup(&self, domain: Domain) -> Result<Vec<Ipv6Addr>, Self::Error> {
+        let start = Instant::now();
+        let result = self
+            .inner
+            .ipv6_lookup(domain.clone())
+            .await
+            .map_err(Into::into);
+        self.diagnostics
+            .record(domain, DnsRecordType::Aaaa, &result, start.elapsed());
+        result
+    }
+}
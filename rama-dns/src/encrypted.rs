@@ -0,0 +1,371 @@
+use crate::HickoryDns;
+use crate::hickory::config::{
+    NameServerConfig, NameServerConfigGroup, ResolverConfig, ResolverOpts, ServerOrderingStrategy,
+};
+use hickory_resolver::proto::xfer::Protocol;
+use rama_core::error::OpaqueError;
+use rama_core::telemetry::tracing;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+/// The encrypted protocol used to reach an [`EncryptedDnsUpstream`].
+pub enum EncryptedDnsProtocol {
+    /// DNS-over-TLS (DoT), as defined in RFC 7858.
+    Tls,
+    #[cfg(feature = "https")]
+    /// DNS-over-HTTPS (DoH), as defined in RFC 8484.
+    Https,
+}
+
+impl EncryptedDnsProtocol {
+    /// Returns the default port of the protocol.
+    #[must_use]
+    pub fn default_port(self) -> u16 {
+        match self {
+            Self::Tls => 853,
+            #[cfg(feature = "https")]
+            Self::Https => 443,
+        }
+    }
+
+    fn as_hickory_protocol(self) -> Protocol {
+        match self {
+            Self::Tls => Protocol::Tls,
+            #[cfg(feature = "https")]
+            Self::Https => Protocol::Https,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// An upstream nameserver reached over an [`EncryptedDnsProtocol`].
+///
+/// The bootstrap ips are the addresses used to connect to the upstream,
+/// such that resolving the upstream itself does not require a plaintext lookup.
+/// The server name is used to verify the certificate of the upstream.
+///
+/// # Example
+///
+/// ```
+/// use rama_dns::{EncryptedDnsProtocol, EncryptedDnsUpstream, HickoryDns};
+///
+/// let dns = HickoryDns::try_new_encrypted([
+///     EncryptedDnsUpstream::new(
+///         EncryptedDnsProtocol::Tls,
+///         "dns.example.internal",
+///         ["10.0.0.53".parse().unwrap()],
+///     ),
+///     // only used in case the upstream above fails
+///     EncryptedDnsUpstream::cloudflare(EncryptedDnsProtocol::Tls),
+/// ])
+/// .unwrap();
+/// ```
+pub struct EncryptedDnsUpstream {
+    protocol: EncryptedDnsProtocol,
+    server_name: String,
+    bootstrap_ips: Vec<IpAddr>,
+    port: u16,
+    http_endpoint: Option<String>,
+}
+
+impl EncryptedDnsUpstream {
+    /// Create a new [`EncryptedDnsUpstream`] for the given protocol,
+    /// reached at the given bootstrap ips and verified using the given server name.
+    ///
+    /// The default port of the protocol is used, see [`EncryptedDnsProtocol::default_port`].
+    pub fn new(
+        protocol: EncryptedDnsProtocol,
+        server_name: impl Into<String>,
+        bootstrap_ips: impl IntoIterator<Item = IpAddr>,
+    ) -> Self {
+        Self {
+            protocol,
+            server_name: server_name.into(),
+            bootstrap_ips: bootstrap_ips.into_iter().collect(),
+            port: protocol.default_port(),
+            http_endpoint: None,
+        }
+    }
+
+    /// Create an [`EncryptedDnsUpstream`] for Cloudflare's nameservers,
+    /// using `1.1.1.1`, `1.0.0.1` and `2606:4700:4700::1111`, `2606:4700:4700::1001`
+    /// as bootstrap ips.
+    ///
+    /// Please see: <https://www.cloudflare.com/dns/>
+    #[must_use]
+    pub fn cloudflare(protocol: EncryptedDnsProtocol) -> Self {
+        Self::new(
+            protocol,
+            "cloudflare-dns.com",
+            [
+                Ipv4Addr::new(1, 1, 1, 1).into(),
+                Ipv4Addr::new(1, 0, 0, 1).into(),
+                Ipv6Addr::new(0x2606, 0x4700, 0x4700, 0, 0, 0, 0, 0x1111).into(),
+                Ipv6Addr::new(0x2606, 0x4700, 0x4700, 0, 0, 0, 0, 0x1001).into(),
+            ],
+        )
+    }
+
+    /// Create an [`EncryptedDnsUpstream`] for Google's nameservers,
+    /// using `8.8.8.8`, `8.8.4.4` and `2001:4860:4860::8888`, `2001:4860:4860::8844`
+    /// as bootstrap ips.
+    ///
+    /// Please see Google's [privacy
+    /// statement](https://developers.google.com/speed/public-dns/privacy) for important information
+    /// about what they track.
+    #[must_use]
+    pub fn google(protocol: EncryptedDnsProtocol) -> Self {
+        Self::new(
+            protocol,
+            "dns.google",
+            [
+                Ipv4Addr::new(8, 8, 8, 8).into(),
+                Ipv4Addr::new(8, 8, 4, 4).into(),
+                Ipv6Addr::new(0x2001, 0x4860, 0x4860, 0, 0, 0, 0, 0x8888).into(),
+                Ipv6Addr::new(0x2001, 0x4860, 0x4860, 0, 0, 0, 0, 0x8844).into(),
+            ],
+        )
+    }
+
+    /// Create an [`EncryptedDnsUpstream`] for Quad9's "secure" nameservers,
+    /// using `9.9.9.9`, `149.112.112.112` and `2620:fe::fe`, `2620:fe::9`
+    /// as bootstrap ips.
+    ///
+    /// Please see: <https://www.quad9.net/faq/>
+    #[must_use]
+    pub fn quad9(protocol: EncryptedDnsProtocol) -> Self {
+        Self::new(
+            protocol,
+            "dns.quad9.net",
+            [
+                Ipv4Addr::new(9, 9, 9, 9).into(),
+                Ipv4Addr::new(149, 112, 112, 112).into(),
+                Ipv6Addr::new(0x2620, 0x00fe, 0, 0, 0, 0, 0, 0x00fe).into(),
+                Ipv6Addr::new(0x2620, 0x00fe, 0, 0, 0, 0, 0, 0x0009).into(),
+            ],
+        )
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Set the port used to connect to the bootstrap ips,
+        /// overwriting the default port of the protocol.
+        pub fn port(mut self, port: u16) -> Self {
+            self.port = port;
+            self
+        }
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Set the http path used for DNS-over-HTTPS queries,
+        /// which is `/dns-query` if not defined.
+        ///
+        /// Ignored for other protocols.
+        pub fn http_endpoint(mut self, endpoint: Option<String>) -> Self {
+            self.http_endpoint = endpoint;
+            self
+        }
+    }
+
+    /// Returns the [`EncryptedDnsProtocol`] used to reach this upstream.
+    #[must_use]
+    pub fn protocol(&self) -> EncryptedDnsProtocol {
+        self.protocol
+    }
+
+    /// Returns the server name used to verify the certificate of this upstream.
+    #[must_use]
+    pub fn server_name(&self) -> &str {
+        &self.server_name
+    }
+
+    /// Returns the bootstrap ips used to connect to this upstream.
+    #[must_use]
+    pub fn bootstrap_ips(&self) -> &[IpAddr] {
+        &self.bootstrap_ips
+    }
+
+    fn name_servers(&self) -> impl Iterator<Item = NameServerConfig> + '_ {
+        self.bootstrap_ips.iter().map(|ip| {
+            let mut config = NameServerConfig::new(
+                SocketAddr::new(*ip, self.port),
+                self.protocol.as_hickory_protocol(),
+            );
+            config.tls_dns_name = Some(self.server_name.clone());
+            config.http_endpoint = self.http_endpoint.clone();
+            config.trust_negative_responses = false;
+            config
+        })
+    }
+}
+
+impl HickoryDns {
+    /// Construct a new [`HickoryDns`] instance resolving all domains
+    /// over the given encrypted upstreams only.
+    ///
+    /// The upstreams (and their bootstrap ips) are tried in the order given,
+    /// falling back to the next one when a query fails. No plaintext DNS is used,
+    /// not even to resolve the upstreams themselves.
+    ///
+    /// Returns an error in case no upstream (with at least one bootstrap ip) is given.
+    pub fn try_new_encrypted(
+        upstreams: impl IntoIterator<Item = EncryptedDnsUpstream>,
+    ) -> Result<Self, OpaqueError> {
+        let (config, options) = encrypted_resolver_config(upstreams)?;
+        tracing::trace!(
+            "create HickoryDns resolver using {} encrypted nameserver(s)",
+            config.name_servers().len()
+        );
+        Ok(Self::builder()
+            .with_config(config)
+            .with_options(options)
+            .build())
+    }
+
+    /// Construct a new non-shared [`HickoryDns`] instance using
+    /// Cloudflare's nameservers over DNS-over-TLS.
+    ///
+    /// See [`EncryptedDnsUpstream::cloudflare`] for more information.
+    #[must_use]
+    pub fn new_cloudflare_tls() -> Self {
+        Self::new_encrypted_preset(EncryptedDnsUpstream::cloudflare(EncryptedDnsProtocol::Tls))
+    }
+
+    #[cfg(feature = "https")]
+    /// Construct a new non-shared [`HickoryDns`] instance using
+    /// Cloudflare's nameservers over DNS-over-HTTPS.
+    ///
+    /// See [`EncryptedDnsUpstream::cloudflare`] for more information.
+    #[must_use]
+    pub fn new_cloudflare_https() -> Self {
+        Self::new_encrypted_preset(EncryptedDnsUpstream::cloudflare(
+            EncryptedDnsProtocol::Https,
+        ))
+    }
+
+    /// Construct a new non-shared [`HickoryDns`] instance using
+    /// Google's nameservers over DNS-over-TLS.
+    ///
+    /// See [`EncryptedDnsUpstream::google`] for more information.
+    #[must_use]
+    pub fn new_google_tls() -> Self {
+        Self::new_encrypted_preset(EncryptedDnsUpstream::google(EncryptedDnsProtocol::Tls))
+    }
+
+    #[cfg(feature = "https")]
+    /// Construct a new non-shared [`HickoryDns`] instance using
+    /// Google's nameservers over DNS-over-HTTPS.
+    ///
+    /// See [`EncryptedDnsUpstream::google`] for more information.
+    #[must_use]
+    pub fn new_google_https() -> Self {
+        Self::new_encrypted_preset(EncryptedDnsUpstream::google(EncryptedDnsProtocol::Https))
+    }
+
+    /// Construct a new non-shared [`HickoryDns`] instance using
+    /// Quad9's nameservers over DNS-over-TLS.
+    ///
+    /// See [`EncryptedDnsUpstream::quad9`] for more information.
+    #[must_use]
+    pub fn new_quad9_tls() -> Self {
+        Self::new_encrypted_preset(EncryptedDnsUpstream::quad9(EncryptedDnsProtocol::Tls))
+    }
+
+    #[cfg(feature = "https")]
+    /// Construct a new non-shared [`HickoryDns`] instance using
+    /// Quad9's nameservers over DNS-over-HTTPS.
+    ///
+    /// See [`EncryptedDnsUpstream::quad9`] for more information.
+    #[must_use]
+    pub fn new_quad9_https() -> Self {
+        Self::new_encrypted_preset(EncryptedDnsUpstream::quad9(EncryptedDnsProtocol::Https))
+    }
+
+    fn new_encrypted_preset(upstream: EncryptedDnsUpstream) -> Self {
+        let (config, options) = encrypted_resolver_config([upstream])
+            .expect("preset upstreams have bootstrap ips defined");
+        Self::builder()
+            .with_config(config)
+            .with_options(options)
+            .build()
+    }
+}
+
+fn encrypted_resolver_config(
+    upstreams: impl IntoIterator<Item = EncryptedDnsUpstream>,
+) -> Result<(ResolverConfig, ResolverOpts), OpaqueError> {
+    let mut name_servers = Vec::new();
+    for upstream in upstreams {
+        name_servers.extend(upstream.name_servers());
+    }
+    if name_servers.is_empty() {
+        return Err(OpaqueError::from_display(
+            "encrypted dns resolver requires at least one upstream bootstrap ip",
+        ));
+    }
+
+    let mut options = ResolverOpts::default();
+    // query one nameserver at a time, in the order defined by the user,
+    // such that later upstreams are only used as a fallback
+    options.server_ordering_strategy = ServerOrderingStrategy::UserProvidedOrder;
+    options.num_concurrent_reqs = 1;
+
+    Ok((
+        ResolverConfig::from_parts(None, vec![], NameServerConfigGroup::from(name_servers)),
+        options,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encrypted_resolver_config_order() {
+        let (config, options) = encrypted_resolver_config([
+            EncryptedDnsUpstream::new(
+                EncryptedDnsProtocol::Tls,
+                "dns.example.internal",
+                [IpAddr::V4(Ipv4Addr::new(10, 0, 0, 53))],
+            )
+            .with_port(8853),
+            EncryptedDnsUpstream::cloudflare(EncryptedDnsProtocol::Tls),
+        ])
+        .unwrap();
+
+        assert_eq!(
+            options.server_ordering_strategy,
+            ServerOrderingStrategy::UserProvidedOrder
+        );
+
+        let name_servers = config.name_servers();
+        assert_eq!(name_servers.len(), 5);
+        assert_eq!(
+            name_servers[0].socket_addr,
+            "10.0.0.53:8853".parse().unwrap()
+        );
+        assert_eq!(
+            name_servers[0].tls_dns_name.as_deref(),
+            Some("dns.example.internal")
+        );
+        assert_eq!(name_servers[1].socket_addr, "1.1.1.1:853".parse().unwrap());
+        assert!(
+            name_servers
+                .iter()
+                .all(|config| config.protocol == Protocol::Tls)
+        );
+    }
+
+    #[test]
+    fn test_encrypted_resolver_config_empty() {
+        assert!(encrypted_resolver_config([]).is_err());
+        assert!(
+            encrypted_resolver_config([EncryptedDnsUpstream::new(
+                EncryptedDnsProtocol::Tls,
+                "dns.example.internal",
+                [],
+            )])
+            .is_err()
+        );
+    }
+}
@@ -19,6 +19,31 @@
 //!
 //! Thank you cloudflare.
 //!
+//! ## Other resolvers
+//!
+//! Besides [`HickoryDns`] there is also the [`SystemDns`] resolver,
+//! which makes use of the resolver of the operating system.
+//! Any [`DnsResolver`] can be wrapped in a [`CachedDns`] to cache
+//! its (positive and negative) answers.
+//!
+//! ## Encrypted DNS
+//!
+//! With the `tls` feature enabled, [`HickoryDns`] can be created
+//! using `HickoryDns::try_new_encrypted`, resolving all domains over DNS-over-TLS
+//! upstreams, tried in the given order. The `https` feature adds DNS-over-HTTPS support. The upstreams are reached
+//! using their bootstrap ips, such that no plaintext DNS leaves the host.
+//!
+//! ## Per request overwrites and diagnostics
+//!
+//! The following types can be inserted in the [`Context`] of a request,
+//! and are supported by the official `rama` consumers such as the `TcpConnector`:
+//!
+//! - [`DnsOverwrite`]: resolve specific domains to fixed addresses;
+//! - [`DnsResolverOverwrite`]: use a different [`DnsResolver`] for the request;
+//! - [`DnsDiagnostics`]: record the timing and answers of all lookups made for the request.
+//!
+//! [`Context`]: rama_core::Context
+//!
 //! Use [`try_init_global_dns_resolver`] or [`init_global_dns_resolver`] to
 //! set the global [`DnsResolver`] as early as possible (e.g. at the top of your _main_ function).
 //!
@@ -136,4 +161,22 @@ mod variant;
 
 mod boxed;
 #[doc(inline)]
-pub use boxed::BoxDnsResolver;
+pub use boxed::{BoxDnsResolver, DnsResolverOverwrite};
+
+mod system;
+#[doc(inline)]
+pub use system::SystemDns;
+
+mod cache;
+#[doc(inline)]
+pub use cache::CachedDns;
+
+mod diagnostics;
+#[doc(inline)]
+pub use diagnostics::{DiagnosticsDns, DnsDiagnostics, DnsLookup, DnsRecordType};
+
+#[cfg(feature = "tls")]
+mod encrypted;
+#[cfg(feature = "tls")]
+#[doc(inline)]
+pub use encrypted::{EncryptedDnsProtocol, EncryptedDnsUpstream};
@@ -0,0 +1,65 @@
+use crate::DnsResolver;
+use rama_core::error::{ErrorContext, OpaqueError};
+use rama_net::address::Domain;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+#[derive(Debug, Clone, Default)]
+#[non_exhaustive]
+/// A [`DnsResolver`] using the resolver of the operating system,
+/// e.g. `getaddrinfo` on unix platforms.
+///
+/// Lookups are executed on the blocking thread pool of tokio,
+/// such that they do not block the async runtime.
+///
+/// Use this resolver in case you want to respect the system configuration,
+/// such as `/etc/hosts` and `nsswitch.conf`, which [`HickoryDns`] only partially supports.
+///
+/// [`HickoryDns`]: crate::HickoryDns
+pub struct SystemDns;
+
+impl SystemDns {
+    /// Create a new [`SystemDns`] resolver.
+    #[must_use]
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+async fn lookup(domain: Domain) -> Result<impl Iterator<Item = IpAddr>, OpaqueError> {
+    let addrs = tokio::net::lookup_host(format!("{domain}:0"))
+        .await
+        .with_context(|| format!("system dns lookup of domain {domain}"))?;
+    Ok(addrs.map(|addr| addr.ip()))
+}
+
+impl DnsResolver for SystemDns {
+    type Error = OpaqueError;
+
+    async fn ipv4_lookup(&self, domain: Domain) -> Result<Vec<Ipv4Addr>, Self::Error> {
+        let ips: Vec<_> = lookup(domain)
+            .await?
+            .filter_map(|ip| match ip {
+                IpAddr::V4(ip) => Some(ip),
+                IpAddr::V6(_) => None,
+            })
+            .collect();
+        if ips.is_empty() {
+            return Err(OpaqueError::from_display("no IPv4 address(es) found"));
+        }
+        Ok(ips)
+    }
+
+    async fn ipv6_lookup(&self, domain: Domain) -> Result<Vec<Ipv6Addr>, Self::Error> {
+        let ips: Vec<_> = lookup(domain)
+            .await?
+            .filter_map(|ip| match ip {
+                IpAddr::V4(_) => None,
+                IpAddr::V6(ip) => Some(ip),
+            })
+            .collect();
+        if ips.is_empty() {
+            return Err(OpaqueError::from_display("no IPv6 address(es) found"));
+        }
+        Ok(ips)
+    }
+}
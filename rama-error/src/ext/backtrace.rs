@@ -1,3 +1,4 @@
+use super::classified::is_classified;
 use std::{
     backtrace::Backtrace,
     error::Error,
@@ -43,7 +44,9 @@ impl<E: Error> Debug for BacktraceError<E> {
 
 impl<E: Error + 'static> Error for BacktraceError<E> {
     fn source(&self) -> Option<&(dyn Error + 'static)> {
-        if let Some(err) = self.inner.source() {
+        if !is_classified(&self.inner)
+            && let Some(err) = self.inner.source()
+        {
             return Some(err);
         }
         let err = &self.inner;
@@ -0,0 +1,378 @@
+use super::OpaqueError;
+use crate::BoxError;
+use std::{
+    error::Error as StdError,
+    fmt::{self, Debug, Display},
+    io,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+/// The kind of a [`ClassifiedError`], describing where or how it failed.
+pub enum ErrorKind {
+    /// An operation did not complete in time.
+    Timeout,
+    /// A connection could not be established.
+    Connect,
+    /// A peer violated the protocol, e.g. an invalid http response or tls handshake.
+    Protocol,
+    /// The upstream (e.g. the proxied server) failed or closed the connection.
+    Upstream,
+    /// Any other failure, e.g. a bug or misconfiguration.
+    Internal,
+}
+
+impl ErrorKind {
+    /// Returns true if errors of this kind are retryable by default,
+    /// which is the case for [`ErrorKind::Timeout`], [`ErrorKind::Connect`]
+    /// and [`ErrorKind::Upstream`] errors.
+    #[must_use]
+    pub fn is_retryable(self) -> bool {
+        matches!(self, Self::Timeout | Self::Connect | Self::Upstream)
+    }
+
+    /// Returns the kind of an [`io::ErrorKind`],
+    /// or `None` in case it cannot be classified.
+    #[must_use]
+    pub fn from_io(kind: io::ErrorKind) -> Option<Self> {
+        match kind {
+            io::ErrorKind::TimedOut => Some(Self::Timeout),
+            io::ErrorKind::ConnectionRefused
+            | io::ErrorKind::AddrNotAvailable
+            | io::ErrorKind::HostUnreachable
+            | io::ErrorKind::NetworkUnreachable
+            | io::ErrorKind::NetworkDown => Some(Self::Connect),
+            io::ErrorKind::ConnectionReset
+            | io::ErrorKind::ConnectionAborted
+            | io::ErrorKind::BrokenPipe
+            | io::ErrorKind::NotConnected
+            | io::ErrorKind::UnexpectedEof => Some(Self::Upstream),
+            io::ErrorKind::InvalidData => Some(Self::Protocol),
+            _ => None,
+        }
+    }
+
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Timeout => "timeout",
+            Self::Connect => "connect",
+            Self::Protocol => "protocol",
+            Self::Upstream => "upstream",
+            Self::Internal => "internal",
+        }
+    }
+}
+
+impl Display for ErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// An error classified by its [`ErrorKind`] and retryability,
+/// with a chain of contexts describing what was being done when it occurred.
+///
+/// Layers such as retry, circuit breakers and logging can act on the classification
+/// using [`ClassifiedError::classify`], which also finds a classified error
+/// wrapped in an [`OpaqueError`] or as the source of another error.
+///
+/// See the [module level documentation](crate::error) for more information.
+///
+/// # Examples
+///
+/// ```
+/// use rama_error::{ClassifiedError, ErrorContext, ErrorKind, OpaqueError};
+///
+/// let error = ClassifiedError::connect(std::io::Error::other("refused"))
+///     .context("connect to example.com:443");
+/// assert_eq!(error.kind(), ErrorKind::Connect);
+/// assert!(error.is_retryable());
+/// assert_eq!(error.to_string(), "connect to example.com:443\r\n ↪ refused");
+///
+/// let result: Result<(), _> = Err(error);
+/// let error: OpaqueError = result.context("serve request").unwrap_err();
+/// let class = ClassifiedError::classify(&error).unwrap();
+/// assert_eq!(class.kind(), ErrorKind::Connect);
+/// ```
+pub struct ClassifiedError {
+    kind: ErrorKind,
+    retryable: bool,
+    contexts: Vec<Box<dyn Display + Send + Sync + 'static>>,
+    source: BoxError,
+}
+
+/// The classification of an error, as found by [`ClassifiedError::classify`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Classification {
+    kind: ErrorKind,
+    retryable: bool,
+}
+
+impl Classification {
+    /// Returns the [`ErrorKind`] of the error.
+    #[must_use]
+    pub fn kind(&self) -> ErrorKind {
+        self.kind
+    }
+
+    /// Returns true if the failed operation can be retried.
+    #[must_use]
+    pub fn is_retryable(&self) -> bool {
+        self.retryable
+    }
+}
+
+impl ClassifiedError {
+    /// Create a new [`ClassifiedError`] of the given kind,
+    /// retryable depending on [`ErrorKind::is_retryable`].
+    pub fn new(kind: ErrorKind, error: impl Into<BoxError>) -> Self {
+        Self {
+            kind,
+            retryable: kind.is_retryable(),
+            contexts: Vec::new(),
+            source: error.into(),
+        }
+    }
+
+    /// Create a new [`ErrorKind::Timeout`] error.
+    pub fn timeout(error: impl Into<BoxError>) -> Self {
+        Self::new(ErrorKind::Timeout, error)
+    }
+
+    /// Create a new [`ErrorKind::Connect`] error.
+    pub fn connect(error: impl Into<BoxError>) -> Self {
+        Self::new(ErrorKind::Connect, error)
+    }
+
+    /// Create a new [`ErrorKind::Protocol`] error.
+    pub fn protocol(error: impl Into<BoxError>) -> Self {
+        Self::new(ErrorKind::Protocol, error)
+    }
+
+    /// Create a new [`ErrorKind::Upstream`] error.
+    pub fn upstream(error: impl Into<BoxError>) -> Self {
+        Self::new(ErrorKind::Upstream, error)
+    }
+
+    /// Create a new [`ErrorKind::Internal`] error.
+    pub fn internal(error: impl Into<BoxError>) -> Self {
+        Self::new(ErrorKind::Internal, error)
+    }
+
+    /// Overwrite the default retryability of the error's [`ErrorKind`].
+    #[must_use]
+    pub fn with_retryable(mut self, retryable: bool) -> Self {
+        self.retryable = retryable;
+        self
+    }
+
+    /// Add a context to the error, keeping its classification.
+    ///
+    /// Contexts are displayed from the most recently added one to the source error.
+    #[must_use]
+    pub fn context<M>(mut self, context: M) -> Self
+    where
+        M: Display + Send + Sync + 'static,
+    {
+        self.contexts.push(Box::new(context));
+        self
+    }
+
+    /// Lazily add a context to the error, keeping its classification.
+    #[must_use]
+    pub fn with_context<C, F>(self, context: F) -> Self
+    where
+        C: Display + Send + Sync + 'static,
+        F: FnOnce() -> C,
+    {
+        self.context(context())
+    }
+
+    /// Returns the [`ErrorKind`] of the error.
+    #[must_use]
+    pub fn kind(&self) -> ErrorKind {
+        self.kind
+    }
+
+    /// Returns true if the failed operation can be retried.
+    #[must_use]
+    pub fn is_retryable(&self) -> bool {
+        self.retryable
+    }
+
+    /// Returns the contexts of the error, from the most recently added one.
+    pub fn contexts(&self) -> impl Iterator<Item = &(dyn Display + Send + Sync + 'static)> {
+        self.contexts.iter().rev().map(AsRef::as_ref)
+    }
+
+    /// Returns the [`Classification`] of the given error.
+    ///
+    /// This is the classification of the first [`ClassifiedError`] found in its source chain.
+    /// Otherwise the kind of the first [`io::Error`] found is used,
+    /// if it can be classified using [`ErrorKind::from_io`].
+    #[must_use]
+    pub fn classify(error: &(dyn StdError + 'static)) -> Option<Classification> {
+        if let Some(error) = Self::find(error) {
+            return Some(Classification {
+                kind: error.kind,
+                retryable: error.retryable,
+            });
+        }
+        let kind = sources(error)
+            .find_map(|error| error.downcast_ref::<io::Error>())
+            .and_then(|error| ErrorKind::from_io(error.kind()))?;
+        Some(Classification {
+            kind,
+            retryable: kind.is_retryable(),
+        })
+    }
+
+    /// Returns the first [`ClassifiedError`] found in the source chain of the given error.
+    #[must_use]
+    pub fn find(error: &(dyn StdError + 'static)) -> Option<&Self> {
+        sources(error).find_map(|error| error.downcast_ref::<Self>())
+    }
+
+    /// Consumes the [`ClassifiedError`] and returns its source error.
+    #[must_use]
+    pub fn into_source(self) -> BoxError {
+        self.source
+    }
+}
+
+/// Iterate over the error and its sources.
+///
+/// Stops at an error returning itself as its source,
+/// as some errors do to hide a generic source error.
+fn sources<'a>(
+    error: &'a (dyn StdError + 'static),
+) -> impl Iterator<Item = &'a (dyn StdError + 'static)> {
+    std::iter::successors(Some(error), |error| {
+        error
+            .source()
+            .filter(|source| !std::ptr::addr_eq(*source, *error))
+    })
+}
+
+/// Returns true if the error is a [`ClassifiedError`] or an [`OpaqueError`] wrapping one.
+///
+/// The wrappers of this crate return such an error as their source,
+/// rather than skipping to its source, such that it can be found by [`ClassifiedError::find`].
+pub(crate) fn is_classified(error: &(dyn StdError + 'static)) -> bool {
+    error.is::<ClassifiedError>()
+        || error
+            .downcast_ref::<OpaqueError>()
+            .is_some_and(|error| error.is::<ClassifiedError>())
+}
+
+impl Debug for ClassifiedError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let contexts: Vec<_> = self.contexts().map(ToString::to_string).collect();
+        f.debug_struct("ClassifiedError")
+            .field("kind", &self.kind)
+            .field("retryable", &self.retryable)
+            .field("contexts", &contexts)
+            .field("source", &self.source)
+            .finish()
+    }
+}
+
+impl Display for ClassifiedError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for context in self.contexts() {
+            write!(f, "{context}\r\n ↪ ")?;
+        }
+        Display::fmt(&self.source, f)
+    }
+}
+
+impl StdError for ClassifiedError {
+    fn source(&self) -> Option<&(dyn StdError + 'static)> {
+        Some(self.source.as_ref())
+    }
+}
+
+impl From<BoxError> for ClassifiedError {
+    /// Returns the boxed error in case it is a [`ClassifiedError`],
+    /// or otherwise classifies it using [`ClassifiedError::classify`],
+    /// defaulting to an [`ErrorKind::Internal`] error.
+    fn from(error: BoxError) -> Self {
+        let error = match error.downcast::<Self>() {
+            Ok(error) => return *error,
+            Err(error) => error,
+        };
+        match Self::classify(error.as_ref()) {
+            Some(class) => Self::new(class.kind, error).with_retryable(class.retryable),
+            None => Self::internal(error),
+        }
+    }
+}
+
+impl From<OpaqueError> for ClassifiedError {
+    fn from(error: OpaqueError) -> Self {
+        Self::from(error.into_boxed())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ErrorContext, ErrorExt};
+
+    #[test]
+    fn classified_error_context() {
+        let error = ClassifiedError::timeout(io::Error::other("slow"))
+            .context("read response")
+            .context("proxy request");
+        assert_eq!(
+            error.to_string(),
+            "proxy request\r\n ↪ read response\r\n ↪ slow"
+        );
+        assert_eq!(error.kind(), ErrorKind::Timeout);
+        assert!(error.is_retryable());
+    }
+
+    #[test]
+    fn classified_error_classify_wrapped() {
+        let error = ClassifiedError::protocol(io::Error::other("invalid response"))
+            .context("read response");
+        let error = error.context("proxy request").context("serve").backtrace();
+        let class = ClassifiedError::classify(&error).unwrap();
+        assert_eq!(class.kind(), ErrorKind::Protocol);
+        assert!(!class.is_retryable());
+
+        let result: Result<(), _> = Err(ClassifiedError::upstream("closed").with_retryable(false));
+        let error = result.context("proxy request").unwrap_err().into_boxed();
+        let class = ClassifiedError::classify(error.as_ref()).unwrap();
+        assert_eq!(class.kind(), ErrorKind::Upstream);
+        assert!(!class.is_retryable());
+    }
+
+    #[test]
+    fn classified_error_classify_io() {
+        let error = io::Error::from(io::ErrorKind::ConnectionRefused).context("connect");
+        let class = ClassifiedError::classify(&error).unwrap();
+        assert_eq!(class.kind(), ErrorKind::Connect);
+        assert!(class.is_retryable());
+
+        // a readiness signal and not a timeout
+        let error = io::Error::from(io::ErrorKind::WouldBlock).context("read");
+        assert!(ClassifiedError::classify(&error).is_none());
+
+        assert!(ClassifiedError::classify(&OpaqueError::from_display("unknown")).is_none());
+    }
+
+    #[test]
+    fn classified_error_from_box_error() {
+        let error: BoxError = Box::new(ClassifiedError::timeout("slow"));
+        assert_eq!(ClassifiedError::from(error).kind(), ErrorKind::Timeout);
+
+        let error: BoxError = Box::new(io::Error::from(io::ErrorKind::ConnectionReset));
+        assert_eq!(ClassifiedError::from(error).kind(), ErrorKind::Upstream);
+
+        let error: BoxError = "oops".into();
+        let error = ClassifiedError::from(error);
+        assert_eq!(error.kind(), ErrorKind::Internal);
+        assert!(!error.is_retryable());
+    }
+}
@@ -1,3 +1,4 @@
+use super::classified::is_classified;
 use std::fmt::{self, Debug, Display, Write};
 
 pub(crate) struct ContextError<C, E> {
@@ -34,7 +35,9 @@ where
     E: std::error::Error + 'static,
 {
     fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
-        if let Some(err) = self.error.source() {
+        if !is_classified(&self.error)
+            && let Some(err) = self.error.source()
+        {
             return Some(err);
         }
         let err = &self.error;
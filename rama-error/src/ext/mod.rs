@@ -1,8 +1,11 @@
 use std::fmt::Display;
 
 mod backtrace;
+mod classified;
 mod context;
 
+pub use classified::{Classification, ClassifiedError, ErrorKind};
+
 mod wrapper;
 pub use wrapper::OpaqueError;
 
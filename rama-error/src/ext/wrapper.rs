@@ -1,3 +1,4 @@
+use super::classified::is_classified;
 use crate::BoxError;
 use std::fmt::{self, Debug, Display};
 
@@ -87,7 +88,9 @@ impl Display for OpaqueError {
 
 impl std::error::Error for OpaqueError {
     fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
-        if let Some(err) = self.0.source() {
+        if !is_classified(self.0.as_ref())
+            && let Some(err) = self.0.source()
+        {
             return Some(err);
         }
         let err = self.0.as_ref();
@@ -148,6 +148,27 @@
 //! assert!(result.is_err());
 //! ```
 //!
+//! ## Classified Error
+//!
+//! The [`ClassifiedError`] type classifies an error by its [`ErrorKind`]
+//! (timeout, connect, protocol, upstream or internal) and whether or not it is retryable,
+//! while keeping a chain of contexts, similar to the ones added using [`ErrorExt::context`].
+//!
+//! Middleware such as retry and circuit-breaker layers, or your logging, can use
+//! [`ClassifiedError::classify`] to act on the classification of an error, even when it
+//! is wrapped in an [`OpaqueError`] or [`BoxError`], rather than matching on its message.
+//!
+//! ```rust
+//! use rama_error::{BoxError, ClassifiedError, ErrorKind};
+//!
+//! let error: BoxError = ClassifiedError::timeout("no response within 10s")
+//!     .context("proxy request")
+//!     .into();
+//! let class = ClassifiedError::classify(error.as_ref()).unwrap();
+//! assert_eq!(class.kind(), ErrorKind::Timeout);
+//! assert!(class.is_retryable());
+//! ```
+//!
 //! ## Error Composition
 //!
 //! Sometimes it can be useful to compose errors with more
@@ -191,7 +212,7 @@ use std::error::Error as StdError;
 pub type BoxError = Box<dyn StdError + Send + Sync>;
 
 mod ext;
-pub use ext::{ErrorContext, ErrorExt, OpaqueError};
+pub use ext::{Classification, ClassifiedError, ErrorContext, ErrorExt, ErrorKind, OpaqueError};
 
 mod macros;
 #[doc(inline)]
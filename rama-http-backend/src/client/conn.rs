@@ -178,7 +178,8 @@ where
                     server.service.name = %server_address,
                 );
 
-                ctx.spawn(
+                ctx.spawn_labeled(
+                    "h2 client connection",
                     async move {
                         if let Err(err) = conn.await {
                             tracing::debug!("connection failed: {err:?}");
@@ -222,7 +223,8 @@ where
                     server.service.name = %server_address,
                 );
 
-                ctx.spawn(
+                ctx.spawn_labeled(
+                    "h1 client connection",
                     async move {
                         if let Err(err) = conn.await {
                             tracing::debug!("connection failed: {err:?}");
@@ -1,6 +1,6 @@
 use std::fmt;
 
-use rama_core::error::BoxError;
+use rama_core::error::{BoxError, ClassifiedError};
 
 #[derive(Debug)]
 /// error that can be returned in case a http proxy
@@ -65,3 +65,17 @@ impl std::error::Error for HttpProxyError {
         }
     }
 }
+
+impl From<HttpProxyError> for ClassifiedError {
+    fn from(error: HttpProxyError) -> Self {
+        match &error {
+            HttpProxyError::AuthRequired => Self::upstream(error).with_retryable(false),
+            HttpProxyError::Unavailable => Self::upstream(error),
+            HttpProxyError::Transport(err) => match Self::classify(err.as_ref()) {
+                Some(class) => Self::new(class.kind(), error).with_retryable(class.is_retryable()),
+                None => Self::connect(error),
+            },
+            HttpProxyError::Other(_) => Self::protocol(error),
+        }
+    }
+}
@@ -4,7 +4,7 @@ use super::InnerHttpProxyConnector;
 use pin_project_lite::pin_project;
 use rama_core::{
     Context, Service,
-    error::{BoxError, ErrorExt, OpaqueError},
+    error::{BoxError, ClassifiedError, ErrorExt, OpaqueError},
     telemetry::tracing,
 };
 use rama_http::{HeaderMap, io::upgrade};
@@ -170,14 +170,16 @@ where
                 .connect(ctx, req)
                 .await
                 .map_err(|err| match address.as_ref() {
-                    Some(address) => OpaqueError::from_std(HttpProxyError::Transport(
-                        OpaqueError::from_boxed(err.into())
-                            .context(format!(
-                                "establish connection to proxy {} (protocol: {:?})",
-                                address.authority, address.protocol,
-                            ))
-                            .into_boxed(),
-                    )),
+                    Some(address) => {
+                        OpaqueError::from_std(ClassifiedError::from(HttpProxyError::Transport(
+                            OpaqueError::from_boxed(err.into())
+                                .context(format!(
+                                    "establish connection to proxy {} (protocol: {:?})",
+                                    address.authority, address.protocol,
+                                ))
+                                .into_boxed(),
+                        )))
+                    }
                     None => {
                         OpaqueError::from_boxed(err.into()).context("establish connection target")
                     }
@@ -246,10 +248,9 @@ where
             }
         }
 
-        let (headers, conn) = connector
-            .handshake(conn)
-            .await
-            .map_err(|err| OpaqueError::from_std(err).context("http proxy handshake"))?;
+        let (headers, conn) = connector.handshake(conn).await.map_err(|err| {
+            OpaqueError::from_std(ClassifiedError::from(err)).context("http proxy handshake")
+        })?;
 
         tracing::trace!("inserting HttpProxyHeaders in context");
         ctx.insert(HttpProxyConnectResponseHeaders::new(headers));
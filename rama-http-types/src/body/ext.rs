@@ -1,4 +1,6 @@
 use crate::dep::http_body_util::BodyExt;
+use rama_core::buffer::{BufferPool, PooledBuffer};
+use rama_core::bytes::{Buf, BufMut, Bytes};
 use rama_error::{BoxError, ErrorContext, OpaqueError};
 
 /// An extension trait for [`Body`] that provides methods to extract data from it.
@@ -21,23 +23,17 @@ where
     async fn try_into_json<T: serde::de::DeserializeOwned + Send + 'static>(
         self,
     ) -> Result<T, OpaqueError> {
-        let body = self
-            .into_body()
-            .collect()
+        let body = collect_pooled(self.into_body())
             .await
-            .map_err(|err| OpaqueError::from_boxed(err.into()))?;
-        serde_json::from_slice(body.to_bytes().as_ref())
-            .context("deserialize response body as JSON")
+            .map_err(OpaqueError::from_boxed)?;
+        serde_json::from_slice(body.as_slice()).context("deserialize response body as JSON")
     }
 
     async fn try_into_string(self) -> Result<String, OpaqueError> {
-        let body = self
-            .into_body()
-            .collect()
+        let body = collect_pooled(self.into_body())
             .await
-            .map_err(|err| OpaqueError::from_boxed(err.into()))?;
-        let bytes = body.to_bytes();
-        String::from_utf8(bytes.to_vec()).context("parse body as utf-8 string")
+            .map_err(OpaqueError::from_boxed)?;
+        String::from_utf8(body.into_vec()).context("parse body as utf-8 string")
     }
 }
 
@@ -48,22 +44,17 @@ where
     async fn try_into_json<T: serde::de::DeserializeOwned + Send + 'static>(
         self,
     ) -> Result<T, OpaqueError> {
-        let body = self
-            .into_body()
-            .collect()
+        let body = collect_pooled(self.into_body())
             .await
-            .map_err(|err| OpaqueError::from_boxed(err.into()))?;
-        serde_json::from_slice(body.to_bytes().as_ref()).context("deserialize request body as JSON")
+            .map_err(OpaqueError::from_boxed)?;
+        serde_json::from_slice(body.as_slice()).context("deserialize request body as JSON")
     }
 
     async fn try_into_string(self) -> Result<String, OpaqueError> {
-        let body = self
-            .into_body()
-            .collect()
+        let body = collect_pooled(self.into_body())
             .await
-            .map_err(|err| OpaqueError::from_boxed(err.into()))?;
-        let bytes = body.to_bytes();
-        String::from_utf8(bytes.to_vec()).context("parse request body as utf-8 string")
+            .map_err(OpaqueError::from_boxed)?;
+        String::from_utf8(body.into_vec()).context("parse request body as utf-8 string")
     }
 }
 
@@ -71,15 +62,78 @@ impl<B: Into<crate::Body> + Send + 'static> BodyExtractExt for B {
     async fn try_into_json<T: serde::de::DeserializeOwned + Send + 'static>(
         self,
     ) -> Result<T, OpaqueError> {
-        let body = self.into().collect().await.context("collect body")?;
-        serde_json::from_slice(body.to_bytes().as_ref()).context("deserialize body as JSON")
+        let body = collect_pooled(self.into())
+            .await
+            .map_err(OpaqueError::from_boxed)
+            .context("collect body")?;
+        serde_json::from_slice(body.as_slice()).context("deserialize body as JSON")
     }
 
     async fn try_into_string(self) -> Result<String, OpaqueError> {
-        let body = self.into().collect().await.context("collect body")?;
-        let bytes = body.to_bytes();
-        String::from_utf8(bytes.to_vec()).context("parse body as utf-8 string")
+        let body = collect_pooled(self.into())
+            .await
+            .map_err(OpaqueError::from_boxed)
+            .context("collect body")?;
+        String::from_utf8(body.into_vec()).context("parse body as utf-8 string")
+    }
+}
+
+/// The aggregated data of a body, see [`collect_pooled`].
+enum CollectedData {
+    /// The body consisted of at most one data frame, which is kept as-is.
+    Single(Bytes),
+    /// The data frames of the body were aggregated into a pooled buffer.
+    Pooled(PooledBuffer),
+}
+
+impl CollectedData {
+    fn as_slice(&self) -> &[u8] {
+        match self {
+            Self::Single(bytes) => bytes,
+            Self::Pooled(buf) => buf,
+        }
+    }
+
+    fn into_vec(self) -> Vec<u8> {
+        match self {
+            Self::Single(bytes) => Vec::from(bytes),
+            Self::Pooled(buf) => buf.to_vec(),
+        }
+    }
+}
+
+/// Aggregate the data of the body.
+///
+/// A body consisting of a single data frame is returned without copying,
+/// while multiple data frames are aggregated into a buffer of the global [`BufferPool`],
+/// which returns to the pool once the body is deserialized.
+async fn collect_pooled<B>(body: B) -> Result<CollectedData, BoxError>
+where
+    B: crate::dep::http_body::Body<Error: Into<BoxError>>,
+{
+    let mut body = std::pin::pin!(body);
+    let mut collected = CollectedData::Single(Bytes::new());
+    while let Some(frame) = body.frame().await {
+        let Ok(mut data) = frame.map_err(Into::into)?.into_data() else {
+            continue;
+        };
+        collected = match collected {
+            CollectedData::Single(bytes) if bytes.is_empty() => {
+                CollectedData::Single(data.copy_to_bytes(data.remaining()))
+            }
+            CollectedData::Single(bytes) => {
+                let mut buf = BufferPool::global().get();
+                buf.put(bytes);
+                buf.put(data);
+                CollectedData::Pooled(buf)
+            }
+            CollectedData::Pooled(mut buf) => {
+                buf.put(data);
+                CollectedData::Pooled(buf)
+            }
+        };
     }
+    Ok(collected)
 }
 
 mod private {
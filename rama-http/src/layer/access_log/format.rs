@@ -0,0 +1,333 @@
+use super::AccessLogRecord;
+use rama_core::error::OpaqueError;
+use serde_json::{Map, Value};
+use std::{fmt::Write as _, sync::Arc};
+
+/// The format in which [`AccessLogRecord`]s are written.
+#[derive(Debug, Clone, Default)]
+pub enum AccessLogFormat {
+    /// Write each record as a single line JSON object.
+    #[default]
+    Json,
+    /// Write each record using a user-defined [`AccessLogTemplate`].
+    Template(AccessLogTemplate),
+}
+
+impl AccessLogFormat {
+    /// Format the [`AccessLogRecord`] as a single line.
+    #[must_use]
+    pub fn format(&self, record: &AccessLogRecord) -> String {
+        match self {
+            Self::Json => format_json(record),
+            Self::Template(template) => template.format(record),
+        }
+    }
+}
+
+impl From<AccessLogTemplate> for AccessLogFormat {
+    fn from(template: AccessLogTemplate) -> Self {
+        Self::Template(template)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Field {
+    Timestamp,
+    Method,
+    Uri,
+    Path,
+    Version,
+    Route,
+    Status,
+    DurationMs,
+    TtfbMs,
+    RequestBytes,
+    ResponseBytes,
+    Client,
+    UaKind,
+    RequestHeaders,
+    RequestId,
+    Error,
+}
+
+impl Field {
+    const ALL: [Self; 16] = [
+        Self::Timestamp,
+        Self::Method,
+        Self::Uri,
+        Self::Path,
+        Self::Version,
+        Self::Route,
+        Self::Status,
+        Self::DurationMs,
+        Self::TtfbMs,
+        Self::RequestBytes,
+        Self::ResponseBytes,
+        Self::Client,
+        Self::UaKind,
+        Self::RequestHeaders,
+        Self::RequestId,
+        Self::Error,
+    ];
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::Timestamp => "timestamp",
+            Self::Method => "method",
+            Self::Uri => "uri",
+            Self::Path => "path",
+            Self::Version => "version",
+            Self::Route => "route",
+            Self::Status => "status",
+            Self::DurationMs => "duration_ms",
+            Self::TtfbMs => "ttfb_ms",
+            Self::RequestBytes => "request_bytes",
+            Self::ResponseBytes => "response_bytes",
+            Self::Client => "client",
+            Self::UaKind => "ua_kind",
+            Self::RequestHeaders => "request_headers",
+            Self::RequestId => "request_id",
+            Self::Error => "error",
+        }
+    }
+
+    fn value(self, record: &AccessLogRecord) -> Value {
+        match self {
+            Self::Timestamp => record.timestamp.to_rfc3339().into(),
+            Self::Method => record.method.as_str().into(),
+            Self::Uri => record.uri.to_string().into(),
+            Self::Path => record.uri.path().into(),
+            Self::Version => format!("{:?}", record.version).into(),
+            Self::Route => record.route.clone().into(),
+            Self::Status => record.status.map(|status| status.as_u16()).into(),
+            Self::DurationMs => duration_ms(record.duration).into(),
+            Self::TtfbMs => duration_ms(record.time_to_first_byte).into(),
+            Self::RequestBytes => record.request_bytes.into(),
+            Self::ResponseBytes => record.response_bytes.into(),
+            Self::Client => record.client.map(|ip| ip.to_string()).into(),
+            Self::UaKind => record.ua_kind.map(|kind| kind.as_str()).into(),
+            Self::RequestHeaders => record
+                .request_headers
+                .as_ref()
+                .map(|headers| {
+                    headers
+                        .iter()
+                        .map(|(name, value)| {
+                            (
+                                name.as_str().to_owned(),
+                                String::from_utf8_lossy(value.as_bytes()).into(),
+                            )
+                        })
+                        .collect::<Map<String, Value>>()
+                })
+                .into(),
+            Self::RequestId => record.request_id.clone().into(),
+            Self::Error => record.error.clone().into(),
+        }
+    }
+}
+
+fn duration_ms(duration: std::time::Duration) -> f64 {
+    (duration.as_secs_f64() * 1_000_000.0).round() / 1_000.0
+}
+
+fn format_json(record: &AccessLogRecord) -> String {
+    let object: Map<String, Value> = Field::ALL
+        .into_iter()
+        .filter_map(|field| {
+            let value = field.value(record);
+            (!value.is_null()).then(|| (field.name().to_owned(), value))
+        })
+        .collect();
+    Value::Object(object).to_string()
+}
+
+#[derive(Debug, Clone)]
+enum Segment {
+    Literal(String),
+    Field(Field),
+}
+
+/// A user-defined template used to format [`AccessLogRecord`]s.
+///
+/// Fields are referenced by name between curly braces, e.g. `{status}`,
+/// while literal curly braces are escaped by doubling them (`{{` and `}}`).
+/// Fields without a value are written as `-`.
+///
+/// Available fields: `timestamp`, `method`, `uri`, `path`, `version`, `route`,
+/// `status`, `duration_ms`, `ttfb_ms`, `request_bytes`, `response_bytes`,
+/// `client`, `ua_kind`, `request_headers`, `request_id` and `error`.
+///
+/// # Example
+///
+/// ```
+/// use rama_http::layer::access_log::AccessLogTemplate;
+///
+/// let template = AccessLogTemplate::new(
+///     "{client} \"{method} {uri} {version}\" {status} {response_bytes} {duration_ms}ms",
+/// )
+/// .unwrap();
+/// ```
+#[derive(Debug, Clone)]
+pub struct AccessLogTemplate {
+    segments: Arc<[Segment]>,
+}
+
+impl AccessLogTemplate {
+    /// Parse a new [`AccessLogTemplate`].
+    ///
+    /// Fails when the template references an unknown field
+    /// or contains an unbalanced curly brace.
+    pub fn new(template: &str) -> Result<Self, OpaqueError> {
+        let mut segments = Vec::new();
+        let mut literal = String::new();
+        let mut chars = template.chars().peekable();
+
+        while let Some(c) = chars.next() {
+            match c {
+                '{' if chars.peek() == Some(&'{') => {
+                    chars.next();
+                    literal.push('{');
+                }
+                '}' if chars.peek() == Some(&'}') => {
+                    chars.next();
+                    literal.push('}');
+                }
+                '{' => {
+                    let mut name = String::new();
+                    loop {
+                        match chars.next() {
+                            Some('}') => break,
+                            Some(c) => name.push(c),
+                            None => {
+                                return Err(OpaqueError::from_display(
+                                    "access log template: unclosed field",
+                                ));
+                            }
+                        }
+                    }
+                    let field = Field::ALL
+                        .into_iter()
+                        .find(|field| field.name() == name.trim())
+                        .ok_or_else(|| {
+                            OpaqueError::from_display(format!(
+                                "access log template: unknown field '{name}'"
+                            ))
+                        })?;
+                    if !literal.is_empty() {
+                        segments.push(Segment::Literal(std::mem::take(&mut literal)));
+                    }
+                    segments.push(Segment::Field(field));
+                }
+                '}' => {
+                    return Err(OpaqueError::from_display(
+                        "access log template: unmatched '}'",
+                    ));
+                }
+                c => literal.push(c),
+            }
+        }
+        if !literal.is_empty() {
+            segments.push(Segment::Literal(literal));
+        }
+
+        Ok(Self {
+            segments: segments.into(),
+        })
+    }
+
+    /// Format the [`AccessLogRecord`] using this template.
+    #[must_use]
+    pub fn format(&self, record: &AccessLogRecord) -> String {
+        let mut line = String::new();
+        for segment in self.segments.iter() {
+            match segment {
+                Segment::Literal(literal) => line.push_str(literal),
+                Segment::Field(field) => match field.value(record) {
+                    Value::Null => line.push('-'),
+                    Value::String(value) => line.push_str(&value),
+                    value => {
+                        let _ = write!(line, "{value}");
+                    }
+                },
+            }
+        }
+        line
+    }
+}
+
+impl std::str::FromStr for AccessLogTemplate {
+    type Err = OpaqueError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::new(s)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Method, StatusCode, Uri, Version};
+    use std::time::Duration;
+
+    fn record() -> AccessLogRecord {
+        AccessLogRecord {
+            timestamp: chrono::DateTime::from_timestamp(0, 0).unwrap(),
+            method: Method::GET,
+            uri: Uri::from_static("/users/42?verbose=1"),
+            version: Version::HTTP_11,
+            route: Some("/users/{id}".to_owned()),
+            status: Some(StatusCode::OK),
+            time_to_first_byte: Duration::from_micros(1_500),
+            duration: Duration::from_millis(3),
+            request_bytes: None,
+            response_bytes: 42,
+            client: Some("127.0.0.1".parse().unwrap()),
+            ua_kind: None,
+            request_headers: None,
+            request_id: Some("abc".to_owned()),
+            error: None,
+        }
+    }
+
+    #[test]
+    fn test_access_log_format_json() {
+        let line = AccessLogFormat::Json.format(&record());
+        let value: Value = serde_json::from_str(&line).unwrap();
+        assert_eq!(
+            value,
+            serde_json::json!({
+                "timestamp": "1970-01-01T00:00:00+00:00",
+                "method": "GET",
+                "uri": "/users/42?verbose=1",
+                "path": "/users/42",
+                "version": "HTTP/1.1",
+                "route": "/users/{id}",
+                "status": 200,
+                "duration_ms": 3.0,
+                "ttfb_ms": 1.5,
+                "response_bytes": 42,
+                "client": "127.0.0.1",
+                "request_id": "abc",
+            })
+        );
+    }
+
+    #[test]
+    fn test_access_log_format_template() {
+        let template =
+            AccessLogTemplate::new("{{{client}}} {method} {path} {status} {ua_kind} {ttfb_ms}ms")
+                .unwrap();
+        assert_eq!(
+            template.format(&record()),
+            "{127.0.0.1} GET /users/42 200 - 1.5ms"
+        );
+    }
+
+    #[test]
+    fn test_access_log_template_invalid() {
+        for template in ["{unknown}", "{status", "status}"] {
+            assert!(AccessLogTemplate::new(template).is_err(), "{template}");
+        }
+    }
+}
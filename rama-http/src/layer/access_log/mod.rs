@@ -0,0 +1,575 @@
+//! Structured access logging, emitting one record per request.
+//!
+//! Unlike the [`trace`] layer, which integrates with the `tracing` ecosystem,
+//! the [`AccessLogLayer`] writes a single line per request in a stable format,
+//! either as JSON or using a user-defined [`AccessLogTemplate`],
+//! to a pluggable non-blocking [`AccessLogSink`].
+//!
+//! The record is emitted once the response body is fully written (or dropped),
+//! such that the total duration and amount of bytes sent can be logged.
+//! See [`AccessLogRecord`] for the available fields.
+//!
+//! Sensitive data is redacted as configured by the [`Redaction`],
+//! which masks query parameter values by default. Request headers are only
+//! logged when enabled, in which case only the values of allowlisted headers are included.
+//!
+//! [`trace`]: crate::layer::trace
+//!
+//! # Example
+//!
+//! ```
+//! use rama_http::layer::access_log::{AccessLogLayer, AccessLogTemplate};
+//! use rama_http::{Body, Request, Response};
+//! use rama_core::service::service_fn;
+//! use rama_core::{Context, Layer, Service};
+//! use rama_core::error::BoxError;
+//! use std::convert::Infallible;
+//!
+//! # #[tokio::main]
+//! # async fn main() -> Result<(), BoxError> {
+//! // any `AccessLogSink` can be used, e.g. an `AccessLogWriter` to stdout
+//! let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+//!
+//! let svc = AccessLogLayer::new(tx)
+//!     .with_format(AccessLogTemplate::new("{method} {path} {status} {response_bytes}")?)
+//!     .into_layer(service_fn(async |_: Request| {
+//!         Ok::<_, Infallible>(Response::new(Body::from("hello")))
+//!     }));
+//!
+//! let res = svc.serve(Context::default(), Request::get("/greet").body(Body::empty())?).await?;
+//! drop(res);
+//!
+//! assert_eq!(rx.recv().await.unwrap(), "GET /greet 200 0");
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::dep::http_body::{Body as HttpBody, Frame, SizeHint};
+use crate::layer::redact::Redaction;
+use crate::layer::request_id::{REQUEST_ID, RequestId, X_REQUEST_ID};
+use crate::service::web::MatchedRoute;
+use crate::{Body, HeaderMap, Method, Request, Response, StatusCode, Uri, Version, header};
+use chrono::{DateTime, Utc};
+use pin_project_lite::pin_project;
+use rama_core::{Context, Layer, Service, bytes::Bytes, error::BoxError};
+use rama_net::{forwarded::Forwarded, stream::SocketInfo};
+use rama_ua::{UserAgent, UserAgentKind};
+use rama_utils::macros::define_inner_service_accessors;
+use std::{
+    fmt,
+    net::IpAddr,
+    pin::Pin,
+    sync::Arc,
+    task::{Context as TaskContext, Poll},
+    time::{Duration, Instant},
+};
+
+mod format;
+#[doc(inline)]
+pub use format::{AccessLogFormat, AccessLogTemplate};
+
+mod sink;
+#[doc(inline)]
+pub use sink::{AccessLogSink, AccessLogWriter};
+
+/// A single access log record, describing a request and its response.
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct AccessLogRecord {
+    /// Time at which the request was received.
+    pub timestamp: DateTime<Utc>,
+    /// Method of the request.
+    pub method: Method,
+    /// Uri of the request, redacted as configured by the [`Redaction`].
+    pub uri: Uri,
+    /// Http version of the request.
+    pub version: Version,
+    /// Route pattern matched by the [`Router`], if any.
+    ///
+    /// [`Router`]: crate::service::web::Router
+    pub route: Option<String>,
+    /// Status of the response, `None` if the inner service failed.
+    pub status: Option<StatusCode>,
+    /// Time it took for the inner service to return the response (head).
+    pub time_to_first_byte: Duration,
+    /// Time it took for the response body to be fully written.
+    pub duration: Duration,
+    /// Size of the request body, as advertised by its `Content-Length`.
+    pub request_bytes: Option<u64>,
+    /// Amount of response body bytes written.
+    pub response_bytes: u64,
+    /// Ip address of the client, using the [`Forwarded`] info if available.
+    pub client: Option<IpAddr>,
+    /// Kind of user agent which made the request.
+    pub ua_kind: Option<UserAgentKind>,
+    /// Headers of the request, redacted as configured by the [`Redaction`],
+    /// if enabled using [`AccessLogLayer::with_request_headers`].
+    pub request_headers: Option<HeaderMap>,
+    /// Id of the request, if one was set (see [`request_id`]).
+    ///
+    /// [`request_id`]: crate::layer::request_id
+    pub request_id: Option<String>,
+    /// Error which occurred while serving the request or streaming its response body.
+    pub error: Option<String>,
+}
+
+impl AccessLogRecord {
+    fn new<B>(ctx: &Context, req: &Request<B>, redaction: &Redaction, headers: bool) -> Self {
+        let client = ctx
+            .get::<Forwarded>()
+            .and_then(|f| f.client_ip())
+            .or_else(|| ctx.get::<SocketInfo>().map(|s| s.peer_addr().ip()));
+
+        let ua_kind = match ctx.get::<UserAgent>() {
+            Some(ua) => ua.ua_kind(),
+            None => req
+                .headers()
+                .get(header::USER_AGENT)
+                .and_then(|value| value.to_str().ok())
+                .and_then(|value| UserAgent::new(value).ua_kind()),
+        };
+
+        let request_id = req
+            .extensions()
+            .get::<RequestId>()
+            .map(RequestId::header_value)
+            .or_else(|| req.headers().get(X_REQUEST_ID))
+            .or_else(|| req.headers().get(REQUEST_ID))
+            .and_then(|value| value.to_str().ok())
+            .map(ToOwned::to_owned);
+
+        let request_bytes = req
+            .headers()
+            .get(header::CONTENT_LENGTH)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.parse().ok());
+
+        Self {
+            timestamp: Utc::now(),
+            method: req.method().clone(),
+            uri: redaction.redact_uri(req.uri()),
+            version: req.version(),
+            route: None,
+            status: None,
+            time_to_first_byte: Duration::ZERO,
+            duration: Duration::ZERO,
+            request_bytes,
+            response_bytes: 0,
+            client,
+            ua_kind,
+            request_headers: headers.then(|| redaction.redact_headers(req.headers())),
+            request_id,
+            error: None,
+        }
+    }
+}
+
+/// Layer that applies the [`AccessLog`] middleware.
+///
+/// See the [module docs](crate::layer::access_log) for more details.
+pub struct AccessLogLayer<W> {
+    sink: Arc<W>,
+    format: AccessLogFormat,
+    redaction: Redaction,
+    request_headers: bool,
+}
+
+impl<W: fmt::Debug> fmt::Debug for AccessLogLayer<W> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AccessLogLayer")
+            .field("sink", &self.sink)
+            .field("format", &self.format)
+            .field("redaction", &self.redaction)
+            .field("request_headers", &self.request_headers)
+            .finish()
+    }
+}
+
+impl<W> Clone for AccessLogLayer<W> {
+    fn clone(&self) -> Self {
+        Self {
+            sink: self.sink.clone(),
+            format: self.format.clone(),
+            redaction: self.redaction.clone(),
+            request_headers: self.request_headers,
+        }
+    }
+}
+
+impl<W> AccessLogLayer<W> {
+    /// Create a new [`AccessLogLayer`] writing JSON records to the given [`AccessLogSink`].
+    pub fn new(sink: W) -> Self {
+        Self {
+            sink: Arc::new(sink),
+            format: AccessLogFormat::default(),
+            redaction: Redaction::new(),
+            request_headers: false,
+        }
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Set the [`AccessLogFormat`] used to write the records.
+        pub fn format(mut self, format: impl Into<AccessLogFormat>) -> Self {
+            self.format = format.into();
+            self
+        }
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Set the [`Redaction`] applied to the request uri and headers.
+        ///
+        /// Defaults to [`Redaction::new`].
+        pub fn redaction(mut self, redaction: Redaction) -> Self {
+            self.redaction = redaction;
+            self
+        }
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Include the (redacted) request headers in the records.
+        ///
+        /// Disabled by default.
+        pub fn request_headers(mut self, include: bool) -> Self {
+            self.request_headers = include;
+            self
+        }
+    }
+}
+
+impl<S, W> Layer<S> for AccessLogLayer<W> {
+    type Service = AccessLog<S, W>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        AccessLog {
+            inner,
+            sink: self.sink.clone(),
+            format: self.format.clone(),
+            redaction: self.redaction.clone(),
+            request_headers: self.request_headers,
+        }
+    }
+
+    fn into_layer(self, inner: S) -> Self::Service {
+        AccessLog {
+            inner,
+            sink: self.sink,
+            format: self.format,
+            redaction: self.redaction,
+            request_headers: self.request_headers,
+        }
+    }
+}
+
+/// Middleware which writes an [`AccessLogRecord`] for each request.
+///
+/// See the [module docs](crate::layer::access_log) for more details.
+pub struct AccessLog<S, W> {
+    inner: S,
+    sink: Arc<W>,
+    format: AccessLogFormat,
+    redaction: Redaction,
+    request_headers: bool,
+}
+
+impl<S: fmt::Debug, W: fmt::Debug> fmt::Debug for AccessLog<S, W> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AccessLog")
+            .field("inner", &self.inner)
+            .field("sink", &self.sink)
+            .field("format", &self.format)
+            .field("redaction", &self.redaction)
+            .field("request_headers", &self.request_headers)
+            .finish()
+    }
+}
+
+impl<S: Clone, W> Clone for AccessLog<S, W> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            sink: self.sink.clone(),
+            format: self.format.clone(),
+            redaction: self.redaction.clone(),
+            request_headers: self.request_headers,
+        }
+    }
+}
+
+impl<S, W> AccessLog<S, W> {
+    /// Create a new [`AccessLog`] writing JSON records to the given [`AccessLogSink`].
+    pub fn new(inner: S, sink: W) -> Self {
+        Self {
+            inner,
+            sink: Arc::new(sink),
+            format: AccessLogFormat::default(),
+            redaction: Redaction::new(),
+            request_headers: false,
+        }
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Set the [`AccessLogFormat`] used to write the records.
+        pub fn format(mut self, format: impl Into<AccessLogFormat>) -> Self {
+            self.format = format.into();
+            self
+        }
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Set the [`Redaction`] applied to the request uri and headers.
+        ///
+        /// Defaults to [`Redaction::new`].
+        pub fn redaction(mut self, redaction: Redaction) -> Self {
+            self.redaction = redaction;
+            self
+        }
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Include the (redacted) request headers in the records.
+        ///
+        /// Disabled by default.
+        pub fn request_headers(mut self, include: bool) -> Self {
+            self.request_headers = include;
+            self
+        }
+    }
+
+    define_inner_service_accessors!();
+}
+
+impl<S, W, ReqBody, ResBody> Service<Request<ReqBody>> for AccessLog<S, W>
+where
+    S: Service<Request<ReqBody>, Response = Response<ResBody>, Error: Into<BoxError>>,
+    W: AccessLogSink,
+    ReqBody: Send + 'static,
+    ResBody: HttpBody<Data = Bytes, Error: Into<BoxError>> + Send + Sync + 'static,
+{
+    type Response = Response;
+    type Error = BoxError;
+
+    async fn serve(
+        &self,
+        ctx: Context,
+        req: Request<ReqBody>,
+    ) -> Result<Self::Response, Self::Error> {
+        let start = Instant::now();
+        let mut record = AccessLogRecord::new(&ctx, &req, &self.redaction, self.request_headers);
+
+        let result = self.inner.serve(ctx, req).await.map_err(Into::into);
+        record.time_to_first_byte = start.elapsed();
+
+        let mut log = PendingAccessLog {
+            record: None,
+            start,
+            sink: self.sink.clone(),
+            format: self.format.clone(),
+        };
+
+        match result {
+            Ok(res) => {
+                record.status = Some(res.status());
+                record.route = res
+                    .extensions()
+                    .get::<MatchedRoute>()
+                    .map(|route| route.as_str().to_owned());
+                log.record = Some(record);
+                Ok(res.map(|body| Body::new(AccessLogBody { inner: body, log })))
+            }
+            Err(err) => {
+                record.error = Some(err.to_string());
+                log.record = Some(record);
+                log.emit();
+                Err(err)
+            }
+        }
+    }
+}
+
+/// Access log record which is yet to be emitted.
+///
+/// Emitted at the end of the body stream, or on drop at the latest.
+struct PendingAccessLog<W: AccessLogSink> {
+    record: Option<AccessLogRecord>,
+    start: Instant,
+    sink: Arc<W>,
+    format: AccessLogFormat,
+}
+
+impl<W: AccessLogSink> PendingAccessLog<W> {
+    fn emit(&mut self) {
+        if let Some(mut record) = self.record.take() {
+            record.duration = self.start.elapsed();
+            self.sink.write_line(self.format.format(&record));
+        }
+    }
+}
+
+impl<W: AccessLogSink> Drop for PendingAccessLog<W> {
+    fn drop(&mut self) {
+        self.emit();
+    }
+}
+
+pin_project! {
+    /// Response body which emits the access log record once it is done.
+    struct AccessLogBody<B, W: AccessLogSink> {
+        #[pin]
+        inner: B,
+        log: PendingAccessLog<W>,
+    }
+}
+
+impl<B, W> HttpBody for AccessLogBody<B, W>
+where
+    B: HttpBody<Data = Bytes, Error: Into<BoxError>>,
+    W: AccessLogSink,
+{
+    type Data = Bytes;
+    type Error = BoxError;
+
+    fn poll_frame(
+        self: Pin<&mut Self>,
+        cx: &mut TaskContext<'_>,
+    ) -> Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
+        let this = self.project();
+        match std::task::ready!(this.inner.poll_frame(cx)) {
+            Some(Ok(frame)) => {
+                if let (Some(data), Some(record)) = (frame.data_ref(), this.log.record.as_mut()) {
+                    record.response_bytes += data.len() as u64;
+                }
+                Poll::Ready(Some(Ok(frame)))
+            }
+            Some(Err(err)) => {
+                let err = err.into();
+                if let Some(record) = this.log.record.as_mut() {
+                    record.error = Some(err.to_string());
+                }
+                this.log.emit();
+                Poll::Ready(Some(Err(err)))
+            }
+            None => {
+                this.log.emit();
+                Poll::Ready(None)
+            }
+        }
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.inner.is_end_stream()
+    }
+
+    fn size_hint(&self) -> SizeHint {
+        self.inner.size_hint()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layer::ua::UserAgentClassifierLayer;
+    use crate::service::web::Router;
+    use rama_core::service::service_fn;
+    use rama_http_types::BodyExtractExt;
+    use serde_json::Value;
+    use std::convert::Infallible;
+    use tokio::sync::mpsc::unbounded_channel;
+
+    const UA: &str = "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/124.0.0.0 Safari/537.36";
+
+    #[tokio::test]
+    async fn test_access_log_json() {
+        let (tx, mut rx) = unbounded_channel();
+        let svc = (UserAgentClassifierLayer::new(), AccessLogLayer::new(tx)).into_layer(
+            Router::new().get(
+                "/users/{id}",
+                service_fn(async || Ok::<_, Infallible>(Response::new(Body::from("hello")))),
+            ),
+        );
+
+        let req = Request::get("/users/42")
+            .header(header::USER_AGENT, UA)
+            .header(X_REQUEST_ID, "abc")
+            .body(Body::empty())
+            .unwrap();
+        let res = svc.serve(Context::default(), req).await.unwrap();
+        assert!(
+            rx.try_recv().is_err(),
+            "record is only emitted once the body is done"
+        );
+        assert_eq!(res.try_into_string().await.unwrap(), "hello");
+
+        let record: Value = serde_json::from_str(&rx.recv().await.unwrap()).unwrap();
+        assert_eq!(record["method"], "GET");
+        assert_eq!(record["path"], "/users/42");
+        assert_eq!(record["route"], "/users/{id}");
+        assert_eq!(record["status"], 200);
+        assert_eq!(record["response_bytes"], 5);
+        assert_eq!(record["ua_kind"], "Chromium");
+        assert_eq!(record["request_id"], "abc");
+        assert!(record["duration_ms"].is_f64());
+    }
+
+    #[tokio::test]
+    async fn test_access_log_error() {
+        let (tx, mut rx) = unbounded_channel();
+        let svc = AccessLogLayer::new(tx)
+            .with_format(AccessLogTemplate::new("{status} {error}").unwrap())
+            .into_layer(service_fn(async |_: Request| {
+                Err::<Response, _>(BoxError::from("boom"))
+            }));
+
+        let err = svc
+            .serve(Context::default(), Request::new(Body::empty()))
+            .await
+            .unwrap_err();
+        assert_eq!(err.to_string(), "boom");
+        assert_eq!(rx.recv().await.unwrap(), "- boom");
+    }
+
+    #[tokio::test]
+    async fn test_access_log_redaction() {
+        let (tx, mut rx) = unbounded_channel();
+        let svc = AccessLogLayer::new(tx)
+            .with_request_headers(true)
+            .into_layer(service_fn(async |_: Request| {
+                Ok::<_, Infallible>(Response::new(Body::empty()))
+            }));
+
+        let req = Request::get("/search?q=rama&api_key=secret")
+            .header(header::ACCEPT, "text/html")
+            .header(header::AUTHORIZATION, "Bearer secret")
+            .header(header::COOKIE, "session=secret")
+            .body(Body::empty())
+            .unwrap();
+        drop(svc.serve(Context::default(), req).await.unwrap());
+
+        let line = rx.recv().await.unwrap();
+        assert!(!line.contains("secret"), "{line}");
+        let record: Value = serde_json::from_str(&line).unwrap();
+        assert_eq!(record["uri"], "/search?q=REDACTED&api_key=REDACTED");
+        assert_eq!(record["request_headers"]["accept"], "text/html");
+        assert_eq!(record["request_headers"]["authorization"], "REDACTED");
+        assert_eq!(record["request_headers"]["cookie"], "session=REDACTED");
+    }
+
+    #[tokio::test]
+    async fn test_access_log_body_dropped() {
+        let (tx, mut rx) = unbounded_channel();
+        let svc = AccessLogLayer::new(tx)
+            .with_format(AccessLogTemplate::new("{method} {status} {response_bytes}").unwrap())
+            .into_layer(service_fn(async |_: Request| {
+                Ok::<_, Infallible>(Response::new(Body::from("hello")))
+            }));
+
+        let res = svc
+            .serve(
+                Context::default(),
+                Request::head("/").body(Body::empty()).unwrap(),
+            )
+            .await
+            .unwrap();
+        drop(res);
+        assert_eq!(rx.recv().await.unwrap(), "HEAD 200 0");
+    }
+}
@@ -0,0 +1,108 @@
+use rama_core::{
+    rt::Executor,
+    telemetry::tracing::{self, Instrument},
+};
+use std::sync::Arc;
+use tokio::{
+    io::{AsyncWrite, AsyncWriteExt, stderr, stdout},
+    sync::mpsc::{Sender, UnboundedSender, channel, error::TrySendError},
+};
+
+/// A sink to which formatted access log lines are written.
+///
+/// Sinks are called from within the request (body) lifecycle,
+/// and as such should never block. Sinks which write to I/O
+/// are expected to hand off the line, e.g. over a channel,
+/// such as is done by the [`AccessLogWriter`].
+pub trait AccessLogSink: Send + Sync + 'static {
+    /// Write a single formatted access log line, without trailing newline.
+    fn write_line(&self, line: String);
+}
+
+impl<T: AccessLogSink> AccessLogSink for Arc<T> {
+    fn write_line(&self, line: String) {
+        (**self).write_line(line)
+    }
+}
+
+impl AccessLogSink for UnboundedSender<String> {
+    fn write_line(&self, line: String) {
+        if let Err(err) = self.send(line) {
+            tracing::debug!("failed to send access log line over unbounded channel: {err:?}");
+        }
+    }
+}
+
+impl AccessLogSink for Sender<String> {
+    fn write_line(&self, line: String) {
+        match self.try_send(line) {
+            Ok(()) => (),
+            Err(TrySendError::Full(_)) => {
+                tracing::warn!("access log channel is full: dropping access log line");
+            }
+            Err(TrySendError::Closed(_)) => {
+                tracing::debug!("access log channel is closed: dropping access log line");
+            }
+        }
+    }
+}
+
+/// An [`AccessLogSink`] which writes access log lines to an [`AsyncWrite`]r,
+/// gated behind a bounded channel.
+///
+/// Lines are dropped (and a warning is logged) when the channel is full,
+/// such that a slow writer never slows down the requests being logged.
+#[derive(Debug, Clone)]
+pub struct AccessLogWriter {
+    sender: Sender<String>,
+}
+
+impl AccessLogWriter {
+    /// Create a new [`AccessLogWriter`] that writes to a custom [`AsyncWrite`]r,
+    /// buffering up to `buffer` lines.
+    pub fn new<W>(executor: &Executor, mut writer: W, buffer: usize) -> Self
+    where
+        W: AsyncWrite + Unpin + Send + Sync + 'static,
+    {
+        let (tx, mut rx) = channel::<String>(buffer);
+
+        let span = tracing::trace_root_span!("AccessLogWriter", otel.kind = "consumer");
+
+        executor.spawn_task(
+            async move {
+                while let Some(mut line) = rx.recv().await {
+                    line.push('\n');
+                    if let Err(err) = writer.write_all(line.as_bytes()).await {
+                        tracing::error!("failed to write access log line to writer: {err:?}")
+                    }
+                }
+                if let Err(err) = writer.flush().await {
+                    tracing::error!("failed to flush access log writer: {err:?}")
+                }
+            }
+            .instrument(span),
+        );
+
+        Self { sender: tx }
+    }
+
+    /// Create a new [`AccessLogWriter`] that writes to stdout,
+    /// buffering up to `buffer` lines.
+    #[must_use]
+    pub fn stdout(executor: &Executor, buffer: usize) -> Self {
+        Self::new(executor, stdout(), buffer)
+    }
+
+    /// Create a new [`AccessLogWriter`] that writes to stderr,
+    /// buffering up to `buffer` lines.
+    #[must_use]
+    pub fn stderr(executor: &Executor, buffer: usize) -> Self {
+        Self::new(executor, stderr(), buffer)
+    }
+}
+
+impl AccessLogSink for AccessLogWriter {
+    fn write_line(&self, line: String) {
+        self.sender.write_line(line)
+    }
+}
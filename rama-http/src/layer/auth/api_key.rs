@@ -0,0 +1,376 @@
+//! API-key authentication backed by an async [`KeyStore`].
+//!
+//! The [`ApiKeyAuthorizer`] is a [`ValidateRequest`] implementation which
+//! looks for an API key in the configured header(s) and/or query parameter(s),
+//! resolves it using a [`KeyStore`] and rejects the request in case the key
+//! is unknown, inactive or misses one of the required scopes.
+//!
+//! On success the resolved [`ApiKeyIdentity`] is inserted in the [`Context`],
+//! together with a [`UserId::Username`] for its id, such that it can be used
+//! by accounting and rate-limiting layers further down the stack.
+//!
+//! # Example
+//!
+//! ```
+//! use rama_core::{Context, Layer, Service, service::service_fn};
+//! use rama_http::layer::auth::api_key::{ApiKeyAuthorizer, ApiKeyIdentity};
+//! use rama_http::layer::validate_request::ValidateRequestHeaderLayer;
+//! use rama_http::{Body, Request, Response, StatusCode};
+//! use std::{collections::HashMap, convert::Infallible};
+//!
+//! # #[tokio::main]
+//! # async fn main() {
+//! let store = HashMap::from([(
+//!     "secret-key".to_owned(),
+//!     ApiKeyIdentity::new("tenant-a").with_scope("read"),
+//! )]);
+//!
+//! let service = ValidateRequestHeaderLayer::api_key(
+//!     ApiKeyAuthorizer::new(store).with_required_scope("read"),
+//! )
+//! .into_layer(service_fn(async |ctx: Context, _: Request| {
+//!     let identity = ctx.get::<ApiKeyIdentity>().unwrap();
+//!     Ok::<_, Infallible>(Response::new(Body::from(identity.id.clone())))
+//! }));
+//!
+//! let req = Request::builder()
+//!     .header("x-api-key", "secret-key")
+//!     .body(Body::empty())
+//!     .unwrap();
+//! let resp = service.serve(Context::default(), req).await.unwrap();
+//! assert_eq!(resp.status(), StatusCode::OK);
+//! # }
+//! ```
+
+use std::{collections::HashMap, fmt, sync::Arc};
+
+use rama_core::{Context, error::OpaqueError, telemetry::tracing};
+use rama_http_types::{Body, HeaderName, Request, Response, StatusCode};
+use rama_net::user::UserId;
+
+use crate::{
+    layer::validate_request::{ValidateRequest, ValidateRequestHeader, ValidateRequestHeaderLayer},
+    service::web::response::IntoResponse,
+};
+
+/// The default header used to look for an API key.
+pub const DEFAULT_API_KEY_HEADER: HeaderName = HeaderName::from_static("x-api-key");
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// The identity resolved for an API key by a [`KeyStore`].
+pub struct ApiKeyIdentity {
+    /// Identifier of the owner of the key (e.g. a tenant or user id).
+    pub id: String,
+    /// Inactive (e.g. revoked) keys are rejected.
+    pub active: bool,
+    /// Scopes granted to the key.
+    pub scopes: Vec<String>,
+}
+
+impl ApiKeyIdentity {
+    #[must_use]
+    /// Create a new active [`ApiKeyIdentity`] without any scopes.
+    pub fn new(id: impl Into<String>) -> Self {
+        Self {
+            id: id.into(),
+            active: true,
+            scopes: Vec::new(),
+        }
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Define whether or not the key is active.
+        pub fn active(mut self, active: bool) -> Self {
+            self.active = active;
+            self
+        }
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Grant a scope to the key.
+        pub fn scope(mut self, scope: impl Into<String>) -> Self {
+            self.scopes.push(scope.into());
+            self
+        }
+    }
+
+    #[must_use]
+    /// Returns true if the given scope is granted to the key.
+    pub fn has_scope(&self, scope: &str) -> bool {
+        self.scopes.iter().any(|s| s == scope)
+    }
+}
+
+/// Storage used to resolve API keys into their [`ApiKeyIdentity`].
+pub trait KeyStore: Send + Sync + 'static {
+    /// Lookup the [`ApiKeyIdentity`] for the given key,
+    /// returning `None` in case the key is unknown.
+    fn lookup(
+        &self,
+        key: &str,
+    ) -> impl Future<Output = Result<Option<ApiKeyIdentity>, OpaqueError>> + Send;
+}
+
+impl KeyStore for HashMap<String, ApiKeyIdentity> {
+    async fn lookup(&self, key: &str) -> Result<Option<ApiKeyIdentity>, OpaqueError> {
+        Ok(self.get(key).cloned())
+    }
+}
+
+impl<K: KeyStore> KeyStore for Arc<K> {
+    fn lookup(
+        &self,
+        key: &str,
+    ) -> impl Future<Output = Result<Option<ApiKeyIdentity>, OpaqueError>> + Send {
+        self.as_ref().lookup(key)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// Location of an API key within a request.
+pub enum ApiKeyLocation {
+    /// The key is passed as the value of a header.
+    Header(HeaderName),
+    /// The key is passed as the value of a query parameter.
+    Query(String),
+}
+
+impl ApiKeyLocation {
+    fn extract<'a, B>(&self, request: &'a Request<B>) -> Option<std::borrow::Cow<'a, str>> {
+        match self {
+            Self::Header(name) => request
+                .headers()
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .map(|value| value.trim())
+                .filter(|value| !value.is_empty())
+                .map(Into::into),
+            Self::Query(name) => {
+                let query = request.uri().query()?;
+                serde_html_form::from_str::<Vec<(String, String)>>(query)
+                    .ok()?
+                    .into_iter()
+                    .find(|(key, value)| key == name && !value.is_empty())
+                    .map(|(_, value)| value.into())
+            }
+        }
+    }
+}
+
+/// Validates the API key of requests using a [`KeyStore`].
+///
+/// See the [module docs](self) for more information.
+pub struct ApiKeyAuthorizer<K> {
+    store: K,
+    locations: Vec<ApiKeyLocation>,
+    required_scopes: Vec<String>,
+}
+
+impl<K> ApiKeyAuthorizer<K> {
+    /// Create a new [`ApiKeyAuthorizer`] using the given [`KeyStore`].
+    ///
+    /// By default the key is expected in the [`DEFAULT_API_KEY_HEADER`].
+    pub fn new(store: K) -> Self {
+        Self {
+            store,
+            locations: vec![ApiKeyLocation::Header(DEFAULT_API_KEY_HEADER)],
+            required_scopes: Vec::new(),
+        }
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Define the locations, in order of preference, where the API key is looked for.
+        pub fn locations(mut self, locations: impl IntoIterator<Item = ApiKeyLocation>) -> Self {
+            self.locations = locations.into_iter().collect();
+            self
+        }
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Look for the API key in the given header,
+        /// replacing any previously defined location.
+        pub fn header(mut self, name: HeaderName) -> Self {
+            self.locations = vec![ApiKeyLocation::Header(name)];
+            self
+        }
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Look for the API key in the given query parameter,
+        /// replacing any previously defined location.
+        pub fn query_param(mut self, name: impl Into<String>) -> Self {
+            self.locations = vec![ApiKeyLocation::Query(name.into())];
+            self
+        }
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Require the given scope to be granted to the API key.
+        ///
+        /// Requests with a key missing a required scope
+        /// are rejected with [`StatusCode::FORBIDDEN`].
+        pub fn required_scope(mut self, scope: impl Into<String>) -> Self {
+            self.required_scopes.push(scope.into());
+            self
+        }
+    }
+}
+
+impl<K: fmt::Debug> fmt::Debug for ApiKeyAuthorizer<K> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ApiKeyAuthorizer")
+            .field("store", &self.store)
+            .field("locations", &self.locations)
+            .field("required_scopes", &self.required_scopes)
+            .finish()
+    }
+}
+
+impl<K: Clone> Clone for ApiKeyAuthorizer<K> {
+    fn clone(&self) -> Self {
+        Self {
+            store: self.store.clone(),
+            locations: self.locations.clone(),
+            required_scopes: self.required_scopes.clone(),
+        }
+    }
+}
+
+impl<ReqBody, K> ValidateRequest<ReqBody> for ApiKeyAuthorizer<K>
+where
+    ReqBody: Send + 'static,
+    K: KeyStore,
+{
+    type ResponseBody = Body;
+
+    async fn validate(
+        &self,
+        mut ctx: Context,
+        request: Request<ReqBody>,
+    ) -> Result<(Context, Request<ReqBody>), Response<Self::ResponseBody>> {
+        let Some(key) = self
+            .locations
+            .iter()
+            .find_map(|location| location.extract(&request))
+        else {
+            tracing::trace!("no api key found in request");
+            return Err(StatusCode::UNAUTHORIZED.into_response());
+        };
+
+        let identity = match self.store.lookup(&key).await {
+            Ok(Some(identity)) => identity,
+            Ok(None) => {
+                tracing::debug!("unknown api key used");
+                return Err(StatusCode::UNAUTHORIZED.into_response());
+            }
+            Err(err) => {
+                tracing::error!("failed to lookup api key: {err}");
+                return Err(StatusCode::INTERNAL_SERVER_ERROR.into_response());
+            }
+        };
+
+        if !identity.active {
+            tracing::debug!(id = %identity.id, "inactive api key used");
+            return Err(StatusCode::UNAUTHORIZED.into_response());
+        }
+
+        if let Some(scope) = self
+            .required_scopes
+            .iter()
+            .find(|scope| !identity.has_scope(scope))
+        {
+            tracing::debug!(id = %identity.id, "api key is missing required scope: {scope}");
+            return Err(StatusCode::FORBIDDEN.into_response());
+        }
+
+        ctx.insert(UserId::Username(identity.id.clone()));
+        ctx.insert(identity);
+
+        Ok((ctx, request))
+    }
+}
+
+impl<S, K> ValidateRequestHeader<S, ApiKeyAuthorizer<K>> {
+    #[inline]
+    /// Validate the API key of requests with an [`ApiKeyAuthorizer`].
+    pub fn api_key(inner: S, authorizer: ApiKeyAuthorizer<K>) -> Self {
+        Self::custom(inner, authorizer)
+    }
+}
+
+impl<K> ValidateRequestHeaderLayer<ApiKeyAuthorizer<K>> {
+    #[inline]
+    /// Validate the API key of requests with an [`ApiKeyAuthorizer`].
+    pub fn api_key(authorizer: ApiKeyAuthorizer<K>) -> Self {
+        Self::custom(authorizer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rama_core::{Layer, Service, service::service_fn};
+    use std::convert::Infallible;
+
+    fn store() -> Arc<HashMap<String, ApiKeyIdentity>> {
+        Arc::new(HashMap::from([
+            (
+                "key-a".to_owned(),
+                ApiKeyIdentity::new("a")
+                    .with_scope("read")
+                    .with_scope("write"),
+            ),
+            (
+                "key-b".to_owned(),
+                ApiKeyIdentity::new("b").with_scope("read"),
+            ),
+            (
+                "key-c".to_owned(),
+                ApiKeyIdentity::new("c")
+                    .with_scope("write")
+                    .with_active(false),
+            ),
+        ]))
+    }
+
+    #[tokio::test]
+    async fn test_api_key_auth() {
+        let service = ValidateRequestHeaderLayer::api_key(
+            ApiKeyAuthorizer::new(store())
+                .with_locations([
+                    ApiKeyLocation::Header(DEFAULT_API_KEY_HEADER),
+                    ApiKeyLocation::Query("api_key".to_owned()),
+                ])
+                .with_required_scope("write"),
+        )
+        .into_layer(service_fn(async |ctx: Context, _: Request| {
+            let identity = ctx.get::<ApiKeyIdentity>().unwrap();
+            assert_eq!(
+                ctx.get::<UserId>(),
+                Some(&UserId::Username(identity.id.clone()))
+            );
+            Ok::<_, Infallible>(Response::new(Body::empty()))
+        }));
+
+        for (uri, header, expected) in [
+            ("/", None, StatusCode::UNAUTHORIZED),
+            ("/", Some("unknown"), StatusCode::UNAUTHORIZED),
+            ("/", Some("key-a"), StatusCode::OK),
+            ("/?api_key=key-a", None, StatusCode::OK),
+            ("/?foo=bar&api_key=key-a", None, StatusCode::OK),
+            ("/?api_key=", None, StatusCode::UNAUTHORIZED),
+            ("/", Some("key-b"), StatusCode::FORBIDDEN),
+            ("/", Some("key-c"), StatusCode::UNAUTHORIZED),
+        ] {
+            let mut builder = Request::builder().uri(uri);
+            if let Some(key) = header {
+                builder = builder.header(DEFAULT_API_KEY_HEADER, key);
+            }
+            let resp = service
+                .serve(Context::default(), builder.body(Body::empty()).unwrap())
+                .await
+                .unwrap();
+            assert_eq!(resp.status(), expected, "uri: {uri}; header: {header:?}");
+        }
+    }
+}
@@ -0,0 +1,1111 @@
+//! JWT (JSON Web Token) bearer authentication, e.g. for OIDC access tokens.
+//!
+//! The [`JwtValidator`] is a [`ValidateRequest`] implementation which
+//! validates the `Authorization: Bearer <jwt>` header of incoming requests:
+//!
+//! - the signature is verified using a [`JWK`] found via a [`JwtKeySource`],
+//!   such as a static [`JwkSet`] or a [`RemoteJwks`] (fetched and cached from a JWKS endpoint);
+//! - the `exp` and `nbf` claims are checked, allowing for a configurable clock skew (leeway);
+//! - the `iss` and `aud` claims are checked against the configured values (if any);
+//! - an optional [`JwtClaimsAuthorizer`] can authorize the request based on the validated claims.
+//!
+//! On success the validated [`JwtClaims`] are inserted in the [`Context`],
+//! together with a [`UserId::Username`] for the `sub` claim (if present).
+//!
+//! # Example
+//!
+//! ```
+//! use rama_core::{Context, Layer, Service, service::service_fn};
+//! use rama_crypto::jose::JWA;
+//! use rama_http::layer::auth::jwt::{JwkSet, JwtClaims, JwtValidator};
+//! use rama_http::layer::validate_request::ValidateRequestHeaderLayer;
+//! use rama_http::{Body, Request, Response, StatusCode};
+//! use std::convert::Infallible;
+//!
+//! # #[tokio::main]
+//! # async fn main() {
+//! let keys = JwkSet::from_hmac_secret(JWA::HS256, b"super secret");
+//!
+//! let service = ValidateRequestHeaderLayer::jwt(
+//!     JwtValidator::new(keys)
+//!         .with_issuer("https://auth.example.com")
+//!         .with_audience("my-api"),
+//! )
+//! .into_layer(service_fn(async |ctx: Context, _: Request| {
+//!     let claims = ctx.get::<JwtClaims>().unwrap();
+//!     Ok::<_, Infallible>(Response::new(Body::from(format!(
+//!         "hello {}",
+//!         claims.sub.as_deref().unwrap_or_default()
+//!     ))))
+//! }));
+//!
+//! let resp = service
+//!     .serve(Context::default(), Request::new(Body::empty()))
+//!     .await
+//!     .unwrap();
+//! assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+//! # }
+//! ```
+
+use std::{
+    fmt,
+    sync::{Arc, RwLock},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use base64::{Engine as _, prelude::BASE64_URL_SAFE_NO_PAD};
+use rama_core::{
+    Context, Service,
+    error::{BoxError, ErrorContext, OpaqueError},
+    telemetry::tracing,
+};
+use rama_crypto::{
+    dep::aws_lc_rs::{hmac, signature},
+    jose::{JWA, JWK, JWKEllipticCurves, JWKType, JWKUse},
+};
+use rama_http_headers::{Authorization, HeaderMapExt};
+use rama_http_types::{
+    Body, BodyExtractExt, HeaderValue, Method, Request, Response, StatusCode, Uri, header,
+};
+use rama_net::user::{Bearer, UserId};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::{
+    layer::validate_request::{ValidateRequest, ValidateRequestHeader, ValidateRequestHeaderLayer},
+    service::web::response::IntoResponse,
+};
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+/// The claims of a validated JWT.
+///
+/// Inserted into the [`Context`] by the [`JwtValidator`]
+/// for all requests that were successfully validated.
+pub struct JwtClaims {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    /// Issuer (`iss`) of the token.
+    pub iss: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    /// Subject (`sub`) of the token, usually identifying the user.
+    pub sub: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    /// Audience (`aud`) for which the token was issued.
+    pub aud: Option<JwtAudience>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    /// Expiration time (`exp`) as seconds since the unix epoch.
+    pub exp: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    /// Time (`nbf`) before which the token is not valid, as seconds since the unix epoch.
+    pub nbf: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    /// Time (`iat`) at which the token was issued, as seconds since the unix epoch.
+    pub iat: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    /// Unique identifier (`jti`) of the token.
+    pub jti: Option<String>,
+    #[serde(flatten)]
+    /// All other (non-registered) claims.
+    pub extra: Map<String, Value>,
+}
+
+impl JwtClaims {
+    #[must_use]
+    /// Get a non-registered claim by name.
+    pub fn get(&self, name: &str) -> Option<&Value> {
+        self.extra.get(name)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+/// The audience (`aud`) claim, which can be a single or multiple values.
+pub enum JwtAudience {
+    /// A single audience
+    Single(String),
+    /// Multiple audiences
+    Multiple(Vec<String>),
+}
+
+impl JwtAudience {
+    #[must_use]
+    /// Returns true if the given audience is part of this [`JwtAudience`].
+    pub fn contains(&self, audience: &str) -> bool {
+        match self {
+            Self::Single(aud) => aud == audience,
+            Self::Multiple(auds) => auds.iter().any(|aud| aud == audience),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct JwtHeader {
+    alg: JWA,
+    #[serde(default)]
+    kid: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+/// A JSON Web Key Set as defined in [`rfc7517, section 5`].
+///
+/// Keys which cannot be parsed, e.g. because of an unsupported key type
+/// or algorithm, are skipped when deserializing the set.
+///
+/// [`rfc7517, section 5`]: https://datatracker.ietf.org/doc/html/rfc7517#section-5
+pub struct JwkSet {
+    /// The keys of this set.
+    pub keys: Vec<JwkSetKey>,
+}
+
+impl<'de> Deserialize<'de> for JwkSet {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        struct RawJwkSet {
+            keys: Vec<Value>,
+        }
+
+        let raw = RawJwkSet::deserialize(deserializer)?;
+        let keys = raw
+            .keys
+            .into_iter()
+            .filter_map(|key| match serde_json::from_value::<JwkSetKey>(key) {
+                Ok(key) => Some(key),
+                Err(err) => {
+                    tracing::debug!("skip unsupported jwk in jwks: {err}");
+                    None
+                }
+            })
+            .collect();
+        Ok(Self { keys })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(from = "RawJwk", into = "RawJwk")]
+/// A key as part of a [`JwkSet`], optionally identified by a key id.
+///
+/// Unlike a [`JWK`], the algorithm is optional, as allowed by [`rfc7517`].
+/// A key without algorithm can be used for any algorithm supported by its key type.
+///
+/// [`rfc7517`]: https://datatracker.ietf.org/doc/html/rfc7517#section-4.4
+pub struct JwkSetKey {
+    /// Key id (`kid`) used to match the key with the `kid` header of a JWT.
+    pub kid: Option<String>,
+    /// Algorithm (`alg`) intended for use with this key, if restricted to one.
+    pub alg: Option<JWA>,
+    /// Intended use (`use`) of this key, if specified.
+    pub r#use: Option<JWKUse>,
+    /// The key type and its (public) key material.
+    pub key_type: JWKType,
+}
+
+impl JwkSetKey {
+    /// Returns `true` in case this key can be used to verify a JWT signed with the given algorithm.
+    fn supports(&self, alg: JWA) -> bool {
+        if self.r#use == Some(JWKUse::Encryption) {
+            return false;
+        }
+        if let Some(key_alg) = self.alg {
+            return key_alg == alg;
+        }
+        match &self.key_type {
+            JWKType::OCT { .. } => matches!(alg, JWA::HS256 | JWA::HS384 | JWA::HS512),
+            JWKType::RSA { .. } => matches!(
+                alg,
+                JWA::RS256 | JWA::RS384 | JWA::RS512 | JWA::PS256 | JWA::PS384 | JWA::PS512
+            ),
+            JWKType::EC { crv, .. } => JWA::from(*crv) == alg,
+        }
+    }
+
+    fn to_jwk(&self, alg: JWA) -> JWK {
+        JWK {
+            alg,
+            key_type: self.key_type.clone(),
+            r#use: self.r#use,
+            key_ops: None,
+            x5c: None,
+            x5t: None,
+            x5t_sha256: None,
+        }
+    }
+}
+
+/// The wire format of a [`JwkSetKey`], using the `kty` values of [`rfc7518`].
+///
+/// [`rfc7518`]: https://datatracker.ietf.org/doc/html/rfc7518#section-6.1
+#[derive(Clone, Serialize, Deserialize)]
+struct RawJwk {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    kid: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    alg: Option<JWA>,
+    #[serde(default, rename = "use", skip_serializing_if = "Option::is_none")]
+    key_use: Option<JWKUse>,
+    #[serde(flatten)]
+    key_type: RawJwkType,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(tag = "kty")]
+enum RawJwkType {
+    #[serde(rename = "RSA")]
+    Rsa { n: String, e: String },
+    #[serde(rename = "EC")]
+    Ec {
+        crv: JWKEllipticCurves,
+        x: String,
+        y: String,
+    },
+    #[serde(rename = "oct", alias = "OCT")]
+    Oct { k: String },
+}
+
+impl From<RawJwk> for JwkSetKey {
+    fn from(raw: RawJwk) -> Self {
+        Self {
+            kid: raw.kid,
+            alg: raw.alg,
+            r#use: raw.key_use,
+            key_type: match raw.key_type {
+                RawJwkType::Rsa { n, e } => JWKType::RSA { n, e },
+                RawJwkType::Ec { crv, x, y } => JWKType::EC { crv, x, y },
+                RawJwkType::Oct { k } => JWKType::OCT { k },
+            },
+        }
+    }
+}
+
+impl From<JwkSetKey> for RawJwk {
+    fn from(key: JwkSetKey) -> Self {
+        Self {
+            kid: key.kid,
+            alg: key.alg,
+            key_use: key.r#use,
+            key_type: match key.key_type {
+                JWKType::RSA { n, e } => RawJwkType::Rsa { n, e },
+                JWKType::EC { crv, x, y } => RawJwkType::Ec { crv, x, y },
+                JWKType::OCT { k } => RawJwkType::Oct { k },
+            },
+        }
+    }
+}
+
+impl JwkSet {
+    #[must_use]
+    /// Create a [`JwkSet`] containing a single symmetric (HMAC) key.
+    pub fn from_hmac_secret(alg: JWA, secret: &[u8]) -> Self {
+        Self {
+            keys: vec![JwkSetKey {
+                kid: None,
+                alg: Some(alg),
+                r#use: None,
+                key_type: JWKType::OCT {
+                    k: BASE64_URL_SAFE_NO_PAD.encode(secret),
+                },
+            }],
+        }
+    }
+
+    #[must_use]
+    /// Find the [`JWK`] matching the given algorithm and optional key id.
+    ///
+    /// In case no key id is given, the first key matching the algorithm is returned.
+    /// A key without algorithm matches any algorithm supported by its key type.
+    pub fn find(&self, kid: Option<&str>, alg: JWA) -> Option<JWK> {
+        self.keys
+            .iter()
+            .filter(|key| key.supports(alg))
+            .find(|key| kid.is_none() || key.kid.as_deref() == kid)
+            .map(|key| key.to_jwk(alg))
+    }
+}
+
+/// A source of [`JWK`]s used by the [`JwtValidator`] to verify JWT signatures.
+pub trait JwtKeySource: Send + Sync + 'static {
+    /// Find the [`JWK`] to verify a JWT signed with the given algorithm and optional key id.
+    fn find_key(
+        &self,
+        ctx: &Context,
+        kid: Option<&str>,
+        alg: JWA,
+    ) -> impl Future<Output = Result<JWK, OpaqueError>> + Send;
+}
+
+impl JwtKeySource for JwkSet {
+    async fn find_key(
+        &self,
+        _ctx: &Context,
+        kid: Option<&str>,
+        alg: JWA,
+    ) -> Result<JWK, OpaqueError> {
+        self.find(kid, alg).context("find matching jwk")
+    }
+}
+
+impl<K: JwtKeySource> JwtKeySource for Arc<K> {
+    fn find_key(
+        &self,
+        ctx: &Context,
+        kid: Option<&str>,
+        alg: JWA,
+    ) -> impl Future<Output = Result<JWK, OpaqueError>> + Send {
+        self.as_ref().find_key(ctx, kid, alg)
+    }
+}
+
+/// A [`JwtKeySource`] which fetches a [`JwkSet`] from a (JWKS) uri,
+/// e.g. the `jwks_uri` of an OIDC provider, and caches it.
+///
+/// The cached set is refreshed once it expires, or when a JWT refers to a key
+/// that is not part of the cached set (e.g. after a key rotation). The latter
+/// happens at most once per minimum refresh interval.
+pub struct RemoteJwks<C> {
+    client: C,
+    uri: Uri,
+    ttl: Duration,
+    min_refresh_interval: Duration,
+    state: Arc<RemoteJwksState>,
+}
+
+#[derive(Debug, Default)]
+struct RemoteJwksState {
+    cache: RwLock<Option<CachedJwkSet>>,
+    refresh: tokio::sync::Mutex<()>,
+}
+
+#[derive(Debug, Clone)]
+struct CachedJwkSet {
+    keys: Arc<JwkSet>,
+    fetched_at: Instant,
+}
+
+impl<C> RemoteJwks<C> {
+    /// Create a new [`RemoteJwks`] which fetches the [`JwkSet`]
+    /// from the given uri using the given http client.
+    pub fn new(client: C, uri: Uri) -> Self {
+        Self {
+            client,
+            uri,
+            ttl: Duration::from_secs(3600),
+            min_refresh_interval: Duration::from_secs(30),
+            state: Default::default(),
+        }
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Set the duration for which a fetched [`JwkSet`] is cached.
+        ///
+        /// Defaults to one hour.
+        pub fn ttl(mut self, ttl: Duration) -> Self {
+            self.ttl = ttl;
+            self
+        }
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Set the minimum interval between two fetches
+        /// triggered by an unknown key id.
+        ///
+        /// Defaults to 30 seconds.
+        pub fn min_refresh_interval(mut self, interval: Duration) -> Self {
+            self.min_refresh_interval = interval;
+            self
+        }
+    }
+
+    fn cached(&self) -> Option<CachedJwkSet> {
+        self.state
+            .cache
+            .read()
+            .unwrap_or_else(|err| err.into_inner())
+            .clone()
+    }
+}
+
+impl<C> RemoteJwks<C>
+where
+    C: Service<Request, Response = Response, Error: Into<BoxError>>,
+{
+    async fn refresh(
+        &self,
+        ctx: &Context,
+        stale: Option<Instant>,
+    ) -> Result<Arc<JwkSet>, OpaqueError> {
+        let _guard = self.state.refresh.lock().await;
+
+        // another task might have refreshed the set while we were waiting
+        if let Some(cached) = self.cached()
+            && stale.is_none_or(|stale| cached.fetched_at > stale)
+        {
+            return Ok(cached.keys);
+        }
+
+        let req = Request::builder()
+            .method(Method::GET)
+            .uri(self.uri.clone())
+            .body(Body::empty())
+            .context("build jwks request")?;
+
+        let resp = self
+            .client
+            .serve(Context::new(ctx.executor().clone()), req)
+            .await
+            .map_err(|err| OpaqueError::from_boxed(err.into()))
+            .context("fetch jwks")?;
+
+        if !resp.status().is_success() {
+            return Err(OpaqueError::from_display(format!(
+                "unexpected jwks response status: {}",
+                resp.status()
+            )));
+        }
+
+        let keys: Arc<JwkSet> = Arc::new(resp.try_into_json().await.context("decode jwks")?);
+        tracing::debug!(uri = %self.uri, "fetched {} jwk(s)", keys.keys.len());
+
+        *self
+            .state
+            .cache
+            .write()
+            .unwrap_or_else(|err| err.into_inner()) = Some(CachedJwkSet {
+            keys: keys.clone(),
+            fetched_at: Instant::now(),
+        });
+
+        Ok(keys)
+    }
+}
+
+impl<C> JwtKeySource for RemoteJwks<C>
+where
+    C: Service<Request, Response = Response, Error: Into<BoxError>>,
+{
+    async fn find_key(
+        &self,
+        ctx: &Context,
+        kid: Option<&str>,
+        alg: JWA,
+    ) -> Result<JWK, OpaqueError> {
+        let stale = match self.cached() {
+            Some(cached) => {
+                let age = cached.fetched_at.elapsed();
+                if age < self.ttl {
+                    if let Some(jwk) = cached.keys.find(kid, alg) {
+                        return Ok(jwk);
+                    }
+                    if age < self.min_refresh_interval {
+                        return Err(OpaqueError::from_display(
+                            "no matching jwk found in recently fetched jwks",
+                        ));
+                    }
+                }
+                Some(cached.fetched_at)
+            }
+            None => None,
+        };
+
+        let keys = self.refresh(ctx, stale).await?;
+        keys.find(kid, alg).context("find matching jwk")
+    }
+}
+
+impl<C: fmt::Debug> fmt::Debug for RemoteJwks<C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RemoteJwks")
+            .field("client", &self.client)
+            .field("uri", &self.uri)
+            .field("ttl", &self.ttl)
+            .field("min_refresh_interval", &self.min_refresh_interval)
+            .finish()
+    }
+}
+
+impl<C: Clone> Clone for RemoteJwks<C> {
+    fn clone(&self) -> Self {
+        Self {
+            client: self.client.clone(),
+            uri: self.uri.clone(),
+            ttl: self.ttl,
+            min_refresh_interval: self.min_refresh_interval,
+            state: self.state.clone(),
+        }
+    }
+}
+
+/// Authorizes a request based on the [`JwtClaims`] of its validated JWT.
+///
+/// Implemented for `()` (allowing all requests) and
+/// for any `Fn(&Context, &JwtClaims) -> bool`, which results
+/// in a [`StatusCode::FORBIDDEN`] response when returning `false`.
+pub trait JwtClaimsAuthorizer: Send + Sync + 'static {
+    /// Authorize the request, returning the response to be used in case it is not.
+    fn authorize(
+        &self,
+        ctx: &Context,
+        claims: &JwtClaims,
+    ) -> impl Future<Output = Result<(), Response>> + Send;
+}
+
+impl JwtClaimsAuthorizer for () {
+    async fn authorize(&self, _ctx: &Context, _claims: &JwtClaims) -> Result<(), Response> {
+        Ok(())
+    }
+}
+
+impl<F> JwtClaimsAuthorizer for F
+where
+    F: Fn(&Context, &JwtClaims) -> bool + Send + Sync + 'static,
+{
+    async fn authorize(&self, ctx: &Context, claims: &JwtClaims) -> Result<(), Response> {
+        if (self)(ctx, claims) {
+            Ok(())
+        } else {
+            Err(StatusCode::FORBIDDEN.into_response())
+        }
+    }
+}
+
+/// Validates the JWT bearer token of requests.
+///
+/// See the [module docs](self) for more information.
+pub struct JwtValidator<K, A = ()> {
+    keys: K,
+    authorizer: A,
+    issuers: Vec<String>,
+    audiences: Vec<String>,
+    leeway: Duration,
+    require_exp: bool,
+}
+
+impl<K> JwtValidator<K> {
+    /// Create a new [`JwtValidator`] using the given [`JwtKeySource`].
+    pub fn new(keys: K) -> Self {
+        Self {
+            keys,
+            authorizer: (),
+            issuers: Vec::new(),
+            audiences: Vec::new(),
+            leeway: Duration::from_secs(60),
+            require_exp: true,
+        }
+    }
+}
+
+impl<K, A> JwtValidator<K, A> {
+    /// Authorize validated requests using the given [`JwtClaimsAuthorizer`].
+    pub fn with_claims_authorizer<T>(self, authorizer: T) -> JwtValidator<K, T> {
+        JwtValidator {
+            keys: self.keys,
+            authorizer,
+            issuers: self.issuers,
+            audiences: self.audiences,
+            leeway: self.leeway,
+            require_exp: self.require_exp,
+        }
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Add an accepted issuer (`iss`).
+        ///
+        /// If no issuers are added, the issuer claim is not validated.
+        pub fn issuer(mut self, issuer: impl Into<String>) -> Self {
+            self.issuers.push(issuer.into());
+            self
+        }
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Add an accepted audience (`aud`).
+        ///
+        /// If no audiences are added, the audience claim is not validated.
+        pub fn audience(mut self, audience: impl Into<String>) -> Self {
+            self.audiences.push(audience.into());
+            self
+        }
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Set the leeway allowed for clock skew when validating
+        /// the `exp` and `nbf` claims.
+        ///
+        /// Defaults to 60 seconds.
+        pub fn leeway(mut self, leeway: Duration) -> Self {
+            self.leeway = leeway;
+            self
+        }
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Define whether or not the `exp` claim is required.
+        ///
+        /// Defaults to `true`.
+        pub fn require_exp(mut self, require: bool) -> Self {
+            self.require_exp = require;
+            self
+        }
+    }
+
+    fn validate_claims(&self, claims: &JwtClaims) -> Result<(), OpaqueError> {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        let leeway = self.leeway.as_secs();
+
+        match claims.exp {
+            Some(exp) if exp.saturating_add(leeway) < now => {
+                return Err(OpaqueError::from_display("jwt is expired"));
+            }
+            None if self.require_exp => {
+                return Err(OpaqueError::from_display("jwt is missing the exp claim"));
+            }
+            _ => (),
+        }
+
+        if let Some(nbf) = claims.nbf
+            && nbf > now.saturating_add(leeway)
+        {
+            return Err(OpaqueError::from_display("jwt is not yet valid"));
+        }
+
+        if !self.issuers.is_empty()
+            && !claims
+                .iss
+                .as_deref()
+                .is_some_and(|iss| self.issuers.iter().any(|expected| expected == iss))
+        {
+            return Err(OpaqueError::from_display("jwt issuer is not accepted"));
+        }
+
+        if !self.audiences.is_empty()
+            && !claims
+                .aud
+                .as_ref()
+                .is_some_and(|aud| self.audiences.iter().any(|expected| aud.contains(expected)))
+        {
+            return Err(OpaqueError::from_display("jwt audience is not accepted"));
+        }
+
+        Ok(())
+    }
+}
+
+impl<K: JwtKeySource, A> JwtValidator<K, A> {
+    /// Verify the given JWT (in compact serialization) and return its validated claims.
+    pub async fn verify(&self, ctx: &Context, token: &str) -> Result<JwtClaims, OpaqueError> {
+        let (signed_data, signature) = token.rsplit_once('.').context("split jwt signature")?;
+        let (header, payload) = signed_data.split_once('.').context("split jwt payload")?;
+
+        let header = BASE64_URL_SAFE_NO_PAD
+            .decode(header)
+            .context("decode jwt header")?;
+        let header: JwtHeader = serde_json::from_slice(&header).context("parse jwt header")?;
+
+        let signature = BASE64_URL_SAFE_NO_PAD
+            .decode(signature)
+            .context("decode jwt signature")?;
+
+        let jwk = self
+            .keys
+            .find_key(ctx, header.kid.as_deref(), header.alg)
+            .await?;
+        verify_signature(&jwk, header.alg, signed_data.as_bytes(), &signature)?;
+
+        let payload = BASE64_URL_SAFE_NO_PAD
+            .decode(payload)
+            .context("decode jwt payload")?;
+        let claims: JwtClaims = serde_json::from_slice(&payload).context("parse jwt claims")?;
+
+        self.validate_claims(&claims)?;
+        Ok(claims)
+    }
+}
+
+fn verify_signature(
+    jwk: &JWK,
+    alg: JWA,
+    message: &[u8],
+    signature: &[u8],
+) -> Result<(), OpaqueError> {
+    if jwk.alg != alg {
+        return Err(OpaqueError::from_display(
+            "jwk algorithm does not match jwt algorithm",
+        ));
+    }
+
+    match (&jwk.key_type, alg) {
+        (JWKType::OCT { k }, JWA::HS256 | JWA::HS384 | JWA::HS512) => {
+            let algorithm = match alg {
+                JWA::HS256 => hmac::HMAC_SHA256,
+                JWA::HS384 => hmac::HMAC_SHA384,
+                _ => hmac::HMAC_SHA512,
+            };
+            let secret = BASE64_URL_SAFE_NO_PAD
+                .decode(k)
+                .context("decode symmetric key")?;
+            hmac::verify(&hmac::Key::new(algorithm, &secret), message, signature)
+                .context("verify hmac signature")
+        }
+        (
+            JWKType::RSA { n, e },
+            JWA::RS256 | JWA::RS384 | JWA::RS512 | JWA::PS256 | JWA::PS384 | JWA::PS512,
+        ) => {
+            let params: &'static signature::RsaParameters = match alg {
+                JWA::RS256 => &signature::RSA_PKCS1_2048_8192_SHA256,
+                JWA::RS384 => &signature::RSA_PKCS1_2048_8192_SHA384,
+                JWA::RS512 => &signature::RSA_PKCS1_2048_8192_SHA512,
+                JWA::PS256 => &signature::RSA_PSS_2048_8192_SHA256,
+                JWA::PS384 => &signature::RSA_PSS_2048_8192_SHA384,
+                _ => &signature::RSA_PSS_2048_8192_SHA512,
+            };
+            let n = BASE64_URL_SAFE_NO_PAD
+                .decode(n)
+                .context("decode rsa modulus")?;
+            let e = BASE64_URL_SAFE_NO_PAD
+                .decode(e)
+                .context("decode rsa exponent")?;
+            signature::RsaPublicKeyComponents { n, e }
+                .verify(params, message, signature)
+                .context("verify rsa signature")
+        }
+        (JWKType::EC { .. }, JWA::ES256 | JWA::ES384) => jwk
+            .unparsed_public_key()?
+            .verify(message, signature)
+            .context("verify ecdsa signature"),
+        _ => Err(OpaqueError::from_display(
+            "jwt algorithm is not supported for the jwk key type",
+        )),
+    }
+}
+
+impl<K: fmt::Debug, A: fmt::Debug> fmt::Debug for JwtValidator<K, A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("JwtValidator")
+            .field("keys", &self.keys)
+            .field("authorizer", &self.authorizer)
+            .field("issuers", &self.issuers)
+            .field("audiences", &self.audiences)
+            .field("leeway", &self.leeway)
+            .field("require_exp", &self.require_exp)
+            .finish()
+    }
+}
+
+impl<K: Clone, A: Clone> Clone for JwtValidator<K, A> {
+    fn clone(&self) -> Self {
+        Self {
+            keys: self.keys.clone(),
+            authorizer: self.authorizer.clone(),
+            issuers: self.issuers.clone(),
+            audiences: self.audiences.clone(),
+            leeway: self.leeway,
+            require_exp: self.require_exp,
+        }
+    }
+}
+
+fn unauthorized(error: Option<&'static str>) -> Response {
+    let mut res = StatusCode::UNAUTHORIZED.into_response();
+    let challenge = match error {
+        Some(error) => HeaderValue::from_str(&format!(r#"Bearer error="{error}""#)),
+        None => Ok(HeaderValue::from_static("Bearer")),
+    };
+    if let Ok(challenge) = challenge {
+        res.headers_mut()
+            .insert(header::WWW_AUTHENTICATE, challenge);
+    }
+    res
+}
+
+impl<ReqBody, K, A> ValidateRequest<ReqBody> for JwtValidator<K, A>
+where
+    ReqBody: Send + 'static,
+    K: JwtKeySource,
+    A: JwtClaimsAuthorizer,
+{
+    type ResponseBody = Body;
+
+    async fn validate(
+        &self,
+        mut ctx: Context,
+        request: Request<ReqBody>,
+    ) -> Result<(Context, Request<ReqBody>), Response<Self::ResponseBody>> {
+        let Some(auth) = request.headers().typed_get::<Authorization<Bearer>>() else {
+            return Err(unauthorized(None));
+        };
+
+        let bearer = auth.into_inner();
+        let claims = match self.verify(&ctx, bearer.token()).await {
+            Ok(claims) => claims,
+            Err(err) => {
+                tracing::debug!("jwt bearer token rejected: {err}");
+                return Err(unauthorized(Some("invalid_token")));
+            }
+        };
+
+        self.authorizer.authorize(&ctx, &claims).await?;
+
+        if let Some(sub) = claims.sub.clone() {
+            ctx.insert(UserId::Username(sub));
+        }
+        ctx.insert(claims);
+
+        Ok((ctx, request))
+    }
+}
+
+impl<S, K, A> ValidateRequestHeader<S, JwtValidator<K, A>> {
+    #[inline]
+    /// Validate the JWT bearer token of requests with a [`JwtValidator`].
+    pub fn jwt(inner: S, validator: JwtValidator<K, A>) -> Self {
+        Self::custom(inner, validator)
+    }
+}
+
+impl<K, A> ValidateRequestHeaderLayer<JwtValidator<K, A>> {
+    #[inline]
+    /// Validate the JWT bearer token of requests with a [`JwtValidator`].
+    pub fn jwt(validator: JwtValidator<K, A>) -> Self {
+        Self::custom(validator)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rama_core::{Layer, service::service_fn};
+    use std::convert::Infallible;
+
+    const SECRET: &[u8] = b"test-secret";
+
+    fn now() -> u64 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs()
+    }
+
+    fn sign_hs256(header: &Value, claims: &Value) -> String {
+        let header = BASE64_URL_SAFE_NO_PAD.encode(serde_json::to_vec(header).unwrap());
+        let claims = BASE64_URL_SAFE_NO_PAD.encode(serde_json::to_vec(claims).unwrap());
+        let signed_data = format!("{header}.{claims}");
+        let tag = hmac::sign(
+            &hmac::Key::new(hmac::HMAC_SHA256, SECRET),
+            signed_data.as_bytes(),
+        );
+        format!(
+            "{signed_data}.{}",
+            BASE64_URL_SAFE_NO_PAD.encode(tag.as_ref())
+        )
+    }
+
+    fn token(claims: &Value) -> String {
+        sign_hs256(&serde_json::json!({"alg": "HS256", "typ": "JWT"}), claims)
+    }
+
+    fn validator() -> JwtValidator<JwkSet> {
+        JwtValidator::new(JwkSet::from_hmac_secret(JWA::HS256, SECRET))
+            .with_issuer("https://auth.example.com")
+            .with_audience("rama")
+    }
+
+    #[tokio::test]
+    async fn test_verify_valid_token() {
+        let token = token(&serde_json::json!({
+            "iss": "https://auth.example.com",
+            "aud": ["other", "rama"],
+            "sub": "john",
+            "exp": now() + 60,
+            "role": "admin",
+        }));
+
+        let claims = validator()
+            .verify(&Context::default(), &token)
+            .await
+            .unwrap();
+        assert_eq!(claims.sub.as_deref(), Some("john"));
+        assert_eq!(claims.get("role"), Some(&Value::from("admin")));
+    }
+
+    #[tokio::test]
+    async fn test_jwks_without_alg_and_unsupported_keys() {
+        let keys: JwkSet = serde_json::from_value(serde_json::json!({
+            "keys": [
+                {"kty": "OKP", "crv": "Ed25519", "x": "11qYAYKxCrfVS_7TyWQHOg7hcvPapiMlrwIaaPcHURo"},
+                {"kty": "RSA", "alg": "RSA-OAEP", "n": "AQAB", "e": "AQAB"},
+                {"kty": "oct", "kid": "enc", "use": "enc", "k": "ZW5j"},
+                {"kty": "oct", "kid": "sig", "k": BASE64_URL_SAFE_NO_PAD.encode(SECRET)},
+            ],
+        }))
+        .unwrap();
+        assert_eq!(keys.keys.len(), 2);
+        assert!(keys.find(Some("enc"), JWA::HS256).is_none());
+        assert!(keys.find(None, JWA::RS256).is_none());
+        assert_eq!(
+            keys.find(None, JWA::HS384).map(|jwk| jwk.alg),
+            Some(JWA::HS384)
+        );
+
+        let token = sign_hs256(
+            &serde_json::json!({"alg": "HS256", "kid": "sig"}),
+            &serde_json::json!({"sub": "john", "exp": now() + 60}),
+        );
+        let claims = JwtValidator::new(keys)
+            .verify(&Context::default(), &token)
+            .await
+            .unwrap();
+        assert_eq!(claims.sub.as_deref(), Some("john"));
+    }
+
+    #[tokio::test]
+    async fn test_verify_invalid_tokens() {
+        let exp = now() + 60;
+        let tokens = [
+            // expired (beyond leeway)
+            token(&serde_json::json!({
+                "iss": "https://auth.example.com", "aud": "rama", "exp": now() - 120,
+            })),
+            // not yet valid
+            token(&serde_json::json!({
+                "iss": "https://auth.example.com", "aud": "rama", "exp": exp, "nbf": now() + 120,
+            })),
+            // missing exp
+            token(&serde_json::json!({
+                "iss": "https://auth.example.com", "aud": "rama",
+            })),
+            // wrong issuer
+            token(&serde_json::json!({
+                "iss": "https://evil.example.com", "aud": "rama", "exp": exp,
+            })),
+            // wrong audience
+            token(&serde_json::json!({
+                "iss": "https://auth.example.com", "aud": "other", "exp": exp,
+            })),
+            // alg none is not supported
+            format!(
+                "{}.{}.",
+                BASE64_URL_SAFE_NO_PAD.encode(br#"{"alg":"none"}"#),
+                BASE64_URL_SAFE_NO_PAD.encode(br#"{"aud":"rama"}"#),
+            ),
+            // garbage
+            "foo.bar".to_owned(),
+        ];
+
+        for token in tokens {
+            assert!(
+                validator()
+                    .verify(&Context::default(), &token)
+                    .await
+                    .is_err(),
+                "token: {token}"
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_verify_tampered_token() {
+        let token = token(&serde_json::json!({
+            "iss": "https://auth.example.com", "aud": "rama", "exp": now() + 60, "sub": "john",
+        }));
+        let (_, signature) = token.rsplit_once('.').unwrap();
+        let tampered = format!(
+            "{}.{}.{signature}",
+            BASE64_URL_SAFE_NO_PAD.encode(br#"{"alg":"HS256"}"#),
+            BASE64_URL_SAFE_NO_PAD
+                .encode(br#"{"iss":"https://auth.example.com","aud":"rama","sub":"admin"}"#),
+        );
+        assert!(
+            validator()
+                .verify(&Context::default(), &tampered)
+                .await
+                .is_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_jwt_layer() {
+        let service = ValidateRequestHeaderLayer::jwt(validator().with_claims_authorizer(
+            |_: &Context, claims: &JwtClaims| claims.get("role") == Some(&Value::from("admin")),
+        ))
+        .into_layer(service_fn(async |ctx: Context, _: Request| {
+            assert!(ctx.contains::<JwtClaims>());
+            assert_eq!(
+                ctx.get::<UserId>(),
+                Some(&UserId::Username("john".to_owned()))
+            );
+            Ok::<_, Infallible>(Response::new(Body::empty()))
+        }));
+
+        let serve = async |token: Option<String>| {
+            let mut builder = Request::builder();
+            if let Some(token) = token {
+                builder = builder.header(header::AUTHORIZATION, format!("Bearer {token}"));
+            }
+            service
+                .serve(Context::default(), builder.body(Body::empty()).unwrap())
+                .await
+                .unwrap()
+                .status()
+        };
+
+        assert_eq!(serve(None).await, StatusCode::UNAUTHORIZED);
+        assert_eq!(
+            serve(Some("invalid".to_owned())).await,
+            StatusCode::UNAUTHORIZED
+        );
+        assert_eq!(
+            serve(Some(token(&serde_json::json!({
+                "iss": "https://auth.example.com", "aud": "rama", "exp": now() + 60,
+                "sub": "john", "role": "user",
+            }))))
+            .await,
+            StatusCode::FORBIDDEN
+        );
+        assert_eq!(
+            serve(Some(token(&serde_json::json!({
+                "iss": "https://auth.example.com", "aud": "rama", "exp": now() + 60,
+                "sub": "john", "role": "admin",
+            }))))
+            .await,
+            StatusCode::OK
+        );
+    }
+
+    #[tokio::test]
+    async fn test_remote_jwks_is_cached() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let fetches = Arc::new(AtomicUsize::new(0));
+        let client = service_fn({
+            let fetches = fetches.clone();
+            move |_: Context, _: Request| {
+                let fetches = fetches.clone();
+                async move {
+                    fetches.fetch_add(1, Ordering::SeqCst);
+                    let body =
+                        serde_json::to_vec(&JwkSet::from_hmac_secret(JWA::HS256, SECRET)).unwrap();
+                    Ok::<_, Infallible>(Response::new(Body::from(body)))
+                }
+            }
+        });
+
+        let keys = RemoteJwks::new(
+            client,
+            Uri::from_static("https://auth.example.com/.well-known/jwks.json"),
+        );
+
+        let ctx = Context::default();
+        keys.find_key(&ctx, None, JWA::HS256).await.unwrap();
+        keys.find_key(&ctx, None, JWA::HS256).await.unwrap();
+        assert_eq!(fetches.load(Ordering::SeqCst), 1);
+
+        // unknown keys do not trigger a refetch within the min refresh interval
+        keys.find_key(&ctx, Some("unknown"), JWA::HS256)
+            .await
+            .unwrap_err();
+        assert_eq!(fetches.load(Ordering::SeqCst), 1);
+    }
+}
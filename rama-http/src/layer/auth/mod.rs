@@ -1,6 +1,8 @@
 //! Authorization related middleware.
 
 pub mod add_authorization;
+pub mod api_key;
+pub mod jwt;
 pub mod validate_authorization;
 
 #[doc(inline)]
@@ -85,8 +85,66 @@
 //! # Ok(())
 //! # }
 //! ```
+//!
+//! Using a panic hook, which receives a [`PanicReport`] containing
+//! the panic payload together with metadata of the request that caused it.
+//! The matched route is only known when the middleware is applied
+//! to the endpoints of a [`Router`]:
+//!
+//! [`Router`]: crate::service::web::Router
+//!
+//! ```rust
+//! use std::convert::Infallible;
+//!
+//! use rama_http::{Body, Request, StatusCode, Response};
+//! use rama_http::layer::catch_panic::{CatchPanicLayer, PanicReport};
+//! use rama_core::service::{Service, service_fn};
+//! use rama_http::service::web::Router;
+//! use rama_core::{Context, Layer};
+//! use rama_core::error::BoxError;
+//!
+//! # #[tokio::main]
+//! # async fn main() -> Result<(), BoxError> {
+//! async fn handle(req: Request) -> Result<Response, Infallible> {
+//!     panic!("something went wrong...")
+//! }
+//!
+//! fn on_panic(report: PanicReport) -> Response {
+//!     // e.g. emit structured telemetry
+//!     eprintln!(
+//!         "panic: {:?} (request id: {:?}, route: {:?})",
+//!         report.message(),
+//!         report.request_id(),
+//!         report.route(),
+//!     );
+//!
+//!     let mut res = Response::new(Body::from(format!(
+//!         "internal error, reference: {}",
+//!         report.request_id().unwrap_or("-"),
+//!     )));
+//!     *res.status_mut() = StatusCode::INTERNAL_SERVER_ERROR;
+//!     res
+//! }
+//!
+//! let svc = Router::new().get(
+//!     "/users/{id}",
+//!     CatchPanicLayer::hook(on_panic).into_layer(service_fn(handle)),
+//! );
+//!
+//! let req = Request::builder()
+//!     .uri("/users/42")
+//!     .header("x-request-id", "abc")
+//!     .body(Body::empty())?;
+//! let res = svc.serve(Context::default(), req).await?;
+//! assert_eq!(res.status(), StatusCode::INTERNAL_SERVER_ERROR);
+//! #
+//! # Ok(())
+//! # }
+//! ```
 
-use crate::{Body, HeaderValue, Request, Response, StatusCode};
+use crate::layer::request_id::{REQUEST_ID, RequestId, X_REQUEST_ID};
+use crate::service::web::MatchedRoute;
+use crate::{Body, HeaderValue, Method, Request, Response, StatusCode, Uri};
 use rama_core::futures::FutureExt;
 use rama_core::telemetry::tracing;
 use rama_core::{Context, Layer, Service};
@@ -144,6 +202,19 @@ impl<T> CatchPanicLayer<T> {
     }
 }
 
+impl<F> CatchPanicLayer<PanicHook<F>> {
+    /// Create a new `CatchPanicLayer` with a [`PanicHook`],
+    /// receiving a [`PanicReport`] for each panic caught.
+    pub fn hook(hook: F) -> Self
+    where
+        F: Fn(PanicReport) -> Response + Clone,
+    {
+        Self {
+            panic_handler: PanicHook::new(hook),
+        }
+    }
+}
+
 impl<T, S> Layer<S> for CatchPanicLayer<T>
 where
     T: Clone,
@@ -232,25 +303,179 @@ where
         ctx: Context,
         req: Request<ReqBody>,
     ) -> Result<Self::Response, Self::Error> {
+        // the request and its context are consumed by the inner service,
+        // so anything reported on panic has to be kept aside upfront
+        let head = self
+            .panic_handler
+            .capture_context()
+            .then(|| (RequestHead::new(&req), ctx.clone()));
+        let route = ctx.get::<MatchedRoute>().cloned();
+        let respond = |payload| {
+            let (head, context) = head.unzip();
+            self.panic_handler.response_for_panic_report(PanicReport {
+                payload,
+                head,
+                route,
+                context,
+            })
+        };
+
         let future = match std::panic::catch_unwind(AssertUnwindSafe(|| self.inner.serve(ctx, req)))
         {
             Ok(future) => future,
-            Err(panic_err) => return Ok(self.panic_handler.response_for_panic(panic_err)),
+            Err(panic_err) => return Ok(respond(panic_err)),
         };
         match AssertUnwindSafe(future).catch_unwind().await {
             Ok(res) => match res {
                 Ok(res) => Ok(res.map(Into::into)),
                 Err(err) => Err(err),
             },
-            Err(panic_err) => Ok(self.panic_handler.response_for_panic(panic_err)),
+            Err(panic_err) => Ok(respond(panic_err)),
+        }
+    }
+}
+
+/// The head of the request which caused a panic,
+/// only captured in case [`ResponseForPanic::capture_context`] returns `true`.
+#[derive(Debug)]
+struct RequestHead {
+    method: Method,
+    uri: Uri,
+    request_id: Option<HeaderValue>,
+}
+
+impl RequestHead {
+    fn new<B>(req: &Request<B>) -> Self {
+        Self {
+            method: req.method().clone(),
+            uri: req.uri().clone(),
+            request_id: req
+                .extensions()
+                .get::<RequestId>()
+                .map(RequestId::header_value)
+                .or_else(|| req.headers().get(X_REQUEST_ID))
+                .or_else(|| req.headers().get(REQUEST_ID))
+                .cloned(),
         }
     }
 }
 
+/// Information about a panic caught by the [`CatchPanic`] middleware,
+/// passed to [`ResponseForPanic::response_for_panic_report`].
+pub struct PanicReport {
+    payload: Box<dyn Any + Send + 'static>,
+    head: Option<RequestHead>,
+    route: Option<MatchedRoute>,
+    context: Option<Context>,
+}
+
+impl fmt::Debug for PanicReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PanicReport")
+            .field("message", &self.message())
+            .field("head", &self.head)
+            .field("route", &self.route)
+            .field("context", &self.context)
+            .finish()
+    }
+}
+
+impl PanicReport {
+    /// Returns the panic message, in case the payload is a string.
+    #[must_use]
+    pub fn message(&self) -> Option<&str> {
+        self.payload
+            .downcast_ref::<String>()
+            .map(String::as_str)
+            .or_else(|| self.payload.downcast_ref::<&str>().copied())
+    }
+
+    /// Returns the panic payload.
+    #[must_use]
+    pub fn payload(&self) -> &(dyn Any + Send + 'static) {
+        &*self.payload
+    }
+
+    /// Consumes the report, returning the panic payload.
+    #[must_use]
+    pub fn into_payload(self) -> Box<dyn Any + Send + 'static> {
+        self.payload
+    }
+
+    /// Returns the method of the request which caused the panic.
+    ///
+    /// Only captured in case [`ResponseForPanic::capture_context`] returns `true`.
+    #[must_use]
+    pub fn method(&self) -> Option<&Method> {
+        self.head.as_ref().map(|head| &head.method)
+    }
+
+    /// Returns the uri of the request which caused the panic.
+    ///
+    /// Only captured in case [`ResponseForPanic::capture_context`] returns `true`.
+    #[must_use]
+    pub fn uri(&self) -> Option<&Uri> {
+        self.head.as_ref().map(|head| &head.uri)
+    }
+
+    /// Returns the id of the request which caused the panic, if one was set.
+    ///
+    /// Only captured in case [`ResponseForPanic::capture_context`] returns `true`.
+    /// See [`request_id`] for more information.
+    ///
+    /// [`request_id`]: crate::layer::request_id
+    #[must_use]
+    pub fn request_id(&self) -> Option<&str> {
+        self.head
+            .as_ref()?
+            .request_id
+            .as_ref()
+            .and_then(|value| value.to_str().ok())
+    }
+
+    /// Returns the route matched by the [`Router`] for the request which caused the panic.
+    ///
+    /// The route is read from the [`Context`], and is therefore only known
+    /// in case the [`CatchPanic`] middleware is applied to the endpoints of a [`Router`].
+    /// For nested routers this is the route of the innermost [`Router`].
+    ///
+    /// [`Router`]: crate::service::web::Router
+    #[must_use]
+    pub fn route(&self) -> Option<&MatchedRoute> {
+        self.route.as_ref()
+    }
+
+    /// Returns the [`Context`] of the request which caused the panic,
+    /// as it was received by the [`CatchPanic`] middleware.
+    ///
+    /// Only captured in case [`ResponseForPanic::capture_context`] returns `true`,
+    /// which is the case for a [`PanicHook`].
+    #[must_use]
+    pub fn context(&self) -> Option<&Context> {
+        self.context.as_ref()
+    }
+}
+
 /// Trait for creating responses from panics.
 pub trait ResponseForPanic: Clone {
     /// Create a response from the panic error.
     fn response_for_panic(&self, err: Box<dyn Any + Send + 'static>) -> Response<Body>;
+
+    /// Create a response from the [`PanicReport`], containing the panic error
+    /// together with metadata of the request which caused it.
+    ///
+    /// Defaults to [`ResponseForPanic::response_for_panic`], ignoring the metadata.
+    fn response_for_panic_report(&self, report: PanicReport) -> Response<Body> {
+        self.response_for_panic(report.into_payload())
+    }
+
+    /// Returns `true` in case the [`Context`] and head of requests have to be captured
+    /// for the [`PanicReport`], which requires them to be cloned for each request.
+    ///
+    /// Defaults to `false`.
+    fn capture_context(&self) -> bool {
+        false
+    }
 }
 
 impl<F> ResponseForPanic for F
@@ -281,7 +506,32 @@ impl ResponseForPanic for DefaultResponseForPanic {
                 "Service panicked but `CatchPanic` was unable to downcast the panic info"
             );
         };
+        Self::response()
+    }
 
+    fn response_for_panic_report(&self, report: PanicReport) -> Response {
+        match report.message() {
+            Some(message) => tracing::error!(
+                http.request.method = report.method().map(tracing::field::display),
+                url.full = report.uri().map(tracing::field::display),
+                http.request.id = report.request_id(),
+                http.route = report.route.as_ref().map(MatchedRoute::as_str),
+                "Service panicked: {message}",
+            ),
+            None => tracing::error!(
+                http.request.method = report.method().map(tracing::field::display),
+                url.full = report.uri().map(tracing::field::display),
+                http.request.id = report.request_id(),
+                http.route = report.route.as_ref().map(MatchedRoute::as_str),
+                "Service panicked but `CatchPanic` was unable to downcast the panic info",
+            ),
+        }
+        Self::response()
+    }
+}
+
+impl DefaultResponseForPanic {
+    fn response() -> Response {
         let mut res = Response::new(Body::from("Service panicked"));
         *res.status_mut() = StatusCode::INTERNAL_SERVER_ERROR;
 
@@ -294,6 +544,45 @@ impl ResponseForPanic for DefaultResponseForPanic {
     }
 }
 
+/// A [`ResponseForPanic`] which calls a function with the [`PanicReport`]
+/// of each panic caught, e.g. to emit structured telemetry or create a custom response.
+///
+/// The [`Context`] and head of requests are captured for the [`PanicReport`].
+#[derive(Clone)]
+pub struct PanicHook<F>(F);
+
+impl<F> PanicHook<F> {
+    /// Create a new [`PanicHook`] calling the given function.
+    pub const fn new(hook: F) -> Self {
+        Self(hook)
+    }
+}
+
+impl<F> fmt::Debug for PanicHook<F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("PanicHook")
+            .field(&std::any::type_name::<F>())
+            .finish()
+    }
+}
+
+impl<F> ResponseForPanic for PanicHook<F>
+where
+    F: Fn(PanicReport) -> Response + Clone,
+{
+    fn response_for_panic(&self, err: Box<dyn Any + Send + 'static>) -> Response {
+        DefaultResponseForPanic.response_for_panic(err)
+    }
+
+    fn response_for_panic_report(&self, report: PanicReport) -> Response {
+        (self.0)(report)
+    }
+
+    fn capture_context(&self) -> bool {
+        true
+    }
+}
+
 #[cfg(test)]
 mod tests {
     #![allow(unreachable_code)]
@@ -301,6 +590,7 @@ mod tests {
     use super::*;
 
     use crate::dep::http_body_util::BodyExt;
+    use crate::service::web::Router;
     use crate::{Body, Response};
     use rama_core::service::service_fn;
     use rama_core::{Context, Service};
@@ -337,4 +627,42 @@ mod tests {
         let body = res.into_body().collect().await.unwrap().to_bytes();
         assert_eq!(&body[..], b"Service panicked");
     }
+
+    #[tokio::test]
+    async fn panic_hook_receives_report() {
+        #[derive(Debug, Clone)]
+        struct Tenant(&'static str);
+
+        let svc = CatchPanicLayer::hook(|report: PanicReport| {
+            let body = format!(
+                "{} {} {} {} {}",
+                report.message().unwrap(),
+                report.method().unwrap(),
+                report.request_id().unwrap(),
+                report.route().unwrap(),
+                report.context().unwrap().get::<Tenant>().unwrap().0,
+            );
+            let mut res = Response::new(Body::from(body));
+            *res.status_mut() = StatusCode::SERVICE_UNAVAILABLE;
+            res
+        })
+        .into_layer(service_fn(async |_: Request| {
+            panic!("route panic");
+            Ok::<_, Infallible>(Response::new(Body::empty()))
+        }));
+        let svc = Router::new().get("/users/{id}", svc);
+
+        let mut ctx = Context::default();
+        ctx.insert(Tenant("acme"));
+        let req = Request::get("/users/42")
+            .header(X_REQUEST_ID, "abc")
+            .body(Body::empty())
+            .unwrap();
+
+        let res = svc.serve(ctx, req).await.unwrap();
+
+        assert_eq!(res.status(), StatusCode::SERVICE_UNAVAILABLE);
+        let body = res.into_body().collect().await.unwrap().to_bytes();
+        assert_eq!(&body[..], b"route panic GET abc /users/{id} acme");
+    }
 }
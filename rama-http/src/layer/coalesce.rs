@@ -0,0 +1,570 @@
+//! Middleware that coalesces concurrent identical requests.
+//!
+//! Only one of multiple concurrent requests with the same key
+//! is forwarded to the inner service, while the others wait for its response,
+//! which is then shared with all of them. This protects an origin from
+//! a thundering herd of identical requests, e.g. when a popular resource
+//! expired in a cache.
+//!
+//! By default only `GET` and `HEAD` requests without credentials
+//! (`Authorization`, `Proxy-Authorization` or `Cookie` headers) are coalesced,
+//! keyed by their method, host, path and query using [`DefaultMakeCoalesceKey`].
+//! A custom [`MakeCoalesceKey`] can be used to change the key,
+//! e.g. to include headers which influence the response.
+//!
+//! The response to be shared is buffered in memory, up to a configurable maximum size.
+//! A response is not shared, but each waiting request is forwarded to the inner service
+//! itself instead, in case the response:
+//!
+//! - sets cookies, or is marked `private` or `no-store`;
+//! - varies on request headers of which the values differ from the leading request;
+//! - has a body larger than the maximum size.
+//!
+//! The same happens when the request in flight is cancelled.
+//!
+//! # Example
+//!
+//! ```
+//! use rama_core::{Context, Layer, Service, service::service_fn};
+//! use rama_http::layer::coalesce::CoalesceLayer;
+//! use rama_http::{Body, Request, Response};
+//! use std::convert::Infallible;
+//!
+//! # #[tokio::main]
+//! # async fn main() {
+//! let service = CoalesceLayer::new().into_layer(service_fn(async |_: Request| {
+//!     Ok::<_, Infallible>(Response::new(Body::from("hello")))
+//! }));
+//!
+//! let req = || Request::builder().uri("http://example.com/").body(Body::empty()).unwrap();
+//! let (a, b) = tokio::join!(
+//!     service.serve(Context::default(), req()),
+//!     service.serve(Context::default(), req()),
+//! );
+//! assert!(a.is_ok() && b.is_ok());
+//! # }
+//! ```
+
+use crate::dep::http_body::Body as HttpBody;
+use crate::layer::response_cache::{DefaultMakeCacheKey, MakeCacheKey, vary_request_headers};
+use crate::layer::util::collect::{LimitedBody, collect_limited};
+use crate::{
+    Body, HeaderMap, HeaderName, HeaderValue, Method, Request, Response, StatusCode, Version,
+    header,
+};
+use rama_core::{
+    Context, Layer, Service,
+    bytes::Bytes,
+    error::{BoxError, OpaqueError},
+    telemetry::tracing,
+};
+use rama_http_headers::{CacheControl, HeaderMapExt};
+use rama_utils::macros::define_inner_service_accessors;
+use std::{
+    collections::HashMap,
+    fmt,
+    sync::{Arc, Mutex},
+};
+use tokio::sync::watch;
+
+/// Create the key used to coalesce identical requests.
+pub trait MakeCoalesceKey: Send + Sync + 'static {
+    /// Create the coalesce key for the given request,
+    /// returning `None` in case the request should not be coalesced.
+    fn make_coalesce_key<B>(&self, ctx: &Context, req: &Request<B>) -> Option<String>;
+}
+
+/// The default maximum size of a response body which can be shared.
+pub const DEFAULT_MAX_BODY_SIZE: usize = 1024 * 1024;
+
+#[derive(Debug, Clone, Default)]
+#[non_exhaustive]
+/// The default [`MakeCoalesceKey`], coalescing `GET` and `HEAD` requests
+/// without credentials, keyed by their method, host, path and query,
+/// as for the [`DefaultMakeCacheKey`].
+pub struct DefaultMakeCoalesceKey;
+
+impl MakeCoalesceKey for DefaultMakeCoalesceKey {
+    fn make_coalesce_key<B>(&self, ctx: &Context, req: &Request<B>) -> Option<String> {
+        if req.method() != Method::GET && req.method() != Method::HEAD {
+            return None;
+        }
+        let headers = req.headers();
+        if headers.contains_key(header::AUTHORIZATION)
+            || headers.contains_key(header::PROXY_AUTHORIZATION)
+            || headers.contains_key(header::COOKIE)
+        {
+            return None;
+        }
+        DefaultMakeCacheKey.make_cache_key(ctx, req)
+    }
+}
+
+/// Layer that applies the [`Coalesce`] middleware.
+///
+/// See the [module docs](self) for more information.
+#[derive(Debug, Clone)]
+pub struct CoalesceLayer<K = DefaultMakeCoalesceKey> {
+    make_key: K,
+    max_body_size: usize,
+}
+
+impl CoalesceLayer {
+    /// Create a new [`CoalesceLayer`].
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            make_key: DefaultMakeCoalesceKey,
+            max_body_size: DEFAULT_MAX_BODY_SIZE,
+        }
+    }
+}
+
+impl Default for CoalesceLayer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K> CoalesceLayer<K> {
+    /// Use a custom [`MakeCoalesceKey`] to create the coalesce key of requests.
+    pub fn with_make_coalesce_key<K2>(self, make_key: K2) -> CoalesceLayer<K2> {
+        CoalesceLayer {
+            make_key,
+            max_body_size: self.max_body_size,
+        }
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Set the maximum size of a response body which can be shared.
+        ///
+        /// Defaults to [`DEFAULT_MAX_BODY_SIZE`].
+        pub fn max_body_size(mut self, size: usize) -> Self {
+            self.max_body_size = size;
+            self
+        }
+    }
+}
+
+impl<S, K: Clone> Layer<S> for CoalesceLayer<K> {
+    type Service = Coalesce<S, K>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        Coalesce::new(inner)
+            .with_make_coalesce_key(self.make_key.clone())
+            .with_max_body_size(self.max_body_size)
+    }
+
+    fn into_layer(self, inner: S) -> Self::Service {
+        Coalesce::new(inner)
+            .with_make_coalesce_key(self.make_key)
+            .with_max_body_size(self.max_body_size)
+    }
+}
+
+#[derive(Debug, Clone)]
+enum FlightResult {
+    Shared(SharedResponse),
+    NotShared,
+    Failed(Arc<str>),
+}
+
+#[derive(Debug, Clone)]
+struct SharedResponse {
+    status: StatusCode,
+    version: Version,
+    headers: HeaderMap,
+    body: Bytes,
+    vary: Vec<(HeaderName, Option<HeaderValue>)>,
+}
+
+impl SharedResponse {
+    fn to_response(&self) -> Response {
+        let mut resp = Response::new(Body::from(self.body.clone()));
+        *resp.status_mut() = self.status;
+        *resp.version_mut() = self.version;
+        *resp.headers_mut() = self.headers.clone();
+        resp
+    }
+}
+
+type Flights = Arc<Mutex<HashMap<String, watch::Receiver<Option<FlightResult>>>>>;
+
+/// Middleware which coalesces concurrent identical requests.
+///
+/// See the [module docs](self) for more information.
+pub struct Coalesce<S, K = DefaultMakeCoalesceKey> {
+    inner: S,
+    make_key: K,
+    max_body_size: usize,
+    flights: Flights,
+}
+
+impl<S> Coalesce<S> {
+    /// Create a new [`Coalesce`] middleware.
+    pub fn new(inner: S) -> Self {
+        Self {
+            inner,
+            make_key: DefaultMakeCoalesceKey,
+            max_body_size: DEFAULT_MAX_BODY_SIZE,
+            flights: Default::default(),
+        }
+    }
+}
+
+impl<S, K> Coalesce<S, K> {
+    /// Use a custom [`MakeCoalesceKey`] to create the coalesce key of requests.
+    pub fn with_make_coalesce_key<K2>(self, make_key: K2) -> Coalesce<S, K2> {
+        Coalesce {
+            inner: self.inner,
+            make_key,
+            max_body_size: self.max_body_size,
+            flights: self.flights,
+        }
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Set the maximum size of a response body which can be shared.
+        ///
+        /// Defaults to [`DEFAULT_MAX_BODY_SIZE`].
+        pub fn max_body_size(mut self, size: usize) -> Self {
+            self.max_body_size = size;
+            self
+        }
+    }
+
+    define_inner_service_accessors!();
+}
+
+impl<S: fmt::Debug, K: fmt::Debug> fmt::Debug for Coalesce<S, K> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Coalesce")
+            .field("inner", &self.inner)
+            .field("make_key", &self.make_key)
+            .field("max_body_size", &self.max_body_size)
+            .finish()
+    }
+}
+
+impl<S: Clone, K: Clone> Clone for Coalesce<S, K> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            make_key: self.make_key.clone(),
+            max_body_size: self.max_body_size,
+            flights: self.flights.clone(),
+        }
+    }
+}
+
+/// Removes the flight from the in-flight requests once the leading request
+/// is finished or cancelled.
+struct FlightGuard {
+    flights: Flights,
+    key: String,
+}
+
+impl Drop for FlightGuard {
+    fn drop(&mut self) {
+        self.flights
+            .lock()
+            .unwrap_or_else(|err| err.into_inner())
+            .remove(&self.key);
+    }
+}
+
+enum Role {
+    Leader(watch::Sender<Option<FlightResult>>, FlightGuard),
+    Waiter(watch::Receiver<Option<FlightResult>>),
+}
+
+impl<S, K, ReqBody, ResBody> Service<Request<ReqBody>> for Coalesce<S, K>
+where
+    S: Service<Request<ReqBody>, Response = Response<ResBody>, Error: Into<BoxError>>,
+    K: MakeCoalesceKey,
+    ReqBody: Send + 'static,
+    ResBody: HttpBody<Data = Bytes, Error: Into<BoxError>> + Send + Sync + 'static,
+{
+    type Response = Response;
+    type Error = BoxError;
+
+    async fn serve(
+        &self,
+        ctx: Context,
+        req: Request<ReqBody>,
+    ) -> Result<Self::Response, Self::Error> {
+        let Some(key) = self.make_key.make_coalesce_key(&ctx, &req) else {
+            let resp = self.inner.serve(ctx, req).await.map_err(Into::into)?;
+            return Ok(resp.map(Body::new));
+        };
+
+        let role = {
+            let mut flights = self.flights.lock().unwrap_or_else(|err| err.into_inner());
+            match flights.get(&key) {
+                Some(rx) => Role::Waiter(rx.clone()),
+                None => {
+                    let (tx, rx) = watch::channel(None);
+                    flights.insert(key.clone(), rx);
+                    Role::Leader(
+                        tx,
+                        FlightGuard {
+                            flights: self.flights.clone(),
+                            key: key.clone(),
+                        },
+                    )
+                }
+            }
+        };
+
+        match role {
+            Role::Waiter(mut rx) => {
+                let result = rx
+                    .wait_for(Option::is_some)
+                    .await
+                    .ok()
+                    .and_then(|r| r.clone());
+                match result {
+                    Some(FlightResult::Shared(shared))
+                        if shared
+                            .vary
+                            .iter()
+                            .all(|(name, value)| req.headers().get(name) == value.as_ref()) =>
+                    {
+                        tracing::trace!("serve coalesced response for key {key}");
+                        Ok(shared.to_response())
+                    }
+                    Some(FlightResult::Failed(err)) => Err(OpaqueError::from_display(err).into()),
+                    _ => {
+                        tracing::debug!(
+                            "coalesced response for key {key} is not available: forward request"
+                        );
+                        let resp = self.inner.serve(ctx, req).await.map_err(Into::into)?;
+                        Ok(resp.map(Body::new))
+                    }
+                }
+            }
+            Role::Leader(tx, _guard) => {
+                let request_headers = req.headers().clone();
+                let resp = match self.inner.serve(ctx, req).await {
+                    Ok(resp) => resp,
+                    Err(err) => {
+                        let err = err.into();
+                        let _ = tx.send(Some(FlightResult::Failed(err.to_string().into())));
+                        return Err(err);
+                    }
+                };
+
+                let Some(vary) = shareable_vary(&request_headers, resp.headers()) else {
+                    let _ = tx.send(Some(FlightResult::NotShared));
+                    return Ok(resp.map(Body::new));
+                };
+
+                let (parts, body) = resp.into_parts();
+                match collect_limited(body, self.max_body_size).await {
+                    Ok(LimitedBody::Collected(body)) => {
+                        let shared = SharedResponse {
+                            status: parts.status,
+                            version: parts.version,
+                            headers: parts.headers.clone(),
+                            body: body.clone(),
+                            vary,
+                        };
+                        let _ = tx.send(Some(FlightResult::Shared(shared)));
+                        Ok(Response::from_parts(parts, Body::from(body)))
+                    }
+                    Ok(LimitedBody::Exceeded(body)) => {
+                        let _ = tx.send(Some(FlightResult::NotShared));
+                        Ok(Response::from_parts(parts, body))
+                    }
+                    Err(err) => {
+                        let _ = tx.send(Some(FlightResult::Failed(err.to_string().into())));
+                        Err(err)
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Returns the request headers the response varies on,
+/// or `None` in case the response cannot be shared with other clients.
+fn shareable_vary(
+    request_headers: &HeaderMap,
+    response_headers: &HeaderMap,
+) -> Option<Vec<(HeaderName, Option<HeaderValue>)>> {
+    if response_headers.contains_key(header::SET_COOKIE)
+        || response_headers
+            .typed_get::<CacheControl>()
+            .is_some_and(|cc| cc.private() || cc.no_store())
+    {
+        return None;
+    }
+    vary_request_headers(request_headers, response_headers)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rama_core::service::service_fn;
+    use rama_http_types::BodyExtractExt;
+    use std::{
+        convert::Infallible,
+        sync::atomic::{AtomicUsize, Ordering},
+        time::Duration,
+    };
+
+    fn slow_service(
+        counter: Arc<AtomicUsize>,
+    ) -> impl Service<Request, Response = Response, Error = Infallible> {
+        service_fn(move |_: Request| {
+            let counter = counter.clone();
+            async move {
+                let n = counter.fetch_add(1, Ordering::SeqCst) + 1;
+                tokio::time::sleep(Duration::from_millis(50)).await;
+                Ok::<_, Infallible>(Response::new(Body::from(n.to_string())))
+            }
+        })
+    }
+
+    fn request(method: Method, uri: &str) -> Request {
+        Request::builder()
+            .method(method)
+            .uri(uri)
+            .body(Body::empty())
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_coalesce_identical_requests() {
+        let counter = Arc::new(AtomicUsize::new(0));
+        let svc = CoalesceLayer::new().into_layer(slow_service(counter.clone()));
+
+        let (a, b, c) = tokio::join!(
+            svc.serve(Context::default(), request(Method::GET, "http://a/x")),
+            svc.serve(Context::default(), request(Method::GET, "http://a/x")),
+            svc.serve(Context::default(), request(Method::GET, "http://a/x")),
+        );
+        for resp in [a, b, c] {
+            assert_eq!(resp.unwrap().try_into_string().await.unwrap(), "1");
+        }
+        assert_eq!(counter.load(Ordering::SeqCst), 1);
+        assert!(svc.flights.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_coalesce_distinct_requests() {
+        let counter = Arc::new(AtomicUsize::new(0));
+        let svc = CoalesceLayer::new().into_layer(slow_service(counter.clone()));
+
+        let (a, b, c) = tokio::join!(
+            svc.serve(Context::default(), request(Method::GET, "http://a/x")),
+            svc.serve(Context::default(), request(Method::GET, "http://a/y")),
+            svc.serve(Context::default(), request(Method::POST, "http://a/x")),
+        );
+        assert!(a.is_ok() && b.is_ok() && c.is_ok());
+        assert_eq!(counter.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_coalesce_skips_requests_with_credentials() {
+        let counter = Arc::new(AtomicUsize::new(0));
+        let svc = CoalesceLayer::new().into_layer(slow_service(counter.clone()));
+
+        let request = |name, value| {
+            Request::builder()
+                .uri("http://a/x")
+                .header(name, value)
+                .body(Body::empty())
+                .unwrap()
+        };
+        let (a, b, c) = tokio::join!(
+            svc.serve(
+                Context::default(),
+                request(header::AUTHORIZATION, "Bearer a")
+            ),
+            svc.serve(
+                Context::default(),
+                request(header::AUTHORIZATION, "Bearer b")
+            ),
+            svc.serve(Context::default(), request(header::COOKIE, "session=c")),
+        );
+        assert!(a.is_ok() && b.is_ok() && c.is_ok());
+        assert_eq!(counter.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_coalesce_skips_unshareable_responses() {
+        for (name, value) in [
+            (header::SET_COOKIE, "session=1"),
+            (header::CACHE_CONTROL, "private"),
+            (header::VARY, "accept-language"),
+        ] {
+            let counter = Arc::new(AtomicUsize::new(0));
+            let svc_counter = counter.clone();
+            let svc = CoalesceLayer::new().into_layer(service_fn(move |_: Request| {
+                let counter = svc_counter.clone();
+                let name = name.clone();
+                async move {
+                    counter.fetch_add(1, Ordering::SeqCst);
+                    tokio::time::sleep(Duration::from_millis(50)).await;
+                    Ok::<_, Infallible>(
+                        Response::builder()
+                            .header(name, value)
+                            .body(Body::empty())
+                            .unwrap(),
+                    )
+                }
+            }));
+
+            let request = |lang| {
+                Request::builder()
+                    .uri("http://a/x")
+                    .header(header::ACCEPT_LANGUAGE, lang)
+                    .body(Body::empty())
+                    .unwrap()
+            };
+            let (a, b) = tokio::join!(
+                svc.serve(Context::default(), request("en")),
+                svc.serve(Context::default(), request("nl")),
+            );
+            assert!(a.is_ok() && b.is_ok());
+            assert_eq!(counter.load(Ordering::SeqCst), 2, "{name}");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_coalesce_skips_large_responses() {
+        let counter = Arc::new(AtomicUsize::new(0));
+        let svc = CoalesceLayer::new()
+            .with_max_body_size(0)
+            .into_layer(slow_service(counter.clone()));
+
+        let (a, b) = tokio::join!(
+            svc.serve(Context::default(), request(Method::GET, "http://a/x")),
+            svc.serve(Context::default(), request(Method::GET, "http://a/x")),
+        );
+        assert_eq!(a.unwrap().try_into_string().await.unwrap(), "1");
+        assert_eq!(b.unwrap().try_into_string().await.unwrap(), "2");
+        assert_eq!(counter.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_coalesce_cancelled_leader() {
+        let counter = Arc::new(AtomicUsize::new(0));
+        let svc = CoalesceLayer::new().into_layer(slow_service(counter.clone()));
+
+        let leader = tokio::time::timeout(
+            Duration::from_millis(20),
+            svc.serve(Context::default(), request(Method::GET, "http://a/x")),
+        );
+        let waiter = async {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            svc.serve(Context::default(), request(Method::GET, "http://a/x"))
+                .await
+        };
+        let (leader, waiter) = tokio::join!(leader, waiter);
+        assert!(leader.is_err());
+        assert_eq!(waiter.unwrap().try_into_string().await.unwrap(), "2");
+        assert_eq!(counter.load(Ordering::SeqCst), 2);
+    }
+}
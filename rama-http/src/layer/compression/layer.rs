@@ -1,4 +1,5 @@
 use super::predicate::DefaultPredicate;
+use super::service::EncodingQualities;
 use super::{Compression, Predicate};
 use crate::headers::encoding::AcceptEncoding;
 use crate::layer::util::compression::CompressionLevel;
@@ -14,7 +15,7 @@ use rama_core::Layer;
 pub struct CompressionLayer<P = DefaultPredicate> {
     accept: AcceptEncoding,
     predicate: P,
-    quality: CompressionLevel,
+    quality: EncodingQualities,
 }
 
 impl<S, P> Layer<S> for CompressionLayer<P>
@@ -102,15 +103,88 @@ impl CompressionLayer {
     }
 
     /// Sets the compression quality.
+    ///
+    /// Used for all encodings which do not have their own quality configured.
     #[must_use]
     pub fn quality(mut self, quality: CompressionLevel) -> Self {
-        self.quality = quality;
+        self.quality.default = quality;
         self
     }
 
     /// Sets the compression quality.
+    ///
+    /// Used for all encodings which do not have their own quality configured.
     pub fn set_quality(&mut self, quality: CompressionLevel) -> &mut Self {
-        self.quality = quality;
+        self.quality.default = quality;
+        self
+    }
+
+    /// Sets the compression quality used for the gzip encoding.
+    #[must_use]
+    pub fn gzip_quality(mut self, quality: CompressionLevel) -> Self {
+        self.quality.gzip = Some(quality);
+        self
+    }
+
+    /// Sets the compression quality used for the gzip encoding.
+    pub fn set_gzip_quality(&mut self, quality: CompressionLevel) -> &mut Self {
+        self.quality.gzip = Some(quality);
+        self
+    }
+
+    /// Sets the compression quality used for the Deflate encoding.
+    #[must_use]
+    pub fn deflate_quality(mut self, quality: CompressionLevel) -> Self {
+        self.quality.deflate = Some(quality);
+        self
+    }
+
+    /// Sets the compression quality used for the Deflate encoding.
+    pub fn set_deflate_quality(&mut self, quality: CompressionLevel) -> &mut Self {
+        self.quality.deflate = Some(quality);
+        self
+    }
+
+    /// Sets the compression quality used for the Brotli encoding.
+    #[must_use]
+    pub fn br_quality(mut self, quality: CompressionLevel) -> Self {
+        self.quality.br = Some(quality);
+        self
+    }
+
+    /// Sets the compression quality used for the Brotli encoding.
+    pub fn set_br_quality(&mut self, quality: CompressionLevel) -> &mut Self {
+        self.quality.br = Some(quality);
+        self
+    }
+
+    /// Sets the compression quality used for the Zstd encoding.
+    #[must_use]
+    pub fn zstd_quality(mut self, quality: CompressionLevel) -> Self {
+        self.quality.zstd = Some(quality);
+        self
+    }
+
+    /// Sets the compression quality used for the Zstd encoding.
+    pub fn set_zstd_quality(&mut self, quality: CompressionLevel) -> &mut Self {
+        self.quality.zstd = Some(quality);
+        self
+    }
+
+    /// Only compress responses of at least `min_size_bytes`.
+    ///
+    /// See [`DefaultPredicate::min_size`] for more details.
+    #[must_use]
+    pub fn min_size(mut self, min_size_bytes: u16) -> Self {
+        self.predicate.set_min_size(min_size_bytes);
+        self
+    }
+
+    /// Only compress responses of at least `min_size_bytes`.
+    ///
+    /// See [`DefaultPredicate::min_size`] for more details.
+    pub fn set_min_size(&mut self, min_size_bytes: u16) -> &mut Self {
+        self.predicate.set_min_size(min_size_bytes);
         self
     }
 
@@ -431,6 +431,126 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn compress_with_encoding_quality() {
+        const DATA: &str = "Check compression quality level! Check compression quality level! Check compression quality level!";
+
+        let svc = service_fn(async |_| {
+            let resp = Response::builder()
+                .body(Body::from(DATA.as_bytes()))
+                .unwrap();
+            Ok::<_, std::io::Error>(resp)
+        });
+
+        let svc = Compression::new(svc)
+            .quality(CompressionLevel::Fastest)
+            .br_quality(CompressionLevel::Best);
+
+        let req = Request::builder()
+            .header("accept-encoding", "br")
+            .body(Body::empty())
+            .unwrap();
+        let res = svc.serve(Context::default(), req).await.unwrap();
+        let compressed_data = res.into_body().collect().await.unwrap().to_bytes();
+
+        let compressed_with_level = {
+            use async_compression::tokio::bufread::BrotliEncoder;
+
+            let stream = Box::pin(rama_core::futures::stream::once(async {
+                Ok::<_, std::io::Error>(DATA.as_bytes())
+            }));
+            let reader = StreamReader::new(stream);
+            let mut enc = BrotliEncoder::with_quality(
+                reader,
+                CompressionLevel::Best.into_async_compression(),
+            );
+
+            let mut buf = Vec::new();
+            enc.read_to_end(&mut buf).await.unwrap();
+            buf
+        };
+
+        assert_eq!(
+            compressed_data,
+            compressed_with_level.as_slice(),
+            "Brotli compression level is not respected"
+        );
+    }
+
+    #[tokio::test]
+    async fn doesnt_compress_below_min_size_or_compressed_content() {
+        async fn handle(req: Request<Body>) -> Result<Response<Body>, Infallible> {
+            let mut res = Response::new(Body::from("a".repeat(512)));
+            if let Some(content_type) = req.headers().get("x-content-type") {
+                res.headers_mut().insert(CONTENT_TYPE, content_type.clone());
+            }
+            Ok(res)
+        }
+
+        let svc = Compression::new(service_fn(handle)).min_size(1024);
+        let req = Request::builder()
+            .header(ACCEPT_ENCODING, "gzip")
+            .body(Body::empty())
+            .unwrap();
+        let res = svc.serve(Context::default(), req).await.unwrap();
+        assert!(res.headers().get(CONTENT_ENCODING).is_none());
+
+        let svc = Compression::new(service_fn(handle)).min_size(256);
+        for (content_type, compressed) in [
+            ("text/html", true),
+            ("video/mp4", false),
+            ("font/woff2", false),
+            ("application/zip", false),
+        ] {
+            let req = Request::builder()
+                .header(ACCEPT_ENCODING, "gzip")
+                .header("x-content-type", content_type)
+                .body(Body::empty())
+                .unwrap();
+            let res = svc.serve(Context::default(), req).await.unwrap();
+            assert_eq!(
+                res.headers().contains_key(CONTENT_ENCODING),
+                compressed,
+                "{content_type}"
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn compress_for_content_type() {
+        use predicate::ForContentType;
+
+        async fn handle(req: Request<Body>) -> Result<Response<Body>, Infallible> {
+            let mut res = Response::new(Body::from("a".repeat(512)));
+            if let Some(content_type) = req.headers().get("x-content-type") {
+                res.headers_mut().insert(CONTENT_TYPE, content_type.clone());
+            }
+            Ok(res)
+        }
+
+        let svc = Compression::new(service_fn(handle)).compress_when(ForContentType::TEXT);
+        for (content_type, compressed) in [
+            (Some("text/plain; charset=utf-8"), true),
+            (Some("application/json"), true),
+            (Some("application/octet-stream"), false),
+            (None, false),
+        ] {
+            let mut req = Request::builder().header(ACCEPT_ENCODING, "gzip");
+            if let Some(content_type) = content_type {
+                req = req.header("x-content-type", content_type);
+            }
+            let res = svc
+                .serve(Context::default(), req.body(Body::empty()).unwrap())
+                .await
+                .unwrap();
+            assert_eq!(
+                res.headers().contains_key(CONTENT_ENCODING),
+                compressed,
+                "{content_type:?}"
+            );
+        }
+    }
+
     #[tokio::test]
     async fn should_not_compress_ranges() {
         let svc = service_fn(async |_| {
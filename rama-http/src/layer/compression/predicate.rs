@@ -91,12 +91,13 @@ where
 /// - They're gRPC, which has its own protocol specific compression scheme.
 /// - It's an image as determined by the `content-type` starting with `image/`.
 /// - They're Server-Sent Events (SSE) as determined by the `content-type` being `text/event-stream`.
-/// - The response is less than 32 bytes.
+/// - Their content is already compressed, as determined by [`NotForCompressedContentType`].
+/// - The response is less than 32 bytes, which can be configured using [`DefaultPredicate::min_size`].
 ///
 /// # Configuring the defaults
 ///
-/// `DefaultPredicate` doesn't support any configuration. Instead you can build your own predicate
-/// by combining types in this module:
+/// Besides its minimum size, `DefaultPredicate` doesn't support any configuration.
+/// Instead you can build your own predicate by combining types in this module:
 ///
 /// ```rust
 /// use rama_http::layer::compression::predicate::{SizeAbove, NotForContentType, Predicate};
@@ -114,19 +115,43 @@ where
 /// [`Compression`]: super::Compression
 /// [`CompressionLayer`]: super::CompressionLayer
 #[derive(Debug, Clone)]
-pub struct DefaultPredicate(
-    And<And<And<SizeAbove, NotForContentType>, NotForContentType>, NotForContentType>,
-);
+pub struct DefaultPredicate {
+    size: SizeAbove,
+    content_type: And<
+        And<And<NotForContentType, NotForContentType>, NotForContentType>,
+        NotForCompressedContentType,
+    >,
+}
 
 impl DefaultPredicate {
     /// Create a new `DefaultPredicate`.
     #[must_use]
     pub fn new() -> Self {
-        let inner = SizeAbove::new(SizeAbove::DEFAULT_MIN_SIZE)
-            .and(NotForContentType::GRPC)
+        let content_type = NotForContentType::GRPC
             .and(NotForContentType::IMAGES)
-            .and(NotForContentType::SSE);
-        Self(inner)
+            .and(NotForContentType::SSE)
+            .and(NotForCompressedContentType);
+        Self {
+            size: SizeAbove::new(SizeAbove::DEFAULT_MIN_SIZE),
+            content_type,
+        }
+    }
+
+    /// Only compress responses of at least `min_size_bytes`.
+    ///
+    /// See [`SizeAbove`] for more details.
+    #[must_use]
+    pub fn min_size(mut self, min_size_bytes: u16) -> Self {
+        self.size = SizeAbove::new(min_size_bytes);
+        self
+    }
+
+    /// Only compress responses of at least `min_size_bytes`.
+    ///
+    /// See [`SizeAbove`] for more details.
+    pub fn set_min_size(&mut self, min_size_bytes: u16) -> &mut Self {
+        self.size = SizeAbove::new(min_size_bytes);
+        self
     }
 }
 
@@ -141,7 +166,7 @@ impl Predicate for DefaultPredicate {
     where
         B: Body,
     {
-        self.0.should_compress(response)
+        self.size.should_compress(response) && self.content_type.should_compress(response)
     }
 }
 
@@ -243,6 +268,113 @@ impl Predicate for NotForContentType {
     }
 }
 
+/// Predicate that only allows responses with one of the given `content-type`s to be compressed.
+///
+/// Each content type matches as a prefix, such that e.g. `text/` matches all textual responses.
+/// Responses without `content-type` are never compressed.
+///
+/// ```rust
+/// use rama_http::layer::compression::predicate::{DefaultPredicate, ForContentType, Predicate};
+///
+/// let predicate = DefaultPredicate::new()
+///     .min_size(1024)
+///     .and(ForContentType::const_new(&["text/", "application/json"]));
+/// ```
+#[derive(Clone, Debug)]
+pub struct ForContentType {
+    content_types: StrList,
+}
+
+impl ForContentType {
+    /// Predicate that only compresses textual responses, such as
+    /// html, css, javascript, json and xml, which compress well.
+    pub const TEXT: Self = Self::const_new(&[
+        "text/",
+        "application/json",
+        "application/javascript",
+        "application/xml",
+        "application/wasm",
+        "image/svg+xml",
+    ]);
+
+    /// Create a new `ForContentType`.
+    #[must_use]
+    pub fn new<I, T>(content_types: I) -> Self
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<Arc<str>>,
+    {
+        Self {
+            content_types: StrList::Shared(content_types.into_iter().map(Into::into).collect()),
+        }
+    }
+
+    /// Create a new `ForContentType` from static strings.
+    #[must_use]
+    pub const fn const_new(content_types: &'static [&'static str]) -> Self {
+        Self {
+            content_types: StrList::Static(content_types),
+        }
+    }
+}
+
+impl Predicate for ForContentType {
+    fn should_compress<B>(&self, response: &rama_http_types::Response<B>) -> bool
+    where
+        B: Body,
+    {
+        let content_type = content_type(response);
+        !content_type.is_empty() && self.content_types.any_prefix_of(content_type)
+    }
+}
+
+/// Predicate that wont allow responses with already compressed content to be compressed,
+/// such as video, audio, web fonts and archives, as compressing them again
+/// costs cpu without reducing their size.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct NotForCompressedContentType;
+
+impl NotForCompressedContentType {
+    const CONTENT_TYPES: StrList = StrList::Static(&[
+        "video/",
+        "audio/",
+        "font/woff",
+        "application/zip",
+        "application/gzip",
+        "application/x-gzip",
+        "application/zstd",
+        "application/x-bzip2",
+        "application/x-xz",
+        "application/x-7z-compressed",
+        "application/vnd.rar",
+        "application/x-rar-compressed",
+    ]);
+}
+
+impl Predicate for NotForCompressedContentType {
+    fn should_compress<B>(&self, response: &rama_http_types::Response<B>) -> bool
+    where
+        B: Body,
+    {
+        !Self::CONTENT_TYPES.any_prefix_of(content_type(response))
+    }
+}
+
+#[derive(Clone, Debug)]
+enum StrList {
+    Static(&'static [&'static str]),
+    Shared(Arc<[Arc<str>]>),
+}
+
+impl StrList {
+    fn any_prefix_of(&self, value: &str) -> bool {
+        match self {
+            Self::Static(list) => list.iter().any(|prefix| value.starts_with(prefix)),
+            Self::Shared(list) => list.iter().any(|prefix| value.starts_with(&**prefix)),
+        }
+    }
+}
+
 #[derive(Clone)]
 enum Str {
     Static(&'static str),
@@ -21,7 +21,31 @@ pub struct Compression<S, P = DefaultPredicate> {
     pub(crate) inner: S,
     pub(crate) accept: AcceptEncoding,
     pub(crate) predicate: P,
-    pub(crate) quality: CompressionLevel,
+    pub(crate) quality: EncodingQualities,
+}
+
+/// The [`CompressionLevel`] used per encoding,
+/// falling back to the default level for encodings without an explicit one.
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct EncodingQualities {
+    pub(crate) default: CompressionLevel,
+    pub(crate) gzip: Option<CompressionLevel>,
+    pub(crate) deflate: Option<CompressionLevel>,
+    pub(crate) br: Option<CompressionLevel>,
+    pub(crate) zstd: Option<CompressionLevel>,
+}
+
+impl EncodingQualities {
+    pub(crate) fn for_encoding(&self, encoding: Encoding) -> CompressionLevel {
+        let quality = match encoding {
+            Encoding::Gzip => self.gzip,
+            Encoding::Deflate => self.deflate,
+            Encoding::Brotli => self.br,
+            Encoding::Zstd => self.zstd,
+            Encoding::Identity => None,
+        };
+        quality.unwrap_or(self.default)
+    }
 }
 
 impl<S, P> std::fmt::Debug for Compression<S, P>
@@ -61,9 +85,26 @@ impl<S> Compression<S, DefaultPredicate> {
             inner: service,
             accept: AcceptEncoding::default(),
             predicate: DefaultPredicate::default(),
-            quality: CompressionLevel::default(),
+            quality: EncodingQualities::default(),
         }
     }
+
+    /// Only compress responses of at least `min_size_bytes`.
+    ///
+    /// See [`DefaultPredicate::min_size`] for more details.
+    #[must_use]
+    pub fn min_size(mut self, min_size_bytes: u16) -> Self {
+        self.predicate.set_min_size(min_size_bytes);
+        self
+    }
+
+    /// Only compress responses of at least `min_size_bytes`.
+    ///
+    /// See [`DefaultPredicate::min_size`] for more details.
+    pub fn set_min_size(&mut self, min_size_bytes: u16) -> &mut Self {
+        self.predicate.set_min_size(min_size_bytes);
+        self
+    }
 }
 
 impl<S, P> Compression<S, P> {
@@ -122,15 +163,71 @@ impl<S, P> Compression<S, P> {
     }
 
     /// Sets the compression quality.
+    ///
+    /// Used for all encodings which do not have their own quality configured.
     #[must_use]
     pub fn quality(mut self, quality: CompressionLevel) -> Self {
-        self.quality = quality;
+        self.quality.default = quality;
         self
     }
 
     /// Sets the compression quality.
+    ///
+    /// Used for all encodings which do not have their own quality configured.
     pub fn set_quality(&mut self, quality: CompressionLevel) -> &mut Self {
-        self.quality = quality;
+        self.quality.default = quality;
+        self
+    }
+
+    /// Sets the compression quality used for the gzip encoding.
+    #[must_use]
+    pub fn gzip_quality(mut self, quality: CompressionLevel) -> Self {
+        self.quality.gzip = Some(quality);
+        self
+    }
+
+    /// Sets the compression quality used for the gzip encoding.
+    pub fn set_gzip_quality(&mut self, quality: CompressionLevel) -> &mut Self {
+        self.quality.gzip = Some(quality);
+        self
+    }
+
+    /// Sets the compression quality used for the Deflate encoding.
+    #[must_use]
+    pub fn deflate_quality(mut self, quality: CompressionLevel) -> Self {
+        self.quality.deflate = Some(quality);
+        self
+    }
+
+    /// Sets the compression quality used for the Deflate encoding.
+    pub fn set_deflate_quality(&mut self, quality: CompressionLevel) -> &mut Self {
+        self.quality.deflate = Some(quality);
+        self
+    }
+
+    /// Sets the compression quality used for the Brotli encoding.
+    #[must_use]
+    pub fn br_quality(mut self, quality: CompressionLevel) -> Self {
+        self.quality.br = Some(quality);
+        self
+    }
+
+    /// Sets the compression quality used for the Brotli encoding.
+    pub fn set_br_quality(&mut self, quality: CompressionLevel) -> &mut Self {
+        self.quality.br = Some(quality);
+        self
+    }
+
+    /// Sets the compression quality used for the Zstd encoding.
+    #[must_use]
+    pub fn zstd_quality(mut self, quality: CompressionLevel) -> Self {
+        self.quality.zstd = Some(quality);
+        self
+    }
+
+    /// Sets the compression quality used for the Zstd encoding.
+    pub fn set_zstd_quality(&mut self, quality: CompressionLevel) -> &mut Self {
+        self.quality.zstd = Some(quality);
         self
     }
 
@@ -233,18 +330,22 @@ where
                 ));
             }
 
-            (_, Encoding::Gzip) => {
-                CompressionBody::new(BodyInner::gzip(WrapBody::new(body, self.quality)))
-            }
-            (_, Encoding::Deflate) => {
-                CompressionBody::new(BodyInner::deflate(WrapBody::new(body, self.quality)))
-            }
-            (_, Encoding::Brotli) => {
-                CompressionBody::new(BodyInner::brotli(WrapBody::new(body, self.quality)))
-            }
-            (_, Encoding::Zstd) => {
-                CompressionBody::new(BodyInner::zstd(WrapBody::new(body, self.quality)))
-            }
+            (_, Encoding::Gzip) => CompressionBody::new(BodyInner::gzip(WrapBody::new(
+                body,
+                self.quality.for_encoding(encoding),
+            ))),
+            (_, Encoding::Deflate) => CompressionBody::new(BodyInner::deflate(WrapBody::new(
+                body,
+                self.quality.for_encoding(encoding),
+            ))),
+            (_, Encoding::Brotli) => CompressionBody::new(BodyInner::brotli(WrapBody::new(
+                body,
+                self.quality.for_encoding(encoding),
+            ))),
+            (_, Encoding::Zstd) => CompressionBody::new(BodyInner::zstd(WrapBody::new(
+                body,
+                self.quality.for_encoding(encoding),
+            ))),
             #[allow(unreachable_patterns)]
             (true, _) => {
                 // This should never happen because the `AcceptEncoding` struct which is used to determine
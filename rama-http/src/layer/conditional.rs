@@ -0,0 +1,553 @@
+//! Middleware which evaluates the conditional request headers defined by
+//! [RFC 9110, section 13](https://www.rfc-editor.org/rfc/rfc9110#section-13):
+//! `If-Match`, `If-None-Match`, `If-Modified-Since` and `If-Unmodified-Since`.
+//!
+//! Preconditions are evaluated in the order mandated by the RFC,
+//! against the [`Validators`] (entity tag and last modification date)
+//! of the current representation of the target resource:
+//!
+//! - a failed `If-Match` or `If-Unmodified-Since` precondition
+//!   results in `412 Precondition Failed`;
+//! - a matching `If-None-Match` results in `304 Not Modified` for `GET` and `HEAD`
+//!   requests and `412 Precondition Failed` for all other methods;
+//! - an `If-Modified-Since` precondition, evaluated only for `GET` and `HEAD`
+//!   requests without `If-None-Match`, results in `304 Not Modified`
+//!   in case the resource was not modified since.
+//!
+//! The validators are resolved by a [`ResolveValidators`] implementation,
+//! before the inner service is called, which is required for state-changing methods
+//! (e.g. to prevent lost updates using `If-Match`). In case no validators can be resolved,
+//! the `ETag` and `Last-Modified` headers of successful `GET` and `HEAD` responses
+//! of the inner service are used instead.
+//!
+//! # Example
+//!
+//! ```
+//! use rama_core::{Context, Layer, Service, service::service_fn};
+//! use rama_http::layer::conditional::{ConditionalRequestLayer, Validators};
+//! use rama_http::{Body, Request, Response, StatusCode, header};
+//! use rama_http::dep::http::request::Parts;
+//! use std::convert::Infallible;
+//!
+//! # #[tokio::main]
+//! # async fn main() {
+//! let service = ConditionalRequestLayer::new()
+//!     .with_validators(|_: &Context, _: &Parts| {
+//!         // e.g. lookup the version of the resource in a database
+//!         std::future::ready(Some(Validators::new().with_etag("\"v2\"".parse().unwrap())))
+//!     })
+//!     .into_layer(service_fn(async |_: Request| {
+//!         Ok::<_, Infallible>(Response::new(Body::empty()))
+//!     }));
+//!
+//! let req = Request::put("/document")
+//!     .header(header::IF_MATCH, "\"v1\"")
+//!     .body(Body::from("update"))
+//!     .unwrap();
+//! let resp = service.serve(Context::default(), req).await.unwrap();
+//! assert_eq!(resp.status(), StatusCode::PRECONDITION_FAILED);
+//! # }
+//! ```
+
+use std::{fmt, time::SystemTime};
+
+use crate::dep::http::request::Parts;
+use rama_core::{Context, Layer, Service};
+use rama_http_headers::{
+    ETag, HeaderMapExt, IfMatch, IfModifiedSince, IfNoneMatch, IfUnmodifiedSince, LastModified,
+};
+use rama_http_types::{Body, HeaderMap, Method, Request, Response, StatusCode, header};
+use rama_utils::macros::define_inner_service_accessors;
+
+#[derive(Debug, Clone, Default)]
+/// The validators of the current representation of a resource,
+/// against which the preconditions of a request are evaluated.
+pub struct Validators {
+    etag: Option<ETag>,
+    last_modified: Option<SystemTime>,
+}
+
+impl Validators {
+    /// Create new [`Validators`], without entity tag or last modification date.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Set the entity tag of the current representation.
+        pub fn etag(mut self, etag: ETag) -> Self {
+            self.etag = Some(etag);
+            self
+        }
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Set the last modification date of the current representation.
+        pub fn last_modified(mut self, last_modified: SystemTime) -> Self {
+            self.last_modified = Some(last_modified);
+            self
+        }
+    }
+
+    fn from_headers(headers: &HeaderMap) -> Self {
+        Self {
+            etag: headers.typed_get(),
+            last_modified: headers.typed_get::<LastModified>().map(Into::into),
+        }
+    }
+
+    fn insert_into(&self, headers: &mut HeaderMap) {
+        if let Some(etag) = &self.etag {
+            headers.typed_insert(etag.clone());
+        }
+        if let Some(last_modified) = self.last_modified {
+            headers.typed_insert(LastModified::from(last_modified));
+        }
+    }
+}
+
+/// Resolve the [`Validators`] of the resource targeted by a request.
+///
+/// Implemented for closures taking the [`Context`] and request [`Parts`],
+/// returning a future which resolves to the optional [`Validators`].
+/// The unit type `()` resolves no validators, in which case the validators
+/// of the responses of the inner service are used.
+pub trait ResolveValidators: Send + Sync + 'static {
+    /// Resolve the validators of the resource targeted by the request,
+    /// returning `None` in case they are not known (e.g. the resource does not exist).
+    fn resolve_validators(
+        &self,
+        ctx: &Context,
+        parts: &Parts,
+    ) -> impl Future<Output = Option<Validators>> + Send;
+}
+
+impl ResolveValidators for () {
+    async fn resolve_validators(&self, _ctx: &Context, _parts: &Parts) -> Option<Validators> {
+        None
+    }
+}
+
+impl<F, Fut> ResolveValidators for F
+where
+    F: Fn(&Context, &Parts) -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = Option<Validators>> + Send,
+{
+    fn resolve_validators(
+        &self,
+        ctx: &Context,
+        parts: &Parts,
+    ) -> impl Future<Output = Option<Validators>> + Send {
+        (self)(ctx, parts)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Outcome {
+    Pass,
+    NotModified,
+    PreconditionFailed,
+}
+
+#[derive(Debug, Default)]
+struct Preconditions {
+    if_match: Option<IfMatch>,
+    if_unmodified_since: Option<IfUnmodifiedSince>,
+    if_none_match: Option<IfNoneMatch>,
+    if_modified_since: Option<IfModifiedSince>,
+}
+
+impl Preconditions {
+    fn from_headers(headers: &HeaderMap) -> Self {
+        Self {
+            if_match: headers.typed_get(),
+            if_unmodified_since: headers.typed_get(),
+            if_none_match: headers.typed_get(),
+            if_modified_since: headers.typed_get(),
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.if_match.is_none()
+            && self.if_unmodified_since.is_none()
+            && self.if_none_match.is_none()
+            && self.if_modified_since.is_none()
+    }
+
+    /// Evaluate the preconditions as defined in
+    /// [RFC 9110, section 13.2.2](https://www.rfc-editor.org/rfc/rfc9110#section-13.2.2).
+    fn evaluate(&self, validators: &Validators, safe: bool) -> Outcome {
+        if let Some(if_match) = &self.if_match {
+            let passes = if_match.is_any()
+                || validators
+                    .etag
+                    .as_ref()
+                    .is_some_and(|etag| if_match.precondition_passes(etag));
+            if !passes {
+                return Outcome::PreconditionFailed;
+            }
+        } else if let Some(if_unmodified_since) = &self.if_unmodified_since {
+            let passes = validators
+                .last_modified
+                .is_none_or(|last_modified| if_unmodified_since.precondition_passes(last_modified));
+            if !passes {
+                return Outcome::PreconditionFailed;
+            }
+        }
+
+        if let Some(if_none_match) = &self.if_none_match {
+            let matches = *if_none_match == IfNoneMatch::any()
+                || validators
+                    .etag
+                    .as_ref()
+                    .is_some_and(|etag| !if_none_match.precondition_passes(etag));
+            if matches {
+                return if safe {
+                    Outcome::NotModified
+                } else {
+                    Outcome::PreconditionFailed
+                };
+            }
+        } else if let Some(if_modified_since) = self.if_modified_since.as_ref().filter(|_| safe) {
+            let modified = validators
+                .last_modified
+                .is_none_or(|last_modified| if_modified_since.is_modified(last_modified));
+            if !modified {
+                return Outcome::NotModified;
+            }
+        }
+
+        Outcome::Pass
+    }
+}
+
+/// Layer that applies the [`ConditionalRequest`] middleware.
+///
+/// See the [module docs](self) for more information.
+#[derive(Debug, Clone)]
+pub struct ConditionalRequestLayer<V = ()> {
+    validators: V,
+}
+
+impl Default for ConditionalRequestLayer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ConditionalRequestLayer {
+    /// Create a new [`ConditionalRequestLayer`],
+    /// using the validators of the responses of the inner service.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self { validators: () }
+    }
+}
+
+impl<V> ConditionalRequestLayer<V> {
+    /// Resolve the validators of the targeted resource using the given [`ResolveValidators`],
+    /// before the inner service is called.
+    pub fn with_validators<V2>(self, validators: V2) -> ConditionalRequestLayer<V2> {
+        ConditionalRequestLayer { validators }
+    }
+}
+
+impl<S, V: Clone> Layer<S> for ConditionalRequestLayer<V> {
+    type Service = ConditionalRequest<S, V>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        ConditionalRequest {
+            inner,
+            validators: self.validators.clone(),
+        }
+    }
+
+    fn into_layer(self, inner: S) -> Self::Service {
+        ConditionalRequest {
+            inner,
+            validators: self.validators,
+        }
+    }
+}
+
+/// Middleware which evaluates the conditional request headers of requests.
+///
+/// See the [module docs](self) for more information.
+pub struct ConditionalRequest<S, V = ()> {
+    inner: S,
+    validators: V,
+}
+
+impl<S> ConditionalRequest<S> {
+    /// Create a new [`ConditionalRequest`],
+    /// using the validators of the responses of the inner service.
+    pub const fn new(inner: S) -> Self {
+        Self {
+            inner,
+            validators: (),
+        }
+    }
+}
+
+impl<S, V> ConditionalRequest<S, V> {
+    /// Resolve the validators of the targeted resource using the given [`ResolveValidators`],
+    /// before the inner service is called.
+    pub fn with_validators<V2>(self, validators: V2) -> ConditionalRequest<S, V2> {
+        ConditionalRequest {
+            inner: self.inner,
+            validators,
+        }
+    }
+
+    define_inner_service_accessors!();
+}
+
+impl<S: fmt::Debug, V: fmt::Debug> fmt::Debug for ConditionalRequest<S, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ConditionalRequest")
+            .field("inner", &self.inner)
+            .field("validators", &self.validators)
+            .finish()
+    }
+}
+
+impl<S: Clone, V: Clone> Clone for ConditionalRequest<S, V> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            validators: self.validators.clone(),
+        }
+    }
+}
+
+impl<S, V, ReqBody> Service<Request<ReqBody>> for ConditionalRequest<S, V>
+where
+    S: Service<Request<ReqBody>, Response = Response>,
+    V: ResolveValidators,
+    ReqBody: Send + 'static,
+{
+    type Response = Response;
+    type Error = S::Error;
+
+    async fn serve(
+        &self,
+        ctx: Context,
+        req: Request<ReqBody>,
+    ) -> Result<Self::Response, Self::Error> {
+        let preconditions = Preconditions::from_headers(req.headers());
+        if preconditions.is_empty() {
+            return self.inner.serve(ctx, req).await;
+        }
+        let safe = req.method() == Method::GET || req.method() == Method::HEAD;
+
+        let (parts, body) = req.into_parts();
+        if let Some(validators) = self.validators.resolve_validators(&ctx, &parts).await {
+            return match preconditions.evaluate(&validators, safe) {
+                Outcome::Pass => {
+                    self.inner
+                        .serve(ctx, Request::from_parts(parts, body))
+                        .await
+                }
+                outcome => {
+                    let mut headers = HeaderMap::new();
+                    validators.insert_into(&mut headers);
+                    Ok(empty_response(outcome, headers))
+                }
+            };
+        }
+
+        let resp = self
+            .inner
+            .serve(ctx, Request::from_parts(parts, body))
+            .await?;
+        if !safe || !resp.status().is_success() {
+            return Ok(resp);
+        }
+
+        let validators = Validators::from_headers(resp.headers());
+        match preconditions.evaluate(&validators, safe) {
+            Outcome::Pass => Ok(resp),
+            outcome => {
+                let (mut parts, _) = resp.into_parts();
+                parts.headers.remove(header::CONTENT_LENGTH);
+                Ok(empty_response(outcome, parts.headers))
+            }
+        }
+    }
+}
+
+fn empty_response(outcome: Outcome, headers: HeaderMap) -> Response {
+    let mut resp = Response::new(Body::empty());
+    *resp.status_mut() = match outcome {
+        Outcome::NotModified => StatusCode::NOT_MODIFIED,
+        _ => StatusCode::PRECONDITION_FAILED,
+    };
+    *resp.headers_mut() = headers;
+    resp
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rama_core::service::service_fn;
+    use std::{convert::Infallible, time::Duration};
+
+    fn etag(value: &str) -> ETag {
+        value.parse().unwrap()
+    }
+
+    #[test]
+    fn test_preconditions_evaluate() {
+        let now = SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let validators = Validators::new()
+            .with_etag(etag("\"v2\""))
+            .with_last_modified(now);
+
+        for (headers, safe, expected) in [
+            (vec![], true, Outcome::Pass),
+            (vec![(header::IF_MATCH, "\"v2\"")], false, Outcome::Pass),
+            (vec![(header::IF_MATCH, "*")], false, Outcome::Pass),
+            (
+                vec![(header::IF_MATCH, "\"v1\"")],
+                false,
+                Outcome::PreconditionFailed,
+            ),
+            (
+                vec![(header::IF_MATCH, "W/\"v2\"")],
+                false,
+                Outcome::PreconditionFailed,
+            ),
+            (
+                vec![(header::IF_UNMODIFIED_SINCE, "Tue, 14 Nov 2023 22:13:20 GMT")],
+                false,
+                Outcome::Pass,
+            ),
+            (
+                vec![(header::IF_UNMODIFIED_SINCE, "Tue, 14 Nov 2023 22:13:19 GMT")],
+                false,
+                Outcome::PreconditionFailed,
+            ),
+            // If-Unmodified-Since is ignored when If-Match is present
+            (
+                vec![
+                    (header::IF_MATCH, "\"v2\""),
+                    (header::IF_UNMODIFIED_SINCE, "Tue, 14 Nov 2023 22:13:19 GMT"),
+                ],
+                false,
+                Outcome::Pass,
+            ),
+            (
+                vec![(header::IF_NONE_MATCH, "W/\"v2\"")],
+                true,
+                Outcome::NotModified,
+            ),
+            (
+                vec![(header::IF_NONE_MATCH, "*")],
+                false,
+                Outcome::PreconditionFailed,
+            ),
+            (vec![(header::IF_NONE_MATCH, "\"v1\"")], true, Outcome::Pass),
+            (
+                vec![(header::IF_MODIFIED_SINCE, "Tue, 14 Nov 2023 22:13:20 GMT")],
+                true,
+                Outcome::NotModified,
+            ),
+            (
+                vec![(header::IF_MODIFIED_SINCE, "Tue, 14 Nov 2023 22:13:19 GMT")],
+                true,
+                Outcome::Pass,
+            ),
+            // If-Modified-Since is only evaluated for GET and HEAD
+            (
+                vec![(header::IF_MODIFIED_SINCE, "Tue, 14 Nov 2023 22:13:20 GMT")],
+                false,
+                Outcome::Pass,
+            ),
+            // If-Modified-Since is ignored when If-None-Match is present
+            (
+                vec![
+                    (header::IF_NONE_MATCH, "\"v1\""),
+                    (header::IF_MODIFIED_SINCE, "Tue, 14 Nov 2023 22:13:20 GMT"),
+                ],
+                true,
+                Outcome::Pass,
+            ),
+        ] {
+            let mut map = HeaderMap::new();
+            for (name, value) in &headers {
+                map.insert(name, value.parse().unwrap());
+            }
+            assert_eq!(
+                Preconditions::from_headers(&map).evaluate(&validators, safe),
+                expected,
+                "{headers:?} (safe: {safe})"
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_conditional_request_resolved_validators() {
+        let service = ConditionalRequestLayer::new()
+            .with_validators(|_: &Context, parts: &Parts| {
+                std::future::ready(
+                    (parts.uri.path() == "/document")
+                        .then(|| Validators::new().with_etag(etag("\"v2\""))),
+                )
+            })
+            .into_layer(service_fn(async |req: Request| {
+                assert_ne!(req.method(), Method::DELETE, "precondition should fail");
+                Ok::<_, Infallible>(Response::new(Body::from("document")))
+            }));
+
+        let req = Request::delete("/document")
+            .header(header::IF_MATCH, "\"v1\"")
+            .body(Body::empty())
+            .unwrap();
+        let resp = service.serve(Context::default(), req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::PRECONDITION_FAILED);
+        assert_eq!(resp.headers()[header::ETAG], "\"v2\"");
+
+        let req = Request::get("/document")
+            .header(header::IF_NONE_MATCH, "\"v2\"")
+            .body(Body::empty())
+            .unwrap();
+        let resp = service.serve(Context::default(), req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::NOT_MODIFIED);
+
+        let req = Request::put("/document")
+            .header(header::IF_MATCH, "\"v2\"")
+            .body(Body::empty())
+            .unwrap();
+        let resp = service.serve(Context::default(), req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_conditional_request_response_validators() {
+        let service = ConditionalRequestLayer::new().into_layer(service_fn(async |_: Request| {
+            Ok::<_, Infallible>(
+                Response::builder()
+                    .header(header::LAST_MODIFIED, "Tue, 14 Nov 2023 22:13:20 GMT")
+                    .header(header::CACHE_CONTROL, "max-age=60")
+                    .body(Body::from("report"))
+                    .unwrap(),
+            )
+        }));
+
+        let req = Request::get("/report")
+            .header(header::IF_MODIFIED_SINCE, "Tue, 14 Nov 2023 22:13:20 GMT")
+            .body(Body::empty())
+            .unwrap();
+        let resp = service.serve(Context::default(), req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::NOT_MODIFIED);
+        assert_eq!(resp.headers()[header::CACHE_CONTROL], "max-age=60");
+
+        let req = Request::get("/report")
+            .header(header::IF_MODIFIED_SINCE, "Mon, 13 Nov 2023 22:13:20 GMT")
+            .body(Body::empty())
+            .unwrap();
+        let resp = service.serve(Context::default(), req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+    }
+}
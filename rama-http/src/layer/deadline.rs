@@ -0,0 +1,385 @@
+//! Middleware to propagate the [`Deadline`] of a request to upstream services via a header.
+//!
+//! The [`Deadline`] is sent as the number of milliseconds remaining,
+//! in the [`DEFAULT_DEADLINE_HEADER`] header by default, such that it is
+//! not affected by clock differences between the services.
+//!
+//! - [`SetDeadlineHeaderLayer`] is used by clients to add the time remaining until
+//!   the [`Deadline`] found in the [`Context`] (e.g. inserted by a timeout layer)
+//!   to outgoing requests, refusing to send requests of which the deadline expired.
+//! - [`DeadlineFromHeaderLayer`] is used by servers to insert the [`Deadline`]
+//!   received from the downstream client into the [`Context`], optionally capped
+//!   to a maximum, such that nested calls are limited to the caller's budget.
+//!
+//! # Example
+//!
+//! ```
+//! use rama_core::{Context, Layer, Service, context::Deadline, service::service_fn};
+//! use rama_http::layer::deadline::{DEFAULT_DEADLINE_HEADER, SetDeadlineHeaderLayer};
+//! use rama_http::{Body, Request, Response};
+//! use std::{convert::Infallible, time::Duration};
+//!
+//! # #[tokio::main]
+//! # async fn main() {
+//! let client = SetDeadlineHeaderLayer::new().into_layer(service_fn(async |req: Request| {
+//!     let remaining: u64 = req.headers()[DEFAULT_DEADLINE_HEADER]
+//!         .to_str()
+//!         .unwrap()
+//!         .parse()
+//!         .unwrap();
+//!     assert!(remaining <= 1000);
+//!     Ok::<_, Infallible>(Response::new(Body::empty()))
+//! }));
+//!
+//! let mut ctx = Context::default();
+//! Deadline::after(Duration::from_secs(1)).apply(&mut ctx);
+//!
+//! let req = Request::get("http://example.com").body(Body::empty()).unwrap();
+//! client.serve(ctx, req).await.unwrap();
+//! # }
+//! ```
+
+use std::{fmt, time::Duration};
+
+use rama_core::{
+    Context, Layer, Service,
+    context::{Deadline, DeadlineExceeded},
+    error::BoxError,
+    telemetry::tracing,
+};
+use rama_http_types::{HeaderName, HeaderValue, Request};
+use rama_utils::macros::define_inner_service_accessors;
+
+/// The default header used to propagate the [`Deadline`] of a request,
+/// containing the number of milliseconds remaining.
+pub const DEFAULT_DEADLINE_HEADER: HeaderName = HeaderName::from_static("x-request-timeout-ms");
+
+/// Layer that applies the [`SetDeadlineHeader`] middleware.
+///
+/// See the [module docs](self) for more information.
+#[derive(Debug, Clone)]
+pub struct SetDeadlineHeaderLayer {
+    header_name: HeaderName,
+}
+
+impl Default for SetDeadlineHeaderLayer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SetDeadlineHeaderLayer {
+    /// Create a new [`SetDeadlineHeaderLayer`], using the [`DEFAULT_DEADLINE_HEADER`].
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            header_name: DEFAULT_DEADLINE_HEADER,
+        }
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Set the header used to propagate the [`Deadline`].
+        pub fn header_name(mut self, name: HeaderName) -> Self {
+            self.header_name = name;
+            self
+        }
+    }
+}
+
+impl<S> Layer<S> for SetDeadlineHeaderLayer {
+    type Service = SetDeadlineHeader<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        SetDeadlineHeader {
+            inner,
+            header_name: self.header_name.clone(),
+        }
+    }
+
+    fn into_layer(self, inner: S) -> Self::Service {
+        SetDeadlineHeader {
+            inner,
+            header_name: self.header_name,
+        }
+    }
+}
+
+/// Middleware which adds the time remaining until the [`Deadline`]
+/// of the request as a header to outgoing requests.
+///
+/// See the [module docs](self) for more information.
+#[derive(Clone)]
+pub struct SetDeadlineHeader<S> {
+    inner: S,
+    header_name: HeaderName,
+}
+
+impl<S: fmt::Debug> fmt::Debug for SetDeadlineHeader<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SetDeadlineHeader")
+            .field("inner", &self.inner)
+            .field("header_name", &self.header_name)
+            .finish()
+    }
+}
+
+impl<S> SetDeadlineHeader<S> {
+    /// Create a new [`SetDeadlineHeader`], using the [`DEFAULT_DEADLINE_HEADER`].
+    pub const fn new(inner: S) -> Self {
+        Self {
+            inner,
+            header_name: DEFAULT_DEADLINE_HEADER,
+        }
+    }
+
+    define_inner_service_accessors!();
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Set the header used to propagate the [`Deadline`].
+        pub fn header_name(mut self, name: HeaderName) -> Self {
+            self.header_name = name;
+            self
+        }
+    }
+}
+
+impl<S, Body> Service<Request<Body>> for SetDeadlineHeader<S>
+where
+    S: Service<Request<Body>, Error: Into<BoxError>>,
+    Body: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = BoxError;
+
+    async fn serve(
+        &self,
+        ctx: Context,
+        mut req: Request<Body>,
+    ) -> Result<Self::Response, Self::Error> {
+        if let Some(deadline) = Deadline::from_ctx(&ctx) {
+            let remaining = deadline.remaining();
+            if remaining.is_zero() {
+                return Err(DeadlineExceeded::new().into());
+            }
+            req.headers_mut().insert(
+                self.header_name.clone(),
+                HeaderValue::from(remaining.as_millis() as u64),
+            );
+        }
+        self.inner.serve(ctx, req).await.map_err(Into::into)
+    }
+}
+
+/// Layer that applies the [`DeadlineFromHeader`] middleware.
+///
+/// See the [module docs](self) for more information.
+#[derive(Debug, Clone)]
+pub struct DeadlineFromHeaderLayer {
+    header_name: HeaderName,
+    max: Option<Duration>,
+}
+
+impl Default for DeadlineFromHeaderLayer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DeadlineFromHeaderLayer {
+    /// Create a new [`DeadlineFromHeaderLayer`], using the [`DEFAULT_DEADLINE_HEADER`].
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            header_name: DEFAULT_DEADLINE_HEADER,
+            max: None,
+        }
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Set the header from which the [`Deadline`] is read.
+        pub fn header_name(mut self, name: HeaderName) -> Self {
+            self.header_name = name;
+            self
+        }
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Cap the time remaining of a received [`Deadline`] to the given maximum,
+        /// such that clients cannot make the server spend more time than intended.
+        pub fn max(mut self, max: Duration) -> Self {
+            self.max = Some(max);
+            self
+        }
+    }
+}
+
+impl<S> Layer<S> for DeadlineFromHeaderLayer {
+    type Service = DeadlineFromHeader<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        DeadlineFromHeader {
+            inner,
+            header_name: self.header_name.clone(),
+            max: self.max,
+        }
+    }
+
+    fn into_layer(self, inner: S) -> Self::Service {
+        DeadlineFromHeader {
+            inner,
+            header_name: self.header_name,
+            max: self.max,
+        }
+    }
+}
+
+/// Middleware which inserts the [`Deadline`] received
+/// via a header of incoming requests into the [`Context`].
+///
+/// See the [module docs](self) for more information.
+#[derive(Clone)]
+pub struct DeadlineFromHeader<S> {
+    inner: S,
+    header_name: HeaderName,
+    max: Option<Duration>,
+}
+
+impl<S: fmt::Debug> fmt::Debug for DeadlineFromHeader<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DeadlineFromHeader")
+            .field("inner", &self.inner)
+            .field("header_name", &self.header_name)
+            .field("max", &self.max)
+            .finish()
+    }
+}
+
+impl<S> DeadlineFromHeader<S> {
+    /// Create a new [`DeadlineFromHeader`], using the [`DEFAULT_DEADLINE_HEADER`].
+    pub const fn new(inner: S) -> Self {
+        Self {
+            inner,
+            header_name: DEFAULT_DEADLINE_HEADER,
+            max: None,
+        }
+    }
+
+    define_inner_service_accessors!();
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Set the header from which the [`Deadline`] is read.
+        pub fn header_name(mut self, name: HeaderName) -> Self {
+            self.header_name = name;
+            self
+        }
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Cap the time remaining of a received [`Deadline`] to the given maximum,
+        /// such that clients cannot make the server spend more time than intended.
+        pub fn max(mut self, max: Duration) -> Self {
+            self.max = Some(max);
+            self
+        }
+    }
+}
+
+impl<S, Body> Service<Request<Body>> for DeadlineFromHeader<S>
+where
+    S: Service<Request<Body>>,
+    Body: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+
+    async fn serve(
+        &self,
+        mut ctx: Context,
+        req: Request<Body>,
+    ) -> Result<Self::Response, Self::Error> {
+        let remaining = req
+            .headers()
+            .get(&self.header_name)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.trim().parse::<u64>().ok())
+            .map(Duration::from_millis);
+        match remaining {
+            Some(remaining) => {
+                let remaining = self.max.map_or(remaining, |max| remaining.min(max));
+                Deadline::after(remaining).apply(&mut ctx);
+            }
+            None => {
+                if req.headers().contains_key(&self.header_name) {
+                    tracing::debug!("ignoring invalid deadline header: {}", self.header_name);
+                }
+                if let Some(max) = self.max {
+                    Deadline::after(max).apply(&mut ctx);
+                }
+            }
+        }
+        self.inner.serve(ctx, req).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rama_core::service::service_fn;
+    use rama_http_types::{Body, BodyExtractExt, Response};
+    use std::convert::Infallible;
+
+    #[tokio::test]
+    async fn test_deadline_round_trip() {
+        let server = DeadlineFromHeaderLayer::new()
+            .with_max(Duration::from_secs(5))
+            .into_layer(service_fn(async |ctx: Context, _: Request| {
+                let remaining = Deadline::from_ctx(&ctx).unwrap().remaining();
+                Ok::<_, Infallible>(Response::new(Body::from(remaining.as_millis().to_string())))
+            }));
+        let client = SetDeadlineHeaderLayer::new().into_layer(server);
+
+        for (timeout, max_expected) in [
+            (Duration::from_secs(1), 1000),
+            (Duration::from_secs(60), 5000),
+        ] {
+            let mut ctx = Context::default();
+            Deadline::after(timeout).apply(&mut ctx);
+            let req = Request::new(Body::empty());
+            let resp = client.serve(ctx, req).await.unwrap();
+            let remaining: u64 = resp.try_into_string().await.unwrap().parse().unwrap();
+            assert!(remaining <= max_expected);
+            assert!(remaining > max_expected - 500);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_deadline_expired_not_sent() {
+        let client = SetDeadlineHeaderLayer::new().into_layer(service_fn(
+            async |_: Request| -> Result<Response, Infallible> {
+                unreachable!("request with expired deadline should not be sent")
+            },
+        ));
+
+        let mut ctx = Context::default();
+        Deadline::at(std::time::Instant::now()).apply(&mut ctx);
+        let err = client
+            .serve(ctx, Request::new(Body::empty()))
+            .await
+            .unwrap_err();
+        assert!(err.downcast_ref::<DeadlineExceeded>().is_some());
+    }
+
+    #[tokio::test]
+    async fn test_deadline_no_header() {
+        let server = DeadlineFromHeaderLayer::new().into_layer(service_fn(
+            async |ctx: Context, _: Request| {
+                assert!(Deadline::from_ctx(&ctx).is_none());
+                Ok::<_, Infallible>(Response::new(Body::empty()))
+            },
+        ));
+        server
+            .serve(Context::default(), Request::new(Body::empty()))
+            .await
+            .unwrap();
+    }
+}
@@ -29,6 +29,8 @@ pin_project! {
     {
         #[pin]
         pub(crate) inner: BodyInner<B>,
+        limit: Option<usize>,
+        decompressed: usize,
     }
 }
 
@@ -41,6 +43,8 @@ where
             inner: BodyInner::Identity {
                 inner: B::default(),
             },
+            limit: None,
+            decompressed: 0,
         }
     }
 }
@@ -50,10 +54,51 @@ where
     B: Body,
 {
     pub(crate) fn new(inner: BodyInner<B>) -> Self {
-        Self { inner }
+        Self {
+            inner,
+            limit: None,
+            decompressed: 0,
+        }
+    }
+
+    /// Fail the body with a [`DecompressionLimitExceeded`] error
+    /// once more than `limit` bytes have been decompressed.
+    ///
+    /// Bodies which are not decompressed are never limited.
+    pub(crate) fn with_limit(mut self, limit: Option<usize>) -> Self {
+        self.limit = limit;
+        self
+    }
+}
+
+/// Error returned by a [`DecompressionBody`] when
+/// its decompressed size exceeds the configured limit,
+/// e.g. in case of a decompression bomb.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DecompressionLimitExceeded {
+    limit: usize,
+}
+
+impl DecompressionLimitExceeded {
+    /// The limit, in bytes, which was exceeded.
+    #[must_use]
+    pub fn limit(&self) -> usize {
+        self.limit
+    }
+}
+
+impl std::fmt::Display for DecompressionLimitExceeded {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "decompressed body limit of {} bytes exceeded",
+            self.limit
+        )
     }
 }
 
+impl std::error::Error for DecompressionLimitExceeded {}
+
 type GzipBody<B> = WrapBody<GzipDecoder<B>>;
 type DeflateBody<B> = WrapBody<ZlibDecoder<B>>;
 type BrotliBody<B> = WrapBody<BrotliDecoder<B>>;
@@ -121,20 +166,31 @@ where
         self: Pin<&mut Self>,
         cx: &mut Context<'_>,
     ) -> Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
-        match self.project().inner.project() {
-            BodyInnerProj::Gzip { inner } => inner.poll_frame(cx),
-            BodyInnerProj::Deflate { inner } => inner.poll_frame(cx),
-            BodyInnerProj::Brotli { inner } => inner.poll_frame(cx),
-            BodyInnerProj::Zstd { inner } => inner.poll_frame(cx),
-            BodyInnerProj::Identity { inner } => match ready!(inner.poll_frame(cx)) {
-                Some(Ok(frame)) => {
-                    let frame = frame.map_data(|mut buf| buf.copy_to_bytes(buf.remaining()));
-                    Poll::Ready(Some(Ok(frame)))
-                }
-                Some(Err(err)) => Poll::Ready(Some(Err(err.into()))),
-                None => Poll::Ready(None),
-            },
+        let this = self.project();
+        let result = match this.inner.project() {
+            BodyInnerProj::Gzip { inner } => ready!(inner.poll_frame(cx)),
+            BodyInnerProj::Deflate { inner } => ready!(inner.poll_frame(cx)),
+            BodyInnerProj::Brotli { inner } => ready!(inner.poll_frame(cx)),
+            BodyInnerProj::Zstd { inner } => ready!(inner.poll_frame(cx)),
+            BodyInnerProj::Identity { inner } => {
+                return match ready!(inner.poll_frame(cx)) {
+                    Some(Ok(frame)) => {
+                        let frame = frame.map_data(|mut buf| buf.copy_to_bytes(buf.remaining()));
+                        Poll::Ready(Some(Ok(frame)))
+                    }
+                    Some(Err(err)) => Poll::Ready(Some(Err(err.into()))),
+                    None => Poll::Ready(None),
+                };
+            }
+        };
+
+        if let (Some(limit), Some(Ok(frame))) = (*this.limit, &result) {
+            *this.decompressed += frame.data_ref().map(Bytes::len).unwrap_or_default();
+            if *this.decompressed > limit {
+                return Poll::Ready(Some(Err(DecompressionLimitExceeded { limit }.into())));
+            }
         }
+        Poll::Ready(result)
     }
 
     fn size_hint(&self) -> SizeHint {
@@ -102,7 +102,11 @@ mod layer;
 mod service;
 
 #[doc(inline)]
-pub use self::{body::DecompressionBody, layer::DecompressionLayer, service::Decompression};
+pub use self::{
+    body::{DecompressionBody, DecompressionLimitExceeded},
+    layer::DecompressionLayer,
+    service::Decompression,
+};
 
 #[doc(inline)]
 pub use self::request::layer::RequestDecompressionLayer;
@@ -17,6 +17,7 @@ use rama_core::Layer;
 pub struct RequestDecompressionLayer {
     accept: AcceptEncoding,
     pass_through_unaccepted: bool,
+    max_decompressed_size: Option<usize>,
 }
 
 impl<S> Layer<S> for RequestDecompressionLayer {
@@ -27,6 +28,7 @@ impl<S> Layer<S> for RequestDecompressionLayer {
             inner: service,
             accept: self.accept,
             pass_through_unaccepted: self.pass_through_unaccepted,
+            max_decompressed_size: self.max_decompressed_size,
         }
     }
 }
@@ -102,4 +104,29 @@ impl RequestDecompressionLayer {
         self.pass_through_unaccepted = enable;
         self
     }
+
+    /// Limits the decompressed size of request bodies to `size` bytes,
+    /// protecting the underlying service against decompression bombs.
+    ///
+    /// Reading a body which exceeds the limit fails with a
+    /// [`DecompressionLimitExceeded`] error. By default no limit is applied.
+    ///
+    /// [`DecompressionLimitExceeded`]: crate::layer::decompression::DecompressionLimitExceeded
+    #[must_use]
+    pub fn max_decompressed_size(mut self, size: usize) -> Self {
+        self.max_decompressed_size = Some(size);
+        self
+    }
+
+    /// Limits the decompressed size of request bodies to `size` bytes,
+    /// protecting the underlying service against decompression bombs.
+    ///
+    /// Reading a body which exceeds the limit fails with a
+    /// [`DecompressionLimitExceeded`] error. By default no limit is applied.
+    ///
+    /// [`DecompressionLimitExceeded`]: crate::layer::decompression::DecompressionLimitExceeded
+    pub fn set_max_decompressed_size(&mut self, size: usize) -> &mut Self {
+        self.max_decompressed_size = Some(size);
+        self
+    }
 }
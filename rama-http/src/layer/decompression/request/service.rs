@@ -29,6 +29,7 @@ pub struct RequestDecompression<S> {
     pub(super) inner: S,
     pub(super) accept: AcceptEncoding,
     pub(super) pass_through_unaccepted: bool,
+    pub(super) max_decompressed_size: Option<usize>,
 }
 
 impl<S: fmt::Debug> fmt::Debug for RequestDecompression<S> {
@@ -37,6 +38,7 @@ impl<S: fmt::Debug> fmt::Debug for RequestDecompression<S> {
             .field("inner", &self.inner)
             .field("accept", &self.accept)
             .field("pass_through_unaccepted", &self.pass_through_unaccepted)
+            .field("max_decompressed_size", &self.max_decompressed_size)
             .finish()
     }
 }
@@ -47,6 +49,7 @@ impl<S: Clone> Clone for RequestDecompression<S> {
             inner: self.inner.clone(),
             accept: self.accept,
             pass_through_unaccepted: self.pass_through_unaccepted,
+            max_decompressed_size: self.max_decompressed_size,
         }
     }
 }
@@ -102,7 +105,7 @@ where
             } else {
                 BodyInner::identity(body)
             };
-        let body = DecompressionBody::new(body);
+        let body = DecompressionBody::new(body).with_limit(self.max_decompressed_size);
         let req = Request::from_parts(parts, body);
         self.inner
             .serve(ctx, req)
@@ -138,6 +141,7 @@ impl<S> RequestDecompression<S> {
             inner: service,
             accept: AcceptEncoding::default(),
             pass_through_unaccepted: false,
+            max_decompressed_size: None,
         }
     }
 
@@ -160,6 +164,31 @@ impl<S> RequestDecompression<S> {
         self
     }
 
+    /// Limits the decompressed size of request bodies to `size` bytes,
+    /// protecting the underlying service against decompression bombs.
+    ///
+    /// Reading a body which exceeds the limit fails with a
+    /// [`DecompressionLimitExceeded`] error. By default no limit is applied.
+    ///
+    /// [`DecompressionLimitExceeded`]: crate::layer::decompression::DecompressionLimitExceeded
+    #[must_use]
+    pub fn max_decompressed_size(mut self, size: usize) -> Self {
+        self.max_decompressed_size = Some(size);
+        self
+    }
+
+    /// Limits the decompressed size of request bodies to `size` bytes,
+    /// protecting the underlying service against decompression bombs.
+    ///
+    /// Reading a body which exceeds the limit fails with a
+    /// [`DecompressionLimitExceeded`] error. By default no limit is applied.
+    ///
+    /// [`DecompressionLimitExceeded`]: crate::layer::decompression::DecompressionLimitExceeded
+    pub fn set_max_decompressed_size(&mut self, size: usize) -> &mut Self {
+        self.max_decompressed_size = Some(size);
+        self
+    }
+
     /// Sets whether to support gzip encoding.
     #[must_use]
     pub fn gzip(mut self, enable: bool) -> Self {
@@ -0,0 +1,328 @@
+//! Middleware which adds an `ETag` to responses and answers
+//! matching `If-None-Match` requests with `304 Not Modified`.
+//!
+//! The [`ETagLayer`] computes the entity tag of successful `GET` and `HEAD` responses
+//! from their body, which is buffered for this purpose. Only bodies of which the size
+//! is known to be at most the configured maximum are buffered, such that
+//! streaming responses (e.g. Server-Sent Events) are passed through as is.
+//!
+//! Responses which already have an `ETag` header (e.g. set by the handler) keep it,
+//! without their body being buffered, but are still answered with
+//! `304 Not Modified` in case the request's `If-None-Match` header matches it.
+//!
+//! # Example
+//!
+//! ```
+//! use rama_core::{Context, Layer, Service, service::service_fn};
+//! use rama_http::layer::etag::ETagLayer;
+//! use rama_http::{Body, Request, Response, StatusCode, header};
+//! use std::convert::Infallible;
+//!
+//! # #[tokio::main]
+//! # async fn main() {
+//! let service = ETagLayer::new().into_layer(service_fn(async |_: Request| {
+//!     Ok::<_, Infallible>(Response::new(Body::from("report")))
+//! }));
+//!
+//! let req = Request::builder().uri("/report").body(Body::empty()).unwrap();
+//! let resp = service.serve(Context::default(), req).await.unwrap();
+//! let etag = resp.headers()[header::ETAG].clone();
+//!
+//! let req = Request::builder()
+//!     .uri("/report")
+//!     .header(header::IF_NONE_MATCH, etag)
+//!     .body(Body::empty())
+//!     .unwrap();
+//! let resp = service.serve(Context::default(), req).await.unwrap();
+//! assert_eq!(resp.status(), StatusCode::NOT_MODIFIED);
+//! # }
+//! ```
+
+use std::fmt;
+
+use crate::dep::http_body::Body as HttpBody;
+use crate::dep::http_body_util::BodyExt;
+use base64::{Engine as _, prelude::BASE64_URL_SAFE_NO_PAD};
+use rama_core::{
+    Context, Layer, Service,
+    bytes::Bytes,
+    error::{BoxError, OpaqueError},
+};
+use rama_crypto::dep::aws_lc_rs::digest;
+use rama_http_headers::{ETag, HeaderMapExt, IfNoneMatch};
+use rama_http_types::{Body, HeaderValue, Method, Request, Response, StatusCode, header};
+use rama_utils::macros::define_inner_service_accessors;
+
+/// The default maximum size of a response body which is buffered to compute its `ETag`.
+pub const DEFAULT_MAX_BODY_SIZE: usize = 1024 * 1024;
+
+/// Layer that applies the [`ETagService`] middleware.
+///
+/// See the [module docs](self) for more information.
+#[derive(Debug, Clone)]
+pub struct ETagLayer {
+    weak: bool,
+    max_body_size: usize,
+}
+
+impl Default for ETagLayer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ETagLayer {
+    /// Create a new [`ETagLayer`], generating strong entity tags.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            weak: false,
+            max_body_size: DEFAULT_MAX_BODY_SIZE,
+        }
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Generate weak (`W/"..."`) instead of strong entity tags.
+        ///
+        /// Weak entity tags should be used when the body might still be transformed
+        /// (e.g. compressed) by a layer which wraps this one.
+        pub fn weak(mut self, weak: bool) -> Self {
+            self.weak = weak;
+            self
+        }
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Set the maximum size of a response body which is buffered to compute its `ETag`.
+        ///
+        /// Defaults to [`DEFAULT_MAX_BODY_SIZE`].
+        pub fn max_body_size(mut self, size: usize) -> Self {
+            self.max_body_size = size;
+            self
+        }
+    }
+}
+
+impl<S> Layer<S> for ETagLayer {
+    type Service = ETagService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        ETagService {
+            inner,
+            weak: self.weak,
+            max_body_size: self.max_body_size,
+        }
+    }
+}
+
+/// Middleware which adds an `ETag` to responses and answers
+/// matching `If-None-Match` requests with `304 Not Modified`.
+///
+/// See the [module docs](self) for more information.
+#[derive(Clone)]
+pub struct ETagService<S> {
+    inner: S,
+    weak: bool,
+    max_body_size: usize,
+}
+
+impl<S: fmt::Debug> fmt::Debug for ETagService<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ETagService")
+            .field("inner", &self.inner)
+            .field("weak", &self.weak)
+            .field("max_body_size", &self.max_body_size)
+            .finish()
+    }
+}
+
+impl<S> ETagService<S> {
+    /// Create a new [`ETagService`], generating strong entity tags.
+    pub const fn new(inner: S) -> Self {
+        Self {
+            inner,
+            weak: false,
+            max_body_size: DEFAULT_MAX_BODY_SIZE,
+        }
+    }
+
+    define_inner_service_accessors!();
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Generate weak (`W/"..."`) instead of strong entity tags.
+        ///
+        /// Weak entity tags should be used when the body might still be transformed
+        /// (e.g. compressed) by a layer which wraps this one.
+        pub fn weak(mut self, weak: bool) -> Self {
+            self.weak = weak;
+            self
+        }
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Set the maximum size of a response body which is buffered to compute its `ETag`.
+        ///
+        /// Defaults to [`DEFAULT_MAX_BODY_SIZE`].
+        pub fn max_body_size(mut self, size: usize) -> Self {
+            self.max_body_size = size;
+            self
+        }
+    }
+
+    fn make_etag(&self, body: &[u8]) -> Result<HeaderValue, OpaqueError> {
+        let hash = digest::digest(&digest::SHA256, body);
+        // 128 bits of the digest are plenty to identify a representation
+        let tag = BASE64_URL_SAFE_NO_PAD.encode(&hash.as_ref()[..16]);
+        let value = if self.weak {
+            format!("W/\"{tag}\"")
+        } else {
+            format!("\"{tag}\"")
+        };
+        HeaderValue::try_from(value).map_err(OpaqueError::from_std)
+    }
+}
+
+impl<S, ReqBody, ResBody> Service<Request<ReqBody>> for ETagService<S>
+where
+    S: Service<Request<ReqBody>, Response = Response<ResBody>, Error: Into<BoxError>>,
+    ReqBody: Send + 'static,
+    ResBody: HttpBody<Data = Bytes, Error: Into<BoxError>> + Send + Sync + 'static,
+{
+    type Response = Response;
+    type Error = BoxError;
+
+    async fn serve(
+        &self,
+        ctx: Context,
+        req: Request<ReqBody>,
+    ) -> Result<Self::Response, Self::Error> {
+        let applicable = req.method() == Method::GET || req.method() == Method::HEAD;
+        let if_none_match = req.headers().typed_get::<IfNoneMatch>();
+
+        let resp = self.inner.serve(ctx, req).await.map_err(Into::into)?;
+        if !applicable || resp.status() != StatusCode::OK {
+            return Ok(resp.map(Body::new));
+        }
+
+        let resp = if resp.headers().contains_key(header::ETAG) {
+            resp.map(Body::new)
+        } else if resp
+            .body()
+            .size_hint()
+            .upper()
+            .is_some_and(|size| size <= self.max_body_size as u64)
+        {
+            let (mut parts, body) = resp.into_parts();
+            let body = body.collect().await.map_err(Into::into)?.to_bytes();
+            parts.headers.insert(header::ETAG, self.make_etag(&body)?);
+            Response::from_parts(parts, Body::from(body))
+        } else {
+            return Ok(resp.map(Body::new));
+        };
+
+        let not_modified = if_none_match
+            .zip(resp.headers().typed_get::<ETag>())
+            .is_some_and(|(if_none_match, etag)| !if_none_match.precondition_passes(&etag));
+        if !not_modified {
+            return Ok(resp);
+        }
+
+        let (mut parts, _) = resp.into_parts();
+        parts.status = StatusCode::NOT_MODIFIED;
+        parts.headers.remove(header::CONTENT_LENGTH);
+        Ok(Response::from_parts(parts, Body::empty()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rama_core::service::service_fn;
+    use rama_http_types::BodyExtractExt;
+    use std::convert::Infallible;
+
+    fn req(if_none_match: Option<&HeaderValue>) -> Request {
+        let mut req = Request::builder().uri("/report");
+        if let Some(value) = if_none_match {
+            req = req.header(header::IF_NONE_MATCH, value);
+        }
+        req.body(Body::empty()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_etag_generated() {
+        let service = ETagLayer::new().into_layer(service_fn(async |_: Request| {
+            Ok::<_, Infallible>(Response::new(Body::from("report")))
+        }));
+
+        let resp = service.serve(Context::default(), req(None)).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let etag = resp.headers()[header::ETAG].clone();
+        assert!(etag.as_bytes().starts_with(b"\""));
+        assert_eq!(resp.try_into_string().await.unwrap(), "report");
+
+        let resp = service
+            .serve(Context::default(), req(Some(&etag)))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::NOT_MODIFIED);
+        assert_eq!(resp.headers()[header::ETAG], etag);
+        assert!(resp.try_into_string().await.unwrap().is_empty());
+
+        let other = HeaderValue::from_static("\"other\"");
+        let resp = service
+            .serve(Context::default(), req(Some(&other)))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        let weak = ETagLayer::new()
+            .with_weak(true)
+            .into_layer(service.into_inner());
+        let resp = weak
+            .serve(Context::default(), req(Some(&etag)))
+            .await
+            .unwrap();
+        assert!(resp.headers()[header::ETAG].as_bytes().starts_with(b"W/\""));
+        // weak comparison is used for If-None-Match
+        assert_eq!(resp.status(), StatusCode::NOT_MODIFIED);
+    }
+
+    #[tokio::test]
+    async fn test_etag_handler_provided() {
+        let service = ETagLayer::new().into_layer(service_fn(async |_: Request| {
+            Ok::<_, Infallible>(
+                Response::builder()
+                    .header(header::ETAG, "\"v1\"")
+                    .body(Body::from("report"))
+                    .unwrap(),
+            )
+        }));
+
+        let resp = service.serve(Context::default(), req(None)).await.unwrap();
+        assert_eq!(resp.headers()[header::ETAG], "\"v1\"");
+
+        let etag = HeaderValue::from_static("\"v0\", \"v1\"");
+        let resp = service
+            .serve(Context::default(), req(Some(&etag)))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::NOT_MODIFIED);
+    }
+
+    #[tokio::test]
+    async fn test_etag_skips_large_and_unsafe() {
+        let service = ETagLayer::new()
+            .with_max_body_size(4)
+            .into_layer(service_fn(async |_: Request| {
+                Ok::<_, Infallible>(Response::new(Body::from("report")))
+            }));
+        let resp = service.serve(Context::default(), req(None)).await.unwrap();
+        assert!(!resp.headers().contains_key(header::ETAG));
+
+        let service = ETagLayer::new().into_layer(service.into_inner());
+        let req = Request::post("/report").body(Body::empty()).unwrap();
+        let resp = service.serve(Context::default(), req).await.unwrap();
+        assert!(!resp.headers().contains_key(header::ETAG));
+    }
+}
@@ -0,0 +1,654 @@
+//! Middleware which serves a fallback response when the inner service fails,
+//! for graceful degradation at the edge.
+//!
+//! The [`FallbackLayer`] serves the response created by its [`MakeFallback`]
+//! instead of propagating the failure when the inner service:
+//!
+//! - returns an error;
+//! - does not respond within the (optional) configured timeout;
+//! - responds with a server error (`5xx`), unless disabled.
+//!
+//! Two fallback sources are provided:
+//!
+//! - [`StaticFallback`]: a static response, e.g. a maintenance page;
+//! - [`StaleFallback`]: the last successful response for the same request,
+//!   recorded in a [`CacheStore`] and served even if it is stale.
+//!
+//! Fallback responses carry a [`Warning`] header (`110` for stale responses,
+//! `199` for static ones), as well as the custom header configured
+//! using [`FallbackLayer::with_header`], if any.
+//! In case no fallback response is available, the original failure is returned.
+//!
+//! [`Warning`]: crate::header::WARNING
+//!
+//! # Example
+//!
+//! ```
+//! use rama_core::{Context, Layer, Service, service::service_fn};
+//! use rama_http::layer::fallback::{FallbackLayer, StaticFallback};
+//! use rama_http::{Body, Request, Response, StatusCode, header};
+//! use rama_core::error::OpaqueError;
+//! use std::time::Duration;
+//!
+//! # #[tokio::main]
+//! # async fn main() {
+//! let service = FallbackLayer::new(StaticFallback::new(StatusCode::OK, "offline mode"))
+//!     .with_timeout(Duration::from_secs(5))
+//!     .into_layer(service_fn(async |_: Request| {
+//!         Err::<Response, _>(OpaqueError::from_display("upstream unavailable"))
+//!     }));
+//!
+//! let req = Request::builder().uri("http://example.com/").body(Body::empty()).unwrap();
+//! let resp = service.serve(Context::default(), req).await.unwrap();
+//! assert_eq!(resp.status(), StatusCode::OK);
+//! assert!(resp.headers().contains_key(header::WARNING));
+//! # }
+//! ```
+
+use std::{
+    fmt,
+    time::{Duration, SystemTime},
+};
+
+use crate::dep::http_body::Body as HttpBody;
+use crate::layer::response_cache::{
+    CacheStore, CachedResponse, DEFAULT_MAX_BODY_SIZE, DefaultMakeCacheKey, MakeCacheKey,
+};
+use crate::layer::util::collect::{LimitedBody, collect_limited};
+use rama_core::{
+    Context, Layer, Service,
+    bytes::Bytes,
+    error::{BoxError, OpaqueError},
+    telemetry::tracing,
+};
+use rama_http_headers::{CacheControl, HeaderMapExt};
+use rama_http_types::{
+    Body, HeaderMap, HeaderName, HeaderValue, Method, Request, Response, StatusCode, header,
+};
+use rama_utils::macros::define_inner_service_accessors;
+
+/// The default duration for which a [`StaleFallback`] keeps serving
+/// the last successful response of a request.
+pub const DEFAULT_MAX_STALE: Duration = Duration::from_secs(60 * 60);
+
+const WARNING_STALE: HeaderValue = HeaderValue::from_static("110 - \"Response is Stale\"");
+const WARNING_FALLBACK: HeaderValue = HeaderValue::from_static("199 - \"Fallback Response\"");
+
+/// Create the fallback response served by the [`Fallback`] middleware.
+pub trait MakeFallback: Send + Sync + 'static {
+    /// Create the fallback response for the given request head,
+    /// returning `None` in case no fallback response is available.
+    fn make_fallback(
+        &self,
+        ctx: &Context,
+        req: &Request<()>,
+    ) -> impl Future<Output = Option<Response>> + Send;
+
+    /// Observe a successful response of the inner service,
+    /// e.g. to record it for later use as a fallback.
+    ///
+    /// By default the response is returned as is.
+    fn record(
+        &self,
+        ctx: &Context,
+        req: &Request<()>,
+        resp: Response,
+    ) -> impl Future<Output = Result<Response, BoxError>> + Send {
+        let _ = (ctx, req);
+        std::future::ready(Ok(resp))
+    }
+}
+
+#[derive(Debug, Clone)]
+/// A [`MakeFallback`] which always serves the same static response.
+pub struct StaticFallback {
+    status: StatusCode,
+    headers: HeaderMap,
+    body: Bytes,
+}
+
+impl StaticFallback {
+    /// Create a new [`StaticFallback`] serving the given status and body.
+    pub fn new(status: StatusCode, body: impl Into<Bytes>) -> Self {
+        Self {
+            status,
+            headers: HeaderMap::new(),
+            body: body.into(),
+        }
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Add a header to the fallback response, e.g. its `Content-Type`.
+        pub fn header(mut self, name: HeaderName, value: HeaderValue) -> Self {
+            self.headers.append(name, value);
+            self
+        }
+    }
+}
+
+impl MakeFallback for StaticFallback {
+    async fn make_fallback(&self, _ctx: &Context, _req: &Request<()>) -> Option<Response> {
+        let mut resp = Response::new(Body::from(self.body.clone()));
+        *resp.status_mut() = self.status;
+        *resp.headers_mut() = self.headers.clone();
+        if !resp.headers().contains_key(header::WARNING) {
+            resp.headers_mut().insert(header::WARNING, WARNING_FALLBACK);
+        }
+        Some(resp)
+    }
+}
+
+/// A [`MakeFallback`] which serves the last successful response
+/// of the same request, even if it is stale.
+///
+/// Successful responses to `GET` requests are recorded in the [`CacheStore`],
+/// unless they set cookies, are marked `no-store` or `private`,
+/// `Vary` on `*` or exceed the maximum body size.
+/// Recording a response requires its body to be collected in memory,
+/// which is why responses of unknown length are streamed as-is, unrecorded.
+///
+/// Use a dedicated store, as recorded responses are always stale,
+/// and would otherwise be served by the [`ResponseCache`] as such.
+///
+/// [`ResponseCache`]: crate::layer::response_cache::ResponseCache
+pub struct StaleFallback<St, K = DefaultMakeCacheKey> {
+    store: St,
+    make_key: K,
+    max_stale: Duration,
+    max_body_size: usize,
+}
+
+impl<St> StaleFallback<St> {
+    /// Create a new [`StaleFallback`] recording responses in the given store.
+    pub fn new(store: St) -> Self {
+        Self {
+            store,
+            make_key: DefaultMakeCacheKey,
+            max_stale: DEFAULT_MAX_STALE,
+            max_body_size: DEFAULT_MAX_BODY_SIZE,
+        }
+    }
+}
+
+impl<St, K> StaleFallback<St, K> {
+    /// Use a custom [`MakeCacheKey`] to create the key of requests.
+    pub fn with_make_cache_key<K2>(self, make_key: K2) -> StaleFallback<St, K2> {
+        StaleFallback {
+            store: self.store,
+            make_key,
+            max_stale: self.max_stale,
+            max_body_size: self.max_body_size,
+        }
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Set for how long a recorded response can be served as a fallback.
+        ///
+        /// Defaults to [`DEFAULT_MAX_STALE`].
+        pub fn max_stale(mut self, max_stale: Duration) -> Self {
+            self.max_stale = max_stale;
+            self
+        }
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Set the maximum size of a response body which can be recorded.
+        ///
+        /// Defaults to [`DEFAULT_MAX_BODY_SIZE`].
+        pub fn max_body_size(mut self, size: usize) -> Self {
+            self.max_body_size = size;
+            self
+        }
+    }
+}
+
+impl<St: fmt::Debug, K: fmt::Debug> fmt::Debug for StaleFallback<St, K> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("StaleFallback")
+            .field("store", &self.store)
+            .field("make_key", &self.make_key)
+            .field("max_stale", &self.max_stale)
+            .field("max_body_size", &self.max_body_size)
+            .finish()
+    }
+}
+
+impl<St: Clone, K: Clone> Clone for StaleFallback<St, K> {
+    fn clone(&self) -> Self {
+        Self {
+            store: self.store.clone(),
+            make_key: self.make_key.clone(),
+            max_stale: self.max_stale,
+            max_body_size: self.max_body_size,
+        }
+    }
+}
+
+impl<St, K> StaleFallback<St, K>
+where
+    St: CacheStore,
+    K: MakeCacheKey,
+{
+    fn key(&self, ctx: &Context, req: &Request<()>) -> Option<String> {
+        if req.method() != Method::GET {
+            return None;
+        }
+        self.make_key.make_cache_key(ctx, req)
+    }
+
+    /// Returns the request headers the response varies on,
+    /// or `None` in case the response should not be recorded.
+    fn vary(
+        &self,
+        req: &Request<()>,
+        resp: &Response,
+    ) -> Option<Vec<(HeaderName, Option<HeaderValue>)>> {
+        if !resp.status().is_success() || resp.headers().contains_key(header::SET_COOKIE) {
+            return None;
+        }
+        if resp
+            .headers()
+            .typed_get::<CacheControl>()
+            .is_some_and(|cc| cc.no_store() || cc.private())
+        {
+            return None;
+        }
+        if !resp
+            .body()
+            .size_hint()
+            .exact()
+            .and_then(|len| usize::try_from(len).ok())
+            .is_some_and(|len| len <= self.max_body_size)
+        {
+            return None;
+        }
+
+        let mut vary = Vec::new();
+        for value in resp.headers().get_all(header::VARY) {
+            let value = value.to_str().ok()?;
+            for name in value.split(',').map(str::trim).filter(|s| !s.is_empty()) {
+                if name == "*" {
+                    return None;
+                }
+                let name = HeaderName::try_from(name).ok()?;
+                let value = req.headers().get(&name).cloned();
+                vary.push((name, value));
+            }
+        }
+        Some(vary)
+    }
+}
+
+impl<St, K> MakeFallback for StaleFallback<St, K>
+where
+    St: CacheStore,
+    K: MakeCacheKey,
+{
+    async fn make_fallback(&self, ctx: &Context, req: &Request<()>) -> Option<Response> {
+        let key = self.key(ctx, req)?;
+        let now = SystemTime::now();
+        match self.store.get(&key).await {
+            Ok(Some(recorded))
+                if recorded.matches_vary(req.headers()) && !recorded.is_expired(now) =>
+            {
+                tracing::trace!("serve stale fallback response for key {key}");
+                let mut resp = recorded.to_response(now);
+                resp.headers_mut().append(header::WARNING, WARNING_STALE);
+                Some(resp)
+            }
+            Ok(_) => None,
+            Err(err) => {
+                tracing::debug!("failed to lookup fallback response for key {key}: {err}");
+                None
+            }
+        }
+    }
+
+    async fn record(
+        &self,
+        ctx: &Context,
+        req: &Request<()>,
+        resp: Response,
+    ) -> Result<Response, BoxError> {
+        let Some(key) = self.key(ctx, req) else {
+            return Ok(resp);
+        };
+        let Some(vary) = self.vary(req, &resp) else {
+            return Ok(resp);
+        };
+
+        let (parts, body) = resp.into_parts();
+        let body = match collect_limited(body, self.max_body_size).await? {
+            LimitedBody::Collected(body) => body,
+            LimitedBody::Exceeded(body) => return Ok(Response::from_parts(parts, body)),
+        };
+        let now = SystemTime::now();
+        let recorded = CachedResponse {
+            status: parts.status,
+            headers: parts.headers.clone(),
+            body: body.clone(),
+            vary,
+            stored_at: now,
+            fresh_until: now,
+            stale_until: now + self.max_stale,
+        };
+        if let Err(err) = self.store.put(key.clone(), recorded).await {
+            tracing::debug!("failed to record fallback response for key {key}: {err}");
+        }
+        Ok(Response::from_parts(parts, Body::from(body)))
+    }
+}
+
+#[derive(Debug, Clone)]
+struct FallbackConfig {
+    timeout: Option<Duration>,
+    server_errors: bool,
+    headers: HeaderMap,
+}
+
+/// Layer that applies the [`Fallback`] middleware.
+///
+/// See the [module docs](self) for more information.
+#[derive(Debug, Clone)]
+pub struct FallbackLayer<F> {
+    make_fallback: F,
+    config: FallbackConfig,
+}
+
+impl<F> FallbackLayer<F> {
+    /// Create a new [`FallbackLayer`] serving the responses created by the given [`MakeFallback`].
+    pub fn new(make_fallback: F) -> Self {
+        Self {
+            make_fallback,
+            config: FallbackConfig {
+                timeout: None,
+                server_errors: true,
+                headers: HeaderMap::new(),
+            },
+        }
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Serve the fallback response in case the inner service
+        /// does not respond within the given duration.
+        ///
+        /// No timeout is applied by default.
+        pub fn timeout(mut self, timeout: Duration) -> Self {
+            self.config.timeout = Some(timeout);
+            self
+        }
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Set whether server error (`5xx`) responses of the inner service
+        /// are replaced by the fallback response.
+        ///
+        /// Enabled by default.
+        pub fn server_errors(mut self, enabled: bool) -> Self {
+            self.config.server_errors = enabled;
+            self
+        }
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Add a header to all fallback responses,
+        /// e.g. to signal downstream that the response is degraded.
+        pub fn header(mut self, name: HeaderName, value: HeaderValue) -> Self {
+            self.config.headers.append(name, value);
+            self
+        }
+    }
+}
+
+impl<S, F: Clone> Layer<S> for FallbackLayer<F> {
+    type Service = Fallback<S, F>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        Fallback {
+            inner,
+            make_fallback: self.make_fallback.clone(),
+            config: self.config.clone(),
+        }
+    }
+
+    fn into_layer(self, inner: S) -> Self::Service {
+        Fallback {
+            inner,
+            make_fallback: self.make_fallback,
+            config: self.config,
+        }
+    }
+}
+
+/// Middleware which serves a fallback response when the inner service fails.
+///
+/// See the [module docs](self) for more information.
+pub struct Fallback<S, F> {
+    inner: S,
+    make_fallback: F,
+    config: FallbackConfig,
+}
+
+impl<S, F> Fallback<S, F> {
+    define_inner_service_accessors!();
+}
+
+impl<S: fmt::Debug, F: fmt::Debug> fmt::Debug for Fallback<S, F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Fallback")
+            .field("inner", &self.inner)
+            .field("make_fallback", &self.make_fallback)
+            .field("config", &self.config)
+            .finish()
+    }
+}
+
+impl<S: Clone, F: Clone> Clone for Fallback<S, F> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            make_fallback: self.make_fallback.clone(),
+            config: self.config.clone(),
+        }
+    }
+}
+
+impl<S, F, ReqBody, ResBody> Service<Request<ReqBody>> for Fallback<S, F>
+where
+    S: Service<Request<ReqBody>, Response = Response<ResBody>, Error: Into<BoxError>>,
+    F: MakeFallback,
+    ReqBody: Send + 'static,
+    ResBody: HttpBody<Data = Bytes, Error: Into<BoxError>> + Send + Sync + 'static,
+{
+    type Response = Response;
+    type Error = BoxError;
+
+    async fn serve(
+        &self,
+        ctx: Context,
+        req: Request<ReqBody>,
+    ) -> Result<Self::Response, Self::Error> {
+        let head = request_head(&req);
+
+        let result = match self.config.timeout {
+            Some(timeout) => {
+                match tokio::time::timeout(timeout, self.inner.serve(ctx.clone(), req)).await {
+                    Ok(result) => result.map_err(Into::into),
+                    Err(_) => Err(OpaqueError::from_display(format!(
+                        "inner service did not respond within {timeout:?}"
+                    ))
+                    .into_boxed()),
+                }
+            }
+            None => self.inner.serve(ctx.clone(), req).await.map_err(Into::into),
+        };
+
+        let result = match result {
+            Ok(resp) if self.config.server_errors && resp.status().is_server_error() => {
+                tracing::debug!(
+                    "inner service responded with {}: try fallback response",
+                    resp.status()
+                );
+                Ok(resp.map(Body::new))
+            }
+            Ok(resp) => {
+                return self
+                    .make_fallback
+                    .record(&ctx, &head, resp.map(Body::new))
+                    .await;
+            }
+            Err(err) => {
+                tracing::debug!("inner service failed: try fallback response: {err}");
+                Err(err)
+            }
+        };
+
+        match self.make_fallback.make_fallback(&ctx, &head).await {
+            Some(mut resp) => {
+                for (name, value) in &self.config.headers {
+                    resp.headers_mut().append(name, value.clone());
+                }
+                Ok(resp)
+            }
+            None => result,
+        }
+    }
+}
+
+/// Copy the head of the request, such that it remains
+/// available after the request is consumed by the inner service.
+fn request_head<B>(req: &Request<B>) -> Request<()> {
+    let mut head = Request::new(());
+    *head.method_mut() = req.method().clone();
+    *head.uri_mut() = req.uri().clone();
+    *head.version_mut() = req.version();
+    *head.headers_mut() = req.headers().clone();
+    head
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layer::response_cache::MemoryCacheStore;
+    use rama_core::service::service_fn;
+    use rama_http_types::BodyExtractExt;
+    use std::sync::{
+        Arc,
+        atomic::{AtomicUsize, Ordering},
+    };
+
+    fn req() -> Request {
+        Request::builder()
+            .uri("http://example.com/")
+            .body(Body::empty())
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_static_fallback() {
+        let layer = FallbackLayer::new(
+            StaticFallback::new(StatusCode::SERVICE_UNAVAILABLE, "degraded")
+                .with_header(header::CONTENT_TYPE, HeaderValue::from_static("text/plain")),
+        )
+        .with_timeout(Duration::from_millis(10))
+        .with_header(
+            HeaderName::from_static("x-fallback"),
+            HeaderValue::from_static("1"),
+        );
+
+        let erroring = layer.layer(service_fn(async |_: Request| {
+            Err::<Response, _>(OpaqueError::from_display("boom"))
+        }));
+        let resp = erroring.serve(Context::default(), req()).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(resp.headers()[header::WARNING], WARNING_FALLBACK);
+        assert_eq!(resp.headers()[header::CONTENT_TYPE], "text/plain");
+        assert_eq!(resp.headers()["x-fallback"], "1");
+        assert_eq!(resp.try_into_string().await.unwrap(), "degraded");
+
+        let slow = layer.layer(service_fn(async |_: Request| {
+            tokio::time::sleep(Duration::from_secs(60)).await;
+            Ok::<_, OpaqueError>(Response::new(Body::empty()))
+        }));
+        let resp = slow.serve(Context::default(), req()).await.unwrap();
+        assert_eq!(resp.headers()[header::WARNING], WARNING_FALLBACK);
+
+        let server_error = layer.layer(service_fn(async |_: Request| {
+            let mut resp = Response::new(Body::empty());
+            *resp.status_mut() = StatusCode::BAD_GATEWAY;
+            Ok::<_, OpaqueError>(resp)
+        }));
+        let resp = server_error.serve(Context::default(), req()).await.unwrap();
+        assert_eq!(resp.headers()[header::WARNING], WARNING_FALLBACK);
+
+        let server_error = layer
+            .clone()
+            .with_server_errors(false)
+            .into_layer(server_error.into_inner());
+        let resp = server_error.serve(Context::default(), req()).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::BAD_GATEWAY);
+        assert!(!resp.headers().contains_key(header::WARNING));
+    }
+
+    #[tokio::test]
+    async fn test_stale_fallback() {
+        let counter = Arc::new(AtomicUsize::new(0));
+        let service = FallbackLayer::new(StaleFallback::new(MemoryCacheStore::new(16))).into_layer(
+            service_fn({
+                let counter = counter.clone();
+                move |_: Request| {
+                    let counter = counter.clone();
+                    async move {
+                        match counter.fetch_add(1, Ordering::SeqCst) {
+                            0 => Ok(Response::new(Body::from("hello"))),
+                            _ => Err(OpaqueError::from_display("boom")),
+                        }
+                    }
+                }
+            }),
+        );
+
+        let resp = service.serve(Context::default(), req()).await.unwrap();
+        assert!(!resp.headers().contains_key(header::WARNING));
+        assert_eq!(resp.try_into_string().await.unwrap(), "hello");
+
+        let resp = service.serve(Context::default(), req()).await.unwrap();
+        assert_eq!(resp.headers()[header::WARNING], WARNING_STALE);
+        assert!(resp.headers().contains_key(header::AGE));
+        assert_eq!(resp.try_into_string().await.unwrap(), "hello");
+
+        // nothing recorded for other requests: the error is propagated
+        let other = Request::builder()
+            .uri("http://example.com/other")
+            .body(Body::empty())
+            .unwrap();
+        assert!(service.serve(Context::default(), other).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_stale_fallback_streams_unknown_length() {
+        let counter = Arc::new(AtomicUsize::new(0));
+        let service = FallbackLayer::new(StaleFallback::new(MemoryCacheStore::new(16))).into_layer(
+            service_fn({
+                let counter = counter.clone();
+                move |_: Request| {
+                    let counter = counter.clone();
+                    async move {
+                        match counter.fetch_add(1, Ordering::SeqCst) {
+                            0 => Ok(Response::new(Body::from_stream(
+                                rama_core::futures::stream::iter([Ok::<_, OpaqueError>("hello")]),
+                            ))),
+                            _ => Err(OpaqueError::from_display("boom")),
+                        }
+                    }
+                }
+            }),
+        );
+
+        let resp = service.serve(Context::default(), req()).await.unwrap();
+        assert_eq!(resp.try_into_string().await.unwrap(), "hello");
+
+        // the streamed response was not recorded: the error is propagated
+        assert!(service.serve(Context::default(), req()).await.is_err());
+    }
+}
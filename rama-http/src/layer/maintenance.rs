@@ -0,0 +1,285 @@
+//! Middleware to put a service in maintenance mode at runtime.
+//!
+//! The [`Maintenance`] middleware is controlled by a [`MaintenanceMode`] handle,
+//! which can be cloned and toggled from anywhere, e.g. from an admin endpoint or a signal handler,
+//! without having to redeploy or restart the service.
+//!
+//! While enabled, requests are answered with `503 Service Unavailable`
+//! and a `Retry-After` header, instead of being forwarded to the inner service.
+//! By default all requests are affected, which can be narrowed down to specific
+//! routes using [`MaintenanceLayer::with_routes`], while requests such as health checks and admin
+//! paths can be exempted using [`MaintenanceLayer::with_exempt`].
+//!
+//! # Example
+//!
+//! ```
+//! use rama_core::{Context, Layer, Service, service::service_fn};
+//! use rama_http::layer::maintenance::{MaintenanceLayer, MaintenanceMode};
+//! use rama_http::matcher::HttpMatcher;
+//! use rama_http::{Body, Request, Response, StatusCode};
+//! use std::convert::Infallible;
+//!
+//! # #[tokio::main]
+//! # async fn main() {
+//! let mode = MaintenanceMode::new();
+//! let service = MaintenanceLayer::new(mode.clone())
+//!     .with_exempt(HttpMatcher::path("/health").or_path("/admin/*"))
+//!     .into_layer(service_fn(async |_: Request| {
+//!         Ok::<_, Infallible>(Response::new(Body::empty()))
+//!     }));
+//!
+//! mode.enable();
+//!
+//! let req = Request::get("/api/users").body(Body::empty()).unwrap();
+//! let resp = service.serve(Context::default(), req).await.unwrap();
+//! assert_eq!(resp.status(), StatusCode::SERVICE_UNAVAILABLE);
+//!
+//! let req = Request::get("/health").body(Body::empty()).unwrap();
+//! let resp = service.serve(Context::default(), req).await.unwrap();
+//! assert_eq!(resp.status(), StatusCode::OK);
+//! # }
+//! ```
+
+use std::{
+    fmt,
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+    time::Duration,
+};
+
+use crate::service::web::response::IntoResponse;
+use rama_core::{Context, Layer, Service, matcher::Matcher};
+use rama_http_types::{HeaderValue, Request, Response, StatusCode, header};
+use rama_utils::macros::define_inner_service_accessors;
+
+#[derive(Debug, Clone, Default)]
+/// Shared handle to toggle the maintenance mode of the [`Maintenance`] middleware.
+///
+/// All clones of a handle control the same mode.
+pub struct MaintenanceMode(Arc<AtomicBool>);
+
+impl MaintenanceMode {
+    /// Create a new [`MaintenanceMode`] handle, which is disabled.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Enable the maintenance mode.
+    pub fn enable(&self) {
+        self.set(true);
+    }
+
+    /// Disable the maintenance mode.
+    pub fn disable(&self) {
+        self.set(false);
+    }
+
+    /// Enable or disable the maintenance mode.
+    pub fn set(&self, enabled: bool) {
+        self.0.store(enabled, Ordering::Release);
+    }
+
+    /// Returns `true` in case the maintenance mode is enabled.
+    #[must_use]
+    pub fn is_enabled(&self) -> bool {
+        self.0.load(Ordering::Acquire)
+    }
+}
+
+/// Layer that applies the [`Maintenance`] middleware.
+///
+/// See the [module docs](self) for more information.
+#[derive(Debug, Clone)]
+pub struct MaintenanceLayer<M = bool, E = bool> {
+    mode: MaintenanceMode,
+    routes: M,
+    exempt: E,
+    retry_after: Duration,
+}
+
+impl MaintenanceLayer {
+    /// Create a new [`MaintenanceLayer`] controlled by the given [`MaintenanceMode`],
+    /// affecting all requests while enabled.
+    #[must_use]
+    pub fn new(mode: MaintenanceMode) -> Self {
+        Self {
+            mode,
+            routes: true,
+            exempt: false,
+            retry_after: Duration::from_secs(60),
+        }
+    }
+}
+
+impl<M, E> MaintenanceLayer<M, E> {
+    /// Only affect the requests matched by the given [`Matcher`].
+    pub fn with_routes<T>(self, routes: T) -> MaintenanceLayer<T, E> {
+        MaintenanceLayer {
+            mode: self.mode,
+            routes,
+            exempt: self.exempt,
+            retry_after: self.retry_after,
+        }
+    }
+
+    /// Exempt the requests matched by the given [`Matcher`],
+    /// e.g. health checks and admin paths.
+    pub fn with_exempt<T>(self, exempt: T) -> MaintenanceLayer<M, T> {
+        MaintenanceLayer {
+            mode: self.mode,
+            routes: self.routes,
+            exempt,
+            retry_after: self.retry_after,
+        }
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Set the duration advertised in the `Retry-After` header.
+        ///
+        /// Defaults to 60 seconds.
+        pub fn retry_after(mut self, retry_after: Duration) -> Self {
+            self.retry_after = retry_after;
+            self
+        }
+    }
+}
+
+impl<S, M: Clone, E: Clone> Layer<S> for MaintenanceLayer<M, E> {
+    type Service = Maintenance<S, M, E>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        Maintenance {
+            inner,
+            mode: self.mode.clone(),
+            routes: self.routes.clone(),
+            exempt: self.exempt.clone(),
+            retry_after: self.retry_after,
+        }
+    }
+
+    fn into_layer(self, inner: S) -> Self::Service {
+        Maintenance {
+            inner,
+            mode: self.mode,
+            routes: self.routes,
+            exempt: self.exempt,
+            retry_after: self.retry_after,
+        }
+    }
+}
+
+/// Middleware which answers requests with `503 Service Unavailable`
+/// while its [`MaintenanceMode`] is enabled.
+///
+/// See the [module docs](self) for more information.
+pub struct Maintenance<S, M = bool, E = bool> {
+    inner: S,
+    mode: MaintenanceMode,
+    routes: M,
+    exempt: E,
+    retry_after: Duration,
+}
+
+impl<S, M, E> Maintenance<S, M, E> {
+    define_inner_service_accessors!();
+}
+
+impl<S: fmt::Debug, M: fmt::Debug, E: fmt::Debug> fmt::Debug for Maintenance<S, M, E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Maintenance")
+            .field("inner", &self.inner)
+            .field("mode", &self.mode)
+            .field("routes", &self.routes)
+            .field("exempt", &self.exempt)
+            .field("retry_after", &self.retry_after)
+            .finish()
+    }
+}
+
+impl<S: Clone, M: Clone, E: Clone> Clone for Maintenance<S, M, E> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            mode: self.mode.clone(),
+            routes: self.routes.clone(),
+            exempt: self.exempt.clone(),
+            retry_after: self.retry_after,
+        }
+    }
+}
+
+impl<S, M, E, ReqBody> Service<Request<ReqBody>> for Maintenance<S, M, E>
+where
+    S: Service<Request<ReqBody>, Response = Response>,
+    M: Matcher<Request<ReqBody>>,
+    E: Matcher<Request<ReqBody>>,
+    ReqBody: Send + 'static,
+{
+    type Response = Response;
+    type Error = S::Error;
+
+    async fn serve(
+        &self,
+        ctx: Context,
+        req: Request<ReqBody>,
+    ) -> Result<Self::Response, Self::Error> {
+        if self.mode.is_enabled()
+            && self.routes.matches(None, &ctx, &req)
+            && !self.exempt.matches(None, &ctx, &req)
+        {
+            return Ok((
+                StatusCode::SERVICE_UNAVAILABLE,
+                [
+                    (
+                        header::RETRY_AFTER,
+                        HeaderValue::from(self.retry_after.as_secs()),
+                    ),
+                    (header::CACHE_CONTROL, HeaderValue::from_static("no-store")),
+                ],
+            )
+                .into_response());
+        }
+        self.inner.serve(ctx, req).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::matcher::HttpMatcher;
+    use rama_core::service::service_fn;
+    use rama_http_types::Body;
+    use std::convert::Infallible;
+
+    #[tokio::test]
+    async fn test_maintenance_toggle() {
+        let mode = MaintenanceMode::new();
+        let service = MaintenanceLayer::new(mode.clone())
+            .with_routes(HttpMatcher::path("/api/*"))
+            .with_exempt(HttpMatcher::path("/api/health"))
+            .with_retry_after(Duration::from_secs(120))
+            .into_layer(service_fn(async |_: Request| {
+                Ok::<_, Infallible>(Response::new(Body::empty()))
+            }));
+
+        let serve = async |path: &str| {
+            let req = Request::get(path).body(Body::empty()).unwrap();
+            service.serve(Context::default(), req).await.unwrap()
+        };
+
+        assert_eq!(serve("/api/users").await.status(), StatusCode::OK);
+
+        mode.enable();
+        let resp = serve("/api/users").await;
+        assert_eq!(resp.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(resp.headers()[header::RETRY_AFTER], "120");
+        assert_eq!(serve("/api/health").await.status(), StatusCode::OK);
+        assert_eq!(serve("/index.html").await.status(), StatusCode::OK);
+
+        mode.disable();
+        assert_eq!(serve("/api/users").await.status(), StatusCode::OK);
+    }
+}
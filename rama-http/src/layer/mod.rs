@@ -16,40 +16,61 @@
 //! [`Layer`]: rama_core::Layer
 //! [`Service`]: rama_core::Service
 
+pub mod access_log;
 pub mod auth;
 pub mod body_limit;
 pub mod catch_panic;
 pub mod classify;
+pub mod coalesce;
 pub mod collect_body;
+pub mod conditional;
 pub mod cors;
+pub mod deadline;
 pub mod dns;
 pub mod error_handling;
+pub mod etag;
+pub mod fallback;
 pub mod follow_redirect;
 pub mod forwarded;
 pub mod har;
 pub mod header_config;
 pub mod header_from_str_config;
 pub mod header_option_value;
+pub mod maintenance;
 pub mod map_request_body;
 pub mod map_response_body;
 pub mod normalize_path;
+pub mod prometheus;
 pub mod propagate_headers;
 pub mod proxy_auth;
+pub mod range;
+pub mod redact;
 pub mod remove_header;
 pub mod request_id;
 pub mod required_header;
+pub mod response_body_limit;
+pub mod response_cache;
 pub mod retry;
+pub mod route_limits;
+pub mod sanitize_headers;
+pub mod security_headers;
 pub mod sensitive_headers;
+pub mod session;
 pub mod set_header;
 pub mod set_status;
+pub mod signed_url;
+pub mod tarpit;
 pub mod timeout;
 pub mod trace;
+pub mod traffic_split;
 pub mod traffic_writer;
 pub mod ua;
 pub mod validate_request;
 
 #[cfg(feature = "opentelemetry")]
 pub mod opentelemetry;
+#[cfg(feature = "opentelemetry")]
+pub mod trace_context;
 
 pub(crate) mod util;
 
@@ -59,3 +80,6 @@ pub mod compress_adapter;
 pub mod compression;
 #[cfg(feature = "compression")]
 pub mod decompression;
+
+#[cfg(feature = "wasm")]
+pub mod wasm_plugin;
@@ -0,0 +1,452 @@
+//! Prometheus metrics, recording the RED (Rate, Errors, Duration) metrics of http servers.
+//!
+//! The [`PrometheusMetrics`] registry is shared between the [`PrometheusMetricsLayer`],
+//! which records the metrics, and the [`PrometheusExporter`], which serves them
+//! in the Prometheus text exposition format, e.g. mounted on `/metrics`.
+//!
+//! All metrics are labeled by route, method and status class (e.g. `2xx`).
+//! The route is the pattern matched by the [`Router`] (e.g. `/users/{id}`),
+//! or `unmatched` otherwise, and non-standard methods are labeled as `_OTHER`,
+//! such that the cardinality of the metrics remains bounded.
+//!
+//! [`Router`]: crate::service::web::Router
+//!
+//! # Example
+//!
+//! ```
+//! use rama_http::layer::prometheus::PrometheusMetrics;
+//! use rama_http::service::web::Router;
+//! use rama_http::{Body, Request, StatusCode};
+//! use rama_http::service::client::HttpClientExt;
+//! use rama_core::{Context, Layer};
+//!
+//! # #[tokio::main]
+//! # async fn main() {
+//! let metrics = PrometheusMetrics::new();
+//!
+//! let app = metrics.layer().into_layer(
+//!     Router::new()
+//!         .get("/", StatusCode::OK)
+//!         .get("/metrics", metrics.exporter()),
+//! );
+//!
+//! let _ = app.get("http://example.com/").send(Context::default()).await.unwrap();
+//!
+//! let res = app.get("http://example.com/metrics").send(Context::default()).await.unwrap();
+//! assert_eq!(res.status(), StatusCode::OK);
+//! # }
+//! ```
+
+use crate::service::web::MatchedRoute;
+use crate::{Body, HeaderValue, Method, Request, Response, StatusCode, header};
+use rama_core::{Context, Layer, Service};
+use rama_utils::macros::define_inner_service_accessors;
+use std::{
+    collections::BTreeMap,
+    convert::Infallible,
+    fmt::{self, Write as _},
+    sync::{Arc, Mutex},
+    time::Instant,
+};
+
+/// The default histogram buckets, in seconds, used for the request duration.
+pub const DEFAULT_BUCKETS: &[f64] = &[
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
+const UNMATCHED_ROUTE: &str = "unmatched";
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+struct SeriesKey {
+    route: String,
+    method: &'static str,
+    status_class: &'static str,
+}
+
+#[derive(Debug)]
+struct Series {
+    requests: u64,
+    errors: u64,
+    buckets: Vec<u64>,
+    duration_sum: f64,
+}
+
+/// Registry of the http server metrics recorded by the [`PrometheusMetricsLayer`].
+///
+/// Cloning it is cheap, all clones share the same metrics.
+/// See the [module docs](crate::layer::prometheus) for an example.
+#[derive(Debug, Clone)]
+pub struct PrometheusMetrics {
+    namespace: Option<Arc<str>>,
+    buckets: Arc<[f64]>,
+    series: Arc<Mutex<BTreeMap<SeriesKey, Series>>>,
+}
+
+impl Default for PrometheusMetrics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PrometheusMetrics {
+    /// Create a new [`PrometheusMetrics`] registry, using the [`DEFAULT_BUCKETS`].
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            namespace: None,
+            buckets: DEFAULT_BUCKETS.into(),
+            series: Default::default(),
+        }
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Set the namespace prefixed to all metric names, e.g. `myapp_http_server_requests_total`.
+        pub fn namespace(mut self, namespace: impl Into<Arc<str>>) -> Self {
+            self.namespace = Some(namespace.into());
+            self
+        }
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Set the histogram buckets, in seconds, used for the request duration.
+        ///
+        /// This resets the metrics recorded so far,
+        /// and should therefore be done before creating any layer or exporter.
+        pub fn buckets(mut self, buckets: impl Into<Vec<f64>>) -> Self {
+            let mut buckets = buckets.into();
+            buckets.sort_by(f64::total_cmp);
+            buckets.dedup();
+            self.buckets = buckets.into();
+            self.series = Default::default();
+            self
+        }
+    }
+
+    /// Create a [`PrometheusMetricsLayer`] recording into this registry.
+    #[must_use]
+    pub fn layer(&self) -> PrometheusMetricsLayer {
+        PrometheusMetricsLayer {
+            metrics: self.clone(),
+        }
+    }
+
+    /// Create a [`PrometheusExporter`] serving the metrics of this registry.
+    #[must_use]
+    pub fn exporter(&self) -> PrometheusExporter {
+        PrometheusExporter {
+            metrics: self.clone(),
+        }
+    }
+
+    fn record(&self, route: Option<&str>, method: &Method, status: Option<StatusCode>, secs: f64) {
+        let key = SeriesKey {
+            route: route.unwrap_or(UNMATCHED_ROUTE).to_owned(),
+            method: method_label(method),
+            // a failed inner service is reported as a server error
+            status_class: status.map_or("5xx", status_class),
+        };
+        let is_error = key.status_class == "5xx";
+
+        let mut series = self.series.lock().unwrap_or_else(|err| err.into_inner());
+        let series = series.entry(key).or_insert_with(|| Series {
+            requests: 0,
+            errors: 0,
+            buckets: vec![0; self.buckets.len()],
+            duration_sum: 0.0,
+        });
+        series.requests += 1;
+        if is_error {
+            series.errors += 1;
+        }
+        if let Some(index) = self.buckets.iter().position(|le| secs <= *le) {
+            series.buckets[index] += 1;
+        }
+        series.duration_sum += secs;
+    }
+
+    /// Render the metrics in the Prometheus text exposition format.
+    #[must_use]
+    pub fn render(&self) -> String {
+        let prefix = self
+            .namespace
+            .as_deref()
+            .map(|namespace| format!("{namespace}_"))
+            .unwrap_or_default();
+        let series = self.series.lock().unwrap_or_else(|err| err.into_inner());
+        let mut out = String::new();
+
+        let mut counter = |name: &str, help: &str, value: fn(&Series) -> u64| {
+            let _ = writeln!(out, "# HELP {prefix}{name} {help}");
+            let _ = writeln!(out, "# TYPE {prefix}{name} counter");
+            for (key, series) in series.iter() {
+                let _ = writeln!(out, "{prefix}{name}{{{}}} {}", labels(key), value(series));
+            }
+        };
+        counter(
+            "http_server_requests_total",
+            "Total number of http requests.",
+            |series| series.requests,
+        );
+        counter(
+            "http_server_errors_total",
+            "Total number of http requests which failed with a server error.",
+            |series| series.errors,
+        );
+
+        let name = "http_server_request_duration_seconds";
+        let _ = writeln!(
+            out,
+            "# HELP {prefix}{name} Duration of http requests, until the response head is returned."
+        );
+        let _ = writeln!(out, "# TYPE {prefix}{name} histogram");
+        for (key, series) in series.iter() {
+            let labels = labels(key);
+            let mut cumulative = 0;
+            for (le, count) in self.buckets.iter().zip(&series.buckets) {
+                cumulative += count;
+                let _ = writeln!(
+                    out,
+                    "{prefix}{name}_bucket{{{labels},le=\"{le}\"}} {cumulative}"
+                );
+            }
+            let _ = writeln!(
+                out,
+                "{prefix}{name}_bucket{{{labels},le=\"+Inf\"}} {}",
+                series.requests
+            );
+            let _ = writeln!(
+                out,
+                "{prefix}{name}_sum{{{labels}}} {}",
+                series.duration_sum
+            );
+            let _ = writeln!(out, "{prefix}{name}_count{{{labels}}} {}", series.requests);
+        }
+
+        out
+    }
+}
+
+fn method_label(method: &Method) -> &'static str {
+    match *method {
+        Method::GET => "GET",
+        Method::HEAD => "HEAD",
+        Method::POST => "POST",
+        Method::PUT => "PUT",
+        Method::DELETE => "DELETE",
+        Method::CONNECT => "CONNECT",
+        Method::OPTIONS => "OPTIONS",
+        Method::TRACE => "TRACE",
+        Method::PATCH => "PATCH",
+        _ => "_OTHER",
+    }
+}
+
+fn status_class(status: StatusCode) -> &'static str {
+    match status.as_u16() {
+        100..=199 => "1xx",
+        200..=299 => "2xx",
+        300..=399 => "3xx",
+        400..=499 => "4xx",
+        _ => "5xx",
+    }
+}
+
+fn labels(key: &SeriesKey) -> String {
+    format!(
+        "route=\"{}\",method=\"{}\",status=\"{}\"",
+        escape_label_value(&key.route),
+        key.method,
+        key.status_class,
+    )
+}
+
+fn escape_label_value(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+/// Layer that applies the [`PrometheusMetricsService`] middleware.
+///
+/// Created using [`PrometheusMetrics::layer`].
+#[derive(Debug, Clone)]
+pub struct PrometheusMetricsLayer {
+    metrics: PrometheusMetrics,
+}
+
+impl<S> Layer<S> for PrometheusMetricsLayer {
+    type Service = PrometheusMetricsService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        PrometheusMetricsService {
+            inner,
+            metrics: self.metrics.clone(),
+        }
+    }
+
+    fn into_layer(self, inner: S) -> Self::Service {
+        PrometheusMetricsService {
+            inner,
+            metrics: self.metrics,
+        }
+    }
+}
+
+/// Middleware which records the RED metrics of the requests it serves
+/// into a [`PrometheusMetrics`] registry.
+///
+/// See the [module docs](crate::layer::prometheus) for more details.
+#[derive(Clone)]
+pub struct PrometheusMetricsService<S> {
+    inner: S,
+    metrics: PrometheusMetrics,
+}
+
+impl<S: fmt::Debug> fmt::Debug for PrometheusMetricsService<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PrometheusMetricsService")
+            .field("inner", &self.inner)
+            .field("metrics", &self.metrics)
+            .finish()
+    }
+}
+
+impl<S> PrometheusMetricsService<S> {
+    /// Create a new [`PrometheusMetricsService`] recording into the given registry.
+    pub const fn new(inner: S, metrics: PrometheusMetrics) -> Self {
+        Self { inner, metrics }
+    }
+
+    define_inner_service_accessors!();
+}
+
+impl<S, ReqBody, ResBody> Service<Request<ReqBody>> for PrometheusMetricsService<S>
+where
+    S: Service<Request<ReqBody>, Response = Response<ResBody>>,
+    ReqBody: Send + 'static,
+    ResBody: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+
+    async fn serve(
+        &self,
+        ctx: Context,
+        req: Request<ReqBody>,
+    ) -> Result<Self::Response, Self::Error> {
+        let start = Instant::now();
+        let method = req.method().clone();
+
+        let result = self.inner.serve(ctx, req).await;
+
+        let secs = start.elapsed().as_secs_f64();
+        match &result {
+            Ok(res) => self.metrics.record(
+                res.extensions()
+                    .get::<MatchedRoute>()
+                    .map(MatchedRoute::as_str),
+                &method,
+                Some(res.status()),
+                secs,
+            ),
+            Err(_) => self.metrics.record(None, &method, None, secs),
+        }
+        result
+    }
+}
+
+/// Service which serves the metrics of a [`PrometheusMetrics`] registry
+/// in the Prometheus text exposition format.
+///
+/// Created using [`PrometheusMetrics::exporter`],
+/// and usually mounted on `/metrics` in a [`Router`].
+///
+/// [`Router`]: crate::service::web::Router
+#[derive(Debug, Clone)]
+pub struct PrometheusExporter {
+    metrics: PrometheusMetrics,
+}
+
+impl<B> Service<Request<B>> for PrometheusExporter
+where
+    B: Send + 'static,
+{
+    type Response = Response;
+    type Error = Infallible;
+
+    async fn serve(&self, _ctx: Context, _req: Request<B>) -> Result<Self::Response, Self::Error> {
+        let mut res = Response::new(Body::from(self.metrics.render()));
+        res.headers_mut().insert(
+            header::CONTENT_TYPE,
+            HeaderValue::from_static("text/plain; version=0.0.4; charset=utf-8"),
+        );
+        Ok(res)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::service::web::Router;
+    use rama_http_types::BodyExtractExt;
+
+    #[tokio::test]
+    async fn test_prometheus_metrics() {
+        let metrics = PrometheusMetrics::new()
+            .with_namespace("test")
+            .with_buckets([0.5, 0.1]);
+        let app = metrics.layer().into_layer(
+            Router::new()
+                .get("/users/{id}", StatusCode::OK)
+                .get("/fail", StatusCode::SERVICE_UNAVAILABLE)
+                .get("/metrics", metrics.exporter()),
+        );
+
+        for path in ["/users/1", "/users/2", "/fail", "/unknown"] {
+            let req = Request::get(path).body(Body::empty()).unwrap();
+            app.serve(Context::default(), req).await.unwrap();
+        }
+        for method in ["PURGE", "FOO"] {
+            let req = Request::builder()
+                .method(method)
+                .uri("/unknown")
+                .body(Body::empty())
+                .unwrap();
+            app.serve(Context::default(), req).await.unwrap();
+        }
+
+        let req = Request::get("/metrics").body(Body::empty()).unwrap();
+        let res = app.serve(Context::default(), req).await.unwrap();
+        assert_eq!(
+            res.headers()[header::CONTENT_TYPE],
+            "text/plain; version=0.0.4; charset=utf-8"
+        );
+        let body = res.try_into_string().await.unwrap();
+
+        for line in [
+            "# TYPE test_http_server_requests_total counter",
+            r#"test_http_server_requests_total{route="/users/{id}",method="GET",status="2xx"} 2"#,
+            r#"test_http_server_requests_total{route="/fail",method="GET",status="5xx"} 1"#,
+            r#"test_http_server_requests_total{route="unmatched",method="GET",status="4xx"} 1"#,
+            r#"test_http_server_requests_total{route="unmatched",method="_OTHER",status="4xx"} 2"#,
+            r#"test_http_server_errors_total{route="/users/{id}",method="GET",status="2xx"} 0"#,
+            r#"test_http_server_errors_total{route="/fail",method="GET",status="5xx"} 1"#,
+            "# TYPE test_http_server_request_duration_seconds histogram",
+            r#"test_http_server_request_duration_seconds_bucket{route="/users/{id}",method="GET",status="2xx",le="0.1"} 2"#,
+            r#"test_http_server_request_duration_seconds_bucket{route="/users/{id}",method="GET",status="2xx",le="0.5"} 2"#,
+            r#"test_http_server_request_duration_seconds_bucket{route="/users/{id}",method="GET",status="2xx",le="+Inf"} 2"#,
+            r#"test_http_server_request_duration_seconds_count{route="/users/{id}",method="GET",status="2xx"} 2"#,
+        ] {
+            assert!(
+                body.lines().any(|l| l == line),
+                "missing '{line}' in:\n{body}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_escape_label_value() {
+        assert_eq!(escape_label_value("a\"b\\c\nd"), r#"a\"b\\c\nd"#);
+    }
+}
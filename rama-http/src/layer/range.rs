@@ -0,0 +1,559 @@
+//! Middleware which answers `Range` requests by slicing the response body.
+//!
+//! The [`RangeLayer`] serves `GET` requests with a (bytes) `Range` header
+//! from the full `200 OK` response of the inner service:
+//!
+//! - a single satisfiable range is answered with `206 Partial Content`,
+//!   slicing the response body as it streams, without buffering it;
+//! - multiple satisfiable ranges are answered with a `multipart/byteranges`
+//!   `206 Partial Content` response, for which the response body is buffered,
+//!   unless it exceeds the configured maximum size,
+//!   in which case the full response is returned;
+//! - unsatisfiable ranges are answered with `416 Range Not Satisfiable`.
+//!
+//! This requires the length of the response body to be known upfront,
+//! either as the exact size hint of the body or from its `Content-Length` header.
+//! Responses of unknown length, as well as responses which are already partial
+//! (e.g. served by [`ServeDir`] or a proxied upstream) are passed through as is.
+//!
+//! An `If-Range` header is honored, returning the full response
+//! in case the representation was modified.
+//!
+//! [`ServeDir`]: crate::service::fs::ServeDir
+//!
+//! # Example
+//!
+//! ```
+//! use rama_core::{Context, Layer, Service, service::service_fn};
+//! use rama_http::layer::range::RangeLayer;
+//! use rama_http::{Body, BodyExtractExt, Request, Response, StatusCode, header};
+//! use std::convert::Infallible;
+//!
+//! # #[tokio::main]
+//! # async fn main() {
+//! let service = RangeLayer::new().into_layer(service_fn(async |_: Request| {
+//!     Ok::<_, Infallible>(Response::new(Body::from("hello world")))
+//! }));
+//!
+//! let req = Request::builder()
+//!     .header(header::RANGE, "bytes=6-")
+//!     .body(Body::empty())
+//!     .unwrap();
+//! let resp = service.serve(Context::default(), req).await.unwrap();
+//! assert_eq!(resp.status(), StatusCode::PARTIAL_CONTENT);
+//! assert_eq!(resp.headers()[header::CONTENT_RANGE], "bytes 6-10/11");
+//! assert_eq!(resp.try_into_string().await.unwrap(), "world");
+//! # }
+//! ```
+
+use std::{
+    fmt,
+    ops::RangeInclusive,
+    pin::Pin,
+    task::{Context as TaskContext, Poll},
+};
+
+use crate::dep::http_body::{Body as HttpBody, Frame, SizeHint};
+use crate::layer::util::collect::{LimitedBody, collect_limited};
+use pin_project_lite::pin_project;
+use rama_core::{
+    Context, Layer, Service,
+    bytes::{Buf, Bytes, BytesMut},
+    error::{BoxError, OpaqueError},
+    futures::ready,
+};
+use rama_http_headers::{AcceptRanges, ETag, HeaderMapExt, IfRange, LastModified};
+use rama_http_types::{Body, HeaderValue, Method, Request, Response, StatusCode, header};
+use rama_utils::macros::define_inner_service_accessors;
+
+/// The default maximum size of a response body which is buffered
+/// to answer a request for multiple ranges.
+pub const DEFAULT_MAX_BODY_SIZE: usize = 1024 * 1024;
+
+/// Layer that applies the [`RangeService`] middleware.
+///
+/// See the [module docs](self) for more information.
+#[derive(Debug, Clone)]
+pub struct RangeLayer {
+    max_body_size: usize,
+}
+
+impl Default for RangeLayer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RangeLayer {
+    /// Create a new [`RangeLayer`].
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            max_body_size: DEFAULT_MAX_BODY_SIZE,
+        }
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Set the maximum size of a response body which is buffered
+        /// to answer a request for multiple ranges.
+        ///
+        /// Defaults to [`DEFAULT_MAX_BODY_SIZE`].
+        pub fn max_body_size(mut self, size: usize) -> Self {
+            self.max_body_size = size;
+            self
+        }
+    }
+}
+
+impl<S> Layer<S> for RangeLayer {
+    type Service = RangeService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        RangeService {
+            inner,
+            max_body_size: self.max_body_size,
+        }
+    }
+}
+
+/// Middleware which answers `Range` requests by slicing the response body.
+///
+/// See the [module docs](self) for more information.
+#[derive(Clone)]
+pub struct RangeService<S> {
+    inner: S,
+    max_body_size: usize,
+}
+
+impl<S: fmt::Debug> fmt::Debug for RangeService<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RangeService")
+            .field("inner", &self.inner)
+            .field("max_body_size", &self.max_body_size)
+            .finish()
+    }
+}
+
+impl<S> RangeService<S> {
+    /// Create a new [`RangeService`].
+    pub const fn new(inner: S) -> Self {
+        Self {
+            inner,
+            max_body_size: DEFAULT_MAX_BODY_SIZE,
+        }
+    }
+
+    define_inner_service_accessors!();
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Set the maximum size of a response body which is buffered
+        /// to answer a request for multiple ranges.
+        ///
+        /// Defaults to [`DEFAULT_MAX_BODY_SIZE`].
+        pub fn max_body_size(mut self, size: usize) -> Self {
+            self.max_body_size = size;
+            self
+        }
+    }
+}
+
+impl<S, ReqBody, ResBody> Service<Request<ReqBody>> for RangeService<S>
+where
+    S: Service<Request<ReqBody>, Response = Response<ResBody>, Error: Into<BoxError>>,
+    ReqBody: Send + 'static,
+    ResBody: HttpBody<Data = Bytes, Error: Into<BoxError>> + Send + Sync + 'static,
+{
+    type Response = Response;
+    type Error = BoxError;
+
+    async fn serve(
+        &self,
+        ctx: Context,
+        req: Request<ReqBody>,
+    ) -> Result<Self::Response, Self::Error> {
+        let range = (req.method() == Method::GET)
+            .then(|| req.headers().get(header::RANGE))
+            .flatten()
+            .and_then(|value| value.to_str().ok())
+            .map(ToOwned::to_owned);
+        let if_range = req.headers().typed_get::<IfRange>();
+
+        let resp = self.inner.serve(ctx, req).await.map_err(Into::into)?;
+        if resp.status() != StatusCode::OK
+            || resp.headers().contains_key(header::CONTENT_RANGE)
+            || resp
+                .headers()
+                .typed_get::<AcceptRanges>()
+                .is_some_and(|accept_ranges| accept_ranges.is_none())
+        {
+            return Ok(resp.map(Body::new));
+        }
+        let Some(len) = content_length(&resp) else {
+            return Ok(resp.map(Body::new));
+        };
+
+        let (mut parts, body) = resp.into_parts();
+        parts
+            .headers
+            .insert(header::ACCEPT_RANGES, HeaderValue::from_static("bytes"));
+
+        let Some(range) = range else {
+            return Ok(Response::from_parts(parts, Body::new(body)));
+        };
+        let modified = if_range.is_some_and(|if_range| {
+            if_range.is_modified(
+                parts.headers.typed_get::<ETag>().as_ref(),
+                parts.headers.typed_get::<LastModified>().as_ref(),
+            )
+        });
+        if modified {
+            return Ok(Response::from_parts(parts, Body::new(body)));
+        }
+
+        let ranges = match http_range_header::parse_range_header(&range)
+            .and_then(|ranges| ranges.validate(len))
+        {
+            Ok(ranges) => ranges,
+            Err(_) => {
+                parts.status = StatusCode::RANGE_NOT_SATISFIABLE;
+                parts.headers.insert(
+                    header::CONTENT_RANGE,
+                    HeaderValue::try_from(format!("bytes */{len}"))?,
+                );
+                parts
+                    .headers
+                    .insert(header::CONTENT_LENGTH, HeaderValue::from(0));
+                return Ok(Response::from_parts(parts, Body::empty()));
+            }
+        };
+
+        match ranges.as_slice() {
+            [range] => {
+                let range_len = range.end() - range.start() + 1;
+                parts.status = StatusCode::PARTIAL_CONTENT;
+                parts.headers.insert(
+                    header::CONTENT_RANGE,
+                    HeaderValue::try_from(format!(
+                        "bytes {}-{}/{len}",
+                        range.start(),
+                        range.end()
+                    ))?,
+                );
+                parts
+                    .headers
+                    .insert(header::CONTENT_LENGTH, HeaderValue::from(range_len));
+                let body = SliceBody::new(body, *range.start(), range_len);
+                Ok(Response::from_parts(parts, Body::new(body)))
+            }
+            ranges if len <= self.max_body_size as u64 => {
+                // the content length is not trusted when slicing the collected body
+                let body = match collect_limited(body, self.max_body_size).await? {
+                    LimitedBody::Collected(body) if body.len() as u64 == len => body,
+                    LimitedBody::Collected(body) => {
+                        parts.headers.remove(header::CONTENT_LENGTH);
+                        return Ok(Response::from_parts(parts, Body::from(body)));
+                    }
+                    LimitedBody::Exceeded(body) => {
+                        parts.headers.remove(header::CONTENT_LENGTH);
+                        return Ok(Response::from_parts(parts, body));
+                    }
+                };
+                let content_type = parts.headers.remove(header::CONTENT_TYPE);
+                let boundary = format!("{:016x}", rand::random::<u64>());
+                let body = multipart_byteranges(&body, ranges, content_type.as_ref(), &boundary);
+
+                parts.status = StatusCode::PARTIAL_CONTENT;
+                parts.headers.insert(
+                    header::CONTENT_TYPE,
+                    HeaderValue::try_from(format!("multipart/byteranges; boundary={boundary}"))?,
+                );
+                parts
+                    .headers
+                    .insert(header::CONTENT_LENGTH, HeaderValue::from(body.len()));
+                Ok(Response::from_parts(parts, Body::from(body)))
+            }
+            // too large to buffer: a server is free to ignore the range request
+            _ => Ok(Response::from_parts(parts, Body::new(body))),
+        }
+    }
+}
+
+/// The length of the response body, if known upfront.
+fn content_length<B: HttpBody>(resp: &Response<B>) -> Option<u64> {
+    resp.body().size_hint().exact().or_else(|| {
+        resp.headers()
+            .get(header::CONTENT_LENGTH)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.parse().ok())
+    })
+}
+
+/// Create a `multipart/byteranges` body for the given ranges of the full body.
+fn multipart_byteranges(
+    body: &Bytes,
+    ranges: &[RangeInclusive<u64>],
+    content_type: Option<&HeaderValue>,
+    boundary: &str,
+) -> Bytes {
+    let len = body.len();
+    let mut out = BytesMut::new();
+    for range in ranges {
+        out.extend_from_slice(format!("\r\n--{boundary}\r\n").as_bytes());
+        if let Some(content_type) = content_type {
+            out.extend_from_slice(b"Content-Type: ");
+            out.extend_from_slice(content_type.as_bytes());
+            out.extend_from_slice(b"\r\n");
+        }
+        out.extend_from_slice(
+            format!(
+                "Content-Range: bytes {}-{}/{len}\r\n\r\n",
+                range.start(),
+                range.end()
+            )
+            .as_bytes(),
+        );
+        // ranges are validated against the content length, which matches the body length
+        out.extend_from_slice(&body[*range.start() as usize..=*range.end() as usize]);
+    }
+    out.extend_from_slice(format!("\r\n--{boundary}--\r\n").as_bytes());
+    out.freeze()
+}
+
+pin_project! {
+    /// Body which only yields `len` bytes of the inner body, after skipping `skip` bytes.
+    ///
+    /// Fails in case the inner body ends before these bytes are yielded,
+    /// as the inner body is not trusted to match its declared length.
+    struct SliceBody<B> {
+        #[pin]
+        inner: B,
+        skip: u64,
+        remaining: u64,
+    }
+}
+
+impl<B> SliceBody<B> {
+    fn new(inner: B, skip: u64, len: u64) -> Self {
+        Self {
+            inner,
+            skip,
+            remaining: len,
+        }
+    }
+}
+
+impl<B> HttpBody for SliceBody<B>
+where
+    B: HttpBody<Data = Bytes, Error: Into<BoxError>>,
+{
+    type Data = Bytes;
+    type Error = BoxError;
+
+    fn poll_frame(
+        self: Pin<&mut Self>,
+        cx: &mut TaskContext<'_>,
+    ) -> Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
+        let mut this = self.project();
+        loop {
+            if *this.remaining == 0 {
+                return Poll::Ready(None);
+            }
+            let frame = match ready!(this.inner.as_mut().poll_frame(cx)) {
+                Some(Ok(frame)) => frame,
+                Some(Err(err)) => return Poll::Ready(Some(Err(err.into()))),
+                None => {
+                    // mark the slice as done, such that the error is only returned once
+                    *this.remaining = 0;
+                    return Poll::Ready(Some(Err(OpaqueError::from_display(
+                        "inner body ended before the requested range was complete",
+                    )
+                    .into_boxed())));
+                }
+            };
+            // trailers are not part of a partial response
+            let Ok(mut data) = frame.into_data() else {
+                continue;
+            };
+
+            let skip = (*this.skip).min(data.len() as u64);
+            data.advance(skip as usize);
+            *this.skip -= skip;
+
+            let take = (*this.remaining).min(data.len() as u64);
+            data.truncate(take as usize);
+            *this.remaining -= take;
+
+            if !data.is_empty() {
+                return Poll::Ready(Some(Ok(Frame::data(data))));
+            }
+        }
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.remaining == 0
+    }
+
+    fn size_hint(&self) -> SizeHint {
+        // the inner body might end early, in which case an error is returned instead
+        let mut hint = SizeHint::new();
+        hint.set_upper(self.remaining);
+        hint
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rama_core::service::service_fn;
+    use rama_http_types::{BodyExtractExt, dep::http_body_util::BodyExt};
+    use std::convert::Infallible;
+
+    async fn handle(_: Request) -> Result<Response, Infallible> {
+        Ok(Response::builder()
+            .header(header::CONTENT_TYPE, "text/plain")
+            .header(header::ETAG, "\"v1\"")
+            .body(Body::from_stream(rama_core::futures::stream::iter([
+                Ok::<_, Infallible>(Bytes::from_static(b"hello ")),
+                Ok(Bytes::from_static(b"world")),
+            ])))
+            .unwrap())
+    }
+
+    fn req(range: &str) -> Request {
+        Request::builder()
+            .header(header::RANGE, range)
+            .body(Body::empty())
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_range_unknown_length() {
+        let service = RangeLayer::new().into_layer(service_fn(handle));
+        let resp = service
+            .serve(Context::default(), req("bytes=0-4"))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert!(!resp.headers().contains_key(header::ACCEPT_RANGES));
+    }
+
+    #[tokio::test]
+    async fn test_range_single() {
+        let service = RangeLayer::new().into_layer(service_fn(async |req: Request| {
+            let mut resp = handle(req).await?;
+            resp.headers_mut()
+                .insert(header::CONTENT_LENGTH, HeaderValue::from(11));
+            Ok::<_, Infallible>(resp)
+        }));
+
+        for (range, expected) in [
+            ("bytes=0-4", "hello"),
+            ("bytes=4-7", "o wo"),
+            ("bytes=-3", "rld"),
+        ] {
+            let resp = service.serve(Context::default(), req(range)).await.unwrap();
+            assert_eq!(resp.status(), StatusCode::PARTIAL_CONTENT, "{range}");
+            assert_eq!(
+                resp.headers()[header::CONTENT_LENGTH],
+                expected.len().to_string()
+            );
+            assert_eq!(resp.try_into_string().await.unwrap(), expected, "{range}");
+        }
+
+        let resp = service
+            .serve(Context::default(), req("bytes=20-30"))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::RANGE_NOT_SATISFIABLE);
+        assert_eq!(resp.headers()[header::CONTENT_RANGE], "bytes */11");
+
+        let mut stale = req("bytes=0-4");
+        stale
+            .headers_mut()
+            .insert(header::IF_RANGE, HeaderValue::from_static("\"v0\""));
+        let resp = service.serve(Context::default(), stale).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(resp.headers()[header::ACCEPT_RANGES], "bytes");
+        assert_eq!(resp.try_into_string().await.unwrap(), "hello world");
+    }
+
+    #[tokio::test]
+    async fn test_range_multiple() {
+        let service = RangeLayer::new().into_layer(service_fn(async |_: Request| {
+            Ok::<_, Infallible>(
+                Response::builder()
+                    .header(header::CONTENT_TYPE, "text/plain")
+                    .body(Body::from("hello world"))
+                    .unwrap(),
+            )
+        }));
+
+        let resp = service
+            .serve(Context::default(), req("bytes=0-1, 6-7"))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::PARTIAL_CONTENT);
+        let content_type = resp.headers()[header::CONTENT_TYPE].to_str().unwrap();
+        let boundary = content_type
+            .strip_prefix("multipart/byteranges; boundary=")
+            .unwrap()
+            .to_owned();
+        let body = resp.try_into_string().await.unwrap();
+        assert_eq!(
+            body,
+            format!(
+                "\r\n--{boundary}\r\nContent-Type: text/plain\r\nContent-Range: bytes 0-1/11\r\n\r\nhe\
+                 \r\n--{boundary}\r\nContent-Type: text/plain\r\nContent-Range: bytes 6-7/11\r\n\r\nwo\
+                 \r\n--{boundary}--\r\n"
+            )
+        );
+
+        let service = RangeLayer::new()
+            .with_max_body_size(4)
+            .into_layer(service.into_inner());
+        let resp = service
+            .serve(Context::default(), req("bytes=0-1, 6-7"))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_range_multiple_wrong_content_length() {
+        let service = RangeLayer::new().into_layer(service_fn(async |req: Request| {
+            let mut resp = handle(req).await?;
+            resp.headers_mut()
+                .insert(header::CONTENT_LENGTH, HeaderValue::from(20));
+            Ok::<_, Infallible>(resp)
+        }));
+
+        let resp = service
+            .serve(Context::default(), req("bytes=0-1, 15-19"))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert!(!resp.headers().contains_key(header::CONTENT_LENGTH));
+        assert_eq!(resp.try_into_string().await.unwrap(), "hello world");
+    }
+
+    #[tokio::test]
+    async fn test_range_single_short_body() {
+        let service = RangeLayer::new().into_layer(service_fn(async |req: Request| {
+            let mut resp = handle(req).await?;
+            resp.headers_mut()
+                .insert(header::CONTENT_LENGTH, HeaderValue::from(20));
+            Ok::<_, Infallible>(resp)
+        }));
+
+        let resp = service
+            .serve(Context::default(), req("bytes=6-15"))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::PARTIAL_CONTENT);
+        assert_eq!(resp.headers()[header::CONTENT_RANGE], "bytes 6-15/20");
+        let body = resp.into_body();
+        assert_eq!(body.size_hint().exact(), None);
+        assert_eq!(body.size_hint().upper(), Some(10));
+        assert!(body.collect().await.is_err());
+    }
+}
@@ -0,0 +1,326 @@
+//! Redaction of sensitive data in logs, used by the [`trace`] and [`access_log`] layers.
+//!
+//! A [`Redaction`] describes which parts of a request or response can be logged as-is:
+//!
+//! - only the values of allowlisted headers are logged, all other values
+//!   are replaced by [`REDACTED`] (see [`Redaction::with_allowed_headers`]);
+//! - the values of cookies (`Cookie` and `Set-Cookie`) are scrubbed,
+//!   keeping only their names and attributes;
+//! - the values of query parameters are masked, unless allowlisted;
+//! - body sampling is off, and has to be enabled explicitly
+//!   by setting a sample size (see [`Redaction::with_body_sample_size`]).
+//!
+//! The defaults of [`Redaction::new`] are safe to use for production proxies,
+//! while [`Redaction::disabled`] logs everything as-is, e.g. for local development.
+//!
+//! [`trace`]: crate::layer::trace
+//! [`access_log`]: crate::layer::access_log
+//!
+//! # Example
+//!
+//! ```
+//! use rama_http::layer::redact::Redaction;
+//! use rama_http::{HeaderMap, HeaderValue, Uri, header};
+//!
+//! let redaction = Redaction::new().with_allowed_query_param("page");
+//!
+//! let uri: Uri = "/search?page=2&token=secret".parse().unwrap();
+//! assert_eq!(redaction.redact_uri(&uri), "/search?page=2&token=REDACTED");
+//!
+//! let mut headers = HeaderMap::new();
+//! headers.insert(header::AUTHORIZATION, HeaderValue::from_static("Bearer secret"));
+//! headers.insert(header::COOKIE, HeaderValue::from_static("session=secret; theme=dark"));
+//! let headers = redaction.redact_headers(&headers);
+//! assert_eq!(headers[header::AUTHORIZATION], "REDACTED");
+//! assert_eq!(headers[header::COOKIE], "session=REDACTED; theme=REDACTED");
+//! ```
+
+use rama_http_types::dep::http::uri::PathAndQuery;
+use rama_http_types::{HeaderMap, HeaderName, HeaderValue, Uri, header};
+use std::sync::Arc;
+
+/// The value logged in place of redacted data.
+pub const REDACTED: &str = "REDACTED";
+
+/// The headers of which the values are logged by default.
+pub const DEFAULT_ALLOWED_HEADERS: [HeaderName; 20] = [
+    header::ACCEPT,
+    header::ACCEPT_ENCODING,
+    header::ACCEPT_LANGUAGE,
+    header::ACCEPT_RANGES,
+    header::AGE,
+    header::CACHE_CONTROL,
+    header::CONNECTION,
+    header::CONTENT_ENCODING,
+    header::CONTENT_LENGTH,
+    header::CONTENT_RANGE,
+    header::CONTENT_TYPE,
+    header::COOKIE,
+    header::DATE,
+    header::HOST,
+    header::RANGE,
+    header::SERVER,
+    header::SET_COOKIE,
+    header::TRANSFER_ENCODING,
+    header::USER_AGENT,
+    header::VARY,
+];
+
+#[derive(Debug, Clone)]
+/// Configuration of the data redacted from logs.
+///
+/// See the [module docs](self) for more information.
+pub struct Redaction {
+    // `None` if all headers are allowed
+    allowed_headers: Option<Arc<Vec<HeaderName>>>,
+    scrub_cookies: bool,
+    mask_query: bool,
+    allowed_query_params: Arc<Vec<String>>,
+    body_sample_size: usize,
+}
+
+impl Default for Redaction {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Redaction {
+    /// Create a new [`Redaction`] with safe defaults:
+    /// only the [`DEFAULT_ALLOWED_HEADERS`] are logged, cookies are scrubbed,
+    /// query parameters masked and bodies are not sampled.
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            allowed_headers: Some(Arc::new(DEFAULT_ALLOWED_HEADERS.to_vec())),
+            scrub_cookies: true,
+            mask_query: true,
+            allowed_query_params: Arc::new(Vec::new()),
+            body_sample_size: 0,
+        }
+    }
+
+    /// Create a new [`Redaction`] which does not redact anything.
+    ///
+    /// Bodies are still not sampled, unless a sample size is set.
+    #[must_use]
+    pub fn disabled() -> Self {
+        Self {
+            allowed_headers: None,
+            scrub_cookies: false,
+            mask_query: false,
+            allowed_query_params: Arc::new(Vec::new()),
+            body_sample_size: 0,
+        }
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Set the headers of which the values are logged,
+        /// replacing the [`DEFAULT_ALLOWED_HEADERS`].
+        pub fn allowed_headers(mut self, headers: impl IntoIterator<Item = HeaderName>) -> Self {
+            self.allowed_headers = Some(Arc::new(headers.into_iter().collect()));
+            self
+        }
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Add a header of which the value is logged.
+        pub fn allowed_header(mut self, header: HeaderName) -> Self {
+            if let Some(headers) = self.allowed_headers.as_mut() {
+                Arc::make_mut(headers).push(header);
+            }
+            self
+        }
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Scrub the values of cookies, keeping only their names and attributes.
+        ///
+        /// Enabled by default.
+        pub fn cookie_scrubbing(mut self, scrub: bool) -> Self {
+            self.scrub_cookies = scrub;
+            self
+        }
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Mask the values of query parameters which are not allowlisted.
+        ///
+        /// Enabled by default.
+        pub fn query_masking(mut self, mask: bool) -> Self {
+            self.mask_query = mask;
+            self
+        }
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Add a query parameter of which the value is not masked.
+        pub fn allowed_query_param(mut self, name: impl Into<String>) -> Self {
+            Arc::make_mut(&mut self.allowed_query_params).push(name.into());
+            self
+        }
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Set the maximum amount of body bytes which are logged.
+        ///
+        /// Defaults to `0`, meaning bodies are not sampled.
+        pub fn body_sample_size(mut self, size: usize) -> Self {
+            self.body_sample_size = size;
+            self
+        }
+    }
+
+    /// Returns the maximum amount of body bytes which are logged.
+    #[must_use]
+    pub fn body_sample_size(&self) -> usize {
+        self.body_sample_size
+    }
+
+    /// Returns `true` in case the value of the given header can be logged,
+    /// possibly after scrubbing (see [`Redaction::redact_header_value`]).
+    #[must_use]
+    pub fn is_header_allowed(&self, name: &HeaderName) -> bool {
+        self.allowed_headers
+            .as_ref()
+            .is_none_or(|headers| headers.contains(name))
+    }
+
+    /// Redact the value of the given header.
+    #[must_use]
+    pub fn redact_header_value(&self, name: &HeaderName, value: &HeaderValue) -> HeaderValue {
+        if !self.is_header_allowed(name) {
+            return HeaderValue::from_static(REDACTED);
+        }
+        if self.scrub_cookies && (name == header::COOKIE || name == header::SET_COOKIE) {
+            let Ok(value) = value.to_str() else {
+                return HeaderValue::from_static(REDACTED);
+            };
+            let scrubbed = if name == header::COOKIE {
+                value
+                    .split(';')
+                    .map(|pair| scrub_cookie_pair(pair.trim()))
+                    .collect::<Vec<_>>()
+                    .join("; ")
+            } else {
+                // only the first pair is the cookie, the others are attributes
+                let (cookie, attributes) = value.split_once(';').unwrap_or((value, ""));
+                let cookie = scrub_cookie_pair(cookie.trim());
+                if attributes.is_empty() {
+                    cookie
+                } else {
+                    format!("{cookie};{attributes}")
+                }
+            };
+            return HeaderValue::try_from(scrubbed)
+                .unwrap_or_else(|_| HeaderValue::from_static(REDACTED));
+        }
+        value.clone()
+    }
+
+    /// Redact the values of the given headers.
+    #[must_use]
+    pub fn redact_headers(&self, headers: &HeaderMap) -> HeaderMap {
+        let mut redacted = HeaderMap::with_capacity(headers.len());
+        for (name, value) in headers {
+            redacted.append(name.clone(), self.redact_header_value(name, value));
+        }
+        redacted
+    }
+
+    /// Redact the query of the given [`Uri`].
+    #[must_use]
+    pub fn redact_uri(&self, uri: &Uri) -> Uri {
+        let Some(query) = uri.query().filter(|_| self.mask_query) else {
+            return uri.clone();
+        };
+        let query = self.redact_query(query);
+        let mut parts = uri.clone().into_parts();
+        parts.path_and_query = PathAndQuery::try_from(format!("{}?{query}", uri.path())).ok();
+        Uri::from_parts(parts).unwrap_or_else(|_| uri.clone())
+    }
+
+    /// Redact the given query string (without leading `?`).
+    #[must_use]
+    pub fn redact_query(&self, query: &str) -> String {
+        if !self.mask_query {
+            return query.to_owned();
+        }
+        query
+            .split('&')
+            .map(|pair| match pair.split_once('=') {
+                Some((name, _)) if !self.allowed_query_params.iter().any(|p| p == name) => {
+                    format!("{name}={REDACTED}")
+                }
+                _ => pair.to_owned(),
+            })
+            .collect::<Vec<_>>()
+            .join("&")
+    }
+}
+
+fn scrub_cookie_pair(pair: &str) -> String {
+    match pair.split_once('=') {
+        Some((name, _)) => format!("{name}={REDACTED}"),
+        None => pair.to_owned(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_redact_headers() {
+        let mut headers = HeaderMap::new();
+        headers.insert(header::CONTENT_TYPE, HeaderValue::from_static("text/plain"));
+        headers.insert(
+            header::AUTHORIZATION,
+            HeaderValue::from_static("Basic Zm9v"),
+        );
+        headers.insert(
+            header::SET_COOKIE,
+            HeaderValue::from_static("id=a3fWa; Path=/; HttpOnly"),
+        );
+        headers.insert("x-api-key", HeaderValue::from_static("secret"));
+
+        let redacted = Redaction::new().redact_headers(&headers);
+        assert_eq!(redacted[header::CONTENT_TYPE], "text/plain");
+        assert_eq!(redacted[header::AUTHORIZATION], REDACTED);
+        assert_eq!(
+            redacted[header::SET_COOKIE],
+            "id=REDACTED; Path=/; HttpOnly"
+        );
+        assert_eq!(redacted["x-api-key"], REDACTED);
+
+        let redacted = Redaction::new()
+            .with_allowed_header(HeaderName::from_static("x-api-key"))
+            .with_cookie_scrubbing(false)
+            .redact_headers(&headers);
+        assert_eq!(redacted["x-api-key"], "secret");
+        assert_eq!(redacted[header::SET_COOKIE], "id=a3fWa; Path=/; HttpOnly");
+
+        assert_eq!(Redaction::disabled().redact_headers(&headers), headers);
+    }
+
+    #[test]
+    fn test_redact_uri() {
+        let uri: Uri = "https://example.com/a/b?token=x&page=2&flag"
+            .parse()
+            .unwrap();
+        assert_eq!(
+            Redaction::new().redact_uri(&uri),
+            "https://example.com/a/b?token=REDACTED&page=REDACTED&flag"
+        );
+        assert_eq!(
+            Redaction::new()
+                .with_allowed_query_param("page")
+                .redact_uri(&uri),
+            "https://example.com/a/b?token=REDACTED&page=2&flag"
+        );
+        assert_eq!(Redaction::disabled().redact_uri(&uri), uri);
+
+        let uri: Uri = "/no-query".parse().unwrap();
+        assert_eq!(Redaction::new().redact_uri(&uri), uri);
+    }
+}
@@ -0,0 +1,383 @@
+//! Apply a limit to the response body.
+//!
+//! This is the response counterpart of the [`body_limit`] layer,
+//! useful when proxying untrusted upstreams. Responses exceeding the limit
+//! are either aborted or truncated, depending on the [`ResponseBodyLimitMode`].
+//!
+//! Aborted responses fail with a [`ResponseBodyLimitExceeded`] error:
+//! immediately when the size of the response body is known upfront to exceed the limit,
+//! or as an error of the body stream otherwise, in which case the
+//! [`trace`] layer classifies it as a failure at the end of the stream.
+//!
+//! [`body_limit`]: crate::layer::body_limit
+//! [`trace`]: crate::layer::trace
+//!
+//! # Example
+//!
+//! ```
+//! use rama_http::{Body, Request, Response};
+//! use std::convert::Infallible;
+//! use rama_core::service::service_fn;
+//! use rama_core::{Context, Layer, Service};
+//! use rama_http::layer::response_body_limit::{ResponseBodyLimitLayer, ResponseBodyLimitExceeded};
+//!
+//! async fn handle<B>(_: Request<B>) -> Result<Response, Infallible> {
+//!     Ok(Response::new(Body::from("a body larger than 8 bytes")))
+//! }
+//!
+//! # #[tokio::main]
+//! # async fn main() {
+//! let svc = ResponseBodyLimitLayer::new(8).into_layer(service_fn(handle));
+//!
+//! let err = svc
+//!     .serve(Context::default(), Request::new(Body::empty()))
+//!     .await
+//!     .unwrap_err();
+//! assert!(err.downcast_ref::<ResponseBodyLimitExceeded>().is_some());
+//! # }
+//! ```
+
+use crate::dep::http_body::{Body as HttpBody, Frame, SizeHint};
+use crate::{Body, HeaderValue, Request, Response, header};
+use pin_project_lite::pin_project;
+use rama_core::{Context, Layer, Service, bytes::Bytes, error::BoxError, telemetry::tracing};
+use rama_utils::macros::define_inner_service_accessors;
+use std::{
+    fmt,
+    pin::Pin,
+    task::{Context as TaskContext, Poll},
+};
+
+/// What to do with a response body exceeding the limit.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ResponseBodyLimitMode {
+    /// Fail the response with a [`ResponseBodyLimitExceeded`] error.
+    #[default]
+    Abort,
+    /// Cut the response body off at the limit.
+    Truncate,
+}
+
+/// Error returned when a response body exceeds the limit
+/// in [`ResponseBodyLimitMode::Abort`] mode.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ResponseBodyLimitExceeded {
+    limit: usize,
+}
+
+impl ResponseBodyLimitExceeded {
+    /// The limit, in bytes, which was exceeded.
+    #[must_use]
+    pub fn limit(&self) -> usize {
+        self.limit
+    }
+}
+
+impl fmt::Display for ResponseBodyLimitExceeded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "response body limit of {} bytes exceeded", self.limit)
+    }
+}
+
+impl std::error::Error for ResponseBodyLimitExceeded {}
+
+/// Apply a limit to the response body's size.
+///
+/// See the [module docs](crate::layer::response_body_limit) for an example.
+#[derive(Debug, Clone)]
+pub struct ResponseBodyLimitLayer {
+    size: usize,
+    mode: ResponseBodyLimitMode,
+}
+
+impl ResponseBodyLimitLayer {
+    /// Create a new [`ResponseBodyLimitLayer`],
+    /// aborting responses with a body exceeding the given size.
+    #[must_use]
+    pub const fn new(size: usize) -> Self {
+        Self {
+            size,
+            mode: ResponseBodyLimitMode::Abort,
+        }
+    }
+
+    /// Create a new [`ResponseBodyLimitLayer`],
+    /// truncating response bodies exceeding the given size.
+    #[must_use]
+    pub const fn truncate(size: usize) -> Self {
+        Self {
+            size,
+            mode: ResponseBodyLimitMode::Truncate,
+        }
+    }
+}
+
+impl<S> Layer<S> for ResponseBodyLimitLayer {
+    type Service = ResponseBodyLimitService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        ResponseBodyLimitService {
+            inner,
+            size: self.size,
+            mode: self.mode,
+        }
+    }
+}
+
+/// Apply a limit to the response body.
+///
+/// See the [module docs](crate::layer::response_body_limit) for an example.
+#[derive(Clone)]
+pub struct ResponseBodyLimitService<S> {
+    inner: S,
+    size: usize,
+    mode: ResponseBodyLimitMode,
+}
+
+impl<S> ResponseBodyLimitService<S> {
+    /// Create a new [`ResponseBodyLimitService`],
+    /// aborting responses with a body exceeding the given size.
+    pub const fn new(service: S, size: usize) -> Self {
+        Self {
+            inner: service,
+            size,
+            mode: ResponseBodyLimitMode::Abort,
+        }
+    }
+
+    /// Create a new [`ResponseBodyLimitService`],
+    /// truncating response bodies exceeding the given size.
+    pub const fn truncate(service: S, size: usize) -> Self {
+        Self {
+            inner: service,
+            size,
+            mode: ResponseBodyLimitMode::Truncate,
+        }
+    }
+
+    define_inner_service_accessors!();
+}
+
+impl<S, ReqBody, ResBody> Service<Request<ReqBody>> for ResponseBodyLimitService<S>
+where
+    S: Service<Request<ReqBody>, Response = Response<ResBody>, Error: Into<BoxError>>,
+    ReqBody: Send + 'static,
+    ResBody: HttpBody<Data = Bytes, Error: Into<BoxError>> + Send + Sync + 'static,
+{
+    type Response = Response;
+    type Error = BoxError;
+
+    async fn serve(
+        &self,
+        ctx: Context,
+        req: Request<ReqBody>,
+    ) -> Result<Self::Response, Self::Error> {
+        let mut res = self.inner.serve(ctx, req).await.map_err(Into::into)?;
+
+        let content_length = res
+            .headers()
+            .get(header::CONTENT_LENGTH)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.parse::<u64>().ok())
+            .unwrap_or_else(|| res.body().size_hint().lower());
+        if content_length > self.size as u64 {
+            tracing::debug!(
+                limit = self.size,
+                content_length,
+                mode = ?self.mode,
+                "response body limit exceeded by known body size",
+            );
+            match self.mode {
+                ResponseBodyLimitMode::Abort => {
+                    return Err(ResponseBodyLimitExceeded { limit: self.size }.into());
+                }
+                ResponseBodyLimitMode::Truncate => {
+                    if res.headers().contains_key(header::CONTENT_LENGTH) {
+                        res.headers_mut()
+                            .insert(header::CONTENT_LENGTH, HeaderValue::from(self.size));
+                    }
+                }
+            }
+        }
+
+        let (size, mode) = (self.size, self.mode);
+        Ok(res.map(|body| {
+            Body::new(ResponseBodyLimit {
+                inner: body,
+                remaining: size,
+                limit: size,
+                mode,
+                done: false,
+            })
+        }))
+    }
+}
+
+impl<S> fmt::Debug for ResponseBodyLimitService<S>
+where
+    S: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ResponseBodyLimitService")
+            .field("inner", &self.inner)
+            .field("size", &self.size)
+            .field("mode", &self.mode)
+            .finish()
+    }
+}
+
+pin_project! {
+    /// Response body which enforces a size limit.
+    struct ResponseBodyLimit<B> {
+        #[pin]
+        inner: B,
+        remaining: usize,
+        limit: usize,
+        mode: ResponseBodyLimitMode,
+        done: bool,
+    }
+}
+
+impl<B> HttpBody for ResponseBodyLimit<B>
+where
+    B: HttpBody<Data = Bytes, Error: Into<BoxError>>,
+{
+    type Data = Bytes;
+    type Error = BoxError;
+
+    fn poll_frame(
+        self: Pin<&mut Self>,
+        cx: &mut TaskContext<'_>,
+    ) -> Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
+        let this = self.project();
+        if *this.done {
+            return Poll::Ready(None);
+        }
+
+        let frame = match std::task::ready!(this.inner.poll_frame(cx)) {
+            Some(Ok(frame)) => frame,
+            Some(Err(err)) => return Poll::Ready(Some(Err(err.into()))),
+            None => return Poll::Ready(None),
+        };
+        let mut data = match frame.into_data() {
+            Ok(data) => data,
+            Err(frame) => return Poll::Ready(Some(Ok(frame))),
+        };
+
+        if data.len() <= *this.remaining {
+            *this.remaining -= data.len();
+            return Poll::Ready(Some(Ok(Frame::data(data))));
+        }
+
+        tracing::debug!(
+            limit = *this.limit,
+            mode = ?*this.mode,
+            "response body limit exceeded",
+        );
+        *this.done = true;
+        match this.mode {
+            ResponseBodyLimitMode::Abort => {
+                Poll::Ready(Some(Err(
+                    ResponseBodyLimitExceeded { limit: *this.limit }.into()
+                )))
+            }
+            ResponseBodyLimitMode::Truncate => {
+                data.truncate(*this.remaining);
+                *this.remaining = 0;
+                Poll::Ready(Some(Ok(Frame::data(data))))
+            }
+        }
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.done || self.inner.is_end_stream()
+    }
+
+    fn size_hint(&self) -> SizeHint {
+        let inner = self.inner.size_hint();
+        let remaining = self.remaining as u64;
+        if self.mode == ResponseBodyLimitMode::Abort && inner.lower() > remaining {
+            return SizeHint::default();
+        }
+        let mut hint = SizeHint::new();
+        hint.set_lower(inner.lower().min(remaining));
+        hint.set_upper(inner.upper().unwrap_or(remaining).min(remaining));
+        hint
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dep::http_body_util::{BodyExt, StreamBody};
+    use rama_core::service::service_fn;
+    use rama_http_types::BodyExtractExt;
+    use std::convert::Infallible;
+
+    fn streaming_service() -> impl Service<Request, Response = Response, Error = Infallible> + Clone
+    {
+        service_fn(async |_: Request| {
+            let chunks = ["hello", " ", "world"].map(|chunk| {
+                Ok::<_, Infallible>(Frame::data(Bytes::from_static(chunk.as_bytes())))
+            });
+            Ok::<_, Infallible>(Response::new(Body::new(StreamBody::new(
+                rama_core::futures::stream::iter(chunks),
+            ))))
+        })
+    }
+
+    #[tokio::test]
+    async fn test_response_body_limit_within_limit() {
+        let svc = ResponseBodyLimitLayer::new(11).into_layer(streaming_service());
+        let res = svc
+            .serve(Context::default(), Request::new(Body::empty()))
+            .await
+            .unwrap();
+        assert_eq!(res.try_into_string().await.unwrap(), "hello world");
+    }
+
+    #[tokio::test]
+    async fn test_response_body_limit_abort_stream() {
+        let svc = ResponseBodyLimitLayer::new(8).into_layer(streaming_service());
+        let res = svc
+            .serve(Context::default(), Request::new(Body::empty()))
+            .await
+            .unwrap();
+        let err = res.into_body().collect().await.unwrap_err();
+        assert!(err.downcast_ref::<ResponseBodyLimitExceeded>().is_some());
+    }
+
+    #[tokio::test]
+    async fn test_response_body_limit_abort_content_length() {
+        let svc = ResponseBodyLimitLayer::new(8).into_layer(service_fn(async |_: Request| {
+            Ok::<_, Infallible>(
+                Response::builder()
+                    .header(header::CONTENT_LENGTH, "11")
+                    .body(Body::new(StreamBody::new(
+                        rama_core::futures::stream::empty::<Result<Frame<Bytes>, Infallible>>(),
+                    )))
+                    .unwrap(),
+            )
+        }));
+
+        let err = svc
+            .serve(Context::default(), Request::new(Body::empty()))
+            .await
+            .unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<ResponseBodyLimitExceeded>()
+                .map(ResponseBodyLimitExceeded::limit),
+            Some(8)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_response_body_limit_truncate() {
+        let svc = ResponseBodyLimitLayer::truncate(8).into_layer(streaming_service());
+        let res = svc
+            .serve(Context::default(), Request::new(Body::empty()))
+            .await
+            .unwrap();
+        assert_eq!(res.try_into_string().await.unwrap(), "hello wo");
+    }
+}
@@ -0,0 +1,769 @@
+//! Server-side response caching middleware.
+//!
+//! The [`ResponseCacheLayer`] stores cacheable responses of the inner service
+//! in a [`CacheStore`] and serves subsequent matching requests from that store,
+//! which is useful to absorb repeated traffic, e.g. when running as a reverse proxy.
+//!
+//! Caching is driven by the HTTP semantics of the exchange:
+//!
+//! - only `GET` and `HEAD` requests are cached, and requests with
+//!   `Cache-Control: no-store` bypass the cache entirely, while requests
+//!   with `Cache-Control: no-cache` (or `max-age=0`) are always forwarded;
+//! - responses are only stored when they have a cacheable status code
+//!   and a freshness lifetime (`s-maxage`, `max-age` or the configured default TTL),
+//!   and are never stored when marked `no-store`, `no-cache` or `private`,
+//!   when they set cookies or when they `Vary` on `*`;
+//! - the `Vary` header of a stored response is honored on lookup;
+//! - stale responses within their `stale-while-revalidate` window are served
+//!   immediately, while a fresh response is fetched in the background.
+//!
+//! The key under which a response is stored is created by a [`MakeCacheKey`]
+//! implementation, [`DefaultMakeCacheKey`] by default.
+//!
+//! # Example
+//!
+//! ```
+//! use rama_core::{Context, Layer, Service, service::service_fn};
+//! use rama_http::layer::response_cache::{MemoryCacheStore, ResponseCacheLayer};
+//! use rama_http::{Body, Request, Response, header};
+//! use std::{convert::Infallible, time::Duration};
+//!
+//! # #[tokio::main]
+//! # async fn main() {
+//! let service = ResponseCacheLayer::new(MemoryCacheStore::new(1024))
+//!     .with_stale_while_revalidate(Duration::from_secs(10))
+//!     .into_layer(service_fn(async |_: Request| {
+//!         Ok::<_, Infallible>(
+//!             Response::builder()
+//!                 .header(header::CACHE_CONTROL, "max-age=60")
+//!                 .body(Body::from("hello"))
+//!                 .unwrap(),
+//!         )
+//!     }));
+//!
+//! let req = || Request::builder().uri("http://example.com/").body(Body::empty()).unwrap();
+//!
+//! let resp = service.serve(Context::default(), req()).await.unwrap();
+//! assert!(!resp.headers().contains_key(header::AGE));
+//!
+//! // served from the cache
+//! let resp = service.serve(Context::default(), req()).await.unwrap();
+//! assert!(resp.headers().contains_key(header::AGE));
+//! # }
+//! ```
+
+use std::{
+    collections::HashSet,
+    fmt,
+    sync::{Arc, Mutex},
+    time::{Duration, SystemTime},
+};
+
+use crate::dep::http_body::Body as HttpBody;
+use crate::layer::util::collect::{LimitedBody, collect_limited};
+use rama_core::{
+    Context, Layer, Service,
+    bytes::Bytes,
+    error::{BoxError, OpaqueError},
+    telemetry::tracing,
+};
+use rama_http_headers::{CacheControl, HeaderMapExt};
+use rama_http_types::{
+    Body, HeaderMap, HeaderName, HeaderValue, Method, Request, Response, StatusCode, header,
+};
+
+mod store;
+#[doc(inline)]
+pub use store::{CacheStore, CachedResponse, MemoryCacheStore};
+
+/// The default maximum size of a response body which can be cached.
+pub const DEFAULT_MAX_BODY_SIZE: usize = 1024 * 1024;
+
+/// Create the key used to store and look up the response of a request.
+pub trait MakeCacheKey: Send + Sync + 'static {
+    /// Create the cache key for the given request,
+    /// returning `None` in case the request should not be cached.
+    fn make_cache_key<B>(&self, ctx: &Context, req: &Request<B>) -> Option<String>;
+}
+
+#[derive(Debug, Clone, Default)]
+#[non_exhaustive]
+/// The default [`MakeCacheKey`], keying responses by
+/// the method, host, path and query of the request.
+pub struct DefaultMakeCacheKey;
+
+impl MakeCacheKey for DefaultMakeCacheKey {
+    fn make_cache_key<B>(&self, _ctx: &Context, req: &Request<B>) -> Option<String> {
+        let host = match req.uri().authority() {
+            Some(authority) => authority.as_str(),
+            None => req
+                .headers()
+                .get(header::HOST)
+                .and_then(|host| host.to_str().ok())
+                .unwrap_or_default(),
+        };
+        let path_and_query = req
+            .uri()
+            .path_and_query()
+            .map(|pq| pq.as_str())
+            .unwrap_or("/");
+        Some(format!("{} {host}{path_and_query}", req.method()))
+    }
+}
+
+#[derive(Debug, Clone)]
+struct CacheConfig {
+    default_ttl: Option<Duration>,
+    stale_while_revalidate: Option<Duration>,
+    max_body_size: usize,
+}
+
+/// Layer that applies the [`ResponseCache`] middleware.
+///
+/// See the [module docs](self) for more information.
+pub struct ResponseCacheLayer<St, K = DefaultMakeCacheKey> {
+    store: St,
+    make_key: K,
+    config: CacheConfig,
+}
+
+impl<St> ResponseCacheLayer<St> {
+    /// Create a new [`ResponseCacheLayer`] using the given store.
+    pub fn new(store: St) -> Self {
+        Self {
+            store,
+            make_key: DefaultMakeCacheKey,
+            config: CacheConfig {
+                default_ttl: None,
+                stale_while_revalidate: None,
+                max_body_size: DEFAULT_MAX_BODY_SIZE,
+            },
+        }
+    }
+}
+
+impl<St, K> ResponseCacheLayer<St, K> {
+    /// Use a custom [`MakeCacheKey`] to create the cache key of requests.
+    pub fn with_make_cache_key<K2>(self, make_key: K2) -> ResponseCacheLayer<St, K2> {
+        ResponseCacheLayer {
+            store: self.store,
+            make_key,
+            config: self.config,
+        }
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Cache responses without explicit freshness lifetime
+        /// (`s-maxage` or `max-age`) for the given duration.
+        ///
+        /// Such responses are not cached by default.
+        pub fn default_ttl(mut self, ttl: Duration) -> Self {
+            self.config.default_ttl = Some(ttl);
+            self
+        }
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Serve stale responses for the given duration while they are revalidated
+        /// in the background, for responses which do not define a
+        /// `stale-while-revalidate` directive themselves.
+        ///
+        /// Stale responses are not served by default.
+        pub fn stale_while_revalidate(mut self, window: Duration) -> Self {
+            self.config.stale_while_revalidate = Some(window);
+            self
+        }
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Set the maximum size of a response body which can be cached.
+        ///
+        /// Defaults to [`DEFAULT_MAX_BODY_SIZE`].
+        pub fn max_body_size(mut self, size: usize) -> Self {
+            self.config.max_body_size = size;
+            self
+        }
+    }
+}
+
+impl<St: fmt::Debug, K: fmt::Debug> fmt::Debug for ResponseCacheLayer<St, K> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ResponseCacheLayer")
+            .field("store", &self.store)
+            .field("make_key", &self.make_key)
+            .field("config", &self.config)
+            .finish()
+    }
+}
+
+impl<St: Clone, K: Clone> Clone for ResponseCacheLayer<St, K> {
+    fn clone(&self) -> Self {
+        Self {
+            store: self.store.clone(),
+            make_key: self.make_key.clone(),
+            config: self.config.clone(),
+        }
+    }
+}
+
+impl<S, St: Clone, K: Clone> Layer<S> for ResponseCacheLayer<St, K> {
+    type Service = ResponseCache<S, St, K>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        ResponseCache {
+            inner: Arc::new(inner),
+            store: self.store.clone(),
+            make_key: self.make_key.clone(),
+            config: self.config.clone(),
+            revalidating: Default::default(),
+        }
+    }
+
+    fn into_layer(self, inner: S) -> Self::Service {
+        ResponseCache {
+            inner: Arc::new(inner),
+            store: self.store,
+            make_key: self.make_key,
+            config: self.config,
+            revalidating: Default::default(),
+        }
+    }
+}
+
+/// Middleware which serves cacheable responses from a [`CacheStore`].
+///
+/// See the [module docs](self) for more information.
+pub struct ResponseCache<S, St, K = DefaultMakeCacheKey> {
+    inner: Arc<S>,
+    store: St,
+    make_key: K,
+    config: CacheConfig,
+    revalidating: Arc<Mutex<HashSet<String>>>,
+}
+
+impl<S, St, K> ResponseCache<S, St, K> {
+    /// Gets a reference to the underlying service.
+    pub fn get_ref(&self) -> &S {
+        &self.inner
+    }
+}
+
+impl<S: fmt::Debug, St: fmt::Debug, K: fmt::Debug> fmt::Debug for ResponseCache<S, St, K> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ResponseCache")
+            .field("inner", &self.inner)
+            .field("store", &self.store)
+            .field("make_key", &self.make_key)
+            .field("config", &self.config)
+            .finish()
+    }
+}
+
+impl<S, St: Clone, K: Clone> Clone for ResponseCache<S, St, K> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            store: self.store.clone(),
+            make_key: self.make_key.clone(),
+            config: self.config.clone(),
+            revalidating: self.revalidating.clone(),
+        }
+    }
+}
+
+impl<S, St, K, ReqBody, ResBody> Service<Request<ReqBody>> for ResponseCache<S, St, K>
+where
+    S: Service<Request<ReqBody>, Response = Response<ResBody>, Error: Into<BoxError>>,
+    St: CacheStore + Clone,
+    K: MakeCacheKey,
+    ReqBody: Default + Send + 'static,
+    ResBody: HttpBody<Data = Bytes, Error: Into<BoxError>> + Send + Sync + 'static,
+{
+    type Response = Response;
+    type Error = BoxError;
+
+    async fn serve(
+        &self,
+        ctx: Context,
+        req: Request<ReqBody>,
+    ) -> Result<Self::Response, Self::Error> {
+        let request_cache_control = req.headers().typed_get::<CacheControl>();
+        let key = (req.method() == Method::GET || req.method() == Method::HEAD)
+            && !request_cache_control
+                .as_ref()
+                .is_some_and(|cc| cc.no_store());
+        let Some(key) = key
+            .then(|| self.make_key.make_cache_key(&ctx, &req))
+            .flatten()
+        else {
+            let resp = self.inner.serve(ctx, req).await.map_err(Into::into)?;
+            return Ok(resp.map(Body::new));
+        };
+
+        let skip_lookup = request_cache_control
+            .is_some_and(|cc| cc.no_cache() || cc.max_age() == Some(Duration::ZERO));
+        if !skip_lookup {
+            let now = SystemTime::now();
+            match self.store.get(&key).await {
+                Ok(Some(cached)) if cached.matches_vary(req.headers()) => {
+                    if cached.is_fresh(now) {
+                        tracing::trace!("serve fresh cached response for key {key}");
+                        return Ok(cached.to_response(now));
+                    }
+                    if !cached.is_expired(now) {
+                        tracing::trace!("serve stale cached response for key {key}");
+                        self.revalidate(ctx, &req, key);
+                        return Ok(cached.to_response(now));
+                    }
+                }
+                Ok(_) => (),
+                Err(err) => {
+                    tracing::debug!("failed to lookup cached response for key {key}: {err}");
+                }
+            }
+        }
+
+        let (parts, body) = req.into_parts();
+        let request_headers = parts.headers.clone();
+        let resp = self
+            .inner
+            .serve(ctx, Request::from_parts(parts, body))
+            .await
+            .map_err(Into::into)?;
+        store_response(&self.store, &self.config, key, &request_headers, resp).await
+    }
+}
+
+impl<S, St, K> ResponseCache<S, St, K> {
+    /// Fetch a fresh response for the given request in the background,
+    /// unless this is already happening for the given key.
+    fn revalidate<ReqBody, ResBody>(&self, ctx: Context, req: &Request<ReqBody>, key: String)
+    where
+        S: Service<Request<ReqBody>, Response = Response<ResBody>, Error: Into<BoxError>>,
+        St: CacheStore + Clone,
+        ReqBody: Default + Send + 'static,
+        ResBody: HttpBody<Data = Bytes, Error: Into<BoxError>> + Send + Sync + 'static,
+    {
+        {
+            let mut revalidating = self
+                .revalidating
+                .lock()
+                .unwrap_or_else(|err| err.into_inner());
+            if !revalidating.insert(key.clone()) {
+                return;
+            }
+        }
+
+        let mut builder = Request::builder()
+            .method(req.method().clone())
+            .uri(req.uri().clone())
+            .version(req.version());
+        if let Some(headers) = builder.headers_mut() {
+            *headers = req.headers().clone();
+        }
+        let req = match builder.body(ReqBody::default()) {
+            Ok(req) => req,
+            Err(err) => {
+                tracing::debug!("failed to create revalidation request for key {key}: {err}");
+                self.revalidating
+                    .lock()
+                    .unwrap_or_else(|err| err.into_inner())
+                    .remove(&key);
+                return;
+            }
+        };
+
+        let inner = self.inner.clone();
+        let store = self.store.clone();
+        let config = self.config.clone();
+        let revalidating = self.revalidating.clone();
+        ctx.clone().spawn(async move {
+            let request_headers = req.headers().clone();
+            match inner.serve(ctx, req).await {
+                Ok(resp) => {
+                    if let Err(err) =
+                        store_response(&store, &config, key.clone(), &request_headers, resp).await
+                    {
+                        tracing::debug!(
+                            "failed to store revalidated response for key {key}: {err}"
+                        );
+                    }
+                }
+                Err(err) => {
+                    let err = OpaqueError::from_boxed(err.into());
+                    tracing::debug!("failed to revalidate cached response for key {key}: {err}");
+                }
+            }
+            revalidating
+                .lock()
+                .unwrap_or_else(|err| err.into_inner())
+                .remove(&key);
+        });
+    }
+}
+
+/// Store the response in the cache if it is cacheable,
+/// returning it with its body collected in that case.
+async fn store_response<St, B>(
+    store: &St,
+    config: &CacheConfig,
+    key: String,
+    request_headers: &HeaderMap,
+    resp: Response<B>,
+) -> Result<Response, BoxError>
+where
+    St: CacheStore,
+    B: HttpBody<Data = Bytes, Error: Into<BoxError>> + Send + Sync + 'static,
+{
+    let now = SystemTime::now();
+    let Some((fresh_until, stale_until, vary)) = cache_policy(config, request_headers, &resp, now)
+    else {
+        return Ok(resp.map(Body::new));
+    };
+
+    let (parts, body) = resp.into_parts();
+    let body = match collect_limited(body, config.max_body_size).await? {
+        LimitedBody::Collected(body) => body,
+        LimitedBody::Exceeded(body) => {
+            tracing::trace!("response for key {key} is too large to be cached");
+            return Ok(Response::from_parts(parts, body));
+        }
+    };
+
+    let cached = CachedResponse {
+        status: parts.status,
+        headers: parts.headers.clone(),
+        body: body.clone(),
+        vary,
+        stored_at: now,
+        fresh_until,
+        stale_until,
+    };
+    if let Err(err) = store.put(key.clone(), cached).await {
+        tracing::debug!("failed to store response for key {key}: {err}");
+    }
+    Ok(Response::from_parts(parts, Body::from(body)))
+}
+
+type CachePolicy = (
+    SystemTime,
+    SystemTime,
+    Vec<(HeaderName, Option<HeaderValue>)>,
+);
+
+/// Compute until when the response is fresh and can be served stale,
+/// as well as the request headers it varies on,
+/// returning `None` in case the response is not cacheable.
+fn cache_policy<B>(
+    config: &CacheConfig,
+    request_headers: &HeaderMap,
+    resp: &Response<B>,
+    now: SystemTime,
+) -> Option<CachePolicy> {
+    if !is_cacheable_status(resp.status()) || resp.headers().contains_key(header::SET_COOKIE) {
+        return None;
+    }
+
+    let cache_control = resp.headers().typed_get::<CacheControl>();
+    if cache_control
+        .as_ref()
+        .is_some_and(|cc| cc.no_store() || cc.no_cache() || cc.private())
+    {
+        return None;
+    }
+    if request_headers.contains_key(header::AUTHORIZATION)
+        && !cache_control
+            .as_ref()
+            .is_some_and(|cc| cc.public() || cc.s_max_age().is_some())
+    {
+        return None;
+    }
+
+    let vary = vary_request_headers(request_headers, resp.headers())?;
+
+    let content_length = resp
+        .headers()
+        .get(header::CONTENT_LENGTH)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse::<usize>().ok());
+    if content_length.is_some_and(|len| len > config.max_body_size) {
+        return None;
+    }
+
+    let ttl = cache_control
+        .as_ref()
+        .and_then(|cc| cc.s_max_age().or_else(|| cc.max_age()))
+        .or(config.default_ttl)?;
+    let age = resp
+        .headers()
+        .get(header::AGE)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse::<u64>().ok())
+        .map(Duration::from_secs)
+        .unwrap_or_default();
+    let ttl = ttl.saturating_sub(age);
+    let stale_while_revalidate = stale_while_revalidate(resp.headers())
+        .or(config.stale_while_revalidate)
+        .unwrap_or_default();
+    if ttl.is_zero() && stale_while_revalidate.is_zero() {
+        return None;
+    }
+
+    let fresh_until = now + ttl;
+    Some((fresh_until, fresh_until + stale_while_revalidate, vary))
+}
+
+/// Collect the request headers (and their values) the response varies on,
+/// returning `None` in case it varies on `*` or its `Vary` header is invalid.
+pub(crate) fn vary_request_headers(
+    request_headers: &HeaderMap,
+    response_headers: &HeaderMap,
+) -> Option<Vec<(HeaderName, Option<HeaderValue>)>> {
+    let mut vary = Vec::new();
+    for value in response_headers.get_all(header::VARY) {
+        let value = value.to_str().ok()?;
+        for name in value.split(',').map(str::trim).filter(|s| !s.is_empty()) {
+            if name == "*" {
+                return None;
+            }
+            let name = HeaderName::try_from(name).ok()?;
+            let value = request_headers.get(&name).cloned();
+            vary.push((name, value));
+        }
+    }
+    Some(vary)
+}
+
+/// Status codes which are cacheable by default, as defined in RFC 9110,
+/// except for `206 Partial Content`, as the cache key does not cover the `Range` of requests.
+fn is_cacheable_status(status: StatusCode) -> bool {
+    matches!(
+        status,
+        StatusCode::OK
+            | StatusCode::NON_AUTHORITATIVE_INFORMATION
+            | StatusCode::NO_CONTENT
+            | StatusCode::MULTIPLE_CHOICES
+            | StatusCode::MOVED_PERMANENTLY
+            | StatusCode::PERMANENT_REDIRECT
+            | StatusCode::NOT_FOUND
+            | StatusCode::METHOD_NOT_ALLOWED
+            | StatusCode::GONE
+            | StatusCode::URI_TOO_LONG
+            | StatusCode::NOT_IMPLEMENTED
+    )
+}
+
+/// Parse the `stale-while-revalidate` directive (RFC 5861),
+/// which is not supported by the typed [`CacheControl`] header.
+fn stale_while_revalidate(headers: &HeaderMap) -> Option<Duration> {
+    headers
+        .get_all(header::CACHE_CONTROL)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .find_map(|directive| {
+            let (name, value) = directive.trim().split_once('=')?;
+            name.eq_ignore_ascii_case("stale-while-revalidate")
+                .then(|| value.trim_matches('"').parse().ok())
+                .flatten()
+                .map(Duration::from_secs)
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rama_core::service::service_fn;
+    use rama_http_types::BodyExtractExt;
+    use std::{
+        convert::Infallible,
+        sync::atomic::{AtomicUsize, Ordering},
+    };
+
+    fn counting_service(
+        cache_control: &'static str,
+    ) -> (
+        Arc<AtomicUsize>,
+        impl Service<Request, Response = Response, Error = Infallible>,
+    ) {
+        let counter = Arc::new(AtomicUsize::new(0));
+        let svc_counter = counter.clone();
+        let svc = service_fn(move |req: Request| {
+            let counter = svc_counter.clone();
+            async move {
+                let n = counter.fetch_add(1, Ordering::SeqCst) + 1;
+                let lang = req
+                    .headers()
+                    .get(header::ACCEPT_LANGUAGE)
+                    .and_then(|v| v.to_str().ok())
+                    .unwrap_or("none")
+                    .to_owned();
+                Ok::<_, Infallible>(
+                    Response::builder()
+                        .header(header::CACHE_CONTROL, cache_control)
+                        .header(header::VARY, "accept-language")
+                        .body(Body::from(format!("{lang}:{n}")))
+                        .unwrap(),
+                )
+            }
+        });
+        (counter, svc)
+    }
+
+    fn request(lang: Option<&'static str>) -> Request {
+        let mut builder = Request::builder().uri("http://example.com/foo");
+        if let Some(lang) = lang {
+            builder = builder.header(header::ACCEPT_LANGUAGE, lang);
+        }
+        builder.body(Body::empty()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_response_cache_serves_fresh_responses() {
+        let (counter, svc) = counting_service("max-age=60");
+        let svc = ResponseCacheLayer::new(MemoryCacheStore::new(16)).into_layer(svc);
+
+        for _ in 0..3 {
+            let resp = svc.serve(Context::default(), request(None)).await.unwrap();
+            assert_eq!(resp.try_into_string().await.unwrap(), "none:1");
+        }
+        assert_eq!(counter.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_response_cache_honors_vary() {
+        let (counter, svc) = counting_service("max-age=60");
+        let svc = ResponseCacheLayer::new(MemoryCacheStore::new(16)).into_layer(svc);
+
+        let resp = svc
+            .serve(Context::default(), request(Some("en")))
+            .await
+            .unwrap();
+        assert_eq!(resp.try_into_string().await.unwrap(), "en:1");
+        let resp = svc
+            .serve(Context::default(), request(Some("nl")))
+            .await
+            .unwrap();
+        assert_eq!(resp.try_into_string().await.unwrap(), "nl:2");
+        let resp = svc
+            .serve(Context::default(), request(Some("nl")))
+            .await
+            .unwrap();
+        assert_eq!(resp.try_into_string().await.unwrap(), "nl:2");
+        assert_eq!(counter.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_response_cache_skips_uncacheable_responses() {
+        for cache_control in ["no-store", "no-cache", "private, max-age=60", "public"] {
+            let (counter, svc) = counting_service(cache_control);
+            let svc = ResponseCacheLayer::new(MemoryCacheStore::new(16)).into_layer(svc);
+
+            for _ in 0..2 {
+                svc.serve(Context::default(), request(None)).await.unwrap();
+            }
+            assert_eq!(counter.load(Ordering::SeqCst), 2, "{cache_control}");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_response_cache_streams_large_and_partial_responses() {
+        let counter = Arc::new(AtomicUsize::new(0));
+        let svc_counter = counter.clone();
+        let inner = service_fn(move |req: Request| {
+            svc_counter.fetch_add(1, Ordering::SeqCst);
+            let partial = req.uri().path() == "/partial";
+            async move {
+                let chunks = ["hello", " ", "world", "!"].map(Ok::<_, Infallible>);
+                Ok::<_, Infallible>(
+                    Response::builder()
+                        .status(if partial {
+                            StatusCode::PARTIAL_CONTENT
+                        } else {
+                            StatusCode::OK
+                        })
+                        .header(header::CACHE_CONTROL, "max-age=60")
+                        .body(Body::from_stream(rama_core::futures::stream::iter(chunks)))
+                        .unwrap(),
+                )
+            }
+        });
+        let svc = ResponseCacheLayer::new(MemoryCacheStore::new(16))
+            .with_max_body_size(8)
+            .into_layer(inner.clone());
+
+        for _ in 0..2 {
+            let resp = svc.serve(Context::default(), request(None)).await.unwrap();
+            assert_eq!(resp.try_into_string().await.unwrap(), "hello world!");
+        }
+        assert_eq!(counter.load(Ordering::SeqCst), 2);
+
+        let svc = ResponseCacheLayer::new(MemoryCacheStore::new(16)).into_layer(inner);
+        let partial = || {
+            Request::builder()
+                .uri("http://example.com/partial")
+                .body(Body::empty())
+                .unwrap()
+        };
+        for _ in 0..2 {
+            let resp = svc.serve(Context::default(), partial()).await.unwrap();
+            assert_eq!(resp.status(), StatusCode::PARTIAL_CONTENT);
+        }
+        assert_eq!(counter.load(Ordering::SeqCst), 4);
+    }
+
+    #[tokio::test]
+    async fn test_response_cache_honors_request_cache_control() {
+        let (counter, svc) = counting_service("max-age=60");
+        let svc = ResponseCacheLayer::new(MemoryCacheStore::new(16)).into_layer(svc);
+
+        svc.serve(Context::default(), request(None)).await.unwrap();
+
+        let mut req = request(None);
+        req.headers_mut()
+            .insert(header::CACHE_CONTROL, "no-cache".parse().unwrap());
+        let resp = svc.serve(Context::default(), req).await.unwrap();
+        assert_eq!(resp.try_into_string().await.unwrap(), "none:2");
+
+        let resp = svc.serve(Context::default(), request(None)).await.unwrap();
+        assert_eq!(resp.try_into_string().await.unwrap(), "none:2");
+    }
+
+    #[tokio::test]
+    async fn test_response_cache_stale_while_revalidate() {
+        let (counter, svc) = counting_service("max-age=0, stale-while-revalidate=60");
+        let svc = ResponseCacheLayer::new(MemoryCacheStore::new(16)).into_layer(svc);
+
+        let resp = svc.serve(Context::default(), request(None)).await.unwrap();
+        assert_eq!(resp.try_into_string().await.unwrap(), "none:1");
+
+        let resp = svc.serve(Context::default(), request(None)).await.unwrap();
+        assert_eq!(resp.try_into_string().await.unwrap(), "none:1");
+
+        for _ in 0..100 {
+            let revalidating = svc.revalidating.lock().unwrap().len();
+            if counter.load(Ordering::SeqCst) == 2 && revalidating == 0 {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        assert_eq!(counter.load(Ordering::SeqCst), 2);
+
+        let resp = svc.serve(Context::default(), request(None)).await.unwrap();
+        assert_eq!(resp.try_into_string().await.unwrap(), "none:2");
+    }
+
+    #[test]
+    fn test_parse_stale_while_revalidate() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(stale_while_revalidate(&headers), None);
+        headers.insert(
+            header::CACHE_CONTROL,
+            "max-age=10, Stale-While-Revalidate=30".parse().unwrap(),
+        );
+        assert_eq!(
+            stale_while_revalidate(&headers),
+            Some(Duration::from_secs(30))
+        );
+    }
+}
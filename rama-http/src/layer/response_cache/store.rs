@@ -0,0 +1,265 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::{Arc, Mutex},
+    time::SystemTime,
+};
+
+use rama_core::{bytes::Bytes, error::OpaqueError};
+use rama_http_types::{Body, HeaderMap, HeaderName, HeaderValue, Response, StatusCode, header};
+
+#[derive(Debug, Clone)]
+/// A response as stored in a [`CacheStore`].
+pub struct CachedResponse {
+    /// Status code of the response.
+    pub status: StatusCode,
+    /// Headers of the response.
+    pub headers: HeaderMap,
+    /// The full body of the response.
+    pub body: Bytes,
+    /// The request headers selected by the `Vary` header of the response,
+    /// with the values they had for the request which produced this response.
+    pub vary: Vec<(HeaderName, Option<HeaderValue>)>,
+    /// Time at which the response was stored.
+    pub stored_at: SystemTime,
+    /// Time until which the response is fresh.
+    pub fresh_until: SystemTime,
+    /// Time until which the response can still be served
+    /// while it is being revalidated in the background.
+    pub stale_until: SystemTime,
+}
+
+impl CachedResponse {
+    #[must_use]
+    /// Returns true if the response is fresh at the given time.
+    pub fn is_fresh(&self, now: SystemTime) -> bool {
+        now < self.fresh_until
+    }
+
+    #[must_use]
+    /// Returns true if the response can no longer be served at the given time,
+    /// not even while revalidating.
+    pub fn is_expired(&self, now: SystemTime) -> bool {
+        now >= self.stale_until
+    }
+
+    #[must_use]
+    /// Returns true if the given request headers match
+    /// the headers selected by the `Vary` header of the response.
+    pub fn matches_vary(&self, headers: &HeaderMap) -> bool {
+        self.vary
+            .iter()
+            .all(|(name, value)| headers.get(name) == value.as_ref())
+    }
+
+    pub(crate) fn to_response(&self, now: SystemTime) -> Response {
+        let mut resp = Response::new(Body::from(self.body.clone()));
+        *resp.status_mut() = self.status;
+        *resp.headers_mut() = self.headers.clone();
+        let age = now
+            .duration_since(self.stored_at)
+            .unwrap_or_default()
+            .as_secs();
+        resp.headers_mut()
+            .insert(header::AGE, HeaderValue::from(age));
+        resp
+    }
+}
+
+/// Storage backend for cached responses.
+pub trait CacheStore: Send + Sync + 'static {
+    /// Get the response stored for the given key, if any.
+    fn get(
+        &self,
+        key: &str,
+    ) -> impl Future<Output = Result<Option<CachedResponse>, OpaqueError>> + Send;
+
+    /// Store a response for the given key, replacing any previous response.
+    fn put(
+        &self,
+        key: String,
+        response: CachedResponse,
+    ) -> impl Future<Output = Result<(), OpaqueError>> + Send;
+
+    /// Remove the response stored for the given key.
+    fn remove(&self, key: &str) -> impl Future<Output = Result<(), OpaqueError>> + Send;
+}
+
+impl<S: CacheStore> CacheStore for Arc<S> {
+    fn get(
+        &self,
+        key: &str,
+    ) -> impl Future<Output = Result<Option<CachedResponse>, OpaqueError>> + Send {
+        self.as_ref().get(key)
+    }
+
+    fn put(
+        &self,
+        key: String,
+        response: CachedResponse,
+    ) -> impl Future<Output = Result<(), OpaqueError>> + Send {
+        self.as_ref().put(key, response)
+    }
+
+    fn remove(&self, key: &str) -> impl Future<Output = Result<(), OpaqueError>> + Send {
+        self.as_ref().remove(key)
+    }
+}
+
+#[derive(Debug, Clone)]
+/// An in-memory [`CacheStore`], evicting the least recently used
+/// response once its capacity is reached.
+///
+/// Expired responses are evicted lazily, when looked up.
+/// Cloning this store shares the underlying responses.
+pub struct MemoryCacheStore {
+    state: Arc<Mutex<LruState>>,
+}
+
+#[derive(Debug)]
+struct LruState {
+    capacity: usize,
+    tick: u64,
+    entries: HashMap<String, (CachedResponse, u64)>,
+    order: BTreeMap<u64, String>,
+}
+
+impl LruState {
+    fn touch(&mut self, key: &str) {
+        self.tick += 1;
+        let tick = self.tick;
+        if let Some((_, last_used)) = self.entries.get_mut(key) {
+            if let Some(key) = self.order.remove(last_used) {
+                self.order.insert(tick, key);
+            }
+            *last_used = tick;
+        }
+    }
+
+    fn remove(&mut self, key: &str) {
+        if let Some((_, last_used)) = self.entries.remove(key) {
+            self.order.remove(&last_used);
+        }
+    }
+}
+
+impl MemoryCacheStore {
+    #[must_use]
+    /// Create a new empty [`MemoryCacheStore`],
+    /// holding at most `capacity` responses.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            state: Arc::new(Mutex::new(LruState {
+                capacity: capacity.max(1),
+                tick: 0,
+                entries: HashMap::new(),
+                order: BTreeMap::new(),
+            })),
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, LruState> {
+        self.state.lock().unwrap_or_else(|err| err.into_inner())
+    }
+
+    #[must_use]
+    /// Returns the number of responses currently stored.
+    pub fn len(&self) -> usize {
+        self.lock().entries.len()
+    }
+
+    #[must_use]
+    /// Returns true if no responses are currently stored.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl CacheStore for MemoryCacheStore {
+    async fn get(&self, key: &str) -> Result<Option<CachedResponse>, OpaqueError> {
+        let mut state = self.lock();
+        match state.entries.get(key) {
+            None => Ok(None),
+            Some((response, _)) if response.is_expired(SystemTime::now()) => {
+                state.remove(key);
+                Ok(None)
+            }
+            Some((response, _)) => {
+                let response = response.clone();
+                state.touch(key);
+                Ok(Some(response))
+            }
+        }
+    }
+
+    async fn put(&self, key: String, response: CachedResponse) -> Result<(), OpaqueError> {
+        let mut state = self.lock();
+        state.remove(&key);
+        while state.entries.len() >= state.capacity {
+            let Some((_, lru_key)) = state.order.pop_first() else {
+                break;
+            };
+            state.entries.remove(&lru_key);
+        }
+        state.tick += 1;
+        let tick = state.tick;
+        state.order.insert(tick, key.clone());
+        state.entries.insert(key, (response, tick));
+        Ok(())
+    }
+
+    async fn remove(&self, key: &str) -> Result<(), OpaqueError> {
+        self.lock().remove(key);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn response(ttl: Duration) -> CachedResponse {
+        let now = SystemTime::now();
+        CachedResponse {
+            status: StatusCode::OK,
+            headers: HeaderMap::new(),
+            body: Bytes::from_static(b"hello"),
+            vary: Vec::new(),
+            stored_at: now,
+            fresh_until: now + ttl,
+            stale_until: now + ttl,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_memory_cache_store_evicts_least_recently_used() {
+        let store = MemoryCacheStore::new(2);
+        let ttl = Duration::from_secs(60);
+
+        store.put("a".to_owned(), response(ttl)).await.unwrap();
+        store.put("b".to_owned(), response(ttl)).await.unwrap();
+        assert!(store.get("a").await.unwrap().is_some());
+
+        store.put("c".to_owned(), response(ttl)).await.unwrap();
+        assert_eq!(store.len(), 2);
+        assert!(store.get("a").await.unwrap().is_some());
+        assert!(store.get("b").await.unwrap().is_none());
+        assert!(store.get("c").await.unwrap().is_some());
+
+        store.remove("a").await.unwrap();
+        store.remove("c").await.unwrap();
+        assert!(store.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_memory_cache_store_evicts_expired() {
+        let store = MemoryCacheStore::new(2);
+
+        store
+            .put("a".to_owned(), response(Duration::ZERO))
+            .await
+            .unwrap();
+        assert!(store.get("a").await.unwrap().is_none());
+        assert!(store.is_empty());
+    }
+}
@@ -5,10 +5,12 @@
 //! [`Policy`]: super::Policy
 
 use super::{Policy, PolicyResult, RetryBody};
-use crate::{Request, Response};
-use rama_core::Context;
+use crate::{Method, Request, Response};
+use rama_core::error::{BoxError, Classification, ClassifiedError, ErrorKind, OpaqueError};
 use rama_core::telemetry::tracing;
+use rama_core::{Context, context::Deadline};
 use rama_utils::backoff::Backoff;
+use std::any::Any;
 
 #[derive(Debug, Clone, Default)]
 /// An [`Extensions`] value that can be added to the [`Context`]
@@ -26,6 +28,13 @@ pub struct DoNotRetry;
 /// [`DoNotRetry`] can be added to the [`Context`] of a [`Request`]
 /// to signal that the request should not be retried, regardless
 /// of the retry functionality defined.
+///
+/// A request is no longer retried once its [`Deadline`] expires,
+/// including while waiting for the backoff.
+///
+/// By default server error responses and all errors are retried.
+/// Use [`ClassifiedRetry`] as the retry rule to no longer retry
+/// errors classified as not retryable, see [`ClassifiedError::classify`].
 pub struct ManagedPolicy<B = Undefined, C = Undefined, R = Undefined> {
     backoff: B,
     clone: C,
@@ -51,8 +60,8 @@ where
             return PolicyResult::Abort(result);
         }
 
-        let (ctx, result, retry) = self.retry.retry(ctx, result).await;
-        if retry && self.backoff.next_backoff().await {
+        let (ctx, result, retry) = self.retry.retry_request(ctx, &req, result).await;
+        if retry && self.next_backoff(&ctx).await {
             PolicyResult::Retry { ctx, req }
         } else {
             self.backoff.reset().await;
@@ -73,6 +82,23 @@ where
     }
 }
 
+impl<B: Backoff, C, R> ManagedPolicy<B, C, R> {
+    /// Wait for the next backoff, giving up in case
+    /// the [`Deadline`] of the request expires first.
+    async fn next_backoff(&self, ctx: &Context) -> bool {
+        match Deadline::from_ctx(ctx) {
+            Some(deadline) => tokio::select! {
+                retry = self.backoff.next_backoff() => retry && !deadline.is_expired(),
+                _ = deadline.expired() => {
+                    tracing::debug!("not retrying: deadline expires during backoff");
+                    false
+                }
+            },
+            None => self.backoff.next_backoff().await,
+        }
+    }
+}
+
 impl<B, C, R> std::fmt::Debug for ManagedPolicy<B, C, R>
 where
     B: std::fmt::Debug,
@@ -168,6 +194,20 @@ pub trait RetryRule<R, E>: private::Sealed<(R, E)> + Send + Sync + 'static {
         ctx: Context,
         result: Result<R, E>,
     ) -> impl Future<Output = (Context, Result<R, E>, bool)> + Send + '_;
+
+    /// Check if the given result for the given request should be retried.
+    ///
+    /// This is the check used by the [`ManagedPolicy`],
+    /// which defaults to [`RetryRule::retry`].
+    fn retry_request(
+        &self,
+        ctx: Context,
+        req: &Request<RetryBody>,
+        result: Result<R, E>,
+    ) -> impl Future<Output = (Context, Result<R, E>, bool)> + Send + '_ {
+        let _ = req;
+        self.retry(ctx, result)
+    }
 }
 
 impl<Body, E> RetryRule<Response<Body>, E> for Undefined
@@ -201,6 +241,102 @@ where
     }
 }
 
+#[derive(Debug, Clone, Default)]
+/// A [`RetryRule`] which retries server error responses and errors,
+/// like the default rule of the [`ManagedPolicy`], except for errors
+/// classified as not retryable, see [`ClassifiedError::classify`].
+///
+/// E.g. connect and timeout errors are retried, while protocol errors are not.
+/// Upstream errors, such as a connection reset by the server, are only retried
+/// for idempotent requests (e.g. `GET` or `PUT`), as a non-idempotent request
+/// (e.g. `POST`) might already have been processed by the server. Errors classified
+/// as [`ErrorKind::Connect`] happen before the request is sent and are retried regardless.
+///
+/// As the request method is not known when using [`RetryRule::retry`] directly,
+/// upstream errors are not retried in that case.
+pub struct ClassifiedRetry;
+
+impl ClassifiedRetry {
+    async fn retry_classified<Body, E>(
+        ctx: Context,
+        result: Result<Response<Body>, E>,
+        idempotent: bool,
+    ) -> (Context, Result<Response<Body>, E>, bool)
+    where
+        E: std::fmt::Debug + Send + Sync + 'static,
+        Body: Send + 'static,
+    {
+        if let Err(error) = &result
+            && let Some(class) = classify(error)
+        {
+            if !class.is_retryable() {
+                tracing::debug!(
+                    "not retrying non-retryable {} error: {:?}",
+                    class.kind(),
+                    error
+                );
+                return (ctx, result, false);
+            }
+            if class.kind() == ErrorKind::Upstream && !idempotent {
+                tracing::debug!(
+                    "not retrying upstream error of non-idempotent request: {:?}",
+                    error
+                );
+                return (ctx, result, false);
+            }
+        }
+        Undefined.retry(ctx, result).await
+    }
+}
+
+impl<Body, E> RetryRule<Response<Body>, E> for ClassifiedRetry
+where
+    E: std::fmt::Debug + Send + Sync + 'static,
+    Body: Send + 'static,
+{
+    async fn retry(
+        &self,
+        ctx: Context,
+        result: Result<Response<Body>, E>,
+    ) -> (Context, Result<Response<Body>, E>, bool) {
+        Self::retry_classified(ctx, result, false).await
+    }
+
+    fn retry_request(
+        &self,
+        ctx: Context,
+        req: &Request<RetryBody>,
+        result: Result<Response<Body>, E>,
+    ) -> impl Future<Output = (Context, Result<Response<Body>, E>, bool)> + Send + '_ {
+        let idempotent = is_idempotent(req.method());
+        Self::retry_classified(ctx, result, idempotent)
+    }
+}
+
+/// Returns true if the method is idempotent,
+/// see [RFC 9110, section 9.2.2](https://www.rfc-editor.org/rfc/rfc9110#section-9.2.2).
+fn is_idempotent(method: &Method) -> bool {
+    matches!(
+        *method,
+        Method::GET | Method::HEAD | Method::OPTIONS | Method::TRACE | Method::PUT | Method::DELETE
+    )
+}
+
+/// Classify the error in case it is one of the error types of rama,
+/// see [`ClassifiedError::classify`].
+fn classify<E: 'static>(error: &E) -> Option<Classification> {
+    let error = error as &dyn Any;
+    if let Some(error) = error.downcast_ref::<BoxError>() {
+        ClassifiedError::classify(error.as_ref())
+    } else if let Some(error) = error.downcast_ref::<OpaqueError>() {
+        ClassifiedError::classify(error)
+    } else if let Some(error) = error.downcast_ref::<ClassifiedError>() {
+        ClassifiedError::classify(error)
+    } else {
+        None
+    }
+}
+
 impl<F, Fut, R, E> RetryRule<R, E> for F
 where
     F: Fn(Context, Result<R, E>) -> Fut + Send + Sync + 'static,
@@ -281,6 +417,7 @@ mod private {
     pub trait Sealed<S> {}
 
     impl<S> Sealed<S> for Undefined {}
+    impl<S> Sealed<S> for ClassifiedRetry {}
     impl<F> Sealed<()> for F where
         F: Fn(&Context, &Request<RetryBody>) -> Option<(Context, Request<RetryBody>)>
             + Send
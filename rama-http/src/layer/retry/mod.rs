@@ -4,7 +4,7 @@ use crate::Request;
 use crate::dep::http_body::Body as HttpBody;
 use crate::dep::http_body_util::BodyExt;
 use rama_core::error::BoxError;
-use rama_core::{Context, Service};
+use rama_core::{Context, Service, context::Deadline};
 use rama_utils::macros::define_inner_service_accessors;
 
 mod layer;
@@ -137,6 +137,14 @@ where
 
         loop {
             let resp = self.inner.serve(ctx, request).await;
+            // no use in retrying once the caller's deadline has passed
+            if cloned
+                .as_ref()
+                .and_then(|(ctx, _)| Deadline::from_ctx(ctx))
+                .is_some_and(|deadline| deadline.is_expired())
+            {
+                cloned = None;
+            }
             match cloned.take() {
                 Some((cloned_ctx, cloned_req)) => {
                     let (cloned_ctx, cloned_req) =
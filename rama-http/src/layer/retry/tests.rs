@@ -1,10 +1,11 @@
+use super::managed::ClassifiedRetry;
 use super::*;
 use crate::BodyExtractExt;
 use crate::service::web::response::IntoResponse;
 use crate::{Request, Response};
 use parking_lot::Mutex;
-use rama_core::error::{OpaqueError, error};
-use rama_core::{Layer, Service};
+use rama_core::error::{ClassifiedError, ErrorExt, OpaqueError, error};
+use rama_core::{Layer, Service, context::Deadline};
 use std::sync::{
     Arc,
     atomic::{AtomicBool, AtomicUsize, Ordering},
@@ -88,7 +89,8 @@ async fn retry_limit() {
         .await
         .unwrap_err();
     assert_eq!(err.to_string(), "service error: error forever");
-    assert_eq!(error_counter.load(Ordering::Acquire), 3);
+    // each attempt takes at least 10ms, so at most 3 attempts fit within the deadline
+    assert!((1..=3).contains(&error_counter.load(Ordering::Acquire)));
 }
 
 #[tokio::test]
@@ -224,6 +226,143 @@ async fn retry_mutating_policy() {
     assert_eq!(response_counter.load(Ordering::Acquire), 3);
 }
 
+#[tokio::test]
+async fn retry_stops_at_deadline() {
+    struct Svc {
+        error_counter: Arc<AtomicUsize>,
+    }
+
+    impl Service<Request<RetryBody>> for Svc {
+        type Response = Response;
+        type Error = OpaqueError;
+
+        async fn serve(
+            &self,
+            _ctx: Context,
+            _req: Request<RetryBody>,
+        ) -> Result<Self::Response, Self::Error> {
+            self.error_counter.fetch_add(1, Ordering::AcqRel);
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+            Err(error!("error forever"))
+        }
+    }
+
+    let error_counter = Arc::new(AtomicUsize::new(0));
+
+    let svc = RetryLayer::new(RetryErrors).into_layer(Svc {
+        error_counter: error_counter.clone(),
+    });
+
+    let mut ctx = Context::default();
+    Deadline::after(std::time::Duration::from_millis(25)).apply(&mut ctx);
+
+    let err = svc.serve(ctx, request("hello")).await.unwrap_err();
+    assert_eq!(err.to_string(), "service error: error forever");
+    // each attempt takes at least 10ms, so at most 3 attempts fit within the deadline
+    assert!((1..=3).contains(&error_counter.load(Ordering::Acquire)));
+}
+
+#[tokio::test]
+async fn managed_policy_error_classification() {
+    struct Svc {
+        error_counter: Arc<AtomicUsize>,
+    }
+
+    impl Service<Request<RetryBody>> for Svc {
+        type Response = Response;
+        type Error = OpaqueError;
+
+        async fn serve(
+            &self,
+            _ctx: Context,
+            _req: Request<RetryBody>,
+        ) -> Result<Self::Response, Self::Error> {
+            match self.error_counter.fetch_add(1, Ordering::AcqRel) {
+                0 => Err(ClassifiedError::connect("connection refused").into_opaque()),
+                1 => Err(ClassifiedError::protocol("invalid response")
+                    .context("read response")
+                    .into_opaque()),
+                _ => Ok("ok".into_response()),
+            }
+        }
+    }
+
+    let error_counter = Arc::new(AtomicUsize::new(0));
+
+    let svc = RetryLayer::new(ManagedPolicy::new(ClassifiedRetry)).into_layer(Svc {
+        error_counter: error_counter.clone(),
+    });
+
+    let err = svc
+        .serve(Context::default(), request("hello"))
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("invalid response"), "{err}");
+    // the connect error is retried, the protocol error is not
+    assert_eq!(error_counter.load(Ordering::Acquire), 2);
+
+    // without opting in, all errors are retried
+    let error_counter = Arc::new(AtomicUsize::new(0));
+    let svc = RetryLayer::new(ManagedPolicy::default()).into_layer(Svc {
+        error_counter: error_counter.clone(),
+    });
+    let resp = svc
+        .serve(Context::default(), request("hello"))
+        .await
+        .unwrap();
+    assert_eq!(resp.try_into_string().await.unwrap(), "ok");
+    assert_eq!(error_counter.load(Ordering::Acquire), 3);
+}
+
+#[tokio::test]
+async fn managed_policy_upstream_error_idempotency() {
+    struct Svc {
+        error_counter: Arc<AtomicUsize>,
+    }
+
+    impl Service<Request<RetryBody>> for Svc {
+        type Response = Response;
+        type Error = OpaqueError;
+
+        async fn serve(
+            &self,
+            _ctx: Context,
+            _req: Request<RetryBody>,
+        ) -> Result<Self::Response, Self::Error> {
+            match self.error_counter.fetch_add(1, Ordering::AcqRel) {
+                0 => Err(ClassifiedError::upstream("connection reset").into_opaque()),
+                _ => Ok("ok".into_response()),
+            }
+        }
+    }
+
+    // a non-idempotent request might already be processed, so it is not retried
+    let error_counter = Arc::new(AtomicUsize::new(0));
+    let svc = RetryLayer::new(ManagedPolicy::new(ClassifiedRetry)).into_layer(Svc {
+        error_counter: error_counter.clone(),
+    });
+    let err = svc
+        .serve(Context::default(), request("hello"))
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("connection reset"), "{err}");
+    assert_eq!(error_counter.load(Ordering::Acquire), 1);
+
+    // while an idempotent request is
+    let error_counter = Arc::new(AtomicUsize::new(0));
+    let svc = RetryLayer::new(ManagedPolicy::new(ClassifiedRetry)).into_layer(Svc {
+        error_counter: error_counter.clone(),
+    });
+    let req = Request::builder()
+        .method("PUT")
+        .uri("http://localhost")
+        .body(RetryBody::new("hello".into()))
+        .unwrap();
+    let resp = svc.serve(Context::default(), req).await.unwrap();
+    assert_eq!(resp.try_into_string().await.unwrap(), "ok");
+    assert_eq!(error_counter.load(Ordering::Acquire), 2);
+}
+
 type InnerError = &'static str;
 type Error = rama_core::error::OpaqueError;
 
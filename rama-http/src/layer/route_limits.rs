@@ -0,0 +1,344 @@
+//! Middleware to apply timeout, body-limit and concurrency settings per route.
+//!
+//! The [`RouteLimitsLayer`] is configured with default [`RouteLimits`],
+//! which apply to all requests, and any number of overrides, each attached to a [`Matcher`]
+//! (e.g. an [`HttpMatcher`] matching a path prefix). The limits of the first matching override
+//! are used, with unset limits inherited from the defaults.
+//!
+//! - The timeout applies to the inner service producing a response,
+//!   answering with `504 Gateway Timeout` once elapsed.
+//! - The body limit applies to the request body, such that reading
+//!   a larger body results in an error for the inner service.
+//! - The concurrency limit is tracked separately for each override which sets one,
+//!   answering with `503 Service Unavailable` once reached. Overrides without
+//!   a concurrency limit of their own share the one of the defaults.
+//!
+//! [`HttpMatcher`]: crate::matcher::HttpMatcher
+//!
+//! # Example
+//!
+//! ```
+//! use rama_core::{Context, Layer, Service, service::service_fn};
+//! use rama_http::layer::route_limits::{RouteLimits, RouteLimitsLayer};
+//! use rama_http::matcher::HttpMatcher;
+//! use rama_http::{Body, Request, Response, StatusCode};
+//! use std::{convert::Infallible, time::Duration};
+//!
+//! # #[tokio::main]
+//! # async fn main() {
+//! let service = RouteLimitsLayer::new(
+//!     RouteLimits::new()
+//!         .with_timeout(Duration::from_secs(10))
+//!         .with_max_body_size(64 * 1024),
+//! )
+//! .with_route(
+//!     HttpMatcher::path("/upload/*"),
+//!     RouteLimits::new()
+//!         .with_timeout(Duration::from_secs(600))
+//!         .with_max_body_size(1024 * 1024 * 1024)
+//!         .with_concurrency_limit(4),
+//! )
+//! .into_layer(service_fn(async |_: Request| {
+//!     Ok::<_, Infallible>(Response::new(Body::empty()))
+//! }));
+//!
+//! let req = Request::post("/upload/video").body(Body::empty()).unwrap();
+//! let resp = service.serve(Context::default(), req).await.unwrap();
+//! assert_eq!(resp.status(), StatusCode::OK);
+//! # }
+//! ```
+
+use std::{fmt, sync::Arc, time::Duration};
+
+use crate::dep::http_body_util::Limited;
+use crate::service::web::response::IntoResponse;
+use rama_core::{
+    Context, Layer, Service,
+    bytes::Bytes,
+    context::Extensions,
+    error::BoxError,
+    layer::limit::policy::{ConcurrentCounter, ConcurrentTracker},
+    matcher::Matcher,
+};
+use rama_http_types::{Body, Request, Response, StatusCode};
+use rama_utils::macros::define_inner_service_accessors;
+
+#[derive(Debug, Clone, Default)]
+/// Timeout, body-limit and concurrency settings applied to a route.
+///
+/// Unset limits of an override are inherited from the defaults
+/// of the [`RouteLimitsLayer`], and are not applied in case those are unset as well.
+pub struct RouteLimits {
+    timeout: Option<Duration>,
+    max_body_size: Option<usize>,
+    concurrency: Option<ConcurrentCounter>,
+}
+
+impl RouteLimits {
+    /// Create new [`RouteLimits`], without any limit set.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Set the timeout for the inner service to produce a response.
+        pub fn timeout(mut self, timeout: Duration) -> Self {
+            self.timeout = Some(timeout);
+            self
+        }
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Set the maximum size of the request body.
+        pub fn max_body_size(mut self, size: usize) -> Self {
+            self.max_body_size = Some(size);
+            self
+        }
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Set the maximum number of concurrent requests.
+        pub fn concurrency_limit(mut self, max: usize) -> Self {
+            self.concurrency = Some(ConcurrentCounter::new(max));
+            self
+        }
+    }
+
+    fn or(&self, defaults: &Self) -> Self {
+        Self {
+            timeout: self.timeout.or(defaults.timeout),
+            max_body_size: self.max_body_size.or(defaults.max_body_size),
+            concurrency: self
+                .concurrency
+                .clone()
+                .or_else(|| defaults.concurrency.clone()),
+        }
+    }
+}
+
+/// Layer that applies the [`RouteLimitsService`] middleware.
+///
+/// See the [module docs](self) for more information.
+#[derive(Debug, Clone)]
+pub struct RouteLimitsLayer<M> {
+    defaults: RouteLimits,
+    routes: Vec<(M, RouteLimits)>,
+}
+
+impl<M> RouteLimitsLayer<M> {
+    /// Create a new [`RouteLimitsLayer`], applying the given limits to all requests.
+    #[must_use]
+    pub fn new(defaults: RouteLimits) -> Self {
+        Self {
+            defaults,
+            routes: Vec::new(),
+        }
+    }
+
+    /// Override the default limits for the requests matched by the given [`Matcher`].
+    ///
+    /// Overrides are evaluated in the order they are added, the first match wins.
+    #[must_use]
+    pub fn with_route(mut self, matcher: M, limits: RouteLimits) -> Self {
+        self.set_route(matcher, limits);
+        self
+    }
+
+    /// Override the default limits for the requests matched by the given [`Matcher`].
+    ///
+    /// Overrides are evaluated in the order they are added, the first match wins.
+    pub fn set_route(&mut self, matcher: M, limits: RouteLimits) -> &mut Self {
+        self.routes.push((matcher, limits));
+        self
+    }
+}
+
+impl<S, M: Clone> Layer<S> for RouteLimitsLayer<M> {
+    type Service = RouteLimitsService<S, M>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        RouteLimitsService {
+            inner,
+            defaults: self.defaults.clone(),
+            routes: Arc::new(self.routes.clone()),
+        }
+    }
+
+    fn into_layer(self, inner: S) -> Self::Service {
+        RouteLimitsService {
+            inner,
+            defaults: self.defaults,
+            routes: Arc::new(self.routes),
+        }
+    }
+}
+
+/// Middleware to apply timeout, body-limit and concurrency settings per route.
+///
+/// See the [module docs](self) for more information.
+pub struct RouteLimitsService<S, M> {
+    inner: S,
+    defaults: RouteLimits,
+    routes: Arc<Vec<(M, RouteLimits)>>,
+}
+
+impl<S, M> RouteLimitsService<S, M> {
+    define_inner_service_accessors!();
+}
+
+impl<S: fmt::Debug, M: fmt::Debug> fmt::Debug for RouteLimitsService<S, M> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RouteLimitsService")
+            .field("inner", &self.inner)
+            .field("defaults", &self.defaults)
+            .field("routes", &self.routes)
+            .finish()
+    }
+}
+
+impl<S: Clone, M> Clone for RouteLimitsService<S, M> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            defaults: self.defaults.clone(),
+            routes: self.routes.clone(),
+        }
+    }
+}
+
+impl<S, M, ReqBody> Service<Request<ReqBody>> for RouteLimitsService<S, M>
+where
+    S: Service<Request<Body>, Response = Response>,
+    M: Matcher<Request<ReqBody>>,
+    ReqBody: rama_http_types::dep::http_body::Body<Data = Bytes, Error: Into<BoxError>>
+        + Send
+        + Sync
+        + 'static,
+{
+    type Response = Response;
+    type Error = S::Error;
+
+    async fn serve(
+        &self,
+        mut ctx: Context,
+        req: Request<ReqBody>,
+    ) -> Result<Self::Response, Self::Error> {
+        let mut ext = Extensions::new();
+        let mut limits = None;
+        for (matcher, route_limits) in self.routes.iter() {
+            if matcher.matches(Some(&mut ext), &ctx, &req) {
+                ctx.extend(ext);
+                limits = Some(route_limits.or(&self.defaults));
+                break;
+            }
+            ext.clear();
+        }
+        let limits = limits.as_ref().unwrap_or(&self.defaults);
+
+        let _guard = match limits.concurrency.as_ref().map(|c| c.try_access()) {
+            Some(Err(_)) => return Ok(StatusCode::SERVICE_UNAVAILABLE.into_response()),
+            Some(Ok(guard)) => Some(guard),
+            None => None,
+        };
+
+        let req = req.map(|body| match limits.max_body_size {
+            Some(size) => Body::new(Limited::new(body, size)),
+            None => Body::new(body),
+        });
+
+        match limits.timeout {
+            Some(timeout) => tokio::select! {
+                res = self.inner.serve(ctx, req) => res,
+                _ = tokio::time::sleep(timeout) => Ok(StatusCode::GATEWAY_TIMEOUT.into_response()),
+            },
+            None => self.inner.serve(ctx, req).await,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dep::http_body_util::BodyExt;
+    use crate::matcher::HttpMatcher;
+    use rama_core::service::service_fn;
+    use std::convert::Infallible;
+
+    fn service() -> impl Service<Request, Response = Response, Error = Infallible> {
+        RouteLimitsLayer::new(
+            RouteLimits::new()
+                .with_timeout(Duration::from_millis(50))
+                .with_max_body_size(4),
+        )
+        .with_route(
+            HttpMatcher::path("/upload/*"),
+            RouteLimits::new().with_max_body_size(1024),
+        )
+        .with_route(
+            HttpMatcher::path("/slow"),
+            RouteLimits::new()
+                .with_timeout(Duration::from_secs(60))
+                .with_concurrency_limit(1),
+        )
+        .into_layer(service_fn(async |req: Request| {
+            if req.uri().path() == "/slow" {
+                tokio::time::sleep(Duration::from_millis(100)).await;
+            }
+            let status = match req.into_body().collect().await {
+                Ok(_) => StatusCode::OK,
+                Err(_) => StatusCode::PAYLOAD_TOO_LARGE,
+            };
+            Ok(status.into_response())
+        }))
+    }
+
+    fn req(path: &str, body: &'static str) -> Request {
+        Request::post(path).body(Body::from(body)).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_route_limits_body_size() {
+        let service = service();
+
+        let resp = service
+            .serve(Context::default(), req("/", "small"))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::PAYLOAD_TOO_LARGE);
+
+        let resp = service
+            .serve(Context::default(), req("/upload/file", "small"))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_route_limits_timeout_and_concurrency() {
+        let service = Arc::new(service());
+
+        let (first, second) =
+            tokio::join!(service.serve(Context::default(), req("/slow", "")), async {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+                service.serve(Context::default(), req("/slow", "")).await
+            },);
+        // the route overrides the default timeout,
+        // and limits the number of concurrent requests
+        assert_eq!(first.unwrap().status(), StatusCode::OK);
+        assert_eq!(second.unwrap().status(), StatusCode::SERVICE_UNAVAILABLE);
+
+        let service = RouteLimitsLayer::<HttpMatcher<Body>>::new(
+            RouteLimits::new().with_timeout(Duration::from_millis(10)),
+        )
+        .into_layer(service_fn(async |_: Request| {
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            Ok::<_, Infallible>(Response::new(Body::empty()))
+        }));
+        let resp = service
+            .serve(Context::default(), req("/", ""))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::GATEWAY_TIMEOUT);
+    }
+}
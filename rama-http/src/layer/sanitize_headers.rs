@@ -0,0 +1,422 @@
+//! Middleware to sanitize the headers of proxied requests and responses.
+//!
+//! A proxy is only allowed to forward end-to-end headers, as the hop-by-hop headers
+//! only apply to a single connection ([RFC 9110, section 7.6.1]). The [`SanitizeHeadersLayer`]
+//! applies this to the requests and responses passing through it:
+//!
+//! - the `Connection` header and all headers listed within it are removed;
+//! - the `Keep-Alive`, `Trailer`, `Transfer-Encoding` and `Proxy-*` headers are removed;
+//! - the `TE` header is removed, unless its only value is `trailers` (as allowed by http/2, e.g. for gRPC);
+//! - the `Upgrade` header is removed, unless upgrades are honored, see [`SanitizeHeadersLayer::with_honor_upgrade`];
+//! - header values are trimmed from surrounding whitespace and control characters are replaced by a space;
+//! - requests with a header exceeding the size cap are refused with `431 Request Header Fields Too Large`,
+//!   while responses with such a header are replaced by a `502 Bad Gateway`.
+//!
+//! The `EasyHttpWebClient` of the `rama` crate, the forwarding service used by its proxy stacks,
+//! applies this sanitization by default, honoring upgrades. It can be opted out
+//! of using `EasyHttpWebClient::without_header_sanitization`, in which case this layer can
+//! be used to apply it elsewhere in the stack. The same sanitization is also available
+//! without a service using [`SanitizeHeadersLayer::sanitize_request`]
+//! and [`SanitizeHeadersLayer::sanitize_response`].
+//!
+//! [RFC 9110, section 7.6.1]: https://www.rfc-editor.org/rfc/rfc9110#section-7.6.1
+//!
+//! # Example
+//!
+//! ```
+//! use rama_core::{Context, Layer, Service, service::service_fn};
+//! use rama_http::layer::sanitize_headers::SanitizeHeadersLayer;
+//! use rama_http::{Body, Request, Response, StatusCode, header};
+//! use std::convert::Infallible;
+//!
+//! # #[tokio::main]
+//! # async fn main() {
+//! let service = SanitizeHeadersLayer::new()
+//!     .with_max_header_size(64)
+//!     .into_layer(service_fn(async |req: Request| {
+//!         assert!(!req.headers().contains_key("x-hop"));
+//!         assert_eq!(req.headers()["x-end-to-end"], "value");
+//!         Ok::<_, Infallible>(Response::new(Body::empty()))
+//!     }));
+//!
+//! let req = Request::builder()
+//!     .header(header::CONNECTION, "x-hop")
+//!     .header("x-hop", "value")
+//!     .header("x-end-to-end", " value ")
+//!     .body(Body::empty())
+//!     .unwrap();
+//! let resp = service.serve(Context::default(), req).await.unwrap();
+//! assert_eq!(resp.status(), StatusCode::OK);
+//!
+//! let req = Request::builder()
+//!     .header("x-large", "a".repeat(64))
+//!     .body(Body::empty())
+//!     .unwrap();
+//! let resp = service.serve(Context::default(), req).await.unwrap();
+//! assert_eq!(resp.status(), StatusCode::REQUEST_HEADER_FIELDS_TOO_LARGE);
+//! # }
+//! ```
+
+use crate::service::web::response::IntoResponse;
+use rama_core::{Context, Layer, Service, telemetry::tracing};
+use rama_http_types::{HeaderMap, HeaderName, HeaderValue, Request, Response, StatusCode, header};
+use rama_utils::macros::define_inner_service_accessors;
+use std::fmt;
+
+/// Default maximum size of a single header, name and value combined.
+pub const DEFAULT_MAX_HEADER_SIZE: usize = 8 * 1024;
+
+/// Layer that applies the [`SanitizeHeaders`] middleware.
+///
+/// See the [module docs](self) for more information.
+#[derive(Debug, Clone)]
+pub struct SanitizeHeadersLayer {
+    honor_upgrade: bool,
+    max_header_size: usize,
+}
+
+impl Default for SanitizeHeadersLayer {
+    fn default() -> Self {
+        Self {
+            honor_upgrade: false,
+            max_header_size: DEFAULT_MAX_HEADER_SIZE,
+        }
+    }
+}
+
+impl SanitizeHeadersLayer {
+    /// Create a new [`SanitizeHeadersLayer`], not honoring upgrades
+    /// and using the [`DEFAULT_MAX_HEADER_SIZE`].
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Honor upgrades, keeping the `Upgrade` header of requests
+        /// and of `101 Switching Protocols` responses, together with `Connection: upgrade`.
+        ///
+        /// Only enable this in case the inner service is able to proxy upgraded connections.
+        pub fn honor_upgrade(mut self, honor: bool) -> Self {
+            self.honor_upgrade = honor;
+            self
+        }
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Set the maximum size of a single header, name and value combined.
+        pub fn max_header_size(mut self, size: usize) -> Self {
+            self.max_header_size = size;
+            self
+        }
+    }
+
+    /// Sanitize the headers of the given request in place.
+    ///
+    /// Returns the name of the first header exceeding the size cap as an error,
+    /// in which case the request should be refused.
+    pub fn sanitize_request<B>(&self, req: &mut Request<B>) -> Result<(), HeaderName> {
+        self.sanitize(req.headers_mut(), true)
+    }
+
+    /// Sanitize the headers of the given response in place.
+    ///
+    /// Returns the name of the first header exceeding the size cap as an error,
+    /// in which case the response should not be forwarded.
+    pub fn sanitize_response<B>(&self, resp: &mut Response<B>) -> Result<(), HeaderName> {
+        let upgrade = resp.status() == StatusCode::SWITCHING_PROTOCOLS;
+        self.sanitize(resp.headers_mut(), upgrade)
+    }
+
+    /// Sanitize the headers in place, returning the name of
+    /// the first header exceeding the size cap, if any.
+    fn sanitize(&self, headers: &mut HeaderMap, upgrade: bool) -> Result<(), HeaderName> {
+        let keep_upgrade = upgrade && self.honor_upgrade && headers.contains_key(header::UPGRADE);
+
+        let listed: Vec<_> = headers
+            .get_all(header::CONNECTION)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(','))
+            .filter_map(|name| HeaderName::from_bytes(name.trim().as_bytes()).ok())
+            .collect();
+        for name in listed {
+            if !(keep_upgrade && name == header::UPGRADE) {
+                headers.remove(name);
+            }
+        }
+
+        for name in [
+            &header::CONNECTION,
+            &header::KEEP_ALIVE,
+            &header::TRAILER,
+            &header::TRANSFER_ENCODING,
+        ] {
+            headers.remove(name);
+        }
+        if keep_upgrade {
+            headers.insert(header::CONNECTION, HeaderValue::from_static("upgrade"));
+        } else {
+            headers.remove(header::UPGRADE);
+        }
+        if !headers
+            .get_all(header::TE)
+            .iter()
+            .all(|value| value.as_bytes().eq_ignore_ascii_case(b"trailers"))
+        {
+            headers.remove(header::TE);
+        }
+        let proxy_headers: Vec<_> = headers
+            .keys()
+            // `HeaderName::as_str` is always lowercase
+            .filter(|name| name.as_str().starts_with("proxy-"))
+            .cloned()
+            .collect();
+        for name in proxy_headers {
+            headers.remove(name);
+        }
+
+        for (name, value) in headers.iter_mut() {
+            if let Some(normalized) = normalize_header_value(value) {
+                *value = normalized;
+            }
+            if name.as_str().len() + value.len() > self.max_header_size {
+                return Err(name.clone());
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<S> Layer<S> for SanitizeHeadersLayer {
+    type Service = SanitizeHeaders<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        SanitizeHeaders {
+            inner,
+            config: self.clone(),
+        }
+    }
+}
+
+/// Middleware which sanitizes the headers of proxied requests and responses.
+///
+/// See the [module docs](self) for more information.
+pub struct SanitizeHeaders<S> {
+    inner: S,
+    config: SanitizeHeadersLayer,
+}
+
+impl<S> SanitizeHeaders<S> {
+    /// Create a new [`SanitizeHeaders`] using the default configuration,
+    /// see [`SanitizeHeadersLayer::new`].
+    pub fn new(inner: S) -> Self {
+        SanitizeHeadersLayer::new().into_layer(inner)
+    }
+
+    define_inner_service_accessors!();
+}
+
+impl<S: fmt::Debug> fmt::Debug for SanitizeHeaders<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SanitizeHeaders")
+            .field("inner", &self.inner)
+            .field("config", &self.config)
+            .finish()
+    }
+}
+
+impl<S: Clone> Clone for SanitizeHeaders<S> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            config: self.config.clone(),
+        }
+    }
+}
+
+impl<S, ReqBody> Service<Request<ReqBody>> for SanitizeHeaders<S>
+where
+    S: Service<Request<ReqBody>, Response = Response>,
+    ReqBody: Send + 'static,
+{
+    type Response = Response;
+    type Error = S::Error;
+
+    async fn serve(
+        &self,
+        ctx: Context,
+        mut req: Request<ReqBody>,
+    ) -> Result<Self::Response, Self::Error> {
+        if let Err(name) = self.config.sanitize_request(&mut req) {
+            tracing::debug!(
+                http.header.name = %name,
+                "refuse request with header exceeding the size cap"
+            );
+            return Ok(StatusCode::REQUEST_HEADER_FIELDS_TOO_LARGE.into_response());
+        }
+
+        let mut resp = self.inner.serve(ctx, req).await?;
+        if let Err(name) = self.config.sanitize_response(&mut resp) {
+            tracing::debug!(
+                http.header.name = %name,
+                "refuse response with header exceeding the size cap"
+            );
+            return Ok(StatusCode::BAD_GATEWAY.into_response());
+        }
+        Ok(resp)
+    }
+}
+
+/// Returns the normalized header value in case the given one is not,
+/// trimmed from surrounding whitespace and without control characters.
+fn normalize_header_value(value: &HeaderValue) -> Option<HeaderValue> {
+    let forbidden = |b: u8| b.is_ascii_control() && b != b'\t';
+
+    let bytes = value.as_bytes();
+    let trimmed = bytes.trim_ascii();
+    if trimmed.len() == bytes.len() && !trimmed.iter().copied().any(forbidden) {
+        return None;
+    }
+
+    let normalized: Vec<u8> = trimmed
+        .iter()
+        .map(|&b| if forbidden(b) { b' ' } else { b })
+        .collect();
+    let mut normalized = HeaderValue::from_bytes(&normalized).ok()?;
+    normalized.set_sensitive(value.is_sensitive());
+    Some(normalized)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rama_core::service::service_fn;
+    use rama_http_types::Body;
+    use std::convert::Infallible;
+
+    #[tokio::test]
+    async fn test_sanitize_request_headers() {
+        let service = SanitizeHeadersLayer::new().into_layer(service_fn(async |req: Request| {
+            let headers = req.headers();
+            for name in [
+                "connection",
+                "x-hop",
+                "keep-alive",
+                "te",
+                "trailer",
+                "upgrade",
+                "proxy-authorization",
+                "proxy-connection",
+            ] {
+                assert!(!headers.contains_key(name), "{name}");
+            }
+            assert_eq!(headers["x-end-to-end"], "a\tb");
+            Ok::<_, Infallible>(
+                Response::builder()
+                    .header(header::CONNECTION, "close")
+                    .header(header::PROXY_AUTHENTICATE, "Basic")
+                    .header(header::CONTENT_TYPE, "text/plain")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+        }));
+
+        let req = Request::builder()
+            .header(header::CONNECTION, "keep-alive, X-Hop")
+            .header("x-hop", "value")
+            .header(header::KEEP_ALIVE, "timeout=5")
+            .header(header::TE, "gzip, trailers")
+            .header(header::TRAILER, "expires")
+            .header(header::UPGRADE, "websocket")
+            .header(header::PROXY_AUTHORIZATION, "Basic dXNlcjpwYXNz")
+            .header("proxy-connection", "keep-alive")
+            .header("x-end-to-end", "\ta\tb ")
+            .body(Body::empty())
+            .unwrap();
+
+        let resp = service.serve(Context::default(), req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert!(!resp.headers().contains_key(header::CONNECTION));
+        assert!(!resp.headers().contains_key(header::PROXY_AUTHENTICATE));
+        assert_eq!(resp.headers()[header::CONTENT_TYPE], "text/plain");
+    }
+
+    #[tokio::test]
+    async fn test_sanitize_honor_upgrade() {
+        let service = SanitizeHeadersLayer::new()
+            .with_honor_upgrade(true)
+            .into_layer(service_fn(async |req: Request| {
+                assert_eq!(req.headers()[header::UPGRADE], "websocket");
+                assert_eq!(req.headers()[header::CONNECTION], "upgrade");
+                assert_eq!(req.headers()[header::TE], "trailers");
+                Ok::<_, Infallible>(
+                    Response::builder()
+                        .status(StatusCode::SWITCHING_PROTOCOLS)
+                        .header(header::CONNECTION, "Upgrade")
+                        .header(header::UPGRADE, "websocket")
+                        .body(Body::empty())
+                        .unwrap(),
+                )
+            }));
+
+        let req = Request::builder()
+            .header(header::CONNECTION, "Upgrade, keep-alive")
+            .header(header::UPGRADE, "websocket")
+            .header(header::TE, "trailers")
+            .body(Body::empty())
+            .unwrap();
+
+        let resp = service.serve(Context::default(), req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::SWITCHING_PROTOCOLS);
+        assert_eq!(resp.headers()[header::UPGRADE], "websocket");
+        assert_eq!(resp.headers()[header::CONNECTION], "upgrade");
+    }
+
+    #[tokio::test]
+    async fn test_sanitize_max_header_size() {
+        let service = SanitizeHeadersLayer::new()
+            .with_max_header_size(32)
+            .into_layer(service_fn(async |req: Request| {
+                let value = req.headers()["x-value"].clone();
+                Ok::<_, Infallible>(
+                    Response::builder()
+                        .header("x-value", value)
+                        .body(Body::empty())
+                        .unwrap(),
+                )
+            }));
+
+        let req = |value: &str| {
+            Request::builder()
+                .header("x-value", value)
+                .body(Body::empty())
+                .unwrap()
+        };
+
+        let resp = service
+            .serve(Context::default(), req("fits within the cap"))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        let resp = service
+            .serve(Context::default(), req(&"a".repeat(32)))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::REQUEST_HEADER_FIELDS_TOO_LARGE);
+    }
+
+    #[test]
+    fn test_normalize_header_value() {
+        assert!(normalize_header_value(&HeaderValue::from_static("a\tb")).is_none());
+        assert_eq!(
+            normalize_header_value(&HeaderValue::from_static(" a\tb\t")).unwrap(),
+            "a\tb"
+        );
+        assert_eq!(
+            normalize_header_value(&HeaderValue::from_bytes(b"caf\xc3\xa9 ").unwrap()).unwrap(),
+            HeaderValue::from_bytes(b"caf\xc3\xa9").unwrap(),
+        );
+    }
+}
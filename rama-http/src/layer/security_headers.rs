@@ -0,0 +1,426 @@
+//! Middleware to add security headers to responses.
+//!
+//! Modern browsers support a number of response headers which protect users
+//! against common attacks (e.g. protocol downgrades, clickjacking, MIME sniffing and cross-site leaks).
+//! The [`SecurityHeadersLayer`] adds a configurable set of those to every response,
+//! rather than having each service maintain them by hand.
+//!
+//! [`SecurityHeadersLayer::strict`] is a preset suitable for most web services, which
+//! can be adapted further using the builder methods, e.g. to allow additional sources in the
+//! `Content-Security-Policy`. Headers already set by the inner service are kept,
+//! unless [`SecurityHeadersLayer::with_overwrite`] is enabled.
+//!
+//! # Example
+//!
+//! ```
+//! use rama_core::{Context, Layer, Service, service::service_fn};
+//! use rama_http::layer::security_headers::{Hsts, SecurityHeadersLayer};
+//! use rama_http::{Body, Request, Response, header};
+//! use std::convert::Infallible;
+//!
+//! # #[tokio::main]
+//! # async fn main() {
+//! let service = SecurityHeadersLayer::strict()
+//!     .with_hsts(Hsts::preload())
+//!     .into_layer(service_fn(async |_: Request| {
+//!         Ok::<_, Infallible>(Response::new(Body::empty()))
+//!     }));
+//!
+//! let resp = service
+//!     .serve(Context::default(), Request::new(Body::empty()))
+//!     .await
+//!     .unwrap();
+//! assert_eq!(
+//!     resp.headers()[header::STRICT_TRANSPORT_SECURITY],
+//!     "max-age=63072000; includeSubDomains; preload",
+//! );
+//! assert_eq!(resp.headers()[header::X_FRAME_OPTIONS], "DENY");
+//! # }
+//! ```
+
+use std::{fmt, sync::Arc, time::Duration};
+
+use rama_core::{Context, Layer, Service};
+use rama_http_types::{HeaderName, HeaderValue, Request, Response, header};
+use rama_utils::macros::define_inner_service_accessors;
+
+const CROSS_ORIGIN_OPENER_POLICY: HeaderName =
+    HeaderName::from_static("cross-origin-opener-policy");
+const CROSS_ORIGIN_RESOURCE_POLICY: HeaderName =
+    HeaderName::from_static("cross-origin-resource-policy");
+const PERMISSIONS_POLICY: HeaderName = HeaderName::from_static("permissions-policy");
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// Configuration of the `Strict-Transport-Security` (HSTS) header.
+pub struct Hsts {
+    max_age: Duration,
+    include_subdomains: bool,
+    preload: bool,
+}
+
+impl Hsts {
+    /// Create a new [`Hsts`] configuration with the given max age,
+    /// not including subdomains and without preload.
+    #[must_use]
+    pub const fn new(max_age: Duration) -> Self {
+        Self {
+            max_age,
+            include_subdomains: false,
+            preload: false,
+        }
+    }
+
+    /// Create a new [`Hsts`] configuration which meets the requirements
+    /// of the HSTS preload list: a max age of two years, including subdomains and with preload.
+    ///
+    /// Note that a domain on the preload list is hard to get off of it,
+    /// so only use this once all subdomains are served over HTTPS.
+    #[must_use]
+    pub const fn preload() -> Self {
+        Self {
+            max_age: Duration::from_secs(2 * 365 * 24 * 60 * 60),
+            include_subdomains: true,
+            preload: true,
+        }
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Apply the policy to all subdomains as well.
+        pub fn include_subdomains(mut self, include: bool) -> Self {
+            self.include_subdomains = include;
+            self
+        }
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Signal consent to have the domain included in the HSTS preload list of browsers.
+        pub fn preload_directive(mut self, preload: bool) -> Self {
+            self.preload = preload;
+            self
+        }
+    }
+
+    fn header_value(&self) -> HeaderValue {
+        let mut value = format!("max-age={}", self.max_age.as_secs());
+        if self.include_subdomains {
+            value.push_str("; includeSubDomains");
+        }
+        if self.preload {
+            value.push_str("; preload");
+        }
+        HeaderValue::try_from(value).expect("hsts header value to be valid")
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// The value of the `X-Frame-Options` header.
+pub enum FrameOptions {
+    /// The response cannot be displayed in a frame.
+    Deny,
+    /// The response can only be displayed in a frame on the same origin.
+    SameOrigin,
+}
+
+impl FrameOptions {
+    fn header_value(self) -> HeaderValue {
+        match self {
+            Self::Deny => HeaderValue::from_static("DENY"),
+            Self::SameOrigin => HeaderValue::from_static("SAMEORIGIN"),
+        }
+    }
+}
+
+/// Layer that applies the [`SecurityHeaders`] middleware.
+///
+/// See the [module docs](self) for more information.
+#[derive(Debug, Clone, Default)]
+pub struct SecurityHeadersLayer {
+    hsts: Option<Hsts>,
+    content_security_policy: Option<HeaderValue>,
+    frame_options: Option<FrameOptions>,
+    content_type_nosniff: bool,
+    referrer_policy: Option<HeaderValue>,
+    permissions_policy: Option<HeaderValue>,
+    cross_origin_opener_policy: Option<HeaderValue>,
+    cross_origin_resource_policy: Option<HeaderValue>,
+    overwrite: bool,
+}
+
+impl SecurityHeadersLayer {
+    /// Create a new [`SecurityHeadersLayer`] without any security header configured.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a new [`SecurityHeadersLayer`] with a strict preset:
+    ///
+    /// - `Strict-Transport-Security: max-age=63072000; includeSubDomains`
+    /// - `Content-Security-Policy: default-src 'self'; base-uri 'self'; form-action 'self'; frame-ancestors 'none'; object-src 'none'`
+    /// - `X-Frame-Options: DENY`
+    /// - `X-Content-Type-Options: nosniff`
+    /// - `Referrer-Policy: strict-origin-when-cross-origin`
+    /// - `Permissions-Policy: camera=(), geolocation=(), microphone=()`
+    /// - `Cross-Origin-Opener-Policy: same-origin`
+    /// - `Cross-Origin-Resource-Policy: same-origin`
+    ///
+    /// Preload is not enabled for HSTS, see [`Hsts::preload`] to opt in.
+    #[must_use]
+    pub fn strict() -> Self {
+        Self {
+            hsts: Some(Hsts::preload().with_preload_directive(false)),
+            content_security_policy: Some(HeaderValue::from_static(
+                "default-src 'self'; base-uri 'self'; form-action 'self'; frame-ancestors 'none'; object-src 'none'",
+            )),
+            frame_options: Some(FrameOptions::Deny),
+            content_type_nosniff: true,
+            referrer_policy: Some(HeaderValue::from_static("strict-origin-when-cross-origin")),
+            permissions_policy: Some(HeaderValue::from_static(
+                "camera=(), geolocation=(), microphone=()",
+            )),
+            cross_origin_opener_policy: Some(HeaderValue::from_static("same-origin")),
+            cross_origin_resource_policy: Some(HeaderValue::from_static("same-origin")),
+            overwrite: false,
+        }
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Set the `Strict-Transport-Security` header.
+        pub fn hsts(mut self, hsts: Option<Hsts>) -> Self {
+            self.hsts = hsts;
+            self
+        }
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Set the `Content-Security-Policy` header.
+        pub fn content_security_policy(mut self, policy: Option<HeaderValue>) -> Self {
+            self.content_security_policy = policy;
+            self
+        }
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Set the `X-Frame-Options` header.
+        pub fn frame_options(mut self, options: Option<FrameOptions>) -> Self {
+            self.frame_options = options;
+            self
+        }
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Set the `X-Content-Type-Options: nosniff` header.
+        pub fn content_type_nosniff(mut self, nosniff: bool) -> Self {
+            self.content_type_nosniff = nosniff;
+            self
+        }
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Set the `Referrer-Policy` header.
+        pub fn referrer_policy(mut self, policy: Option<HeaderValue>) -> Self {
+            self.referrer_policy = policy;
+            self
+        }
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Set the `Permissions-Policy` header.
+        pub fn permissions_policy(mut self, policy: Option<HeaderValue>) -> Self {
+            self.permissions_policy = policy;
+            self
+        }
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Set the `Cross-Origin-Opener-Policy` header.
+        pub fn cross_origin_opener_policy(mut self, policy: Option<HeaderValue>) -> Self {
+            self.cross_origin_opener_policy = policy;
+            self
+        }
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Set the `Cross-Origin-Resource-Policy` header.
+        pub fn cross_origin_resource_policy(mut self, policy: Option<HeaderValue>) -> Self {
+            self.cross_origin_resource_policy = policy;
+            self
+        }
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Overwrite the headers already set by the inner service.
+        ///
+        /// Disabled by default.
+        pub fn overwrite(mut self, overwrite: bool) -> Self {
+            self.overwrite = overwrite;
+            self
+        }
+    }
+
+    fn headers(&self) -> Vec<(HeaderName, HeaderValue)> {
+        [
+            (
+                header::STRICT_TRANSPORT_SECURITY,
+                self.hsts.as_ref().map(Hsts::header_value),
+            ),
+            (
+                header::CONTENT_SECURITY_POLICY,
+                self.content_security_policy.clone(),
+            ),
+            (
+                header::X_FRAME_OPTIONS,
+                self.frame_options.map(FrameOptions::header_value),
+            ),
+            (
+                header::X_CONTENT_TYPE_OPTIONS,
+                self.content_type_nosniff
+                    .then(|| HeaderValue::from_static("nosniff")),
+            ),
+            (header::REFERRER_POLICY, self.referrer_policy.clone()),
+            (PERMISSIONS_POLICY, self.permissions_policy.clone()),
+            (
+                CROSS_ORIGIN_OPENER_POLICY,
+                self.cross_origin_opener_policy.clone(),
+            ),
+            (
+                CROSS_ORIGIN_RESOURCE_POLICY,
+                self.cross_origin_resource_policy.clone(),
+            ),
+        ]
+        .into_iter()
+        .filter_map(|(name, value)| Some((name, value?)))
+        .collect()
+    }
+}
+
+impl<S> Layer<S> for SecurityHeadersLayer {
+    type Service = SecurityHeaders<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        SecurityHeaders {
+            inner,
+            headers: self.headers().into(),
+            overwrite: self.overwrite,
+        }
+    }
+}
+
+/// Middleware which adds security headers to responses.
+///
+/// See the [module docs](self) for more information.
+pub struct SecurityHeaders<S> {
+    inner: S,
+    headers: Arc<[(HeaderName, HeaderValue)]>,
+    overwrite: bool,
+}
+
+impl<S> SecurityHeaders<S> {
+    define_inner_service_accessors!();
+}
+
+impl<S: fmt::Debug> fmt::Debug for SecurityHeaders<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SecurityHeaders")
+            .field("inner", &self.inner)
+            .field("headers", &self.headers)
+            .field("overwrite", &self.overwrite)
+            .finish()
+    }
+}
+
+impl<S: Clone> Clone for SecurityHeaders<S> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            headers: self.headers.clone(),
+            overwrite: self.overwrite,
+        }
+    }
+}
+
+impl<S, ReqBody, ResBody> Service<Request<ReqBody>> for SecurityHeaders<S>
+where
+    S: Service<Request<ReqBody>, Response = Response<ResBody>>,
+    ReqBody: Send + 'static,
+    ResBody: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+
+    async fn serve(
+        &self,
+        ctx: Context,
+        req: Request<ReqBody>,
+    ) -> Result<Self::Response, Self::Error> {
+        let mut resp = self.inner.serve(ctx, req).await?;
+        let headers = resp.headers_mut();
+        for (name, value) in self.headers.iter() {
+            if self.overwrite || !headers.contains_key(name) {
+                headers.insert(name.clone(), value.clone());
+            }
+        }
+        Ok(resp)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rama_core::service::service_fn;
+    use rama_http_types::Body;
+    use std::convert::Infallible;
+
+    #[tokio::test]
+    async fn test_security_headers_strict() {
+        let service = SecurityHeadersLayer::strict()
+            .with_frame_options(FrameOptions::SameOrigin)
+            .without_permissions_policy()
+            .into_layer(service_fn(async |_: Request| {
+                Ok::<_, Infallible>(
+                    Response::builder()
+                        .header(header::CONTENT_SECURITY_POLICY, "default-src *")
+                        .body(Body::empty())
+                        .unwrap(),
+                )
+            }));
+
+        let resp = service
+            .serve(Context::default(), Request::new(Body::empty()))
+            .await
+            .unwrap();
+        let headers = resp.headers();
+        assert_eq!(
+            headers[header::STRICT_TRANSPORT_SECURITY],
+            "max-age=63072000; includeSubDomains"
+        );
+        // set by the inner service
+        assert_eq!(headers[header::CONTENT_SECURITY_POLICY], "default-src *");
+        assert_eq!(headers[header::X_FRAME_OPTIONS], "SAMEORIGIN");
+        assert_eq!(headers[header::X_CONTENT_TYPE_OPTIONS], "nosniff");
+        assert_eq!(
+            headers[header::REFERRER_POLICY],
+            "strict-origin-when-cross-origin"
+        );
+        assert!(!headers.contains_key(PERMISSIONS_POLICY));
+        assert_eq!(headers[CROSS_ORIGIN_OPENER_POLICY], "same-origin");
+    }
+
+    #[test]
+    fn test_hsts_header_value() {
+        assert_eq!(
+            Hsts::new(Duration::from_secs(300)).header_value(),
+            "max-age=300"
+        );
+        assert_eq!(
+            Hsts::new(Duration::from_secs(300))
+                .with_include_subdomains(true)
+                .header_value(),
+            "max-age=300; includeSubDomains"
+        );
+        assert_eq!(
+            Hsts::preload().header_value(),
+            "max-age=63072000; includeSubDomains; preload"
+        );
+    }
+}
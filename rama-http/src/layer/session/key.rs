@@ -0,0 +1,147 @@
+use std::{fmt, sync::Arc};
+
+use base64::{Engine as _, prelude::BASE64_URL_SAFE_NO_PAD};
+use rama_core::error::{ErrorContext, OpaqueError};
+use rama_crypto::dep::aws_lc_rs::{
+    aead::{AES_256_GCM, Aad, LessSafeKey, NONCE_LEN, Nonce, UnboundKey},
+    digest::{SHA256, digest},
+    hmac,
+};
+
+use super::SessionId;
+
+#[derive(Clone)]
+/// Key used to protect the session cookie.
+///
+/// The cookie only contains the [`SessionId`], never the session data itself.
+/// It is either signed (HMAC-SHA256), preventing tampering, or
+/// encrypted (AES-256-GCM), which also hides the session id itself.
+pub struct SessionCookieKey(Protection);
+
+#[derive(Clone)]
+enum Protection {
+    Signed(hmac::Key),
+    Encrypted(Arc<LessSafeKey>),
+}
+
+impl SessionCookieKey {
+    #[must_use]
+    /// Create a [`SessionCookieKey`] which signs the session cookie,
+    /// using a key derived from the given secret.
+    pub fn signed(secret: &[u8]) -> Self {
+        Self(Protection::Signed(hmac::Key::new(
+            hmac::HMAC_SHA256,
+            secret,
+        )))
+    }
+
+    #[must_use]
+    /// Create a [`SessionCookieKey`] which encrypts the session cookie,
+    /// using a key derived from the given secret.
+    pub fn encrypted(secret: &[u8]) -> Self {
+        let key = digest(&SHA256, secret);
+        let key = UnboundKey::new(&AES_256_GCM, key.as_ref())
+            .expect("sha256 digest is a valid aes-256 key");
+        Self(Protection::Encrypted(Arc::new(LessSafeKey::new(key))))
+    }
+
+    pub(super) fn encode(&self, id: &SessionId) -> Result<String, OpaqueError> {
+        match &self.0 {
+            Protection::Signed(key) => {
+                let tag = hmac::sign(key, id.as_str().as_bytes());
+                Ok(format!(
+                    "{id}.{}",
+                    BASE64_URL_SAFE_NO_PAD.encode(tag.as_ref())
+                ))
+            }
+            Protection::Encrypted(key) => {
+                let nonce: [u8; NONCE_LEN] = rand::random();
+                let mut data = id.as_str().as_bytes().to_vec();
+                key.seal_in_place_append_tag(
+                    Nonce::assume_unique_for_key(nonce),
+                    Aad::empty(),
+                    &mut data,
+                )
+                .context("encrypt session id")?;
+                let mut value = nonce.to_vec();
+                value.extend_from_slice(&data);
+                Ok(BASE64_URL_SAFE_NO_PAD.encode(value))
+            }
+        }
+    }
+
+    pub(super) fn decode(&self, value: &str) -> Result<SessionId, OpaqueError> {
+        match &self.0 {
+            Protection::Signed(key) => {
+                let (id, tag) = value.rsplit_once('.').context("split signed session id")?;
+                let tag = BASE64_URL_SAFE_NO_PAD
+                    .decode(tag)
+                    .context("decode session id signature")?;
+                hmac::verify(key, id.as_bytes(), &tag).context("verify session id signature")?;
+                Ok(SessionId::from(id.to_owned()))
+            }
+            Protection::Encrypted(key) => {
+                let mut value = BASE64_URL_SAFE_NO_PAD
+                    .decode(value)
+                    .context("decode encrypted session id")?;
+                if value.len() < NONCE_LEN {
+                    return Err(OpaqueError::from_display("encrypted session id too short"));
+                }
+                let mut data = value.split_off(NONCE_LEN);
+                let nonce = Nonce::try_assume_unique_for_key(&value)
+                    .context("read encrypted session id nonce")?;
+                let id = key
+                    .open_in_place(nonce, Aad::empty(), &mut data)
+                    .context("decrypt session id")?;
+                let id = String::from_utf8(id.to_vec()).context("decode session id as utf-8")?;
+                Ok(SessionId::from(id))
+            }
+        }
+    }
+}
+
+impl fmt::Debug for SessionCookieKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mode = match self.0 {
+            Protection::Signed(_) => "signed",
+            Protection::Encrypted(_) => "encrypted",
+        };
+        f.debug_tuple("SessionCookieKey").field(&mode).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_session_cookie_key_roundtrip() {
+        for key in [
+            SessionCookieKey::signed(b"secret"),
+            SessionCookieKey::encrypted(b"secret"),
+        ] {
+            let id = SessionId::random();
+            let value = key.encode(&id).unwrap();
+            assert_eq!(key.decode(&value).unwrap(), id, "key: {key:?}");
+
+            let mut tampered = value.clone();
+            tampered.insert(0, 'x');
+            assert!(key.decode(&tampered).is_err(), "key: {key:?}");
+        }
+    }
+
+    #[test]
+    fn test_session_cookie_key_rejects_other_secret() {
+        let id = SessionId::random();
+
+        let value = SessionCookieKey::signed(b"secret").encode(&id).unwrap();
+        assert!(SessionCookieKey::signed(b"other").decode(&value).is_err());
+
+        let value = SessionCookieKey::encrypted(b"secret").encode(&id).unwrap();
+        assert!(
+            SessionCookieKey::encrypted(b"other")
+                .decode(&value)
+                .is_err()
+        );
+    }
+}
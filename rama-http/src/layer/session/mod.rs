@@ -0,0 +1,742 @@
+//! Cookie-based session middleware.
+//!
+//! The [`SessionLayer`] loads the session identified by the (signed or encrypted)
+//! session cookie from a [`SessionStore`] and makes it available as a [`Session`]
+//! in the [`Context`]. Session data can be read and written in a typed manner
+//! through this handle, and is persisted once the inner service has produced its response.
+//!
+//! Sessions can expire after a period of inactivity (idle timeout)
+//! and/or a fixed time after they were created (absolute timeout).
+//!
+//! # Example
+//!
+//! ```
+//! use rama_core::{Context, Layer, Service, service::service_fn};
+//! use rama_http::layer::session::{MemorySessionStore, Session, SessionCookieKey, SessionLayer};
+//! use rama_http::{Body, Request, Response, header};
+//! use std::{convert::Infallible, time::Duration};
+//!
+//! # #[tokio::main]
+//! # async fn main() {
+//! let service = SessionLayer::new(
+//!     MemorySessionStore::new(),
+//!     SessionCookieKey::signed(b"a very secret key"),
+//! )
+//! .with_idle_timeout(Duration::from_secs(30 * 60))
+//! .into_layer(service_fn(async |ctx: Context, _: Request| {
+//!     let session = ctx.get::<Session>().unwrap();
+//!     let visits = session.get::<u64>("visits").unwrap_or_default() + 1;
+//!     session.insert("visits", visits).unwrap();
+//!     Ok::<_, Infallible>(Response::new(Body::from(format!("visits: {visits}"))))
+//! }));
+//!
+//! let resp = service
+//!     .serve(Context::default(), Request::new(Body::empty()))
+//!     .await
+//!     .unwrap();
+//! assert!(resp.headers().contains_key(header::SET_COOKIE));
+//! # }
+//! ```
+
+use std::{
+    collections::HashMap,
+    fmt,
+    sync::{Arc, Mutex},
+    time::{Duration, SystemTime},
+};
+
+use rama_core::{
+    Context, Layer, Service,
+    error::{ErrorContext, OpaqueError},
+    telemetry::tracing,
+};
+use rama_http_headers::{Cookie, HeaderMapExt};
+use rama_http_types::{HeaderValue, Request, Response, header};
+use rama_utils::macros::define_inner_service_accessors;
+use serde::{Serialize, de::DeserializeOwned};
+use serde_json::Value;
+
+mod key;
+#[doc(inline)]
+pub use key::SessionCookieKey;
+
+mod store;
+#[doc(inline)]
+pub use store::{MemorySessionStore, SessionId, SessionRecord, SessionStore};
+
+/// The default name of the session cookie.
+pub const DEFAULT_SESSION_COOKIE_NAME: &str = "rama.sid";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// The `SameSite` attribute of the session cookie.
+pub enum SameSite {
+    /// `SameSite=Strict`
+    Strict,
+    /// `SameSite=Lax`
+    Lax,
+    /// `SameSite=None`
+    None,
+}
+
+impl SameSite {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Strict => "Strict",
+            Self::Lax => "Lax",
+            Self::None => "None",
+        }
+    }
+}
+
+#[derive(Clone)]
+/// Handle to the session of the current request.
+///
+/// Inserted in the [`Context`] by the [`SessionService`].
+/// Clones share the same underlying session.
+pub struct Session {
+    state: Arc<Mutex<SessionState>>,
+}
+
+#[derive(Debug)]
+struct SessionState {
+    id: Option<SessionId>,
+    data: HashMap<String, Value>,
+    created_at: SystemTime,
+    modified: bool,
+    renewed: bool,
+    destroyed: bool,
+}
+
+impl Session {
+    fn new(now: SystemTime) -> Self {
+        Self::from_state(SessionState {
+            id: None,
+            data: HashMap::new(),
+            created_at: now,
+            modified: false,
+            renewed: false,
+            destroyed: false,
+        })
+    }
+
+    fn from_record(record: SessionRecord) -> Self {
+        Self::from_state(SessionState {
+            id: Some(record.id),
+            data: record.data,
+            created_at: record.created_at,
+            modified: false,
+            renewed: false,
+            destroyed: false,
+        })
+    }
+
+    fn from_state(state: SessionState) -> Self {
+        Self {
+            state: Arc::new(Mutex::new(state)),
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, SessionState> {
+        self.state.lock().unwrap_or_else(|err| err.into_inner())
+    }
+
+    #[must_use]
+    /// Returns the id of the session, if it was already persisted.
+    pub fn id(&self) -> Option<SessionId> {
+        self.lock().id.clone()
+    }
+
+    #[must_use]
+    /// Get the value stored for the given key,
+    /// returning `None` in case it does not exist or cannot be deserialized as `T`.
+    pub fn get<T: DeserializeOwned>(&self, key: &str) -> Option<T> {
+        let value = self.lock().data.get(key)?.clone();
+        serde_json::from_value(value).ok()
+    }
+
+    /// Store a value for the given key, overwriting any previous value.
+    pub fn insert<T: Serialize>(
+        &self,
+        key: impl Into<String>,
+        value: T,
+    ) -> Result<(), OpaqueError> {
+        let value = serde_json::to_value(value).context("serialize session value")?;
+        let mut state = self.lock();
+        state.data.insert(key.into(), value);
+        state.modified = true;
+        Ok(())
+    }
+
+    /// Remove the value stored for the given key,
+    /// returning true if it existed.
+    pub fn remove(&self, key: &str) -> bool {
+        let mut state = self.lock();
+        let removed = state.data.remove(key).is_some();
+        state.modified |= removed;
+        removed
+    }
+
+    /// Remove all data stored in the session.
+    pub fn clear(&self) {
+        let mut state = self.lock();
+        state.modified |= !state.data.is_empty();
+        state.data.clear();
+    }
+
+    /// Assign a new id to the session, keeping its data.
+    ///
+    /// Use this whenever the privilege level of the session changes
+    /// (e.g. after a login), to prevent session fixation attacks.
+    pub fn renew(&self) {
+        let mut state = self.lock();
+        state.renewed = true;
+        state.modified = true;
+    }
+
+    /// Destroy the session, removing it from the store
+    /// and expiring the session cookie.
+    pub fn destroy(&self) {
+        let mut state = self.lock();
+        state.destroyed = true;
+        state.data.clear();
+    }
+}
+
+impl fmt::Debug for Session {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let state = self.lock();
+        f.debug_struct("Session")
+            .field("id", &state.id)
+            .field("keys", &state.data.keys().collect::<Vec<_>>())
+            .field("created_at", &state.created_at)
+            .field("modified", &state.modified)
+            .field("renewed", &state.renewed)
+            .field("destroyed", &state.destroyed)
+            .finish()
+    }
+}
+
+#[derive(Debug, Clone)]
+struct SessionConfig {
+    key: SessionCookieKey,
+    cookie_name: String,
+    cookie_path: String,
+    cookie_domain: Option<String>,
+    secure: bool,
+    same_site: SameSite,
+    idle_timeout: Option<Duration>,
+    absolute_timeout: Option<Duration>,
+}
+
+impl SessionConfig {
+    fn expires_at(&self, created_at: SystemTime, now: SystemTime) -> Option<SystemTime> {
+        let idle = self.idle_timeout.map(|timeout| now + timeout);
+        let absolute = self.absolute_timeout.map(|timeout| created_at + timeout);
+        match (idle, absolute) {
+            (Some(idle), Some(absolute)) => Some(idle.min(absolute)),
+            (expires_at, None) | (None, expires_at) => expires_at,
+        }
+    }
+
+    fn cookie(&self, value: &str, max_age: Option<Duration>) -> Option<HeaderValue> {
+        let mut cookie = format!(
+            "{}={value}; Path={}; HttpOnly; SameSite={}",
+            self.cookie_name,
+            self.cookie_path,
+            self.same_site.as_str(),
+        );
+        if let Some(domain) = &self.cookie_domain {
+            cookie.push_str("; Domain=");
+            cookie.push_str(domain);
+        }
+        if self.secure {
+            cookie.push_str("; Secure");
+        }
+        if let Some(max_age) = max_age {
+            // round up, as a sub-second max age of zero would delete the cookie
+            let max_age = max_age.as_secs() + u64::from(max_age.subsec_nanos() > 0);
+            cookie.push_str(&format!("; Max-Age={max_age}"));
+        }
+        HeaderValue::try_from(cookie)
+            .inspect_err(|err| tracing::error!("invalid session cookie: {err}"))
+            .ok()
+    }
+}
+
+/// Layer that applies the [`SessionService`] middleware.
+///
+/// See the [module docs](self) for more information.
+pub struct SessionLayer<St> {
+    store: St,
+    config: SessionConfig,
+}
+
+impl<St> SessionLayer<St> {
+    /// Create a new [`SessionLayer`] using the given store and cookie key.
+    pub fn new(store: St, key: SessionCookieKey) -> Self {
+        Self {
+            store,
+            config: SessionConfig {
+                key,
+                cookie_name: DEFAULT_SESSION_COOKIE_NAME.to_owned(),
+                cookie_path: "/".to_owned(),
+                cookie_domain: None,
+                secure: true,
+                same_site: SameSite::Lax,
+                idle_timeout: None,
+                absolute_timeout: None,
+            },
+        }
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Set the name of the session cookie.
+        ///
+        /// Defaults to [`DEFAULT_SESSION_COOKIE_NAME`].
+        pub fn cookie_name(mut self, name: impl Into<String>) -> Self {
+            self.config.cookie_name = name.into();
+            self
+        }
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Set the `Path` attribute of the session cookie.
+        ///
+        /// Defaults to `/`.
+        pub fn cookie_path(mut self, path: impl Into<String>) -> Self {
+            self.config.cookie_path = path.into();
+            self
+        }
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Set the `Domain` attribute of the session cookie.
+        ///
+        /// Not set by default.
+        pub fn cookie_domain(mut self, domain: impl Into<String>) -> Self {
+            self.config.cookie_domain = Some(domain.into());
+            self
+        }
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Define whether or not the session cookie has the `Secure` attribute.
+        ///
+        /// Defaults to `true`.
+        pub fn secure(mut self, secure: bool) -> Self {
+            self.config.secure = secure;
+            self
+        }
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Set the `SameSite` attribute of the session cookie.
+        ///
+        /// Defaults to [`SameSite::Lax`].
+        pub fn same_site(mut self, same_site: SameSite) -> Self {
+            self.config.same_site = same_site;
+            self
+        }
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Expire sessions which have not been accessed for the given duration.
+        pub fn idle_timeout(mut self, timeout: Duration) -> Self {
+            self.config.idle_timeout = Some(timeout);
+            self
+        }
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Expire sessions the given duration after they were created,
+        /// regardless of their activity.
+        pub fn absolute_timeout(mut self, timeout: Duration) -> Self {
+            self.config.absolute_timeout = Some(timeout);
+            self
+        }
+    }
+}
+
+impl<St: fmt::Debug> fmt::Debug for SessionLayer<St> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SessionLayer")
+            .field("store", &self.store)
+            .field("config", &self.config)
+            .finish()
+    }
+}
+
+impl<St: Clone> Clone for SessionLayer<St> {
+    fn clone(&self) -> Self {
+        Self {
+            store: self.store.clone(),
+            config: self.config.clone(),
+        }
+    }
+}
+
+impl<S, St: Clone> Layer<S> for SessionLayer<St> {
+    type Service = SessionService<S, St>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        SessionService {
+            inner,
+            store: self.store.clone(),
+            config: self.config.clone(),
+        }
+    }
+
+    fn into_layer(self, inner: S) -> Self::Service {
+        SessionService {
+            inner,
+            store: self.store,
+            config: self.config,
+        }
+    }
+}
+
+/// Middleware which provides a [`Session`] to the inner service.
+///
+/// See the [module docs](self) for more information.
+pub struct SessionService<S, St> {
+    inner: S,
+    store: St,
+    config: SessionConfig,
+}
+
+impl<S, St> SessionService<S, St> {
+    define_inner_service_accessors!();
+}
+
+impl<S: fmt::Debug, St: fmt::Debug> fmt::Debug for SessionService<S, St> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SessionService")
+            .field("inner", &self.inner)
+            .field("store", &self.store)
+            .field("config", &self.config)
+            .finish()
+    }
+}
+
+impl<S: Clone, St: Clone> Clone for SessionService<S, St> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            store: self.store.clone(),
+            config: self.config.clone(),
+        }
+    }
+}
+
+impl<S, St> SessionService<S, St>
+where
+    St: SessionStore,
+{
+    async fn load<B>(&self, req: &Request<B>, now: SystemTime) -> Option<SessionRecord> {
+        let cookie = req.headers().typed_get::<Cookie>()?;
+        let value = cookie.get(&self.config.cookie_name)?;
+
+        let id = self
+            .config
+            .key
+            .decode(value)
+            .inspect_err(|err| tracing::debug!("invalid session cookie: {err}"))
+            .ok()?;
+
+        let record = match self.store.load(&id).await {
+            Ok(record) => record?,
+            Err(err) => {
+                tracing::error!("failed to load session: {err}");
+                return None;
+            }
+        };
+
+        let expires_at = self
+            .config
+            .expires_at(record.created_at, record.last_accessed_at);
+        if record.is_expired(now) || expires_at.is_some_and(|expires_at| expires_at <= now) {
+            tracing::debug!(session.id = %id, "session expired");
+            if let Err(err) = self.store.delete(&id).await {
+                tracing::error!("failed to delete expired session: {err}");
+            }
+            return None;
+        }
+
+        Some(record)
+    }
+
+    async fn persist<B>(
+        &self,
+        session: &Session,
+        now: SystemTime,
+        res: &mut Response<B>,
+    ) -> Result<(), OpaqueError> {
+        let (old_id, id, record) = {
+            let mut state = session.lock();
+
+            if state.destroyed {
+                (state.id.take(), None, None)
+            } else if state.id.is_none() && state.data.is_empty() {
+                // nothing to persist for a new session without any data
+                return Ok(());
+            } else if !state.modified && self.config.idle_timeout.is_none() {
+                // untouched existing session, no need to refresh its last access time
+                return Ok(());
+            } else {
+                let old_id = if state.renewed || state.id.is_none() {
+                    state.renewed = false;
+                    state.id.replace(SessionId::random())
+                } else {
+                    None
+                };
+                let id = state.id.clone().context("session id")?;
+                let record = SessionRecord {
+                    id: id.clone(),
+                    data: state.data.clone(),
+                    created_at: state.created_at,
+                    last_accessed_at: now,
+                    expires_at: self.config.expires_at(state.created_at, now),
+                };
+                state.modified = false;
+                (old_id, Some(id), Some(record))
+            }
+        };
+
+        if let Some(old_id) = old_id {
+            self.store
+                .delete(&old_id)
+                .await
+                .context("delete old session")?;
+        }
+
+        let cookie = match (id, record) {
+            (Some(id), Some(record)) => {
+                let max_age = record
+                    .expires_at
+                    .map(|expires_at| expires_at.duration_since(now).unwrap_or_default());
+                self.store.save(record).await.context("save session")?;
+                let value = self.config.key.encode(&id)?;
+                self.config.cookie(&value, max_age)
+            }
+            _ => self.config.cookie("", Some(Duration::ZERO)),
+        };
+
+        if let Some(cookie) = cookie {
+            res.headers_mut().append(header::SET_COOKIE, cookie);
+        }
+
+        Ok(())
+    }
+}
+
+impl<S, St, ReqBody, ResBody> Service<Request<ReqBody>> for SessionService<S, St>
+where
+    S: Service<Request<ReqBody>, Response = Response<ResBody>>,
+    St: SessionStore,
+    ReqBody: Send + 'static,
+    ResBody: Send + 'static,
+{
+    type Response = Response<ResBody>;
+    type Error = S::Error;
+
+    async fn serve(
+        &self,
+        mut ctx: Context,
+        req: Request<ReqBody>,
+    ) -> Result<Self::Response, Self::Error> {
+        let now = SystemTime::now();
+
+        let had_cookie = req
+            .headers()
+            .typed_get::<Cookie>()
+            .is_some_and(|cookie| cookie.get(&self.config.cookie_name).is_some());
+
+        let session = match self.load(&req, now).await {
+            Some(record) => Session::from_record(record),
+            None => Session::new(now),
+        };
+        ctx.insert(session.clone());
+
+        let mut res = self.inner.serve(ctx, req).await?;
+
+        let destroyed = session.lock().destroyed;
+        if !destroyed || had_cookie {
+            if let Err(err) = self.persist(&session, now, &mut res).await {
+                tracing::error!("failed to persist session: {err}");
+            }
+        }
+
+        Ok(res)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Body, StatusCode};
+    use rama_core::service::service_fn;
+    use std::convert::Infallible;
+
+    fn cookie_value(res: &Response) -> Option<String> {
+        let cookie = res.headers().get(header::SET_COOKIE)?.to_str().unwrap();
+        let (pair, _) = cookie.split_once(';')?;
+        Some(pair.to_owned())
+    }
+
+    fn request(cookie: Option<&str>, path: &str) -> Request {
+        let mut builder = Request::builder().uri(path);
+        if let Some(cookie) = cookie {
+            builder = builder.header(header::COOKIE, cookie);
+        }
+        builder.body(Body::empty()).unwrap()
+    }
+
+    fn service(
+        store: MemorySessionStore,
+        layer: impl FnOnce(SessionLayer<MemorySessionStore>) -> SessionLayer<MemorySessionStore>,
+    ) -> impl Service<Request, Response = Response, Error = Infallible> {
+        layer(SessionLayer::new(
+            store,
+            SessionCookieKey::signed(b"secret"),
+        ))
+        .into_layer(service_fn(async |ctx: Context, req: Request| {
+            let session = ctx.get::<Session>().unwrap();
+            match req.uri().path() {
+                "/login" => {
+                    session.insert("user", "john").unwrap();
+                    session.renew();
+                }
+                "/logout" => session.destroy(),
+                "/visit" => {
+                    let visits = session.get::<u64>("visits").unwrap_or_default();
+                    session.insert("visits", visits + 1).unwrap();
+                }
+                _ => (),
+            }
+            let body = format!(
+                "{}:{}",
+                session.get::<String>("user").unwrap_or_default(),
+                session.get::<u64>("visits").unwrap_or_default(),
+            );
+            Ok::<_, Infallible>(Response::new(Body::from(body)))
+        }))
+    }
+
+    async fn body(res: Response) -> String {
+        use crate::dep::http_body_util::BodyExt;
+        String::from_utf8(res.into_body().collect().await.unwrap().to_bytes().to_vec()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_session_lifecycle() {
+        let store = MemorySessionStore::new();
+        let svc = service(store.clone(), |layer| layer);
+
+        // no session is created without data
+        let res = svc
+            .serve(Context::default(), request(None, "/"))
+            .await
+            .unwrap();
+        assert!(cookie_value(&res).is_none());
+        assert!(store.is_empty());
+
+        let res = svc
+            .serve(Context::default(), request(None, "/visit"))
+            .await
+            .unwrap();
+        let cookie = cookie_value(&res).unwrap();
+        assert_eq!(body(res).await, ":1");
+
+        let res = svc
+            .serve(Context::default(), request(Some(&cookie), "/visit"))
+            .await
+            .unwrap();
+        assert_eq!(body(res).await, ":2");
+
+        // login renews the session id, keeping the data
+        let res = svc
+            .serve(Context::default(), request(Some(&cookie), "/login"))
+            .await
+            .unwrap();
+        let renewed_cookie = cookie_value(&res).unwrap();
+        assert_ne!(cookie, renewed_cookie);
+        assert_eq!(body(res).await, "john:2");
+        assert_eq!(store.len(), 1);
+
+        let res = svc
+            .serve(Context::default(), request(Some(&cookie), "/"))
+            .await
+            .unwrap();
+        assert_eq!(body(res).await, ":0");
+
+        let res = svc
+            .serve(Context::default(), request(Some(&renewed_cookie), "/"))
+            .await
+            .unwrap();
+        assert_eq!(body(res).await, "john:2");
+
+        // logout destroys the session and expires the cookie
+        let res = svc
+            .serve(
+                Context::default(),
+                request(Some(&renewed_cookie), "/logout"),
+            )
+            .await
+            .unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+        let cookie = res.headers().get(header::SET_COOKIE).unwrap();
+        assert!(cookie.to_str().unwrap().contains("Max-Age=0"));
+        assert!(store.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_session_tampered_cookie() {
+        let store = MemorySessionStore::new();
+        let svc = service(store.clone(), |layer| layer);
+
+        let res = svc
+            .serve(Context::default(), request(None, "/visit"))
+            .await
+            .unwrap();
+        let cookie = cookie_value(&res).unwrap();
+
+        let res = svc
+            .serve(
+                Context::default(),
+                request(Some(&format!("{cookie}x")), "/"),
+            )
+            .await
+            .unwrap();
+        assert_eq!(body(res).await, ":0");
+    }
+
+    #[tokio::test]
+    async fn test_session_absolute_timeout() {
+        let store = MemorySessionStore::new();
+        let svc = service(store.clone(), |layer| {
+            layer.with_absolute_timeout(Duration::from_millis(50))
+        });
+
+        let res = svc
+            .serve(Context::default(), request(None, "/visit"))
+            .await
+            .unwrap();
+        let set_cookie = res.headers().get(header::SET_COOKIE).unwrap();
+        assert!(set_cookie.to_str().unwrap().contains("Max-Age=1"));
+        let cookie = cookie_value(&res).unwrap();
+
+        let res = svc
+            .serve(Context::default(), request(Some(&cookie), "/"))
+            .await
+            .unwrap();
+        assert_eq!(body(res).await, ":1");
+
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        let res = svc
+            .serve(Context::default(), request(Some(&cookie), "/"))
+            .await
+            .unwrap();
+        assert_eq!(body(res).await, ":0");
+    }
+}
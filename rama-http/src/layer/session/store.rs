@@ -0,0 +1,197 @@
+use std::{
+    collections::HashMap,
+    fmt,
+    sync::{Arc, Mutex},
+    time::SystemTime,
+};
+
+use rama_core::error::OpaqueError;
+use serde_json::Value;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+/// Identifier of a session, as stored in a [`SessionStore`].
+pub struct SessionId(String);
+
+impl SessionId {
+    #[must_use]
+    /// Generate a new random [`SessionId`].
+    pub fn random() -> Self {
+        use base64::{Engine as _, prelude::BASE64_URL_SAFE_NO_PAD};
+        let bytes: [u8; 32] = rand::random();
+        Self(BASE64_URL_SAFE_NO_PAD.encode(bytes))
+    }
+
+    #[must_use]
+    /// View this [`SessionId`] as a str.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl From<String> for SessionId {
+    fn from(value: String) -> Self {
+        Self(value)
+    }
+}
+
+impl fmt::Display for SessionId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// A session as persisted in a [`SessionStore`].
+pub struct SessionRecord {
+    /// Id of the session.
+    pub id: SessionId,
+    /// Data stored in the session.
+    pub data: HashMap<String, Value>,
+    /// Time at which the session was created,
+    /// used to enforce the absolute session timeout.
+    pub created_at: SystemTime,
+    /// Time at which the session was last accessed,
+    /// used to enforce the idle session timeout.
+    pub last_accessed_at: SystemTime,
+    /// Time at which the session expires, if it does.
+    ///
+    /// Stores can use this to evict expired sessions.
+    pub expires_at: Option<SystemTime>,
+}
+
+impl SessionRecord {
+    #[must_use]
+    /// Returns true if the session is expired at the given time.
+    pub fn is_expired(&self, now: SystemTime) -> bool {
+        self.expires_at.is_some_and(|expires_at| expires_at <= now)
+    }
+}
+
+/// Storage backend for sessions.
+pub trait SessionStore: Send + Sync + 'static {
+    /// Load the session for the given id, if it exists.
+    fn load(
+        &self,
+        id: &SessionId,
+    ) -> impl Future<Output = Result<Option<SessionRecord>, OpaqueError>> + Send;
+
+    /// Create or update the given session.
+    fn save(&self, record: SessionRecord) -> impl Future<Output = Result<(), OpaqueError>> + Send;
+
+    /// Delete the session for the given id.
+    fn delete(&self, id: &SessionId) -> impl Future<Output = Result<(), OpaqueError>> + Send;
+}
+
+impl<S: SessionStore> SessionStore for Arc<S> {
+    fn load(
+        &self,
+        id: &SessionId,
+    ) -> impl Future<Output = Result<Option<SessionRecord>, OpaqueError>> + Send {
+        self.as_ref().load(id)
+    }
+
+    fn save(&self, record: SessionRecord) -> impl Future<Output = Result<(), OpaqueError>> + Send {
+        self.as_ref().save(record)
+    }
+
+    fn delete(&self, id: &SessionId) -> impl Future<Output = Result<(), OpaqueError>> + Send {
+        self.as_ref().delete(id)
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+/// An in-memory [`SessionStore`].
+///
+/// Expired sessions are evicted lazily, when loaded or when a session is saved.
+/// Cloning this store shares the underlying sessions.
+pub struct MemorySessionStore {
+    sessions: Arc<Mutex<HashMap<SessionId, SessionRecord>>>,
+}
+
+impl MemorySessionStore {
+    #[must_use]
+    /// Create a new empty [`MemorySessionStore`].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    #[must_use]
+    /// Returns the number of sessions currently stored.
+    pub fn len(&self) -> usize {
+        self.sessions
+            .lock()
+            .unwrap_or_else(|err| err.into_inner())
+            .len()
+    }
+
+    #[must_use]
+    /// Returns true if no sessions are currently stored.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl SessionStore for MemorySessionStore {
+    async fn load(&self, id: &SessionId) -> Result<Option<SessionRecord>, OpaqueError> {
+        let mut sessions = self.sessions.lock().unwrap_or_else(|err| err.into_inner());
+        match sessions.get(id) {
+            Some(record) if record.is_expired(SystemTime::now()) => {
+                sessions.remove(id);
+                Ok(None)
+            }
+            record => Ok(record.cloned()),
+        }
+    }
+
+    async fn save(&self, record: SessionRecord) -> Result<(), OpaqueError> {
+        let now = SystemTime::now();
+        let mut sessions = self.sessions.lock().unwrap_or_else(|err| err.into_inner());
+        sessions.retain(|_, record| !record.is_expired(now));
+        sessions.insert(record.id.clone(), record);
+        Ok(())
+    }
+
+    async fn delete(&self, id: &SessionId) -> Result<(), OpaqueError> {
+        self.sessions
+            .lock()
+            .unwrap_or_else(|err| err.into_inner())
+            .remove(id);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn record(expires_at: Option<SystemTime>) -> SessionRecord {
+        let now = SystemTime::now();
+        SessionRecord {
+            id: SessionId::random(),
+            data: HashMap::new(),
+            created_at: now,
+            last_accessed_at: now,
+            expires_at,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_memory_store_evicts_expired_sessions() {
+        let store = MemorySessionStore::new();
+
+        let expired = record(Some(SystemTime::now() - Duration::from_secs(1)));
+        let active = record(Some(SystemTime::now() + Duration::from_secs(60)));
+
+        store.save(expired.clone()).await.unwrap();
+        assert!(store.load(&expired.id).await.unwrap().is_none());
+
+        store.save(expired.clone()).await.unwrap();
+        store.save(active.clone()).await.unwrap();
+        assert_eq!(store.len(), 1);
+        assert_eq!(store.load(&active.id).await.unwrap(), Some(active.clone()));
+
+        store.delete(&active.id).await.unwrap();
+        assert!(store.is_empty());
+    }
+}
@@ -0,0 +1,493 @@
+//! Signed URLs, granting temporary access to resources served by rama.
+//!
+//! A [`UrlSigner`] signs a URL with an HMAC-SHA256 signature, according to the [`SignOptions`]:
+//!
+//! - the URL expires after the configured duration;
+//! - optionally, it can only be used with the given methods (e.g. `GET` and `HEAD` only);
+//! - optionally, it is bound to attributes of the client it was handed out to
+//!   (its IP address and/or header values such as the `User-Agent`), such that
+//!   a leaked link cannot be used by anyone else.
+//!
+//! The expiry, methods and names of the bound attributes are added as query parameters
+//! (`expires`, `methods` and `bind`), followed by the `signature`. The values of the bound
+//! attributes are only part of the signed payload, and are never disclosed in the URL.
+//!
+//! The [`SignedUrlLayer`] validates the signed URLs of incoming requests,
+//! answering with `403 Forbidden` for requests without a valid signature.
+//!
+//! # Example
+//!
+//! ```
+//! use rama_core::{Context, Layer, Service, service::service_fn};
+//! use rama_http::layer::signed_url::{SignOptions, SignedUrlLayer, UrlSigner};
+//! use rama_http::{Body, Method, Request, Response, StatusCode};
+//! use std::{convert::Infallible, time::Duration};
+//!
+//! # #[tokio::main]
+//! # async fn main() {
+//! let signer = UrlSigner::new(b"a secret of at least 32 bytes long!!");
+//! let uri = signer
+//!     .sign(
+//!         &"/downloads/report.pdf".parse().unwrap(),
+//!         &SignOptions::new(Duration::from_secs(300)).with_method(Method::GET),
+//!     )
+//!     .unwrap();
+//!
+//! let service = SignedUrlLayer::new(signer).into_layer(service_fn(async |_: Request| {
+//!     Ok::<_, Infallible>(Response::new(Body::from("report")))
+//! }));
+//!
+//! let req = Request::get(uri).body(Body::empty()).unwrap();
+//! let resp = service.serve(Context::default(), req).await.unwrap();
+//! assert_eq!(resp.status(), StatusCode::OK);
+//!
+//! let req = Request::get("/downloads/report.pdf").body(Body::empty()).unwrap();
+//! let resp = service.serve(Context::default(), req).await.unwrap();
+//! assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+//! # }
+//! ```
+
+use std::{
+    fmt,
+    net::IpAddr,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use crate::service::web::response::IntoResponse;
+use base64::{Engine as _, prelude::BASE64_URL_SAFE_NO_PAD};
+use rama_core::{
+    Context, Layer, Service,
+    error::{ErrorContext, OpaqueError},
+    telemetry::tracing,
+};
+use rama_crypto::dep::aws_lc_rs::hmac;
+use rama_http_types::{
+    HeaderName, HeaderValue, Method, Request, Response, StatusCode, Uri,
+    dep::http::uri::PathAndQuery,
+};
+use rama_net::{forwarded::Forwarded, stream::SocketInfo};
+use rama_utils::macros::define_inner_service_accessors;
+
+const EXPIRES_PARAM: &str = "expires";
+const METHODS_PARAM: &str = "methods";
+const BIND_PARAM: &str = "bind";
+const SIGNATURE_PARAM: &str = "signature";
+const CLIENT_IP_ATTRIBUTE: &str = "ip";
+
+#[derive(Debug, Clone)]
+/// Options of a URL signed by the [`UrlSigner`].
+pub struct SignOptions {
+    ttl: Duration,
+    methods: Vec<Method>,
+    client_ip: Option<IpAddr>,
+    headers: Vec<(HeaderName, HeaderValue)>,
+}
+
+impl SignOptions {
+    /// Create new [`SignOptions`], for a URL which expires after the given duration.
+    #[must_use]
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            methods: Vec::new(),
+            client_ip: None,
+            headers: Vec::new(),
+        }
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Allow the URL to be used with the given method.
+        ///
+        /// All methods are allowed, unless at least one method is added.
+        pub fn method(mut self, method: Method) -> Self {
+            self.methods.push(method);
+            self
+        }
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Bind the URL to the client with the given IP address.
+        pub fn client_ip(mut self, ip: IpAddr) -> Self {
+            self.client_ip = Some(ip);
+            self
+        }
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Bind the URL to clients sending the given header value, e.g. their `User-Agent`.
+        pub fn bound_header(mut self, name: HeaderName, value: HeaderValue) -> Self {
+            self.headers.push((name, value));
+            self
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// The reason a signed URL is rejected by the [`UrlSigner`].
+pub enum SignedUrlError {
+    /// The URL is not signed, or has malformed signed parameters.
+    Missing,
+    /// The signature does not match the URL or the bound client attributes.
+    InvalidSignature,
+    /// The URL has expired.
+    Expired,
+    /// The URL cannot be used with the method of the request.
+    MethodNotAllowed,
+}
+
+impl fmt::Display for SignedUrlError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Missing => "url is not signed",
+            Self::InvalidSignature => "invalid url signature",
+            Self::Expired => "signed url expired",
+            Self::MethodNotAllowed => "method not allowed by signed url",
+        })
+    }
+}
+
+impl std::error::Error for SignedUrlError {}
+
+#[derive(Clone)]
+/// Signs and verifies URLs using HMAC-SHA256.
+///
+/// See the [module docs](self) for more information.
+pub struct UrlSigner {
+    key: hmac::Key,
+}
+
+impl fmt::Debug for UrlSigner {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("UrlSigner").finish_non_exhaustive()
+    }
+}
+
+impl UrlSigner {
+    /// Create a new [`UrlSigner`] using the given secret.
+    #[must_use]
+    pub fn new(secret: &[u8]) -> Self {
+        Self {
+            key: hmac::Key::new(hmac::HMAC_SHA256, secret),
+        }
+    }
+
+    /// Sign the given [`Uri`] according to the given [`SignOptions`].
+    ///
+    /// Existing query parameters of the [`Uri`] are kept, and covered by the signature.
+    pub fn sign(&self, uri: &Uri, options: &SignOptions) -> Result<Uri, OpaqueError> {
+        let expires = (SystemTime::now() + options.ttl)
+            .duration_since(UNIX_EPOCH)
+            .context("compute expiry of signed url")?
+            .as_secs();
+
+        let mut query = uri.query().unwrap_or_default().to_owned();
+        let mut push_param = |name: &str, value: &str| {
+            if !query.is_empty() {
+                query.push('&');
+            }
+            query.push_str(name);
+            query.push('=');
+            query.push_str(value);
+        };
+        push_param(EXPIRES_PARAM, &expires.to_string());
+        if !options.methods.is_empty() {
+            let methods: Vec<_> = options.methods.iter().map(Method::as_str).collect();
+            push_param(METHODS_PARAM, &methods.join(","));
+        }
+        let mut bound = Vec::new();
+        let mut bound_values = Vec::new();
+        if let Some(ip) = options.client_ip {
+            bound.push(CLIENT_IP_ATTRIBUTE);
+            bound_values.push(ip.to_string().into_bytes());
+        }
+        for (name, value) in &options.headers {
+            bound.push(name.as_str());
+            bound_values.push(value.as_bytes().to_vec());
+        }
+        if !bound.is_empty() {
+            push_param(BIND_PARAM, &bound.join(","));
+        }
+
+        let tag = hmac::sign(
+            &self.key,
+            &payload(uri.path(), &query, bound_values.iter().map(Vec::as_slice)),
+        );
+        let signature = BASE64_URL_SAFE_NO_PAD.encode(tag.as_ref());
+        query.push('&');
+        query.push_str(SIGNATURE_PARAM);
+        query.push('=');
+        query.push_str(&signature);
+
+        let mut parts = uri.clone().into_parts();
+        parts.path_and_query = Some(
+            PathAndQuery::try_from(format!("{}?{query}", uri.path()))
+                .context("create signed path and query")?,
+        );
+        Uri::from_parts(parts).context("create signed uri")
+    }
+
+    /// Verify the signed URL of the given request.
+    pub fn verify<B>(&self, ctx: &Context, req: &Request<B>) -> Result<(), SignedUrlError> {
+        let query = req.uri().query().ok_or(SignedUrlError::Missing)?;
+        let (signed_query, signature) = query
+            .rsplit_once(&format!("{SIGNATURE_PARAM}="))
+            .filter(|(signed, _)| signed.is_empty() || signed.ends_with('&'))
+            .ok_or(SignedUrlError::Missing)?;
+        let signed_query = signed_query.strip_suffix('&').unwrap_or(signed_query);
+        let signature = BASE64_URL_SAFE_NO_PAD
+            .decode(signature)
+            .map_err(|_| SignedUrlError::Missing)?;
+
+        let param = |name: &str| {
+            signed_query.split('&').find_map(|pair| {
+                pair.split_once('=')
+                    .filter(|(key, _)| *key == name)
+                    .map(|(_, value)| value)
+            })
+        };
+
+        let mut bound_values = Vec::new();
+        for attribute in param(BIND_PARAM)
+            .into_iter()
+            .flat_map(|bound| bound.split(','))
+        {
+            let value = if attribute == CLIENT_IP_ATTRIBUTE {
+                ctx.get::<Forwarded>()
+                    .and_then(|f| f.client_ip())
+                    .or_else(|| ctx.get::<SocketInfo>().map(|s| s.peer_addr().ip()))
+                    .map(|ip| ip.to_string().into_bytes())
+            } else {
+                req.headers()
+                    .get(attribute)
+                    .map(|value| value.as_bytes().to_vec())
+            };
+            bound_values.push(value.ok_or(SignedUrlError::InvalidSignature)?);
+        }
+
+        hmac::verify(
+            &self.key,
+            &payload(
+                req.uri().path(),
+                signed_query,
+                bound_values.iter().map(Vec::as_slice),
+            ),
+            &signature,
+        )
+        .map_err(|_| SignedUrlError::InvalidSignature)?;
+
+        // only trust the parameters once the signature is verified
+        let expires: u64 = param(EXPIRES_PARAM)
+            .and_then(|expires| expires.parse().ok())
+            .ok_or(SignedUrlError::Missing)?;
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |now| now.as_secs());
+        if now >= expires {
+            return Err(SignedUrlError::Expired);
+        }
+
+        if let Some(methods) = param(METHODS_PARAM)
+            && !methods.split(',').any(|m| m == req.method().as_str())
+        {
+            return Err(SignedUrlError::MethodNotAllowed);
+        }
+
+        Ok(())
+    }
+}
+
+fn payload<'a>(path: &str, query: &str, bound_values: impl Iterator<Item = &'a [u8]>) -> Vec<u8> {
+    let mut payload = format!("{path}?{query}").into_bytes();
+    for value in bound_values {
+        payload.push(b'\n');
+        payload.extend_from_slice(value);
+    }
+    payload
+}
+
+/// Layer that applies the [`SignedUrl`] middleware.
+///
+/// See the [module docs](self) for more information.
+#[derive(Debug, Clone)]
+pub struct SignedUrlLayer {
+    signer: UrlSigner,
+}
+
+impl SignedUrlLayer {
+    /// Create a new [`SignedUrlLayer`], verifying signed URLs using the given [`UrlSigner`].
+    #[must_use]
+    pub fn new(signer: UrlSigner) -> Self {
+        Self { signer }
+    }
+}
+
+impl<S> Layer<S> for SignedUrlLayer {
+    type Service = SignedUrl<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        SignedUrl {
+            inner,
+            signer: self.signer.clone(),
+        }
+    }
+
+    fn into_layer(self, inner: S) -> Self::Service {
+        SignedUrl {
+            inner,
+            signer: self.signer,
+        }
+    }
+}
+
+/// Middleware which only forwards requests with a valid signed URL to the inner service.
+///
+/// See the [module docs](self) for more information.
+pub struct SignedUrl<S> {
+    inner: S,
+    signer: UrlSigner,
+}
+
+impl<S> SignedUrl<S> {
+    /// Create a new [`SignedUrl`], verifying signed URLs using the given [`UrlSigner`].
+    pub const fn new(inner: S, signer: UrlSigner) -> Self {
+        Self { inner, signer }
+    }
+
+    define_inner_service_accessors!();
+}
+
+impl<S: fmt::Debug> fmt::Debug for SignedUrl<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SignedUrl")
+            .field("inner", &self.inner)
+            .field("signer", &self.signer)
+            .finish()
+    }
+}
+
+impl<S: Clone> Clone for SignedUrl<S> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            signer: self.signer.clone(),
+        }
+    }
+}
+
+impl<S, ReqBody> Service<Request<ReqBody>> for SignedUrl<S>
+where
+    S: Service<Request<ReqBody>, Response = Response>,
+    ReqBody: Send + 'static,
+{
+    type Response = Response;
+    type Error = S::Error;
+
+    async fn serve(
+        &self,
+        ctx: Context,
+        req: Request<ReqBody>,
+    ) -> Result<Self::Response, Self::Error> {
+        if let Err(err) = self.signer.verify(&ctx, &req) {
+            tracing::debug!(uri = %req.uri(), "rejecting signed url: {err}");
+            return Ok(StatusCode::FORBIDDEN.into_response());
+        }
+        self.inner.serve(ctx, req).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rama_http_types::{Body, header};
+
+    fn req(method: Method, uri: &Uri) -> Request {
+        Request::builder()
+            .method(method)
+            .uri(uri.clone())
+            .header(header::USER_AGENT, "curl/8.0")
+            .body(Body::empty())
+            .unwrap()
+    }
+
+    #[test]
+    fn test_signed_url_verify() {
+        let signer = UrlSigner::new(b"secret");
+        let uri: Uri = "https://example.com/files/a.txt?download=1"
+            .parse()
+            .unwrap();
+        let signed = signer
+            .sign(
+                &uri,
+                &SignOptions::new(Duration::from_secs(60))
+                    .with_method(Method::GET)
+                    .with_bound_header(header::USER_AGENT, HeaderValue::from_static("curl/8.0")),
+            )
+            .unwrap();
+        assert!(signed.query().unwrap().starts_with("download=1&expires="));
+        assert!(!signed.to_string().contains("curl"));
+
+        let ctx = Context::default();
+        assert_eq!(signer.verify(&ctx, &req(Method::GET, &signed)), Ok(()));
+        assert_eq!(
+            signer.verify(&ctx, &req(Method::POST, &signed)),
+            Err(SignedUrlError::MethodNotAllowed)
+        );
+        assert_eq!(
+            signer.verify(&ctx, &req(Method::GET, &uri)),
+            Err(SignedUrlError::Missing)
+        );
+
+        let mut other_ua = req(Method::GET, &signed);
+        other_ua
+            .headers_mut()
+            .insert(header::USER_AGENT, HeaderValue::from_static("evil/1.0"));
+        assert_eq!(
+            signer.verify(&ctx, &other_ua),
+            Err(SignedUrlError::InvalidSignature)
+        );
+
+        let tampered: Uri = signed
+            .to_string()
+            .replace("a.txt", "b.txt")
+            .parse()
+            .unwrap();
+        assert_eq!(
+            signer.verify(&ctx, &req(Method::GET, &tampered)),
+            Err(SignedUrlError::InvalidSignature)
+        );
+
+        assert_eq!(
+            UrlSigner::new(b"other").verify(&ctx, &req(Method::GET, &signed)),
+            Err(SignedUrlError::InvalidSignature)
+        );
+    }
+
+    #[test]
+    fn test_signed_url_expired_and_client_ip() {
+        let signer = UrlSigner::new(b"secret");
+        let uri: Uri = "/files/a.txt".parse().unwrap();
+        let ip: IpAddr = "10.0.0.1".parse().unwrap();
+
+        let signed = signer
+            .sign(&uri, &SignOptions::new(Duration::ZERO))
+            .unwrap();
+        assert_eq!(
+            signer.verify(&Context::default(), &req(Method::GET, &signed)),
+            Err(SignedUrlError::Expired)
+        );
+
+        let signed = signer
+            .sign(
+                &uri,
+                &SignOptions::new(Duration::from_secs(60)).with_client_ip(ip),
+            )
+            .unwrap();
+        let mut ctx = Context::default();
+        ctx.insert(SocketInfo::new(None, (ip, 4242).into()));
+        assert_eq!(signer.verify(&ctx, &req(Method::GET, &signed)), Ok(()));
+        assert_eq!(
+            signer.verify(&Context::default(), &req(Method::GET, &signed)),
+            Err(SignedUrlError::InvalidSignature)
+        );
+    }
+}
@@ -0,0 +1,431 @@
+//! Middleware to tarpit abusive clients.
+//!
+//! Rather than refusing requests of abusive clients (e.g. vulnerability scanners,
+//! credential stuffers or blocklisted addresses), which only makes them move on faster,
+//! the [`Tarpit`] middleware answers them extremely slowly: the response body is drip-fed,
+//! a single byte at a time with a long interval in between, for as long as the configured duration.
+//! This keeps the attacker's connection (and often a worker of its tooling) occupied,
+//! while costing the server no more than a timer per tarpitted request.
+//!
+//! A request is tarpitted when:
+//!
+//! - it is matched by the [`Matcher`] of the layer, e.g. an [`HttpMatcher`] matching
+//!   a known bad user agent, or a [`SocketMatcher`] matching a blocklist of addresses;
+//! - or, if enabled using [`TarpitLayer::with_failed_auth_streak`], its client
+//!   recently got a streak of consecutive `401 Unauthorized` or `403 Forbidden` responses.
+//!
+//! The number of requests tarpitted concurrently is limited (see [`TarpitLayer::with_max_concurrent`]),
+//! such that abusive clients cannot turn the tarpit against the server itself.
+//! Once the limit is reached, abusive requests are answered with `403 Forbidden` immediately.
+//!
+//! [`HttpMatcher`]: crate::matcher::HttpMatcher
+//! [`SocketMatcher`]: rama_net::stream::matcher::SocketMatcher
+//!
+//! # Example
+//!
+//! ```
+//! use rama_core::{Context, Layer, Service, matcher::match_fn, service::service_fn};
+//! use rama_http::layer::tarpit::TarpitLayer;
+//! use rama_http::{Body, BodyExtractExt, Request, Response, header};
+//! use std::{convert::Infallible, time::Duration};
+//!
+//! # #[tokio::main]
+//! # async fn main() {
+//! let service = TarpitLayer::new(match_fn(|req: &Request| {
+//!     req.headers()
+//!         .get(header::USER_AGENT)
+//!         .and_then(|ua| ua.to_str().ok())
+//!         .is_some_and(|ua| ua.contains("sqlmap"))
+//! }))
+//! .with_failed_auth_streak(5, Duration::from_secs(600))
+//! .into_layer(service_fn(async |_: Request| {
+//!     Ok::<_, Infallible>(Response::new(Body::from("hello")))
+//! }));
+//!
+//! let req = Request::get("/").body(Body::empty()).unwrap();
+//! let resp = service.serve(Context::default(), req).await.unwrap();
+//! assert_eq!(resp.try_into_string().await.unwrap(), "hello");
+//! # }
+//! ```
+
+use std::{
+    collections::HashMap,
+    convert::Infallible,
+    fmt,
+    net::IpAddr,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use crate::service::web::response::IntoResponse;
+use rama_core::{
+    Context, Layer, Service,
+    bytes::Bytes,
+    futures::stream,
+    layer::limit::policy::{ConcurrentCounter, ConcurrentTracker},
+    matcher::Matcher,
+    telemetry::tracing,
+};
+use rama_http_types::{Body, HeaderValue, Request, Response, StatusCode, header};
+use rama_net::{forwarded::Forwarded, stream::SocketInfo};
+use rama_utils::macros::define_inner_service_accessors;
+
+/// The maximum number of clients of which the failed auth streak is tracked.
+const MAX_TRACKED_CLIENTS: usize = 10_000;
+
+/// Layer that applies the [`Tarpit`] middleware.
+///
+/// See the [module docs](self) for more information.
+#[derive(Debug, Clone)]
+pub struct TarpitLayer<M> {
+    matcher: M,
+    status: StatusCode,
+    interval: Duration,
+    duration: Duration,
+    concurrency: ConcurrentCounter,
+    auth_failures: Option<Arc<AuthFailures>>,
+}
+
+impl<M> TarpitLayer<M> {
+    /// Create a new [`TarpitLayer`], tarpitting the requests matched by the given [`Matcher`].
+    ///
+    /// Use `false` as the matcher to only tarpit clients with a failed auth streak.
+    pub fn new(matcher: M) -> Self {
+        Self {
+            matcher,
+            status: StatusCode::OK,
+            interval: Duration::from_secs(10),
+            duration: Duration::from_secs(10 * 60),
+            concurrency: ConcurrentCounter::new(1024),
+            auth_failures: None,
+        }
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Set the status of the tarpit responses.
+        ///
+        /// Defaults to `200 OK`, which keeps most tooling waiting for the body.
+        pub fn status(mut self, status: StatusCode) -> Self {
+            self.status = status;
+            self
+        }
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Set the interval in between the bytes of the tarpit responses.
+        ///
+        /// Defaults to 10 seconds.
+        pub fn interval(mut self, interval: Duration) -> Self {
+            self.interval = interval;
+            self
+        }
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Set the duration for which the body of a tarpit response is drip-fed,
+        /// after which it ends.
+        ///
+        /// Defaults to 10 minutes.
+        pub fn duration(mut self, duration: Duration) -> Self {
+            self.duration = duration;
+            self
+        }
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Set the maximum number of requests tarpitted concurrently.
+        ///
+        /// Defaults to 1024.
+        pub fn max_concurrent(mut self, max: usize) -> Self {
+            self.concurrency = ConcurrentCounter::new(max);
+            self
+        }
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Tarpit the clients which received at least `max` consecutive
+        /// `401 Unauthorized` or `403 Forbidden` responses, the last of which within the given window.
+        ///
+        /// Clients are identified by their IP address, as found in the [`Forwarded`]
+        /// or [`SocketInfo`] of the [`Context`]. A successful response resets the streak.
+        pub fn failed_auth_streak(mut self, max: u32, window: Duration) -> Self {
+            self.auth_failures = Some(Arc::new(AuthFailures {
+                max,
+                window,
+                clients: Mutex::new(HashMap::new()),
+            }));
+            self
+        }
+    }
+}
+
+impl<S, M: Clone> Layer<S> for TarpitLayer<M> {
+    type Service = Tarpit<S, M>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        Tarpit {
+            inner,
+            matcher: self.matcher.clone(),
+            status: self.status,
+            interval: self.interval,
+            duration: self.duration,
+            concurrency: self.concurrency.clone(),
+            auth_failures: self.auth_failures.clone(),
+        }
+    }
+
+    fn into_layer(self, inner: S) -> Self::Service {
+        Tarpit {
+            inner,
+            matcher: self.matcher,
+            status: self.status,
+            interval: self.interval,
+            duration: self.duration,
+            concurrency: self.concurrency,
+            auth_failures: self.auth_failures,
+        }
+    }
+}
+
+/// Middleware to tarpit abusive clients.
+///
+/// See the [module docs](self) for more information.
+pub struct Tarpit<S, M> {
+    inner: S,
+    matcher: M,
+    status: StatusCode,
+    interval: Duration,
+    duration: Duration,
+    concurrency: ConcurrentCounter,
+    auth_failures: Option<Arc<AuthFailures>>,
+}
+
+impl<S, M> Tarpit<S, M> {
+    define_inner_service_accessors!();
+}
+
+impl<S: fmt::Debug, M: fmt::Debug> fmt::Debug for Tarpit<S, M> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Tarpit")
+            .field("inner", &self.inner)
+            .field("matcher", &self.matcher)
+            .field("status", &self.status)
+            .field("interval", &self.interval)
+            .field("duration", &self.duration)
+            .field("concurrency", &self.concurrency)
+            .field("auth_failures", &self.auth_failures)
+            .finish()
+    }
+}
+
+impl<S: Clone, M: Clone> Clone for Tarpit<S, M> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            matcher: self.matcher.clone(),
+            status: self.status,
+            interval: self.interval,
+            duration: self.duration,
+            concurrency: self.concurrency.clone(),
+            auth_failures: self.auth_failures.clone(),
+        }
+    }
+}
+
+impl<S, M> Tarpit<S, M> {
+    fn tarpit(&self) -> Response {
+        let Ok(guard) = self.concurrency.try_access() else {
+            tracing::debug!("tarpit full: refusing abusive request immediately");
+            return StatusCode::FORBIDDEN.into_response();
+        };
+
+        let interval = self.interval;
+        let drips = self.duration.as_millis() / interval.as_millis().max(1);
+        // the guard is kept alive for as long as the body is being streamed
+        let body = stream::unfold((guard, drips), move |(guard, drips)| async move {
+            if drips == 0 {
+                return None;
+            }
+            tokio::time::sleep(interval).await;
+            Some((
+                Ok::<_, Infallible>(Bytes::from_static(b" ")),
+                (guard, drips - 1),
+            ))
+        });
+
+        let mut resp = Response::new(Body::from_stream(body));
+        *resp.status_mut() = self.status;
+        resp.headers_mut().insert(
+            header::CONTENT_TYPE,
+            HeaderValue::from_static("text/html; charset=utf-8"),
+        );
+        resp
+    }
+}
+
+impl<S, M, ReqBody> Service<Request<ReqBody>> for Tarpit<S, M>
+where
+    S: Service<Request<ReqBody>, Response = Response>,
+    M: Matcher<Request<ReqBody>>,
+    ReqBody: Send + 'static,
+{
+    type Response = Response;
+    type Error = S::Error;
+
+    async fn serve(
+        &self,
+        ctx: Context,
+        req: Request<ReqBody>,
+    ) -> Result<Self::Response, Self::Error> {
+        let client = self.auth_failures.as_ref().and_then(|_| {
+            ctx.get::<Forwarded>()
+                .and_then(|f| f.client_ip())
+                .or_else(|| ctx.get::<SocketInfo>().map(|s| s.peer_addr().ip()))
+        });
+
+        let abusive = self.matcher.matches(None, &ctx, &req)
+            || self
+                .auth_failures
+                .as_ref()
+                .zip(client)
+                .is_some_and(|(failures, ip)| failures.is_abusive(ip));
+        if abusive {
+            tracing::debug!(uri = %req.uri(), "tarpitting abusive request");
+            return Ok(self.tarpit());
+        }
+
+        let resp = self.inner.serve(ctx, req).await?;
+        if let Some((failures, ip)) = self.auth_failures.as_ref().zip(client) {
+            failures.record(ip, resp.status());
+        }
+        Ok(resp)
+    }
+}
+
+/// Tracks the streak of failed auth responses per client.
+struct AuthFailures {
+    max: u32,
+    window: Duration,
+    clients: Mutex<HashMap<IpAddr, (u32, Instant)>>,
+}
+
+impl fmt::Debug for AuthFailures {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AuthFailures")
+            .field("max", &self.max)
+            .field("window", &self.window)
+            .finish()
+    }
+}
+
+impl AuthFailures {
+    fn is_abusive(&self, ip: IpAddr) -> bool {
+        let clients = self.clients.lock().unwrap_or_else(|err| err.into_inner());
+        clients
+            .get(&ip)
+            .is_some_and(|(count, last)| *count >= self.max && last.elapsed() < self.window)
+    }
+
+    fn record(&self, ip: IpAddr, status: StatusCode) {
+        let mut clients = self.clients.lock().unwrap_or_else(|err| err.into_inner());
+        if status == StatusCode::UNAUTHORIZED || status == StatusCode::FORBIDDEN {
+            if clients.len() >= MAX_TRACKED_CLIENTS && !clients.contains_key(&ip) {
+                clients.retain(|_, (_, last)| last.elapsed() < self.window);
+                if clients.len() >= MAX_TRACKED_CLIENTS {
+                    return;
+                }
+            }
+            let (count, last) = clients.entry(ip).or_insert((0, Instant::now()));
+            if last.elapsed() >= self.window {
+                *count = 0;
+            }
+            *count += 1;
+            *last = Instant::now();
+        } else if status.is_success() {
+            clients.remove(&ip);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dep::http_body_util::BodyExt;
+    use crate::matcher::HttpMatcher;
+    use rama_core::service::service_fn;
+
+    #[tokio::test]
+    async fn test_tarpit_matched_request() {
+        let service = TarpitLayer::new(HttpMatcher::header(
+            header::USER_AGENT,
+            HeaderValue::from_static("sqlmap/1.8"),
+        ))
+        .with_interval(Duration::from_millis(10))
+        .with_duration(Duration::from_millis(50))
+        .into_layer(service_fn(async |_: Request| {
+            Ok::<_, Infallible>(Response::new(Body::from("hello")))
+        }));
+
+        let req = Request::get("/").body(Body::empty()).unwrap();
+        let resp = service.serve(Context::default(), req).await.unwrap();
+        assert_eq!(
+            resp.into_body().collect().await.unwrap().to_bytes(),
+            "hello"
+        );
+
+        let req = Request::get("/")
+            .header(header::USER_AGENT, "sqlmap/1.8")
+            .body(Body::empty())
+            .unwrap();
+        let start = Instant::now();
+        let resp = service.serve(Context::default(), req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let body = resp.into_body().collect().await.unwrap().to_bytes();
+        assert_eq!(body, "     ");
+        assert!(start.elapsed() >= Duration::from_millis(50));
+    }
+
+    #[tokio::test]
+    async fn test_tarpit_failed_auth_streak() {
+        let service = TarpitLayer::new(false)
+            .with_failed_auth_streak(2, Duration::from_secs(60))
+            .with_interval(Duration::from_millis(1))
+            .with_duration(Duration::from_millis(1))
+            .with_max_concurrent(1)
+            .into_layer(service_fn(async |req: Request| {
+                let status = if req.headers().contains_key(header::AUTHORIZATION) {
+                    StatusCode::OK
+                } else {
+                    StatusCode::UNAUTHORIZED
+                };
+                Ok::<_, Infallible>(status.into_response())
+            }));
+
+        let ctx = |ip: &str| {
+            let mut ctx = Context::default();
+            ctx.insert(SocketInfo::new(None, format!("{ip}:4242").parse().unwrap()));
+            ctx
+        };
+        let req = || Request::get("/").body(Body::empty()).unwrap();
+
+        for _ in 0..2 {
+            let resp = service.serve(ctx("10.0.0.1"), req()).await.unwrap();
+            assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+        }
+        let resp = service.serve(ctx("10.0.0.1"), req()).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(
+            resp.headers()[header::CONTENT_TYPE],
+            "text/html; charset=utf-8"
+        );
+
+        // the tarpit is full while the previous body is not consumed
+        let resp2 = service.serve(ctx("10.0.0.1"), req()).await.unwrap();
+        assert_eq!(resp2.status(), StatusCode::FORBIDDEN);
+        drop(resp);
+
+        // other clients are not affected
+        let resp = service.serve(ctx("10.0.0.2"), req()).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+    }
+}
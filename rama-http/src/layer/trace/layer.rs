@@ -5,6 +5,7 @@ use super::{
 use crate::layer::classify::{
     GrpcErrorsAsFailures, MakeClassifier, ServerErrorsAsFailures, SharedClassifier,
 };
+use crate::layer::redact::Redaction;
 use rama_core::Layer;
 use std::fmt;
 
@@ -222,6 +223,39 @@ impl<M, MakeSpan, OnRequest, OnResponse, OnBodyChunk, OnEos, OnFailure>
     }
 }
 
+impl<M, OnRequest, OnEos, OnFailure>
+    TraceLayer<
+        M,
+        DefaultMakeSpan,
+        OnRequest,
+        DefaultOnResponse,
+        DefaultOnBodyChunk,
+        OnEos,
+        OnFailure,
+    >
+{
+    /// Apply the given [`Redaction`] to the request uri, headers and response headers,
+    /// as well as the response body sampling.
+    ///
+    /// Defaults to [`Redaction::new`].
+    #[must_use]
+    pub fn redaction(mut self, redaction: Redaction) -> Self {
+        self.set_redaction(redaction);
+        self
+    }
+
+    /// Apply the given [`Redaction`] to the request uri, headers and response headers,
+    /// as well as the response body sampling.
+    ///
+    /// Defaults to [`Redaction::new`].
+    pub fn set_redaction(&mut self, redaction: Redaction) -> &mut Self {
+        self.on_body_chunk.set_redaction(&redaction);
+        self.on_response.set_redaction(redaction.clone());
+        self.make_span.set_redaction(redaction);
+        self
+    }
+}
+
 impl TraceLayer<HttpMakeClassifier> {
     /// Create a new [`TraceLayer`] using [`ServerErrorsAsFailures`] which supports classifying
     /// regular HTTP responses based on the status code.
@@ -1,5 +1,6 @@
 use crate::Request;
 use crate::header::USER_AGENT;
+use crate::layer::redact::Redaction;
 use crate::opentelemetry::version_as_protocol_version;
 use rama_core::telemetry::tracing::{self, Level, Span};
 
@@ -38,15 +39,17 @@ where
 pub struct DefaultMakeSpan {
     level: Level,
     include_headers: bool,
+    redaction: Redaction,
 }
 
 impl DefaultMakeSpan {
     /// Create a new `DefaultMakeSpan`.
     #[must_use]
-    pub const fn new() -> Self {
+    pub fn new() -> Self {
         Self {
             level: DEFAULT_MESSAGE_LEVEL,
             include_headers: false,
+            redaction: Redaction::new(),
         }
     }
 
@@ -91,6 +94,23 @@ impl DefaultMakeSpan {
         self.include_headers = include_headers;
         self
     }
+
+    /// Set the [`Redaction`] applied to the request uri and headers.
+    ///
+    /// Defaults to [`Redaction::new`].
+    #[must_use]
+    pub fn redaction(mut self, redaction: Redaction) -> Self {
+        self.redaction = redaction;
+        self
+    }
+
+    /// Set the [`Redaction`] applied to the request uri and headers.
+    ///
+    /// Defaults to [`Redaction::new`].
+    pub fn set_redaction(&mut self, redaction: Redaction) -> &mut Self {
+        self.redaction = redaction;
+        self
+    }
 }
 
 impl Default for DefaultMakeSpan {
@@ -101,6 +121,8 @@ impl Default for DefaultMakeSpan {
 
 impl<B> MakeSpan<B> for DefaultMakeSpan {
     fn make_span(&self, request: &Request<B>) -> Span {
+        let uri = self.redaction.redact_uri(request.uri());
+
         // This ugly macro is needed, unfortunately, because `tracing::span!`
         // required the level argument to be static. Meaning we can't just pass
         // `self.level`.
@@ -111,23 +133,23 @@ impl<B> MakeSpan<B> for DefaultMakeSpan {
                         $level,
                         "request",
                         http.request.method = %request.method(),
-                        url.full = %request.uri(),
-                        url.path = %request.uri().path(),
-                        url.query = request.uri().query().unwrap_or_default(),
+                        url.full = %uri,
+                        url.path = %uri.path(),
+                        url.query = uri.query().unwrap_or_default(),
                         url.scheme = %request.uri().scheme().map(|s| s.as_str()).unwrap_or_default(),
                         network.protocol.name = "http",
                         network.protocol.version = version_as_protocol_version(request.version()),
                         user_agent.original = %request.headers().get(USER_AGENT).and_then(|v| v.to_str().ok()).unwrap_or_default(),
-                        headers = ?request.headers(),
+                        headers = ?self.redaction.redact_headers(request.headers()),
                     )
                 } else {
                     tracing::span!(
                         $level,
                         "request",
                         http.request.method = %request.method(),
-                        url.full = %request.uri(),
-                        url.path = %request.uri().path(),
-                        url.query = request.uri().query().unwrap_or_default(),
+                        url.full = %uri,
+                        url.path = %uri.path(),
+                        url.query = uri.query().unwrap_or_default(),
                         url.scheme = %request.uri().scheme().map(|s| s.as_str()).unwrap_or_default(),
                         network.protocol.name = "http",
                         network.protocol.version = version_as_protocol_version(request.version()),
@@ -177,6 +177,15 @@
 //! # }
 //! ```
 //!
+//! ## Redaction
+//!
+//! The default callbacks redact sensitive data before it is logged: query parameter values
+//! are masked and only the values of allowlisted headers are included, with cookies scrubbed.
+//! Sampling of the response body is disabled by default.
+//! Use [`TraceLayer::redaction`] to configure this, see [`redact`] for more details.
+//!
+//! [`redact`]: crate::layer::redact
+//!
 //! # When the callbacks are called
 //!
 //! ### `on_request`
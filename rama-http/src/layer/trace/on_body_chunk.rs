@@ -1,4 +1,6 @@
-use rama_core::telemetry::tracing::Span;
+use crate::layer::redact::Redaction;
+use rama_core::bytes::Buf;
+use rama_core::telemetry::tracing::{self, Span};
 use std::time::Duration;
 
 /// Trait used to tell [`Trace`] what to do when a body chunk has been sent.
@@ -38,23 +40,57 @@ impl<B> OnBodyChunk<B> for () {
 
 /// The default [`OnBodyChunk`] implementation used by [`Trace`].
 ///
-/// Simply does nothing.
+/// Does nothing, unless body sampling is enabled using [`DefaultOnBodyChunk::redaction`],
+/// in which case the first bytes of the response body are logged.
 ///
 /// [`Trace`]: super::Trace
 #[derive(Debug, Default, Clone)]
 pub struct DefaultOnBodyChunk {
-    _priv: (),
+    sample_size: usize,
+    sampled: usize,
 }
 
 impl DefaultOnBodyChunk {
     /// Create a new `DefaultOnBodyChunk`.
     #[must_use]
     pub const fn new() -> Self {
-        Self { _priv: () }
+        Self {
+            sample_size: 0,
+            sampled: 0,
+        }
+    }
+
+    /// Sample the response body as configured by the given [`Redaction`].
+    ///
+    /// By default bodies are not sampled.
+    #[must_use]
+    pub fn redaction(mut self, redaction: &Redaction) -> Self {
+        self.sample_size = redaction.body_sample_size();
+        self
+    }
+
+    /// Sample the response body as configured by the given [`Redaction`].
+    ///
+    /// By default bodies are not sampled.
+    pub fn set_redaction(&mut self, redaction: &Redaction) -> &mut Self {
+        self.sample_size = redaction.body_sample_size();
+        self
     }
 }
 
-impl<B> OnBodyChunk<B> for DefaultOnBodyChunk {
+impl<B: Buf> OnBodyChunk<B> for DefaultOnBodyChunk {
     #[inline]
-    fn on_body_chunk(&mut self, _: &B, _: Duration, _: &Span) {}
+    fn on_body_chunk(&mut self, chunk: &B, _: Duration, _: &Span) {
+        let remaining = self.sample_size - self.sampled;
+        if remaining == 0 {
+            return;
+        }
+        let data = chunk.chunk();
+        let sample = &data[..data.len().min(remaining)];
+        self.sampled += sample.len();
+        tracing::debug!(
+            body.sample = %String::from_utf8_lossy(sample),
+            "response body sample"
+        );
+    }
 }
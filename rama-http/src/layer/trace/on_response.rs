@@ -1,5 +1,6 @@
 use super::{DEFAULT_MESSAGE_LEVEL, Latency};
 use crate::Response;
+use crate::layer::redact::Redaction;
 use rama_core::telemetry::tracing::{self, Level, Span};
 use rama_utils::latency::LatencyUnit;
 use std::time::Duration;
@@ -47,6 +48,7 @@ pub struct DefaultOnResponse {
     level: Level,
     latency_unit: LatencyUnit,
     include_headers: bool,
+    redaction: Redaction,
 }
 
 impl Default for DefaultOnResponse {
@@ -55,6 +57,7 @@ impl Default for DefaultOnResponse {
             level: DEFAULT_MESSAGE_LEVEL,
             latency_unit: LatencyUnit::Millis,
             include_headers: false,
+            redaction: Redaction::new(),
         }
     }
 }
@@ -136,6 +139,23 @@ impl DefaultOnResponse {
         self.include_headers = include_headers;
         self
     }
+
+    /// Set the [`Redaction`] applied to the response headers.
+    ///
+    /// Defaults to [`Redaction::new`].
+    #[must_use]
+    pub fn redaction(mut self, redaction: Redaction) -> Self {
+        self.redaction = redaction;
+        self
+    }
+
+    /// Set the [`Redaction`] applied to the response headers.
+    ///
+    /// Defaults to [`Redaction::new`].
+    pub fn set_redaction(&mut self, redaction: Redaction) -> &mut Self {
+        self.redaction = redaction;
+        self
+    }
 }
 
 impl<B> OnResponse<B> for DefaultOnResponse {
@@ -146,7 +166,7 @@ impl<B> OnResponse<B> for DefaultOnResponse {
         };
         let response_headers = self
             .include_headers
-            .then(|| tracing::field::debug(response.headers()));
+            .then(|| tracing::field::debug(self.redaction.redact_headers(response.headers())));
 
         event_dynamic_lvl!(
             self.level,
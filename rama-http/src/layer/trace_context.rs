@@ -0,0 +1,604 @@
+//! W3C [trace-context] propagation, with B3 fallback.
+//!
+//! The [`TraceContextLayer`] extracts the upstream trace context from the
+//! `traceparent` and `tracestate` headers of incoming requests,
+//! falling back to the [B3] headers (`b3` or `X-B3-*`) if no valid `traceparent` is found.
+//! The request is served within a new (server) span linked to that upstream trace,
+//! and the resulting [`TraceContext`] is inserted into the [`Context`],
+//! such that it can be propagated further.
+//!
+//! The [`InjectTraceContextLayer`] is its client counterpart, injecting the
+//! [`TraceContext`] found in the [`Context`] (or that of the current span)
+//! into outbound requests made within that same [`Context`].
+//!
+//! [trace-context]: https://www.w3.org/TR/trace-context/
+//! [B3]: https://github.com/openzipkin/b3-propagation
+//!
+//! # Example
+//!
+//! ```
+//! use rama_http::layer::trace_context::{InjectTraceContextLayer, TraceContextLayer};
+//! use rama_http::{Body, Request, Response};
+//! use rama_core::service::service_fn;
+//! use rama_core::{Context, Layer, Service};
+//! use std::convert::Infallible;
+//!
+//! # #[tokio::main]
+//! # async fn main() {
+//! // outbound client, which will receive the `traceparent` header of the incoming request
+//! let client = InjectTraceContextLayer::new().into_layer(service_fn(async |req: Request| {
+//!     Ok::<_, Infallible>(Response::new(Body::from(
+//!         req.headers()["traceparent"].as_bytes().to_vec(),
+//!     )))
+//! }));
+//!
+//! let server = TraceContextLayer::new().into_layer(service_fn(
+//!     async move |ctx: Context, _req: Request| {
+//!         client.serve(ctx, Request::new(Body::empty())).await
+//!     },
+//! ));
+//!
+//! let req = Request::builder()
+//!     .header("traceparent", "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01")
+//!     .body(Body::empty())
+//!     .unwrap();
+//! let _ = server.serve(Context::default(), req).await.unwrap();
+//! # }
+//! ```
+
+use crate::{HeaderMap, HeaderName, HeaderValue, Request};
+use opentelemetry_http::{HeaderExtractor, HeaderInjector};
+use rama_core::telemetry::opentelemetry::{
+    Context as OtelContext, SpanId, TraceFlags, TraceId,
+    propagation::TextMapPropagator,
+    sdk::propagation::TraceContextPropagator,
+    trace::{SpanContext, TraceContextExt, TraceState},
+};
+use rama_core::telemetry::tracing::{self, Instrument, OpenTelemetrySpanExt};
+use rama_core::{Context, Layer, Service};
+use rama_utils::macros::define_inner_service_accessors;
+use std::fmt;
+
+const B3: HeaderName = HeaderName::from_static("b3");
+const X_B3_TRACE_ID: HeaderName = HeaderName::from_static("x-b3-traceid");
+const X_B3_SPAN_ID: HeaderName = HeaderName::from_static("x-b3-spanid");
+const X_B3_SAMPLED: HeaderName = HeaderName::from_static("x-b3-sampled");
+const X_B3_FLAGS: HeaderName = HeaderName::from_static("x-b3-flags");
+
+/// A trace context, as extracted from (or to be injected into) request headers.
+///
+/// Inserted into the [`Context`] by the [`TraceContextService`],
+/// and used by the [`InjectTraceContext`] service.
+#[derive(Debug, Clone)]
+pub struct TraceContext(OtelContext);
+
+impl TraceContext {
+    /// Create a new [`TraceContext`] from an OpenTelemetry [`Context`](OtelContext).
+    #[must_use]
+    pub fn new(cx: OtelContext) -> Self {
+        Self(cx)
+    }
+
+    /// Create a new [`TraceContext`] for a remote [`SpanContext`].
+    #[must_use]
+    pub fn remote(span_context: SpanContext) -> Self {
+        Self(OtelContext::new().with_remote_span_context(span_context))
+    }
+
+    /// Returns a reference to the OpenTelemetry [`Context`](OtelContext).
+    #[must_use]
+    pub fn otel_context(&self) -> &OtelContext {
+        &self.0
+    }
+
+    /// Returns the [`SpanContext`] of this [`TraceContext`].
+    #[must_use]
+    pub fn span_context(&self) -> SpanContext {
+        self.0.span().span_context().clone()
+    }
+
+    fn is_valid(&self) -> bool {
+        self.0.span().span_context().is_valid()
+    }
+
+    /// Extract the W3C trace context (`traceparent` and `tracestate`) from the headers.
+    ///
+    /// Returns `None` if no valid `traceparent` header was found.
+    #[must_use]
+    pub fn extract_w3c(headers: &HeaderMap) -> Option<Self> {
+        let trace_context = Self(TraceContextPropagator::new().extract(&HeaderExtractor(headers)));
+        trace_context.is_valid().then_some(trace_context)
+    }
+
+    /// Extract the B3 trace context from the headers,
+    /// using either the single `b3` header or the multiple `X-B3-*` headers.
+    ///
+    /// Returns `None` if no valid B3 headers were found.
+    #[must_use]
+    pub fn extract_b3(headers: &HeaderMap) -> Option<Self> {
+        let header_str = |name| headers.get(name).and_then(|value| value.to_str().ok());
+
+        let (trace_id, span_id, sampled) = match header_str(B3) {
+            Some(value) => {
+                let mut parts = value.split('-');
+                let trace_id = parts.next()?;
+                let span_id = parts.next()?;
+                (trace_id, span_id, parts.next())
+            }
+            None => (
+                header_str(X_B3_TRACE_ID)?,
+                header_str(X_B3_SPAN_ID)?,
+                header_str(X_B3_FLAGS)
+                    .filter(|flags| *flags == "1")
+                    .or_else(|| header_str(X_B3_SAMPLED)),
+            ),
+        };
+
+        if !matches!(trace_id.len(), 16 | 32) || span_id.len() != 16 {
+            return None;
+        }
+        let trace_flags = match sampled {
+            Some("1" | "d" | "true") => TraceFlags::SAMPLED,
+            _ => TraceFlags::default(),
+        };
+        let span_context = SpanContext::new(
+            TraceId::from_hex(trace_id).ok()?,
+            SpanId::from_hex(span_id).ok()?,
+            trace_flags,
+            true,
+            TraceState::NONE,
+        );
+        span_context.is_valid().then(|| Self::remote(span_context))
+    }
+
+    /// Extract the trace context from the headers,
+    /// using the W3C headers if available and the B3 headers otherwise.
+    #[must_use]
+    pub fn extract(headers: &HeaderMap) -> Option<Self> {
+        Self::extract_w3c(headers).or_else(|| Self::extract_b3(headers))
+    }
+
+    /// Inject this trace context into the headers,
+    /// as W3C headers and optionally B3 headers as well.
+    pub fn inject(&self, headers: &mut HeaderMap, b3: B3Propagation) {
+        TraceContextPropagator::new().inject_context(&self.0, &mut HeaderInjector(headers));
+
+        let span_context = self.span_context();
+        let trace_id = span_context.trace_id();
+        let span_id = span_context.span_id();
+        let sampled = if span_context.is_sampled() { "1" } else { "0" };
+        match b3 {
+            B3Propagation::None => (),
+            B3Propagation::Single => {
+                if let Ok(value) = HeaderValue::try_from(format!("{trace_id}-{span_id}-{sampled}"))
+                {
+                    headers.insert(B3, value);
+                }
+            }
+            B3Propagation::Multi => {
+                for (name, value) in [
+                    (X_B3_TRACE_ID, trace_id.to_string()),
+                    (X_B3_SPAN_ID, span_id.to_string()),
+                    (X_B3_SAMPLED, sampled.to_owned()),
+                ] {
+                    if let Ok(value) = HeaderValue::try_from(value) {
+                        headers.insert(name, value);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Whether and how to inject [B3] headers, next to the W3C trace-context headers.
+///
+/// [B3]: https://github.com/openzipkin/b3-propagation
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum B3Propagation {
+    /// Do not inject B3 headers.
+    #[default]
+    None,
+    /// Inject the single `b3` header.
+    Single,
+    /// Inject the multiple `X-B3-TraceId`, `X-B3-SpanId` and `X-B3-Sampled` headers.
+    Multi,
+}
+
+/// Layer that applies the [`TraceContextService`] middleware.
+///
+/// See the [module docs](crate::layer::trace_context) for more details.
+#[derive(Debug, Clone)]
+pub struct TraceContextLayer {
+    b3_fallback: bool,
+}
+
+impl Default for TraceContextLayer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TraceContextLayer {
+    /// Create a new [`TraceContextLayer`], with B3 fallback enabled.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self { b3_fallback: true }
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Set whether the B3 headers are used when no W3C trace context was found.
+        ///
+        /// Enabled by default.
+        pub fn b3_fallback(mut self, enabled: bool) -> Self {
+            self.b3_fallback = enabled;
+            self
+        }
+    }
+}
+
+impl<S> Layer<S> for TraceContextLayer {
+    type Service = TraceContextService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        TraceContextService {
+            inner,
+            b3_fallback: self.b3_fallback,
+        }
+    }
+}
+
+/// Middleware which extracts the upstream trace context from incoming requests,
+/// serving them within a span linked to that trace.
+///
+/// See the [module docs](crate::layer::trace_context) for more details.
+#[derive(Clone)]
+pub struct TraceContextService<S> {
+    inner: S,
+    b3_fallback: bool,
+}
+
+impl<S: fmt::Debug> fmt::Debug for TraceContextService<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TraceContextService")
+            .field("inner", &self.inner)
+            .field("b3_fallback", &self.b3_fallback)
+            .finish()
+    }
+}
+
+impl<S> TraceContextService<S> {
+    /// Create a new [`TraceContextService`], with B3 fallback enabled.
+    pub const fn new(inner: S) -> Self {
+        Self {
+            inner,
+            b3_fallback: true,
+        }
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Set whether the B3 headers are used when no W3C trace context was found.
+        ///
+        /// Enabled by default.
+        pub fn b3_fallback(mut self, enabled: bool) -> Self {
+            self.b3_fallback = enabled;
+            self
+        }
+    }
+
+    define_inner_service_accessors!();
+}
+
+impl<S, Body> Service<Request<Body>> for TraceContextService<S>
+where
+    S: Service<Request<Body>>,
+    Body: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+
+    async fn serve(
+        &self,
+        mut ctx: Context,
+        req: Request<Body>,
+    ) -> Result<Self::Response, Self::Error> {
+        let upstream = if self.b3_fallback {
+            TraceContext::extract(req.headers())
+        } else {
+            TraceContext::extract_w3c(req.headers())
+        };
+
+        let span = tracing::span!(
+            tracing::Level::INFO,
+            "http.server.request",
+            otel.kind = "server",
+            http.request.method = %req.method(),
+            url.path = %req.uri().path(),
+            trace.id = tracing::field::Empty,
+            span.id = tracing::field::Empty,
+        );
+
+        if let Some(upstream) = &upstream {
+            span.set_parent(upstream.0.clone());
+        }
+
+        // without an OpenTelemetry subscriber the span has no valid context,
+        // in which case the upstream trace context is propagated as-is
+        let trace_context = Some(TraceContext(span.context()))
+            .filter(TraceContext::is_valid)
+            .or(upstream);
+        if let Some(trace_context) = trace_context {
+            let span_context = trace_context.span_context();
+            span.record("trace.id", span_context.trace_id().to_string());
+            span.record("span.id", span_context.span_id().to_string());
+            ctx.insert(trace_context);
+        }
+
+        self.inner.serve(ctx, req).instrument(span).await
+    }
+}
+
+/// Layer that applies the [`InjectTraceContext`] middleware.
+///
+/// See the [module docs](crate::layer::trace_context) for more details.
+#[derive(Debug, Clone, Default)]
+pub struct InjectTraceContextLayer {
+    b3: B3Propagation,
+}
+
+impl InjectTraceContextLayer {
+    /// Create a new [`InjectTraceContextLayer`], injecting only the W3C headers.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            b3: B3Propagation::None,
+        }
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Set whether and how B3 headers are injected as well.
+        pub fn b3(mut self, b3: B3Propagation) -> Self {
+            self.b3 = b3;
+            self
+        }
+    }
+}
+
+impl<S> Layer<S> for InjectTraceContextLayer {
+    type Service = InjectTraceContext<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        InjectTraceContext { inner, b3: self.b3 }
+    }
+}
+
+/// Middleware which injects the [`TraceContext`] into outbound requests.
+///
+/// The [`TraceContext`] found in the [`Context`] is used,
+/// falling back to that of the current span.
+/// Requests are left untouched if neither is valid.
+///
+/// See the [module docs](crate::layer::trace_context) for more details.
+#[derive(Clone)]
+pub struct InjectTraceContext<S> {
+    inner: S,
+    b3: B3Propagation,
+}
+
+impl<S: fmt::Debug> fmt::Debug for InjectTraceContext<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("InjectTraceContext")
+            .field("inner", &self.inner)
+            .field("b3", &self.b3)
+            .finish()
+    }
+}
+
+impl<S> InjectTraceContext<S> {
+    /// Create a new [`InjectTraceContext`], injecting only the W3C headers.
+    pub const fn new(inner: S) -> Self {
+        Self {
+            inner,
+            b3: B3Propagation::None,
+        }
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Set whether and how B3 headers are injected as well.
+        pub fn b3(mut self, b3: B3Propagation) -> Self {
+            self.b3 = b3;
+            self
+        }
+    }
+
+    define_inner_service_accessors!();
+}
+
+impl<S, Body> Service<Request<Body>> for InjectTraceContext<S>
+where
+    S: Service<Request<Body>>,
+    Body: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+
+    async fn serve(
+        &self,
+        ctx: Context,
+        mut req: Request<Body>,
+    ) -> Result<Self::Response, Self::Error> {
+        let trace_context = ctx
+            .get::<TraceContext>()
+            .cloned()
+            .filter(TraceContext::is_valid)
+            .or_else(|| {
+                Some(TraceContext(tracing::Span::current().context()))
+                    .filter(TraceContext::is_valid)
+            });
+        if let Some(trace_context) = trace_context {
+            trace_context.inject(req.headers_mut(), self.b3);
+        }
+        self.inner.serve(ctx, req).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Body, Response};
+    use rama_core::service::service_fn;
+    use std::{
+        convert::Infallible,
+        sync::{Arc, Mutex},
+    };
+    use tracing_subscriber::layer::SubscriberExt;
+
+    const TRACE_ID: &str = "4bf92f3577b34da6a3ce929d0e0e4736";
+    const SPAN_ID: &str = "00f067aa0ba902b7";
+
+    fn headers<const N: usize>(pairs: [(&'static str, &str); N]) -> HeaderMap {
+        pairs
+            .into_iter()
+            .map(|(name, value)| {
+                (
+                    HeaderName::from_static(name),
+                    HeaderValue::from_str(value).unwrap(),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_extract_trace_context() {
+        let traceparent = format!("00-{TRACE_ID}-{SPAN_ID}-01");
+        let b3 = format!("{TRACE_ID}-{SPAN_ID}-1");
+        let cases = [
+            headers([("traceparent", &traceparent)]),
+            headers([("b3", &b3)]),
+            headers([
+                ("x-b3-traceid", TRACE_ID),
+                ("x-b3-spanid", SPAN_ID),
+                ("x-b3-sampled", "1"),
+            ]),
+        ];
+        for headers in cases {
+            let span_context = TraceContext::extract(&headers).unwrap().span_context();
+            assert_eq!(span_context.trace_id().to_string(), TRACE_ID);
+            assert_eq!(span_context.span_id().to_string(), SPAN_ID);
+            assert!(span_context.is_sampled());
+            assert!(span_context.is_remote());
+        }
+
+        assert!(TraceContext::extract(&headers([("b3", "invalid")])).is_none());
+        assert!(TraceContext::extract(&HeaderMap::new()).is_none());
+        assert!(TraceContext::extract_w3c(&headers([("b3", &b3)])).is_none());
+    }
+
+    #[test]
+    fn test_inject_trace_context() {
+        let traceparent = format!("00-{TRACE_ID}-{SPAN_ID}-01");
+        let trace_context =
+            TraceContext::extract(&headers([("traceparent", &traceparent)])).unwrap();
+
+        let mut headers = HeaderMap::new();
+        trace_context.inject(&mut headers, B3Propagation::Single);
+        assert_eq!(headers["traceparent"], traceparent);
+        assert_eq!(headers["b3"], format!("{TRACE_ID}-{SPAN_ID}-1"));
+
+        let mut headers = HeaderMap::new();
+        trace_context.inject(&mut headers, B3Propagation::Multi);
+        assert_eq!(headers["x-b3-traceid"], TRACE_ID);
+        assert_eq!(headers["x-b3-spanid"], SPAN_ID);
+        assert_eq!(headers["x-b3-sampled"], "1");
+    }
+
+    #[tokio::test]
+    async fn test_trace_context_propagated_to_client() {
+        let client = InjectTraceContextLayer::new().into_layer(service_fn(async |req: Request| {
+            let traceparent = req.headers().get("traceparent").cloned();
+            Ok::<_, Infallible>(Response::new(traceparent))
+        }));
+        let server = TraceContextLayer::new().into_layer(service_fn(
+            async move |ctx: Context, _req: Request| {
+                client.serve(ctx, Request::new(Body::empty())).await
+            },
+        ));
+
+        let req = Request::builder()
+            .header("b3", format!("{TRACE_ID}-{SPAN_ID}-1"))
+            .body(Body::empty())
+            .unwrap();
+        let traceparent = server
+            .serve(Context::default(), req)
+            .await
+            .unwrap()
+            .into_body()
+            .unwrap();
+        let traceparent = traceparent.to_str().unwrap();
+        assert!(
+            traceparent.starts_with(&format!("00-{TRACE_ID}-")),
+            "{traceparent}"
+        );
+
+        let res = server
+            .serve(Context::default(), Request::new(Body::empty()))
+            .await
+            .unwrap();
+        assert!(res.into_body().is_none());
+    }
+
+    #[derive(Debug, Clone, Default)]
+    struct RecordedFields(Arc<Mutex<Vec<(&'static str, String)>>>);
+
+    impl tracing::field::Visit for RecordedFields {
+        fn record_str(&mut self, field: &tracing::field::Field, value: &str) {
+            self.0
+                .lock()
+                .unwrap()
+                .push((field.name(), value.to_owned()));
+        }
+
+        fn record_debug(&mut self, field: &tracing::field::Field, value: &dyn fmt::Debug) {
+            self.0
+                .lock()
+                .unwrap()
+                .push((field.name(), format!("{value:?}")));
+        }
+    }
+
+    impl<S: tracing::Subscriber> tracing_subscriber::Layer<S> for RecordedFields {
+        fn on_record(
+            &self,
+            _id: &tracing::span::Id,
+            values: &tracing::span::Record<'_>,
+            _ctx: tracing_subscriber::layer::Context<'_, S>,
+        ) {
+            values.record(&mut self.clone());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_trace_context_recorded_in_span() {
+        let fields = RecordedFields::default();
+        let _guard =
+            tracing::subscriber::set_default(tracing_subscriber::registry().with(fields.clone()));
+
+        let server = TraceContextLayer::new().into_layer(service_fn(async |_req: Request| {
+            Ok::<_, Infallible>(Response::new(Body::empty()))
+        }));
+        let req = Request::builder()
+            .header("traceparent", format!("00-{TRACE_ID}-{SPAN_ID}-01"))
+            .body(Body::empty())
+            .unwrap();
+        server.serve(Context::default(), req).await.unwrap();
+
+        let fields = fields.0.lock().unwrap().clone();
+        assert!(
+            fields.contains(&("trace.id", TRACE_ID.to_owned())),
+            "{fields:?}"
+        );
+        assert!(
+            fields.contains(&("span.id", SPAN_ID.to_owned())),
+            "{fields:?}"
+        );
+    }
+}
@@ -0,0 +1,361 @@
+//! Middleware to split traffic over several variants of a service, e.g. for A/B testing.
+//!
+//! The [`TrafficSplitLayer`] wraps the control variant (the inner service),
+//! to which any number of other variants can be added, each with a weight.
+//! Requests are assigned to a variant in proportion to the weights,
+//! using the hash of a sticky [`SplitKey`] (e.g. a cookie, a header or the client IP),
+//! such that a client consistently gets the same variant, across restarts and instances alike.
+//! Requests without a key are assigned randomly.
+//!
+//! The chosen [`SplitVariant`] is inserted in the [`Context`],
+//! so it can be logged or otherwise used further down the stack.
+//!
+//! # Example
+//!
+//! ```
+//! use rama_core::{Context, Layer, Service, service::service_fn};
+//! use rama_http::layer::traffic_split::{SplitKey, SplitVariant, TrafficSplitLayer};
+//! use rama_http::{Body, BodyExtractExt, Request, Response, header};
+//! use std::convert::Infallible;
+//!
+//! # #[tokio::main]
+//! # async fn main() {
+//! let service = TrafficSplitLayer::new(SplitKey::Cookie("uid".to_owned()))
+//!     .with_control_weight(90)
+//!     .with_variant(
+//!         "new-checkout",
+//!         10,
+//!         service_fn(async |ctx: Context, _: Request| {
+//!             let variant = ctx.get::<SplitVariant>().unwrap();
+//!             Ok::<_, Infallible>(Response::new(Body::from(variant.as_str().to_owned())))
+//!         }),
+//!     )
+//!     .into_layer(service_fn(async |_: Request| {
+//!         Ok::<_, Infallible>(Response::new(Body::from("control")))
+//!     }));
+//!
+//! let req = || {
+//!     Request::get("/checkout")
+//!         .header(header::COOKIE, "uid=42")
+//!         .body(Body::empty())
+//!         .unwrap()
+//! };
+//! let first = service.serve(Context::default(), req()).await.unwrap();
+//! let second = service.serve(Context::default(), req()).await.unwrap();
+//! assert_eq!(
+//!     first.try_into_string().await.unwrap(),
+//!     second.try_into_string().await.unwrap(),
+//! );
+//! # }
+//! ```
+
+use std::{fmt, sync::Arc};
+
+use rama_core::{Context, Layer, Service};
+use rama_http_headers::{Cookie, HeaderMapExt};
+use rama_http_types::{HeaderName, Request};
+use rama_net::{forwarded::Forwarded, stream::SocketInfo};
+use rama_utils::macros::define_inner_service_accessors;
+
+/// The name of the [`SplitVariant`] of the inner service of the [`TrafficSplit`] middleware.
+pub const CONTROL_VARIANT: &str = "control";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// The key used to stick a client to a variant.
+pub enum SplitKey {
+    /// The value of the cookie with the given name.
+    Cookie(String),
+    /// The value of the given header.
+    Header(HeaderName),
+    /// The IP address of the client, as found in the [`Forwarded`]
+    /// or [`SocketInfo`] of the [`Context`].
+    ClientIp,
+}
+
+impl SplitKey {
+    fn hash<B>(&self, ctx: &Context, req: &Request<B>) -> Option<u64> {
+        match self {
+            Self::Cookie(name) => {
+                let cookie = req.headers().typed_get::<Cookie>()?;
+                cookie.get(name).map(|value| fnv1a(value.as_bytes()))
+            }
+            Self::Header(name) => req.headers().get(name).map(|value| fnv1a(value.as_bytes())),
+            Self::ClientIp => ctx
+                .get::<Forwarded>()
+                .and_then(|f| f.client_ip())
+                .or_else(|| ctx.get::<SocketInfo>().map(|s| s.peer_addr().ip()))
+                .map(|ip| fnv1a(ip.to_string().as_bytes())),
+        }
+    }
+}
+
+/// FNV-1a hash, used because, unlike the std hasher, it is stable across processes and releases.
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, b| {
+        (hash ^ u64::from(*b)).wrapping_mul(0x100000001b3)
+    })
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+/// The variant a request was assigned to by the [`TrafficSplit`] middleware,
+/// inserted in the [`Context`].
+pub struct SplitVariant(Arc<str>);
+
+impl SplitVariant {
+    /// Returns the name of the variant.
+    #[must_use]
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Returns `true` in case this is the [`CONTROL_VARIANT`].
+    #[must_use]
+    pub fn is_control(&self) -> bool {
+        &*self.0 == CONTROL_VARIANT
+    }
+}
+
+impl fmt::Display for SplitVariant {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Variant<V> {
+    name: SplitVariant,
+    weight: u32,
+    service: V,
+}
+
+/// Layer that applies the [`TrafficSplit`] middleware.
+///
+/// See the [module docs](self) for more information.
+#[derive(Debug, Clone)]
+pub struct TrafficSplitLayer<V> {
+    key: SplitKey,
+    control_weight: u32,
+    variants: Vec<Variant<V>>,
+}
+
+impl<V> TrafficSplitLayer<V> {
+    /// Create a new [`TrafficSplitLayer`], sticking clients to a variant using the given [`SplitKey`].
+    ///
+    /// The control variant has a weight of `100` by default.
+    #[must_use]
+    pub fn new(key: SplitKey) -> Self {
+        Self {
+            key,
+            control_weight: 100,
+            variants: Vec::new(),
+        }
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Set the weight of the control variant (the inner service).
+        pub fn control_weight(mut self, weight: u32) -> Self {
+            self.control_weight = weight;
+            self
+        }
+    }
+
+    /// Add a variant with the given name and weight, served by the given service.
+    #[must_use]
+    pub fn with_variant(mut self, name: impl Into<Arc<str>>, weight: u32, service: V) -> Self {
+        self.set_variant(name, weight, service);
+        self
+    }
+
+    /// Add a variant with the given name and weight, served by the given service.
+    pub fn set_variant(&mut self, name: impl Into<Arc<str>>, weight: u32, service: V) -> &mut Self {
+        self.variants.push(Variant {
+            name: SplitVariant(name.into()),
+            weight,
+            service,
+        });
+        self
+    }
+}
+
+impl<S, V: Clone> Layer<S> for TrafficSplitLayer<V> {
+    type Service = TrafficSplit<S, V>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        TrafficSplit {
+            inner,
+            key: self.key.clone(),
+            control: SplitVariant(CONTROL_VARIANT.into()),
+            control_weight: self.control_weight,
+            variants: Arc::new(self.variants.clone()),
+        }
+    }
+
+    fn into_layer(self, inner: S) -> Self::Service {
+        TrafficSplit {
+            inner,
+            key: self.key,
+            control: SplitVariant(CONTROL_VARIANT.into()),
+            control_weight: self.control_weight,
+            variants: Arc::new(self.variants),
+        }
+    }
+}
+
+/// Middleware to split traffic over several variants of a service.
+///
+/// See the [module docs](self) for more information.
+pub struct TrafficSplit<S, V> {
+    inner: S,
+    key: SplitKey,
+    control: SplitVariant,
+    control_weight: u32,
+    variants: Arc<Vec<Variant<V>>>,
+}
+
+impl<S, V> TrafficSplit<S, V> {
+    define_inner_service_accessors!();
+
+    fn choose(&self, hash: u64) -> Option<&Variant<V>> {
+        let total = self
+            .variants
+            .iter()
+            .fold(u64::from(self.control_weight), |total, v| {
+                total + u64::from(v.weight)
+            });
+        if total == 0 {
+            return None;
+        }
+        let mut bucket = hash % total;
+        if bucket < u64::from(self.control_weight) {
+            return None;
+        }
+        bucket -= u64::from(self.control_weight);
+        for variant in self.variants.iter() {
+            if bucket < u64::from(variant.weight) {
+                return Some(variant);
+            }
+            bucket -= u64::from(variant.weight);
+        }
+        None
+    }
+}
+
+impl<S: fmt::Debug, V: fmt::Debug> fmt::Debug for TrafficSplit<S, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TrafficSplit")
+            .field("inner", &self.inner)
+            .field("key", &self.key)
+            .field("control_weight", &self.control_weight)
+            .field("variants", &self.variants)
+            .finish()
+    }
+}
+
+impl<S: Clone, V> Clone for TrafficSplit<S, V> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            key: self.key.clone(),
+            control: self.control.clone(),
+            control_weight: self.control_weight,
+            variants: self.variants.clone(),
+        }
+    }
+}
+
+impl<S, V, ReqBody> Service<Request<ReqBody>> for TrafficSplit<S, V>
+where
+    S: Service<Request<ReqBody>>,
+    V: Service<Request<ReqBody>, Response = S::Response, Error = S::Error>,
+    ReqBody: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+
+    async fn serve(
+        &self,
+        mut ctx: Context,
+        req: Request<ReqBody>,
+    ) -> Result<Self::Response, Self::Error> {
+        let hash = self
+            .key
+            .hash(&ctx, &req)
+            .unwrap_or_else(rand::random::<u64>);
+        match self.choose(hash) {
+            Some(variant) => {
+                ctx.insert(variant.name.clone());
+                variant.service.serve(ctx, req).await
+            }
+            None => {
+                ctx.insert(self.control.clone());
+                self.inner.serve(ctx, req).await
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rama_core::service::service_fn;
+    use rama_http_types::{Body, BodyExtractExt, Response};
+    use std::convert::Infallible;
+
+    async fn echo_variant(ctx: Context, _: Request) -> Result<Response, Infallible> {
+        let variant = ctx.get::<SplitVariant>().unwrap();
+        Ok(Response::new(Body::from(variant.as_str().to_owned())))
+    }
+
+    #[tokio::test]
+    async fn test_traffic_split_sticky_and_weighted() {
+        let service =
+            TrafficSplitLayer::new(SplitKey::Header(HeaderName::from_static("x-user-id")))
+                .with_control_weight(50)
+                .with_variant("b", 50, service_fn(echo_variant))
+                .with_variant("never", 0, service_fn(echo_variant))
+                .into_layer(service_fn(echo_variant));
+
+        let mut counts = std::collections::HashMap::<String, usize>::new();
+        for user in 0..1000 {
+            let req = || {
+                Request::get("/")
+                    .header("x-user-id", user.to_string())
+                    .body(Body::empty())
+                    .unwrap()
+            };
+            let variant = service
+                .serve(Context::default(), req())
+                .await
+                .unwrap()
+                .try_into_string()
+                .await
+                .unwrap();
+            let again = service
+                .serve(Context::default(), req())
+                .await
+                .unwrap()
+                .try_into_string()
+                .await
+                .unwrap();
+            assert_eq!(variant, again);
+            *counts.entry(variant).or_default() += 1;
+        }
+
+        assert!(!counts.contains_key("never"));
+        assert!((400..600).contains(&counts[CONTROL_VARIANT]), "{counts:?}");
+        assert!((400..600).contains(&counts["b"]), "{counts:?}");
+    }
+
+    #[tokio::test]
+    async fn test_traffic_split_control_only() {
+        let service = TrafficSplitLayer::new(SplitKey::ClientIp)
+            .with_variant("b", 0, service_fn(echo_variant))
+            .into_layer(service_fn(echo_variant));
+
+        let resp = service
+            .serve(Context::default(), Request::new(Body::empty()))
+            .await
+            .unwrap();
+        assert_eq!(resp.try_into_string().await.unwrap(), CONTROL_VARIANT);
+    }
+}
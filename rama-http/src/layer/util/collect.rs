@@ -0,0 +1,52 @@
+//! Utilities to buffer http bodies up to a size limit.
+
+use crate::dep::http_body::{Body as HttpBody, Frame};
+use crate::dep::http_body_util::{BodyExt, BodyStream, StreamBody};
+use rama_core::{
+    bytes::{Bytes, BytesMut},
+    error::BoxError,
+    futures::{StreamExt, stream},
+};
+use rama_http_types::Body;
+
+/// The result of [`collect_limited`].
+pub(crate) enum LimitedBody {
+    /// The full body, which fits within the limit.
+    Collected(Bytes),
+    /// A body which exceeds the limit, streamed as-is,
+    /// starting with the data which was already read.
+    Exceeded(Body),
+}
+
+/// Collect the body in case it fits within the limit,
+/// and otherwise return it, without buffering more than the limit.
+///
+/// Trailers are discarded in case the body is collected.
+pub(crate) async fn collect_limited<B>(body: B, limit: usize) -> Result<LimitedBody, BoxError>
+where
+    B: HttpBody<Data = Bytes, Error: Into<BoxError>> + Send + Sync + 'static,
+{
+    if !usize::try_from(body.size_hint().lower()).is_ok_and(|lower| lower <= limit) {
+        return Ok(LimitedBody::Exceeded(Body::new(body)));
+    }
+
+    let mut body = Box::pin(body);
+    let mut buf = BytesMut::new();
+    while let Some(frame) = body.frame().await {
+        let Ok(data) = frame.map_err(Into::into)?.into_data() else {
+            continue;
+        };
+        if buf.len() + data.len() > limit {
+            let read = stream::iter([
+                Ok::<_, BoxError>(Frame::data(buf.freeze())),
+                Ok(Frame::data(data)),
+            ]);
+            let rest = BodyStream::new(body).map(|frame| frame.map_err(Into::into));
+            return Ok(LimitedBody::Exceeded(Body::new(StreamBody::new(
+                read.chain(rest),
+            ))));
+        }
+        buf.extend_from_slice(&data);
+    }
+    Ok(LimitedBody::Collected(buf.freeze()))
+}
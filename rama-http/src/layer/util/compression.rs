@@ -2,7 +2,8 @@
 
 use crate::dep::http_body::{Body, Frame};
 use pin_project_lite::pin_project;
-use rama_core::bytes::{Buf, Bytes, BytesMut};
+use rama_core::buffer::{BufferPool, PooledBuffer};
+use rama_core::bytes::{Buf, Bytes};
 use rama_core::error::BoxError;
 use rama_core::futures::Stream;
 use rama_core::futures::ready;
@@ -39,17 +40,14 @@ pin_project! {
         // rust-analyser thinks this field is private if its `pub(crate)` but works fine when its
         // `pub`
         pub read: M::Output,
-        // A buffer to temporarily store the data read from the underlying body.
-        // Reused as much as possible to optimize allocations.
-        buf: BytesMut,
+        // A buffer to temporarily store the data read from the underlying body,
+        // taken from (and returned to) the global buffer pool. Chunks are split off
+        // without copying, after which its allocation is reclaimed once they are dropped.
+        buf: PooledBuffer,
         read_all_data: bool,
     }
 }
 
-impl<M: DecorateAsyncRead> WrapBody<M> {
-    const INTERNAL_BUF_CAPACITY: usize = 4096;
-}
-
 impl<M: DecorateAsyncRead> WrapBody<M> {
     #[allow(dead_code)]
     pub(crate) fn new<B>(body: B, quality: CompressionLevel) -> Self
@@ -72,7 +70,7 @@ impl<M: DecorateAsyncRead> WrapBody<M> {
 
         Self {
             read,
-            buf: BytesMut::with_capacity(Self::INTERNAL_BUF_CAPACITY),
+            buf: BufferPool::global().get(),
             read_all_data: false,
         }
     }
@@ -94,10 +92,11 @@ where
 
         if !*this.read_all_data {
             if this.buf.capacity() == 0 {
-                this.buf.reserve(Self::INTERNAL_BUF_CAPACITY);
+                let chunk_size = this.buf.pool().chunk_size();
+                this.buf.reserve(chunk_size);
             }
 
-            let result = tokio_util::io::poll_read_buf(this.read.as_mut(), cx, &mut this.buf);
+            let result = tokio_util::io::poll_read_buf(this.read.as_mut(), cx, &mut **this.buf);
 
             match ready!(result) {
                 Ok(0) => {
@@ -1,4 +1,6 @@
 //! Http Layer Utilities.
 
+pub(crate) mod collect;
+
 #[cfg(feature = "compression")]
 pub(crate) mod compression;
@@ -0,0 +1,111 @@
+use super::ValidateRequest;
+use crate::service::web::response::{IntoResponse, ProblemDetails};
+use crate::{
+    Body, Request, Response, StatusCode,
+    dep::{http_body::Body as HttpBody, mime::Mime},
+    header,
+};
+use rama_core::Context;
+use std::{fmt, sync::Arc};
+
+/// Type that performs validation of the Content-Type header.
+///
+/// Requests without a body are always allowed through,
+/// while requests with a body of which the `Content-Type` is missing
+/// or not allowed are rejected with a `415 Unsupported Media Type`
+/// problem details response.
+#[derive(Clone)]
+pub struct ContentTypeHeader {
+    allowed: Arc<[Mime]>,
+}
+
+impl ContentTypeHeader {
+    /// Create a new [`ContentTypeHeader`], allowing the given content types.
+    ///
+    /// Content types can be `type/subtype`, `type/*` or `*/*`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if any of the values is not in the form `type/subtype`, such as `application/json`.
+    pub fn new<I, V>(values: I) -> Self
+    where
+        I: IntoIterator<Item = V>,
+        V: AsRef<str>,
+    {
+        Self {
+            allowed: values
+                .into_iter()
+                .map(|value| {
+                    value
+                        .as_ref()
+                        .parse::<Mime>()
+                        .expect("value is not a valid content type")
+                })
+                .collect(),
+        }
+    }
+
+    fn is_allowed(&self, content_type: &Mime) -> bool {
+        self.allowed
+            .iter()
+            .any(|allowed| match (allowed.type_(), allowed.subtype()) {
+                (mime::STAR, mime::STAR) => true,
+                (t, mime::STAR) => t == content_type.type_(),
+                (t, s) => t == content_type.type_() && s == content_type.subtype(),
+            })
+    }
+}
+
+impl fmt::Debug for ContentTypeHeader {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ContentTypeHeader")
+            .field("allowed", &self.allowed)
+            .finish()
+    }
+}
+
+/// Returns true if the request (might) have a body.
+///
+/// This is based on the body itself rather than the framing headers,
+/// as e.g. an http/2 request can have a body without any of these headers.
+fn has_body<B: HttpBody>(req: &Request<B>) -> bool {
+    let body = req.body();
+    !body.is_end_stream() && body.size_hint().exact() != Some(0)
+}
+
+impl<B> ValidateRequest<B> for ContentTypeHeader
+where
+    B: HttpBody + Send + 'static,
+{
+    type ResponseBody = Body;
+
+    async fn validate(
+        &self,
+        ctx: Context,
+        req: Request<B>,
+    ) -> Result<(Context, Request<B>), Response<Self::ResponseBody>> {
+        if !has_body(&req) {
+            return Ok((ctx, req));
+        }
+
+        let content_type = req
+            .headers()
+            .get(header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.parse::<Mime>().ok());
+        match content_type {
+            Some(content_type) if self.is_allowed(&content_type) => Ok((ctx, req)),
+            content_type => {
+                let allowed: Vec<_> = self.allowed.iter().map(ToString::to_string).collect();
+                let detail = match content_type {
+                    Some(content_type) => format!("content type {content_type} is not supported"),
+                    None => "missing or invalid content type".to_owned(),
+                };
+                Err(ProblemDetails::new(StatusCode::UNSUPPORTED_MEDIA_TYPE)
+                    .with_detail(detail)
+                    .with_extension("supported", allowed)
+                    .into_response())
+            }
+        }
+    }
+}
@@ -0,0 +1,225 @@
+use super::{JsonSchema, ValidateRequest};
+use crate::dep::http_body_util::{BodyExt, LengthLimitError, Limited};
+use crate::service::web::response::{IntoResponse, ProblemDetails};
+use crate::{Body, Request, Response, StatusCode, dep::mime::Mime, header};
+use rama_core::Context;
+use serde_json::{Value, json};
+
+/// The default maximum size of a JSON body validated by the [`JsonBodyValidator`].
+pub const DEFAULT_MAX_JSON_BODY_SIZE: usize = 2 * 1024 * 1024;
+
+/// Type that validates JSON request bodies.
+///
+/// Requests are rejected with a problem details response when:
+///
+/// - the `Content-Type` is not `application/json` (or `application/*+json`): `415 Unsupported Media Type`;
+/// - the body exceeds the maximum size: `413 Payload Too Large`;
+/// - the body is not valid JSON: `400 Bad Request`;
+/// - the body does not match the (optional) [`JsonSchema`]: `400 Bad Request`,
+///   listing the validation errors as `errors`, each with a `pointer` and `detail`.
+///
+/// As the body is read in full, the validated request is forwarded with a buffered body.
+#[derive(Debug, Clone)]
+pub struct JsonBodyValidator {
+    schema: Option<JsonSchema>,
+    max_body_size: usize,
+}
+
+impl Default for JsonBodyValidator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl JsonBodyValidator {
+    /// Create a new [`JsonBodyValidator`], only checking that the body is valid JSON.
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            schema: None,
+            max_body_size: DEFAULT_MAX_JSON_BODY_SIZE,
+        }
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Validate the JSON body against the given [`JsonSchema`].
+        pub fn schema(mut self, schema: Option<JsonSchema>) -> Self {
+            self.schema = schema;
+            self
+        }
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Set the maximum size of the JSON body.
+        ///
+        /// Defaults to [`DEFAULT_MAX_JSON_BODY_SIZE`].
+        pub fn max_body_size(mut self, size: usize) -> Self {
+            self.max_body_size = size;
+            self
+        }
+    }
+}
+
+fn is_json(content_type: &Mime) -> bool {
+    content_type.type_() == mime::APPLICATION
+        && (content_type.subtype() == mime::JSON || content_type.suffix() == Some(mime::JSON))
+}
+
+impl ValidateRequest<Body> for JsonBodyValidator {
+    type ResponseBody = Body;
+
+    async fn validate(
+        &self,
+        ctx: Context,
+        req: Request<Body>,
+    ) -> Result<(Context, Request<Body>), Response<Self::ResponseBody>> {
+        let content_type = req
+            .headers()
+            .get(header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.parse::<Mime>().ok());
+        if !content_type.as_ref().is_some_and(is_json) {
+            return Err(ProblemDetails::new(StatusCode::UNSUPPORTED_MEDIA_TYPE)
+                .with_detail("expected request with content type application/json")
+                .into_response());
+        }
+
+        let (parts, body) = req.into_parts();
+        let bytes = match Limited::new(body, self.max_body_size).collect().await {
+            Ok(collected) => collected.to_bytes(),
+            Err(err) if err.is::<LengthLimitError>() => {
+                return Err(ProblemDetails::new(StatusCode::PAYLOAD_TOO_LARGE)
+                    .with_detail(format!(
+                        "request body exceeds the limit of {} bytes",
+                        self.max_body_size
+                    ))
+                    .into_response());
+            }
+            Err(err) => {
+                return Err(ProblemDetails::new(StatusCode::BAD_REQUEST)
+                    .with_detail(format!("failed to read request body: {err}"))
+                    .into_response());
+            }
+        };
+
+        let value: Value = match serde_json::from_slice(&bytes) {
+            Ok(value) => value,
+            Err(err) => {
+                return Err(ProblemDetails::new(StatusCode::BAD_REQUEST)
+                    .with_detail(format!("invalid json: {err}"))
+                    .into_response());
+            }
+        };
+
+        if let Some(Err(errors)) = self.schema.as_ref().map(|schema| schema.validate(&value)) {
+            let errors: Vec<_> = errors
+                .iter()
+                .map(|err| {
+                    json!({
+                        "pointer": format!("#{}", err.instance_path()),
+                        "detail": err.message(),
+                    })
+                })
+                .collect();
+            return Err(ProblemDetails::new(StatusCode::BAD_REQUEST)
+                .with_detail("request body does not match the expected schema")
+                .with_extension("errors", errors)
+                .into_response());
+        }
+
+        Ok((ctx, Request::from_parts(parts, Body::from(bytes))))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layer::validate_request::ValidateRequestHeaderLayer;
+    use rama_core::{Layer, Service, service::service_fn};
+    use rama_http_types::BodyExtractExt;
+    use std::convert::Infallible;
+
+    fn service() -> impl Service<Request, Response = Response, Error = Infallible> {
+        let schema = JsonSchema::new(json!({
+            "type": "object",
+            "properties": { "name": { "type": "string" } },
+            "required": ["name"],
+        }))
+        .unwrap();
+        ValidateRequestHeaderLayer::json_body(
+            JsonBodyValidator::new()
+                .with_schema(schema)
+                .with_max_body_size(32),
+        )
+        .into_layer(service_fn(async |req: Request| {
+            Ok::<_, Infallible>(Response::new(req.into_body()))
+        }))
+    }
+
+    fn request(content_type: &str, body: &'static str) -> Request {
+        Request::post("/")
+            .header(header::CONTENT_TYPE, content_type)
+            .body(Body::from(body))
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_json_body_valid() {
+        let res = service()
+            .serve(
+                Context::default(),
+                request("application/json; charset=utf-8", r#"{"name":"john"}"#),
+            )
+            .await
+            .unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(res.try_into_string().await.unwrap(), r#"{"name":"john"}"#);
+    }
+
+    #[tokio::test]
+    async fn test_json_body_invalid() {
+        let svc = service();
+
+        let res = svc
+            .serve(Context::default(), request("text/plain", "{}"))
+            .await
+            .unwrap();
+        assert_eq!(res.status(), StatusCode::UNSUPPORTED_MEDIA_TYPE);
+
+        let res = svc
+            .serve(Context::default(), request("application/json", "{"))
+            .await
+            .unwrap();
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+
+        let res = svc
+            .serve(
+                Context::default(),
+                request(
+                    "application/json",
+                    r#"{"name":"a name which makes this body exceed the limit"}"#,
+                ),
+            )
+            .await
+            .unwrap();
+        assert_eq!(res.status(), StatusCode::PAYLOAD_TOO_LARGE);
+
+        let res = svc
+            .serve(
+                Context::default(),
+                request("application/merge-patch+json", r#"{"name":1}"#),
+            )
+            .await
+            .unwrap();
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(
+            res.headers()[header::CONTENT_TYPE],
+            "application/problem+json"
+        );
+        let body: Value = res.try_into_json().await.unwrap();
+        assert_eq!(
+            body["errors"],
+            json!([{ "pointer": "#/name", "detail": "expected string" }])
+        );
+    }
+}
@@ -0,0 +1,663 @@
+use rama_core::error::{ErrorContext, OpaqueError};
+use regex::Regex;
+use serde_json::{Map, Value};
+use std::{fmt, sync::Arc};
+
+/// A compiled [JSON Schema], used to validate JSON documents.
+///
+/// Only a subset of the specification is supported, covering the
+/// validation keywords commonly used to describe request bodies:
+///
+/// - `type`, `enum` and `const`;
+/// - `properties`, `required`, `additionalProperties`,
+///   `minProperties` and `maxProperties` for objects;
+/// - `items`, `minItems`, `maxItems` and `uniqueItems` for arrays;
+/// - `minLength`, `maxLength` and `pattern` for strings;
+/// - `minimum`, `maximum`, `exclusiveMinimum`, `exclusiveMaximum`
+///   and `multipleOf` for numbers;
+/// - `allOf`, `anyOf`, `oneOf` and `not`.
+///
+/// Annotations such as `title`, `description` or `format` are ignored,
+/// while schemas using other keywords (e.g. `$ref`) are rejected
+/// on creation, rather than being validated incompletely.
+///
+/// [JSON Schema]: https://json-schema.org/
+///
+/// # Example
+///
+/// ```
+/// use rama_http::layer::validate_request::JsonSchema;
+/// use serde_json::json;
+///
+/// let schema = JsonSchema::new(json!({
+///     "type": "object",
+///     "properties": {
+///         "name": { "type": "string", "minLength": 1 },
+///         "age": { "type": "integer", "minimum": 0 },
+///     },
+///     "required": ["name"],
+/// }))
+/// .unwrap();
+///
+/// assert!(schema.validate(&json!({"name": "john", "age": 30})).is_ok());
+///
+/// let errors = schema.validate(&json!({"age": -1})).unwrap_err();
+/// assert_eq!(errors.len(), 2);
+/// ```
+#[derive(Debug, Clone)]
+pub struct JsonSchema {
+    root: Arc<Node>,
+}
+
+/// An error produced when validating a JSON document against a [`JsonSchema`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JsonSchemaError {
+    instance_path: String,
+    message: String,
+}
+
+impl JsonSchemaError {
+    /// The JSON pointer to the invalid value within the document.
+    #[must_use]
+    pub fn instance_path(&self) -> &str {
+        &self.instance_path
+    }
+
+    /// A description of why the value is invalid.
+    #[must_use]
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+impl fmt::Display for JsonSchemaError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.instance_path.is_empty() {
+            write!(f, "{}", self.message)
+        } else {
+            write!(f, "{}: {}", self.instance_path, self.message)
+        }
+    }
+}
+
+impl std::error::Error for JsonSchemaError {}
+
+impl JsonSchema {
+    /// Compile the given JSON Schema document.
+    ///
+    /// Fails in case the schema is invalid or uses unsupported keywords.
+    pub fn new(schema: Value) -> Result<Self, OpaqueError> {
+        Ok(Self {
+            root: Arc::new(Node::compile(&schema, "#")?),
+        })
+    }
+
+    /// Validate the given JSON document against this schema,
+    /// returning all errors found in case it is invalid.
+    pub fn validate(&self, value: &Value) -> Result<(), Vec<JsonSchemaError>> {
+        let mut errors = Vec::new();
+        self.root.validate(value, &mut String::new(), &mut errors);
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    #[must_use]
+    /// Returns true if the given JSON document is valid against this schema.
+    pub fn is_valid(&self, value: &Value) -> bool {
+        let mut errors = Vec::new();
+        self.root.validate(value, &mut String::new(), &mut errors);
+        errors.is_empty()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum JsonType {
+    Null,
+    Boolean,
+    Object,
+    Array,
+    Number,
+    Integer,
+    String,
+}
+
+impl JsonType {
+    fn parse(s: &str) -> Option<Self> {
+        Some(match s {
+            "null" => Self::Null,
+            "boolean" => Self::Boolean,
+            "object" => Self::Object,
+            "array" => Self::Array,
+            "number" => Self::Number,
+            "integer" => Self::Integer,
+            "string" => Self::String,
+            _ => return None,
+        })
+    }
+
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Null => "null",
+            Self::Boolean => "boolean",
+            Self::Object => "object",
+            Self::Array => "array",
+            Self::Number => "number",
+            Self::Integer => "integer",
+            Self::String => "string",
+        }
+    }
+
+    fn matches(self, value: &Value) -> bool {
+        match self {
+            Self::Null => value.is_null(),
+            Self::Boolean => value.is_boolean(),
+            Self::Object => value.is_object(),
+            Self::Array => value.is_array(),
+            Self::Number => value.is_number(),
+            Self::Integer => {
+                value.is_i64() || value.is_u64() || value.as_f64().is_some_and(|n| n.fract() == 0.0)
+            }
+            Self::String => value.is_string(),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct Node {
+    reject: bool,
+    types: Vec<JsonType>,
+    enum_values: Option<Vec<Value>>,
+    const_value: Option<Value>,
+    properties: Vec<(String, Node)>,
+    required: Vec<String>,
+    additional_properties: Option<Box<Node>>,
+    min_properties: Option<usize>,
+    max_properties: Option<usize>,
+    items: Option<Box<Node>>,
+    min_items: Option<usize>,
+    max_items: Option<usize>,
+    unique_items: bool,
+    min_length: Option<usize>,
+    max_length: Option<usize>,
+    pattern: Option<Regex>,
+    minimum: Option<f64>,
+    maximum: Option<f64>,
+    exclusive_minimum: Option<f64>,
+    exclusive_maximum: Option<f64>,
+    multiple_of: Option<f64>,
+    all_of: Vec<Node>,
+    any_of: Vec<Node>,
+    one_of: Vec<Node>,
+    not: Option<Box<Node>>,
+}
+
+const ANNOTATION_KEYWORDS: &[&str] = &[
+    "$schema",
+    "$id",
+    "$comment",
+    "title",
+    "description",
+    "default",
+    "examples",
+    "format",
+    "deprecated",
+    "readOnly",
+    "writeOnly",
+    "contentEncoding",
+    "contentMediaType",
+];
+
+impl Node {
+    fn compile(schema: &Value, location: &str) -> Result<Self, OpaqueError> {
+        let schema = match schema {
+            Value::Bool(accept) => {
+                return Ok(Self {
+                    reject: !accept,
+                    ..Default::default()
+                });
+            }
+            Value::Object(schema) => schema,
+            _ => {
+                return Err(OpaqueError::from_display(format!(
+                    "{location}: schema must be an object or boolean"
+                )));
+            }
+        };
+
+        let mut node = Self::default();
+        for (keyword, value) in schema {
+            let location = format!("{location}/{keyword}");
+            match keyword.as_str() {
+                "type" => {
+                    node.types = match value {
+                        Value::String(s) => vec![parse_type(s, &location)?],
+                        Value::Array(types) => types
+                            .iter()
+                            .map(|t| {
+                                t.as_str()
+                                    .context("type must be a string")
+                                    .and_then(|s| parse_type(s, &location))
+                            })
+                            .collect::<Result<_, _>>()?,
+                        _ => return Err(invalid_keyword(&location)),
+                    }
+                }
+                "enum" => {
+                    node.enum_values = Some(
+                        value
+                            .as_array()
+                            .ok_or_else(|| invalid_keyword(&location))?
+                            .clone(),
+                    )
+                }
+                "const" => node.const_value = Some(value.clone()),
+                "properties" => {
+                    node.properties = value
+                        .as_object()
+                        .ok_or_else(|| invalid_keyword(&location))?
+                        .iter()
+                        .map(|(name, schema)| {
+                            Ok((
+                                name.clone(),
+                                Self::compile(schema, &format!("{location}/{name}"))?,
+                            ))
+                        })
+                        .collect::<Result<_, OpaqueError>>()?
+                }
+                "required" => {
+                    node.required = value
+                        .as_array()
+                        .ok_or_else(|| invalid_keyword(&location))?
+                        .iter()
+                        .map(|name| {
+                            name.as_str()
+                                .map(ToOwned::to_owned)
+                                .ok_or_else(|| invalid_keyword(&location))
+                        })
+                        .collect::<Result<_, _>>()?
+                }
+                "additionalProperties" => {
+                    node.additional_properties = Some(Box::new(Self::compile(value, &location)?))
+                }
+                "minProperties" => node.min_properties = Some(parse_usize(value, &location)?),
+                "maxProperties" => node.max_properties = Some(parse_usize(value, &location)?),
+                "items" => node.items = Some(Box::new(Self::compile(value, &location)?)),
+                "minItems" => node.min_items = Some(parse_usize(value, &location)?),
+                "maxItems" => node.max_items = Some(parse_usize(value, &location)?),
+                "uniqueItems" => {
+                    node.unique_items = value.as_bool().ok_or_else(|| invalid_keyword(&location))?
+                }
+                "minLength" => node.min_length = Some(parse_usize(value, &location)?),
+                "maxLength" => node.max_length = Some(parse_usize(value, &location)?),
+                "pattern" => {
+                    let pattern = value.as_str().ok_or_else(|| invalid_keyword(&location))?;
+                    node.pattern = Some(Regex::new(pattern).context("compile pattern")?);
+                }
+                "minimum" => node.minimum = Some(parse_f64(value, &location)?),
+                "maximum" => node.maximum = Some(parse_f64(value, &location)?),
+                "exclusiveMinimum" => node.exclusive_minimum = Some(parse_f64(value, &location)?),
+                "exclusiveMaximum" => node.exclusive_maximum = Some(parse_f64(value, &location)?),
+                "multipleOf" => {
+                    let n = parse_f64(value, &location)?;
+                    if n <= 0.0 {
+                        return Err(invalid_keyword(&location));
+                    }
+                    node.multiple_of = Some(n);
+                }
+                "allOf" => node.all_of = compile_all(value, &location)?,
+                "anyOf" => node.any_of = compile_all(value, &location)?,
+                "oneOf" => node.one_of = compile_all(value, &location)?,
+                "not" => node.not = Some(Box::new(Self::compile(value, &location)?)),
+                keyword if ANNOTATION_KEYWORDS.contains(&keyword) => (),
+                _ => {
+                    return Err(OpaqueError::from_display(format!(
+                        "{location}: unsupported json schema keyword"
+                    )));
+                }
+            }
+        }
+        Ok(node)
+    }
+
+    fn validate(&self, value: &Value, path: &mut String, errors: &mut Vec<JsonSchemaError>) {
+        let mut error = |message: String| {
+            errors.push(JsonSchemaError {
+                instance_path: path.clone(),
+                message,
+            })
+        };
+
+        if self.reject {
+            error("no value is allowed here".to_owned());
+            return;
+        }
+
+        if !self.types.is_empty() && !self.types.iter().any(|t| t.matches(value)) {
+            let types: Vec<_> = self.types.iter().map(|t| t.as_str()).collect();
+            error(format!("expected {}", types.join(" or ")));
+            return;
+        }
+        if self
+            .enum_values
+            .as_ref()
+            .is_some_and(|values| !values.contains(value))
+        {
+            error("value is not one of the allowed values".to_owned());
+        }
+        if let Some(expected) = self
+            .const_value
+            .as_ref()
+            .filter(|expected| *expected != value)
+        {
+            error(format!("expected {expected}"));
+        }
+
+        match value {
+            Value::Object(object) => self.validate_object(object, path, errors),
+            Value::Array(array) => self.validate_array(array, path, errors),
+            Value::String(s) => self.validate_string(s, path, errors),
+            Value::Number(n) => {
+                if let Some(n) = n.as_f64() {
+                    self.validate_number(n, path, errors);
+                }
+            }
+            Value::Null | Value::Bool(_) => (),
+        }
+
+        for schema in &self.all_of {
+            schema.validate(value, path, errors);
+        }
+        let mut error = |message: &str| {
+            errors.push(JsonSchemaError {
+                instance_path: path.clone(),
+                message: message.to_owned(),
+            })
+        };
+        if !self.any_of.is_empty() && !self.any_of.iter().any(|s| s.is_valid(value)) {
+            error("value does not match any of the allowed schemas");
+        }
+        if !self.one_of.is_empty() && self.one_of.iter().filter(|s| s.is_valid(value)).count() != 1
+        {
+            error("value does not match exactly one of the allowed schemas");
+        }
+        if self.not.as_ref().is_some_and(|s| s.is_valid(value)) {
+            error("value matches a disallowed schema");
+        }
+    }
+
+    fn is_valid(&self, value: &Value) -> bool {
+        let mut errors = Vec::new();
+        self.validate(value, &mut String::new(), &mut errors);
+        errors.is_empty()
+    }
+
+    fn validate_object(
+        &self,
+        object: &Map<String, Value>,
+        path: &mut String,
+        errors: &mut Vec<JsonSchemaError>,
+    ) {
+        for name in &self.required {
+            if !object.contains_key(name) {
+                errors.push(JsonSchemaError {
+                    instance_path: path.clone(),
+                    message: format!("missing required property `{name}`"),
+                });
+            }
+        }
+        if self.min_properties.is_some_and(|min| object.len() < min) {
+            errors.push(JsonSchemaError {
+                instance_path: path.clone(),
+                message: "too few properties".to_owned(),
+            });
+        }
+        if self.max_properties.is_some_and(|max| object.len() > max) {
+            errors.push(JsonSchemaError {
+                instance_path: path.clone(),
+                message: "too many properties".to_owned(),
+            });
+        }
+
+        for (name, value) in object {
+            let schema = self
+                .properties
+                .iter()
+                .find_map(|(property, schema)| (property == name).then_some(schema))
+                .or(self.additional_properties.as_deref());
+            if let Some(schema) = schema {
+                let len = path.len();
+                push_pointer_segment(path, name);
+                schema.validate(value, path, errors);
+                path.truncate(len);
+            }
+        }
+    }
+
+    fn validate_array(
+        &self,
+        array: &[Value],
+        path: &mut String,
+        errors: &mut Vec<JsonSchemaError>,
+    ) {
+        if self.min_items.is_some_and(|min| array.len() < min) {
+            errors.push(JsonSchemaError {
+                instance_path: path.clone(),
+                message: "too few items".to_owned(),
+            });
+        }
+        if self.max_items.is_some_and(|max| array.len() > max) {
+            errors.push(JsonSchemaError {
+                instance_path: path.clone(),
+                message: "too many items".to_owned(),
+            });
+        }
+        if self.unique_items
+            && array
+                .iter()
+                .enumerate()
+                .any(|(i, item)| array[..i].contains(item))
+        {
+            errors.push(JsonSchemaError {
+                instance_path: path.clone(),
+                message: "items are not unique".to_owned(),
+            });
+        }
+        if let Some(schema) = &self.items {
+            for (index, item) in array.iter().enumerate() {
+                let len = path.len();
+                push_pointer_segment(path, &index.to_string());
+                schema.validate(item, path, errors);
+                path.truncate(len);
+            }
+        }
+    }
+
+    fn validate_string(&self, s: &str, path: &mut String, errors: &mut Vec<JsonSchemaError>) {
+        let mut error = |message: &str| {
+            errors.push(JsonSchemaError {
+                instance_path: path.clone(),
+                message: message.to_owned(),
+            })
+        };
+        let len = s.chars().count();
+        if self.min_length.is_some_and(|min| len < min) {
+            error("string is too short");
+        }
+        if self.max_length.is_some_and(|max| len > max) {
+            error("string is too long");
+        }
+        if self.pattern.as_ref().is_some_and(|re| !re.is_match(s)) {
+            error("string does not match the pattern");
+        }
+    }
+
+    fn validate_number(&self, n: f64, path: &mut String, errors: &mut Vec<JsonSchemaError>) {
+        let mut error = |message: String| {
+            errors.push(JsonSchemaError {
+                instance_path: path.clone(),
+                message,
+            })
+        };
+        if let Some(min) = self.minimum.filter(|min| n < *min) {
+            error(format!("must be greater than or equal to {min}"));
+        }
+        if let Some(max) = self.maximum.filter(|max| n > *max) {
+            error(format!("must be less than or equal to {max}"));
+        }
+        if let Some(min) = self.exclusive_minimum.filter(|min| n <= *min) {
+            error(format!("must be greater than {min}"));
+        }
+        if let Some(max) = self.exclusive_maximum.filter(|max| n >= *max) {
+            error(format!("must be less than {max}"));
+        }
+        if let Some(multiple_of) = self
+            .multiple_of
+            .filter(|multiple_of| (n / multiple_of).fract() != 0.0)
+        {
+            error(format!("must be a multiple of {multiple_of}"));
+        }
+    }
+}
+
+/// Append a segment to a JSON pointer, escaping it as defined in RFC 6901.
+fn push_pointer_segment(path: &mut String, segment: &str) {
+    path.push('/');
+    path.push_str(&segment.replace('~', "~0").replace('/', "~1"));
+}
+
+fn invalid_keyword(location: &str) -> OpaqueError {
+    OpaqueError::from_display(format!("{location}: invalid keyword value"))
+}
+
+fn parse_type(s: &str, location: &str) -> Result<JsonType, OpaqueError> {
+    JsonType::parse(s).ok_or_else(|| invalid_keyword(location))
+}
+
+fn parse_usize(value: &Value, location: &str) -> Result<usize, OpaqueError> {
+    value
+        .as_u64()
+        .and_then(|n| usize::try_from(n).ok())
+        .ok_or_else(|| invalid_keyword(location))
+}
+
+fn parse_f64(value: &Value, location: &str) -> Result<f64, OpaqueError> {
+    value.as_f64().ok_or_else(|| invalid_keyword(location))
+}
+
+fn compile_all(value: &Value, location: &str) -> Result<Vec<Node>, OpaqueError> {
+    value
+        .as_array()
+        .filter(|schemas| !schemas.is_empty())
+        .ok_or_else(|| invalid_keyword(location))?
+        .iter()
+        .enumerate()
+        .map(|(index, schema)| Node::compile(schema, &format!("{location}/{index}")))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn errors(schema: Value, value: Value) -> Vec<String> {
+        let mut errors: Vec<_> = JsonSchema::new(schema)
+            .unwrap()
+            .validate(&value)
+            .err()
+            .unwrap_or_default()
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        errors.sort();
+        errors
+    }
+
+    #[test]
+    fn test_json_schema_object() {
+        let schema = json!({
+            "type": "object",
+            "properties": {
+                "name": { "type": "string", "minLength": 1, "pattern": "^[a-z]+$" },
+                "tags": { "type": "array", "items": { "type": "string" }, "uniqueItems": true },
+                "a/b": { "type": "integer" },
+            },
+            "required": ["name"],
+            "additionalProperties": false,
+        });
+
+        assert!(errors(schema.clone(), json!({"name": "john", "tags": ["a", "b"]})).is_empty());
+        assert_eq!(
+            errors(schema.clone(), json!({"tags": ["a", 1, "a"], "a/b": 1.5})),
+            vec![
+                "/a~1b: expected integer",
+                "/tags/1: expected string",
+                "/tags: items are not unique",
+                "missing required property `name`",
+            ]
+        );
+        assert_eq!(
+            errors(schema, json!({"name": "John", "extra": true})),
+            vec![
+                "/extra: no value is allowed here",
+                "/name: string does not match the pattern"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_json_schema_numbers() {
+        let schema =
+            json!({ "type": "number", "minimum": 0, "exclusiveMaximum": 10, "multipleOf": 0.5 });
+        assert!(errors(schema.clone(), json!(9.5)).is_empty());
+        assert_eq!(
+            errors(schema.clone(), json!(-1)),
+            vec!["must be greater than or equal to 0"]
+        );
+        assert_eq!(
+            errors(schema.clone(), json!(10)),
+            vec!["must be less than 10"]
+        );
+        assert_eq!(
+            errors(schema, json!(1.25)),
+            vec!["must be a multiple of 0.5"]
+        );
+    }
+
+    #[test]
+    fn test_json_schema_combinators() {
+        let schema = json!({
+            "anyOf": [{ "type": "string" }, { "type": "integer" }],
+            "not": { "const": "forbidden" },
+        });
+        assert!(errors(schema.clone(), json!("ok")).is_empty());
+        assert!(errors(schema.clone(), json!(1)).is_empty());
+        assert_eq!(
+            errors(schema.clone(), json!(true)),
+            vec!["value does not match any of the allowed schemas"]
+        );
+        assert_eq!(
+            errors(schema, json!("forbidden")),
+            vec!["value matches a disallowed schema"]
+        );
+
+        let schema = json!({ "oneOf": [{ "type": "number" }, { "type": "integer" }] });
+        assert!(errors(schema.clone(), json!(1.5)).is_empty());
+        assert_eq!(
+            errors(schema, json!(1)),
+            vec!["value does not match exactly one of the allowed schemas"]
+        );
+    }
+
+    #[test]
+    fn test_json_schema_rejects_unsupported_schema() {
+        assert!(JsonSchema::new(json!({ "$ref": "#/definitions/foo" })).is_err());
+        assert!(JsonSchema::new(json!({ "type": "foo" })).is_err());
+        assert!(JsonSchema::new(json!({ "pattern": "(" })).is_err());
+        assert!(JsonSchema::new(json!(1)).is_err());
+        assert!(JsonSchema::new(json!({ "title": "annotations are fine" })).is_ok());
+    }
+}
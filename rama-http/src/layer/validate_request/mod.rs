@@ -47,6 +47,50 @@
 //! # }
 //! ```
 //!
+//! Content types and JSON bodies can be validated as well,
+//! rejecting invalid requests with `application/problem+json` responses:
+//!
+//! ```
+//! use rama_http::layer::validate_request::{
+//!     JsonBodyValidator, JsonSchema, ValidateRequestHeaderLayer,
+//! };
+//! use rama_http::{Body, Request, Response, StatusCode, header::CONTENT_TYPE};
+//! use rama_core::service::service_fn;
+//! use rama_core::{Context, Service, Layer};
+//! use rama_core::error::BoxError;
+//! use serde_json::json;
+//!
+//! async fn handle(request: Request) -> Result<Response, BoxError> {
+//!     Ok(Response::new(Body::empty()))
+//! }
+//!
+//! # #[tokio::main]
+//! # async fn main() -> Result<(), BoxError> {
+//! let schema = JsonSchema::new(json!({
+//!     "type": "object",
+//!     "properties": { "name": { "type": "string" } },
+//!     "required": ["name"],
+//! }))?;
+//!
+//! let service = (
+//!     ValidateRequestHeaderLayer::json_body(JsonBodyValidator::new().with_schema(schema)),
+//! ).into_layer(service_fn(handle));
+//!
+//! // Requests without the `name` property get a `400 Bad Request` response
+//! let request = Request::builder()
+//!     .header(CONTENT_TYPE, "application/json")
+//!     .body(Body::from(r#"{"age":42}"#))
+//!     .unwrap();
+//!
+//! let response = service
+//!     .serve(Context::default(), request)
+//!     .await?;
+//!
+//! assert_eq!(StatusCode::BAD_REQUEST, response.status());
+//! # Ok(())
+//! # }
+//! ```
+//!
 //! Custom validation can be made by implementing [`ValidateRequest`]:
 //!
 //! ```
@@ -137,6 +181,9 @@
 //! ```
 
 mod accept_header;
+mod content_type;
+mod json_body;
+mod json_schema;
 mod validate;
 mod validate_fn;
 mod validate_request_header;
@@ -144,6 +191,12 @@ mod validate_request_header;
 #[doc(inline)]
 pub use accept_header::AcceptHeader;
 #[doc(inline)]
+pub use content_type::ContentTypeHeader;
+#[doc(inline)]
+pub use json_body::{DEFAULT_MAX_JSON_BODY_SIZE, JsonBodyValidator};
+#[doc(inline)]
+pub use json_schema::{JsonSchema, JsonSchemaError};
+#[doc(inline)]
 pub use validate::ValidateRequest;
 #[doc(inline)]
 pub use validate_fn::{BoxValidateRequestFn, ValidateRequestFn};
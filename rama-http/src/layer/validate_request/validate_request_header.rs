@@ -1,4 +1,6 @@
-use super::{AcceptHeader, BoxValidateRequestFn, ValidateRequest};
+use super::{
+    AcceptHeader, BoxValidateRequestFn, ContentTypeHeader, JsonBodyValidator, ValidateRequest,
+};
 use crate::{Request, Response};
 use rama_core::{Context, Layer, Service};
 use rama_utils::macros::define_inner_service_accessors;
@@ -59,6 +61,43 @@ impl<ResBody> ValidateRequestHeaderLayer<AcceptHeader<ResBody>> {
     }
 }
 
+impl ValidateRequestHeaderLayer<ContentTypeHeader> {
+    /// Validate requests with a body have one of the allowed Content-Type headers.
+    ///
+    /// Each content type can be `type/subtype`, `type/*` or `*/*`.
+    ///
+    /// # Panics
+    ///
+    /// See `ContentTypeHeader::new` for when this method panics.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rama_http::layer::validate_request::ValidateRequestHeaderLayer;
+    ///
+    /// let layer = ValidateRequestHeaderLayer::content_type(["application/json", "text/*"]);
+    /// ```
+    pub fn content_type<I, V>(values: I) -> Self
+    where
+        I: IntoIterator<Item = V>,
+        V: AsRef<str>,
+    {
+        Self::custom(ContentTypeHeader::new(values))
+    }
+}
+
+impl ValidateRequestHeaderLayer<JsonBodyValidator> {
+    /// Validate requests have a JSON body, optionally matching a [`JsonSchema`].
+    ///
+    /// See [`JsonBodyValidator`] for more information.
+    ///
+    /// [`JsonSchema`]: super::JsonSchema
+    #[must_use]
+    pub fn json_body(validator: JsonBodyValidator) -> Self {
+        Self::custom(validator)
+    }
+}
+
 impl<T> ValidateRequestHeaderLayer<T> {
     /// Validate requests using a custom validator.
     pub fn custom(validate: T) -> Self {
@@ -145,6 +184,34 @@ impl<S, ResBody> ValidateRequestHeader<S, AcceptHeader<ResBody>> {
     }
 }
 
+impl<S> ValidateRequestHeader<S, ContentTypeHeader> {
+    /// Validate requests with a body have one of the allowed Content-Type headers.
+    ///
+    /// Each content type can be `type/subtype`, `type/*` or `*/*`.
+    ///
+    /// # Panics
+    ///
+    /// See `ContentTypeHeader::new` for when this method panics.
+    pub fn content_type<I, V>(inner: S, values: I) -> Self
+    where
+        I: IntoIterator<Item = V>,
+        V: AsRef<str>,
+    {
+        Self::custom(inner, ContentTypeHeader::new(values))
+    }
+}
+
+impl<S> ValidateRequestHeader<S, JsonBodyValidator> {
+    /// Validate requests have a JSON body, optionally matching a [`JsonSchema`].
+    ///
+    /// See [`JsonBodyValidator`] for more information.
+    ///
+    /// [`JsonSchema`]: super::JsonSchema
+    pub fn json_body(inner: S, validator: JsonBodyValidator) -> Self {
+        Self::custom(inner, validator)
+    }
+}
+
 impl<S, T> ValidateRequestHeader<S, T> {
     /// Validate requests using a custom validator.
     pub fn custom(inner: S, validate: T) -> Self {
@@ -343,6 +410,46 @@ mod tests {
         assert_eq!(res.status(), StatusCode::NOT_ACCEPTABLE);
     }
 
+    #[tokio::test]
+    async fn content_type_header() {
+        let service = ValidateRequestHeaderLayer::content_type(["application/json", "text/*"])
+            .into_layer(service_fn(echo));
+
+        for (content_type, expected) in [
+            (Some("application/json"), StatusCode::OK),
+            (Some("text/plain; charset=utf-8"), StatusCode::OK),
+            (Some("application/xml"), StatusCode::UNSUPPORTED_MEDIA_TYPE),
+            (Some("invalid"), StatusCode::UNSUPPORTED_MEDIA_TYPE),
+            (None, StatusCode::UNSUPPORTED_MEDIA_TYPE),
+        ] {
+            let mut request = Request::post("/").header(header::CONTENT_LENGTH, "2");
+            if let Some(content_type) = content_type {
+                request = request.header(header::CONTENT_TYPE, content_type);
+            }
+            let request = request.body(Body::from("{}")).unwrap();
+
+            let res = service.serve(Context::default(), request).await.unwrap();
+
+            assert_eq!(res.status(), expected, "content type: {content_type:?}");
+        }
+
+        // a body without any framing headers, as can be sent over http/2
+        let request = Request::post("/").body(Body::from("{}")).unwrap();
+        let res = service.serve(Context::default(), request).await.unwrap();
+        assert_eq!(res.status(), StatusCode::UNSUPPORTED_MEDIA_TYPE);
+
+        let request = Request::get("/").body(Body::empty()).unwrap();
+        let res = service.serve(Context::default(), request).await.unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+
+        let request = Request::post("/")
+            .header(header::CONTENT_TYPE, "application/xml")
+            .body(Body::empty())
+            .unwrap();
+        let res = service.serve(Context::default(), request).await.unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+    }
+
     async fn echo<B>(req: Request<B>) -> Result<Response<B>, BoxError> {
         Ok(Response::new(req.into_body()))
     }
@@ -0,0 +1,818 @@
+//! Middleware to filter requests using dynamically loaded WASM plugins.
+//!
+//! A [`WasmPlugin`] is a WebAssembly module, run using [`wasmtime`], which gets
+//! to inspect and modify each request before it is forwarded to the inner service,
+//! or to short-circuit it with a response of its own. As plugins are loaded at runtime,
+//! e.g. from a file path in a config, request filtering logic can be deployed to
+//! a running proxy without having to recompile it. A loaded plugin can be replaced
+//! at any time using [`WasmPlugin::reload`], affecting all services using it.
+//!
+//! # Plugin interface
+//!
+//! A plugin module has to export its `memory` and a function
+//! `on_request() -> i32`, which is called once per request. It returns `0` to
+//! forward the (possibly modified) request to the inner service, and any other value
+//! to short-circuit it, using the response set with `respond`,
+//! defaulting to `403 Forbidden` in case no response was set.
+//!
+//! The host API is imported from the `rama` module, where all pointers
+//! and lengths refer to the plugin's memory:
+//!
+//! | function | description |
+//! | -------- | ----------- |
+//! | `request_method(buf, cap) -> len` | read the method of the request |
+//! | `request_uri(buf, cap) -> len` | read the uri of the request |
+//! | `header_get(name, name_len, buf, cap) -> len` | read the (first) value of a request header, `-1` if absent |
+//! | `header_set(name, name_len, value, value_len) -> i32` | set a request header, `-1` if invalid |
+//! | `header_remove(name, name_len) -> i32` | remove a request header, `1` if it was present, `-1` if invalid |
+//! | `context_get(key, key_len, buf, cap) -> len` | read a context metadata value, `-1` if absent |
+//! | `respond(status, body, body_len) -> i32` | set the response to short-circuit with, `-1` if invalid |
+//! | `response_header_set(name, name_len, value, value_len) -> i32` | set a header of that response, `-1` if invalid |
+//! | `log(level, msg, msg_len)` | log a message, with levels `0` (trace) up to `4` (error) |
+//!
+//! The functions which read a value return its length, while only writing
+//! it into the given buffer in case it fits within `cap` bytes, such that
+//! a plugin can retry using a bigger buffer.
+//!
+//! The context metadata consists of the values of the [`WasmPluginMetadata`]
+//! found in the [`Context`], e.g. inserted by a previous layer,
+//! and the following built-in keys:
+//!
+//! - `peer_addr`: the address of the peer, if known;
+//! - `authority`, `protocol` and `http_version`: as found in the [`RequestContext`].
+//!
+//! Each request runs in a fresh instance of the plugin, limited in the fuel it
+//! can consume and the memory it can grow to. A plugin which fails, e.g. because
+//! it traps or runs out of fuel, fails the request with a `500 Internal Server Error`,
+//! unless the layer is configured to fail open, in which case the unmodified
+//! request is forwarded to the inner service.
+//!
+//! # Example
+//!
+//! ```
+//! use rama_core::{Context, Layer, Service, service::service_fn};
+//! use rama_http::layer::wasm_plugin::{WasmPlugin, WasmPluginLayer};
+//! use rama_http::{Body, Request, Response, StatusCode};
+//! use std::convert::Infallible;
+//!
+//! # #[tokio::main]
+//! # async fn main() {
+//! // usually loaded using `WasmPlugin::from_file`
+//! let plugin = WasmPlugin::new(
+//!     r#"(module
+//!         (import "rama" "header_get" (func $header_get (param i32 i32 i32 i32) (result i32)))
+//!         (memory (export "memory") 1)
+//!         (data (i32.const 0) "x-debug")
+//!         (func (export "on_request") (result i32)
+//!             (i32.ge_s
+//!                 (call $header_get (i32.const 0) (i32.const 7) (i32.const 0) (i32.const 0))
+//!                 (i32.const 0))))"#,
+//! )
+//! .unwrap();
+//!
+//! let service = WasmPluginLayer::new(plugin).into_layer(service_fn(async |_: Request| {
+//!     Ok::<_, Infallible>(Response::new(Body::empty()))
+//! }));
+//!
+//! let req = Request::get("/").body(Body::empty()).unwrap();
+//! let resp = service.serve(Context::default(), req).await.unwrap();
+//! assert_eq!(resp.status(), StatusCode::OK);
+//!
+//! let req = Request::get("/").header("x-debug", "1").body(Body::empty()).unwrap();
+//! let resp = service.serve(Context::default(), req).await.unwrap();
+//! assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+//! # }
+//! ```
+
+use std::{
+    collections::HashMap,
+    fmt,
+    path::Path,
+    sync::{Arc, PoisonError, RwLock},
+};
+
+use crate::dep::http::request::Parts;
+use crate::service::web::response::IntoResponse;
+use crate::{Body, HeaderMap, HeaderName, HeaderValue, Request, Response, StatusCode};
+use rama_core::{
+    Context, Layer, Service,
+    error::{ErrorContext, ErrorExt, OpaqueError},
+    telemetry::tracing,
+};
+use rama_net::{http::RequestContext, stream::SocketInfo};
+use rama_utils::macros::define_inner_service_accessors;
+use wasmtime::{
+    Caller, Config, Engine, Extern, InstancePre, Linker, Memory, Module, Store, StoreLimits,
+    StoreLimitsBuilder,
+};
+
+/// The name of the host module from which plugins import the host API.
+const HOST_MODULE: &str = "rama";
+
+#[derive(Debug, Clone, Default)]
+/// Metadata exposed to [`WasmPlugin`]s, as the context metadata of a request.
+///
+/// Insert it in the [`Context`], e.g. from a previous layer, to expose
+/// information such as the tenant or the authenticated user to the plugins.
+pub struct WasmPluginMetadata(HashMap<String, String>);
+
+impl WasmPluginMetadata {
+    /// Create a new empty [`WasmPluginMetadata`].
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Insert a value, returning the previous value of the key, if any.
+    pub fn insert(&mut self, key: impl Into<String>, value: impl Into<String>) -> Option<String> {
+        self.0.insert(key.into(), value.into())
+    }
+
+    /// Returns the value of the given key, if any.
+    #[must_use]
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.0.get(key).map(String::as_str)
+    }
+}
+
+/// A loaded WASM plugin, used by the [`WasmPluginService`] middleware.
+///
+/// All clones of a plugin share the same module,
+/// such that it can be reloaded for all services using it at once.
+///
+/// See the [module docs](self) for more information.
+#[derive(Clone)]
+pub struct WasmPlugin {
+    engine: Engine,
+    linker: Arc<Linker<PluginState>>,
+    module: Arc<RwLock<InstancePre<PluginState>>>,
+}
+
+impl WasmPlugin {
+    /// Load a [`WasmPlugin`] from a module in the WebAssembly binary or text format.
+    pub fn new(module: impl AsRef<[u8]>) -> Result<Self, OpaqueError> {
+        let mut config = Config::new();
+        config.consume_fuel(true);
+        let engine = Engine::new(&config).map_err(|err| wasm_error(err, "create wasm engine"))?;
+        let linker = host_linker(&engine)?;
+        let module = instantiate_pre(&engine, &linker, module.as_ref())?;
+        Ok(Self {
+            engine,
+            linker: Arc::new(linker),
+            module: Arc::new(RwLock::new(module)),
+        })
+    }
+
+    /// Load a [`WasmPlugin`] from a file in the WebAssembly binary or text format.
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, OpaqueError> {
+        let path = path.as_ref();
+        let module = std::fs::read(path)
+            .with_context(|| format!("read wasm plugin from {}", path.display()))?;
+        Self::new(module)
+    }
+
+    /// Replace the module of this [`WasmPlugin`], for all its clones.
+    ///
+    /// Requests being filtered keep using the previous module,
+    /// which also remains in use in case the new module fails to load.
+    pub fn reload(&self, module: impl AsRef<[u8]>) -> Result<(), OpaqueError> {
+        let module = instantiate_pre(&self.engine, &self.linker, module.as_ref())?;
+        *self.module.write().unwrap_or_else(PoisonError::into_inner) = module;
+        Ok(())
+    }
+
+    /// Replace the module of this [`WasmPlugin`], for all its clones,
+    /// by the module found in the given file.
+    ///
+    /// See [`WasmPlugin::reload`] for more information.
+    pub fn reload_file(&self, path: impl AsRef<Path>) -> Result<(), OpaqueError> {
+        let path = path.as_ref();
+        let module = std::fs::read(path)
+            .with_context(|| format!("read wasm plugin from {}", path.display()))?;
+        self.reload(module)
+    }
+
+    fn run(
+        &self,
+        ctx: &Context,
+        parts: &Parts,
+        limits: &PluginLimits,
+    ) -> Result<(HeaderMap, Option<Response>), OpaqueError> {
+        let module = self
+            .module
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone();
+
+        let mut store = Store::new(&self.engine, PluginState::new(ctx, parts, limits));
+        store.limiter(|state| &mut state.limits);
+        store
+            .set_fuel(limits.fuel)
+            .map_err(|err| wasm_error(err, "set wasm plugin fuel"))?;
+
+        let instance = module
+            .instantiate(&mut store)
+            .map_err(|err| wasm_error(err, "instantiate wasm plugin"))?;
+        let on_request = instance
+            .get_typed_func::<(), i32>(&mut store, "on_request")
+            .map_err(|err| wasm_error(err, "get on_request function of wasm plugin"))?;
+        let action = on_request
+            .call(&mut store, ())
+            .map_err(|err| wasm_error(err, "call on_request function of wasm plugin"))?;
+
+        let mut state = store.into_data();
+        let response = (action != 0).then(|| state.take_response());
+        Ok((state.headers, response))
+    }
+}
+
+impl fmt::Debug for WasmPlugin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WasmPlugin").finish_non_exhaustive()
+    }
+}
+
+fn wasm_error(err: wasmtime::Error, context: &'static str) -> OpaqueError {
+    OpaqueError::from_boxed(err.into()).context(context)
+}
+
+fn instantiate_pre(
+    engine: &Engine,
+    linker: &Linker<PluginState>,
+    module: &[u8],
+) -> Result<InstancePre<PluginState>, OpaqueError> {
+    let module =
+        Module::new(engine, module).map_err(|err| wasm_error(err, "compile wasm plugin"))?;
+    linker
+        .instantiate_pre(&module)
+        .map_err(|err| wasm_error(err, "link wasm plugin"))
+}
+
+#[derive(Debug, Clone, Copy)]
+struct PluginLimits {
+    fuel: u64,
+    max_memory: usize,
+}
+
+/// The state of a single plugin run, exposed to the plugin using the host API.
+struct PluginState {
+    method: String,
+    uri: String,
+    headers: HeaderMap,
+    context: HashMap<String, String>,
+    response: Option<(StatusCode, Vec<u8>)>,
+    response_headers: HeaderMap,
+    limits: StoreLimits,
+}
+
+impl PluginState {
+    fn new(ctx: &Context, parts: &Parts, limits: &PluginLimits) -> Self {
+        let mut context = ctx
+            .get::<WasmPluginMetadata>()
+            .map(|metadata| metadata.0.clone())
+            .unwrap_or_default();
+        if let Some(info) = ctx.get::<SocketInfo>() {
+            context.insert("peer_addr".to_owned(), info.peer_addr().to_string());
+        }
+        if let Ok(req_ctx) = RequestContext::try_from((ctx, parts)) {
+            context.insert("authority".to_owned(), req_ctx.authority.to_string());
+            context.insert("protocol".to_owned(), req_ctx.protocol.to_string());
+            context.insert(
+                "http_version".to_owned(),
+                format!("{:?}", req_ctx.http_version),
+            );
+        }
+
+        Self {
+            method: parts.method.to_string(),
+            uri: parts.uri.to_string(),
+            headers: parts.headers.clone(),
+            context,
+            response: None,
+            response_headers: HeaderMap::new(),
+            limits: StoreLimitsBuilder::new()
+                .memory_size(limits.max_memory)
+                .build(),
+        }
+    }
+
+    fn take_response(&mut self) -> Response {
+        let (status, body) = self
+            .response
+            .take()
+            .unwrap_or((StatusCode::FORBIDDEN, Vec::new()));
+        let mut response = (status, Body::from(body)).into_response();
+        response
+            .headers_mut()
+            .extend(std::mem::take(&mut self.response_headers));
+        response
+    }
+}
+
+fn host_linker(engine: &Engine) -> Result<Linker<PluginState>, OpaqueError> {
+    let mut linker = Linker::new(engine);
+    let define = |result: wasmtime::Result<&mut Linker<PluginState>>| {
+        result
+            .map(|_| ())
+            .map_err(|err| wasm_error(err, "define wasm plugin host function"))
+    };
+
+    define(linker.func_wrap(
+        HOST_MODULE,
+        "request_method",
+        |mut caller: Caller<'_, PluginState>, buf: i32, cap: i32| -> wasmtime::Result<i32> {
+            let method = caller.data().method.clone();
+            write_guest(&mut caller, method.as_bytes(), buf, cap)
+        },
+    ))?;
+
+    define(linker.func_wrap(
+        HOST_MODULE,
+        "request_uri",
+        |mut caller: Caller<'_, PluginState>, buf: i32, cap: i32| -> wasmtime::Result<i32> {
+            let uri = caller.data().uri.clone();
+            write_guest(&mut caller, uri.as_bytes(), buf, cap)
+        },
+    ))?;
+
+    define(linker.func_wrap(
+        HOST_MODULE,
+        "header_get",
+        |mut caller: Caller<'_, PluginState>,
+         name: i32,
+         name_len: i32,
+         buf: i32,
+         cap: i32|
+         -> wasmtime::Result<i32> {
+            let Ok(name) = HeaderName::from_bytes(&read_guest(&mut caller, name, name_len)?) else {
+                return Ok(-1);
+            };
+            match caller.data().headers.get(name).cloned() {
+                Some(value) => write_guest(&mut caller, value.as_bytes(), buf, cap),
+                None => Ok(-1),
+            }
+        },
+    ))?;
+
+    define(linker.func_wrap(
+        HOST_MODULE,
+        "header_set",
+        |mut caller: Caller<'_, PluginState>,
+         name: i32,
+         name_len: i32,
+         value: i32,
+         value_len: i32|
+         -> wasmtime::Result<i32> {
+            let (name, value) = read_header(&mut caller, name, name_len, value, value_len)?;
+            Ok(match name.zip(value) {
+                Some((name, value)) => {
+                    caller.data_mut().headers.insert(name, value);
+                    0
+                }
+                None => -1,
+            })
+        },
+    ))?;
+
+    define(linker.func_wrap(
+        HOST_MODULE,
+        "header_remove",
+        |mut caller: Caller<'_, PluginState>, name: i32, name_len: i32| -> wasmtime::Result<i32> {
+            let Ok(name) = HeaderName::from_bytes(&read_guest(&mut caller, name, name_len)?) else {
+                return Ok(-1);
+            };
+            Ok(i32::from(caller.data_mut().headers.remove(name).is_some()))
+        },
+    ))?;
+
+    define(linker.func_wrap(
+        HOST_MODULE,
+        "context_get",
+        |mut caller: Caller<'_, PluginState>,
+         key: i32,
+         key_len: i32,
+         buf: i32,
+         cap: i32|
+         -> wasmtime::Result<i32> {
+            let key = read_guest(&mut caller, key, key_len)?;
+            let value = std::str::from_utf8(&key)
+                .ok()
+                .and_then(|key| caller.data().context.get(key).cloned());
+            match value {
+                Some(value) => write_guest(&mut caller, value.as_bytes(), buf, cap),
+                None => Ok(-1),
+            }
+        },
+    ))?;
+
+    define(linker.func_wrap(
+        HOST_MODULE,
+        "respond",
+        |mut caller: Caller<'_, PluginState>,
+         status: i32,
+         body: i32,
+         body_len: i32|
+         -> wasmtime::Result<i32> {
+            let body = read_guest(&mut caller, body, body_len)?;
+            let Some(status) = u16::try_from(status)
+                .ok()
+                .and_then(|status| StatusCode::from_u16(status).ok())
+            else {
+                return Ok(-1);
+            };
+            caller.data_mut().response = Some((status, body));
+            Ok(0)
+        },
+    ))?;
+
+    define(linker.func_wrap(
+        HOST_MODULE,
+        "response_header_set",
+        |mut caller: Caller<'_, PluginState>,
+         name: i32,
+         name_len: i32,
+         value: i32,
+         value_len: i32|
+         -> wasmtime::Result<i32> {
+            let (name, value) = read_header(&mut caller, name, name_len, value, value_len)?;
+            Ok(match name.zip(value) {
+                Some((name, value)) => {
+                    caller.data_mut().response_headers.insert(name, value);
+                    0
+                }
+                None => -1,
+            })
+        },
+    ))?;
+
+    define(linker.func_wrap(
+        HOST_MODULE,
+        "log",
+        |mut caller: Caller<'_, PluginState>,
+         level: i32,
+         msg: i32,
+         msg_len: i32|
+         -> wasmtime::Result<()> {
+            let msg = read_guest(&mut caller, msg, msg_len)?;
+            let msg = String::from_utf8_lossy(&msg);
+            match level {
+                0 => tracing::trace!("wasm plugin: {msg}"),
+                1 => tracing::debug!("wasm plugin: {msg}"),
+                2 => tracing::info!("wasm plugin: {msg}"),
+                3 => tracing::warn!("wasm plugin: {msg}"),
+                _ => tracing::error!("wasm plugin: {msg}"),
+            }
+            Ok(())
+        },
+    ))?;
+
+    Ok(linker)
+}
+
+fn guest_memory(caller: &mut Caller<'_, PluginState>) -> wasmtime::Result<Memory> {
+    caller
+        .get_export("memory")
+        .and_then(Extern::into_memory)
+        .ok_or_else(|| wasmtime::Error::msg("wasm plugin does not export its memory"))
+}
+
+fn guest_offset(value: i32) -> wasmtime::Result<usize> {
+    usize::try_from(value)
+        .map_err(|_| wasmtime::Error::msg("negative wasm plugin pointer or length"))
+}
+
+fn read_guest(
+    caller: &mut Caller<'_, PluginState>,
+    ptr: i32,
+    len: i32,
+) -> wasmtime::Result<Vec<u8>> {
+    let memory = guest_memory(caller)?;
+    let mut buf = vec![0; guest_offset(len)?];
+    memory.read(&*caller, guest_offset(ptr)?, &mut buf)?;
+    Ok(buf)
+}
+
+/// Write the value in the guest buffer in case it fits, returning its length.
+fn write_guest(
+    caller: &mut Caller<'_, PluginState>,
+    value: &[u8],
+    buf: i32,
+    cap: i32,
+) -> wasmtime::Result<i32> {
+    let len = i32::try_from(value.len())?;
+    if len <= cap {
+        let memory = guest_memory(caller)?;
+        memory.write(&mut *caller, guest_offset(buf)?, value)?;
+    }
+    Ok(len)
+}
+
+fn read_header(
+    caller: &mut Caller<'_, PluginState>,
+    name: i32,
+    name_len: i32,
+    value: i32,
+    value_len: i32,
+) -> wasmtime::Result<(Option<HeaderName>, Option<HeaderValue>)> {
+    let name = read_guest(caller, name, name_len)?;
+    let value = read_guest(caller, value, value_len)?;
+    Ok((
+        HeaderName::from_bytes(&name).ok(),
+        HeaderValue::from_bytes(&value).ok(),
+    ))
+}
+
+/// Layer that applies the [`WasmPluginService`] middleware.
+///
+/// See the [module docs](self) for more information.
+#[derive(Debug, Clone)]
+pub struct WasmPluginLayer {
+    plugin: WasmPlugin,
+    limits: PluginLimits,
+    fail_open: bool,
+}
+
+impl WasmPluginLayer {
+    /// Create a new [`WasmPluginLayer`] filtering the requests using the given [`WasmPlugin`].
+    #[must_use]
+    pub fn new(plugin: WasmPlugin) -> Self {
+        Self {
+            plugin,
+            limits: PluginLimits {
+                fuel: 10_000_000,
+                max_memory: 16 * 1024 * 1024,
+            },
+            fail_open: false,
+        }
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Set the fuel a plugin can consume per request,
+        /// which roughly corresponds to the number of executed instructions.
+        ///
+        /// Defaults to 10 million.
+        pub fn fuel(mut self, fuel: u64) -> Self {
+            self.limits.fuel = fuel;
+            self
+        }
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Set the maximum size in bytes the memory of a plugin can grow to.
+        ///
+        /// Defaults to 16 MiB.
+        pub fn max_memory(mut self, max_memory: usize) -> Self {
+            self.limits.max_memory = max_memory;
+            self
+        }
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Forward the unmodified request to the inner service in case the plugin fails,
+        /// instead of failing the request with a `500 Internal Server Error`.
+        pub fn fail_open(mut self, fail_open: bool) -> Self {
+            self.fail_open = fail_open;
+            self
+        }
+    }
+}
+
+impl<S> Layer<S> for WasmPluginLayer {
+    type Service = WasmPluginService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        WasmPluginService {
+            inner,
+            plugin: self.plugin.clone(),
+            limits: self.limits,
+            fail_open: self.fail_open,
+        }
+    }
+
+    fn into_layer(self, inner: S) -> Self::Service {
+        WasmPluginService {
+            inner,
+            plugin: self.plugin,
+            limits: self.limits,
+            fail_open: self.fail_open,
+        }
+    }
+}
+
+/// Middleware which filters the requests using a [`WasmPlugin`].
+///
+/// See the [module docs](self) for more information.
+pub struct WasmPluginService<S> {
+    inner: S,
+    plugin: WasmPlugin,
+    limits: PluginLimits,
+    fail_open: bool,
+}
+
+impl<S> WasmPluginService<S> {
+    define_inner_service_accessors!();
+}
+
+impl<S: fmt::Debug> fmt::Debug for WasmPluginService<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WasmPluginService")
+            .field("inner", &self.inner)
+            .field("plugin", &self.plugin)
+            .field("limits", &self.limits)
+            .field("fail_open", &self.fail_open)
+            .finish()
+    }
+}
+
+impl<S: Clone> Clone for WasmPluginService<S> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            plugin: self.plugin.clone(),
+            limits: self.limits,
+            fail_open: self.fail_open,
+        }
+    }
+}
+
+impl<S, ReqBody> Service<Request<ReqBody>> for WasmPluginService<S>
+where
+    S: Service<Request<ReqBody>, Response = Response>,
+    ReqBody: Send + 'static,
+{
+    type Response = Response;
+    type Error = S::Error;
+
+    async fn serve(
+        &self,
+        ctx: Context,
+        req: Request<ReqBody>,
+    ) -> Result<Self::Response, Self::Error> {
+        let (mut parts, body) = req.into_parts();
+        match self.plugin.run(&ctx, &parts, &self.limits) {
+            Ok((headers, None)) => parts.headers = headers,
+            Ok((_, Some(response))) => return Ok(response),
+            Err(err) if self.fail_open => {
+                tracing::warn!("wasm plugin failed, forwarding unfiltered request: {err}");
+            }
+            Err(err) => {
+                tracing::error!("wasm plugin failed: {err}");
+                return Ok(StatusCode::INTERNAL_SERVER_ERROR.into_response());
+            }
+        }
+        self.inner
+            .serve(ctx, Request::from_parts(parts, body))
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dep::http_body_util::BodyExt;
+    use rama_core::service::service_fn;
+    use std::convert::Infallible;
+
+    const ECHO_HEADER_PLUGIN: &str = r#"(module
+        (import "rama" "header_get" (func $header_get (param i32 i32 i32 i32) (result i32)))
+        (import "rama" "header_set" (func $header_set (param i32 i32 i32 i32) (result i32)))
+        (import "rama" "header_remove" (func $header_remove (param i32 i32) (result i32)))
+        (import "rama" "respond" (func $respond (param i32 i32 i32) (result i32)))
+        (import "rama" "response_header_set" (func $response_header_set (param i32 i32 i32 i32) (result i32)))
+        (memory (export "memory") 1)
+        (data (i32.const 0) "x-plugin")
+        (data (i32.const 16) "v1")
+        (data (i32.const 32) "x-block")
+        (data (i32.const 48) "blocked")
+        (data (i32.const 64) "x-secret")
+        (func (export "on_request") (result i32)
+            (if (i32.ge_s
+                    (call $header_get (i32.const 32) (i32.const 7) (i32.const 0) (i32.const 0))
+                    (i32.const 0))
+                (then
+                    (drop (call $respond (i32.const 429) (i32.const 48) (i32.const 7)))
+                    (drop (call $response_header_set (i32.const 0) (i32.const 8) (i32.const 16) (i32.const 2)))
+                    (return (i32.const 1))))
+            (drop (call $header_remove (i32.const 64) (i32.const 8)))
+            (drop (call $header_set (i32.const 0) (i32.const 8) (i32.const 16) (i32.const 2)))
+            (i32.const 0)))"#;
+
+    fn echo_headers_service() -> impl Service<Request, Response = Response, Error = Infallible> {
+        service_fn(async |req: Request| {
+            let mut resp = Response::new(Body::empty());
+            *resp.headers_mut() = req.headers().clone();
+            Ok::<_, Infallible>(resp)
+        })
+    }
+
+    #[tokio::test]
+    async fn test_wasm_plugin_modify_and_respond() {
+        let plugin = WasmPlugin::new(ECHO_HEADER_PLUGIN).unwrap();
+        let service = WasmPluginLayer::new(plugin).into_layer(echo_headers_service());
+
+        let req = Request::get("/")
+            .header("x-secret", "hunter2")
+            .body(Body::empty())
+            .unwrap();
+        let resp = service.serve(Context::default(), req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(resp.headers()["x-plugin"], "v1");
+        assert!(!resp.headers().contains_key("x-secret"));
+
+        let req = Request::get("/")
+            .header("x-block", "1")
+            .body(Body::empty())
+            .unwrap();
+        let resp = service.serve(Context::default(), req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(resp.headers()["x-plugin"], "v1");
+        assert_eq!(
+            resp.into_body().collect().await.unwrap().to_bytes(),
+            "blocked"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_wasm_plugin_context_metadata() {
+        let plugin = WasmPlugin::new(
+            r#"(module
+                (import "rama" "context_get" (func $context_get (param i32 i32 i32 i32) (result i32)))
+                (import "rama" "header_set" (func $header_set (param i32 i32 i32 i32) (result i32)))
+                (memory (export "memory") 1)
+                (data (i32.const 0) "tenant")
+                (data (i32.const 16) "x-tenant")
+                (func (export "on_request") (result i32)
+                    (local $len i32)
+                    (local.set $len
+                        (call $context_get (i32.const 0) (i32.const 6) (i32.const 128) (i32.const 64)))
+                    (if (i32.lt_s (local.get $len) (i32.const 0))
+                        (then (return (i32.const 1))))
+                    (drop (call $header_set (i32.const 16) (i32.const 8) (i32.const 128) (local.get $len)))
+                    (i32.const 0)))"#,
+        )
+        .unwrap();
+        let service = WasmPluginLayer::new(plugin).into_layer(echo_headers_service());
+
+        let req = Request::get("/").body(Body::empty()).unwrap();
+        let resp = service.serve(Context::default(), req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+
+        let mut metadata = WasmPluginMetadata::new();
+        metadata.insert("tenant", "acme");
+        let mut ctx = Context::default();
+        ctx.insert(metadata);
+        let req = Request::get("/").body(Body::empty()).unwrap();
+        let resp = service.serve(ctx, req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(resp.headers()["x-tenant"], "acme");
+    }
+
+    #[tokio::test]
+    async fn test_wasm_plugin_reload() {
+        let plugin = WasmPlugin::new(ECHO_HEADER_PLUGIN).unwrap();
+        let service = WasmPluginLayer::new(plugin.clone()).into_layer(echo_headers_service());
+
+        let serve = async || {
+            let req = Request::get("/").body(Body::empty()).unwrap();
+            service.serve(Context::default(), req).await.unwrap()
+        };
+        assert_eq!(serve().await.headers()["x-plugin"], "v1");
+
+        plugin
+            .reload(ECHO_HEADER_PLUGIN.replace("\"v1\"", "\"v2\""))
+            .unwrap();
+        assert_eq!(serve().await.headers()["x-plugin"], "v2");
+
+        plugin.reload("(module").unwrap_err();
+        assert_eq!(serve().await.headers()["x-plugin"], "v2");
+    }
+
+    #[tokio::test]
+    async fn test_wasm_plugin_failure() {
+        let plugin = WasmPlugin::new(
+            r#"(module
+                (memory (export "memory") 1)
+                (func (export "on_request") (result i32)
+                    (loop $spin (br $spin))
+                    (i32.const 0)))"#,
+        )
+        .unwrap();
+
+        let service = WasmPluginLayer::new(plugin.clone()).into_layer(echo_headers_service());
+        let req = Request::get("/").body(Body::empty()).unwrap();
+        let resp = service.serve(Context::default(), req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::INTERNAL_SERVER_ERROR);
+
+        let service = WasmPluginLayer::new(plugin)
+            .with_fuel(1_000)
+            .with_fail_open(true)
+            .into_layer(echo_headers_service());
+        let req = Request::get("/")
+            .header("x-test", "1")
+            .body(Body::empty())
+            .unwrap();
+        let resp = service.serve(Context::default(), req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(resp.headers()["x-test"], "1");
+    }
+}
@@ -38,6 +38,14 @@ mod subdomain_trie;
 #[doc(inline)]
 pub use subdomain_trie::SubdomainTrieMatcher;
 
+mod query;
+#[doc(inline)]
+pub use query::QueryMatcher;
+
+mod user_agent;
+#[doc(inline)]
+pub use user_agent::UserAgentKindMatcher;
+
 /// A matcher that is used to match an http [`Request`]
 pub struct HttpMatcher<Body> {
     kind: HttpMatcherKind<Body>,
@@ -86,6 +94,10 @@ pub enum HttpMatcherKind<Body> {
     Socket(SocketMatcher<Request<Body>>),
     /// [`SubdomainTrieMatcher`], a matcher based on domain and subdomains using a trie structure.
     SubdomainTrie(SubdomainTrieMatcher),
+    /// [`QueryMatcher`], a matcher based on the query parameters of the [`Request`]'s URI.
+    Query(QueryMatcher),
+    /// [`UserAgentKindMatcher`], a matcher based on the kind of user agent of the [`Request`].
+    UserAgentKind(UserAgentKindMatcher),
     /// A custom matcher that implements [`rama_core::matcher::Matcher`].
     Custom(Arc<dyn rama_core::matcher::Matcher<Request<Body>>>),
 }
@@ -103,6 +115,8 @@ impl<Body> Clone for HttpMatcherKind<Body> {
             Self::Header(inner) => Self::Header(inner.clone()),
             Self::Socket(inner) => Self::Socket(inner.clone()),
             Self::SubdomainTrie(inner) => Self::SubdomainTrie(inner.clone()),
+            Self::Query(inner) => Self::Query(inner.clone()),
+            Self::UserAgentKind(inner) => Self::UserAgentKind(inner.clone()),
             Self::Custom(inner) => Self::Custom(inner.clone()),
         }
     }
@@ -121,6 +135,8 @@ impl<Body> fmt::Debug for HttpMatcherKind<Body> {
             Self::Header(inner) => f.debug_tuple("Header").field(inner).finish(),
             Self::Socket(inner) => f.debug_tuple("Socket").field(inner).finish(),
             Self::SubdomainTrie(inner) => f.debug_tuple("SubdomainTrie").field(inner).finish(),
+            Self::Query(inner) => f.debug_tuple("Query").field(inner).finish(),
+            Self::UserAgentKind(inner) => f.debug_tuple("UserAgentKind").field(inner).finish(),
             Self::Custom(_) => f.debug_tuple("Custom").finish(),
         }
     }
@@ -634,6 +650,87 @@ impl<Body> HttpMatcher<Body> {
         self.or(Self::header_contains(name, value))
     }
 
+    /// Create a [`QueryMatcher`] matcher to match on a query parameter with the given value.
+    #[must_use]
+    pub fn query(name: impl Into<String>, value: impl Into<String>) -> Self {
+        Self {
+            kind: HttpMatcherKind::Query(QueryMatcher::is(name, value)),
+            negate: false,
+        }
+    }
+
+    /// Add a [`QueryMatcher`] to match on a query parameter with the given value
+    /// on top of the existing set of [`HttpMatcher`] matchers.
+    ///
+    /// See [`QueryMatcher`] for more information.
+    #[must_use]
+    pub fn and_query(self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.and(Self::query(name, value))
+    }
+
+    /// Create a [`QueryMatcher`] matcher to match on a query parameter with the given value
+    /// as an alternative to the existing set of [`HttpMatcher`] matchers.
+    ///
+    /// See [`QueryMatcher`] for more information.
+    #[must_use]
+    pub fn or_query(self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.or(Self::query(name, value))
+    }
+
+    /// Create a [`QueryMatcher`] matcher to match on the existence of a query parameter.
+    #[must_use]
+    pub fn query_exists(name: impl Into<String>) -> Self {
+        Self {
+            kind: HttpMatcherKind::Query(QueryMatcher::exists(name)),
+            negate: false,
+        }
+    }
+
+    /// Add a [`QueryMatcher`] to match on the existence of a query parameter
+    /// on top of the existing set of [`HttpMatcher`] matchers.
+    ///
+    /// See [`QueryMatcher`] for more information.
+    #[must_use]
+    pub fn and_query_exists(self, name: impl Into<String>) -> Self {
+        self.and(Self::query_exists(name))
+    }
+
+    /// Create a [`QueryMatcher`] matcher to match on the existence of a query parameter
+    /// as an alternative to the existing set of [`HttpMatcher`] matchers.
+    ///
+    /// See [`QueryMatcher`] for more information.
+    #[must_use]
+    pub fn or_query_exists(self, name: impl Into<String>) -> Self {
+        self.or(Self::query_exists(name))
+    }
+
+    /// Create a [`UserAgentKindMatcher`] matcher to match on any of the given kinds of user agent.
+    #[must_use]
+    pub fn ua_kind(kinds: impl IntoIterator<Item = rama_ua::UserAgentKind>) -> Self {
+        Self {
+            kind: HttpMatcherKind::UserAgentKind(UserAgentKindMatcher::new(kinds)),
+            negate: false,
+        }
+    }
+
+    /// Add a [`UserAgentKindMatcher`] to match on any of the given kinds of user agent
+    /// on top of the existing set of [`HttpMatcher`] matchers.
+    ///
+    /// See [`UserAgentKindMatcher`] for more information.
+    #[must_use]
+    pub fn and_ua_kind(self, kinds: impl IntoIterator<Item = rama_ua::UserAgentKind>) -> Self {
+        self.and(Self::ua_kind(kinds))
+    }
+
+    /// Create a [`UserAgentKindMatcher`] matcher to match on any of the given kinds of user agent
+    /// as an alternative to the existing set of [`HttpMatcher`] matchers.
+    ///
+    /// See [`UserAgentKindMatcher`] for more information.
+    #[must_use]
+    pub fn or_ua_kind(self, kinds: impl IntoIterator<Item = rama_ua::UserAgentKind>) -> Self {
+        self.or(Self::ua_kind(kinds))
+    }
+
     /// Create a [`SocketMatcher`] matcher.
     #[must_use]
     pub fn socket(socket: SocketMatcher<Request<Body>>) -> Self {
@@ -855,6 +952,8 @@ where
             Self::Socket(socket) => socket.matches(ext, ctx, req),
             Self::Any(all) => all.iter().matches_or(ext, ctx, req),
             Self::SubdomainTrie(subdomain_trie) => subdomain_trie.matches(ext, ctx, req),
+            Self::Query(query) => query.matches(ext, ctx, req),
+            Self::UserAgentKind(ua_kind) => ua_kind.matches(ext, ctx, req),
             Self::Custom(matcher) => matcher.matches(ext, ctx, req),
         }
     }
@@ -881,6 +980,45 @@ mod test {
         }
     }
 
+    #[test]
+    fn test_matcher_composition() {
+        use rama_net::stream::SocketInfo;
+
+        // e.g. to rate limit non-browser API clients outside of the private network
+        let matcher = HttpMatcher::method_post()
+            .and_path("/api/{*rest}")
+            .and(
+                HttpMatcher::query_exists("debug")
+                    .or_header_exists(rama_http_types::header::HeaderName::from_static("x-debug")),
+            )
+            .and(
+                HttpMatcher::ua_kind([rama_ua::UserAgentKind::Chromium])
+                    .or_socket(SocketMatcher::private_ip_net())
+                    .negate(),
+            );
+
+        let mut ctx = Context::default();
+        ctx.insert(SocketInfo::new(None, ([1, 2, 3, 4], 8080).into()));
+
+        let req = Request::post("/api/v1/users?debug").body(()).unwrap();
+        assert!(matcher.matches(None, &ctx, &req));
+
+        let req = Request::post("/api/v1/users").body(()).unwrap();
+        assert!(!matcher.matches(None, &ctx, &req));
+
+        let req = Request::get("/api/v1/users?debug=1").body(()).unwrap();
+        assert!(!matcher.matches(None, &ctx, &req));
+
+        let mut private_ctx = Context::default();
+        private_ctx.insert(SocketInfo::new(None, ([10, 0, 0, 1], 8080).into()));
+        let req = Request::post("/api/v1/users")
+            .header("x-debug", "1")
+            .body(())
+            .unwrap();
+        assert!(matcher.matches(None, &ctx, &req));
+        assert!(!matcher.matches(None, &private_ctx, &req));
+    }
+
     #[test]
     fn test_matcher_and_combination() {
         for v in [true, false].into_iter().permutations(3) {
@@ -161,6 +161,7 @@ enum PathFragment {
     Literal(String),
     Param(String),
     Glob,
+    NamedGlob(String),
 }
 
 #[derive(Debug, Clone)]
@@ -177,6 +178,11 @@ pub struct PathMatcher {
 
 impl PathMatcher {
     /// Create a new [`PathMatcher`] for the given path.
+    ///
+    /// The path can contain parameters (e.g. `/users/{id}` or `/users/:id`),
+    /// and end with a wildcard (`/assets/*`) or named catch-all (`/assets/{*path}`)
+    /// segment, matching one or more remaining segments.
+    /// Captured values are available via the [`UriParams`] inserted into the [`Context`].
     pub fn new(path: impl AsRef<str>) -> Self {
         let path = path.as_ref();
         let path = path.trim().trim_matches('/');
@@ -211,6 +217,13 @@ impl PathMatcher {
                     Some(PathFragment::Param(param_name))
                 } else if s == "*" && index == fragment_length - 1 {
                     Some(PathFragment::Glob)
+                } else if s.starts_with("{*")
+                    && s.ends_with('}')
+                    && s.len() > 3
+                    && index == fragment_length - 1
+                {
+                    let param_name = s[2..s.len() - 1].to_lowercase();
+                    Some(PathFragment::NamedGlob(param_name))
                 } else {
                     Some(PathFragment::Literal(s.to_lowercase()))
                 }
@@ -258,7 +271,7 @@ impl PathMatcher {
                                     .unwrap_or_else(|_| segment.to_owned());
                                 params.insert(name.to_owned(), segment);
                             }
-                            PathFragment::Glob => {
+                            PathFragment::Glob | PathFragment::NamedGlob(_) => {
                                 params.append_glob(segment);
                             }
                         },
@@ -275,6 +288,17 @@ impl PathMatcher {
                     }
                 }
 
+                if let Some(PathFragment::NamedGlob(name)) = fragments.last()
+                    && let Some(glob) = params.glob()
+                {
+                    let rest = glob.trim_start_matches('/');
+                    let rest = percent_encoding::percent_decode(rest.as_bytes())
+                        .decode_utf8()
+                        .map(|s| s.to_string())
+                        .unwrap_or_else(|_| rest.to_owned());
+                    params.insert(name.to_owned(), rest);
+                }
+
                 Some(params)
             }
         }
@@ -426,6 +450,13 @@ mod test {
                 params.glob = Some("/css/reset.css".to_owned());
                 params
             }),
+            TestCase::some("/assets/css/reset%20v2.css", "/assets/{*path}", {
+                let mut params = UriParams::default();
+                params.insert("path".to_owned(), "css/reset v2.css".to_owned());
+                params.glob = Some("/css/reset%20v2.css".to_owned());
+                params
+            }),
+            TestCase::none("/assets", "/assets/{*path}"),
             TestCase::some("/assets/eu/css/reset.css", "/assets/:local/css/*", {
                 let mut params = UriParams::default();
                 params.insert("local".to_owned(), "eu".to_owned());
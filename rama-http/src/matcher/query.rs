@@ -0,0 +1,104 @@
+use crate::Request;
+use rama_core::{Context, context::Extensions, matcher::Matcher};
+use std::borrow::Cow;
+
+#[derive(Debug, Clone)]
+/// Matcher based on the query parameters of the [`Request`]'s URI.
+///
+/// Parameter names and values are compared after being (percent) decoded.
+///
+/// [`Request`]: crate::Request
+pub struct QueryMatcher {
+    name: String,
+    value: Option<String>,
+}
+
+impl QueryMatcher {
+    /// Create a new query matcher to match on the existence of a query parameter.
+    pub fn exists(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            value: None,
+        }
+    }
+
+    /// Create a new query matcher to match on a query parameter with the given value.
+    ///
+    /// The matcher matches in case any of the parameters with that name has the given value.
+    pub fn is(name: impl Into<String>, value: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            value: Some(value.into()),
+        }
+    }
+
+    fn matches_query(&self, query: &str) -> bool {
+        query
+            .split('&')
+            .filter(|pair| !pair.is_empty())
+            .map(|pair| match pair.split_once('=') {
+                Some((name, value)) => (decode(name), decode(value)),
+                None => (decode(pair), String::new()),
+            })
+            .any(|(name, value)| {
+                name == self.name.as_str()
+                    && self
+                        .value
+                        .as_deref()
+                        .is_none_or(|expected| value == expected)
+            })
+    }
+}
+
+fn decode(s: &str) -> String {
+    let s = s.replace('+', " ");
+    let decoded = percent_encoding::percent_decode_str(&s)
+        .decode_utf8()
+        .map(Cow::into_owned)
+        .ok();
+    decoded.unwrap_or(s)
+}
+
+impl<Body> Matcher<Request<Body>> for QueryMatcher {
+    fn matches(&self, _ext: Option<&mut Extensions>, _ctx: &Context, req: &Request<Body>) -> bool {
+        req.uri()
+            .query()
+            .is_some_and(|query| self.matches_query(query))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_query_matcher() {
+        let test_cases = [
+            (QueryMatcher::exists("debug"), "/?debug", true),
+            (QueryMatcher::exists("debug"), "/?a=1&debug=0", true),
+            (QueryMatcher::exists("debug"), "/?a=1", false),
+            (QueryMatcher::exists("debug"), "/", false),
+            (QueryMatcher::exists("lang"), "/?language=en", false),
+            (QueryMatcher::is("lang", "en"), "/?lang=nl&lang=en", true),
+            (
+                QueryMatcher::is("q", "hello world"),
+                "/?q=hello+world",
+                true,
+            ),
+            (
+                QueryMatcher::is("q", "hello world"),
+                "/?q=hello%20world",
+                true,
+            ),
+            (QueryMatcher::is("q", "hello"), "/?q=hello+world", false),
+        ];
+        for (matcher, uri, expected) in test_cases {
+            let req = Request::builder().uri(uri).body(()).unwrap();
+            assert_eq!(
+                matcher.matches(None, &Context::default(), &req),
+                expected,
+                "({matcher:?}).matches({uri})",
+            );
+        }
+    }
+}
@@ -0,0 +1,66 @@
+use crate::{Request, header};
+use rama_core::{Context, context::Extensions, matcher::Matcher};
+use rama_ua::{UserAgent, UserAgentKind};
+
+#[derive(Debug, Clone)]
+/// Matcher based on the [`UserAgentKind`] of the [`Request`].
+///
+/// The [`UserAgent`] found in the [`Context`] is used if present
+/// (e.g. inserted by the `UserAgentClassifierLayer`),
+/// and otherwise the `User-Agent` header of the [`Request`] is parsed.
+///
+/// [`Request`]: crate::Request
+pub struct UserAgentKindMatcher {
+    kinds: Vec<UserAgentKind>,
+}
+
+impl UserAgentKindMatcher {
+    /// Create a new [`UserAgentKindMatcher`] matching any of the given [`UserAgentKind`]s.
+    pub fn new(kinds: impl IntoIterator<Item = UserAgentKind>) -> Self {
+        Self {
+            kinds: kinds.into_iter().collect(),
+        }
+    }
+}
+
+impl<Body> Matcher<Request<Body>> for UserAgentKindMatcher {
+    fn matches(&self, _ext: Option<&mut Extensions>, ctx: &Context, req: &Request<Body>) -> bool {
+        let ua_kind = match ctx.get::<UserAgent>() {
+            Some(ua) => ua.ua_kind(),
+            None => req
+                .headers()
+                .get(header::USER_AGENT)
+                .and_then(|value| value.to_str().ok())
+                .and_then(|value| UserAgent::new(value).ua_kind()),
+        };
+        ua_kind.is_some_and(|kind| self.kinds.contains(&kind))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_user_agent_kind_matcher() {
+        let matcher = UserAgentKindMatcher::new([UserAgentKind::Firefox]);
+
+        let req = Request::builder()
+            .header(
+                "user-agent",
+                "Mozilla/5.0 (X11; Linux x86_64; rv:135.0) Gecko/20100101 Firefox/135.0",
+            )
+            .body(())
+            .unwrap();
+        assert!(matcher.matches(None, &Context::default(), &req));
+
+        let mut ctx = Context::default();
+        ctx.insert(UserAgent::new(
+            "Mozilla/5.0 (Macintosh; Intel Mac OS X 10_15_7) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/133.0.0.0 Safari/537.36",
+        ));
+        assert!(!matcher.matches(None, &ctx, &req));
+
+        let req = Request::builder().body(()).unwrap();
+        assert!(!matcher.matches(None, &Context::default(), &req));
+    }
+}
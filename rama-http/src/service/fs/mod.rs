@@ -14,11 +14,13 @@ use tokio_util::io::ReaderStream;
 
 mod serve_dir;
 mod serve_file;
+mod spa;
 
 #[doc(inline)]
 pub use self::{
     serve_dir::{DefaultServeDirFallback, DirectoryServeMode, ServeDir},
     serve_file::ServeFile,
+    spa::ServeSpa,
 };
 
 pin_project! {
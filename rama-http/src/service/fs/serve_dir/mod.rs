@@ -475,6 +475,16 @@ impl TryFrom<&str> for DirectoryServeMode {
     }
 }
 
+impl<'de> serde::Deserialize<'de> for DirectoryServeMode {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let s = <std::borrow::Cow<'de, str>>::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
 // Allow the ServeDir service to be used in the ServeFile service
 // with almost no overhead
 #[derive(Clone, Debug)]
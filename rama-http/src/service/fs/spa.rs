@@ -0,0 +1,223 @@
+//! Service that serves a single-page application (SPA).
+
+use super::{ServeDir, ServeFile};
+use crate::{Body, Method, Request, Response, StatusCode};
+use rama_core::{Context, Service};
+use std::{convert::Infallible, path::Path};
+
+/// Service that serves a single-page application (SPA) from a given directory.
+///
+/// Files are served using [`ServeDir`]. Requests which do not match any file
+/// are answered with the index file (`index.html` by default) instead,
+/// such that client-side routing can take over. This fallback only applies when:
+///
+/// - the request method is `GET` or `HEAD`;
+/// - the last segment of the path has no file extension (e.g. `/users/42`),
+///   as missing assets such as `/app.js` should still result in a `404 Not Found`;
+/// - the path does not start with one of the excluded prefixes (e.g. `/api`).
+///
+/// # Example
+///
+/// ```
+/// use rama_http::service::fs::ServeSpa;
+///
+/// let service = ServeSpa::new("dist").with_excluded_prefix("/api");
+/// ```
+#[derive(Clone, Debug)]
+pub struct ServeSpa {
+    dir: ServeDir,
+    index: ServeFile,
+    excluded_prefixes: Vec<String>,
+}
+
+impl ServeSpa {
+    /// Create a new [`ServeSpa`] serving the files in the given directory,
+    /// using the `index.html` file within that directory as fallback.
+    pub fn new<P>(path: P) -> Self
+    where
+        P: AsRef<Path>,
+    {
+        let path = path.as_ref();
+        Self {
+            dir: ServeDir::new(path),
+            index: ServeFile::new(path.join("index.html")),
+            excluded_prefixes: Vec::new(),
+        }
+    }
+
+    /// Overwrite the file served for requests which do not match any file.
+    #[must_use]
+    pub fn with_index_file<P: AsRef<Path>>(mut self, path: P) -> Self {
+        self.index = ServeFile::new(path);
+        self
+    }
+
+    /// Overwrite the file served for requests which do not match any file.
+    pub fn set_index_file<P: AsRef<Path>>(&mut self, path: P) -> &mut Self {
+        self.index = ServeFile::new(path);
+        self
+    }
+
+    /// Exclude all paths starting with the given prefix from the index fallback,
+    /// such that these result in a `404 Not Found` when no file matches.
+    ///
+    /// A prefix matches whole path segments only: `/api` excludes `/api`
+    /// and `/api/users`, but not `/apiary`.
+    #[must_use]
+    pub fn with_excluded_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.excluded_prefixes.push(prefix.into());
+        self
+    }
+
+    /// Exclude all paths starting with the given prefix from the index fallback,
+    /// such that these result in a `404 Not Found` when no file matches.
+    ///
+    /// A prefix matches whole path segments only: `/api` excludes `/api`
+    /// and `/api/users`, but not `/apiary`.
+    pub fn set_excluded_prefix(&mut self, prefix: impl Into<String>) -> &mut Self {
+        self.excluded_prefixes.push(prefix.into());
+        self
+    }
+
+    /// Set a specific read buffer chunk size.
+    ///
+    /// The default capacity is 64kb.
+    #[must_use]
+    pub fn with_buf_chunk_size(self, chunk_size: usize) -> Self {
+        Self {
+            dir: self.dir.with_buf_chunk_size(chunk_size),
+            index: self.index.with_buf_chunk_size(chunk_size),
+            excluded_prefixes: self.excluded_prefixes,
+        }
+    }
+
+    fn is_fallback_path(&self, path: &str) -> bool {
+        let is_asset = path
+            .rsplit('/')
+            .next()
+            .is_some_and(|segment| segment.contains('.'));
+        if is_asset {
+            return false;
+        }
+
+        !self.excluded_prefixes.iter().any(|prefix| {
+            let prefix = prefix.trim_end_matches('/');
+            path.strip_prefix(prefix)
+                .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+        })
+    }
+}
+
+impl<ReqBody> Service<Request<ReqBody>> for ServeSpa
+where
+    ReqBody: Send + 'static,
+{
+    type Response = Response;
+    type Error = Infallible;
+
+    async fn serve(
+        &self,
+        ctx: Context,
+        req: Request<ReqBody>,
+    ) -> Result<Self::Response, Self::Error> {
+        let fallback_req = ((req.method() == Method::GET || req.method() == Method::HEAD)
+            && self.is_fallback_path(req.uri().path()))
+        .then(|| {
+            let mut fallback_req = Request::new(Body::empty());
+            *fallback_req.method_mut() = req.method().clone();
+            *fallback_req.uri_mut() = req.uri().clone();
+            *fallback_req.headers_mut() = req.headers().clone();
+            fallback_req
+        });
+
+        let res = self.dir.serve(ctx.clone(), req).await?;
+        match fallback_req {
+            Some(fallback_req) if res.status() == StatusCode::NOT_FOUND => {
+                self.index.serve(ctx, fallback_req).await
+            }
+            _ => Ok(res),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::header;
+    use rama_http_types::BodyExtractExt;
+
+    async fn get(svc: &ServeSpa, uri: &str) -> Response {
+        let req = Request::builder().uri(uri).body(Body::empty()).unwrap();
+        svc.serve(Context::default(), req).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_serve_spa_serves_files() {
+        let svc = ServeSpa::new("../test-files");
+
+        let res = get(&svc, "/hello.txt").await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(res.headers()[header::CONTENT_TYPE], "text/plain");
+    }
+
+    #[tokio::test]
+    async fn test_serve_spa_falls_back_to_index() {
+        let svc = ServeSpa::new("../test-files");
+
+        for uri in ["/users/42", "/settings", "/nested/deep/route?q=1"] {
+            let res = get(&svc, uri).await;
+            assert_eq!(res.status(), StatusCode::OK, "uri: {uri}");
+            assert_eq!(
+                res.headers()[header::CONTENT_TYPE],
+                "text/html",
+                "uri: {uri}"
+            );
+            let body = res.try_into_string().await.unwrap();
+            assert!(body.contains("HTML!"), "uri: {uri}");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_serve_spa_no_fallback_for_assets() {
+        let svc = ServeSpa::new("../test-files");
+
+        let res = get(&svc, "/missing.js").await;
+        assert_eq!(res.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_serve_spa_no_fallback_for_excluded_prefix() {
+        let svc = ServeSpa::new("../test-files").with_excluded_prefix("/api/");
+
+        let res = get(&svc, "/api/users").await;
+        assert_eq!(res.status(), StatusCode::NOT_FOUND);
+
+        let res = get(&svc, "/api").await;
+        assert_eq!(res.status(), StatusCode::NOT_FOUND);
+
+        let res = get(&svc, "/apiary").await;
+        assert_eq!(res.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_serve_spa_no_fallback_for_post() {
+        let svc = ServeSpa::new("../test-files");
+
+        let req = Request::builder()
+            .method(Method::POST)
+            .uri("/users/42")
+            .body(Body::empty())
+            .unwrap();
+        let res = svc.serve(Context::default(), req).await.unwrap();
+        assert_eq!(res.status(), StatusCode::METHOD_NOT_ALLOWED);
+    }
+
+    #[tokio::test]
+    async fn test_serve_spa_custom_index_file() {
+        let svc = ServeSpa::new("../test-files").with_index_file("../test-files/hello.txt");
+
+        let res = get(&svc, "/users/42").await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(res.headers()[header::CONTENT_TYPE], "text/plain");
+    }
+}
@@ -0,0 +1,597 @@
+//! Admin web service, to inspect and control a running application.
+//!
+//! The [`AdminService`] exposes the following endpoints,
+//! relative to the prefix it is mounted under:
+//!
+//! | method   | path                | description                                                         |
+//! |----------|---------------------|---------------------------------------------------------------------|
+//! | `GET`    | `/stats`            | runtime stats: connections, tasks, maintenance mode and custom stats |
+//! | `GET`    | `/connections`      | the active connections of the [`ConnectionRegistry`]                 |
+//! | `DELETE` | `/connections/{id}` | forcefully close an active connection                               |
+//! | `GET`    | `/config`           | dump the configuration of the application                           |
+//! | `GET`    | `/log-level`        | the current log level, e.g. `{ "level": "info" }`                   |
+//! | `PUT`    | `/log-level`        | change the log level, e.g. `{ "level": "rama=debug,info" }`         |
+//! | `GET`    | `/maintenance`      | whether the maintenance mode is enabled, e.g. `{ "enabled": false }` |
+//! | `PUT`    | `/maintenance`      | toggle the maintenance mode, e.g. `{ "enabled": true }`             |
+//!
+//! Endpoints of which the source is not configured respond with `404 Not Found`.
+//!
+//! All requests have to be authenticated using the bearer token
+//! the service is created with (`Authorization: Bearer <token>`),
+//! and by default are only accepted from a loopback peer address,
+//! as found in the [`SocketInfo`] of the [`Context`].
+//!
+//! # Example
+//!
+//! ```
+//! use rama_core::{Context, Service, rt::Tasks};
+//! use rama_http::layer::maintenance::MaintenanceMode;
+//! use rama_http::service::web::{Router, admin::AdminService};
+//! use rama_http::{Body, Request, StatusCode};
+//! use rama_net::stream::{SocketInfo, layer::ConnectionRegistry};
+//!
+//! # #[tokio::main]
+//! # async fn main() {
+//! let mode = MaintenanceMode::new();
+//! let admin = AdminService::new("secret")
+//!     .with_connections(ConnectionRegistry::new())
+//!     .with_tasks(Tasks::new())
+//!     .with_maintenance(mode.clone())
+//!     .with_stats("pool", || serde_json::json!({ "idle": 4 }));
+//!
+//! let app = Router::new().nest("/admin", admin);
+//!
+//! let mut ctx = Context::default();
+//! ctx.insert(SocketInfo::new(None, ([127, 0, 0, 1], 54321).into()));
+//!
+//! let req = Request::put("/admin/maintenance")
+//!     .header("authorization", "Bearer secret")
+//!     .body(Body::from(r#"{ "enabled": true }"#))
+//!     .unwrap();
+//! let res = app.serve(ctx, req).await.unwrap();
+//! assert_eq!(res.status(), StatusCode::OK);
+//! assert!(mode.is_enabled());
+//! # }
+//! ```
+//!
+//! [`ConnectionRegistry`]: rama_net::stream::layer::ConnectionRegistry
+
+use crate::layer::maintenance::MaintenanceMode;
+use crate::service::web::response::{IntoResponse, Json};
+use crate::{Method, Request, Response, StatusCode, header};
+use rama_core::error::BoxError;
+use rama_core::rt::Tasks;
+use rama_core::{Context, Service};
+use rama_http_types::BodyExtractExt;
+use rama_net::stream::SocketInfo;
+use rama_net::stream::layer::{
+    ConnectionId, ConnectionInfo, ConnectionKind, ConnectionRegistry, ConnectionState,
+};
+use serde::Deserialize;
+use serde_json::{Map, Value, json};
+use std::{convert::Infallible, fmt, sync::Arc};
+
+/// Runtime control of the log level of an application, used by an [`AdminService`].
+///
+/// Usually implemented on top of a reloadable tracing filter,
+/// e.g. the reload handle of the `tracing-subscriber` crate.
+pub trait LogLevelControl: Send + Sync + 'static {
+    /// Returns the current log level (filter directives).
+    fn log_level(&self) -> String;
+
+    /// Change the log level to the given filter directives, e.g. `info` or `rama=debug,info`.
+    fn set_log_level(&self, level: &str) -> Result<(), BoxError>;
+}
+
+type StatsFn = dyn Fn() -> Value + Send + Sync + 'static;
+
+#[derive(Clone)]
+/// Web service to inspect and control a running application,
+/// protected by a bearer token.
+///
+/// Mount it under a prefix using [`Router::nest`].
+///
+/// See the [module docs](crate::service::web::admin) for more details.
+///
+/// [`Router::nest`]: crate::service::web::Router::nest
+pub struct AdminService {
+    token: Arc<str>,
+    allow_remote: bool,
+    connections: Option<ConnectionRegistry>,
+    tasks: Option<Tasks>,
+    maintenance: Option<MaintenanceMode>,
+    log_level: Option<Arc<dyn LogLevelControl>>,
+    config: Option<Arc<Value>>,
+    stats: Arc<Vec<(String, Arc<StatsFn>)>>,
+}
+
+impl fmt::Debug for AdminService {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AdminService")
+            .field("allow_remote", &self.allow_remote)
+            .field("connections", &self.connections)
+            .field("tasks", &self.tasks)
+            .field("maintenance", &self.maintenance)
+            .field("log_level", &self.log_level.is_some())
+            .field("config", &self.config.is_some())
+            .field(
+                "stats",
+                &self
+                    .stats
+                    .iter()
+                    .map(|(name, _)| name.as_str())
+                    .collect::<Vec<_>>(),
+            )
+            .finish()
+    }
+}
+
+impl AdminService {
+    /// Create a new [`AdminService`], authenticating requests using the given bearer token.
+    #[must_use]
+    pub fn new(token: impl Into<String>) -> Self {
+        Self {
+            token: Arc::from(token.into()),
+            allow_remote: false,
+            connections: None,
+            tasks: None,
+            maintenance: None,
+            log_level: None,
+            config: None,
+            stats: Arc::new(Vec::new()),
+        }
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Accept requests from any peer, rather than only from a loopback address.
+        ///
+        /// Requests still have to be authenticated using the bearer token.
+        pub fn allow_remote(mut self, allow: bool) -> Self {
+            self.allow_remote = allow;
+            self
+        }
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Expose and allow to close the connections of the given [`ConnectionRegistry`].
+        pub fn connections(mut self, registry: ConnectionRegistry) -> Self {
+            self.connections = Some(registry);
+            self
+        }
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Expose the running tasks of the given [`Tasks`] registry.
+        pub fn tasks(mut self, tasks: Tasks) -> Self {
+            self.tasks = Some(tasks);
+            self
+        }
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Expose and allow to toggle the given [`MaintenanceMode`].
+        pub fn maintenance(mut self, mode: MaintenanceMode) -> Self {
+            self.maintenance = Some(mode);
+            self
+        }
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Expose and allow to change the log level using the given [`LogLevelControl`].
+        pub fn log_level(mut self, control: impl LogLevelControl) -> Self {
+            self.log_level = Some(Arc::new(control));
+            self
+        }
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Expose the given configuration of the application.
+        ///
+        /// Make sure to remove secrets from it, as it is served as-is.
+        pub fn config(mut self, config: Value) -> Self {
+            self.config = Some(Arc::new(config));
+            self
+        }
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Expose custom runtime stats under the given name in the `/stats` endpoint,
+        /// e.g. the state of a connection pool or rate limiter.
+        ///
+        /// The function is called for each request to the `/stats` endpoint.
+        pub fn stats(
+            mut self,
+            name: impl Into<String>,
+            stats: impl Fn() -> Value + Send + Sync + 'static,
+        ) -> Self {
+            Arc::make_mut(&mut self.stats).push((name.into(), Arc::new(stats)));
+            self
+        }
+    }
+
+    fn is_local(&self, ctx: &Context) -> bool {
+        self.allow_remote
+            || ctx
+                .get::<SocketInfo>()
+                .is_some_and(|info| info.peer_addr().ip().is_loopback())
+    }
+
+    fn is_authorized(&self, req: &Request) -> bool {
+        req.headers()
+            .get(header::AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .is_some_and(|token| constant_time_eq(token.trim().as_bytes(), self.token.as_bytes()))
+    }
+
+    fn runtime_stats(&self) -> Response {
+        let mut stats = Map::new();
+        if let Some(registry) = &self.connections {
+            let connections = registry.connections();
+            let incoming = connections
+                .iter()
+                .filter(|info| info.kind() == ConnectionKind::Incoming)
+                .count();
+            stats.insert(
+                "connections".to_owned(),
+                json!({
+                    "total": connections.len(),
+                    "incoming": incoming,
+                    "outgoing": connections.len() - incoming,
+                }),
+            );
+        }
+        if let Some(tasks) = &self.tasks {
+            let labels: Map<String, Value> = tasks
+                .labels()
+                .into_iter()
+                .map(|(label, count)| (label.to_owned(), count.into()))
+                .collect();
+            stats.insert(
+                "tasks".to_owned(),
+                json!({ "total": tasks.count(), "labels": labels }),
+            );
+        }
+        if let Some(mode) = &self.maintenance {
+            stats.insert(
+                "maintenance".to_owned(),
+                json!({ "enabled": mode.is_enabled() }),
+            );
+        }
+        for (name, stats_fn) in self.stats.iter() {
+            stats.insert(name.clone(), stats_fn());
+        }
+        Json(Value::Object(stats)).into_response()
+    }
+
+    fn list_connections(&self) -> Response {
+        let Some(registry) = &self.connections else {
+            return StatusCode::NOT_FOUND.into_response();
+        };
+        let connections: Vec<Value> = registry.connections().iter().map(connection_json).collect();
+        Json(json!({ "connections": connections })).into_response()
+    }
+
+    fn close_connection(&self, id: &str) -> Response {
+        let Some(registry) = &self.connections else {
+            return StatusCode::NOT_FOUND.into_response();
+        };
+        let Ok(id) = id.parse() else {
+            return (StatusCode::BAD_REQUEST, "invalid connection id").into_response();
+        };
+        if registry.close(ConnectionId::from_u64(id)) {
+            StatusCode::NO_CONTENT.into_response()
+        } else {
+            StatusCode::NOT_FOUND.into_response()
+        }
+    }
+
+    async fn serve_log_level(&self, req: Request) -> Response {
+        let Some(control) = &self.log_level else {
+            return StatusCode::NOT_FOUND.into_response();
+        };
+        match *req.method() {
+            Method::GET => Json(json!({ "level": control.log_level() })).into_response(),
+            Method::PUT => {
+                let update: LogLevelUpdate = match req.try_into_json().await {
+                    Ok(update) => update,
+                    Err(err) => return (StatusCode::BAD_REQUEST, err.to_string()).into_response(),
+                };
+                match control.set_log_level(&update.level) {
+                    Ok(()) => Json(json!({ "level": control.log_level() })).into_response(),
+                    Err(err) => (StatusCode::BAD_REQUEST, err.to_string()).into_response(),
+                }
+            }
+            _ => method_not_allowed("GET, PUT"),
+        }
+    }
+
+    async fn serve_maintenance(&self, req: Request) -> Response {
+        let Some(mode) = &self.maintenance else {
+            return StatusCode::NOT_FOUND.into_response();
+        };
+        match *req.method() {
+            Method::GET => {}
+            Method::PUT => match req.try_into_json::<MaintenanceUpdate>().await {
+                Ok(update) => mode.set(update.enabled),
+                Err(err) => return (StatusCode::BAD_REQUEST, err.to_string()).into_response(),
+            },
+            _ => return method_not_allowed("GET, PUT"),
+        }
+        Json(json!({ "enabled": mode.is_enabled() })).into_response()
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct LogLevelUpdate {
+    level: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct MaintenanceUpdate {
+    enabled: bool,
+}
+
+fn connection_json(info: &ConnectionInfo) -> Value {
+    let stats = info.stats();
+    json!({
+        "id": info.id().as_u64(),
+        "kind": match info.kind() {
+            ConnectionKind::Incoming => "incoming",
+            ConnectionKind::Outgoing => "outgoing",
+        },
+        "peer_addr": info.peer_addr().map(|addr| addr.to_string()),
+        "state": match info.state() {
+            ConnectionState::Open => "open",
+            ConnectionState::WriteShutdown => "write_shutdown",
+            ConnectionState::Closed => "closed",
+        },
+        "bytes_read": stats.bytes_read(),
+        "bytes_written": stats.bytes_written(),
+        "age_secs": stats.elapsed().as_secs_f64(),
+        "idle_secs": stats.idle_duration().as_secs_f64(),
+    })
+}
+
+fn method_not_allowed(allow: &'static str) -> Response {
+    (StatusCode::METHOD_NOT_ALLOWED, [(header::ALLOW, allow)]).into_response()
+}
+
+/// Compare two byte slices in constant time (for equal lengths),
+/// such that the token cannot be guessed by timing the responses.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+impl Service<Request> for AdminService {
+    type Response = Response;
+    type Error = Infallible;
+
+    async fn serve(&self, ctx: Context, req: Request) -> Result<Self::Response, Self::Error> {
+        if !self.is_local(&ctx) {
+            return Ok(StatusCode::FORBIDDEN.into_response());
+        }
+        if !self.is_authorized(&req) {
+            return Ok((
+                StatusCode::UNAUTHORIZED,
+                [(header::WWW_AUTHENTICATE, "Bearer")],
+            )
+                .into_response());
+        }
+
+        let path = req.uri().path().trim_end_matches('/').to_owned();
+        if let Some(id) = path.strip_prefix("/connections/") {
+            return Ok(match *req.method() {
+                Method::DELETE => self.close_connection(id),
+                _ => method_not_allowed("DELETE"),
+            });
+        }
+        Ok(match path.as_str() {
+            "/stats" | "/connections" | "/config" if req.method() != Method::GET => {
+                method_not_allowed("GET")
+            }
+            "/stats" => self.runtime_stats(),
+            "/connections" => self.list_connections(),
+            "/config" => match &self.config {
+                Some(config) => Json(config.as_ref().clone()).into_response(),
+                None => StatusCode::NOT_FOUND.into_response(),
+            },
+            "/log-level" => self.serve_log_level(req).await,
+            "/maintenance" => self.serve_maintenance(req).await,
+            _ => StatusCode::NOT_FOUND.into_response(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Body;
+    use parking_lot::Mutex;
+    use rama_core::{Layer, service::service_fn};
+    use rama_net::stream::layer::{IncomingRegistryLayer, RegisteredStream};
+    use std::time::Duration;
+    use tokio::io::{AsyncReadExt, DuplexStream};
+
+    #[derive(Debug, Default)]
+    struct TestLogLevel(Mutex<String>);
+
+    impl LogLevelControl for Arc<TestLogLevel> {
+        fn log_level(&self) -> String {
+            self.0.lock().clone()
+        }
+
+        fn set_log_level(&self, level: &str) -> Result<(), BoxError> {
+            if level.is_empty() {
+                return Err("empty log level".into());
+            }
+            *self.0.lock() = level.to_owned();
+            Ok(())
+        }
+    }
+
+    fn local_ctx() -> Context {
+        let mut ctx = Context::default();
+        ctx.insert(SocketInfo::new(None, ([127, 0, 0, 1], 54321).into()));
+        ctx
+    }
+
+    async fn request(
+        admin: &AdminService,
+        ctx: Context,
+        method: Method,
+        path: &str,
+        body: &str,
+    ) -> (StatusCode, Value) {
+        let req = Request::builder()
+            .method(method)
+            .uri(path)
+            .header(header::AUTHORIZATION, "Bearer secret")
+            .body(Body::from(body.to_owned()))
+            .unwrap();
+        let res = admin.serve(ctx, req).await.unwrap();
+        let status = res.status();
+        let body = res.try_into_string().await.unwrap();
+        (status, serde_json::from_str(&body).unwrap_or(Value::Null))
+    }
+
+    #[tokio::test]
+    async fn test_admin_auth() {
+        let admin = AdminService::new("secret");
+
+        let req = Request::get("/stats")
+            .header(header::AUTHORIZATION, "Bearer wrong")
+            .body(Body::empty())
+            .unwrap();
+        let res = admin.serve(local_ctx(), req).await.unwrap();
+        assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+        assert_eq!(res.headers()[header::WWW_AUTHENTICATE], "Bearer");
+
+        let (status, _) = request(&admin, local_ctx(), Method::GET, "/stats", "").await;
+        assert_eq!(status, StatusCode::OK);
+
+        let mut remote_ctx = Context::default();
+        remote_ctx.insert(SocketInfo::new(None, ([1, 2, 3, 4], 54321).into()));
+        let (status, _) = request(&admin, remote_ctx.clone(), Method::GET, "/stats", "").await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+        let (status, _) = request(&admin, Context::default(), Method::GET, "/stats", "").await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+
+        let admin = admin.with_allow_remote(true);
+        let (status, _) = request(&admin, remote_ctx, Method::GET, "/stats", "").await;
+        assert_eq!(status, StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_admin_stats_and_controls() {
+        let mode = MaintenanceMode::new();
+        let log_level = Arc::new(TestLogLevel(Mutex::new("info".to_owned())));
+        let admin = AdminService::new("secret")
+            .with_maintenance(mode.clone())
+            .with_log_level(log_level.clone())
+            .with_config(json!({ "listeners": [{ "bind": "127.0.0.1:8080" }] }))
+            .with_stats("pool", || json!({ "idle": 2 }));
+
+        let (status, body) = request(&admin, local_ctx(), Method::GET, "/stats", "").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["maintenance"]["enabled"], false);
+        assert_eq!(body["pool"]["idle"], 2);
+        assert!(body.get("connections").is_none());
+
+        let (status, body) = request(&admin, local_ctx(), Method::GET, "/config", "").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["listeners"][0]["bind"], "127.0.0.1:8080");
+
+        let (status, body) = request(
+            &admin,
+            local_ctx(),
+            Method::PUT,
+            "/maintenance",
+            r#"{ "enabled": true }"#,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["enabled"], true);
+        assert!(mode.is_enabled());
+
+        let (status, body) = request(
+            &admin,
+            local_ctx(),
+            Method::PUT,
+            "/log-level",
+            r#"{ "level": "rama=debug,info" }"#,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["level"], "rama=debug,info");
+        assert_eq!(log_level.log_level(), "rama=debug,info");
+
+        let (status, _) = request(
+            &admin,
+            local_ctx(),
+            Method::PUT,
+            "/log-level",
+            r#"{ "level": "" }"#,
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+
+        let (status, _) = request(&admin, local_ctx(), Method::POST, "/stats", "").await;
+        assert_eq!(status, StatusCode::METHOD_NOT_ALLOWED);
+        let (status, _) = request(&admin, local_ctx(), Method::GET, "/connections", "").await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_admin_connections() {
+        let registry = ConnectionRegistry::new();
+        let admin = AdminService::new("secret").with_connections(registry.clone());
+
+        let service = IncomingRegistryLayer::new(registry.clone()).into_layer(service_fn(
+            async |mut stream: RegisteredStream<DuplexStream>| {
+                let mut buf = [0; 8];
+                // completes once the connection is closed
+                let _ = stream.read(&mut buf).await;
+                Ok::<_, Infallible>(())
+            },
+        ));
+        let (_client, server) = tokio::io::duplex(64);
+        let handle = tokio::spawn(async move { service.serve(local_ctx(), server).await });
+
+        tokio::time::timeout(Duration::from_secs(1), async {
+            while registry.is_empty() {
+                tokio::task::yield_now().await;
+            }
+        })
+        .await
+        .unwrap();
+
+        let (status, body) = request(&admin, local_ctx(), Method::GET, "/connections", "").await;
+        assert_eq!(status, StatusCode::OK);
+        let connection = &body["connections"][0];
+        assert_eq!(connection["kind"], "incoming");
+        assert_eq!(connection["state"], "open");
+        assert_eq!(connection["peer_addr"], "127.0.0.1:54321");
+        let id = connection["id"].as_u64().unwrap();
+
+        let (status, body) = request(&admin, local_ctx(), Method::GET, "/stats", "").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["connections"]["total"], 1);
+        assert_eq!(body["connections"]["incoming"], 1);
+
+        let path = format!("/connections/{id}");
+        let (status, _) = request(&admin, local_ctx(), Method::DELETE, &path, "").await;
+        assert_eq!(status, StatusCode::NO_CONTENT);
+        tokio::time::timeout(Duration::from_secs(1), handle)
+            .await
+            .unwrap()
+            .unwrap()
+            .unwrap();
+
+        let (status, _) = request(&admin, local_ctx(), Method::DELETE, &path, "").await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        let (status, _) =
+            request(&admin, local_ctx(), Method::DELETE, "/connections/abc", "").await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+}
@@ -3,6 +3,7 @@ use rama_core::bytes::Bytes;
 use super::BytesRejection;
 use crate::dep::http_body_util::BodyExt;
 use crate::service::web::extract::FromRequest;
+use crate::service::web::openapi::{Operation, ParameterLocation, schema_for};
 use crate::utils::macros::{composite_http_rejection, define_http_rejection};
 use crate::{Method, Request};
 
@@ -59,7 +60,7 @@ where
             Ok(bytes.to_bytes())
         }
 
-        if req.method() == Method::GET {
+        if req.method() == Method::GET || req.method() == Method::HEAD {
             let query = req.uri().query().unwrap_or_default();
             let value = match serde_html_form::from_bytes(query.as_bytes()) {
                 Ok(value) => value,
@@ -74,6 +75,19 @@ where
             }))
         }
     }
+
+    fn describe_openapi(operation: &mut Operation) {
+        let schema = schema_for::<T>();
+        if operation.method() == Method::GET || operation.method() == Method::HEAD {
+            operation.add_object_parameters(ParameterLocation::Query, &schema, true);
+        } else {
+            operation.set_request_body(
+                mime::APPLICATION_WWW_FORM_URLENCODED.as_ref(),
+                schema,
+                true,
+            );
+        }
+    }
 }
 
 #[cfg(test)]
@@ -189,4 +203,24 @@ mod test {
         let resp = service.serve(Context::default(), req).await.unwrap();
         assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
     }
+
+    #[tokio::test]
+    async fn test_form_head() {
+        #[derive(Debug, serde::Deserialize)]
+        struct Input {
+            name: String,
+        }
+
+        let service = WebService::default().head("/", async |Form(body): Form<Input>| {
+            assert_eq!(body.name, "Devan");
+        });
+
+        let req = Request::builder()
+            .uri("/?name=Devan")
+            .method(Method::HEAD)
+            .body(Body::empty())
+            .unwrap();
+        let resp = service.serve(Context::default(), req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+    }
 }
@@ -1,14 +1,31 @@
 use rama_core::bytes::Bytes;
+use rama_core::error::OpaqueError;
 use rama_http_types::{HeaderMap, header};
 
-use super::BytesRejection;
+use super::{BytesRejection, is_length_limit_error};
 use crate::Request;
-use crate::dep::http_body_util::BodyExt;
+use crate::dep::http_body_util::{BodyExt, Limited};
+use crate::layer::validate_request::DEFAULT_MAX_JSON_BODY_SIZE;
 use crate::service::web::extract::{FromRequest, OptionalFromRequest};
-use crate::utils::macros::{composite_http_rejection, define_http_rejection};
+use crate::service::web::openapi::{Operation, schema_for};
+use crate::service::web::response::{IntoResponse, ProblemDetails};
+use crate::utils::macros::{define_http_rejection, log_http_rejection};
 
 pub use crate::service::web::endpoint::response::Json;
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// The maximum size of a request body extracted by the [`Json`] extractor.
+///
+/// Insert it in the [`Request`] extensions (e.g. from a middleware) to override
+/// the default limit of [`DEFAULT_MAX_JSON_BODY_SIZE`] bytes for that request.
+pub struct JsonBodyLimit(pub usize);
+
+impl Default for JsonBodyLimit {
+    fn default() -> Self {
+        Self(DEFAULT_MAX_JSON_BODY_SIZE)
+    }
+}
+
 define_http_rejection! {
     #[status = UNSUPPORTED_MEDIA_TYPE]
     #[body = "Json requests must have `Content-Type: application/json`"]
@@ -18,6 +35,14 @@ define_http_rejection! {
     pub struct InvalidJsonContentType;
 }
 
+define_http_rejection! {
+    #[status = PAYLOAD_TOO_LARGE]
+    #[body = "Json payload is too large"]
+    /// Rejection type used if the request body of the [`Json`] extractor
+    /// exceeds its [`JsonBodyLimit`] or the limit of a body limit middleware.
+    pub struct JsonPayloadTooLarge(Error);
+}
+
 define_http_rejection! {
     #[status = BAD_REQUEST]
     #[body = "Failed to deserialize json payload"]
@@ -26,15 +51,106 @@ define_http_rejection! {
     pub struct FailedToDeserializeJson(Error);
 }
 
-composite_http_rejection! {
-    /// Rejection used for [`Json`]
-    ///
-    /// Contains one variant for each way the [`Json`] extractor
-    /// can fail.
-    pub enum JsonRejection {
-        InvalidJsonContentType,
-        FailedToDeserializeJson,
-        BytesRejection,
+#[derive(Debug)]
+#[non_exhaustive]
+/// Rejection used for [`Json`]
+///
+/// Contains one variant for each way the [`Json`] extractor
+/// can fail. Unlike the rejections it wraps, it is turned into
+/// an `application/problem+json` response, using [`ProblemDetails`].
+pub enum JsonRejection {
+    #[allow(missing_docs)]
+    InvalidJsonContentType(InvalidJsonContentType),
+    #[allow(missing_docs)]
+    JsonPayloadTooLarge(JsonPayloadTooLarge),
+    #[allow(missing_docs)]
+    FailedToDeserializeJson(FailedToDeserializeJson),
+    #[allow(missing_docs)]
+    BytesRejection(BytesRejection),
+}
+
+impl JsonRejection {
+    /// Get the response body text used for this rejection.
+    #[must_use]
+    pub fn body_text(&self) -> String {
+        match self {
+            Self::InvalidJsonContentType(inner) => inner.body_text(),
+            Self::JsonPayloadTooLarge(inner) => inner.body_text(),
+            Self::FailedToDeserializeJson(inner) => inner.body_text(),
+            Self::BytesRejection(inner) => inner.body_text(),
+        }
+    }
+
+    /// Get the status code used for this rejection.
+    #[must_use]
+    pub fn status(&self) -> crate::StatusCode {
+        match self {
+            Self::InvalidJsonContentType(inner) => inner.status(),
+            Self::JsonPayloadTooLarge(inner) => inner.status(),
+            Self::FailedToDeserializeJson(inner) => inner.status(),
+            Self::BytesRejection(inner) => inner.status(),
+        }
+    }
+}
+
+impl IntoResponse for JsonRejection {
+    fn into_response(self) -> crate::Response {
+        let status = self.status();
+        let detail = self.body_text();
+        log_http_rejection!(
+            rejection_type = JsonRejection,
+            body_text = detail,
+            status = status,
+        );
+        ProblemDetails::new(status)
+            .with_detail(detail)
+            .into_response()
+    }
+}
+
+impl From<InvalidJsonContentType> for JsonRejection {
+    fn from(inner: InvalidJsonContentType) -> Self {
+        Self::InvalidJsonContentType(inner)
+    }
+}
+
+impl From<JsonPayloadTooLarge> for JsonRejection {
+    fn from(inner: JsonPayloadTooLarge) -> Self {
+        Self::JsonPayloadTooLarge(inner)
+    }
+}
+
+impl From<FailedToDeserializeJson> for JsonRejection {
+    fn from(inner: FailedToDeserializeJson) -> Self {
+        Self::FailedToDeserializeJson(inner)
+    }
+}
+
+impl From<BytesRejection> for JsonRejection {
+    fn from(inner: BytesRejection) -> Self {
+        Self::BytesRejection(inner)
+    }
+}
+
+impl std::fmt::Display for JsonRejection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::InvalidJsonContentType(inner) => write!(f, "{inner}"),
+            Self::JsonPayloadTooLarge(inner) => write!(f, "{inner}"),
+            Self::FailedToDeserializeJson(inner) => write!(f, "{inner}"),
+            Self::BytesRejection(inner) => write!(f, "{inner}"),
+        }
+    }
+}
+
+impl std::error::Error for JsonRejection {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::InvalidJsonContentType(inner) => Some(inner),
+            Self::JsonPayloadTooLarge(inner) => Some(inner),
+            Self::FailedToDeserializeJson(inner) => Some(inner),
+            Self::BytesRejection(inner) => Some(inner),
+        }
     }
 }
 
@@ -51,11 +167,34 @@ where
                 return Err(InvalidJsonContentType.into());
             }
 
-            let body = req.into_body();
+            let JsonBodyLimit(limit) = req
+                .extensions()
+                .get::<JsonBodyLimit>()
+                .copied()
+                .unwrap_or_default();
+            let too_large = || {
+                JsonPayloadTooLarge::from_display(format!(
+                    "request body exceeds the limit of {limit} bytes"
+                ))
+            };
+
+            // reject early when the announced length already exceeds the limit
+            if req
+                .headers()
+                .get(header::CONTENT_LENGTH)
+                .and_then(|value| value.to_str().ok())
+                .and_then(|value| value.parse::<u64>().ok())
+                .is_some_and(|length| length > limit as u64)
+            {
+                return Err(too_large().into());
+            }
+
+            let body = Limited::new(req.into_body(), limit);
 
             match body.collect().await {
                 Ok(c) => Ok(c.to_bytes()),
-                Err(err) => Err(BytesRejection::from_err(err).into()),
+                Err(err) if is_length_limit_error(err.as_ref()) => Err(too_large().into()),
+                Err(err) => Err(BytesRejection(OpaqueError::from_boxed(err)).into()),
             }
         }
 
@@ -65,6 +204,10 @@ where
             Err(err) => Err(FailedToDeserializeJson::from_err(err).into()),
         }
     }
+
+    fn describe_openapi(operation: &mut Operation) {
+        operation.set_request_body(mime::APPLICATION_JSON.as_ref(), schema_for::<T>(), true);
+    }
 }
 
 impl<T> OptionalFromRequest for Json<T>
@@ -81,6 +224,10 @@ where
             Ok(None)
         }
     }
+
+    fn describe_openapi(operation: &mut Operation) {
+        operation.set_request_body(mime::APPLICATION_JSON.as_ref(), schema_for::<T>(), false);
+    }
 }
 
 fn json_content_type(headers: &HeaderMap) -> bool {
@@ -100,6 +247,7 @@ mod test {
     use crate::StatusCode;
     use crate::service::web::WebService;
     use rama_core::{Context, Service};
+    use rama_http_types::BodyExtractExt;
 
     #[tokio::test]
     async fn test_json() {
@@ -146,6 +294,10 @@ mod test {
             .unwrap();
         let resp = service.serve(Context::default(), req).await.unwrap();
         assert_eq!(resp.status(), StatusCode::UNSUPPORTED_MEDIA_TYPE);
+        assert_eq!(
+            resp.headers()[header::CONTENT_TYPE],
+            "application/problem+json"
+        );
     }
 
     #[tokio::test]
@@ -170,4 +322,33 @@ mod test {
         let resp = service.serve(Context::default(), req).await.unwrap();
         assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
     }
+
+    #[tokio::test]
+    async fn test_json_payload_too_large() {
+        #[derive(Debug, serde::Deserialize)]
+        struct Input {
+            _name: String,
+        }
+
+        let service = WebService::default().post("/", async |Json(_): Json<Input>| StatusCode::OK);
+
+        let req = rama_http_types::Request::builder()
+            .method(rama_http_types::Method::POST)
+            .header(rama_http_types::header::CONTENT_TYPE, "application/json")
+            .extension(JsonBodyLimit(8))
+            .body(r#"{"_name": "glen"}"#.into())
+            .unwrap();
+        let resp = service.serve(Context::default(), req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::PAYLOAD_TOO_LARGE);
+        assert_eq!(
+            resp.headers()[header::CONTENT_TYPE],
+            "application/problem+json"
+        );
+        let body: serde_json::Value = resp.try_into_json().await.unwrap();
+        assert_eq!(body["status"], 413);
+        assert_eq!(
+            body["detail"],
+            "Json payload is too large: request body exceeds the limit of 8 bytes"
+        );
+    }
 }
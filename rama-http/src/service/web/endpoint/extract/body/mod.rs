@@ -1,6 +1,7 @@
 //! module in function of extractors for `Request` bodies
 
 use super::FromRequest;
+use crate::dep::http_body_util::LengthLimitError;
 use rama_http_types as http;
 use rama_utils::macros::impl_deref;
 use std::convert::Infallible;
@@ -25,6 +26,10 @@ mod form;
 #[doc(inline)]
 pub use form::*;
 
+mod multipart;
+#[doc(inline)]
+pub use multipart::*;
+
 /// Extractor to get the response body.
 #[derive(Debug)]
 pub struct Body(pub http::Body);
@@ -39,6 +44,19 @@ impl FromRequest for Body {
     }
 }
 
+/// Returns `true` in case the error, or one of its sources,
+/// is caused by a body exceeding its limit.
+fn is_length_limit_error(err: &(dyn std::error::Error + 'static)) -> bool {
+    let mut source = Some(err);
+    while let Some(err) = source {
+        if err.is::<LengthLimitError>() {
+            return true;
+        }
+        source = err.source();
+    }
+    false
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -0,0 +1,770 @@
+use super::is_length_limit_error;
+use crate::layer::validate_request::DEFAULT_MAX_JSON_BODY_SIZE;
+use crate::service::web::extract::FromRequest;
+use crate::service::web::response::IntoResponse;
+use crate::utils::macros::{composite_http_rejection, define_http_rejection, log_http_rejection};
+use crate::{
+    Body, BodyDataStream, HeaderMap, HeaderName, HeaderValue, Request, Response, StatusCode, header,
+};
+use mime::Mime;
+use rama_core::bytes::{Buf, Bytes, BytesMut};
+use rama_core::error::BoxError;
+use rama_core::futures::Stream;
+use std::{
+    fmt,
+    future::poll_fn,
+    io,
+    path::{Path, PathBuf},
+    pin::Pin,
+    task::{Context, Poll, ready},
+};
+use tokio::io::AsyncWriteExt;
+
+/// The maximum size of the headers of a single part.
+const MAX_PART_HEADERS_SIZE: usize = 8 * 1024;
+
+/// Extractor to stream the parts of a `multipart/form-data` request body.
+///
+/// Parts are read one after the other using [`Multipart::next_part`],
+/// each of which is itself a [`Stream`] of its data, such that (large) uploads
+/// can be processed without buffering the request body in memory.
+/// Unread data of a part is skipped when moving to the next part.
+///
+/// By default the data of a single part is limited to [`Multipart::DEFAULT_MAX_PART_SIZE`] bytes,
+/// and the body as a whole to [`Multipart::DEFAULT_MAX_TOTAL_SIZE`] bytes,
+/// failing with a `413 Payload Too Large` [`MultipartError`] once exceeded.
+/// Use [`Multipart::set_max_part_size`] and [`Multipart::set_max_total_size`]
+/// to change (or remove) these limits, e.g. to accept larger uploads.
+///
+/// # Example
+///
+/// ```
+/// use rama_http::service::web::extract::body::{Multipart, MultipartError};
+///
+/// async fn upload(mut multipart: Multipart) -> Result<String, MultipartError> {
+///     multipart
+///         .set_max_part_size(Some(16 * 1024 * 1024))
+///         .set_max_total_size(Some(64 * 1024 * 1024));
+///
+///     let mut report = String::new();
+///     while let Some(part) = multipart.next_part().await? {
+///         let name = part.name().unwrap_or_default().to_owned();
+///         match part.file_name().map(ToOwned::to_owned) {
+///             Some(file_name) => {
+///                 let file = part.spool_to_temp_file().await?;
+///                 report.push_str(&format!("{name}: {file_name} ({} bytes)\n", file.len()));
+///             }
+///             None => {
+///                 let value = part.text().await?;
+///                 report.push_str(&format!("{name}: {value}\n"));
+///             }
+///         }
+///     }
+///     Ok(report)
+/// }
+/// ```
+pub struct Multipart {
+    stream: BodyDataStream,
+    buffer: BytesMut,
+    delimiter: Bytes,
+    state: State,
+    eof: bool,
+    max_part_size: Option<u64>,
+    max_total_size: Option<u64>,
+    part_size: u64,
+    total_size: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    Preamble,
+    Delimiter,
+    Headers,
+    Body,
+    Done,
+}
+
+impl fmt::Debug for Multipart {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Multipart")
+            .field("delimiter", &self.delimiter)
+            .field("state", &self.state)
+            .field("max_part_size", &self.max_part_size)
+            .field("max_total_size", &self.max_total_size)
+            .finish()
+    }
+}
+
+define_http_rejection! {
+    #[status = UNSUPPORTED_MEDIA_TYPE]
+    #[body = "Multipart requests must have `Content-Type: multipart/form-data`"]
+    /// Rejection type for [`Multipart`]
+    /// used if the `Content-Type` header is missing
+    /// or its value is not `multipart/form-data`.
+    pub struct InvalidMultipartContentType;
+}
+
+define_http_rejection! {
+    #[status = BAD_REQUEST]
+    #[body = "Multipart requests must define a valid boundary in their `Content-Type`"]
+    /// Rejection type for [`Multipart`]
+    /// used if the `Content-Type` header has no (valid) boundary parameter.
+    pub struct InvalidMultipartBoundary;
+}
+
+composite_http_rejection! {
+    /// Rejection used for [`Multipart`]
+    ///
+    /// Contains one variant for each way the [`Multipart`] extractor
+    /// can fail.
+    pub enum MultipartRejection {
+        InvalidMultipartContentType,
+        InvalidMultipartBoundary,
+    }
+}
+
+impl FromRequest for Multipart {
+    type Rejection = MultipartRejection;
+
+    async fn from_request(req: Request) -> Result<Self, Self::Rejection> {
+        let content_type = req
+            .headers()
+            .get(header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.parse::<Mime>().ok())
+            .filter(|content_type| {
+                content_type.type_() == mime::MULTIPART && content_type.subtype() == mime::FORM_DATA
+            })
+            .ok_or(InvalidMultipartContentType)?;
+
+        // RFC 2046: boundaries are at most 70 characters long
+        let boundary = content_type
+            .get_param(mime::BOUNDARY)
+            .filter(|boundary| (1..=70).contains(&boundary.as_str().len()))
+            .ok_or(InvalidMultipartBoundary)?;
+
+        Ok(Self::new(req.into_body(), boundary.as_str()))
+    }
+}
+
+impl Multipart {
+    /// The default maximum size of the data of a single part, in bytes.
+    pub const DEFAULT_MAX_PART_SIZE: u64 = 1024 * 1024;
+
+    /// The default maximum size of the entire multipart body, in bytes,
+    /// aligned with the [`DEFAULT_MAX_JSON_BODY_SIZE`].
+    ///
+    /// [`DEFAULT_MAX_JSON_BODY_SIZE`]: crate::layer::validate_request::DEFAULT_MAX_JSON_BODY_SIZE
+    pub const DEFAULT_MAX_TOTAL_SIZE: u64 = DEFAULT_MAX_JSON_BODY_SIZE as u64;
+
+    /// Create a new [`Multipart`] reading the parts of the given body,
+    /// which are separated by the given boundary.
+    pub fn new(body: Body, boundary: &str) -> Self {
+        let mut delimiter = BytesMut::with_capacity(boundary.len() + 4);
+        delimiter.extend_from_slice(b"\r\n--");
+        delimiter.extend_from_slice(boundary.as_bytes());
+
+        Self {
+            stream: body.into_data_stream(),
+            // the first delimiter is not preceded by a line break,
+            // start with one such that it is found like all others
+            buffer: BytesMut::from(&b"\r\n"[..]),
+            delimiter: delimiter.freeze(),
+            state: State::Preamble,
+            eof: false,
+            max_part_size: Some(Self::DEFAULT_MAX_PART_SIZE),
+            max_total_size: Some(Self::DEFAULT_MAX_TOTAL_SIZE),
+            part_size: 0,
+            total_size: 0,
+        }
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Set the maximum size of the data of a single part, in bytes.
+        ///
+        /// Defaults to [`Self::DEFAULT_MAX_PART_SIZE`], use `None` to remove the limit.
+        pub fn max_part_size(mut self, size: Option<u64>) -> Self {
+            self.max_part_size = size;
+            self
+        }
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Set the maximum size of the entire multipart body, in bytes.
+        ///
+        /// Defaults to [`Self::DEFAULT_MAX_TOTAL_SIZE`], use `None` to remove the limit.
+        pub fn max_total_size(mut self, size: Option<u64>) -> Self {
+            self.max_total_size = size;
+            self
+        }
+    }
+
+    /// Returns the next [`Part`], or `None` once all parts were read.
+    ///
+    /// The unread data of the previous part, if any, is skipped.
+    pub async fn next_part(&mut self) -> Result<Option<Part<'_>>, MultipartError> {
+        let Some(headers) = poll_fn(|cx| self.poll_next_part(cx)).await? else {
+            return Ok(None);
+        };
+
+        let (name, file_name) = headers
+            .get(header::CONTENT_DISPOSITION)
+            .and_then(|value| value.to_str().ok())
+            .map(|value| {
+                (
+                    disposition_param(value, "name"),
+                    disposition_param(value, "filename"),
+                )
+            })
+            .unwrap_or_default();
+        let content_type = headers
+            .get(header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.parse().ok());
+
+        Ok(Some(Part {
+            multipart: self,
+            headers,
+            name,
+            file_name,
+            content_type,
+        }))
+    }
+
+    fn poll_next_part(
+        &mut self,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<Option<HeaderMap>, MultipartError>> {
+        loop {
+            match self.state {
+                State::Body => {
+                    while ready!(self.poll_part_chunk(cx))?.is_some() {}
+                    continue;
+                }
+                State::Preamble => {
+                    if let Some(index) = memchr::memmem::find(&self.buffer, &self.delimiter) {
+                        self.buffer.advance(index + self.delimiter.len());
+                        self.state = State::Delimiter;
+                        continue;
+                    }
+                    // keep what could be the start of the delimiter
+                    let keep = self.delimiter.len() - 1;
+                    if self.buffer.len() > keep {
+                        self.buffer.advance(self.buffer.len() - keep);
+                    }
+                }
+                State::Delimiter => {
+                    if self.buffer.len() >= 2 {
+                        match &self.buffer[..2] {
+                            b"--" => {
+                                // the epilogue is ignored
+                                self.buffer.clear();
+                                self.state = State::Done;
+                            }
+                            b"\r\n" => {
+                                self.buffer.advance(2);
+                                self.state = State::Headers;
+                            }
+                            _ => return Poll::Ready(Err(ErrorKind::InvalidDelimiter.into())),
+                        }
+                        continue;
+                    }
+                }
+                State::Headers => {
+                    let headers_end = if self.buffer.starts_with(b"\r\n") {
+                        Some((0, 2))
+                    } else {
+                        memchr::memmem::find(&self.buffer, b"\r\n\r\n").map(|index| (index, 4))
+                    };
+                    if let Some((index, terminator)) = headers_end {
+                        let headers = parse_headers(&self.buffer[..index])?;
+                        self.buffer.advance(index + terminator);
+                        self.state = State::Body;
+                        self.part_size = 0;
+                        return Poll::Ready(Ok(Some(headers)));
+                    }
+                    if self.buffer.len() > MAX_PART_HEADERS_SIZE {
+                        return Poll::Ready(Err(ErrorKind::HeadersTooLarge.into()));
+                    }
+                }
+                State::Done => return Poll::Ready(Ok(None)),
+            }
+
+            if self.eof {
+                return Poll::Ready(Err(ErrorKind::Incomplete.into()));
+            }
+            ready!(self.poll_fill(cx))?;
+        }
+    }
+
+    fn poll_part_chunk(
+        &mut self,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<Option<Bytes>, MultipartError>> {
+        if self.state != State::Body {
+            return Poll::Ready(Ok(None));
+        }
+
+        loop {
+            if let Some(index) = memchr::memmem::find(&self.buffer, &self.delimiter) {
+                if index == 0 {
+                    self.buffer.advance(self.delimiter.len());
+                    self.state = State::Delimiter;
+                    return Poll::Ready(Ok(None));
+                }
+                return Poll::Ready(self.take_part_chunk(index).map(Some));
+            }
+
+            // all but what could be the start of the delimiter is part data
+            let available = self.buffer.len().saturating_sub(self.delimiter.len() - 1);
+            if available > 0 {
+                return Poll::Ready(self.take_part_chunk(available).map(Some));
+            }
+
+            if self.eof {
+                return Poll::Ready(Err(ErrorKind::Incomplete.into()));
+            }
+            ready!(self.poll_fill(cx))?;
+        }
+    }
+
+    fn take_part_chunk(&mut self, len: usize) -> Result<Bytes, MultipartError> {
+        self.part_size += len as u64;
+        if let Some(limit) = self.max_part_size
+            && self.part_size > limit
+        {
+            return Err(ErrorKind::PartTooLarge(limit).into());
+        }
+        Ok(self.buffer.split_to(len).freeze())
+    }
+
+    fn poll_fill(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), MultipartError>> {
+        match ready!(Pin::new(&mut self.stream).poll_next(cx)) {
+            Some(Ok(chunk)) => {
+                self.total_size += chunk.len() as u64;
+                if let Some(limit) = self.max_total_size
+                    && self.total_size > limit
+                {
+                    return Poll::Ready(Err(ErrorKind::TotalTooLarge(limit).into()));
+                }
+                self.buffer.extend_from_slice(&chunk);
+            }
+            Some(Err(err)) => return Poll::Ready(Err(ErrorKind::Body(err).into())),
+            None => self.eof = true,
+        }
+        Poll::Ready(Ok(()))
+    }
+}
+
+/// A single part of a [`Multipart`] body.
+///
+/// The data of the part can be read in chunks, using [`Part::chunk`] or as a [`Stream`],
+/// or all at once using [`Part::bytes`] and [`Part::text`]. It can also be spooled
+/// to a file, using [`Part::spool_to_file`] or [`Part::spool_to_temp_file`].
+pub struct Part<'a> {
+    multipart: &'a mut Multipart,
+    headers: HeaderMap,
+    name: Option<String>,
+    file_name: Option<String>,
+    content_type: Option<Mime>,
+}
+
+impl fmt::Debug for Part<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Part")
+            .field("headers", &self.headers)
+            .field("name", &self.name)
+            .field("file_name", &self.file_name)
+            .field("content_type", &self.content_type)
+            .finish()
+    }
+}
+
+impl Part<'_> {
+    /// Returns the name of the form field of this part,
+    /// as found in its `Content-Disposition` header.
+    #[must_use]
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    /// Returns the file name of this part,
+    /// as found in its `Content-Disposition` header.
+    #[must_use]
+    pub fn file_name(&self) -> Option<&str> {
+        self.file_name.as_deref()
+    }
+
+    /// Returns the parsed `Content-Type` header of this part.
+    #[must_use]
+    pub fn content_type(&self) -> Option<&Mime> {
+        self.content_type.as_ref()
+    }
+
+    /// Returns all headers of this part.
+    #[must_use]
+    pub fn headers(&self) -> &HeaderMap {
+        &self.headers
+    }
+
+    /// Returns the next chunk of data of this part, or `None` once all data was read.
+    pub async fn chunk(&mut self) -> Result<Option<Bytes>, MultipartError> {
+        poll_fn(|cx| self.multipart.poll_part_chunk(cx)).await
+    }
+
+    /// Read all data of this part, buffered in memory.
+    pub async fn bytes(mut self) -> Result<Bytes, MultipartError> {
+        let mut buffer = BytesMut::new();
+        while let Some(chunk) = self.chunk().await? {
+            buffer.extend_from_slice(&chunk);
+        }
+        Ok(buffer.freeze())
+    }
+
+    /// Read all data of this part, buffered in memory, as UTF-8 text.
+    pub async fn text(self) -> Result<String, MultipartError> {
+        let bytes = self.bytes().await?;
+        String::from_utf8(bytes.into()).map_err(|err| ErrorKind::InvalidUtf8(err).into())
+    }
+
+    /// Write all data of this part to the file at the given path,
+    /// which is created or truncated, returning the amount of bytes written.
+    pub async fn spool_to_file(mut self, path: impl AsRef<Path>) -> Result<u64, MultipartError> {
+        let mut file = tokio::fs::File::create(path).await.map_err(ErrorKind::Io)?;
+        let mut written = 0;
+        while let Some(chunk) = self.chunk().await? {
+            file.write_all(&chunk).await.map_err(ErrorKind::Io)?;
+            written += chunk.len() as u64;
+        }
+        file.flush().await.map_err(ErrorKind::Io)?;
+        Ok(written)
+    }
+
+    /// Write all data of this part to a new file in the temporary directory
+    /// of the system, which is removed again once the returned [`SpooledFile`] is dropped.
+    pub async fn spool_to_temp_file(self) -> Result<SpooledFile, MultipartError> {
+        // created upfront such that the file is also removed on failure
+        let mut file = SpooledFile {
+            path: std::env::temp_dir().join(format!("rama-multipart-{}", uuid::Uuid::new_v4())),
+            len: 0,
+            keep: false,
+        };
+        file.len = self.spool_to_file(&file.path).await?;
+        Ok(file)
+    }
+}
+
+impl Stream for Part<'_> {
+    type Item = Result<Bytes, MultipartError>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.get_mut()
+            .multipart
+            .poll_part_chunk(cx)
+            .map(Result::transpose)
+    }
+}
+
+#[derive(Debug)]
+/// A [`Part`] spooled to a temporary file using [`Part::spool_to_temp_file`].
+///
+/// The file is removed once dropped, unless [`SpooledFile::keep`] is called.
+pub struct SpooledFile {
+    path: PathBuf,
+    len: u64,
+    keep: bool,
+}
+
+impl SpooledFile {
+    /// Returns the path of the temporary file.
+    #[must_use]
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Returns the size of the file, in bytes.
+    #[must_use]
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    /// Returns `true` in case the file is empty.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Keep the file once dropped, returning its path,
+    /// e.g. such that it can be moved to its final location.
+    #[must_use]
+    pub fn keep(mut self) -> PathBuf {
+        self.keep = true;
+        std::mem::take(&mut self.path)
+    }
+}
+
+impl Drop for SpooledFile {
+    fn drop(&mut self) {
+        if !self.keep {
+            let _ = std::fs::remove_file(&self.path);
+        }
+    }
+}
+
+/// The error returned when reading the parts of a [`Multipart`] body fails.
+///
+/// It can be returned as a response, using the status code of [`MultipartError::status`].
+#[derive(Debug)]
+pub struct MultipartError {
+    kind: ErrorKind,
+}
+
+#[derive(Debug)]
+enum ErrorKind {
+    Incomplete,
+    InvalidDelimiter,
+    InvalidHeader,
+    HeadersTooLarge,
+    PartTooLarge(u64),
+    TotalTooLarge(u64),
+    InvalidUtf8(std::string::FromUtf8Error),
+    Body(BoxError),
+    Io(io::Error),
+}
+
+impl From<ErrorKind> for MultipartError {
+    fn from(kind: ErrorKind) -> Self {
+        Self { kind }
+    }
+}
+
+impl MultipartError {
+    /// Get the response body text used for this error.
+    #[must_use]
+    pub fn body_text(&self) -> String {
+        format!("Failed to read multipart body: {self}")
+    }
+
+    /// Get the status code used for this error.
+    #[must_use]
+    pub fn status(&self) -> StatusCode {
+        match &self.kind {
+            ErrorKind::PartTooLarge(_) | ErrorKind::TotalTooLarge(_) => {
+                StatusCode::PAYLOAD_TOO_LARGE
+            }
+            ErrorKind::Body(err) if is_length_limit_error(err.as_ref()) => {
+                StatusCode::PAYLOAD_TOO_LARGE
+            }
+            ErrorKind::Io(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            _ => StatusCode::BAD_REQUEST,
+        }
+    }
+}
+
+impl fmt::Display for MultipartError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.kind {
+            ErrorKind::Incomplete => write!(f, "unexpected end of body"),
+            ErrorKind::InvalidDelimiter => write!(f, "invalid boundary delimiter"),
+            ErrorKind::InvalidHeader => write!(f, "invalid part header"),
+            ErrorKind::HeadersTooLarge => write!(
+                f,
+                "part headers exceed the limit of {MAX_PART_HEADERS_SIZE} bytes"
+            ),
+            ErrorKind::PartTooLarge(limit) => write!(f, "part exceeds the limit of {limit} bytes"),
+            ErrorKind::TotalTooLarge(limit) => {
+                write!(f, "body exceeds the limit of {limit} bytes")
+            }
+            ErrorKind::InvalidUtf8(err) => write!(f, "part is not valid UTF-8: {err}"),
+            ErrorKind::Body(err) => write!(f, "failed to read body: {err}"),
+            ErrorKind::Io(err) => write!(f, "failed to write part to file: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for MultipartError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match &self.kind {
+            ErrorKind::InvalidUtf8(err) => Some(err),
+            ErrorKind::Body(err) => Some(err.as_ref()),
+            ErrorKind::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl IntoResponse for MultipartError {
+    fn into_response(self) -> Response {
+        let status = self.status();
+        let body_text = self.body_text();
+        log_http_rejection!(
+            rejection_type = MultipartError,
+            body_text = body_text,
+            status = status,
+        );
+        (status, body_text).into_response()
+    }
+}
+
+fn parse_headers(raw: &[u8]) -> Result<HeaderMap, MultipartError> {
+    let mut headers = HeaderMap::new();
+    for line in raw.split(|b| *b == b'\n') {
+        let line = line.strip_suffix(b"\r").unwrap_or(line);
+        if line.is_empty() {
+            continue;
+        }
+        let colon = line
+            .iter()
+            .position(|b| *b == b':')
+            .ok_or(ErrorKind::InvalidHeader)?;
+        let name = HeaderName::from_bytes(line[..colon].trim_ascii())
+            .map_err(|_| ErrorKind::InvalidHeader)?;
+        let value = HeaderValue::from_bytes(line[colon + 1..].trim_ascii())
+            .map_err(|_| ErrorKind::InvalidHeader)?;
+        headers.append(name, value);
+    }
+    Ok(headers)
+}
+
+/// Returns the value of the given parameter of a `Content-Disposition` header value.
+///
+/// Quoted values end at the next quote, as the HTML spec
+/// percent-encodes quotes in field and file names, instead of escaping them.
+fn disposition_param(value: &str, key: &str) -> Option<String> {
+    let (_, mut rest) = value.split_once(';')?;
+    loop {
+        rest = rest.trim_start_matches([' ', '\t', ';']);
+        let (name, value) = rest.split_once('=')?;
+        let value = value.trim_start();
+        let (value, remaining) = match value.strip_prefix('"') {
+            Some(quoted) => quoted.split_once('"')?,
+            None => value.split_once(';').unwrap_or((value, "")),
+        };
+        if name.trim().eq_ignore_ascii_case(key) {
+            return Some(value.trim().to_owned());
+        }
+        rest = remaining;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::service::web::WebService;
+    use crate::{Method, StatusCode};
+    use rama_core::{Context, Service};
+
+    const BODY: &str = "preamble\r\n\
+        --BOUNDARY\r\n\
+        Content-Disposition: form-data; name=\"title\"\r\n\
+        \r\n\
+        hello world\r\n\
+        --BOUNDARY\r\n\
+        Content-Disposition: form-data; name=\"file\"; filename=\"a;b.txt\"\r\n\
+        Content-Type: text/plain\r\n\
+        \r\n\
+        line one\r\nline two\r\n\
+        --BOUNDARY--\r\n";
+
+    fn chunked_body(size: usize) -> Body {
+        let chunks: Vec<Result<Bytes, BoxError>> = BODY
+            .as_bytes()
+            .chunks(size)
+            .map(|chunk| Ok(Bytes::copy_from_slice(chunk)))
+            .collect();
+        Body::from_stream(rama_core::futures::stream::iter(chunks))
+    }
+
+    #[tokio::test]
+    async fn test_multipart_parts() {
+        for size in [1, 3, 7, BODY.len()] {
+            let mut multipart = Multipart::new(chunked_body(size), "BOUNDARY");
+
+            let part = multipart.next_part().await.unwrap().unwrap();
+            assert_eq!(part.name(), Some("title"));
+            assert_eq!(part.file_name(), None);
+            assert_eq!(part.text().await.unwrap(), "hello world");
+
+            let part = multipart.next_part().await.unwrap().unwrap();
+            assert_eq!(part.name(), Some("file"));
+            assert_eq!(part.file_name(), Some("a;b.txt"));
+            assert_eq!(part.content_type(), Some(&mime::TEXT_PLAIN));
+            let file = part.spool_to_temp_file().await.unwrap();
+            assert_eq!(file.len(), 18);
+            assert_eq!(
+                std::fs::read_to_string(file.path()).unwrap(),
+                "line one\r\nline two"
+            );
+            let path = file.path().to_owned();
+            drop(file);
+            assert!(!path.exists());
+
+            assert!(multipart.next_part().await.unwrap().is_none());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_multipart_skips_unread_parts() {
+        let mut multipart = Multipart::new(chunked_body(5), "BOUNDARY");
+        assert!(multipart.next_part().await.unwrap().is_some());
+        let part = multipart.next_part().await.unwrap().unwrap();
+        assert_eq!(part.name(), Some("file"));
+        assert!(multipart.next_part().await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_multipart_default_limits() {
+        let data = "a".repeat(Multipart::DEFAULT_MAX_PART_SIZE as usize + 1);
+        let body = format!(
+            "--BOUNDARY\r\nContent-Disposition: form-data; name=\"big\"\r\n\r\n{data}\r\n--BOUNDARY--\r\n"
+        );
+
+        let mut multipart = Multipart::new(Body::from(body.clone()), "BOUNDARY");
+        let part = multipart.next_part().await.unwrap().unwrap();
+        let err = part.bytes().await.unwrap_err();
+        assert_eq!(err.status(), StatusCode::PAYLOAD_TOO_LARGE);
+
+        let mut multipart = Multipart::new(Body::from(body), "BOUNDARY");
+        multipart.set_max_part_size(None);
+        let part = multipart.next_part().await.unwrap().unwrap();
+        assert_eq!(part.bytes().await.unwrap().len(), data.len());
+    }
+
+    #[tokio::test]
+    async fn test_multipart_extractor() {
+        let service = WebService::default().post("/", async |mut multipart: Multipart| {
+            multipart.set_max_part_size(Some(12));
+            while let Some(part) = multipart.next_part().await? {
+                part.bytes().await?;
+            }
+            Ok::<_, MultipartError>(StatusCode::OK)
+        });
+
+        let req = Request::builder()
+            .method(Method::POST)
+            .header(
+                header::CONTENT_TYPE,
+                "multipart/form-data; boundary=BOUNDARY",
+            )
+            .body(Body::from(BODY))
+            .unwrap();
+        let resp = service.serve(Context::default(), req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::PAYLOAD_TOO_LARGE);
+
+        let req = Request::builder()
+            .method(Method::POST)
+            .header(header::CONTENT_TYPE, "multipart/form-data")
+            .body(Body::from(BODY))
+            .unwrap();
+        let resp = service.serve(Context::default(), req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+        let req = Request::builder()
+            .method(Method::POST)
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(BODY))
+            .unwrap();
+        let resp = service.serve(Context::default(), req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::UNSUPPORTED_MEDIA_TYPE);
+    }
+}
@@ -1,6 +1,7 @@
 //! Extract utilities to develop endpoint services efortless.
 
 use super::IntoResponse;
+use crate::service::web::openapi::Operation;
 use crate::{HeaderMap, dep::http::request::Parts, dep::mime, header};
 use rama_core::Context;
 
@@ -29,7 +30,7 @@ pub use typed_header::TypedHeader;
 
 pub mod body;
 #[doc(inline)]
-pub use body::{Body, Bytes, Csv, Form, Json, Text};
+pub use body::{Body, Bytes, Csv, Form, Json, Multipart, Text};
 
 pub mod datastar;
 
@@ -57,6 +58,12 @@ pub trait FromRequestContextRefPair: Sized + Send + Sync + 'static {
         ctx: &Context,
         parts: &Parts,
     ) -> impl Future<Output = Result<Self, Self::Rejection>> + Send;
+
+    /// Describe the input consumed by this extractor in the OpenAPI [`Operation`]
+    /// of the endpoint it is used in. Does nothing by default.
+    fn describe_openapi(operation: &mut Operation) {
+        let _ = operation;
+    }
 }
 
 /// Types that can be created from requests.
@@ -78,6 +85,12 @@ pub trait FromRequest: Sized + Send + 'static {
     fn from_request(
         req: crate::Request,
     ) -> impl Future<Output = Result<Self, Self::Rejection>> + Send;
+
+    /// Describe the input consumed by this extractor in the OpenAPI [`Operation`]
+    /// of the endpoint it is used in. Does nothing by default.
+    fn describe_openapi(operation: &mut Operation) {
+        let _ = operation;
+    }
 }
 
 fn has_any_content_type(headers: &HeaderMap, expected_content_types: &[&mime::Mime]) -> bool {
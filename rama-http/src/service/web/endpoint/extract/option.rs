@@ -1,6 +1,7 @@
 use rama_core::Context;
 
 use crate::service::web::endpoint::IntoResponse;
+use crate::service::web::openapi::Operation;
 use crate::{Request, dep::http::request::Parts};
 
 use super::{FromRequest, FromRequestContextRefPair};
@@ -18,6 +19,12 @@ pub trait OptionalFromRequestContextRefPair: Sized + Send + Sync + 'static {
         ctx: &Context,
         parts: &Parts,
     ) -> impl Future<Output = Result<Option<Self>, Self::Rejection>> + Send;
+
+    /// Describe the optional input consumed by this extractor in the OpenAPI [`Operation`]
+    /// of the endpoint it is used in. Does nothing by default.
+    fn describe_openapi(operation: &mut Operation) {
+        let _ = operation;
+    }
 }
 
 /// Customize the behavior of `Option<Self>` as a [`FromRequest`] extractor.
@@ -31,6 +38,12 @@ pub trait OptionalFromRequest: Sized + Send + Sync + 'static {
     fn from_request(
         req: Request,
     ) -> impl Future<Output = Result<Option<Self>, Self::Rejection>> + Send;
+
+    /// Describe the optional input consumed by this extractor in the OpenAPI [`Operation`]
+    /// of the endpoint it is used in. Does nothing by default.
+    fn describe_openapi(operation: &mut Operation) {
+        let _ = operation;
+    }
 }
 
 impl<T> FromRequestContextRefPair for Option<T>
@@ -45,6 +58,10 @@ where
     ) -> impl Future<Output = Result<Self, Self::Rejection>> + Send {
         T::from_request_context_ref_pair(ctx, parts)
     }
+
+    fn describe_openapi(operation: &mut Operation) {
+        <T as OptionalFromRequestContextRefPair>::describe_openapi(operation);
+    }
 }
 
 impl<T> FromRequest for Option<T>
@@ -56,4 +73,8 @@ where
     async fn from_request(req: Request) -> Result<Self, Self::Rejection> {
         T::from_request(req).await
     }
+
+    fn describe_openapi(operation: &mut Operation) {
+        <T as OptionalFromRequest>::describe_openapi(operation);
+    }
 }
@@ -3,6 +3,7 @@
 use super::FromRequestContextRefPair;
 use crate::dep::http::request::Parts;
 use crate::matcher::{UriParams, UriParamsDeserializeError};
+use crate::service::web::openapi::{Operation, schema_for};
 use crate::utils::macros::{composite_http_rejection, define_http_rejection};
 use rama_core::Context;
 use serde::de::DeserializeOwned;
@@ -59,6 +60,10 @@ where
             None => Err(MissingPathParams.into()),
         }
     }
+
+    fn describe_openapi(operation: &mut Operation) {
+        operation.describe_path_parameters(&schema_for::<T>());
+    }
 }
 
 impl<T> Deref for Path<T> {
@@ -2,6 +2,7 @@
 
 use super::{FromRequestContextRefPair, OptionalFromRequestContextRefPair};
 use crate::dep::http::request::Parts;
+use crate::service::web::openapi::{Operation, ParameterLocation, schema_for};
 use crate::utils::macros::define_http_rejection;
 use rama_core::Context;
 use serde::de::DeserializeOwned;
@@ -9,6 +10,30 @@ use serde::de::DeserializeOwned;
 /// Extractor that deserializes query strings into some type.
 ///
 /// `T` is expected to implement [`serde::Deserialize`].
+/// Repeated keys (e.g. `?tag=a&tag=b`) can be deserialized into a [`Vec`],
+/// while a missing query string is treated as an empty one.
+///
+/// Requests of which the query string cannot be deserialized are rejected
+/// with a `400 Bad Request`, of which the body describes the failure.
+/// Use `Option<Query<T>>` to only extract the query string in case it is present.
+///
+/// # Example
+///
+/// ```
+/// use rama_http::service::web::{WebService, extract::Query};
+///
+/// #[derive(Debug, serde::Deserialize)]
+/// struct Filter {
+///     page: Option<u32>,
+///     #[serde(default)]
+///     tag: Vec<String>,
+/// }
+///
+/// let service = WebService::default().get("/posts", async |Query(filter): Query<Filter>| {
+///     format!("page {} for tags {:?}", filter.page.unwrap_or(1), filter.tag)
+/// });
+/// # let _ = service;
+/// ```
 pub struct Query<T>(pub T);
 
 define_http_rejection! {
@@ -43,6 +68,14 @@ where
             serde_html_form::from_str(query).map_err(FailedToDeserializeQueryString::from_err)?;
         Ok(Self(params))
     }
+
+    /// Create a `Query<T>` from the query of the given [`Uri`],
+    /// which is treated as empty in case it has no query.
+    ///
+    /// [`Uri`]: crate::Uri
+    pub fn try_from_uri(uri: &crate::Uri) -> Result<Self, FailedToDeserializeQueryString> {
+        Self::parse_query_str(uri.query().unwrap_or_default())
+    }
 }
 
 impl<T> FromRequestContextRefPair for Query<T>
@@ -55,8 +88,11 @@ where
         _ctx: &Context,
         parts: &Parts,
     ) -> Result<Self, Self::Rejection> {
-        let query = parts.uri.query().unwrap_or_default();
-        Self::parse_query_str(query)
+        Self::try_from_uri(&parts.uri)
+    }
+
+    fn describe_openapi(operation: &mut Operation) {
+        operation.add_object_parameters(ParameterLocation::Query, &schema_for::<T>(), true);
     }
 }
 
@@ -70,13 +106,73 @@ where
         _ctx: &Context,
         parts: &Parts,
     ) -> Result<Option<Self>, Self::Rejection> {
-        match parts.uri.query() {
-            Some(query) => {
-                let params = serde_html_form::from_str(query)
-                    .map_err(FailedToDeserializeQueryString::from_err)?;
-                Ok(Some(Self(params)))
-            }
-            None => Ok(None),
-        }
+        parts.uri.query().map(Self::parse_query_str).transpose()
+    }
+
+    fn describe_openapi(operation: &mut Operation) {
+        operation.add_object_parameters(ParameterLocation::Query, &schema_for::<T>(), false);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::dep::http_body_util::BodyExt as _;
+    use crate::service::web::WebService;
+    use crate::{Body, Request, StatusCode};
+    use rama_core::Service;
+
+    #[derive(Debug, serde::Deserialize)]
+    struct Filter {
+        page: Option<u32>,
+        #[serde(default)]
+        tag: Vec<String>,
+    }
+
+    async fn get(svc: &WebService, uri: &str) -> (StatusCode, String) {
+        let req = Request::get(uri).body(Body::empty()).unwrap();
+        let res = svc.serve(Context::default(), req).await.unwrap();
+        let status = res.status();
+        let body = res.into_body().collect().await.unwrap().to_bytes();
+        (status, String::from_utf8(body.to_vec()).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_query_extractor() {
+        let svc = WebService::default()
+            .get("/posts", async |Query(filter): Query<Filter>| {
+                format!("{:?} {:?}", filter.page, filter.tag)
+            })
+            .get("/optional", async |query: Option<Query<Filter>>| {
+                format!("{:?}", query.map(|Query(filter)| filter.tag))
+            });
+
+        assert_eq!(
+            get(&svc, "/posts?page=2&tag=rust&tag=http%20proxy").await,
+            (
+                StatusCode::OK,
+                r#"Some(2) ["rust", "http proxy"]"#.to_owned()
+            )
+        );
+        assert_eq!(
+            get(&svc, "/posts").await,
+            (StatusCode::OK, "None []".to_owned())
+        );
+        assert_eq!(
+            get(&svc, "/optional").await,
+            (StatusCode::OK, "None".to_owned())
+        );
+        assert_eq!(
+            get(&svc, "/optional?tag=a").await,
+            (StatusCode::OK, r#"Some(["a"])"#.to_owned())
+        );
+
+        let (status, body) = get(&svc, "/posts?page=two").await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert!(
+            body.starts_with("Failed to deserialize query string: "),
+            "{body}"
+        );
     }
 }
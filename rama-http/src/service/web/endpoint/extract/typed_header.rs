@@ -3,12 +3,17 @@
 use super::IntoResponse;
 use super::{FromRequestContextRefPair, OptionalFromRequestContextRefPair};
 use crate::dep::http::request::Parts;
-use crate::headers::{self, HeaderDecode};
+use crate::headers::{self, HeaderDecode, HeaderEncode, HeaderMapExt};
+use crate::service::web::openapi::{Operation, Parameter, ParameterLocation};
+use crate::service::web::response::{IntoResponseParts, ResponseParts};
 use crate::{HeaderName, Response};
 use rama_core::Context;
 use std::ops::Deref;
 
 /// Extractor to get a TypedHeader from the request.
+///
+/// It can also be returned by a handler (as part of its response),
+/// in order to insert the typed header into the response.
 pub struct TypedHeader<H>(pub H);
 
 impl<H: std::fmt::Debug> std::fmt::Debug for TypedHeader<H> {
@@ -47,6 +52,10 @@ where
                 },
             })
     }
+
+    fn describe_openapi(operation: &mut Operation) {
+        operation.add_parameter(header_parameter::<H>(true));
+    }
 }
 
 impl<H> OptionalFromRequestContextRefPair for TypedHeader<H>
@@ -70,6 +79,19 @@ where
             }),
         }
     }
+
+    fn describe_openapi(operation: &mut Operation) {
+        operation.add_parameter(header_parameter::<H>(false));
+    }
+}
+
+fn header_parameter<H: HeaderDecode>(required: bool) -> Parameter {
+    Parameter::new(
+        H::name().as_str(),
+        ParameterLocation::Header,
+        required,
+        serde_json::json!({ "type": "string" }),
+    )
 }
 
 impl<H> Deref for TypedHeader<H> {
@@ -80,6 +102,27 @@ impl<H> Deref for TypedHeader<H> {
     }
 }
 
+impl<H> IntoResponseParts for TypedHeader<H>
+where
+    H: HeaderEncode,
+{
+    type Error = std::convert::Infallible;
+
+    fn into_response_parts(self, mut res: ResponseParts) -> Result<ResponseParts, Self::Error> {
+        res.headers_mut().typed_insert(self.0);
+        Ok(res)
+    }
+}
+
+impl<H> IntoResponse for TypedHeader<H>
+where
+    H: HeaderEncode,
+{
+    fn into_response(self) -> Response {
+        (self, ()).into_response()
+    }
+}
+
 /// Rejection used for [`TypedHeader`].
 #[derive(Debug)]
 pub struct TypedHeaderRejection {
@@ -185,4 +228,33 @@ mod tests {
 
         assert_eq!(typed_header.unwrap().0, "application/json".parse().unwrap());
     }
+
+    #[tokio::test]
+    async fn test_typed_header_in_response() {
+        use crate::headers::{CacheControl, UserAgent};
+        use crate::service::web::WebService;
+        use crate::{StatusCode, header};
+        use rama_core::Service;
+        use std::time::Duration;
+
+        let svc =
+            WebService::default().get("/", async |TypedHeader(ua): TypedHeader<UserAgent>| {
+                (
+                    TypedHeader(CacheControl::new().with_max_age(Duration::from_secs(60))),
+                    ua.to_string(),
+                )
+            });
+
+        let req = Request::builder()
+            .header("user-agent", "rama")
+            .body(Body::empty())
+            .unwrap();
+        let res = svc.serve(Context::default(), req).await.unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(res.headers()[header::CACHE_CONTROL], "max-age=60");
+
+        let req = Request::builder().body(Body::empty()).unwrap();
+        let res = svc.serve(Context::default(), req).await.unwrap();
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+    }
 }
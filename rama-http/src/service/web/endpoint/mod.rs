@@ -1,3 +1,4 @@
+use super::openapi::Operation;
 use crate::{Body, Request, Response, matcher::HttpMatcher};
 use rama_core::{Context, Layer, Service, layer::MapResponseLayer, service::BoxService};
 use std::{convert::Infallible, fmt};
@@ -7,6 +8,11 @@ pub mod response;
 
 use response::IntoResponse;
 
+/// Inserted into the [`Response`] extensions of an endpoint
+/// of which an extractor rejected the request.
+#[derive(Debug, Clone)]
+pub(crate) struct ExtractorRejection;
+
 pub(crate) struct Endpoint {
     pub(crate) matcher: HttpMatcher<Body>,
     pub(crate) service: BoxService<Request, Response, Infallible>,
@@ -18,6 +24,19 @@ pub trait IntoEndpointService<T>: private::Sealed<T> {
     fn into_endpoint_service(
         self,
     ) -> impl Service<Request, Response = Response, Error = Infallible>;
+
+    #[doc(hidden)]
+    /// describe the input of the endpoint in the OpenAPI [`Operation`] of its route.
+    fn describe_openapi(&self, operation: &mut Operation) {
+        let _ = operation;
+    }
+
+    #[doc(hidden)]
+    /// describe the names of the layers wrapping the endpoint, outermost first,
+    /// and the type name of its handler, as listed in the route table of a router.
+    fn describe_route(&self) -> (Vec<&'static str>, &'static str) {
+        (Vec::new(), std::any::type_name::<Self>())
+    }
 }
 
 impl<S, R> IntoEndpointService<(R,)> for S
@@ -86,6 +105,80 @@ where
     }
 }
 
+/// An endpoint [`Service`] wrapped by named layers.
+///
+/// Using it as the service of a route records the names of its layers,
+/// and the type name of the wrapped service as its handler, in the
+/// [`RouteInfo`] of that route. Services wrapped in layers
+/// without it are listed without layers.
+///
+/// [`RouteInfo`]: super::RouteInfo
+pub struct LayeredEndpoint<S> {
+    service: S,
+    layers: Vec<&'static str>,
+    handler: &'static str,
+}
+
+impl<S> LayeredEndpoint<S> {
+    /// Create a new [`LayeredEndpoint`] for the given handler service, without any layers.
+    pub fn new(service: S) -> Self {
+        Self {
+            service,
+            layers: Vec::new(),
+            handler: std::any::type_name::<S>(),
+        }
+    }
+
+    /// Wrap the service using the given [`Layer`], listed under the given name.
+    ///
+    /// The layers are listed outermost first, so the last added layer is listed first.
+    pub fn layer<L: Layer<S>>(self, name: &'static str, layer: L) -> LayeredEndpoint<L::Service> {
+        let mut layers = self.layers;
+        layers.insert(0, name);
+        LayeredEndpoint {
+            service: layer.into_layer(self.service),
+            layers,
+            handler: self.handler,
+        }
+    }
+}
+
+impl<S: fmt::Debug> fmt::Debug for LayeredEndpoint<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LayeredEndpoint")
+            .field("service", &self.service)
+            .field("layers", &self.layers)
+            .field("handler", &self.handler)
+            .finish()
+    }
+}
+
+impl<S: Clone> Clone for LayeredEndpoint<S> {
+    fn clone(&self) -> Self {
+        Self {
+            service: self.service.clone(),
+            layers: self.layers.clone(),
+            handler: self.handler,
+        }
+    }
+}
+
+impl<S, R> IntoEndpointService<(LayeredEndpoint<S>, Request, R)> for LayeredEndpoint<S>
+where
+    S: Service<Request, Response = R, Error = Infallible>,
+    R: IntoResponse + Send + Sync + 'static,
+{
+    fn into_endpoint_service(
+        self,
+    ) -> impl Service<Request, Response = Response, Error = Infallible> {
+        MapResponseLayer::new(R::into_response).into_layer(self.service)
+    }
+
+    fn describe_route(&self) -> (Vec<&'static str>, &'static str) {
+        (self.layers.clone(), self.handler)
+    }
+}
+
 mod service;
 #[doc(inline)]
 pub use service::EndpointServiceFn;
@@ -145,6 +238,10 @@ where
             _marker: std::marker::PhantomData,
         }
     }
+
+    fn describe_openapi(&self, operation: &mut Operation) {
+        F::describe_operation(operation);
+    }
 }
 
 mod private {
@@ -177,6 +274,13 @@ mod private {
 
     impl<R> Sealed<()> for R where R: IntoResponse + Send + Sync + 'static {}
 
+    impl<S, R> Sealed<(LayeredEndpoint<S>, Request, R)> for LayeredEndpoint<S>
+    where
+        S: Service<Request, Response = R, Error = Infallible>,
+        R: IntoResponse + Send + Sync + 'static,
+    {
+    }
+
     impl<F, T> Sealed<(F, T)> for F where F: EndpointServiceFn<T> {}
 }
 
@@ -45,6 +45,9 @@ use super::Headers;
 ///
 /// ## Extracting Form from a Request
 ///
+/// For `GET` and `HEAD` requests the form is deserialized from the query string,
+/// for all other methods from the `application/x-www-form-urlencoded` request body.
+///
 /// ```
 /// use rama_http::service::web::response::Form;
 ///
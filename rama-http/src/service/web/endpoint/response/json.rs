@@ -34,6 +34,14 @@ use super::Headers;
 ///
 /// ## Extracting Json from a Request
 ///
+/// The request must have an `application/json` (or `application/*+json`) content type,
+/// and its body may not exceed the [`JsonBodyLimit`] (2MiB by default). Otherwise the
+/// request is rejected with a [`JsonRejection`], answered as `application/problem+json`
+/// with status `415`, `413` or `400` respectively.
+///
+/// [`JsonBodyLimit`]: crate::service::web::extract::body::JsonBodyLimit
+/// [`JsonRejection`]: crate::service::web::extract::body::JsonRejection
+///
 /// ```
 /// use serde_json::json;
 /// use rama_http::service::web::response::Json;
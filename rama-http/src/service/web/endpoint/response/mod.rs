@@ -41,6 +41,10 @@ mod csv;
 #[doc(inline)]
 pub use csv::Csv;
 
+mod problem;
+#[doc(inline)]
+pub use problem::ProblemDetails;
+
 mod form;
 #[doc(inline)]
 pub use form::Form;
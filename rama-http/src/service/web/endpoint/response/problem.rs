@@ -0,0 +1,154 @@
+use super::IntoResponse;
+use crate::{Body, HeaderValue, Response, StatusCode, header};
+use serde::{Serialize, Serializer};
+use serde_json::{Map, Value};
+
+/// Problem details as defined in [RFC 9457],
+/// used to create machine-readable `application/problem+json` error [`Response`]s.
+///
+/// [RFC 9457]: https://www.rfc-editor.org/rfc/rfc9457
+///
+/// # Example
+///
+/// ```
+/// use rama_http::StatusCode;
+/// use rama_http::service::web::response::{IntoResponse, ProblemDetails};
+///
+/// async fn handler() -> impl IntoResponse {
+///     ProblemDetails::new(StatusCode::FORBIDDEN)
+///         .with_problem_type("https://example.com/probs/out-of-credit")
+///         .with_detail("Your current balance is 30, but that costs 50.")
+///         .with_extension("balance", 30)
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ProblemDetails {
+    #[serde(rename = "type", skip_serializing_if = "Option::is_none")]
+    problem_type: Option<String>,
+    #[serde(serialize_with = "serialize_status")]
+    status: StatusCode,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    title: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    detail: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    instance: Option<String>,
+    #[serde(flatten)]
+    extensions: Map<String, Value>,
+}
+
+fn serialize_status<S: Serializer>(status: &StatusCode, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_u16(status.as_u16())
+}
+
+impl ProblemDetails {
+    /// Create new [`ProblemDetails`] for the given status,
+    /// using its canonical reason as title.
+    #[must_use]
+    pub fn new(status: StatusCode) -> Self {
+        Self {
+            problem_type: None,
+            status,
+            title: status.canonical_reason().map(ToOwned::to_owned),
+            detail: None,
+            instance: None,
+            extensions: Map::new(),
+        }
+    }
+
+    /// Returns the status of these [`ProblemDetails`].
+    #[must_use]
+    pub fn status(&self) -> StatusCode {
+        self.status
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Set the URI reference identifying the problem type.
+        ///
+        /// Defaults to `about:blank` when not set.
+        pub fn problem_type(mut self, problem_type: impl Into<String>) -> Self {
+            self.problem_type = Some(problem_type.into());
+            self
+        }
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Set the short, human-readable summary of the problem type.
+        pub fn title(mut self, title: impl Into<String>) -> Self {
+            self.title = Some(title.into());
+            self
+        }
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Set the human-readable explanation specific to this occurrence of the problem.
+        pub fn detail(mut self, detail: impl Into<String>) -> Self {
+            self.detail = Some(detail.into());
+            self
+        }
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Set the URI reference identifying this specific occurrence of the problem.
+        pub fn instance(mut self, instance: impl Into<String>) -> Self {
+            self.instance = Some(instance.into());
+            self
+        }
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Add an extension member, providing additional information about the problem.
+        pub fn extension(mut self, name: impl Into<String>, value: impl Into<Value>) -> Self {
+            self.extensions.insert(name.into(), value.into());
+            self
+        }
+    }
+}
+
+impl IntoResponse for ProblemDetails {
+    fn into_response(self) -> Response {
+        let body = match serde_json::to_vec(&self) {
+            Ok(body) => body,
+            Err(err) => {
+                return (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()).into_response();
+            }
+        };
+        let mut res = Response::new(Body::from(body));
+        *res.status_mut() = self.status;
+        res.headers_mut().insert(
+            header::CONTENT_TYPE,
+            HeaderValue::from_static("application/problem+json"),
+        );
+        res
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rama_http_types::BodyExtractExt;
+
+    #[tokio::test]
+    async fn test_problem_details_into_response() {
+        let res = ProblemDetails::new(StatusCode::BAD_REQUEST)
+            .with_detail("missing field `name`")
+            .with_extension("field", "name")
+            .into_response();
+
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(
+            res.headers()[header::CONTENT_TYPE],
+            "application/problem+json"
+        );
+        let body: Value = res.try_into_json().await.unwrap();
+        assert_eq!(
+            body,
+            serde_json::json!({
+                "status": 400,
+                "title": "Bad Request",
+                "detail": "missing field `name`",
+                "field": "name",
+            })
+        );
+    }
+}
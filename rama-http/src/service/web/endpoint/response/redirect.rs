@@ -85,7 +85,7 @@ impl Redirect {
     // use the `Location` header, namely `304 Not Modified`.
     //
     // We're open to adding more constructors upon request, if they make sense :)
-    fn with_status_code(status: StatusCode, loc: impl IntoRedirectLoc) -> Self {
+    pub(crate) fn with_status_code(status: StatusCode, loc: impl IntoRedirectLoc) -> Self {
         assert!(status.is_redirection(), "not a redirection status code");
         Self {
             status,
@@ -1,5 +1,6 @@
 use super::IntoResponse;
 use super::extract::{FromRequest, FromRequestContextRefPair};
+use crate::service::web::openapi::Operation;
 use crate::{Request, Response};
 use rama_core::Context;
 use rama_utils::macros::all_the_tuples_no_last_special_case;
@@ -107,6 +108,14 @@ macro_rules! impl_endpoint_service_fn_tuple_with_context_and_from_request {
 
 all_the_tuples_no_last_special_case!(impl_endpoint_service_fn_tuple_with_context_and_from_request);
 
+/// Turn the rejection of an extractor into a [`Response`],
+/// marked as such for the failure handlers of the router.
+fn rejection_into_response(rejection: impl IntoResponse) -> Response {
+    let mut res = rejection.into_response();
+    res.extensions_mut().insert(super::ExtractorRejection);
+    res
+}
+
 mod private {
     use super::*;
 
@@ -116,6 +125,11 @@ mod private {
         /// It is expected to do so by extracting the desired data from the context and/or request,
         /// and then calling the function with the extracted data.
         fn call(&self, ctx: Context, req: Request) -> impl Future<Output = Response> + Send + '_;
+
+        /// Describe the input extracted for the function in the OpenAPI [`Operation`].
+        fn describe_operation(operation: &mut Operation) {
+            let _ = operation;
+        }
     }
 
     impl<F, R, O> Sealed<(F, R, O)> for F
@@ -139,10 +153,14 @@ mod private {
         async fn call(&self, _ctx: Context, req: Request) -> Response {
             let param: I = match I::from_request(req).await {
                 Ok(v) => v,
-                Err(r) => return r.into_response(),
+                Err(r) => return rejection_into_response(r),
             };
             self(param).await.into_response()
         }
+
+        fn describe_operation(operation: &mut Operation) {
+            I::describe_openapi(operation);
+        }
     }
 
     impl<F, R, O> Sealed<(F, R, O, (), Context)> for F
@@ -166,10 +184,14 @@ mod private {
         async fn call(&self, ctx: Context, req: Request) -> Response {
             let param: I = match I::from_request(req).await {
                 Ok(v) => v,
-                Err(r) => return r.into_response(),
+                Err(r) => return rejection_into_response(r),
             };
             self(ctx, param).await.into_response()
         }
+
+        fn describe_operation(operation: &mut Operation) {
+            I::describe_openapi(operation);
+        }
     }
 
     macro_rules! impl_endpoint_service_fn_sealed_tuple {
@@ -187,10 +209,14 @@ mod private {
                         let (parts, _body) = req.into_parts();
                         $(let $ty = match $ty::from_request_context_ref_pair(&ctx, &parts).await {
                             Ok(v) => v,
-                            Err(r) => return r.into_response(),
+                            Err(r) => return rejection_into_response(r),
                         });+;
                         self($($ty),+).await.into_response()
                     }
+
+                fn describe_operation(operation: &mut Operation) {
+                    $($ty::describe_openapi(operation);)+
+                }
             }
         };
     }
@@ -213,15 +239,20 @@ mod private {
                         let (parts, body) = req.into_parts();
                         $(let $ty = match $ty::from_request_context_ref_pair(&ctx, &parts).await {
                             Ok(v) => v,
-                            Err(r) => return r.into_response(),
+                            Err(r) => return rejection_into_response(r),
                         });+;
                         let req = Request::from_parts(parts, body);
                         let last: I = match I::from_request(req).await {
                             Ok(v) => v,
-                            Err(r) => return r.into_response(),
+                            Err(r) => return rejection_into_response(r),
                         };
                         self($($ty),+, last).await.into_response()
                     }
+
+                fn describe_operation(operation: &mut Operation) {
+                    $($ty::describe_openapi(operation);)+
+                    I::describe_openapi(operation);
+                }
             }
         };
     }
@@ -243,10 +274,14 @@ mod private {
                         let (parts, _body) = req.into_parts();
                         $(let $ty = match $ty::from_request_context_ref_pair(&ctx, &parts).await {
                             Ok(v) => v,
-                            Err(r) => return r.into_response(),
+                            Err(r) => return rejection_into_response(r),
                         });+;
                         self($($ty),+, ctx).await.into_response()
                     }
+
+                fn describe_operation(operation: &mut Operation) {
+                    $($ty::describe_openapi(operation);)+
+                }
             }
         };
     }
@@ -269,15 +304,20 @@ mod private {
                         let (parts, body) = req.into_parts();
                         $(let $ty = match $ty::from_request_context_ref_pair(&ctx, &parts).await {
                             Ok(v) => v,
-                            Err(r) => return r.into_response(),
+                            Err(r) => return rejection_into_response(r),
                         });+;
                         let req = Request::from_parts(parts, body);
                         let last: I = match I::from_request(req).await {
                             Ok(v) => v,
-                            Err(r) => return r.into_response(),
+                            Err(r) => return rejection_into_response(r),
                         };
                         self($($ty),+, ctx, last).await.into_response()
                     }
+
+                fn describe_operation(operation: &mut Operation) {
+                    $($ty::describe_openapi(operation);)+
+                    I::describe_openapi(operation);
+                }
             }
         };
     }
@@ -0,0 +1,372 @@
+//! Health-check web service, with liveness and readiness endpoints.
+//!
+//! Unlike the [`k8s`] health service, the [`HealthService`] runs registered async checks
+//! (e.g. upstream reachability, certificate expiry or connection pool health)
+//! and reports their aggregated status as JSON:
+//!
+//! ```json
+//! {
+//!   "status": "fail",
+//!   "checks": {
+//!     "upstream": { "status": "pass", "duration_ms": 1.2 },
+//!     "shutdown": { "status": "fail", "error": "draining" }
+//!   }
+//! }
+//! ```
+//!
+//! Endpoints respond with `200 OK` if all checks pass
+//! and `503 Service Unavailable` otherwise. The readiness endpoint also fails
+//! as soon as the graceful shutdown of the server is triggered,
+//! such that load balancers stop routing traffic while the server drains.
+//!
+//! [`k8s`]: crate::service::web::k8s
+//!
+//! # Example
+//!
+//! ```
+//! use rama_http::service::web::{Router, health::HealthService};
+//! use rama_http::{Body, Request, StatusCode};
+//! use rama_core::{Context, Service};
+//! use rama_core::error::OpaqueError;
+//!
+//! # #[tokio::main]
+//! # async fn main() {
+//! let health = HealthService::new()
+//!     .with_readiness_check("upstream", async || {
+//!         // e.g. try to connect to the upstream
+//!         Ok::<_, OpaqueError>(())
+//!     });
+//!
+//! let app = Router::new().nest("/health", health);
+//!
+//! let req = Request::get("/health/ready").body(Body::empty()).unwrap();
+//! let res = app.serve(Context::default(), req).await.unwrap();
+//! assert_eq!(res.status(), StatusCode::OK);
+//! # }
+//! ```
+
+use crate::service::web::response::{IntoResponse, Json};
+use crate::{Request, Response, StatusCode};
+use rama_core::error::BoxError;
+use rama_core::futures::{FutureExt, future::BoxFuture};
+use rama_core::{Context, Service};
+use serde_json::{Map, Value, json};
+use std::{convert::Infallible, fmt, sync::Arc, time::Duration, time::Instant};
+
+/// The default timeout applied to each [`HealthCheck`].
+pub const DEFAULT_CHECK_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// An async health check, registered with a [`HealthService`].
+///
+/// Implemented for async closures returning a `Result<(), E>`.
+pub trait HealthCheck: Send + Sync + 'static {
+    /// Run the check, returning an error if unhealthy.
+    fn check(&self) -> impl Future<Output = Result<(), BoxError>> + Send + '_;
+}
+
+impl<F, Fut, E> HealthCheck for F
+where
+    F: Fn() -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = Result<(), E>> + Send,
+    E: Into<BoxError>,
+{
+    async fn check(&self) -> Result<(), BoxError> {
+        (self)().await.map_err(Into::into)
+    }
+}
+
+trait DynHealthCheck: Send + Sync + 'static {
+    fn check_boxed(&self) -> BoxFuture<'_, Result<(), BoxError>>;
+}
+
+impl<T: HealthCheck> DynHealthCheck for T {
+    fn check_boxed(&self) -> BoxFuture<'_, Result<(), BoxError>> {
+        self.check().boxed()
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Probe {
+    Liveness,
+    Readiness,
+}
+
+#[derive(Clone)]
+struct RegisteredCheck {
+    name: String,
+    probe: Probe,
+    check: Arc<dyn DynHealthCheck>,
+}
+
+/// Web service with liveness (`/live`) and readiness (`/ready`) endpoints,
+/// reporting the aggregated status of the registered [`HealthCheck`]s.
+///
+/// Mount it under a prefix using [`Router::sub`], or mount
+/// the [`HealthService::liveness`] and [`HealthService::readiness`]
+/// endpoints individually.
+///
+/// See the [module docs](crate::service::web::health) for more details.
+///
+/// [`Router::sub`]: crate::service::web::Router::sub
+#[derive(Clone)]
+pub struct HealthService {
+    checks: Arc<Vec<RegisteredCheck>>,
+    timeout: Duration,
+}
+
+impl fmt::Debug for HealthService {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("HealthService")
+            .field(
+                "checks",
+                &self
+                    .checks
+                    .iter()
+                    .map(|check| check.name.as_str())
+                    .collect::<Vec<_>>(),
+            )
+            .field("timeout", &self.timeout)
+            .finish()
+    }
+}
+
+impl Default for HealthService {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl HealthService {
+    /// Create a new [`HealthService`] without any checks.
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            checks: Arc::new(Vec::new()),
+            timeout: DEFAULT_CHECK_TIMEOUT,
+        }
+    }
+
+    fn push_check(&mut self, name: impl Into<String>, probe: Probe, check: impl HealthCheck) {
+        Arc::make_mut(&mut self.checks).push(RegisteredCheck {
+            name: name.into(),
+            probe,
+            check: Arc::new(check),
+        });
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Register a check used by the liveness endpoint.
+        ///
+        /// Failing liveness checks usually result in the process being restarted,
+        /// so only register checks which cannot recover by themselves.
+        pub fn liveness_check(mut self, name: impl Into<String>, check: impl HealthCheck) -> Self {
+            self.push_check(name, Probe::Liveness, check);
+            self
+        }
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Register a check used by the readiness endpoint.
+        pub fn readiness_check(mut self, name: impl Into<String>, check: impl HealthCheck) -> Self {
+            self.push_check(name, Probe::Readiness, check);
+            self
+        }
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Set the timeout applied to each check, after which it is considered failed.
+        ///
+        /// Defaults to [`DEFAULT_CHECK_TIMEOUT`].
+        pub fn check_timeout(mut self, timeout: Duration) -> Self {
+            self.timeout = timeout;
+            self
+        }
+    }
+
+    /// Returns the liveness endpoint as a standalone service.
+    #[must_use]
+    pub fn liveness(&self) -> HealthEndpoint {
+        HealthEndpoint {
+            health: self.clone(),
+            probe: Probe::Liveness,
+        }
+    }
+
+    /// Returns the readiness endpoint as a standalone service.
+    #[must_use]
+    pub fn readiness(&self) -> HealthEndpoint {
+        HealthEndpoint {
+            health: self.clone(),
+            probe: Probe::Readiness,
+        }
+    }
+
+    async fn report(&self, ctx: &Context, probe: Probe) -> Response {
+        let checks =
+            self.checks
+                .iter()
+                .filter(|check| check.probe == probe)
+                .map(|check| async move {
+                    let start = Instant::now();
+                    let result =
+                        match tokio::time::timeout(self.timeout, check.check.check_boxed()).await {
+                            Ok(result) => result,
+                            Err(_) => Err(BoxError::from("timeout")),
+                        };
+                    let duration_ms =
+                        (start.elapsed().as_secs_f64() * 1_000_000.0).round() / 1_000.0;
+                    let report = match result {
+                        Ok(()) => json!({ "status": "pass", "duration_ms": duration_ms }),
+                        Err(err) => json!({
+                            "status": "fail",
+                            "duration_ms": duration_ms,
+                            "error": err.to_string(),
+                        }),
+                    };
+                    (check.name.clone(), report)
+                });
+        let mut reports: Map<String, Value> = rama_core::futures::future::join_all(checks)
+            .await
+            .into_iter()
+            .collect();
+
+        let draining = probe == Probe::Readiness
+            && ctx
+                .guard()
+                .is_some_and(|guard| guard.shutdown_signal_triggered().now_or_never().is_some());
+        if draining {
+            reports.insert(
+                "shutdown".to_owned(),
+                json!({ "status": "fail", "error": "draining" }),
+            );
+        }
+
+        let healthy = reports.values().all(|report| report["status"] == "pass");
+        let status = if healthy {
+            StatusCode::OK
+        } else {
+            StatusCode::SERVICE_UNAVAILABLE
+        };
+        let body = json!({
+            "status": if healthy { "pass" } else { "fail" },
+            "checks": reports,
+        });
+        (status, Json(body)).into_response()
+    }
+}
+
+impl Service<Request> for HealthService {
+    type Response = Response;
+    type Error = Infallible;
+
+    async fn serve(&self, ctx: Context, req: Request) -> Result<Self::Response, Self::Error> {
+        Ok(match req.uri().path().trim_end_matches('/') {
+            "/live" => self.report(&ctx, Probe::Liveness).await,
+            "/ready" => self.report(&ctx, Probe::Readiness).await,
+            _ => StatusCode::NOT_FOUND.into_response(),
+        })
+    }
+}
+
+/// A single (liveness or readiness) endpoint of a [`HealthService`].
+#[derive(Clone)]
+pub struct HealthEndpoint {
+    health: HealthService,
+    probe: Probe,
+}
+
+impl fmt::Debug for HealthEndpoint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("HealthEndpoint")
+            .field("health", &self.health)
+            .field(
+                "probe",
+                &match self.probe {
+                    Probe::Liveness => "liveness",
+                    Probe::Readiness => "readiness",
+                },
+            )
+            .finish()
+    }
+}
+
+impl Service<Request> for HealthEndpoint {
+    type Response = Response;
+    type Error = Infallible;
+
+    async fn serve(&self, ctx: Context, _req: Request) -> Result<Self::Response, Self::Error> {
+        Ok(self.health.report(&ctx, self.probe).await)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Body;
+    use rama_core::error::OpaqueError;
+    use rama_core::graceful::Shutdown;
+    use rama_core::rt::Executor;
+    use rama_http_types::BodyExtractExt;
+
+    fn health() -> HealthService {
+        HealthService::new()
+            .with_liveness_check("alive", async || Ok::<_, OpaqueError>(()))
+            .with_readiness_check("upstream", async || Ok::<_, OpaqueError>(()))
+            .with_readiness_check("pool", async || {
+                Err(OpaqueError::from_display("no idle connections"))
+            })
+            .with_readiness_check("slow", async || {
+                tokio::time::sleep(Duration::from_secs(60)).await;
+                Ok::<_, OpaqueError>(())
+            })
+            .with_check_timeout(Duration::from_millis(10))
+    }
+
+    async fn get(svc: &HealthService, ctx: Context, path: &str) -> (StatusCode, Value) {
+        let req = Request::get(path).body(Body::empty()).unwrap();
+        let res = svc.serve(ctx, req).await.unwrap();
+        (res.status(), res.try_into_json().await.unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_health_liveness() {
+        let (status, body) = get(&health(), Context::default(), "/live").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["status"], "pass");
+        assert_eq!(body["checks"]["alive"]["status"], "pass");
+        assert!(body["checks"].get("upstream").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_health_readiness() {
+        let (status, body) = get(&health(), Context::default(), "/ready").await;
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(body["status"], "fail");
+        assert_eq!(body["checks"]["upstream"]["status"], "pass");
+        assert_eq!(body["checks"]["pool"]["error"], "no idle connections");
+        assert_eq!(body["checks"]["slow"]["error"], "timeout");
+    }
+
+    #[tokio::test]
+    async fn test_health_readiness_draining() {
+        let health = HealthService::new();
+        let (tx, rx) = tokio::sync::oneshot::channel::<()>();
+        let shutdown = Shutdown::new(async move {
+            let _ = rx.await;
+        });
+        let ctx = Context::new(Executor::graceful(shutdown.guard()));
+
+        let (status, _) = get(&health, ctx.clone(), "/ready").await;
+        assert_eq!(status, StatusCode::OK);
+
+        tx.send(()).unwrap();
+        ctx.guard().unwrap().shutdown_signal_triggered().await;
+
+        let (status, body) = get(&health, ctx.clone(), "/ready").await;
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(body["checks"]["shutdown"]["error"], "draining");
+
+        let (status, _) = get(&health, ctx, "/live").await;
+        assert_eq!(status, StatusCode::OK);
+    }
+}
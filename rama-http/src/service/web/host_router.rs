@@ -0,0 +1,211 @@
+use std::{collections::HashMap, convert::Infallible, fmt};
+
+use crate::{Request, Response};
+
+use rama_core::{
+    Context,
+    service::{BoxService, Service},
+    telemetry::tracing,
+};
+use rama_http_types::{Body, StatusCode};
+use rama_net::{address::Host, http::RequestContext};
+
+use super::IntoEndpointService;
+
+/// A router which dispatches requests to a different service per host,
+/// such that a single listener can serve multiple sites or tenants.
+///
+/// The host is taken from the authority of the request, as found in
+/// the `:authority` pseudo header (http/2+), the `Host` header or the request uri.
+/// Its port is ignored.
+///
+/// Hosts are registered with [`HostRouter::host`] as either:
+///
+/// - an exact pattern, e.g. `example.com` or `127.0.0.1`, matching only that host;
+/// - a wildcard pattern, e.g. `*.example.com`, matching all subdomains
+///   (e.g. `www.example.com` and `api.eu.example.com`), but not `example.com` itself.
+///
+/// An exact pattern takes precedence over a wildcard pattern,
+/// and a more specific wildcard pattern over a less specific one.
+/// Requests matching no pattern are served by the [`HostRouter::fallback`] service,
+/// or otherwise with a `404 Not Found`.
+///
+/// # Example
+///
+/// ```
+/// use rama_http::service::web::{HostRouter, Router};
+///
+/// let router = HostRouter::new()
+///     .host("example.com", Router::new().get("/", "home"))
+///     .host("*.example.com", Router::new().get("/", "tenant"))
+///     .fallback("unknown site");
+/// ```
+pub struct HostRouter {
+    exact: HashMap<String, BoxService<Request, Response, Infallible>>,
+    wildcard: HashMap<String, BoxService<Request, Response, Infallible>>,
+    fallback: Option<BoxService<Request, Response, Infallible>>,
+}
+
+impl fmt::Debug for HostRouter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("HostRouter")
+            .field("exact", &self.exact.keys())
+            .field("wildcard", &self.wildcard.keys())
+            .field("fallback", &self.fallback.is_some())
+            .finish()
+    }
+}
+
+impl Default for HostRouter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl HostRouter {
+    /// create a new host router.
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            exact: HashMap::new(),
+            wildcard: HashMap::new(),
+            fallback: None,
+        }
+    }
+
+    /// serve the requests for the given host pattern using the given service,
+    /// replacing the service registered earlier for the same pattern, if any.
+    ///
+    /// The pattern is either an exact host (e.g. `example.com`),
+    /// or a wildcard matching all its subdomains (e.g. `*.example.com`),
+    /// both of which are matched case-insensitive.
+    #[must_use]
+    pub fn host<I, T>(mut self, pattern: &str, service: I) -> Self
+    where
+        I: IntoEndpointService<T>,
+    {
+        let service = service.into_endpoint_service().boxed();
+        let pattern = normalize_host(pattern);
+        if let Some(parent) = pattern.strip_prefix("*.") {
+            self.wildcard.insert(parent.to_owned(), service);
+        } else {
+            self.exact.insert(pattern, service);
+        }
+        self
+    }
+
+    /// use the provided service when the host of the request matches no pattern.
+    #[must_use]
+    pub fn fallback<I, T>(mut self, service: I) -> Self
+    where
+        I: IntoEndpointService<T>,
+    {
+        self.fallback = Some(service.into_endpoint_service().boxed());
+        self
+    }
+
+    fn find(&self, host: &str) -> Option<&BoxService<Request, Response, Infallible>> {
+        if let Some(service) = self.exact.get(host) {
+            return Some(service);
+        }
+        // most specific wildcard first, e.g. `*.eu.example.com` before `*.example.com`
+        let mut parent = host;
+        while let Some((_, rest)) = parent.split_once('.') {
+            if let Some(service) = self.wildcard.get(rest) {
+                return Some(service);
+            }
+            parent = rest;
+        }
+        None
+    }
+}
+
+fn normalize_host(host: &str) -> String {
+    host.trim().trim_end_matches('.').to_ascii_lowercase()
+}
+
+fn request_host(ctx: &Context, req: &Request) -> Option<Host> {
+    if let Some(req_ctx) = ctx.get::<RequestContext>() {
+        return Some(req_ctx.authority.host().clone());
+    }
+    match RequestContext::try_from((ctx, req)) {
+        Ok(req_ctx) => Some(req_ctx.authority.host().clone()),
+        Err(err) => {
+            tracing::debug!("HostRouter: failed to determine the host of the request: {err:?}");
+            None
+        }
+    }
+}
+
+impl Service<Request> for HostRouter {
+    type Response = Response;
+    type Error = Infallible;
+
+    async fn serve(&self, ctx: Context, req: Request) -> Result<Self::Response, Self::Error> {
+        let service = request_host(&ctx, &req).and_then(|host| {
+            let host = match host {
+                Host::Name(domain) => normalize_host(domain.as_str()),
+                Host::Address(addr) => addr.to_string(),
+            };
+            self.find(&host)
+        });
+
+        match service.or(self.fallback.as_ref()) {
+            Some(service) => service.serve(ctx, req).await,
+            None => Ok(Response::builder()
+                .status(StatusCode::NOT_FOUND)
+                .body(Body::from("Not Found"))
+                .unwrap()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rama_http_types::dep::http_body_util::BodyExt;
+
+    async fn serve_host(router: &HostRouter, host: &str) -> (StatusCode, String) {
+        let req = Request::builder()
+            .uri("/")
+            .header("host", host)
+            .body(Body::empty())
+            .unwrap();
+        let res = router.serve(Context::default(), req).await.unwrap();
+        let status = res.status();
+        let body = res.into_body().collect().await.unwrap().to_bytes();
+        (status, String::from_utf8(body.to_vec()).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_host_router() {
+        let router = HostRouter::new()
+            .host("example.com", "apex")
+            .host("*.example.com", "tenant")
+            .host("*.eu.example.com", "eu tenant")
+            .host("admin.example.com", "admin")
+            .host("127.0.0.1", "local");
+
+        let cases = [
+            ("example.com", StatusCode::OK, "apex"),
+            ("EXAMPLE.com:8080", StatusCode::OK, "apex"),
+            ("www.example.com", StatusCode::OK, "tenant"),
+            ("a.b.example.com", StatusCode::OK, "tenant"),
+            ("shop.eu.example.com", StatusCode::OK, "eu tenant"),
+            ("eu.example.com", StatusCode::OK, "tenant"),
+            ("admin.example.com", StatusCode::OK, "admin"),
+            ("127.0.0.1:3000", StatusCode::OK, "local"),
+            ("example.org", StatusCode::NOT_FOUND, "Not Found"),
+        ];
+        for (host, expected_status, expected_body) in cases {
+            let (status, body) = serve_host(&router, host).await;
+            assert_eq!(status, expected_status, "host = {host}");
+            assert_eq!(body, expected_body, "host = {host}");
+        }
+
+        let router = router.fallback("default site");
+        let (status, body) = serve_host(&router, "example.org").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body, "default site");
+    }
+}
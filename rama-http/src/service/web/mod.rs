@@ -6,12 +6,34 @@ pub use service::{WebService, match_service};
 
 mod endpoint;
 #[doc(inline)]
-pub use endpoint::{EndpointServiceFn, IntoEndpointService, StaticService, extract, response};
+pub use endpoint::{
+    EndpointServiceFn, IntoEndpointService, LayeredEndpoint, StaticService, extract, response,
+};
+
+pub mod admin;
+#[doc(inline)]
+pub use admin::AdminService;
+
+pub mod health;
+#[doc(inline)]
+pub use health::HealthService;
 
 pub mod k8s;
 #[doc(inline)]
 pub use k8s::{k8s_health, k8s_health_builder};
 
+pub mod openapi;
+#[doc(inline)]
+pub use openapi::OpenApi;
+
+pub mod sse;
+#[doc(inline)]
+pub use sse::sse;
+
 mod router;
 #[doc(inline)]
-pub use router::Router;
+pub use router::{MatchedRoute, OriginalUri, RouteError, RouteFailure, RouteInfo, Router};
+
+mod host_router;
+#[doc(inline)]
+pub use host_router::HostRouter;
@@ -0,0 +1,863 @@
+//! OpenAPI 3.1 document generation for the [`Router`].
+//!
+//! The [`Router`] records the path, method and path parameters of each route
+//! registered with a method (e.g. [`Router::get`]) as an [`Operation`], to which
+//! the extractors of endpoint functions add the parameters and request body they consume,
+//! e.g. [`Query`] and [`Json`]. The schemas of the extracted types are derived from
+//! their [`Deserialize`] implementation using [`schema_for`].
+//!
+//! Generation is opt-in: the document is only served once an [`OpenApi`]
+//! is registered using [`Router::openapi`].
+//!
+//! # Example
+//!
+//! ```
+//! use rama_http::service::web::{Router, extract::{Json, Path, Query}, openapi::OpenApi};
+//! use serde::Deserialize;
+//!
+//! #[derive(Debug, Deserialize)]
+//! struct ListParams {
+//!     page: Option<u32>,
+//!     search: String,
+//! }
+//!
+//! #[derive(Debug, Deserialize)]
+//! struct NewUser {
+//!     name: String,
+//!     admin: bool,
+//! }
+//!
+//! let router = Router::new()
+//!     .get("/users", async |Query(_params): Query<ListParams>| "users")
+//!     .post("/users", async |Json(_user): Json<NewUser>| "created")
+//!     .delete("/users/{id}", async |Path(_id): Path<u64>| "deleted")
+//!     .openapi(
+//!         OpenApi::new("users", "1.0.0").with_path("/api-docs/openapi.json"),
+//!     );
+//! ```
+//!
+//! [`Router`]: super::Router
+//! [`Router::get`]: super::Router::get
+//! [`Router::openapi`]: super::Router::openapi
+//! [`Query`]: super::extract::Query
+//! [`Json`]: super::extract::Json
+//! [`Deserialize`]: serde::Deserialize
+
+use crate::Method;
+use serde::de::{self, DeserializeOwned, DeserializeSeed, Visitor, value::StrDeserializer};
+use serde_json::{Map, Value, json};
+use std::{collections::BTreeMap, fmt};
+
+/// The configuration of the OpenAPI document served by a [`Router`].
+///
+/// See the [module docs](self) for more information.
+///
+/// [`Router`]: super::Router
+#[derive(Debug, Clone)]
+pub struct OpenApi {
+    title: String,
+    version: String,
+    description: Option<String>,
+    path: String,
+}
+
+impl OpenApi {
+    /// Create a new [`OpenApi`] for the API with the given title and version,
+    /// served at `/openapi.json` by default.
+    #[must_use]
+    pub fn new(title: impl Into<String>, version: impl Into<String>) -> Self {
+        Self {
+            title: title.into(),
+            version: version.into(),
+            description: None,
+            path: "/openapi.json".to_owned(),
+        }
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Set the description of the API.
+        pub fn description(mut self, description: impl Into<String>) -> Self {
+            self.description = Some(description.into());
+            self
+        }
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Set the path at which the document is served, `/openapi.json` by default.
+        pub fn path(mut self, path: impl Into<String>) -> Self {
+            self.path = path.into();
+            self
+        }
+    }
+
+    /// Returns the path at which the document is served.
+    #[must_use]
+    pub fn served_path(&self) -> &str {
+        &self.path
+    }
+
+    pub(crate) fn document(&self, paths: &PathItems) -> Value {
+        let mut info = json!({
+            "title": self.title,
+            "version": self.version,
+        });
+        if let Some(description) = &self.description {
+            info["description"] = Value::from(description.as_str());
+        }
+
+        let paths: Map<String, Value> = paths
+            .0
+            .iter()
+            .map(|(path, operations)| {
+                let item: Map<String, Value> = operations
+                    .iter()
+                    .map(|(method, operation)| (method.to_lowercase(), operation.to_json()))
+                    .collect();
+                (path.clone(), Value::Object(item))
+            })
+            .collect();
+
+        json!({
+            "openapi": "3.1.0",
+            "info": info,
+            "paths": paths,
+        })
+    }
+}
+
+/// Where a [`Parameter`] of an [`Operation`] is found in the request.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParameterLocation {
+    /// A parameter captured from the path, e.g. `id` in `/users/{id}`.
+    Path,
+    /// A parameter found in the query of the request uri.
+    Query,
+    /// A parameter found in the headers of the request.
+    Header,
+}
+
+impl ParameterLocation {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Path => "path",
+            Self::Query => "query",
+            Self::Header => "header",
+        }
+    }
+}
+
+/// A parameter of an [`Operation`].
+#[derive(Debug, Clone)]
+pub struct Parameter {
+    name: String,
+    location: ParameterLocation,
+    required: bool,
+    schema: Value,
+}
+
+impl Parameter {
+    /// Create a new [`Parameter`], of which the value is described by the given JSON schema.
+    #[must_use]
+    pub fn new(
+        name: impl Into<String>,
+        location: ParameterLocation,
+        required: bool,
+        schema: Value,
+    ) -> Self {
+        Self {
+            name: name.into(),
+            location,
+            required,
+            schema,
+        }
+    }
+}
+
+/// The description of a single route and method in the OpenAPI document.
+///
+/// Extractors add the input they consume to it, by implementing
+/// [`FromRequestContextRefPair::describe_openapi`] or [`FromRequest::describe_openapi`].
+///
+/// [`FromRequestContextRefPair::describe_openapi`]: super::extract::FromRequestContextRefPair::describe_openapi
+/// [`FromRequest::describe_openapi`]: super::extract::FromRequest::describe_openapi
+#[derive(Debug, Clone)]
+pub struct Operation {
+    method: Method,
+    parameters: Vec<Parameter>,
+    request_body: Option<(String, Value, bool)>,
+}
+
+impl Operation {
+    pub(crate) fn new(method: Method, path: &str) -> Self {
+        let parameters = path
+            .split('/')
+            .filter_map(|segment| segment.strip_prefix('{')?.strip_suffix('}'))
+            .map(|name| {
+                Parameter::new(
+                    name.trim_start_matches('*'),
+                    ParameterLocation::Path,
+                    true,
+                    json!({ "type": "string" }),
+                )
+            })
+            .collect();
+        Self {
+            method,
+            parameters,
+            request_body: None,
+        }
+    }
+
+    /// Returns the method of the route described by this [`Operation`].
+    #[must_use]
+    pub fn method(&self) -> &Method {
+        &self.method
+    }
+
+    /// Add a parameter to the [`Operation`],
+    /// replacing the parameter with the same name and location, if any.
+    pub fn add_parameter(&mut self, parameter: Parameter) {
+        match self
+            .parameters
+            .iter_mut()
+            .find(|p| p.name == parameter.name && p.location == parameter.location)
+        {
+            Some(existing) => *existing = parameter,
+            None => self.parameters.push(parameter),
+        }
+    }
+
+    /// Describe the path parameters of the [`Operation`] using the JSON schema
+    /// of the type they are extracted as, e.g. by the [`Path`] extractor.
+    ///
+    /// The schema of a struct describes the parameters by name, the one of a tuple
+    /// by position, and any other schema the single parameter of the route, if so.
+    ///
+    /// [`Path`]: super::extract::Path
+    pub fn describe_path_parameters(&mut self, schema: &Value) {
+        if schema.get("properties").is_some() {
+            self.add_object_parameters(ParameterLocation::Path, schema, true);
+            return;
+        }
+
+        let mut path_parameters = self
+            .parameters
+            .iter_mut()
+            .filter(|p| p.location == ParameterLocation::Path);
+        if let Some(items) = schema.get("prefixItems").and_then(Value::as_array) {
+            for (parameter, item) in path_parameters.zip(items) {
+                parameter.schema = item.clone();
+            }
+        } else if let (Some(parameter), None) = (path_parameters.next(), path_parameters.next()) {
+            parameter.schema = schema.clone();
+        }
+    }
+
+    /// Add a parameter for each property of the given JSON object schema,
+    /// e.g. as returned by [`schema_for`] for a struct.
+    ///
+    /// Nothing is added in case the schema is not one of an object.
+    pub fn add_object_parameters(
+        &mut self,
+        location: ParameterLocation,
+        schema: &Value,
+        required: bool,
+    ) {
+        let Some(properties) = schema.get("properties").and_then(Value::as_object) else {
+            return;
+        };
+        let required_properties = schema.get("required").and_then(Value::as_array);
+        for (name, property) in properties {
+            let is_required = required
+                && required_properties
+                    .is_some_and(|props| props.iter().any(|p| p.as_str() == Some(name)));
+            self.add_parameter(Parameter::new(
+                name.clone(),
+                location,
+                is_required,
+                property.clone(),
+            ));
+        }
+    }
+
+    /// Set the request body of the [`Operation`], of the given content type
+    /// and described by the given JSON schema.
+    pub fn set_request_body(
+        &mut self,
+        content_type: impl Into<String>,
+        schema: Value,
+        required: bool,
+    ) {
+        self.request_body = Some((content_type.into(), schema, required));
+    }
+
+    fn to_json(&self) -> Value {
+        let mut operation = Map::new();
+        if !self.parameters.is_empty() {
+            let parameters = self
+                .parameters
+                .iter()
+                .map(|p| {
+                    json!({
+                        "name": p.name,
+                        "in": p.location.as_str(),
+                        "required": p.required,
+                        "schema": p.schema,
+                    })
+                })
+                .collect();
+            operation.insert("parameters".to_owned(), Value::Array(parameters));
+        }
+        if let Some((content_type, schema, required)) = &self.request_body {
+            operation.insert(
+                "requestBody".to_owned(),
+                json!({
+                    "required": required,
+                    "content": { content_type: { "schema": schema } },
+                }),
+            );
+        }
+        operation.insert(
+            "responses".to_owned(),
+            json!({ "default": { "description": "default response" } }),
+        );
+        Value::Object(operation)
+    }
+}
+
+/// The [`Operation`]s of a [`Router`], by path and method.
+///
+/// [`Router`]: super::Router
+#[derive(Debug, Clone, Default)]
+pub(crate) struct PathItems(BTreeMap<String, BTreeMap<String, Operation>>);
+
+impl PathItems {
+    pub(crate) fn insert(&mut self, path: &str, operation: Operation) {
+        self.0
+            .entry(openapi_path(path))
+            .or_default()
+            .insert(operation.method.as_str().to_owned(), operation);
+    }
+
+    /// Merge the [`PathItems`] of a router nested under the given prefix.
+    pub(crate) fn nest(&mut self, prefix: &str, nested: &Self) {
+        let prefix = prefix.trim().trim_matches('/');
+        for (path, operations) in &nested.0 {
+            let path = if prefix.is_empty() {
+                path.clone()
+            } else {
+                format!("/{prefix}{}", path.trim_end_matches('/'))
+            };
+            let prefix_operation = Operation::new(Method::GET, &path);
+            let entry = self.0.entry(openapi_path(&path)).or_default();
+            for (method, operation) in operations {
+                let mut operation = operation.clone();
+                for parameter in &prefix_operation.parameters {
+                    if !operation
+                        .parameters
+                        .iter()
+                        .any(|p| p.name == parameter.name && p.location == ParameterLocation::Path)
+                    {
+                        operation.parameters.push(parameter.clone());
+                    }
+                }
+                entry.insert(method.clone(), operation);
+            }
+        }
+    }
+}
+
+/// Convert a route pattern into an OpenAPI path,
+/// which has no notion of catch-all parameters.
+fn openapi_path(path: &str) -> String {
+    path.replace("{*", "{")
+}
+
+const MAX_SCHEMA_DEPTH: usize = 16;
+
+/// Derive the JSON schema of a type from its [`Deserialize`] implementation.
+///
+/// The type is traced by deserializing it from a stand-in deserializer,
+/// which records the values requested by the implementation. This describes
+/// structs, sequences, maps, options and primitives. Enums are described
+/// by the names of their variants, and self-describing types (e.g. internally tagged enums)
+/// by an empty schema, which accepts any value.
+///
+/// An empty schema is also returned in case the implementation
+/// rejects the stand-in values, e.g. because of custom validation.
+///
+/// [`Deserialize`]: serde::Deserialize
+#[must_use]
+pub fn schema_for<T: DeserializeOwned>() -> Value {
+    let mut traced = Traced::default();
+    match T::deserialize(Tracer {
+        depth: 0,
+        out: &mut traced,
+    }) {
+        Ok(_) => traced.schema,
+        Err(_) => json!({}),
+    }
+}
+
+#[derive(Debug)]
+struct Traced {
+    schema: Value,
+    optional: bool,
+}
+
+impl Default for Traced {
+    fn default() -> Self {
+        Self {
+            schema: json!({}),
+            optional: false,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct TraceError(String);
+
+impl fmt::Display for TraceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl std::error::Error for TraceError {}
+
+impl de::Error for TraceError {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        Self(msg.to_string())
+    }
+}
+
+/// A [`de::Deserializer`] which records the schema of the value it is asked for.
+struct Tracer<'a> {
+    depth: usize,
+    out: &'a mut Traced,
+}
+
+impl Tracer<'_> {
+    fn record(&mut self, schema: Value) {
+        self.out.schema = schema;
+    }
+
+    fn trace_seq<'de, V: Visitor<'de>>(
+        self,
+        len: usize,
+        visitor: V,
+    ) -> Result<(V::Value, Vec<Value>), TraceError> {
+        let mut items = Vec::with_capacity(len);
+        let value = visitor.visit_seq(SeqTracer {
+            depth: self.depth + 1,
+            remaining: len,
+            items: &mut items,
+        })?;
+        Ok((value, items.into_iter().map(|item| item.schema).collect()))
+    }
+}
+
+macro_rules! trace_primitive {
+    ($($method:ident => $schema:tt, $visit:ident($($value:expr)?);)+) => {
+        $(
+            fn $method<V: Visitor<'de>>(mut self, visitor: V) -> Result<V::Value, Self::Error> {
+                self.record(json!($schema));
+                visitor.$visit($($value)?)
+            }
+        )+
+    };
+}
+
+impl<'de> de::Deserializer<'de> for Tracer<'_> {
+    type Error = TraceError;
+
+    trace_primitive! {
+        deserialize_any => {}, visit_unit();
+        deserialize_ignored_any => {}, visit_unit();
+        deserialize_bool => { "type": "boolean" }, visit_bool(false);
+        deserialize_i8 => { "type": "integer" }, visit_i8(0);
+        deserialize_i16 => { "type": "integer" }, visit_i16(0);
+        deserialize_i32 => { "type": "integer" }, visit_i32(0);
+        deserialize_i64 => { "type": "integer" }, visit_i64(0);
+        deserialize_i128 => { "type": "integer" }, visit_i128(0);
+        deserialize_u8 => { "type": "integer", "minimum": 0 }, visit_u8(0);
+        deserialize_u16 => { "type": "integer", "minimum": 0 }, visit_u16(0);
+        deserialize_u32 => { "type": "integer", "minimum": 0 }, visit_u32(0);
+        deserialize_u64 => { "type": "integer", "minimum": 0 }, visit_u64(0);
+        deserialize_u128 => { "type": "integer", "minimum": 0 }, visit_u128(0);
+        deserialize_f32 => { "type": "number" }, visit_f32(0.0);
+        deserialize_f64 => { "type": "number" }, visit_f64(0.0);
+        deserialize_char => { "type": "string", "minLength": 1, "maxLength": 1 }, visit_char('a');
+        deserialize_str => { "type": "string" }, visit_str("");
+        deserialize_string => { "type": "string" }, visit_string(String::new());
+        deserialize_identifier => { "type": "string" }, visit_str("");
+        deserialize_bytes => { "type": "string" }, visit_bytes(&[]);
+        deserialize_byte_buf => { "type": "string" }, visit_byte_buf(Vec::new());
+        deserialize_unit => { "type": "null" }, visit_unit();
+    }
+
+    fn deserialize_unit_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        self.deserialize_unit(visitor)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(mut self, visitor: V) -> Result<V::Value, Self::Error> {
+        if self.depth >= MAX_SCHEMA_DEPTH {
+            self.out.optional = true;
+            return visitor.visit_none();
+        }
+        let value = visitor.visit_some(Tracer {
+            depth: self.depth + 1,
+            out: &mut *self.out,
+        })?;
+        self.out.optional = true;
+        Ok(value)
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(mut self, visitor: V) -> Result<V::Value, Self::Error> {
+        let len = usize::from(self.depth < MAX_SCHEMA_DEPTH);
+        let out = &mut *self.out;
+        let depth = self.depth;
+        let (value, items) = Tracer { depth, out }.trace_seq(len, visitor)?;
+        let items = items.into_iter().next().unwrap_or_else(|| json!({}));
+        self.record(json!({ "type": "array", "items": items }));
+        Ok(value)
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(
+        mut self,
+        len: usize,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        let out = &mut *self.out;
+        let depth = self.depth;
+        let (value, items) = Tracer { depth, out }.trace_seq(len, visitor)?;
+        self.record(json!({
+            "type": "array",
+            "prefixItems": items,
+            "minItems": len,
+            "maxItems": len,
+        }));
+        Ok(value)
+    }
+
+    fn deserialize_tuple_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        len: usize,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        self.deserialize_tuple(len, visitor)
+    }
+
+    fn deserialize_map<V: Visitor<'de>>(mut self, visitor: V) -> Result<V::Value, Self::Error> {
+        let mut key = Traced::default();
+        let mut value_schema = Traced::default();
+        let value = visitor.visit_map(MapTracer {
+            depth: self.depth + 1,
+            remaining: usize::from(self.depth < MAX_SCHEMA_DEPTH),
+            key: &mut key,
+            value: &mut value_schema,
+        })?;
+        self.record(json!({
+            "type": "object",
+            "additionalProperties": value_schema.schema,
+        }));
+        Ok(value)
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        mut self,
+        _name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        let mut traced = Vec::with_capacity(fields.len());
+        let value = visitor.visit_map(StructTracer {
+            depth: self.depth + 1,
+            fields,
+            traced: &mut traced,
+        })?;
+
+        let required: Vec<&str> = traced
+            .iter()
+            .filter(|(_, field)| !field.optional)
+            .map(|(name, _)| *name)
+            .collect();
+        let properties: Map<String, Value> = traced
+            .into_iter()
+            .map(|(name, field)| (name.to_owned(), field.schema))
+            .collect();
+        self.record(json!({
+            "type": "object",
+            "properties": properties,
+            "required": required,
+        }));
+        Ok(value)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        mut self,
+        _name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        let Some(variant) = variants.first() else {
+            return Err(de::Error::custom("enum without variants"));
+        };
+        self.record(json!({ "type": "string", "enum": variants }));
+        visitor.visit_enum(EnumTracer {
+            depth: self.depth + 1,
+            variant,
+        })
+    }
+}
+
+struct SeqTracer<'a> {
+    depth: usize,
+    remaining: usize,
+    items: &'a mut Vec<Traced>,
+}
+
+impl<'de> de::SeqAccess<'de> for SeqTracer<'_> {
+    type Error = TraceError;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(
+        &mut self,
+        seed: T,
+    ) -> Result<Option<T::Value>, Self::Error> {
+        if self.remaining == 0 {
+            return Ok(None);
+        }
+        self.remaining -= 1;
+        self.items.push(Traced::default());
+        let out = self.items.last_mut().expect("item just pushed");
+        seed.deserialize(Tracer {
+            depth: self.depth,
+            out,
+        })
+        .map(Some)
+    }
+}
+
+struct MapTracer<'a> {
+    depth: usize,
+    remaining: usize,
+    key: &'a mut Traced,
+    value: &'a mut Traced,
+}
+
+impl<'de> de::MapAccess<'de> for MapTracer<'_> {
+    type Error = TraceError;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, Self::Error> {
+        if self.remaining == 0 {
+            return Ok(None);
+        }
+        self.remaining -= 1;
+        seed.deserialize(Tracer {
+            depth: self.depth,
+            out: &mut *self.key,
+        })
+        .map(Some)
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(
+        &mut self,
+        seed: V,
+    ) -> Result<V::Value, Self::Error> {
+        seed.deserialize(Tracer {
+            depth: self.depth,
+            out: &mut *self.value,
+        })
+    }
+}
+
+struct StructTracer<'a> {
+    depth: usize,
+    fields: &'static [&'static str],
+    traced: &'a mut Vec<(&'static str, Traced)>,
+}
+
+impl<'de> de::MapAccess<'de> for StructTracer<'_> {
+    type Error = TraceError;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, Self::Error> {
+        let Some(&field) = self.fields.get(self.traced.len()) else {
+            return Ok(None);
+        };
+        self.traced.push((field, Traced::default()));
+        seed.deserialize(StrDeserializer::new(field)).map(Some)
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(
+        &mut self,
+        seed: V,
+    ) -> Result<V::Value, Self::Error> {
+        let (_, out) = self
+            .traced
+            .last_mut()
+            .ok_or_else(|| de::Error::custom("value requested before key"))?;
+        seed.deserialize(Tracer {
+            depth: self.depth,
+            out,
+        })
+    }
+}
+
+struct EnumTracer {
+    depth: usize,
+    variant: &'static str,
+}
+
+impl<'de> de::EnumAccess<'de> for EnumTracer {
+    type Error = TraceError;
+    type Variant = Self;
+
+    fn variant_seed<V: DeserializeSeed<'de>>(
+        self,
+        seed: V,
+    ) -> Result<(V::Value, Self::Variant), Self::Error> {
+        let variant = seed.deserialize(StrDeserializer::<TraceError>::new(self.variant))?;
+        Ok((variant, self))
+    }
+}
+
+impl<'de> de::VariantAccess<'de> for EnumTracer {
+    type Error = TraceError;
+
+    fn unit_variant(self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    fn newtype_variant_seed<T: DeserializeSeed<'de>>(
+        self,
+        seed: T,
+    ) -> Result<T::Value, Self::Error> {
+        seed.deserialize(Tracer {
+            depth: self.depth,
+            out: &mut Traced::default(),
+        })
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(
+        self,
+        len: usize,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        de::Deserializer::deserialize_tuple(
+            Tracer {
+                depth: self.depth,
+                out: &mut Traced::default(),
+            },
+            len,
+            visitor,
+        )
+    }
+
+    fn struct_variant<V: Visitor<'de>>(
+        self,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        de::Deserializer::deserialize_struct(
+            Tracer {
+                depth: self.depth,
+                out: &mut Traced::default(),
+            },
+            "",
+            fields,
+            visitor,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+    use std::collections::HashMap;
+
+    #[derive(Debug, Deserialize)]
+    #[allow(dead_code)]
+    #[serde(rename_all = "lowercase")]
+    enum Role {
+        Admin,
+        Member,
+    }
+
+    #[derive(Debug, Deserialize)]
+    #[allow(dead_code)]
+    struct User {
+        name: String,
+        age: Option<u8>,
+        role: Role,
+        tags: Vec<String>,
+        scores: HashMap<String, f64>,
+        friends: Vec<User>,
+    }
+
+    #[test]
+    fn test_schema_for_struct() {
+        let schema = schema_for::<User>();
+        assert_eq!(schema["type"], "object");
+        assert_eq!(
+            schema["required"],
+            json!(["name", "role", "tags", "scores", "friends"])
+        );
+
+        let properties = &schema["properties"];
+        assert_eq!(properties["name"], json!({ "type": "string" }));
+        assert_eq!(
+            properties["age"],
+            json!({ "type": "integer", "minimum": 0 })
+        );
+        assert_eq!(
+            properties["role"],
+            json!({ "type": "string", "enum": ["admin", "member"] })
+        );
+        assert_eq!(
+            properties["tags"],
+            json!({ "type": "array", "items": { "type": "string" } })
+        );
+        assert_eq!(
+            properties["scores"],
+            json!({ "type": "object", "additionalProperties": { "type": "number" } })
+        );
+        assert_eq!(properties["friends"]["type"], "array");
+        assert_eq!(properties["friends"]["items"]["type"], "object");
+    }
+
+    #[test]
+    fn test_schema_for_primitives() {
+        assert_eq!(schema_for::<bool>(), json!({ "type": "boolean" }));
+        assert_eq!(schema_for::<i32>(), json!({ "type": "integer" }));
+        assert_eq!(
+            schema_for::<(u16, String)>(),
+            json!({
+                "type": "array",
+                "prefixItems": [{ "type": "integer", "minimum": 0 }, { "type": "string" }],
+                "minItems": 2,
+                "maxItems": 2,
+            })
+        );
+        assert_eq!(schema_for::<serde_json::Value>(), json!({}));
+    }
+}
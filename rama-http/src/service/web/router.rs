@@ -1,7 +1,13 @@
-use std::{convert::Infallible, sync::Arc};
+use regex::Regex;
+use std::{
+    any::Any,
+    collections::HashMap,
+    convert::Infallible,
+    sync::{Arc, OnceLock},
+};
 
 use crate::{
-    Request, Response,
+    Method, Request, Response,
     matcher::{HttpMatcher, MethodMatcher, UriParams},
 };
 
@@ -11,19 +17,98 @@ use rama_core::{
     context::Extensions,
     matcher::Matcher,
     service::{BoxService, Service},
+    telemetry::tracing,
 };
-use rama_http_types::{Body, StatusCode};
+use rama_http_types::{Body, HeaderValue, StatusCode, Uri};
+use serde_json::{Value, json};
 
-use super::IntoEndpointService;
+use super::endpoint::ExtractorRejection;
+use super::openapi::{OpenApi, Operation, Parameter, ParameterLocation, PathItems};
+use super::response::{IntoResponse, Json, Redirect};
+use super::{IntoEndpointService, StaticService};
 
 /// A basic router that can be used to route requests to different services based on the request path.
 ///
 /// This router uses `matchit::Router` to efficiently match incoming requests
 /// to predefined routes. Each route is associated with an `HttpMatcher`
 /// and a corresponding service handler.
+///
+/// # Precedence
+///
+/// Routes are matched by precedence, regardless of the order in which they are registered:
+/// static segments (`/users/me`) take precedence over parameters (`/users/{id}`)
+/// and wildcards (`/files/{*path}`).
+///
+/// The endpoints registered for the same route are tried in order of descending priority,
+/// see [`Router::match_route_with_priority`], and in order of registration for equal priorities.
+///
+/// Registering a route which conflicts with an existing one, e.g. `/users/{name}` or
+/// `/users/{*path}` next to `/users/{id}`, or the same method twice for the same route
+/// and priority, fails with a [`RouteError`], which the methods not prefixed with `try_` panic with.
+///
+/// # Segment constraints
+///
+/// A parameter can be constrained by a regex, e.g. `/users/{id:[0-9]+}`,
+/// or by a validator registered with [`Router::segment_validator`], e.g. `/sessions/{id:uuid}`.
+/// Endpoints of which a constrained segment is invalid are skipped,
+/// such that e.g. `/users/abc` is not found rather than rejected by the endpoint.
+/// The same route can be registered with different constraints, e.g. `/items/{id:[0-9]+}`
+/// and `/items/{id:[a-z]+}`, as long as the parameter names are the same.
 pub struct Router {
-    routes: MatchitRouter<Vec<(HttpMatcher<Body>, BoxService<Request, Response, Infallible>)>>,
+    routes: MatchitRouter<(MatchedRoute, Vec<RouteEndpoint>)>,
     not_found: Option<BoxService<Request, Response, Infallible>>,
+    method_not_allowed: Option<BoxService<Request, Response, Infallible>>,
+    extraction_failed: Option<BoxService<Request, Response, Infallible>>,
+    handler_error: Option<BoxService<Request, Response, Infallible>>,
+    openapi: Option<OpenApi>,
+    openapi_paths: PathItems,
+    openapi_document: OnceLock<Value>,
+    route_table: Vec<RouteInfo>,
+    route_table_path: Option<String>,
+    route_table_document: OnceLock<Value>,
+    segment_validators: HashMap<String, SegmentValidator>,
+}
+
+/// The route pattern matched by the [`Router`], e.g. `/users/{id}`.
+///
+/// Inserted into the [`Response`] extensions by the [`Router`],
+/// such that outer layers (e.g. access logs) can group requests by route.
+/// For nested routers it contains the full pattern, prefix included.
+///
+/// It is also inserted into the [`Context`] of the endpoint serving the request,
+/// in which case it is the pattern of the innermost [`Router`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MatchedRoute(Arc<str>);
+
+impl MatchedRoute {
+    /// Returns the matched route pattern as a string slice.
+    #[must_use]
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl std::fmt::Display for MatchedRoute {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+/// The original [`Uri`] of a request routed to a nested [`Router`],
+/// of which the path no longer contains the prefix the router is mounted on.
+///
+/// Inserted into the [`Request`] extensions by the [`Router`] when it
+/// forwards a request to a router registered with [`Router::nest`].
+/// For deeper nested routers it remains the uri of the outermost router.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OriginalUri(pub Uri);
+
+impl std::ops::Deref for OriginalUri {
+    type Target = Uri;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
 }
 
 impl std::fmt::Debug for Router {
@@ -39,6 +124,16 @@ impl Router {
         Self {
             routes: MatchitRouter::new(),
             not_found: None,
+            method_not_allowed: None,
+            extraction_failed: None,
+            handler_error: None,
+            openapi: None,
+            openapi_paths: PathItems::default(),
+            openapi_document: OnceLock::new(),
+            route_table: Vec::new(),
+            route_table_path: None,
+            route_table_document: OnceLock::new(),
+            segment_validators: HashMap::new(),
         }
     }
 
@@ -50,8 +145,7 @@ impl Router {
     where
         I: IntoEndpointService<T>,
     {
-        let matcher = HttpMatcher::method(MethodMatcher::GET);
-        self.match_route(path, matcher, service)
+        self.method_route(path, Method::GET, MethodMatcher::GET, service)
     }
 
     /// add a POST route to the router.
@@ -60,8 +154,7 @@ impl Router {
     where
         I: IntoEndpointService<T>,
     {
-        let matcher = HttpMatcher::method(MethodMatcher::POST);
-        self.match_route(path, matcher, service)
+        self.method_route(path, Method::POST, MethodMatcher::POST, service)
     }
 
     /// add a PUT route to the router.
@@ -70,8 +163,7 @@ impl Router {
     where
         I: IntoEndpointService<T>,
     {
-        let matcher = HttpMatcher::method(MethodMatcher::PUT);
-        self.match_route(path, matcher, service)
+        self.method_route(path, Method::PUT, MethodMatcher::PUT, service)
     }
 
     /// add a DELETE route to the router.
@@ -80,8 +172,7 @@ impl Router {
     where
         I: IntoEndpointService<T>,
     {
-        let matcher = HttpMatcher::method(MethodMatcher::DELETE);
-        self.match_route(path, matcher, service)
+        self.method_route(path, Method::DELETE, MethodMatcher::DELETE, service)
     }
 
     /// add a PATCH route to the router.
@@ -90,8 +181,7 @@ impl Router {
     where
         I: IntoEndpointService<T>,
     {
-        let matcher = HttpMatcher::method(MethodMatcher::PATCH);
-        self.match_route(path, matcher, service)
+        self.method_route(path, Method::PATCH, MethodMatcher::PATCH, service)
     }
 
     /// add a HEAD route to the router.
@@ -100,8 +190,7 @@ impl Router {
     where
         I: IntoEndpointService<T>,
     {
-        let matcher = HttpMatcher::method(MethodMatcher::HEAD);
-        self.match_route(path, matcher, service)
+        self.method_route(path, Method::HEAD, MethodMatcher::HEAD, service)
     }
 
     /// add a OPTIONS route to the router.
@@ -110,8 +199,7 @@ impl Router {
     where
         I: IntoEndpointService<T>,
     {
-        let matcher = HttpMatcher::method(MethodMatcher::OPTIONS);
-        self.match_route(path, matcher, service)
+        self.method_route(path, Method::OPTIONS, MethodMatcher::OPTIONS, service)
     }
 
     /// add a TRACE route to the router.
@@ -120,8 +208,7 @@ impl Router {
     where
         I: IntoEndpointService<T>,
     {
-        let matcher = HttpMatcher::method(MethodMatcher::TRACE);
-        self.match_route(path, matcher, service)
+        self.method_route(path, Method::TRACE, MethodMatcher::TRACE, service)
     }
 
     /// add a CONNECT route to the router.
@@ -130,19 +217,46 @@ impl Router {
     where
         I: IntoEndpointService<T>,
     {
-        let matcher = HttpMatcher::method(MethodMatcher::CONNECT);
-        self.match_route(path, matcher, service)
+        self.method_route(path, Method::CONNECT, MethodMatcher::CONNECT, service)
     }
 
-    /// register a nested router under a prefix.
+    /// register a nested router (or other service) under a prefix.
     ///
-    /// The prefix is used to match the request path and strip it from the request URI.
+    /// The prefix is used to match the request path and strip it from the request URI,
+    /// such that the nested router only matches on the remainder of the path.
+    /// The query is preserved, while the original URI remains available
+    /// as the [`OriginalUri`] request extension.
+    ///
+    /// The prefix can contain parameters as well, e.g. `/users/{id}`.
+    /// The OpenAPI operations of a nested [`Router`] are included,
+    /// prefixed, in the ones of this router.
     #[must_use]
-    pub fn sub<I, T>(self, prefix: &str, service: I) -> Self
+    pub fn nest<I, T>(mut self, prefix: &str, service: I) -> Self
     where
-        I: IntoEndpointService<T>,
+        I: IntoEndpointService<T> + 'static,
     {
-        let path = format!("{}/{}", prefix.trim().trim_end_matches(['/']), "{*nest}");
+        let path = format!(
+            "{}/{{*{NEST_PARAM}}}",
+            prefix.trim().trim_end_matches(['/'])
+        );
+        let (layers, handler) = service.describe_route();
+        let nested_routes = match (&service as &dyn Any).downcast_ref::<Self>() {
+            Some(router) => {
+                self.openapi_paths.nest(prefix, &router.openapi_paths);
+                router
+                    .route_table
+                    .iter()
+                    .map(|route| route.nested(prefix))
+                    .collect()
+            }
+            None => vec![RouteInfo {
+                pattern: format!("/{}", path.trim().trim_matches('/')),
+                method: None,
+                priority: 0,
+                layers,
+                handler,
+            }],
+        };
         let nested = Arc::new(service.into_endpoint_service().boxed());
 
         let nested_router_service = NestedRouterService {
@@ -150,37 +264,439 @@ impl Router {
             nested,
         };
 
+        // the routes of the nested service replace the ones registered to forward to it
+        let route_count = self.route_table.len();
+        let mut router = self
+            .match_route(
+                prefix,
+                HttpMatcher::custom(true),
+                nested_router_service.clone(),
+            )
+            .add_route(
+                &path,
+                HttpMatcher::custom(true),
+                None,
+                0,
+                nested_router_service,
+            )
+            .unwrap_or_else(|err| panic!("{err}"));
+        router.route_table.truncate(route_count);
+        router.route_table.extend(nested_routes);
+        router
+    }
+
+    /// register a nested router under a prefix.
+    ///
+    /// Alias of [`Router::nest`].
+    #[must_use]
+    pub fn sub<I, T>(self, prefix: &str, service: I) -> Self
+    where
+        I: IntoEndpointService<T> + 'static,
+    {
+        self.nest(prefix, service)
+    }
+
+    /// redirect the requests for the given path to the given location,
+    /// using the given redirection status code, e.g. `308` for a permanent redirect.
+    ///
+    /// The route matches requests of any method.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rama_http::service::web::Router;
+    ///
+    /// let router = Router::new()
+    ///     .get("/new", "new page")
+    ///     .redirect("/old", "/new", 308);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics in case the status is not a redirection (`3xx`) status code,
+    /// or the location is not a valid header value.
+    #[must_use]
+    pub fn redirect(self, path: &str, location: &str, status: u16) -> Self {
+        let status = StatusCode::from_u16(status)
+            .ok()
+            .filter(StatusCode::is_redirection)
+            .unwrap_or_else(|| panic!("redirect route `{path}`: {status} is not a redirection"));
+        let location = HeaderValue::from_str(location)
+            .unwrap_or_else(|err| panic!("redirect route `{path}`: invalid location: {err}"));
+        self.match_route(
+            path,
+            HttpMatcher::custom(true),
+            StaticService::new(Redirect::with_status_code(status, location)),
+        )
+    }
+
+    /// respond to the requests for the given path with the given status code and an empty body,
+    /// e.g. `410 Gone` for a resource which was removed or `204 No Content` for `/favicon.ico`.
+    ///
+    /// The route matches requests of any method.
+    #[must_use]
+    pub fn static_status(self, path: &str, status: StatusCode) -> Self {
+        self.match_route(path, HttpMatcher::custom(true), StaticService::new(status))
+    }
+
+    /// respond to the requests for the given path with the given static response,
+    /// e.g. a body with its headers.
+    ///
+    /// The route matches requests of any method, use e.g. [`Router::get`]
+    /// to serve a static response for a single method instead.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rama_http::header::CONTENT_TYPE;
+    /// use rama_http::service::web::Router;
+    ///
+    /// let router = Router::new().static_response(
+    ///     "/robots.txt",
+    ///     ([(CONTENT_TYPE, "text/plain")], "User-agent: *\nDisallow: /"),
+    /// );
+    /// ```
+    #[must_use]
+    pub fn static_response<R>(self, path: &str, response: R) -> Self
+    where
+        R: IntoResponse + Clone + Send + Sync + 'static,
+    {
         self.match_route(
-            prefix,
+            path,
             HttpMatcher::custom(true),
-            nested_router_service.clone(),
+            StaticService::new(response),
         )
-        .match_route(&path, HttpMatcher::custom(true), nested_router_service)
+    }
+
+    /// serve the OpenAPI document of the routes registered with a method
+    /// (e.g. [`Router::get`]), including those of nested routers.
+    ///
+    /// See [`openapi`](super::openapi) for more information.
+    #[must_use]
+    pub fn openapi(mut self, openapi: OpenApi) -> Self {
+        self.openapi = Some(openapi);
+        self
+    }
+
+    fn method_route<I, T>(
+        self,
+        path: &str,
+        method: Method,
+        matcher: MethodMatcher,
+        service: I,
+    ) -> Self
+    where
+        I: IntoEndpointService<T>,
+    {
+        self.try_add_route(path, HttpMatcher::method(matcher), Some(method), 0, service)
+            .unwrap_or_else(|err| panic!("{err}"))
     }
 
     /// add a route to the router with it's matcher and service.
+    ///
+    /// # Panics
+    ///
+    /// Panics in case the route is invalid or conflicts with an existing one,
+    /// see [`Router::try_match_route`] for a fallible alternative.
+    #[must_use]
+    pub fn match_route<I, T>(self, path: &str, matcher: HttpMatcher<Body>, service: I) -> Self
+    where
+        I: IntoEndpointService<T>,
+    {
+        self.match_route_with_priority(path, matcher, 0, service)
+    }
+
+    /// add a route to the router with it's matcher and service,
+    /// failing in case the route is invalid or conflicts with an existing one.
+    pub fn try_match_route<I, T>(
+        self,
+        path: &str,
+        matcher: HttpMatcher<Body>,
+        service: I,
+    ) -> Result<Self, RouteError>
+    where
+        I: IntoEndpointService<T>,
+    {
+        self.try_match_route_with_priority(path, matcher, 0, service)
+    }
+
+    /// add a route to the router with it's matcher, priority and service.
+    ///
+    /// Endpoints of the same route are tried in order of descending priority,
+    /// such that a higher priority can be used to override an endpoint registered earlier.
+    /// Endpoints added without an explicit priority have a priority of `0`.
+    ///
+    /// # Panics
+    ///
+    /// Panics in case the route is invalid or conflicts with an existing one,
+    /// see [`Router::try_match_route_with_priority`] for a fallible alternative.
     #[must_use]
-    pub fn match_route<I, T>(mut self, path: &str, matcher: HttpMatcher<Body>, service: I) -> Self
+    pub fn match_route_with_priority<I, T>(
+        self,
+        path: &str,
+        matcher: HttpMatcher<Body>,
+        priority: i32,
+        service: I,
+    ) -> Self
     where
         I: IntoEndpointService<T>,
     {
-        let service = service.into_endpoint_service().boxed();
+        self.try_match_route_with_priority(path, matcher, priority, service)
+            .unwrap_or_else(|err| panic!("{err}"))
+    }
 
-        let path = path.trim().trim_matches('/');
-        let path = format!("/{path}");
+    /// add a route to the router with it's matcher, priority and service,
+    /// failing in case the route is invalid or conflicts with an existing one.
+    ///
+    /// See [`Router::match_route_with_priority`] for more information.
+    pub fn try_match_route_with_priority<I, T>(
+        self,
+        path: &str,
+        matcher: HttpMatcher<Body>,
+        priority: i32,
+        service: I,
+    ) -> Result<Self, RouteError>
+    where
+        I: IntoEndpointService<T>,
+    {
+        self.try_add_route(path, matcher, None, priority, service)
+    }
 
-        if let Ok(matched) = self.routes.at_mut(&path) {
-            matched.value.push((matcher, service));
+    fn try_add_route<I, T>(
+        self,
+        path: &str,
+        matcher: HttpMatcher<Body>,
+        method: Option<Method>,
+        priority: i32,
+        service: I,
+    ) -> Result<Self, RouteError>
+    where
+        I: IntoEndpointService<T>,
+    {
+        // the parameter is reserved for the remainder of the path of nested routes
+        let captures_nest_param = path.split('{').skip(1).any(|param| {
+            param.trim_start_matches('*').split([':', '}']).next() == Some(NEST_PARAM)
+        });
+        if captures_nest_param {
+            return Err(RouteError {
+                path: format!("/{}", path.trim().trim_matches('/')),
+                kind: RouteErrorKind::ReservedParam,
+            });
+        }
+        self.add_route(path, matcher, method, priority, service)
+    }
+
+    fn add_route<I, T>(
+        mut self,
+        path: &str,
+        matcher: HttpMatcher<Body>,
+        method: Option<Method>,
+        priority: i32,
+        service: I,
+    ) -> Result<Self, RouteError>
+    where
+        I: IntoEndpointService<T>,
+    {
+        let pattern = path.trim().trim_matches('/');
+        let pattern = format!("/{pattern}");
+        let (path, constraints) = self.parse_segment_constraints(&pattern)?;
+
+        let operation = method.clone().map(|method| {
+            let mut operation = Operation::new(method, &path);
+            for constraint in &constraints {
+                if let SegmentCheck::Regex(regex) = &constraint.check {
+                    operation.add_parameter(Parameter::new(
+                        constraint.param.as_str(),
+                        ParameterLocation::Path,
+                        true,
+                        json!({ "type": "string", "pattern": regex.as_str() }),
+                    ));
+                }
+            }
+            service.describe_openapi(&mut operation);
+            operation
+        });
+        let (layers, handler) = service.describe_route();
+        let info = RouteInfo {
+            pattern,
+            method: method.clone(),
+            priority,
+            layers,
+            handler,
+        };
+        let endpoint = RouteEndpoint {
+            matcher,
+            method,
+            priority,
+            constraints,
+            service: service.into_endpoint_service().boxed(),
+        };
+
+        // matching the pattern as a path also matches other patterns,
+        // e.g. `/users/{id}` for `/users/me`, which are distinct routes
+        let existing = self
+            .routes
+            .at_mut(&path)
+            .ok()
+            .map(|matched| matched.value)
+            .filter(|(route, _)| route.as_str() == path);
+
+        if let Some((_, endpoints)) = existing {
+            if let Some(method) = &endpoint.method
+                && endpoints.iter().any(|e| {
+                    e.priority == priority
+                        && e.method.as_ref() == Some(method)
+                        && e.constraints == endpoint.constraints
+                })
+            {
+                return Err(RouteError {
+                    path: info.pattern,
+                    kind: RouteErrorKind::DuplicateMethod {
+                        method: method.clone(),
+                        priority,
+                    },
+                });
+            }
+            let index = endpoints
+                .iter()
+                .position(|e| e.priority < priority)
+                .unwrap_or(endpoints.len());
+            endpoints.insert(index, endpoint);
         } else {
-            self.routes
-                .insert(path, vec![(matcher, service)])
-                .expect("Failed to add route");
+            let route = MatchedRoute(Arc::from(path.as_str()));
+            if let Err(err) = self.routes.insert(path.clone(), (route, vec![endpoint])) {
+                return Err(RouteError {
+                    path: info.pattern,
+                    kind: RouteErrorKind::Insert(err),
+                });
+            }
+        }
+
+        if let Some(operation) = operation {
+            self.openapi_paths.insert(&path, operation);
         }
+        self.route_table.push(info);
+        Ok(self)
+    }
 
+    /// register a named validator for path segments, which can be used
+    /// in the patterns of routes registered afterwards as `{name:validator}`.
+    ///
+    /// A request is only routed to an endpoint in case all its constrained segments are valid.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rama_http::service::web::Router;
+    ///
+    /// let router = Router::new()
+    ///     .segment_validator("uuid", |segment| uuid::Uuid::parse_str(segment).is_ok())
+    ///     .get("/sessions/{id:uuid}", "session");
+    /// ```
+    #[must_use]
+    pub fn segment_validator(
+        mut self,
+        name: &str,
+        validator: impl Fn(&str) -> bool + Send + Sync + 'static,
+    ) -> Self {
+        self.segment_validators
+            .insert(name.to_owned(), Arc::new(validator));
+        self
+    }
+
+    /// split the segment constraints from a pattern,
+    /// returning the pattern without constraints as understood by `matchit`.
+    fn parse_segment_constraints(
+        &self,
+        pattern: &str,
+    ) -> Result<(String, Vec<SegmentConstraint>), RouteError> {
+        let mut path = String::with_capacity(pattern.len());
+        let mut constraints = Vec::new();
+
+        let mut rest = pattern;
+        while let Some(start) = rest.find('{') {
+            path.push_str(&rest[..start]);
+            rest = &rest[start..];
+            if let Some(escaped) = rest.strip_prefix("{{") {
+                path.push_str("{{");
+                rest = escaped;
+                continue;
+            }
+
+            // find the closing brace of the parameter, allowing braces in its constraint
+            let mut depth = 0usize;
+            let Some(end) = rest.char_indices().find_map(|(index, c)| {
+                match c {
+                    '{' => depth += 1,
+                    '}' => {
+                        depth -= 1;
+                        if depth == 0 {
+                            return Some(index);
+                        }
+                    }
+                    _ => (),
+                }
+                None
+            }) else {
+                // let matchit report the invalid parameter
+                path.push_str(rest);
+                rest = "";
+                break;
+            };
+
+            let param = &rest[1..end];
+            rest = &rest[end + 1..];
+            let Some((name, constraint)) = param.split_once(':') else {
+                path.push('{');
+                path.push_str(param);
+                path.push('}');
+                continue;
+            };
+
+            let check = match self.segment_validators.get(constraint) {
+                Some(validator) => SegmentCheck::Validator(validator.clone()),
+                None => match Regex::new(&format!("^(?:{constraint})$")) {
+                    Ok(regex) => SegmentCheck::Regex(regex),
+                    Err(err) => {
+                        return Err(RouteError {
+                            path: pattern.to_owned(),
+                            kind: RouteErrorKind::InvalidConstraint(err),
+                        });
+                    }
+                },
+            };
+            constraints.push(SegmentConstraint {
+                param: name.trim_start_matches('*').to_owned(),
+                constraint: constraint.to_owned(),
+                check,
+            });
+            path.push('{');
+            path.push_str(name);
+            path.push('}');
+        }
+        path.push_str(rest);
+
+        Ok((path, constraints))
+    }
+
+    /// returns the routes registered to this router, including those of nested routers,
+    /// in order of registration.
+    #[must_use]
+    pub fn routes(&self) -> &[RouteInfo] {
+        &self.route_table
+    }
+
+    /// serve the [`Router::routes`] as a JSON array at the given path, e.g. `/debug/routes`,
+    /// such that a deployment can verify the routes it actually serves.
+    #[must_use]
+    pub fn route_table(mut self, path: &str) -> Self {
+        self.route_table_path = Some(format!("/{}", path.trim().trim_matches('/')));
         self
     }
 
     /// use the provided service when no route matches the request.
+    ///
+    /// [`RouteFailure::NotFound`] is inserted into the [`Context`] passed to the service.
     #[must_use]
     pub fn not_found<I, T>(mut self, service: I) -> Self
     where
@@ -189,11 +705,297 @@ impl Router {
         self.not_found = Some(service.into_endpoint_service().boxed());
         self
     }
+
+    /// use the provided service when the path of the request matches a route,
+    /// but its method matches none of the methods registered for that route.
+    ///
+    /// [`RouteFailure::MethodNotAllowed`] is inserted into the [`Context`] passed to the service,
+    /// e.g. to respond with a `405 Method Not Allowed` and the `Allow` header.
+    /// Such requests are handled as not found in case no service is registered.
+    #[must_use]
+    pub fn method_not_allowed<I, T>(mut self, service: I) -> Self
+    where
+        I: IntoEndpointService<T>,
+    {
+        self.method_not_allowed = Some(service.into_endpoint_service().boxed());
+        self
+    }
+
+    /// use the provided service to respond in place of an endpoint
+    /// of which an extractor rejected the request.
+    ///
+    /// [`RouteFailure::ExtractionFailed`] is inserted into the [`Context`] passed to the service,
+    /// together with the original request, of which the body is empty
+    /// as it was consumed by the endpoint.
+    #[must_use]
+    pub fn extraction_failed<I, T>(mut self, service: I) -> Self
+    where
+        I: IntoEndpointService<T>,
+    {
+        self.extraction_failed = Some(service.into_endpoint_service().boxed());
+        self
+    }
+
+    /// use the provided service to respond in place of an endpoint
+    /// which responded with a server error (`5xx`).
+    ///
+    /// [`RouteFailure::HandlerError`] is inserted into the [`Context`] passed to the service,
+    /// together with the original request, of which the body is empty
+    /// as it was consumed by the endpoint.
+    #[must_use]
+    pub fn handler_error<I, T>(mut self, service: I) -> Self
+    where
+        I: IntoEndpointService<T>,
+    {
+        self.handler_error = Some(service.into_endpoint_service().boxed());
+        self
+    }
+
+    async fn handle_endpoint_failure(
+        &self,
+        mut ctx: Context,
+        req: Request,
+        res: Response,
+    ) -> Result<Response, Infallible> {
+        let status = res.status();
+        let handler = if res.extensions().get::<ExtractorRejection>().is_some() {
+            self.extraction_failed
+                .as_ref()
+                .map(|handler| (handler, RouteFailure::ExtractionFailed { status }))
+        } else if status.is_server_error() {
+            self.handler_error
+                .as_ref()
+                .map(|handler| (handler, RouteFailure::HandlerError { status }))
+        } else {
+            None
+        };
+
+        match handler {
+            Some((handler, failure)) => {
+                ctx.insert(failure);
+                handler.serve(ctx, req).await
+            }
+            None => Ok(res),
+        }
+    }
+}
+
+/// The failure for which a failure handler of the [`Router`] is called,
+/// inserted into the [`Context`] passed to that handler.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum RouteFailure {
+    /// No route matched the request, see [`Router::not_found`].
+    NotFound,
+    /// The route matched, but not the method of the request,
+    /// see [`Router::method_not_allowed`].
+    MethodNotAllowed {
+        /// The methods registered for the matched route.
+        allowed: Vec<Method>,
+    },
+    /// An extractor of the endpoint rejected the request,
+    /// see [`Router::extraction_failed`].
+    ExtractionFailed {
+        /// The status of the rejection.
+        status: StatusCode,
+    },
+    /// The endpoint responded with a server error, see [`Router::handler_error`].
+    HandlerError {
+        /// The status the endpoint responded with.
+        status: StatusCode,
+    },
+}
+
+/// Copy the method, uri, version, headers and extensions of a request.
+fn request_head(req: &Request) -> Request {
+    let mut head = Request::new(Body::empty());
+    *head.method_mut() = req.method().clone();
+    *head.uri_mut() = req.uri().clone();
+    *head.version_mut() = req.version();
+    *head.headers_mut() = req.headers().clone();
+    *head.extensions_mut() = req.extensions().clone();
+    head
 }
 
+/// A route registered to a [`Router`], as returned by [`Router::routes`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RouteInfo {
+    pattern: String,
+    method: Option<Method>,
+    priority: i32,
+    layers: Vec<&'static str>,
+    handler: &'static str,
+}
+
+impl RouteInfo {
+    /// Returns the (normalized) pattern of the route, e.g. `/users/{id}`.
+    #[must_use]
+    pub fn pattern(&self) -> &str {
+        &self.pattern
+    }
+
+    /// Returns the method of the route,
+    /// or `None` for routes registered with a custom matcher.
+    #[must_use]
+    pub fn method(&self) -> Option<&Method> {
+        self.method.as_ref()
+    }
+
+    /// Returns the priority of the route, see [`Router::match_route_with_priority`].
+    #[must_use]
+    pub fn priority(&self) -> i32 {
+        self.priority
+    }
+
+    /// Returns the names of the layers wrapping the handler of the route,
+    /// outermost first, e.g. `["trace", "timeout"]`.
+    ///
+    /// These are recorded when the route is added, for services
+    /// wrapped using a [`LayeredEndpoint`]. Other services are listed without layers.
+    ///
+    /// [`LayeredEndpoint`]: super::LayeredEndpoint
+    #[must_use]
+    pub fn layers(&self) -> &[&'static str] {
+        &self.layers
+    }
+
+    /// Returns the type name of the handler (service or function) of the route,
+    /// without the layers wrapping it in case of a [`LayeredEndpoint`].
+    ///
+    /// [`LayeredEndpoint`]: super::LayeredEndpoint
+    #[must_use]
+    pub fn handler(&self) -> &'static str {
+        self.handler
+    }
+
+    fn nested(&self, prefix: &str) -> Self {
+        let prefix = prefix.trim().trim_matches('/');
+        let pattern = if prefix.is_empty() {
+            self.pattern.clone()
+        } else {
+            format!("/{prefix}{}", self.pattern.trim_end_matches('/'))
+        };
+        Self {
+            pattern,
+            ..self.clone()
+        }
+    }
+}
+
+struct RouteEndpoint {
+    matcher: HttpMatcher<Body>,
+    method: Option<Method>,
+    priority: i32,
+    constraints: Vec<SegmentConstraint>,
+    service: BoxService<Request, Response, Infallible>,
+}
+
+impl RouteEndpoint {
+    fn matches_segments(&self, params: Option<&UriParams>) -> bool {
+        self.constraints.iter().all(|constraint| {
+            let segment = params
+                .and_then(|params| params.get(&constraint.param))
+                .unwrap_or_default();
+            match &constraint.check {
+                SegmentCheck::Regex(regex) => regex.is_match(segment),
+                SegmentCheck::Validator(validator) => validator(segment),
+            }
+        })
+    }
+}
+
+type SegmentValidator = Arc<dyn Fn(&str) -> bool + Send + Sync + 'static>;
+
+/// The constraint of a path parameter, e.g. `{id:[0-9]+}`.
+struct SegmentConstraint {
+    param: String,
+    constraint: String,
+    check: SegmentCheck,
+}
+
+impl PartialEq for SegmentConstraint {
+    fn eq(&self, other: &Self) -> bool {
+        self.param == other.param && self.constraint == other.constraint
+    }
+}
+
+enum SegmentCheck {
+    Regex(Regex),
+    Validator(SegmentValidator),
+}
+
+/// The error returned when a route cannot be registered to a [`Router`].
+#[derive(Debug)]
+pub struct RouteError {
+    path: String,
+    kind: RouteErrorKind,
+}
+
+#[derive(Debug)]
+enum RouteErrorKind {
+    Insert(matchit::InsertError),
+    InvalidConstraint(regex::Error),
+    DuplicateMethod { method: Method, priority: i32 },
+    ReservedParam,
+}
+
+impl RouteError {
+    /// Returns the (normalized) path of the route which could not be registered.
+    #[must_use]
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+}
+
+impl std::fmt::Display for RouteError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let path = &self.path;
+        match &self.kind {
+            RouteErrorKind::Insert(matchit::InsertError::Conflict { with }) => write!(
+                f,
+                "route `{path}` conflicts with registered route `{with}`: \
+                 a position can only be captured by one parameter or wildcard name"
+            ),
+            RouteErrorKind::Insert(err) => write!(f, "invalid route `{path}`: {err}"),
+            RouteErrorKind::InvalidConstraint(err) => write!(
+                f,
+                "invalid segment constraint in route `{path}`: \
+                 neither a registered validator nor a valid regex: {err}"
+            ),
+            RouteErrorKind::DuplicateMethod { method, priority } => write!(
+                f,
+                "route `{path}` already has a {method} endpoint with priority {priority}: \
+                 use a higher priority to override it"
+            ),
+            RouteErrorKind::ReservedParam => write!(
+                f,
+                "route `{path}` captures the `{NEST_PARAM}` parameter, \
+                 which is reserved for nested routes"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for RouteError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match &self.kind {
+            RouteErrorKind::Insert(err) => Some(err),
+            RouteErrorKind::InvalidConstraint(err) => Some(err),
+            RouteErrorKind::DuplicateMethod { .. } | RouteErrorKind::ReservedParam => None,
+        }
+    }
+}
+
+/// The name of the catch-all parameter capturing the remainder of the path of nested routes,
+/// which cannot be captured by user routes.
+const NEST_PARAM: &str = "__rama_nest";
+
+/// The (undecoded) remainder of the path of a request routed to a nested service.
+#[derive(Debug, Clone)]
+struct NestedPath(String);
+
 #[derive(Debug, Clone)]
 struct NestedRouterService {
-    #[expect(unused)]
     prefix: Arc<str>,
     nested: Arc<BoxService<Request, Response, Infallible>>,
 }
@@ -207,25 +1009,36 @@ impl Service<Request> for NestedRouterService {
         mut ctx: Context,
         mut req: Request,
     ) -> Result<Self::Response, Self::Error> {
-        let params: UriParams = match ctx.remove::<UriParams>() {
-            Some(params) => {
-                let nested_path = params.get("nest").unwrap_or_default();
-
-                let filtered_params: UriParams =
-                    params.iter().filter(|(key, _)| *key != "nest").collect();
+        let nested_path = ctx.remove::<NestedPath>();
+        let nested_path = nested_path.as_ref().map_or("", |path| path.0.as_str());
 
-                // build the nested path and update the request URI
-                let path = format!("/{nested_path}");
-                *req.uri_mut() = path.parse().unwrap();
+        if req.extensions().get::<OriginalUri>().is_none() {
+            let uri = req.uri().clone();
+            req.extensions_mut().insert(OriginalUri(uri));
+        }
 
-                filtered_params
-            }
-            None => UriParams::default(),
+        // build the nested path, from the undecoded remainder of the path, and update the request URI
+        let path_and_query = match req.uri().query() {
+            Some(query) => format!("/{nested_path}?{query}"),
+            None => format!("/{nested_path}"),
         };
+        match path_and_query.parse() {
+            Ok(uri) => *req.uri_mut() = uri,
+            Err(err) => {
+                tracing::debug!("invalid uri for nested route ({path_and_query}): {err}");
+                return Ok(StatusCode::BAD_REQUEST.into_response());
+            }
+        }
 
-        ctx.insert(params);
-
-        self.nested.serve(ctx, req).await
+        let mut res = self.nested.serve(ctx, req).await?;
+        let prefix = self.prefix.trim().trim_matches('/');
+        if let Some(route) = res.extensions_mut().get_mut::<MatchedRoute>() {
+            if !prefix.is_empty() {
+                let path = route.as_str().trim_end_matches('/');
+                *route = MatchedRoute(Arc::from(format!("/{prefix}{path}")));
+            }
+        }
+        Ok(res)
     }
 }
 
@@ -240,30 +1053,111 @@ impl Service<Request> for Router {
     type Error = Infallible;
 
     async fn serve(&self, mut ctx: Context, req: Request) -> Result<Self::Response, Self::Error> {
+        if let Some(openapi) = &self.openapi
+            && (req.method() == Method::GET || req.method() == Method::HEAD)
+            && req.uri().path() == openapi.served_path()
+        {
+            let document = self
+                .openapi_document
+                .get_or_init(|| openapi.document(&self.openapi_paths));
+            return Ok(Json(document.clone()).into_response());
+        }
+
+        if let Some(path) = &self.route_table_path
+            && (req.method() == Method::GET || req.method() == Method::HEAD)
+            && req.uri().path() == path
+        {
+            let document = self.route_table_document.get_or_init(|| {
+                self.route_table
+                    .iter()
+                    .map(|route| {
+                        json!({
+                            "pattern": route.pattern(),
+                            "method": route.method().map(Method::as_str),
+                            "priority": route.priority(),
+                            "layers": route.layers(),
+                            "handler": route.handler(),
+                        })
+                    })
+                    .collect()
+            });
+            return Ok(Json(document.clone()).into_response());
+        }
+
         let mut ext = Extensions::new();
 
         if let Ok(matched) = self.routes.at(req.uri().path()) {
-            let uri_params = matched.params.iter();
+            // the nested path is not a parameter, but becomes the (encoded) uri of the nested service
+            ctx.remove::<NestedPath>();
+            if let Some(nested_path) = matched.params.get(NEST_PARAM) {
+                ctx.insert(NestedPath(nested_path.to_owned()));
+            }
 
-            let params: UriParams = match ctx.remove::<UriParams>() {
-                Some(mut params) => {
-                    params.extend(uri_params);
-                    params
-                }
-                None => uri_params.collect(),
-            };
+            // decoded just like the parameters captured by the `PathMatcher`
+            let uri_params = matched
+                .params
+                .iter()
+                .filter(|(name, _)| *name != NEST_PARAM)
+                .map(|(name, value)| {
+                    let value = percent_encoding::percent_decode_str(value)
+                        .decode_utf8()
+                        .map(|value| value.into_owned())
+                        .unwrap_or_else(|_| value.to_owned());
+                    (name, value)
+                });
+
+            let mut params = ctx.remove::<UriParams>().unwrap_or_default();
+            params.extend(uri_params);
             ctx.insert(params);
 
-            for (matcher, service) in matched.value.iter() {
+            let (route, endpoints) = matched.value;
+            // endpoints of which the constrained segments are invalid are not part of the route
+            let endpoints: Vec<_> = endpoints
+                .iter()
+                .filter(|endpoint| endpoint.matches_segments(ctx.get::<UriParams>()))
+                .collect();
+            for RouteEndpoint {
+                matcher, service, ..
+            } in endpoints.iter().copied()
+            {
                 if matcher.matches(Some(&mut ext), &ctx, &req) {
                     ctx.extend(ext);
-                    return service.serve(ctx, req).await;
+                    ctx.insert(route.clone());
+
+                    // the request is consumed by the endpoint,
+                    // so keep a copy of its head for the failure handlers
+                    let failure_input = (self.extraction_failed.is_some()
+                        || self.handler_error.is_some())
+                    .then(|| (ctx.clone(), request_head(&req)));
+
+                    let mut res = service.serve(ctx, req).await?;
+                    if let Some((ctx, req)) = failure_input {
+                        res = self.handle_endpoint_failure(ctx, req, res).await?;
+                    }
+                    if res.extensions().get::<MatchedRoute>().is_none() {
+                        res.extensions_mut().insert(route.clone());
+                    }
+                    return Ok(res);
                 }
                 ext.clear();
             }
+
+            if let Some(method_not_allowed) = &self.method_not_allowed {
+                let mut allowed: Vec<Method> = Vec::new();
+                for method in endpoints.iter().filter_map(|e| e.method.as_ref()) {
+                    if !allowed.contains(method) {
+                        allowed.push(method.clone());
+                    }
+                }
+                if !allowed.is_empty() {
+                    ctx.insert(RouteFailure::MethodNotAllowed { allowed });
+                    return method_not_allowed.serve(ctx, req).await;
+                }
+            }
         }
 
         if let Some(not_found) = &self.not_found {
+            ctx.insert(RouteFailure::NotFound);
             not_found.serve(ctx, req).await
         } else {
             Ok(Response::builder()
@@ -511,4 +1405,545 @@ mod tests {
             }
         }
     }
+
+    #[tokio::test]
+    async fn test_router_typed_path_params() {
+        use crate::service::web::extract::Path;
+
+        #[derive(Debug, serde::Deserialize)]
+        struct PostParams {
+            id: u64,
+            post_id: String,
+        }
+
+        let app = Router::new()
+            .get(
+                "/users/{id}/posts/{post_id}",
+                async |Path(params): Path<PostParams>| {
+                    format!("Get Post: {} of User: {}", params.post_id, params.id)
+                },
+            )
+            .get("/files/{*path}", async |Path(path): Path<String>| {
+                format!("Get File: {path}")
+            });
+
+        let cases = [
+            (
+                "/users/42/posts/hello%20world",
+                StatusCode::OK,
+                "Get Post: hello world of User: 42",
+            ),
+            ("/files/a/b/c.txt", StatusCode::OK, "Get File: a/b/c.txt"),
+        ];
+        for (path, expected_status, expected_body) in cases {
+            let req = Request::get(path).body(Body::empty()).unwrap();
+            let res = app.serve(Context::default(), req).await.unwrap();
+            assert_eq!(res.status(), expected_status, "path = {path}");
+            let body = res.into_body().collect().await.unwrap().to_bytes();
+            assert_eq!(body, expected_body, "path = {path}");
+        }
+
+        let req = Request::get("/users/abc/posts/1")
+            .body(Body::empty())
+            .unwrap();
+        let res = app.serve(Context::default(), req).await.unwrap();
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_router_nest_original_uri() {
+        let app = Router::new().nest(
+            "/api/v1",
+            Router::new().nest(
+                "/users",
+                Router::new().get(
+                    "/{user_id}",
+                    service_fn(|ctx: Context, req: Request| async move {
+                        let original_uri = req.extensions().get::<OriginalUri>().unwrap();
+                        let user_id = ctx.get::<UriParams>().unwrap().get("user_id").unwrap();
+                        Ok::<_, Infallible>(
+                            Response::builder()
+                                .status(200)
+                                .body(Body::from(format!(
+                                    "{} | {} | {user_id}",
+                                    original_uri.0,
+                                    req.uri()
+                                )))
+                                .unwrap(),
+                        )
+                    }),
+                ),
+            ),
+        );
+
+        let req = Request::get("/api/v1/users/123?verbose=1")
+            .body(Body::empty())
+            .unwrap();
+        let res = app.serve(Context::default(), req).await.unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+        let body = res.into_body().collect().await.unwrap().to_bytes();
+        assert_eq!(body, "/api/v1/users/123?verbose=1 | /123?verbose=1 | 123");
+    }
+
+    #[tokio::test]
+    async fn test_router_nest_encoded_path() {
+        let app = Router::new().nest(
+            "/api",
+            Router::new().get(
+                "/files/{*path}",
+                service_fn(async |req: Request| {
+                    Ok::<_, Infallible>(req.uri().to_string().into_response())
+                }),
+            ),
+        );
+
+        let req = Request::get("/api/files/a%20b%2Fc?x=1")
+            .body(Body::empty())
+            .unwrap();
+        let res = app.serve(Context::default(), req).await.unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+        let body = res.into_body().collect().await.unwrap().to_bytes();
+        assert_eq!(body, "/files/a%20b%2Fc?x=1");
+    }
+
+    #[tokio::test]
+    async fn test_router_nest_param_name() {
+        let app = Router::new().nest(
+            "/api",
+            Router::new().get("/tags/{*nest}", async |Path(nest): Path<String>| {
+                format!("Get Tag: {nest}")
+            }),
+        );
+
+        let req = Request::get("/api/tags/hello%20world")
+            .body(Body::empty())
+            .unwrap();
+        let res = app.serve(Context::default(), req).await.unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+        let body = res.into_body().collect().await.unwrap().to_bytes();
+        assert_eq!(body, "Get Tag: hello world");
+
+        let err = Router::new()
+            .try_match_route("/files/{*__rama_nest}", HttpMatcher::method_get(), "files")
+            .unwrap_err();
+        assert_eq!(err.path(), "/files/{*__rama_nest}");
+        assert!(err.to_string().contains("reserved"), "{err}");
+    }
+
+    #[tokio::test]
+    async fn test_router_matched_route() {
+        let app = Router::new().get("/", root_service()).sub(
+            "/api",
+            Router::new()
+                .get("/users/{user_id}", get_user_service())
+                .sub(
+                    "/users/{user_id}/orders",
+                    Router::new().get("/", get_users_service()),
+                ),
+        );
+
+        let cases = [
+            ("/", Some("/")),
+            ("/api/users/123", Some("/api/users/{user_id}")),
+            ("/api/users/123/orders", Some("/api/users/{user_id}/orders")),
+            ("/not-found", None),
+        ];
+        for (path, expected_route) in cases {
+            let req = Request::get(path).body(Body::empty()).unwrap();
+            let res = app.serve(Context::default(), req).await.unwrap();
+            assert_eq!(
+                res.extensions()
+                    .get::<MatchedRoute>()
+                    .map(MatchedRoute::as_str),
+                expected_route,
+                "path = {path}"
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_router_openapi() {
+        use crate::service::web::extract::{Json, Path, Query};
+        use serde_json::json;
+
+        #[derive(Debug, serde::Deserialize)]
+        #[allow(dead_code)]
+        struct ListParams {
+            page: Option<u32>,
+            search: String,
+        }
+
+        #[derive(Debug, serde::Deserialize)]
+        #[allow(dead_code)]
+        struct NewOrder {
+            item: String,
+            amount: u16,
+        }
+
+        let app = Router::new()
+            .get("/users", async |Query(_params): Query<ListParams>| "users")
+            .nest(
+                "/users/{user_id}",
+                Router::new().post(
+                    "/orders",
+                    async |Path(_id): Path<u64>, Json(_order): Json<NewOrder>| "created",
+                ),
+            )
+            .openapi(OpenApi::new("shop", "1.0.0").with_path("/docs/openapi.json"));
+
+        let req = Request::get("/docs/openapi.json")
+            .body(Body::empty())
+            .unwrap();
+        let res = app.serve(Context::default(), req).await.unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+        let body = res.into_body().collect().await.unwrap().to_bytes();
+        let document: serde_json::Value = serde_json::from_slice(&body).unwrap();
+
+        assert_eq!(document["openapi"], "3.1.0");
+        assert_eq!(
+            document["info"],
+            json!({ "title": "shop", "version": "1.0.0" })
+        );
+        assert_eq!(
+            document["paths"]["/users"]["get"]["parameters"],
+            json!([
+                {
+                    "name": "page",
+                    "in": "query",
+                    "required": false,
+                    "schema": { "type": "integer", "minimum": 0 },
+                },
+                {
+                    "name": "search",
+                    "in": "query",
+                    "required": true,
+                    "schema": { "type": "string" },
+                },
+            ])
+        );
+
+        let create_order = &document["paths"]["/users/{user_id}/orders"]["post"];
+        assert_eq!(
+            create_order["parameters"],
+            json!([{
+                "name": "user_id",
+                "in": "path",
+                "required": true,
+                "schema": { "type": "string" },
+            }])
+        );
+        assert_eq!(
+            create_order["requestBody"]["content"]["application/json"]["schema"]["required"],
+            json!(["item", "amount"])
+        );
+    }
+
+    #[tokio::test]
+    async fn test_router_precedence_independent_of_order() {
+        let routes: [(&str, &'static str); 4] = [
+            ("/users/me", "me"),
+            ("/users/{id}", "param"),
+            ("/files/readme", "static"),
+            ("/files/{*path}", "wildcard"),
+        ];
+        let cases = [
+            ("/users/me", "me"),
+            ("/users/42", "param"),
+            ("/files/readme", "static"),
+            ("/files/docs/readme", "wildcard"),
+        ];
+
+        for order in [[0, 1, 2, 3], [3, 2, 1, 0], [1, 3, 0, 2]] {
+            let app = order.iter().fold(Router::new(), |app, &i| {
+                let (path, body) = routes[i];
+                app.get(path, body)
+            });
+            for (path, expected_body) in cases {
+                let req = Request::get(path).body(Body::empty()).unwrap();
+                let res = app.serve(Context::default(), req).await.unwrap();
+                assert_eq!(
+                    res.status(),
+                    StatusCode::OK,
+                    "path = {path}; order = {order:?}"
+                );
+                let body = res.into_body().collect().await.unwrap().to_bytes();
+                assert_eq!(body, expected_body, "path = {path}; order = {order:?}");
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_router_priority() {
+        let app = Router::new()
+            .get("/", "default")
+            .match_route_with_priority("/", HttpMatcher::method_get(), 1, "override")
+            .match_route_with_priority("/", HttpMatcher::method_get(), -1, "fallback");
+
+        let req = Request::get("/").body(Body::empty()).unwrap();
+        let res = app.serve(Context::default(), req).await.unwrap();
+        let body = res.into_body().collect().await.unwrap().to_bytes();
+        assert_eq!(body, "override");
+    }
+
+    #[tokio::test]
+    async fn test_router_static_routes() {
+        let app = Router::new()
+            .get("/new", "new page")
+            .redirect("/old", "/new", 308)
+            .redirect("/login", "https://auth.example.com/login", 302)
+            .static_status("/favicon.ico", StatusCode::NO_CONTENT)
+            .static_response(
+                "/robots.txt",
+                (
+                    [(crate::header::CONTENT_TYPE, "text/plain")],
+                    "User-agent: *",
+                ),
+            );
+
+        let req = Request::post("/old").body(Body::empty()).unwrap();
+        let res = app.serve(Context::default(), req).await.unwrap();
+        assert_eq!(res.status(), StatusCode::PERMANENT_REDIRECT);
+        assert_eq!(res.headers()["location"], "/new");
+
+        let req = Request::get("/login").body(Body::empty()).unwrap();
+        let res = app.serve(Context::default(), req).await.unwrap();
+        assert_eq!(res.status(), StatusCode::FOUND);
+        assert_eq!(res.headers()["location"], "https://auth.example.com/login");
+
+        let req = Request::get("/favicon.ico").body(Body::empty()).unwrap();
+        let res = app.serve(Context::default(), req).await.unwrap();
+        assert_eq!(res.status(), StatusCode::NO_CONTENT);
+
+        let req = Request::get("/robots.txt").body(Body::empty()).unwrap();
+        let res = app.serve(Context::default(), req).await.unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(res.headers()["content-type"], "text/plain");
+        let body = res.into_body().collect().await.unwrap().to_bytes();
+        assert_eq!(body, "User-agent: *");
+    }
+
+    #[test]
+    #[should_panic(expected = "is not a redirection")]
+    fn test_router_redirect_invalid_status() {
+        let _ = Router::new().redirect("/old", "/new", 200);
+    }
+
+    #[tokio::test]
+    async fn test_router_segment_constraints() {
+        let app = Router::new()
+            .segment_validator("even", |segment| {
+                segment.parse::<u64>().is_ok_and(|n| n % 2 == 0)
+            })
+            .get("/users/{id:[0-9]+}", "by id")
+            .get("/items/{id:even}", "even item")
+            .get("/items/{id:[a-z]{2,3}}", "named item")
+            .get("/files/{*path:.+\\.txt}", "text file");
+
+        let cases = [
+            ("/users/42", StatusCode::OK, "by id"),
+            ("/users/abc", StatusCode::NOT_FOUND, "Not Found"),
+            ("/items/4", StatusCode::OK, "even item"),
+            ("/items/abc", StatusCode::OK, "named item"),
+            ("/items/3", StatusCode::NOT_FOUND, "Not Found"),
+            ("/items/abcd", StatusCode::NOT_FOUND, "Not Found"),
+            ("/files/a/b.txt", StatusCode::OK, "text file"),
+            ("/files/a/b.md", StatusCode::NOT_FOUND, "Not Found"),
+        ];
+        for (path, expected_status, expected_body) in cases {
+            let req = Request::get(path).body(Body::empty()).unwrap();
+            let res = app.serve(Context::default(), req).await.unwrap();
+            assert_eq!(res.status(), expected_status, "path = {path}");
+            let body = res.into_body().collect().await.unwrap().to_bytes();
+            assert_eq!(body, expected_body, "path = {path}");
+        }
+
+        let routes: Vec<_> = app.routes().iter().map(RouteInfo::pattern).collect();
+        assert_eq!(routes[0], "/users/{id:[0-9]+}");
+
+        let err = Router::new()
+            .try_match_route("/users/{id:[0-9}", HttpMatcher::method_get(), "by id")
+            .unwrap_err();
+        assert_eq!(err.path(), "/users/{id:[0-9}");
+        assert!(err.to_string().contains("segment constraint"), "{err}");
+    }
+
+    #[test]
+    fn test_router_conflicts() {
+        let err = Router::new()
+            .get("/users/{id}", "by id")
+            .try_match_route(
+                "/users/{name}",
+                HttpMatcher::method(MethodMatcher::POST),
+                "by name",
+            )
+            .unwrap_err();
+        assert_eq!(err.path(), "/users/{name}");
+        assert!(err.to_string().contains("`/users/{id}`"), "{err}");
+
+        let err = Router::new()
+            .get("/users", "list")
+            .try_add_route(
+                "/users/",
+                HttpMatcher::method_get(),
+                Some(Method::GET),
+                0,
+                "list again",
+            )
+            .unwrap_err();
+        assert_eq!(err.path(), "/users");
+        assert!(err.to_string().contains("GET"), "{err}");
+
+        assert!(
+            Router::new()
+                .try_match_route("/files/{*path}/raw", HttpMatcher::method_get(), "raw")
+                .is_err()
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "conflicts with registered route")]
+    fn test_router_conflict_panics() {
+        let _ = Router::new()
+            .get("/users/{id}", "by id")
+            .get("/users/{name}", "by name");
+    }
+
+    #[tokio::test]
+    async fn test_router_failure_handlers() {
+        use crate::service::web::extract::Query;
+
+        #[derive(Debug, serde::Deserialize)]
+        #[allow(dead_code)]
+        struct Page {
+            page: u32,
+        }
+
+        fn failure_handler(
+            label: &'static str,
+        ) -> impl Service<Request, Response = Response, Error = Infallible> {
+            service_fn(move |ctx: Context, req: Request| async move {
+                let failure = ctx.get::<RouteFailure>().unwrap();
+                Ok(Response::builder()
+                    .status(StatusCode::IM_A_TEAPOT)
+                    .body(Body::from(format!(
+                        "{label}: {failure:?} for {} {}",
+                        req.method(),
+                        req.uri()
+                    )))
+                    .unwrap())
+            })
+        }
+
+        let app = Router::new()
+            .get("/items", async |Query(_page): Query<Page>| "items")
+            .post("/items", StatusCode::SERVICE_UNAVAILABLE)
+            .not_found(failure_handler("not found"))
+            .method_not_allowed(failure_handler("method not allowed"))
+            .extraction_failed(failure_handler("extraction failed"))
+            .handler_error(failure_handler("handler error"));
+
+        let cases = [
+            (
+                Method::GET,
+                "/items?page=1",
+                StatusCode::OK,
+                "items".to_owned(),
+            ),
+            (
+                Method::GET,
+                "/nothing",
+                StatusCode::IM_A_TEAPOT,
+                "not found: NotFound for GET /nothing".to_owned(),
+            ),
+            (
+                Method::DELETE,
+                "/items",
+                StatusCode::IM_A_TEAPOT,
+                "method not allowed: MethodNotAllowed { allowed: [GET, POST] } for DELETE /items"
+                    .to_owned(),
+            ),
+            (
+                Method::GET,
+                "/items?page=abc",
+                StatusCode::IM_A_TEAPOT,
+                "extraction failed: ExtractionFailed { status: 400 } for GET /items?page=abc"
+                    .to_owned(),
+            ),
+            (
+                Method::POST,
+                "/items",
+                StatusCode::IM_A_TEAPOT,
+                "handler error: HandlerError { status: 503 } for POST /items".to_owned(),
+            ),
+        ];
+        for (method, path, expected_status, expected_body) in cases {
+            let req = Request::builder()
+                .method(method.clone())
+                .uri(path)
+                .body(Body::empty())
+                .unwrap();
+            let res = app.serve(Context::default(), req).await.unwrap();
+            assert_eq!(res.status(), expected_status, "{method} {path}");
+            let body = res.into_body().collect().await.unwrap().to_bytes();
+            assert_eq!(body, expected_body, "{method} {path}");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_router_routes() {
+        use crate::layer::{body_limit::BodyLimitLayer, timeout::TimeoutLayer};
+        use crate::service::web::LayeredEndpoint;
+        use std::time::Duration;
+
+        let app = Router::new()
+            .get("/", root_service())
+            .post(
+                "/users",
+                LayeredEndpoint::new(create_user_service())
+                    .layer("body_limit", BodyLimitLayer::new(1024))
+                    .layer("timeout", TimeoutLayer::new(Duration::from_secs(5))),
+            )
+            .nest(
+                "/api",
+                Router::new().delete("/users/{user_id}", delete_user_service()),
+            )
+            .nest("/static", "static files")
+            .route_table("/debug/routes");
+
+        let routes: Vec<_> = app
+            .routes()
+            .iter()
+            .map(|route| {
+                (
+                    route.method().cloned(),
+                    route.pattern(),
+                    route.layers().to_vec(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            routes,
+            [
+                (Some(Method::GET), "/", vec![]),
+                (Some(Method::POST), "/users", vec!["timeout", "body_limit"]),
+                (Some(Method::DELETE), "/api/users/{user_id}", vec![]),
+                (None, "/static/{*__rama_nest}", vec![]),
+            ]
+        );
+        assert_eq!(app.routes()[3].handler(), "&str");
+        assert!(!app.routes()[1].handler().contains("BodyLimit"));
+
+        let req = Request::get("/debug/routes").body(Body::empty()).unwrap();
+        let res = app.serve(Context::default(), req).await.unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+        let body = res.into_body().collect().await.unwrap().to_bytes();
+        let table: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(table[1]["method"], "POST");
+        assert_eq!(table[1]["pattern"], "/users");
+        assert_eq!(
+            table[1]["layers"],
+            serde_json::json!(["timeout", "body_limit"])
+        );
+        assert_eq!(table[3]["method"], serde_json::Value::Null);
+    }
 }
@@ -0,0 +1,219 @@
+//! Route helper to serve a stream of typed events as Server-Sent Events (SSE).
+//!
+//! # Example
+//!
+//! ```
+//! use rama_core::{Context, futures::stream};
+//! use rama_http::Request;
+//! use rama_http::service::web::{Router, sse::{SseEvent, sse}};
+//! use serde::Serialize;
+//!
+//! #[derive(Debug, Serialize)]
+//! #[serde(tag = "type", rename_all = "snake_case")]
+//! enum Update {
+//!     Price { id: u64, price: f64 },
+//!     Sold { id: u64 },
+//! }
+//!
+//! impl SseEvent for Update {
+//!     fn event_name(&self) -> Option<&str> {
+//!         Some(match self {
+//!             Self::Price { .. } => "price",
+//!             Self::Sold { .. } => "sold",
+//!         })
+//!     }
+//! }
+//!
+//! let router = Router::new().get(
+//!     "/updates",
+//!     sse(async |_ctx: Context, _req: Request| {
+//!         stream::iter([
+//!             Update::Price { id: 1, price: 9.99 },
+//!             Update::Sold { id: 1 },
+//!         ])
+//!     }),
+//! );
+//! ```
+
+use crate::service::web::response::{IntoResponse, Sse};
+use crate::sse::{Event, EventBuildError, JsonEventData, server::KeepAlive};
+use crate::{Request, Response};
+use rama_core::futures::{Stream, StreamExt};
+use rama_core::{Context, Service};
+use serde::Serialize;
+use smol_str::SmolStr;
+use std::{convert::Infallible, fmt, sync::Arc, time::Duration};
+
+/// A typed event served by the [`SseService`], of which the data is serialized as JSON.
+///
+/// Implement [`SseEvent::event_name`] and [`SseEvent::event_id`]
+/// to also send the name and id of each event.
+pub trait SseEvent: Serialize + Send + 'static {
+    /// Returns the name of the event, sent as its `event` field.
+    fn event_name(&self) -> Option<&str> {
+        None
+    }
+
+    /// Returns the id of the event, sent as its `id` field,
+    /// which the client sends back as `Last-Event-ID` when it reconnects.
+    fn event_id(&self) -> Option<String> {
+        None
+    }
+}
+
+/// Create an [`SseService`] serving the stream of [`SseEvent`]s
+/// returned by the given handler as Server-Sent Events.
+///
+/// See the [module docs](self) for an example.
+pub fn sse<F, R, S>(handler: F) -> SseService<F>
+where
+    F: Fn(Context, Request) -> R + Send + Sync + 'static,
+    R: Future<Output = S> + Send + 'static,
+    S: Stream<Item: SseEvent> + Send + 'static,
+{
+    SseService {
+        handler: Arc::new(handler),
+        keep_alive: Some(Duration::from_secs(15)),
+    }
+}
+
+/// A [`Service`] serving the stream of [`SseEvent`]s returned by its handler
+/// as Server-Sent Events, each serialized as JSON.
+///
+/// A keep-alive comment is sent after 15 seconds without events, by default.
+/// The stream is dropped, and thus cancelled, as soon as the client disconnects.
+///
+/// Created using [`sse`].
+pub struct SseService<F> {
+    handler: Arc<F>,
+    keep_alive: Option<Duration>,
+}
+
+impl<F> SseService<F> {
+    rama_utils::macros::generate_set_and_with! {
+        /// Set the interval after which a keep-alive comment is sent
+        /// in case no event was sent, or `None` to disable keep-alive comments.
+        pub fn keep_alive(mut self, interval: Option<Duration>) -> Self {
+            self.keep_alive = interval;
+            self
+        }
+    }
+}
+
+impl<F> fmt::Debug for SseService<F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SseService")
+            .field("handler", &std::any::type_name::<F>())
+            .field("keep_alive", &self.keep_alive)
+            .finish()
+    }
+}
+
+impl<F> Clone for SseService<F> {
+    fn clone(&self) -> Self {
+        Self {
+            handler: self.handler.clone(),
+            keep_alive: self.keep_alive,
+        }
+    }
+}
+
+impl<F, R, S> Service<Request> for SseService<F>
+where
+    F: Fn(Context, Request) -> R + Send + Sync + 'static,
+    R: Future<Output = S> + Send + 'static,
+    S: Stream<Item: SseEvent> + Send + 'static,
+{
+    type Response = Response;
+    type Error = Infallible;
+
+    async fn serve(&self, ctx: Context, req: Request) -> Result<Self::Response, Self::Error> {
+        let events = (self.handler)(ctx, req).await.map(into_event);
+        Ok(match self.keep_alive {
+            Some(interval) => Sse::new(events)
+                .with_keep_alive(KeepAlive::new().with_interval(interval))
+                .into_response(),
+            None => Sse::new(events).into_response(),
+        })
+    }
+}
+
+fn into_event<T: SseEvent>(event: T) -> Result<Event<JsonEventData<T>>, EventBuildError> {
+    let name = event.event_name().map(SmolStr::new);
+    let id = event.event_id();
+
+    let mut sse_event = Event::new();
+    if let Some(name) = name {
+        sse_event = sse_event.try_with_event(name)?;
+    }
+    if let Some(id) = id {
+        sse_event = sse_event.try_with_id(id)?;
+    }
+    Ok(sse_event.with_data(JsonEventData(event)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::service::web::Router;
+    use crate::{Body, BodyExtractExt, StatusCode};
+    use rama_core::futures::stream;
+    use serde_json::json;
+
+    #[derive(Debug, Serialize)]
+    struct Tick {
+        n: u64,
+    }
+
+    impl SseEvent for Tick {
+        fn event_name(&self) -> Option<&str> {
+            Some("tick")
+        }
+
+        fn event_id(&self) -> Option<String> {
+            Some(self.n.to_string())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_sse_typed_events() {
+        let router = Router::new().get(
+            "/ticks",
+            sse(async |_ctx: Context, _req: Request| stream::iter([Tick { n: 1 }, Tick { n: 2 }])),
+        );
+
+        let req = Request::get("/ticks").body(Body::empty()).unwrap();
+        let resp = router.serve(Context::default(), req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(resp.headers()["content-type"], "text/event-stream");
+
+        let body = resp.try_into_string().await.unwrap();
+        assert_eq!(
+            body,
+            format!(
+                "id: 1\nevent: tick\ndata: {}\n\nid: 2\nevent: tick\ndata: {}\n\n",
+                json!({ "n": 1 }),
+                json!({ "n": 2 }),
+            )
+        );
+    }
+
+    #[tokio::test]
+    async fn test_sse_cancelled_on_disconnect() {
+        let (tx, rx) = tokio::sync::mpsc::unbounded_channel::<Tick>();
+        let rx = std::sync::Mutex::new(Some(rx));
+
+        let service = sse(move |_ctx: Context, _req: Request| {
+            let rx = rx.lock().unwrap().take().unwrap();
+            async move { tokio_stream::wrappers::UnboundedReceiverStream::new(rx) }
+        });
+
+        let req = Request::get("/").body(Body::empty()).unwrap();
+        let resp = service.serve(Context::default(), req).await.unwrap();
+        assert!(!tx.is_closed());
+
+        // the client disconnecting drops the response body, and thus the stream
+        drop(resp);
+        assert!(tx.is_closed());
+    }
+}
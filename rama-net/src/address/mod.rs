@@ -23,6 +23,10 @@ mod socket_address;
 #[doc(inline)]
 pub use socket_address::SocketAddress;
 
+mod socket_target;
+#[doc(inline)]
+pub use socket_target::SocketTarget;
+
 mod proxy;
 
 pub(crate) mod parse_utils;
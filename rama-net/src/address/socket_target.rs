@@ -0,0 +1,201 @@
+use super::SocketAddress;
+use rama_core::error::{ErrorContext, OpaqueError};
+use std::fmt;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+const UNIX_SCHEME: &str = "unix:";
+
+/// The target of a socket, to bind a listener to or to connect to,
+/// regardless of the transport used to reach it.
+///
+/// This allows services to be written once and be deployed
+/// over either transport, by configuring the target only.
+///
+/// In its string format, a unix (domain) socket path is prefixed with `unix:`,
+/// while an ip socket address is written as is.
+///
+/// # Example
+///
+/// ```
+/// use rama_net::address::{SocketAddress, SocketTarget};
+///
+/// let target: SocketTarget = "127.0.0.1:8080".parse().unwrap();
+/// assert_eq!(target, SocketTarget::Address(SocketAddress::local_ipv4(8080)));
+///
+/// let target: SocketTarget = "unix:/tmp/rama.sock".parse().unwrap();
+/// assert_eq!(target.as_unix_path().unwrap().to_str(), Some("/tmp/rama.sock"));
+/// assert_eq!(target.to_string(), "unix:/tmp/rama.sock");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum SocketTarget {
+    /// An ip address with a port, reached over TCP (or UDP).
+    Address(SocketAddress),
+    /// The path of a unix (domain) socket.
+    Unix(PathBuf),
+}
+
+impl SocketTarget {
+    /// Returns the [`SocketAddress`] in case this target is an ip socket address.
+    #[must_use]
+    pub fn as_socket_address(&self) -> Option<SocketAddress> {
+        match self {
+            Self::Address(addr) => Some(*addr),
+            Self::Unix(_) => None,
+        }
+    }
+
+    /// Returns the path in case this target is a unix (domain) socket.
+    #[must_use]
+    pub fn as_unix_path(&self) -> Option<&Path> {
+        match self {
+            Self::Address(_) => None,
+            Self::Unix(path) => Some(path),
+        }
+    }
+}
+
+impl From<SocketAddress> for SocketTarget {
+    fn from(addr: SocketAddress) -> Self {
+        Self::Address(addr)
+    }
+}
+
+impl From<SocketAddr> for SocketTarget {
+    fn from(addr: SocketAddr) -> Self {
+        Self::Address(addr.into())
+    }
+}
+
+impl From<PathBuf> for SocketTarget {
+    fn from(path: PathBuf) -> Self {
+        Self::Unix(path)
+    }
+}
+
+impl From<&Path> for SocketTarget {
+    fn from(path: &Path) -> Self {
+        Self::Unix(path.to_owned())
+    }
+}
+
+impl fmt::Display for SocketTarget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Address(addr) => addr.fmt(f),
+            Self::Unix(path) => write!(f, "{UNIX_SCHEME}{}", path.display()),
+        }
+    }
+}
+
+impl FromStr for SocketTarget {
+    type Err = OpaqueError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::try_from(s)
+    }
+}
+
+impl TryFrom<String> for SocketTarget {
+    type Error = OpaqueError;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.as_str().try_into()
+    }
+}
+
+impl TryFrom<&str> for SocketTarget {
+    type Error = OpaqueError;
+
+    fn try_from(s: &str) -> Result<Self, Self::Error> {
+        if let Some(path) = s.strip_prefix(UNIX_SCHEME) {
+            // also accept the url-like `unix://` notation
+            let path = path.strip_prefix("//").unwrap_or(path);
+            if path.is_empty() {
+                return Err(OpaqueError::from_display(
+                    "empty unix socket path in socket target",
+                ));
+            }
+            return Ok(Self::Unix(path.into()));
+        }
+        let addr = s
+            .parse::<SocketAddress>()
+            .context("parse socket address of socket target")?;
+        Ok(Self::Address(addr))
+    }
+}
+
+impl serde::Serialize for SocketTarget {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        let target = self.to_string();
+        target.serialize(serializer)
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for SocketTarget {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let s = <std::borrow::Cow<'de, str>>::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_socket_target() {
+        for (s, expected, display) in [
+            (
+                "127.0.0.1:80",
+                SocketTarget::Address(SocketAddress::local_ipv4(80)),
+                "127.0.0.1:80",
+            ),
+            (
+                "[::1]:443",
+                SocketTarget::Address(SocketAddress::local_ipv6(443)),
+                "[::1]:443",
+            ),
+            (
+                "unix:/var/run/rama.sock",
+                SocketTarget::Unix("/var/run/rama.sock".into()),
+                "unix:/var/run/rama.sock",
+            ),
+            (
+                "unix:///var/run/rama.sock",
+                SocketTarget::Unix("/var/run/rama.sock".into()),
+                "unix:/var/run/rama.sock",
+            ),
+            (
+                "unix:rama.sock",
+                SocketTarget::Unix("rama.sock".into()),
+                "unix:rama.sock",
+            ),
+        ] {
+            let target: SocketTarget = s.parse().unwrap();
+            assert_eq!(target, expected, "parsing: {s}");
+            assert_eq!(target.to_string(), display, "display: {s}");
+        }
+    }
+
+    #[test]
+    fn test_parse_socket_target_invalid() {
+        for s in [
+            "",
+            "unix:",
+            "unix://",
+            "example.com:80",
+            "/var/run/rama.sock",
+        ] {
+            assert!(s.parse::<SocketTarget>().is_err(), "parsing: {s}");
+        }
+    }
+}
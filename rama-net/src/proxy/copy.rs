@@ -0,0 +1,224 @@
+use crate::stream::Stream;
+use rama_core::buffer::BufferPool;
+use std::{
+    io,
+    pin::{Pin, pin},
+    task::ready,
+};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+/// Copy the bytes of both [`Stream`]s bidirectionally, until both reached EOF,
+/// returning the amount of bytes copied from `a` to `b` and from `b` to `a`.
+///
+/// On Linux, when both streams are (unwrapped) tokio `TcpStream`s, the bytes are moved
+/// between both sockets using `splice(2)`, such that they are never copied into userspace.
+/// In all other cases it falls back to [`copy_bidirectional_with_pool`], using the
+/// [`BufferPool::global`] pool, which is also the case for streams wrapped to track their bytes,
+/// such that these counters are preserved.
+pub async fn copy_bidirectional<A, B>(a: &mut A, b: &mut B) -> io::Result<(u64, u64)>
+where
+    A: Stream + Unpin,
+    B: Stream + Unpin,
+{
+    #[cfg(target_os = "linux")]
+    if let (Some(a), Some(b)) = (
+        (&*a as &dyn std::any::Any).downcast_ref::<tokio::net::TcpStream>(),
+        (&*b as &dyn std::any::Any).downcast_ref::<tokio::net::TcpStream>(),
+    ) {
+        return splice::splice_bidirectional(a, b).await;
+    }
+
+    copy_bidirectional_with_pool(a, b, BufferPool::global()).await
+}
+
+/// Copy the bytes of both [`Stream`]s bidirectionally, until both reached EOF,
+/// using buffers taken from the given [`BufferPool`], one per direction.
+///
+/// Returns the amount of bytes copied from `a` to `b` and from `b` to `a`.
+/// The write side of a stream is shut down once the other stream reached EOF.
+pub async fn copy_bidirectional_with_pool<A, B>(
+    a: &mut A,
+    b: &mut B,
+    pool: &BufferPool,
+) -> io::Result<(u64, u64)>
+where
+    A: Stream + Unpin,
+    B: Stream + Unpin,
+{
+    let (mut a_read, mut a_write) = tokio::io::split(a);
+    let (mut b_read, mut b_write) = tokio::io::split(b);
+    tokio::try_join!(
+        copy_one_direction(&mut a_read, &mut b_write, pool),
+        copy_one_direction(&mut b_read, &mut a_write, pool),
+    )
+}
+
+async fn copy_one_direction<R, W>(
+    reader: &mut R,
+    writer: &mut W,
+    pool: &BufferPool,
+) -> io::Result<u64>
+where
+    R: AsyncRead + Unpin,
+    W: AsyncWrite + Unpin,
+{
+    let mut buf = pool.get();
+    let mut total = 0;
+    let mut needs_flush = false;
+    loop {
+        buf.clear();
+        let n = {
+            let mut read = pin!(reader.read_buf(&mut *buf));
+            // only flush once the reader has no more data ready,
+            // such that consecutive reads are written out together
+            std::future::poll_fn(|cx| {
+                let poll = read.as_mut().poll(cx);
+                if poll.is_pending() && needs_flush {
+                    ready!(Pin::new(&mut *writer).poll_flush(cx))?;
+                    needs_flush = false;
+                }
+                poll
+            })
+            .await?
+        };
+        if n == 0 {
+            break;
+        }
+        writer.write_all(&buf).await?;
+        needs_flush = true;
+        total += n as u64;
+    }
+    writer.shutdown().await?;
+    Ok(total)
+}
+
+#[cfg(target_os = "linux")]
+mod splice {
+    use crate::socket::core::SockRef;
+    use rama_core::telemetry::tracing;
+    use rustix::pipe::{PipeFlags, SpliceFlags, pipe_with, splice};
+    use std::{io, net::Shutdown};
+    use tokio::{io::Interest, net::TcpStream};
+
+    /// Maximum amount of bytes moved per `splice(2)` call,
+    /// matching the default capacity of a pipe.
+    const SPLICE_SIZE: usize = 64 * 1024;
+
+    pub(super) async fn splice_bidirectional(
+        a: &TcpStream,
+        b: &TcpStream,
+    ) -> io::Result<(u64, u64)> {
+        tracing::trace!("copy bidirectional between tcp streams using splice");
+        tokio::try_join!(splice_one_direction(a, b), splice_one_direction(b, a))
+    }
+
+    async fn splice_one_direction(src: &TcpStream, dst: &TcpStream) -> io::Result<u64> {
+        let (pipe_read, pipe_write) = pipe_with(PipeFlags::NONBLOCK | PipeFlags::CLOEXEC)?;
+        let flags = SpliceFlags::NONBLOCK | SpliceFlags::MOVE;
+
+        let mut total = 0;
+        loop {
+            let n = src
+                .async_io(Interest::READABLE, || {
+                    Ok(splice(src, None, &pipe_write, None, SPLICE_SIZE, flags)?)
+                })
+                .await?;
+            if n == 0 {
+                break;
+            }
+
+            // drain the pipe completely, such that it is empty for the next read
+            let mut pending = n;
+            while pending > 0 {
+                let written = dst
+                    .async_io(Interest::WRITABLE, || {
+                        Ok(splice(&pipe_read, None, dst, None, pending, flags)?)
+                    })
+                    .await?;
+                if written == 0 {
+                    return Err(io::Error::new(
+                        io::ErrorKind::WriteZero,
+                        "write zero bytes into destination tcp stream",
+                    ));
+                }
+                pending -= written;
+            }
+            total += n as u64;
+        }
+
+        SockRef::from(dst).shutdown(Shutdown::Write)?;
+        Ok(total)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::{TcpListener, TcpStream},
+    };
+
+    async fn tcp_pair() -> (TcpStream, TcpStream) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let client = TcpStream::connect(listener.local_addr().unwrap())
+            .await
+            .unwrap();
+        let (server, _) = listener.accept().await.unwrap();
+        (client, server)
+    }
+
+    #[tokio::test]
+    async fn test_copy_bidirectional_tcp() {
+        let (mut client, mut proxy_in) = tcp_pair().await;
+        let (mut proxy_out, mut server) = tcp_pair().await;
+
+        let proxy =
+            tokio::spawn(async move { copy_bidirectional(&mut proxy_in, &mut proxy_out).await });
+
+        client.write_all(b"hello").await.unwrap();
+        client.shutdown().await.unwrap();
+        let mut buf = Vec::new();
+        server.read_to_end(&mut buf).await.unwrap();
+        assert_eq!(buf, b"hello");
+
+        server.write_all(b"world!").await.unwrap();
+        server.shutdown().await.unwrap();
+        buf.clear();
+        client.read_to_end(&mut buf).await.unwrap();
+        assert_eq!(buf, b"world!");
+
+        assert_eq!(proxy.await.unwrap().unwrap(), (5, 6));
+    }
+
+    #[tokio::test]
+    async fn test_copy_bidirectional_with_pool() {
+        let (mut client, mut proxy_in) = tokio::io::duplex(64);
+        let (mut proxy_out, mut server) = tokio::io::duplex(64);
+
+        let pool = BufferPool::new(16, 4);
+        let proxy = tokio::spawn({
+            let pool = pool.clone();
+            async move { copy_bidirectional_with_pool(&mut proxy_in, &mut proxy_out, &pool).await }
+        });
+
+        let request = vec![b'a'; 100];
+        client.write_all(&request).await.unwrap();
+        client.shutdown().await.unwrap();
+        let mut buf = Vec::new();
+        server.read_to_end(&mut buf).await.unwrap();
+        assert_eq!(buf, request);
+
+        server.write_all(b"world!").await.unwrap();
+        server.shutdown().await.unwrap();
+        buf.clear();
+        client.read_to_end(&mut buf).await.unwrap();
+        assert_eq!(buf, b"world!");
+
+        assert_eq!(proxy.await.unwrap().unwrap(), (100, 6));
+        let metrics = pool.metrics();
+        assert_eq!(metrics.allocated(), 2);
+        assert_eq!(metrics.returned(), 2);
+        assert_eq!(metrics.idle(), 2);
+    }
+}
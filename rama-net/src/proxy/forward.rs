@@ -6,13 +6,15 @@ use rama_core::{
 
 use crate::stream::Stream;
 
-use super::ProxyRequest;
+use super::{ProxyRequest, copy_bidirectional};
 
 #[derive(Debug, Clone, Default)]
 #[non_exhaustive]
 /// A proxy [`Service`] which takes a [`ProxyRequest`]
 /// and copies the bytes of both the source and target [`Stream`]s
 /// bidirectionally.
+///
+/// See [`copy_bidirectional`] for more information.
 pub struct StreamForwardService;
 
 impl StreamForwardService {
@@ -40,7 +42,7 @@ where
             mut target,
         }: ProxyRequest<S, T>,
     ) -> Result<Self::Response, Self::Error> {
-        match tokio::io::copy_bidirectional(&mut source, &mut target).await {
+        match copy_bidirectional(&mut source, &mut target).await {
             Ok((bytes_copied_north, bytes_copied_south)) => {
                 tracing::trace!(
                     "(proxy) I/O stream forwarder finished: bytes north: {}; bytes south: {}",
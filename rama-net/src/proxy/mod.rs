@@ -6,6 +6,10 @@ mod request;
 #[doc(inline)]
 pub use request::ProxyRequest;
 
+mod copy;
+#[doc(inline)]
+pub use copy::{copy_bidirectional, copy_bidirectional_with_pool};
+
 mod forward;
 #[doc(inline)]
 pub use forward::StreamForwardService;
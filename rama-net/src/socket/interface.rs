@@ -439,6 +439,16 @@ impl FromStr for Interface {
     }
 }
 
+impl<'de> serde::Deserialize<'de> for Interface {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let s = <std::borrow::Cow<'de, str>>::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
 impl TryFrom<String> for Interface {
     type Error = OpaqueError;
 
@@ -10,7 +10,7 @@ pub use interface::DeviceName;
 
 pub mod opts;
 #[doc(inline)]
-pub use opts::SocketOptions;
+pub use opts::{SocketOptions, TcpStreamOptions};
 
 mod svc;
 #[doc(inline)]
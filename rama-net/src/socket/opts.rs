@@ -1142,3 +1142,64 @@ impl SocketOptions {
         Ok(socket)
     }
 }
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+/// Options which can be applied to an established TCP stream,
+/// be it one opened by a connector or accepted by a listener.
+///
+/// Unlike [`SocketOptions`] these do not require control
+/// over the creation of the [`Socket`], and are therefore
+/// the way to configure the streams of long-lived connections,
+/// e.g. to keep proxy tunnels alive behind NATs.
+pub struct TcpStreamOptions {
+    /// Set parameters configuring TCP keepalive probes for the stream.
+    ///
+    /// This will enable `SO_KEEPALIVE` on the stream.
+    ///
+    /// See [`SocketOptions::tcp_keep_alive`] for more information.
+    pub keep_alive: Option<TcpKeepAlive>,
+
+    /// Set the value of the `TCP_NODELAY` option on the stream.
+    ///
+    /// See [`SocketOptions::tcp_no_delay`] for more information.
+    pub no_delay: Option<bool>,
+
+    #[cfg(any(target_os = "android", target_os = "fuchsia", target_os = "linux"))]
+    /// Set the value of the `TCP_USER_TIMEOUT` option on the stream.
+    ///
+    /// See [`SocketOptions::tcp_user_timeout`] for more information.
+    pub user_timeout: Option<Duration>,
+
+    /// Set the value of the `SO_RCVBUF` option on the stream.
+    pub recv_buffer_size: Option<usize>,
+
+    /// Set the value of the `SO_SNDBUF` option on the stream.
+    pub send_buffer_size: Option<usize>,
+}
+
+impl TcpStreamOptions {
+    /// Apply these options to the given (TCP) socket,
+    /// e.g. a [`SockRef`] created from a tokio `TcpStream`.
+    ///
+    /// [`SockRef`]: super::core::SockRef
+    pub fn apply_to(&self, socket: &Socket) -> io::Result<()> {
+        if let Some(keep_alive) = self.keep_alive.clone() {
+            socket.set_tcp_keepalive(&keep_alive.into_socket_keep_alive())?;
+        }
+        if let Some(no_delay) = self.no_delay {
+            socket.set_tcp_nodelay(no_delay)?;
+        }
+        #[cfg(any(target_os = "android", target_os = "fuchsia", target_os = "linux"))]
+        if let Some(user_timeout) = self.user_timeout {
+            socket.set_tcp_user_timeout(Some(user_timeout))?;
+        }
+        if let Some(n) = self.recv_buffer_size {
+            socket.set_recv_buffer_size(n)?;
+        }
+        if let Some(n) = self.send_buffer_size {
+            socket.set_send_buffer_size(n)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,97 @@
+use super::CountingStream;
+use crate::stream::Stream;
+use rama_core::{Context, Layer, Service};
+use rama_utils::macros::define_inner_service_accessors;
+use std::fmt;
+
+/// A [`Service`] that wraps a [`Service`]'s input IO [`Stream`] in a [`CountingStream`],
+/// inserting its [`ConnectionStats`] into the [`Context`].
+///
+/// [`Service`]: rama_core::Service
+/// [`Stream`]: crate::stream::Stream
+/// [`ConnectionStats`]: super::ConnectionStats
+pub struct IncomingCountingService<S> {
+    inner: S,
+}
+
+impl<S: fmt::Debug> fmt::Debug for IncomingCountingService<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("IncomingCountingService")
+            .field("inner", &self.inner)
+            .finish()
+    }
+}
+
+impl<S> IncomingCountingService<S> {
+    /// Create a new [`IncomingCountingService`].
+    ///
+    /// See [`IncomingCountingService`] for more information.
+    pub const fn new(inner: S) -> Self {
+        Self { inner }
+    }
+
+    define_inner_service_accessors!();
+}
+
+impl<S> Clone for IncomingCountingService<S>
+where
+    S: Clone,
+{
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl<S, IO> Service<IO> for IncomingCountingService<S>
+where
+    S: Service<CountingStream<IO>>,
+    IO: Stream,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+
+    fn serve(
+        &self,
+        mut ctx: Context,
+        stream: IO,
+    ) -> impl Future<Output = Result<Self::Response, Self::Error>> + Send + '_ {
+        let stream = CountingStream::new(stream);
+        ctx.insert(stream.stats());
+        self.inner.serve(ctx, stream)
+    }
+}
+
+/// A [`Layer`] that wraps a [`Service`]'s input IO [`Stream`] in a [`CountingStream`].
+///
+/// See [`IncomingCountingService`] for more information.
+///
+/// [`Layer`]: rama_core::Layer
+/// [`Service`]: rama_core::Service
+/// [`Stream`]: crate::stream::Stream
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct IncomingCountingLayer;
+
+impl IncomingCountingLayer {
+    /// Create a new [`IncomingCountingLayer`].
+    #[must_use]
+    pub const fn new() -> Self {
+        Self
+    }
+}
+
+impl Default for IncomingCountingLayer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<S> Layer<S> for IncomingCountingLayer {
+    type Service = IncomingCountingService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        IncomingCountingService { inner }
+    }
+}
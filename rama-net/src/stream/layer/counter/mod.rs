@@ -0,0 +1,22 @@
+//! Per-connection byte and timing counters for [`Stream`]s.
+//!
+//! Wrap accepted streams using the [`IncomingCountingLayer`] or established
+//! connections using the [`OutgoingCountingLayer`] in order to insert a
+//! [`ConnectionStats`] handle into the [`Context`]. It reports the bytes read and written,
+//! the time it took to establish the connection and the time of its last activity,
+//! such that access logs, idle timeouts and accounting can all be served from a single source.
+//!
+//! [`Stream`]: crate::stream::Stream
+//! [`Context`]: rama_core::Context
+
+mod stream;
+#[doc(inline)]
+pub use stream::{ConnectionStats, CountingStream};
+
+mod incoming;
+#[doc(inline)]
+pub use incoming::{IncomingCountingLayer, IncomingCountingService};
+
+mod outgoing;
+#[doc(inline)]
+pub use outgoing::{OutgoingCountingLayer, OutgoingCountingService};
@@ -0,0 +1,103 @@
+use super::CountingStream;
+use crate::{
+    client::{ConnectorService, EstablishedClientConnection},
+    stream::Stream,
+};
+use rama_core::{Context, Layer, Service};
+use rama_utils::macros::define_inner_service_accessors;
+use std::{fmt, time::Instant};
+
+/// A [`Service`] that wraps a [`Service`]'s output IO [`Stream`] in a [`CountingStream`],
+/// inserting its [`ConnectionStats`] into the [`Context`] of the established connection.
+///
+/// The time it took the inner connector to establish the connection
+/// is reported as [`ConnectionStats::connect_duration`].
+///
+/// [`Service`]: rama_core::Service
+/// [`Stream`]: crate::stream::Stream
+/// [`ConnectionStats`]: super::ConnectionStats
+/// [`ConnectionStats::connect_duration`]: super::ConnectionStats::connect_duration
+pub struct OutgoingCountingService<S> {
+    inner: S,
+}
+
+impl<S: fmt::Debug> fmt::Debug for OutgoingCountingService<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("OutgoingCountingService")
+            .field("inner", &self.inner)
+            .finish()
+    }
+}
+
+impl<S> OutgoingCountingService<S> {
+    /// Create a new [`OutgoingCountingService`].
+    ///
+    /// See [`OutgoingCountingService`] for more information.
+    pub const fn new(inner: S) -> Self {
+        Self { inner }
+    }
+
+    define_inner_service_accessors!();
+}
+
+impl<S> Clone for OutgoingCountingService<S>
+where
+    S: Clone,
+{
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl<S, Request> Service<Request> for OutgoingCountingService<S>
+where
+    S: ConnectorService<Request, Connection: Stream + Unpin, Error: Send + 'static>,
+    Request: Send + 'static,
+{
+    type Response = EstablishedClientConnection<CountingStream<S::Connection>, Request>;
+    type Error = S::Error;
+
+    async fn serve(&self, ctx: Context, req: Request) -> Result<Self::Response, Self::Error> {
+        let start = Instant::now();
+        let EstablishedClientConnection { mut ctx, req, conn } =
+            self.inner.connect(ctx, req).await?;
+        let conn = CountingStream::new_connected(conn, start.elapsed());
+        ctx.insert(conn.stats());
+        Ok(EstablishedClientConnection { ctx, req, conn })
+    }
+}
+
+/// A [`Layer`] that wraps a [`Service`]'s output IO [`Stream`] in a [`CountingStream`].
+///
+/// See [`OutgoingCountingService`] for more information.
+///
+/// [`Layer`]: rama_core::Layer
+/// [`Service`]: rama_core::Service
+/// [`Stream`]: crate::stream::Stream
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct OutgoingCountingLayer;
+
+impl OutgoingCountingLayer {
+    /// Create a new [`OutgoingCountingLayer`].
+    #[must_use]
+    pub const fn new() -> Self {
+        Self
+    }
+}
+
+impl Default for OutgoingCountingLayer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<S> Layer<S> for OutgoingCountingLayer {
+    type Service = OutgoingCountingService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        OutgoingCountingService { inner }
+    }
+}
@@ -0,0 +1,280 @@
+use pin_project_lite::pin_project;
+use std::{
+    fmt, io,
+    pin::Pin,
+    sync::{
+        Arc,
+        atomic::{AtomicU64, Ordering},
+    },
+    task::{Context, Poll, ready},
+    time::{Duration, Instant, SystemTime},
+};
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+
+#[derive(Debug)]
+struct Counters {
+    created_at: Instant,
+    created_at_system: SystemTime,
+    connect_duration: Option<Duration>,
+    read: AtomicU64,
+    written: AtomicU64,
+    /// nanoseconds since `created_at`
+    last_activity: AtomicU64,
+}
+
+impl Counters {
+    fn touch(&self) {
+        let nanos = u64::try_from(self.created_at.elapsed().as_nanos()).unwrap_or(u64::MAX);
+        self.last_activity.fetch_max(nanos, Ordering::AcqRel);
+    }
+}
+
+/// A handle to the counters of a [`CountingStream`], which can be used to
+/// get its byte and timing counters even though the stream itself is consumed
+/// by a protocol consumer.
+///
+/// Inserted into the [`Context`] by the [`IncomingCountingLayer`] and [`OutgoingCountingLayer`].
+///
+/// [`Context`]: rama_core::Context
+/// [`IncomingCountingLayer`]: super::IncomingCountingLayer
+/// [`OutgoingCountingLayer`]: super::OutgoingCountingLayer
+#[derive(Debug, Clone)]
+pub struct ConnectionStats(Arc<Counters>);
+
+impl ConnectionStats {
+    fn new(connect_duration: Option<Duration>) -> Self {
+        Self(Arc::new(Counters {
+            created_at: Instant::now(),
+            created_at_system: SystemTime::now(),
+            connect_duration,
+            read: AtomicU64::new(0),
+            written: AtomicU64::new(0),
+            last_activity: AtomicU64::new(0),
+        }))
+    }
+
+    fn count_read(&self, bytes_read: usize) {
+        if bytes_read > 0 {
+            self.0.read.fetch_add(bytes_read as u64, Ordering::AcqRel);
+            self.0.touch();
+        }
+    }
+
+    fn count_written(&self, bytes_written: usize) {
+        if bytes_written > 0 {
+            self.0
+                .written
+                .fetch_add(bytes_written as u64, Ordering::AcqRel);
+            self.0.touch();
+        }
+    }
+
+    /// Get the number of bytes read (so far).
+    #[must_use]
+    pub fn bytes_read(&self) -> u64 {
+        self.0.read.load(Ordering::Acquire)
+    }
+
+    /// Get the number of bytes written (so far).
+    #[must_use]
+    pub fn bytes_written(&self) -> u64 {
+        self.0.written.load(Ordering::Acquire)
+    }
+
+    /// Get the time it took to establish the connection,
+    /// only known for outgoing connections.
+    #[must_use]
+    pub fn connect_duration(&self) -> Option<Duration> {
+        self.0.connect_duration
+    }
+
+    /// Get the (wall clock) time at which the stream started to be counted.
+    #[must_use]
+    pub fn started_at(&self) -> SystemTime {
+        self.0.created_at_system
+    }
+
+    /// Get the time elapsed since the stream started to be counted.
+    #[must_use]
+    pub fn elapsed(&self) -> Duration {
+        self.0.created_at.elapsed()
+    }
+
+    /// Get the instant at which bytes were last read or written,
+    /// which is the start of the stream in case no bytes were read or written yet.
+    #[must_use]
+    pub fn last_activity(&self) -> Instant {
+        self.0.created_at + Duration::from_nanos(self.0.last_activity.load(Ordering::Acquire))
+    }
+
+    /// Get the time elapsed since bytes were last read or written,
+    /// e.g. to be compared against an idle timeout.
+    #[must_use]
+    pub fn idle_duration(&self) -> Duration {
+        self.last_activity().elapsed()
+    }
+}
+
+pin_project! {
+    /// A wrapper around a [`AsyncRead`] and/or [`AsyncWrite`] that counts the bytes
+    /// read and written as well as the time of the last activity.
+    ///
+    /// Use [`CountingStream::stats`] to get a [`ConnectionStats`] handle in order
+    /// to get these counters even though the [`CountingStream`] is consumed by
+    /// a protocol consumer.
+    ///
+    /// [`AsyncRead`]: crate::stream::AsyncRead
+    /// [`AsyncWrite`]: crate::stream::AsyncWrite
+    pub struct CountingStream<S> {
+        stats: ConnectionStats,
+        #[pin]
+        stream: S,
+    }
+}
+
+impl<S: fmt::Debug> fmt::Debug for CountingStream<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CountingStream")
+            .field("stats", &self.stats)
+            .field("stream", &self.stream)
+            .finish()
+    }
+}
+
+impl<S> CountingStream<S> {
+    /// Create a new [`CountingStream`] that wraps the
+    /// given [`AsyncRead`] and/or [`AsyncWrite`].
+    ///
+    /// [`AsyncRead`]: crate::stream::AsyncRead
+    /// [`AsyncWrite`]: crate::stream::AsyncWrite
+    pub fn new(stream: S) -> Self {
+        Self {
+            stats: ConnectionStats::new(None),
+            stream,
+        }
+    }
+
+    /// Create a new [`CountingStream`] for a connection
+    /// which took the given duration to be established.
+    pub fn new_connected(stream: S, connect_duration: Duration) -> Self {
+        Self {
+            stats: ConnectionStats::new(Some(connect_duration)),
+            stream,
+        }
+    }
+
+    /// Get a [`ConnectionStats`] handle to the counters of this stream.
+    pub fn stats(&self) -> ConnectionStats {
+        self.stats.clone()
+    }
+
+    /// Get a reference to the inner stream.
+    pub fn get_ref(&self) -> &S {
+        &self.stream
+    }
+
+    /// Get a mutable reference to the inner stream.
+    pub fn get_mut(&mut self) -> &mut S {
+        &mut self.stream
+    }
+
+    /// Get the inner [`AsyncRead`] and/or [`AsyncWrite`] stream.
+    ///
+    /// Any previously obtained [`ConnectionStats`] will no longer be updated.
+    ///
+    /// [`AsyncRead`]: crate::stream::AsyncRead
+    /// [`AsyncWrite`]: crate::stream::AsyncWrite
+    pub fn into_inner(self) -> S {
+        self.stream
+    }
+}
+
+impl<S> AsyncRead for CountingStream<S>
+where
+    S: AsyncRead,
+{
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let this = self.project();
+        let size = buf.filled().len();
+        ready!(this.stream.poll_read(cx, buf))?;
+        this.stats
+            .count_read(buf.filled().len().saturating_sub(size));
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl<S> AsyncWrite for CountingStream<S>
+where
+    S: AsyncWrite,
+{
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<Result<usize, io::Error>> {
+        let this = self.project();
+        let bytes_written = ready!(this.stream.poll_write(cx, buf))?;
+        this.stats.count_written(bytes_written);
+        Poll::Ready(Ok(bytes_written))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), io::Error>> {
+        self.project().stream.poll_flush(cx)
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), io::Error>> {
+        self.project().stream.poll_shutdown(cx)
+    }
+
+    fn poll_write_vectored(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        bufs: &[io::IoSlice<'_>],
+    ) -> Poll<Result<usize, io::Error>> {
+        let this = self.project();
+        let bytes_written = ready!(this.stream.poll_write_vectored(cx, bufs))?;
+        this.stats.count_written(bytes_written);
+        Poll::Ready(Ok(bytes_written))
+    }
+
+    fn is_write_vectored(&self) -> bool {
+        self.stream.is_write_vectored()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio_test::io::Builder;
+
+    #[tokio::test]
+    async fn test_counting_stream() {
+        let stream = Builder::new()
+            .read(b"foo")
+            .wait(Duration::from_millis(20))
+            .write(b"barbaz")
+            .build();
+
+        let mut stream = CountingStream::new_connected(stream, Duration::from_millis(5));
+        let stats = stream.stats();
+        assert_eq!(stats.connect_duration(), Some(Duration::from_millis(5)));
+        assert_eq!(stats.last_activity(), stats.0.created_at);
+
+        let mut buf = [0u8; 3];
+        stream.read_exact(&mut buf).await.unwrap();
+        assert_eq!(stats.bytes_read(), 3);
+        assert_eq!(stats.bytes_written(), 0);
+        let first_activity = stats.last_activity();
+
+        stream.write_all(b"barbaz").await.unwrap();
+        assert_eq!(stats.bytes_read(), 3);
+        assert_eq!(stats.bytes_written(), 6);
+        assert!(stats.last_activity() >= first_activity + Duration::from_millis(20));
+        assert!(stats.idle_duration() <= stats.elapsed());
+    }
+}
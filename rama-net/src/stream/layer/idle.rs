@@ -0,0 +1,221 @@
+//! Close tunnels (and other long-lived streams) which remain idle for too long.
+//!
+//! The [`IdleTimeoutLayer`] wraps the input IO [`Stream`] in a [`CountingStream`] and
+//! aborts the inner service, closing its stream(s), as soon as no bytes were read or written
+//! for the configured duration. Unlike relying on the (often hours long) keepalive timeouts
+//! of the OS, this frees up the resources of tunnels abandoned by either peer,
+//! with an explicit [`IdleTimeoutError`] as close reason.
+//!
+//! [`Stream`]: crate::stream::Stream
+
+use super::{ConnectionStats, CountingStream};
+use crate::stream::Stream;
+use rama_core::{Context, Layer, Service, error::BoxError, telemetry::tracing};
+use rama_utils::macros::define_inner_service_accessors;
+use std::{fmt, time::Duration};
+
+/// The error returned by the [`IdleTimeoutService`] when it closed
+/// the stream because no bytes were read or written for too long.
+#[derive(Debug, Clone)]
+pub struct IdleTimeoutError {
+    idle: Duration,
+    bytes_read: u64,
+    bytes_written: u64,
+}
+
+impl IdleTimeoutError {
+    /// Returns for how long the stream was idle when it was closed.
+    #[must_use]
+    pub fn idle_duration(&self) -> Duration {
+        self.idle
+    }
+
+    /// Returns the number of bytes read from the stream before it was closed.
+    #[must_use]
+    pub fn bytes_read(&self) -> u64 {
+        self.bytes_read
+    }
+
+    /// Returns the number of bytes written to the stream before it was closed.
+    #[must_use]
+    pub fn bytes_written(&self) -> u64 {
+        self.bytes_written
+    }
+}
+
+impl fmt::Display for IdleTimeoutError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "stream closed after being idle for {:?} ({} bytes read, {} bytes written)",
+            self.idle, self.bytes_read, self.bytes_written
+        )
+    }
+}
+
+impl std::error::Error for IdleTimeoutError {}
+
+/// A [`Service`] that closes its input IO [`Stream`] once it has been idle
+/// for longer than the configured timeout, returning an [`IdleTimeoutError`].
+///
+/// The stream is wrapped in a [`CountingStream`], of which the [`ConnectionStats`]
+/// are inserted into the [`Context`] unless already present, such that it can be used
+/// in place of the [`IncomingCountingLayer`]. A tunnel is considered idle only when no bytes
+/// flow in either direction, given all of its traffic passes through this stream.
+///
+/// [`Service`]: rama_core::Service
+/// [`Stream`]: crate::stream::Stream
+/// [`IncomingCountingLayer`]: super::IncomingCountingLayer
+pub struct IdleTimeoutService<S> {
+    inner: S,
+    timeout: Duration,
+}
+
+impl<S: fmt::Debug> fmt::Debug for IdleTimeoutService<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("IdleTimeoutService")
+            .field("inner", &self.inner)
+            .field("timeout", &self.timeout)
+            .finish()
+    }
+}
+
+impl<S> IdleTimeoutService<S> {
+    /// Create a new [`IdleTimeoutService`].
+    ///
+    /// See [`IdleTimeoutService`] for more information.
+    pub const fn new(inner: S, timeout: Duration) -> Self {
+        Self { inner, timeout }
+    }
+
+    define_inner_service_accessors!();
+}
+
+impl<S> Clone for IdleTimeoutService<S>
+where
+    S: Clone,
+{
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            timeout: self.timeout,
+        }
+    }
+}
+
+impl<S, IO> Service<IO> for IdleTimeoutService<S>
+where
+    S: Service<CountingStream<IO>, Error: Into<BoxError>>,
+    IO: Stream,
+{
+    type Response = S::Response;
+    type Error = BoxError;
+
+    async fn serve(&self, mut ctx: Context, stream: IO) -> Result<Self::Response, Self::Error> {
+        let stream = CountingStream::new(stream);
+        let stats = stream.stats();
+        if !ctx.contains::<ConnectionStats>() {
+            ctx.insert(stats.clone());
+        }
+
+        tokio::select! {
+            result = self.inner.serve(ctx, stream) => result.map_err(Into::into),
+            err = reap_when_idle(&stats, self.timeout) => {
+                tracing::debug!(
+                    close.reason = "idle_timeout",
+                    "IdleTimeoutService: close stream: {err}",
+                );
+                Err(err.into())
+            }
+        }
+    }
+}
+
+/// Resolves once no bytes were read or written for the given timeout.
+async fn reap_when_idle(stats: &ConnectionStats, timeout: Duration) -> IdleTimeoutError {
+    loop {
+        let idle = stats.idle_duration();
+        if idle >= timeout {
+            return IdleTimeoutError {
+                idle,
+                bytes_read: stats.bytes_read(),
+                bytes_written: stats.bytes_written(),
+            };
+        }
+        tokio::time::sleep(timeout - idle).await;
+    }
+}
+
+/// A [`Layer`] that closes its input IO [`Stream`] once it has been idle for too long.
+///
+/// See [`IdleTimeoutService`] for more information.
+///
+/// [`Layer`]: rama_core::Layer
+/// [`Stream`]: crate::stream::Stream
+#[derive(Debug, Clone)]
+pub struct IdleTimeoutLayer {
+    timeout: Duration,
+}
+
+impl IdleTimeoutLayer {
+    /// Create a new [`IdleTimeoutLayer`], closing streams
+    /// which remain idle for longer than the given timeout.
+    #[must_use]
+    pub const fn new(timeout: Duration) -> Self {
+        Self { timeout }
+    }
+}
+
+impl<S> Layer<S> for IdleTimeoutLayer {
+    type Service = IdleTimeoutService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        IdleTimeoutService {
+            inner,
+            timeout: self.timeout,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rama_core::service::service_fn;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    #[tokio::test]
+    async fn test_idle_timeout_closes_idle_stream() {
+        let service = IdleTimeoutLayer::new(Duration::from_millis(50)).into_layer(service_fn(
+            async |_, mut stream: CountingStream<tokio::io::DuplexStream>| {
+                let mut buf = [0u8; 8];
+                loop {
+                    let n = stream.read(&mut buf).await?;
+                    if n == 0 {
+                        return Ok::<_, std::io::Error>(());
+                    }
+                    stream.write_all(&buf[..n]).await?;
+                }
+            },
+        ));
+
+        let (client, server) = tokio::io::duplex(64);
+        let handle = tokio::spawn(async move { service.serve(Context::default(), server).await });
+
+        let mut client = client;
+        for _ in 0..3 {
+            tokio::time::sleep(Duration::from_millis(30)).await;
+            client.write_all(b"ping").await.unwrap();
+            let mut buf = [0u8; 4];
+            client.read_exact(&mut buf).await.unwrap();
+        }
+
+        let err = handle.await.unwrap().unwrap_err();
+        let err = err.downcast_ref::<IdleTimeoutError>().unwrap();
+        assert!(err.idle_duration() >= Duration::from_millis(50));
+        assert_eq!(err.bytes_read(), 12);
+        assert_eq!(err.bytes_written(), 12);
+
+        let mut buf = [0u8; 4];
+        assert_eq!(client.read(&mut buf).await.unwrap(), 0);
+    }
+}
@@ -9,6 +9,40 @@ pub use tracker::{
     OutgoingBytesTrackerLayer, OutgoingBytesTrackerService,
 };
 
+mod counter;
+#[doc(inline)]
+pub use counter::{
+    ConnectionStats, CountingStream, IncomingCountingLayer, IncomingCountingService,
+    OutgoingCountingLayer, OutgoingCountingService,
+};
+
+mod idle;
+#[doc(inline)]
+pub use idle::{IdleTimeoutError, IdleTimeoutLayer, IdleTimeoutService};
+
+mod registry;
+#[doc(inline)]
+pub use registry::{
+    ConnectionId, ConnectionInfo, ConnectionKind, ConnectionRegistry, ConnectionState,
+    IncomingRegistryLayer, IncomingRegistryService, OutgoingRegistryLayer, OutgoingRegistryService,
+    RegisteredConnection, RegisteredStream,
+};
+
+mod throttle;
+#[doc(inline)]
+pub use throttle::{
+    BandwidthLimit, IncomingThrottleLayer, IncomingThrottleService, OutgoingThrottleLayer,
+    OutgoingThrottleService, ThrottledStream,
+};
+
+mod recording;
+#[doc(inline)]
+pub use recording::{
+    IncomingRecordingLayer, IncomingRecordingService, OutgoingRecordingLayer,
+    OutgoingRecordingService, RecordDirection, RecordedChunk, RecordingStream, RingBufferRecorder,
+    RotatingFileRecorder, StreamRecorder,
+};
+
 #[cfg(feature = "http")]
 pub mod http;
 
@@ -0,0 +1,103 @@
+use super::{RecordingStream, StreamRecorder};
+use crate::stream::Stream;
+use rama_core::{Context, Layer, Service};
+use rama_utils::macros::define_inner_service_accessors;
+use std::{fmt, sync::Arc};
+
+/// A [`Service`] that wraps a [`Service`]'s input IO [`Stream`] in a [`RecordingStream`].
+///
+/// [`Service`]: rama_core::Service
+/// [`Stream`]: crate::stream::Stream
+pub struct IncomingRecordingService<S> {
+    inner: S,
+    recorder: Arc<dyn StreamRecorder>,
+}
+
+impl<S: fmt::Debug> fmt::Debug for IncomingRecordingService<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("IncomingRecordingService")
+            .field("inner", &self.inner)
+            .finish()
+    }
+}
+
+impl<S> IncomingRecordingService<S> {
+    /// Create a new [`IncomingRecordingService`].
+    ///
+    /// See [`IncomingRecordingService`] for more information.
+    pub fn new(inner: S, recorder: impl StreamRecorder) -> Self {
+        Self {
+            inner,
+            recorder: Arc::new(recorder),
+        }
+    }
+
+    define_inner_service_accessors!();
+}
+
+impl<S> Clone for IncomingRecordingService<S>
+where
+    S: Clone,
+{
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            recorder: self.recorder.clone(),
+        }
+    }
+}
+
+impl<S, IO> Service<IO> for IncomingRecordingService<S>
+where
+    S: Service<RecordingStream<IO>>,
+    IO: Stream,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+
+    fn serve(
+        &self,
+        ctx: Context,
+        stream: IO,
+    ) -> impl Future<Output = Result<Self::Response, Self::Error>> + Send + '_ {
+        let stream = RecordingStream::new_shared(stream, self.recorder.clone());
+        self.inner.serve(ctx, stream)
+    }
+}
+
+/// A [`Layer`] that wraps a [`Service`]'s input IO [`Stream`] in a [`RecordingStream`].
+///
+/// [`Layer`]: rama_core::Layer
+/// [`Service`]: rama_core::Service
+/// [`Stream`]: crate::stream::Stream
+#[derive(Clone)]
+pub struct IncomingRecordingLayer {
+    recorder: Arc<dyn StreamRecorder>,
+}
+
+impl fmt::Debug for IncomingRecordingLayer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("IncomingRecordingLayer").finish()
+    }
+}
+
+impl IncomingRecordingLayer {
+    /// Create a new [`IncomingRecordingLayer`],
+    /// recording all streams into the given [`StreamRecorder`].
+    pub fn new(recorder: impl StreamRecorder) -> Self {
+        Self {
+            recorder: Arc::new(recorder),
+        }
+    }
+}
+
+impl<S> Layer<S> for IncomingRecordingLayer {
+    type Service = IncomingRecordingService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        IncomingRecordingService {
+            inner,
+            recorder: self.recorder.clone(),
+        }
+    }
+}
@@ -0,0 +1,29 @@
+//! Recording of the bytes read from and written to [`Stream`]s.
+//!
+//! Wrap accepted streams using the [`IncomingRecordingLayer`] or established
+//! connections using the [`OutgoingRecordingLayer`] to copy all bytes read and written
+//! into a [`StreamRecorder`], such as the in-memory [`RingBufferRecorder`]
+//! or the [`RotatingFileRecorder`]. Each recorded chunk is marked with its direction
+//! and a timestamp, which allows to debug protocol-level issues without external capture tools.
+//!
+//! Recording all traffic is expensive, so only enable it when debugging.
+//!
+//! [`Stream`]: crate::stream::Stream
+
+mod recorder;
+#[doc(inline)]
+pub use recorder::{
+    RecordDirection, RecordedChunk, RingBufferRecorder, RotatingFileRecorder, StreamRecorder,
+};
+
+mod stream;
+#[doc(inline)]
+pub use stream::RecordingStream;
+
+mod incoming;
+#[doc(inline)]
+pub use incoming::{IncomingRecordingLayer, IncomingRecordingService};
+
+mod outgoing;
+#[doc(inline)]
+pub use outgoing::{OutgoingRecordingLayer, OutgoingRecordingService};
@@ -0,0 +1,103 @@
+use super::{RecordingStream, StreamRecorder};
+use crate::{
+    client::{ConnectorService, EstablishedClientConnection},
+    stream::Stream,
+};
+use rama_core::{Context, Layer, Service};
+use rama_utils::macros::define_inner_service_accessors;
+use std::{fmt, sync::Arc};
+
+/// A [`Service`] that wraps a [`Service`]'s output IO [`Stream`] in a [`RecordingStream`].
+///
+/// [`Service`]: rama_core::Service
+/// [`Stream`]: crate::stream::Stream
+pub struct OutgoingRecordingService<S> {
+    inner: S,
+    recorder: Arc<dyn StreamRecorder>,
+}
+
+impl<S: fmt::Debug> fmt::Debug for OutgoingRecordingService<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("OutgoingRecordingService")
+            .field("inner", &self.inner)
+            .finish()
+    }
+}
+
+impl<S> OutgoingRecordingService<S> {
+    /// Create a new [`OutgoingRecordingService`].
+    ///
+    /// See [`OutgoingRecordingService`] for more information.
+    pub fn new(inner: S, recorder: impl StreamRecorder) -> Self {
+        Self {
+            inner,
+            recorder: Arc::new(recorder),
+        }
+    }
+
+    define_inner_service_accessors!();
+}
+
+impl<S> Clone for OutgoingRecordingService<S>
+where
+    S: Clone,
+{
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            recorder: self.recorder.clone(),
+        }
+    }
+}
+
+impl<S, Request> Service<Request> for OutgoingRecordingService<S>
+where
+    S: ConnectorService<Request, Connection: Stream + Unpin, Error: Send + 'static>,
+    Request: Send + 'static,
+{
+    type Response = EstablishedClientConnection<RecordingStream<S::Connection>, Request>;
+    type Error = S::Error;
+
+    async fn serve(&self, ctx: Context, req: Request) -> Result<Self::Response, Self::Error> {
+        let EstablishedClientConnection { ctx, req, conn } = self.inner.connect(ctx, req).await?;
+        let conn = RecordingStream::new_shared(conn, self.recorder.clone());
+        Ok(EstablishedClientConnection { ctx, req, conn })
+    }
+}
+
+/// A [`Layer`] that wraps a [`Service`]'s output IO [`Stream`] in a [`RecordingStream`].
+///
+/// [`Layer`]: rama_core::Layer
+/// [`Service`]: rama_core::Service
+/// [`Stream`]: crate::stream::Stream
+#[derive(Clone)]
+pub struct OutgoingRecordingLayer {
+    recorder: Arc<dyn StreamRecorder>,
+}
+
+impl fmt::Debug for OutgoingRecordingLayer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("OutgoingRecordingLayer").finish()
+    }
+}
+
+impl OutgoingRecordingLayer {
+    /// Create a new [`OutgoingRecordingLayer`],
+    /// recording all established connections into the given [`StreamRecorder`].
+    pub fn new(recorder: impl StreamRecorder) -> Self {
+        Self {
+            recorder: Arc::new(recorder),
+        }
+    }
+}
+
+impl<S> Layer<S> for OutgoingRecordingLayer {
+    type Service = OutgoingRecordingService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        OutgoingRecordingService {
+            inner,
+            recorder: self.recorder.clone(),
+        }
+    }
+}
@@ -0,0 +1,374 @@
+use parking_lot::Mutex;
+use rama_core::bytes::Bytes;
+use rama_core::error::{ErrorContext, OpaqueError};
+use rama_core::telemetry::tracing;
+use std::{
+    collections::VecDeque,
+    fmt,
+    fs::{File, OpenOptions},
+    io::{self, Write},
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+/// The direction of a [`RecordedChunk`], relative to the recorded stream.
+pub enum RecordDirection {
+    /// Bytes read from the stream.
+    Read,
+    /// Bytes written to the stream.
+    Write,
+}
+
+impl fmt::Display for RecordDirection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Read => f.write_str("read"),
+            Self::Write => f.write_str("write"),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+/// A chunk of bytes read from or written to a recorded stream.
+pub struct RecordedChunk {
+    stream_id: u64,
+    direction: RecordDirection,
+    timestamp: SystemTime,
+    data: Bytes,
+}
+
+impl RecordedChunk {
+    pub(super) fn new(stream_id: u64, direction: RecordDirection, data: Bytes) -> Self {
+        Self {
+            stream_id,
+            direction,
+            timestamp: SystemTime::now(),
+            data,
+        }
+    }
+
+    /// Returns the id of the recorded stream, unique within the process,
+    /// which allows to tell apart the chunks of different streams.
+    #[must_use]
+    pub fn stream_id(&self) -> u64 {
+        self.stream_id
+    }
+
+    /// Returns the [`RecordDirection`] of the chunk.
+    #[must_use]
+    pub fn direction(&self) -> RecordDirection {
+        self.direction
+    }
+
+    /// Returns the time at which the chunk was read or written.
+    #[must_use]
+    pub fn timestamp(&self) -> SystemTime {
+        self.timestamp
+    }
+
+    /// Returns the bytes of the chunk.
+    #[must_use]
+    pub fn data(&self) -> &Bytes {
+        &self.data
+    }
+
+    /// Write the chunk, prefixed by a marker line such as
+    /// `[1700000000.123 #1 read 42]`, followed by a newline.
+    fn write_to(&self, mut w: impl Write) -> io::Result<usize> {
+        let timestamp = self
+            .timestamp
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+        let marker = format!(
+            "[{}.{:03} #{} {} {}]\n",
+            timestamp.as_secs(),
+            timestamp.subsec_millis(),
+            self.stream_id,
+            self.direction,
+            self.data.len(),
+        );
+        w.write_all(marker.as_bytes())?;
+        w.write_all(&self.data)?;
+        w.write_all(b"\n")?;
+        Ok(marker.len() + self.data.len() + 1)
+    }
+}
+
+/// A sink for the chunks of bytes read from and written to a [`RecordingStream`].
+///
+/// Recording happens while polling the stream,
+/// so implementations should not block.
+///
+/// [`RecordingStream`]: super::RecordingStream
+pub trait StreamRecorder: Send + Sync + 'static {
+    /// Record the given chunk.
+    fn record(&self, chunk: RecordedChunk);
+}
+
+impl<R: StreamRecorder> StreamRecorder for Arc<R> {
+    fn record(&self, chunk: RecordedChunk) {
+        (**self).record(chunk)
+    }
+}
+
+#[derive(Debug)]
+struct RingBuffer {
+    chunks: VecDeque<RecordedChunk>,
+    size: usize,
+    capacity: usize,
+}
+
+#[derive(Debug, Clone)]
+/// A [`StreamRecorder`] keeping the most recent chunks in memory,
+/// up to a maximum amount of bytes.
+///
+/// All clones share the same buffer.
+pub struct RingBufferRecorder(Arc<Mutex<RingBuffer>>);
+
+impl RingBufferRecorder {
+    /// Create a new [`RingBufferRecorder`] which keeps at most `capacity` bytes,
+    /// dropping the oldest chunks once full.
+    #[must_use]
+    pub fn new(capacity: usize) -> Self {
+        Self(Arc::new(Mutex::new(RingBuffer {
+            chunks: VecDeque::new(),
+            size: 0,
+            capacity,
+        })))
+    }
+
+    /// Returns the recorded chunks, oldest first.
+    #[must_use]
+    pub fn chunks(&self) -> Vec<RecordedChunk> {
+        self.0.lock().chunks.iter().cloned().collect()
+    }
+
+    /// Remove all recorded chunks.
+    pub fn clear(&self) {
+        let mut buffer = self.0.lock();
+        buffer.chunks.clear();
+        buffer.size = 0;
+    }
+}
+
+impl StreamRecorder for RingBufferRecorder {
+    fn record(&self, mut chunk: RecordedChunk) {
+        let mut buffer = self.0.lock();
+        if chunk.data.len() > buffer.capacity {
+            // only keep the most recent bytes of a chunk larger than the buffer itself
+            chunk.data = chunk.data.slice(chunk.data.len() - buffer.capacity..);
+        }
+        while buffer.size + chunk.data.len() > buffer.capacity {
+            let Some(oldest) = buffer.chunks.pop_front() else {
+                break;
+            };
+            buffer.size -= oldest.data.len();
+        }
+        buffer.size += chunk.data.len();
+        buffer.chunks.push_back(chunk);
+    }
+}
+
+#[derive(Debug, Clone)]
+/// A [`StreamRecorder`] writing all chunks to a file, which is rotated
+/// once it exceeds its maximum size.
+///
+/// Each chunk is written as a marker line, containing the timestamp (unix epoch,
+/// in seconds with millisecond precision), stream id, direction and size,
+/// followed by the raw bytes and a newline.
+///
+/// Once the file at `path` exceeds the maximum size, it is renamed to `path.1`,
+/// while the previously rotated files shift to `path.2` and so on,
+/// keeping at most `max_files` rotated files.
+///
+/// Files are written on a dedicated thread, such that recording does not block.
+/// All clones share the same files.
+pub struct RotatingFileRecorder {
+    path: Arc<Path>,
+    sender: flume::Sender<RecordedChunk>,
+}
+
+impl RotatingFileRecorder {
+    /// Create a new [`RotatingFileRecorder`] writing to the file at the given path,
+    /// which is rotated once it exceeds `max_file_size` bytes.
+    pub fn try_new(
+        path: impl Into<PathBuf>,
+        max_file_size: u64,
+        max_files: usize,
+    ) -> Result<Self, OpaqueError> {
+        let path: PathBuf = path.into();
+        if let Some(parent) = path.parent()
+            && !parent.as_os_str().is_empty()
+        {
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("create parent dir(s) of recording file {path:?}"))?;
+        }
+
+        let file = open_append(&path).with_context(|| format!("open recording file {path:?}"))?;
+        let size = file
+            .metadata()
+            .context("read metadata of recording file")?
+            .len();
+
+        let (sender, rx) = flume::unbounded::<RecordedChunk>();
+        let mut writer = RotatingFileWriter {
+            path: path.clone(),
+            file: Some(file),
+            size,
+            max_file_size,
+            max_files,
+        };
+        std::thread::spawn(move || {
+            while let Ok(chunk) = rx.recv() {
+                if let Err(err) = writer.write(&chunk) {
+                    tracing::error!(
+                        file.path = ?writer.path,
+                        "RotatingFileRecorder: failed to write chunk: {err:?}",
+                    );
+                }
+            }
+        });
+
+        Ok(Self {
+            path: path.into(),
+            sender,
+        })
+    }
+
+    /// Returns the path of the (current) file written to.
+    #[must_use]
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl StreamRecorder for RotatingFileRecorder {
+    fn record(&self, chunk: RecordedChunk) {
+        if let Err(err) = self.sender.send(chunk) {
+            tracing::debug!(
+                file.path = ?self.path,
+                "RotatingFileRecorder: failed to send chunk to writer: {err:?}",
+            );
+        }
+    }
+}
+
+struct RotatingFileWriter {
+    path: PathBuf,
+    file: Option<File>,
+    size: u64,
+    max_file_size: u64,
+    max_files: usize,
+}
+
+impl RotatingFileWriter {
+    fn write(&mut self, chunk: &RecordedChunk) -> io::Result<()> {
+        let file = match self.file.take() {
+            Some(file) => file,
+            None => open_append(&self.path)?,
+        };
+        let file = self.file.insert(file);
+        self.size += chunk.write_to(file)? as u64;
+        if self.size >= self.max_file_size {
+            self.rotate()?;
+        }
+        Ok(())
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        self.file = None;
+        self.size = 0;
+        if self.max_files == 0 {
+            return std::fs::remove_file(&self.path);
+        }
+        for index in (1..self.max_files).rev() {
+            let from = rotated_path(&self.path, index);
+            if from.exists() {
+                std::fs::rename(from, rotated_path(&self.path, index + 1))?;
+            }
+        }
+        std::fs::rename(&self.path, rotated_path(&self.path, 1))
+    }
+}
+
+fn open_append(path: &Path) -> io::Result<File> {
+    OpenOptions::new().append(true).create(true).open(path)
+}
+
+fn rotated_path(path: &Path, index: usize) -> PathBuf {
+    let mut path = path.as_os_str().to_owned();
+    path.push(format!(".{index}"));
+    path.into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chunk(data: &'static [u8]) -> RecordedChunk {
+        RecordedChunk::new(1, RecordDirection::Read, Bytes::from_static(data))
+    }
+
+    #[test]
+    fn test_ring_buffer_recorder_evicts_oldest() {
+        let recorder = RingBufferRecorder::new(8);
+        recorder.record(chunk(b"abc"));
+        recorder.record(chunk(b"def"));
+        recorder.record(chunk(b"ghi"));
+
+        let data: Vec<_> = recorder.chunks().iter().map(|c| c.data().clone()).collect();
+        assert_eq!(
+            data,
+            vec![Bytes::from_static(b"def"), Bytes::from_static(b"ghi")]
+        );
+
+        recorder.record(chunk(b"0123456789"));
+        let data: Vec<_> = recorder.chunks().iter().map(|c| c.data().clone()).collect();
+        assert_eq!(data, vec![Bytes::from_static(b"23456789")]);
+
+        recorder.clear();
+        assert!(recorder.chunks().is_empty());
+    }
+
+    #[test]
+    fn test_rotating_file_writer() {
+        let dir = std::env::temp_dir().join(format!(
+            "rama-recording-test-{}-{}",
+            std::process::id(),
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap()
+                .as_nanos()
+        ));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("stream.rec");
+
+        let mut writer = RotatingFileWriter {
+            path: path.clone(),
+            file: None,
+            size: 0,
+            max_file_size: 16,
+            max_files: 2,
+        };
+        for data in [&b"first"[..], b"second", b"third"] {
+            writer
+                .write(&RecordedChunk::new(
+                    7,
+                    RecordDirection::Write,
+                    Bytes::from_static(data),
+                ))
+                .unwrap();
+        }
+
+        assert!(!path.exists());
+        let newest = std::fs::read_to_string(rotated_path(&path, 1)).unwrap();
+        assert!(newest.contains(" #7 write 5]\nthird\n"), "{newest}");
+        let oldest = std::fs::read_to_string(rotated_path(&path, 2)).unwrap();
+        assert!(oldest.contains(" #7 write 6]\nsecond\n"), "{oldest}");
+        assert!(!rotated_path(&path, 3).exists());
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,207 @@
+use super::{RecordDirection, RecordedChunk, StreamRecorder};
+use pin_project_lite::pin_project;
+use rama_core::bytes::{Bytes, BytesMut};
+use std::{
+    fmt, io,
+    pin::Pin,
+    sync::{
+        Arc,
+        atomic::{AtomicU64, Ordering},
+    },
+    task::{Context, Poll, ready},
+};
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+
+static NEXT_STREAM_ID: AtomicU64 = AtomicU64::new(1);
+
+pin_project! {
+    /// A wrapper around a [`AsyncRead`] and/or [`AsyncWrite`] that copies
+    /// all bytes read and/or written into a [`StreamRecorder`].
+    ///
+    /// [`AsyncRead`]: crate::stream::AsyncRead
+    /// [`AsyncWrite`]: crate::stream::AsyncWrite
+    pub struct RecordingStream<S> {
+        id: u64,
+        recorder: Arc<dyn StreamRecorder>,
+        #[pin]
+        stream: S,
+    }
+}
+
+impl<S: fmt::Debug> fmt::Debug for RecordingStream<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RecordingStream")
+            .field("id", &self.id)
+            .field("stream", &self.stream)
+            .finish()
+    }
+}
+
+impl<S> RecordingStream<S> {
+    /// Create a new [`RecordingStream`] that wraps the given [`AsyncRead`] and/or [`AsyncWrite`],
+    /// recording its bytes into the given [`StreamRecorder`].
+    ///
+    /// [`AsyncRead`]: crate::stream::AsyncRead
+    /// [`AsyncWrite`]: crate::stream::AsyncWrite
+    pub fn new(stream: S, recorder: impl StreamRecorder) -> Self {
+        Self::new_shared(stream, Arc::new(recorder))
+    }
+
+    pub(super) fn new_shared(stream: S, recorder: Arc<dyn StreamRecorder>) -> Self {
+        Self {
+            id: NEXT_STREAM_ID.fetch_add(1, Ordering::Relaxed),
+            recorder,
+            stream,
+        }
+    }
+
+    /// Returns the id of this stream, as found in its [`RecordedChunk`]s.
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    /// Get a reference to the inner stream.
+    pub fn get_ref(&self) -> &S {
+        &self.stream
+    }
+
+    /// Get a mutable reference to the inner stream.
+    pub fn get_mut(&mut self) -> &mut S {
+        &mut self.stream
+    }
+
+    /// Get the inner [`AsyncRead`] and/or [`AsyncWrite`] stream,
+    /// no longer recording its bytes.
+    ///
+    /// [`AsyncRead`]: crate::stream::AsyncRead
+    /// [`AsyncWrite`]: crate::stream::AsyncWrite
+    pub fn into_inner(self) -> S {
+        self.stream
+    }
+}
+
+impl<S> AsyncRead for RecordingStream<S>
+where
+    S: AsyncRead,
+{
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let this = self.project();
+        let size = buf.filled().len();
+        ready!(this.stream.poll_read(cx, buf))?;
+        if let Some(data) = buf.filled().get(size..)
+            && !data.is_empty()
+        {
+            this.recorder.record(RecordedChunk::new(
+                *this.id,
+                RecordDirection::Read,
+                Bytes::copy_from_slice(data),
+            ));
+        }
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl<S> AsyncWrite for RecordingStream<S>
+where
+    S: AsyncWrite,
+{
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<Result<usize, io::Error>> {
+        let this = self.project();
+        let bytes_written = ready!(this.stream.poll_write(cx, buf))?;
+        if bytes_written > 0 {
+            this.recorder.record(RecordedChunk::new(
+                *this.id,
+                RecordDirection::Write,
+                Bytes::copy_from_slice(&buf[..bytes_written]),
+            ));
+        }
+        Poll::Ready(Ok(bytes_written))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), io::Error>> {
+        self.project().stream.poll_flush(cx)
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), io::Error>> {
+        self.project().stream.poll_shutdown(cx)
+    }
+
+    fn poll_write_vectored(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        bufs: &[io::IoSlice<'_>],
+    ) -> Poll<Result<usize, io::Error>> {
+        let this = self.project();
+        let bytes_written = ready!(this.stream.poll_write_vectored(cx, bufs))?;
+        if bytes_written > 0 {
+            let mut data = BytesMut::with_capacity(bytes_written);
+            for buf in bufs {
+                let remaining = bytes_written - data.len();
+                if remaining == 0 {
+                    break;
+                }
+                data.extend_from_slice(&buf[..buf.len().min(remaining)]);
+            }
+            this.recorder.record(RecordedChunk::new(
+                *this.id,
+                RecordDirection::Write,
+                data.freeze(),
+            ));
+        }
+        Poll::Ready(Ok(bytes_written))
+    }
+
+    fn is_write_vectored(&self) -> bool {
+        self.stream.is_write_vectored()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stream::layer::recording::RingBufferRecorder;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio_test::io::Builder;
+
+    #[tokio::test]
+    async fn test_recording_stream() {
+        let stream = Builder::new()
+            .write(b"ping")
+            .read(b"pong")
+            .write(b"bye")
+            .build();
+
+        let recorder = RingBufferRecorder::new(1024);
+        let mut stream = RecordingStream::new(stream, recorder.clone());
+
+        let mut buf = [0u8; 4];
+        stream.write_all(b"ping").await.unwrap();
+        stream.read_exact(&mut buf).await.unwrap();
+        stream.write_all(b"bye").await.unwrap();
+
+        let chunks: Vec<_> = recorder
+            .chunks()
+            .into_iter()
+            .map(|chunk| {
+                assert_eq!(chunk.stream_id(), stream.id());
+                (chunk.direction(), chunk.data().clone())
+            })
+            .collect();
+        assert_eq!(
+            chunks,
+            vec![
+                (RecordDirection::Write, Bytes::from_static(b"ping")),
+                (RecordDirection::Read, Bytes::from_static(b"pong")),
+                (RecordDirection::Write, Bytes::from_static(b"bye")),
+            ]
+        );
+    }
+}
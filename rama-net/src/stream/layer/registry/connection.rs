@@ -0,0 +1,329 @@
+use crate::stream::layer::ConnectionStats;
+use parking_lot::Mutex;
+use rama_core::futures::task::AtomicWaker;
+use std::{
+    collections::HashMap,
+    fmt,
+    net::SocketAddr,
+    sync::{
+        Arc,
+        atomic::{AtomicU8, AtomicU64, Ordering},
+    },
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+/// Identifier of a connection registered in a [`ConnectionRegistry`],
+/// unique within that registry.
+pub struct ConnectionId(u64);
+
+impl ConnectionId {
+    /// Create a [`ConnectionId`] from its raw value,
+    /// e.g. as received by an admin endpoint.
+    #[must_use]
+    pub const fn from_u64(id: u64) -> Self {
+        Self(id)
+    }
+
+    /// Returns the raw value of this [`ConnectionId`].
+    #[must_use]
+    pub const fn as_u64(&self) -> u64 {
+        self.0
+    }
+}
+
+impl fmt::Display for ConnectionId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+/// Whether a registered connection was accepted or established.
+pub enum ConnectionKind {
+    /// A connection accepted by a server.
+    Incoming,
+    /// A connection established by a client.
+    Outgoing,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+/// The state of a registered connection.
+pub enum ConnectionState {
+    /// The connection is open.
+    Open,
+    /// The write side of the connection is shut down.
+    WriteShutdown,
+    /// The connection is forcefully closed, any further IO on it fails.
+    Closed,
+}
+
+impl ConnectionState {
+    fn from_u8(value: u8) -> Self {
+        match value {
+            0 => Self::Open,
+            1 => Self::WriteShutdown,
+            _ => Self::Closed,
+        }
+    }
+}
+
+pub(super) struct ConnectionEntry {
+    id: ConnectionId,
+    kind: ConnectionKind,
+    peer_addr: Option<SocketAddr>,
+    stats: ConnectionStats,
+    state: AtomicU8,
+    pub(super) read_waker: AtomicWaker,
+    pub(super) write_waker: AtomicWaker,
+}
+
+impl ConnectionEntry {
+    pub(super) fn id(&self) -> ConnectionId {
+        self.id
+    }
+
+    pub(super) fn state(&self) -> ConnectionState {
+        ConnectionState::from_u8(self.state.load(Ordering::Acquire))
+    }
+
+    pub(super) fn is_closed(&self) -> bool {
+        self.state() == ConnectionState::Closed
+    }
+
+    pub(super) fn mark_write_shutdown(&self) {
+        let _ = self.state.compare_exchange(
+            ConnectionState::Open as u8,
+            ConnectionState::WriteShutdown as u8,
+            Ordering::AcqRel,
+            Ordering::Acquire,
+        );
+    }
+
+    fn close(&self) {
+        self.state
+            .store(ConnectionState::Closed as u8, Ordering::Release);
+        self.read_waker.wake();
+        self.write_waker.wake();
+    }
+
+    fn info(&self) -> ConnectionInfo {
+        ConnectionInfo {
+            id: self.id,
+            kind: self.kind,
+            peer_addr: self.peer_addr,
+            state: self.state(),
+            stats: self.stats.clone(),
+        }
+    }
+}
+
+impl fmt::Debug for ConnectionEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ConnectionEntry")
+            .field("id", &self.id)
+            .field("kind", &self.kind)
+            .field("peer_addr", &self.peer_addr)
+            .field("state", &self.state())
+            .field("stats", &self.stats)
+            .finish()
+    }
+}
+
+#[derive(Debug, Default)]
+struct RegistryInner {
+    next_id: AtomicU64,
+    connections: Mutex<HashMap<ConnectionId, Arc<ConnectionEntry>>>,
+}
+
+#[derive(Debug, Clone, Default)]
+/// A registry of the active connections,
+/// registered by the [`IncomingRegistryLayer`] and [`OutgoingRegistryLayer`].
+///
+/// Connections are removed from the registry once their stream is dropped.
+/// All clones share the same registry.
+///
+/// [`IncomingRegistryLayer`]: super::IncomingRegistryLayer
+/// [`OutgoingRegistryLayer`]: super::OutgoingRegistryLayer
+pub struct ConnectionRegistry(Arc<RegistryInner>);
+
+impl ConnectionRegistry {
+    /// Create a new (empty) [`ConnectionRegistry`].
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub(super) fn register(
+        &self,
+        kind: ConnectionKind,
+        peer_addr: Option<SocketAddr>,
+        stats: ConnectionStats,
+    ) -> Registration {
+        let id = ConnectionId(self.0.next_id.fetch_add(1, Ordering::Relaxed) + 1);
+        let entry = Arc::new(ConnectionEntry {
+            id,
+            kind,
+            peer_addr,
+            stats,
+            state: AtomicU8::new(ConnectionState::Open as u8),
+            read_waker: AtomicWaker::new(),
+            write_waker: AtomicWaker::new(),
+        });
+        self.0.connections.lock().insert(id, entry.clone());
+        Registration {
+            registry: self.0.clone(),
+            entry,
+        }
+    }
+
+    /// Returns the information of all active connections, ordered by their id.
+    #[must_use]
+    pub fn connections(&self) -> Vec<ConnectionInfo> {
+        let mut connections: Vec<_> = self
+            .0
+            .connections
+            .lock()
+            .values()
+            .map(|entry| entry.info())
+            .collect();
+        connections.sort_by_key(|info| info.id);
+        connections
+    }
+
+    /// Returns the information of the active connection with the given id, if any.
+    #[must_use]
+    pub fn get(&self, id: ConnectionId) -> Option<ConnectionInfo> {
+        self.0.connections.lock().get(&id).map(|entry| entry.info())
+    }
+
+    /// Forcefully close the active connection with the given id,
+    /// returning `false` in case no such connection is registered.
+    ///
+    /// Any pending or further IO on the connection fails with
+    /// an [`std::io::ErrorKind::ConnectionAborted`] error.
+    pub fn close(&self, id: ConnectionId) -> bool {
+        match self.0.connections.lock().get(&id) {
+            Some(entry) => {
+                entry.close();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Forcefully close all active connections, returning the amount of closed connections.
+    pub fn close_all(&self) -> usize {
+        let connections = self.0.connections.lock();
+        for entry in connections.values() {
+            entry.close();
+        }
+        connections.len()
+    }
+
+    /// Returns the amount of active connections.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.0.connections.lock().len()
+    }
+
+    /// Returns `true` in case there are no active connections.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Registration of a connection, removed from its registry once dropped.
+pub(super) struct Registration {
+    registry: Arc<RegistryInner>,
+    pub(super) entry: Arc<ConnectionEntry>,
+}
+
+impl Registration {
+    pub(super) fn handle(&self) -> RegisteredConnection {
+        RegisteredConnection(self.entry.clone())
+    }
+}
+
+impl fmt::Debug for Registration {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Registration")
+            .field("entry", &self.entry)
+            .finish()
+    }
+}
+
+impl Drop for Registration {
+    fn drop(&mut self) {
+        self.registry.connections.lock().remove(&self.entry.id);
+    }
+}
+
+#[derive(Debug, Clone)]
+/// Information of a connection registered in a [`ConnectionRegistry`].
+pub struct ConnectionInfo {
+    id: ConnectionId,
+    kind: ConnectionKind,
+    peer_addr: Option<SocketAddr>,
+    state: ConnectionState,
+    stats: ConnectionStats,
+}
+
+impl ConnectionInfo {
+    /// Returns the id of the connection.
+    #[must_use]
+    pub fn id(&self) -> ConnectionId {
+        self.id
+    }
+
+    /// Returns whether the connection was accepted or established.
+    #[must_use]
+    pub fn kind(&self) -> ConnectionKind {
+        self.kind
+    }
+
+    /// Returns the address of the peer of the connection, if known.
+    #[must_use]
+    pub fn peer_addr(&self) -> Option<SocketAddr> {
+        self.peer_addr
+    }
+
+    /// Returns the state of the connection, at the time this info was created.
+    #[must_use]
+    pub fn state(&self) -> ConnectionState {
+        self.state
+    }
+
+    /// Returns the [`ConnectionStats`] of the connection,
+    /// such as its byte counters and age, which keep being updated.
+    #[must_use]
+    pub fn stats(&self) -> &ConnectionStats {
+        &self.stats
+    }
+}
+
+#[derive(Debug, Clone)]
+/// A handle to a connection registered in a [`ConnectionRegistry`],
+/// inserted into the [`Context`] of the connection.
+///
+/// [`Context`]: rama_core::Context
+pub struct RegisteredConnection(Arc<ConnectionEntry>);
+
+impl RegisteredConnection {
+    /// Returns the id of the connection.
+    #[must_use]
+    pub fn id(&self) -> ConnectionId {
+        self.0.id
+    }
+
+    /// Returns the current [`ConnectionInfo`] of the connection.
+    #[must_use]
+    pub fn info(&self) -> ConnectionInfo {
+        self.0.info()
+    }
+
+    /// Forcefully close the connection.
+    pub fn close(&self) {
+        self.0.close();
+    }
+}
@@ -0,0 +1,107 @@
+use super::{ConnectionKind, ConnectionRegistry, RegisteredStream};
+use crate::stream::{SocketInfo, Stream, layer::CountingStream};
+use rama_core::{Context, Layer, Service};
+use rama_utils::macros::define_inner_service_accessors;
+use std::fmt;
+
+/// A [`Service`] that registers its input IO [`Stream`] in a [`ConnectionRegistry`],
+/// inserting its [`RegisteredConnection`] and [`ConnectionStats`] into the [`Context`].
+///
+/// The peer address is taken from the [`SocketInfo`] found in the [`Context`], if any.
+///
+/// [`Service`]: rama_core::Service
+/// [`Stream`]: crate::stream::Stream
+/// [`RegisteredConnection`]: super::RegisteredConnection
+/// [`ConnectionStats`]: crate::stream::layer::ConnectionStats
+pub struct IncomingRegistryService<S> {
+    inner: S,
+    registry: ConnectionRegistry,
+}
+
+impl<S: fmt::Debug> fmt::Debug for IncomingRegistryService<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("IncomingRegistryService")
+            .field("inner", &self.inner)
+            .field("registry", &self.registry)
+            .finish()
+    }
+}
+
+impl<S> IncomingRegistryService<S> {
+    /// Create a new [`IncomingRegistryService`].
+    ///
+    /// See [`IncomingRegistryService`] for more information.
+    pub const fn new(inner: S, registry: ConnectionRegistry) -> Self {
+        Self { inner, registry }
+    }
+
+    define_inner_service_accessors!();
+}
+
+impl<S> Clone for IncomingRegistryService<S>
+where
+    S: Clone,
+{
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            registry: self.registry.clone(),
+        }
+    }
+}
+
+impl<S, IO> Service<IO> for IncomingRegistryService<S>
+where
+    S: Service<RegisteredStream<IO>>,
+    IO: Stream,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+
+    fn serve(
+        &self,
+        mut ctx: Context,
+        stream: IO,
+    ) -> impl Future<Output = Result<Self::Response, Self::Error>> + Send + '_ {
+        let stream = CountingStream::new(stream);
+        let peer_addr = ctx.get::<SocketInfo>().map(|info| *info.peer_addr());
+        let registration =
+            self.registry
+                .register(ConnectionKind::Incoming, peer_addr, stream.stats());
+        let stream = RegisteredStream::new(stream, registration);
+        ctx.insert(stream.handle());
+        ctx.insert(stream.stats());
+        self.inner.serve(ctx, stream)
+    }
+}
+
+/// A [`Layer`] that registers its input IO [`Stream`] in a [`ConnectionRegistry`].
+///
+/// See [`IncomingRegistryService`] for more information.
+///
+/// [`Layer`]: rama_core::Layer
+/// [`Stream`]: crate::stream::Stream
+#[derive(Debug, Clone)]
+pub struct IncomingRegistryLayer {
+    registry: ConnectionRegistry,
+}
+
+impl IncomingRegistryLayer {
+    /// Create a new [`IncomingRegistryLayer`],
+    /// registering all streams in the given [`ConnectionRegistry`].
+    #[must_use]
+    pub const fn new(registry: ConnectionRegistry) -> Self {
+        Self { registry }
+    }
+}
+
+impl<S> Layer<S> for IncomingRegistryLayer {
+    type Service = IncomingRegistryService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        IncomingRegistryService {
+            inner,
+            registry: self.registry.clone(),
+        }
+    }
+}
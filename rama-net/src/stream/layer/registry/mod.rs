@@ -0,0 +1,27 @@
+//! Opt-in tracking of the active connections of a running application.
+//!
+//! Wrap accepted streams using the [`IncomingRegistryLayer`] or established
+//! connections using the [`OutgoingRegistryLayer`] in order to register them
+//! in a [`ConnectionRegistry`] for as long as they are alive. The registry can be used by
+//! operators (e.g. via an admin endpoint) to list the active connections, with their
+//! peer, state, byte counters and age, and to forcefully close individual connections,
+//! e.g. to manage the long-lived tunnels of a running proxy.
+
+mod connection;
+#[doc(inline)]
+pub use connection::{
+    ConnectionId, ConnectionInfo, ConnectionKind, ConnectionRegistry, ConnectionState,
+    RegisteredConnection,
+};
+
+mod stream;
+#[doc(inline)]
+pub use stream::RegisteredStream;
+
+mod incoming;
+#[doc(inline)]
+pub use incoming::{IncomingRegistryLayer, IncomingRegistryService};
+
+mod outgoing;
+#[doc(inline)]
+pub use outgoing::{OutgoingRegistryLayer, OutgoingRegistryService};
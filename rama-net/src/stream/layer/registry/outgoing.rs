@@ -0,0 +1,110 @@
+use super::{ConnectionKind, ConnectionRegistry, RegisteredStream};
+use crate::{
+    client::{ConnectorService, EstablishedClientConnection},
+    stream::{ClientSocketInfo, Stream, layer::CountingStream},
+};
+use rama_core::{Context, Layer, Service};
+use rama_utils::macros::define_inner_service_accessors;
+use std::{fmt, time::Instant};
+
+/// A [`Service`] that registers its output IO [`Stream`] in a [`ConnectionRegistry`],
+/// inserting its [`RegisteredConnection`] and [`ConnectionStats`] into the [`Context`]
+/// of the established connection.
+///
+/// The peer address is taken from the [`ClientSocketInfo`] found in the [`Context`], if any.
+///
+/// [`Service`]: rama_core::Service
+/// [`Stream`]: crate::stream::Stream
+/// [`RegisteredConnection`]: super::RegisteredConnection
+/// [`ConnectionStats`]: crate::stream::layer::ConnectionStats
+pub struct OutgoingRegistryService<S> {
+    inner: S,
+    registry: ConnectionRegistry,
+}
+
+impl<S: fmt::Debug> fmt::Debug for OutgoingRegistryService<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("OutgoingRegistryService")
+            .field("inner", &self.inner)
+            .field("registry", &self.registry)
+            .finish()
+    }
+}
+
+impl<S> OutgoingRegistryService<S> {
+    /// Create a new [`OutgoingRegistryService`].
+    ///
+    /// See [`OutgoingRegistryService`] for more information.
+    pub const fn new(inner: S, registry: ConnectionRegistry) -> Self {
+        Self { inner, registry }
+    }
+
+    define_inner_service_accessors!();
+}
+
+impl<S> Clone for OutgoingRegistryService<S>
+where
+    S: Clone,
+{
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            registry: self.registry.clone(),
+        }
+    }
+}
+
+impl<S, Request> Service<Request> for OutgoingRegistryService<S>
+where
+    S: ConnectorService<Request, Connection: Stream + Unpin, Error: Send + 'static>,
+    Request: Send + 'static,
+{
+    type Response = EstablishedClientConnection<RegisteredStream<S::Connection>, Request>;
+    type Error = S::Error;
+
+    async fn serve(&self, ctx: Context, req: Request) -> Result<Self::Response, Self::Error> {
+        let start = Instant::now();
+        let EstablishedClientConnection { mut ctx, req, conn } =
+            self.inner.connect(ctx, req).await?;
+        let conn = CountingStream::new_connected(conn, start.elapsed());
+        let peer_addr = ctx.get::<ClientSocketInfo>().map(|info| *info.peer_addr());
+        let registration =
+            self.registry
+                .register(ConnectionKind::Outgoing, peer_addr, conn.stats());
+        let conn = RegisteredStream::new(conn, registration);
+        ctx.insert(conn.handle());
+        ctx.insert(conn.stats());
+        Ok(EstablishedClientConnection { ctx, req, conn })
+    }
+}
+
+/// A [`Layer`] that registers its output IO [`Stream`] in a [`ConnectionRegistry`].
+///
+/// See [`OutgoingRegistryService`] for more information.
+///
+/// [`Layer`]: rama_core::Layer
+/// [`Stream`]: crate::stream::Stream
+#[derive(Debug, Clone)]
+pub struct OutgoingRegistryLayer {
+    registry: ConnectionRegistry,
+}
+
+impl OutgoingRegistryLayer {
+    /// Create a new [`OutgoingRegistryLayer`],
+    /// registering all established connections in the given [`ConnectionRegistry`].
+    #[must_use]
+    pub const fn new(registry: ConnectionRegistry) -> Self {
+        Self { registry }
+    }
+}
+
+impl<S> Layer<S> for OutgoingRegistryLayer {
+    type Service = OutgoingRegistryService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        OutgoingRegistryService {
+            inner,
+            registry: self.registry.clone(),
+        }
+    }
+}
@@ -0,0 +1,194 @@
+use super::connection::Registration;
+use super::{ConnectionId, RegisteredConnection};
+use crate::stream::layer::{ConnectionStats, CountingStream};
+use pin_project_lite::pin_project;
+use std::{
+    fmt, io,
+    pin::Pin,
+    task::{Context, Poll, ready},
+};
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+
+pin_project! {
+    /// A wrapper around a [`AsyncRead`] and/or [`AsyncWrite`] which is registered
+    /// in a [`ConnectionRegistry`] for as long as it is alive.
+    ///
+    /// Once closed via the registry, all pending and further IO fails
+    /// with an [`io::ErrorKind::ConnectionAborted`] error.
+    ///
+    /// [`AsyncRead`]: crate::stream::AsyncRead
+    /// [`AsyncWrite`]: crate::stream::AsyncWrite
+    /// [`ConnectionRegistry`]: super::ConnectionRegistry
+    pub struct RegisteredStream<S> {
+        registration: Registration,
+        #[pin]
+        stream: CountingStream<S>,
+    }
+}
+
+impl<S: fmt::Debug> fmt::Debug for RegisteredStream<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RegisteredStream")
+            .field("registration", &self.registration)
+            .field("stream", &self.stream)
+            .finish()
+    }
+}
+
+impl<S> RegisteredStream<S> {
+    pub(super) fn new(stream: CountingStream<S>, registration: Registration) -> Self {
+        Self {
+            registration,
+            stream,
+        }
+    }
+
+    /// Returns the id of this stream in its registry.
+    pub fn id(&self) -> ConnectionId {
+        self.registration.entry.id()
+    }
+
+    /// Get a [`RegisteredConnection`] handle to this stream.
+    pub fn handle(&self) -> RegisteredConnection {
+        self.registration.handle()
+    }
+
+    /// Get a [`ConnectionStats`] handle to the counters of this stream.
+    pub fn stats(&self) -> ConnectionStats {
+        self.stream.stats()
+    }
+
+    /// Get a reference to the inner stream.
+    pub fn get_ref(&self) -> &S {
+        self.stream.get_ref()
+    }
+
+    /// Get a mutable reference to the inner stream.
+    pub fn get_mut(&mut self) -> &mut S {
+        self.stream.get_mut()
+    }
+}
+
+fn aborted() -> io::Error {
+    io::Error::new(
+        io::ErrorKind::ConnectionAborted,
+        "connection closed via registry",
+    )
+}
+
+impl<S> AsyncRead for RegisteredStream<S>
+where
+    S: AsyncRead,
+{
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let this = self.project();
+        this.registration.entry.read_waker.register(cx.waker());
+        if this.registration.entry.is_closed() {
+            return Poll::Ready(Err(aborted()));
+        }
+        this.stream.poll_read(cx, buf)
+    }
+}
+
+impl<S> AsyncWrite for RegisteredStream<S>
+where
+    S: AsyncWrite,
+{
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<Result<usize, io::Error>> {
+        let this = self.project();
+        this.registration.entry.write_waker.register(cx.waker());
+        if this.registration.entry.is_closed() {
+            return Poll::Ready(Err(aborted()));
+        }
+        this.stream.poll_write(cx, buf)
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), io::Error>> {
+        let this = self.project();
+        this.registration.entry.write_waker.register(cx.waker());
+        if this.registration.entry.is_closed() {
+            return Poll::Ready(Err(aborted()));
+        }
+        this.stream.poll_flush(cx)
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), io::Error>> {
+        let this = self.project();
+        this.registration.entry.write_waker.register(cx.waker());
+        if this.registration.entry.is_closed() {
+            return Poll::Ready(Err(aborted()));
+        }
+        ready!(this.stream.poll_shutdown(cx))?;
+        this.registration.entry.mark_write_shutdown();
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_write_vectored(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        bufs: &[io::IoSlice<'_>],
+    ) -> Poll<Result<usize, io::Error>> {
+        let this = self.project();
+        this.registration.entry.write_waker.register(cx.waker());
+        if this.registration.entry.is_closed() {
+            return Poll::Ready(Err(aborted()));
+        }
+        this.stream.poll_write_vectored(cx, bufs)
+    }
+
+    fn is_write_vectored(&self) -> bool {
+        self.stream.is_write_vectored()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stream::layer::{ConnectionKind, ConnectionRegistry, ConnectionState};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    #[tokio::test]
+    async fn test_registered_stream() {
+        let registry = ConnectionRegistry::new();
+        let (client, server) = tokio::io::duplex(64);
+
+        let stream = CountingStream::new(server);
+        let registration = registry.register(ConnectionKind::Incoming, None, stream.stats());
+        let mut stream = RegisteredStream::new(stream, registration);
+        let id = stream.id();
+
+        let mut client = client;
+        client.write_all(b"hello").await.unwrap();
+        let mut buf = [0u8; 5];
+        stream.read_exact(&mut buf).await.unwrap();
+
+        let info = registry.get(id).unwrap();
+        assert_eq!(info.kind(), ConnectionKind::Incoming);
+        assert_eq!(info.state(), ConnectionState::Open);
+        assert_eq!(info.stats().bytes_read(), 5);
+        assert_eq!(registry.connections().len(), 1);
+
+        // a pending read is aborted once the connection is closed
+        let pending = tokio::spawn(async move {
+            let result = stream.read(&mut buf).await;
+            (stream, result)
+        });
+        tokio::task::yield_now().await;
+        assert!(registry.close(id));
+        let (stream, result) = pending.await.unwrap();
+        assert_eq!(result.unwrap_err().kind(), io::ErrorKind::ConnectionAborted);
+        assert_eq!(registry.get(id).unwrap().state(), ConnectionState::Closed);
+
+        drop(stream);
+        assert!(registry.is_empty());
+        assert!(!registry.close(id));
+    }
+}
@@ -0,0 +1,99 @@
+use super::{BandwidthLimit, ThrottledStream};
+use crate::stream::Stream;
+use rama_core::{Context, Layer, Service};
+use rama_utils::macros::define_inner_service_accessors;
+use std::fmt;
+
+/// A [`Service`] that wraps a [`Service`]'s input IO [`Stream`] in a [`ThrottledStream`].
+///
+/// The [`BandwidthLimit`] found in the [`Context`] is used if present,
+/// and otherwise the [`BandwidthLimit`] configured for this service.
+///
+/// [`Service`]: rama_core::Service
+/// [`Stream`]: crate::stream::Stream
+pub struct IncomingThrottleService<S> {
+    inner: S,
+    limit: BandwidthLimit,
+}
+
+impl<S: fmt::Debug> fmt::Debug for IncomingThrottleService<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("IncomingThrottleService")
+            .field("inner", &self.inner)
+            .field("limit", &self.limit)
+            .finish()
+    }
+}
+
+impl<S> IncomingThrottleService<S> {
+    /// Create a new [`IncomingThrottleService`].
+    ///
+    /// See [`IncomingThrottleService`] for more information.
+    pub const fn new(inner: S, limit: BandwidthLimit) -> Self {
+        Self { inner, limit }
+    }
+
+    define_inner_service_accessors!();
+}
+
+impl<S> Clone for IncomingThrottleService<S>
+where
+    S: Clone,
+{
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            limit: self.limit,
+        }
+    }
+}
+
+impl<S, IO> Service<IO> for IncomingThrottleService<S>
+where
+    S: Service<ThrottledStream<IO>>,
+    IO: Stream,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+
+    fn serve(
+        &self,
+        ctx: Context,
+        stream: IO,
+    ) -> impl Future<Output = Result<Self::Response, Self::Error>> + Send + '_ {
+        let limit = ctx.get::<BandwidthLimit>().copied().unwrap_or(self.limit);
+        self.inner.serve(ctx, ThrottledStream::new(stream, limit))
+    }
+}
+
+/// A [`Layer`] that wraps a [`Service`]'s input IO [`Stream`] in a [`ThrottledStream`].
+///
+/// See [`IncomingThrottleService`] for more information.
+///
+/// [`Layer`]: rama_core::Layer
+/// [`Service`]: rama_core::Service
+/// [`Stream`]: crate::stream::Stream
+#[derive(Debug, Clone, Default)]
+pub struct IncomingThrottleLayer {
+    limit: BandwidthLimit,
+}
+
+impl IncomingThrottleLayer {
+    /// Create a new [`IncomingThrottleLayer`],
+    /// using the given [`BandwidthLimit`] unless overwritten in the [`Context`].
+    #[must_use]
+    pub const fn new(limit: BandwidthLimit) -> Self {
+        Self { limit }
+    }
+}
+
+impl<S> Layer<S> for IncomingThrottleLayer {
+    type Service = IncomingThrottleService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        IncomingThrottleService {
+            inner,
+            limit: self.limit,
+        }
+    }
+}
@@ -0,0 +1,19 @@
+//! Bandwidth throttling of [`Stream`]s.
+//!
+//! Wrap accepted streams using the [`IncomingThrottleLayer`] or established
+//! connections using the [`OutgoingThrottleLayer`] to enforce a [`BandwidthLimit`]
+//! on them, e.g. to limit the bandwidth of each tunnel of a proxy.
+//!
+//! [`Stream`]: crate::stream::Stream
+
+mod stream;
+#[doc(inline)]
+pub use stream::{BandwidthLimit, ThrottledStream};
+
+mod incoming;
+#[doc(inline)]
+pub use incoming::{IncomingThrottleLayer, IncomingThrottleService};
+
+mod outgoing;
+#[doc(inline)]
+pub use outgoing::{OutgoingThrottleLayer, OutgoingThrottleService};
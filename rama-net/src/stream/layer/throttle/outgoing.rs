@@ -0,0 +1,100 @@
+use super::{BandwidthLimit, ThrottledStream};
+use crate::{
+    client::{ConnectorService, EstablishedClientConnection},
+    stream::Stream,
+};
+use rama_core::{Context, Layer, Service};
+use rama_utils::macros::define_inner_service_accessors;
+use std::fmt;
+
+/// A [`Service`] that wraps a [`Service`]'s output IO [`Stream`] in a [`ThrottledStream`].
+///
+/// The [`BandwidthLimit`] found in the [`Context`] of the established connection is used
+/// if present, and otherwise the [`BandwidthLimit`] configured for this service.
+///
+/// [`Service`]: rama_core::Service
+/// [`Stream`]: crate::stream::Stream
+pub struct OutgoingThrottleService<S> {
+    inner: S,
+    limit: BandwidthLimit,
+}
+
+impl<S: fmt::Debug> fmt::Debug for OutgoingThrottleService<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("OutgoingThrottleService")
+            .field("inner", &self.inner)
+            .field("limit", &self.limit)
+            .finish()
+    }
+}
+
+impl<S> OutgoingThrottleService<S> {
+    /// Create a new [`OutgoingThrottleService`].
+    ///
+    /// See [`OutgoingThrottleService`] for more information.
+    pub const fn new(inner: S, limit: BandwidthLimit) -> Self {
+        Self { inner, limit }
+    }
+
+    define_inner_service_accessors!();
+}
+
+impl<S> Clone for OutgoingThrottleService<S>
+where
+    S: Clone,
+{
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            limit: self.limit,
+        }
+    }
+}
+
+impl<S, Request> Service<Request> for OutgoingThrottleService<S>
+where
+    S: ConnectorService<Request, Connection: Stream + Unpin, Error: Send + 'static>,
+    Request: Send + 'static,
+{
+    type Response = EstablishedClientConnection<ThrottledStream<S::Connection>, Request>;
+    type Error = S::Error;
+
+    async fn serve(&self, ctx: Context, req: Request) -> Result<Self::Response, Self::Error> {
+        let EstablishedClientConnection { ctx, req, conn } = self.inner.connect(ctx, req).await?;
+        let limit = ctx.get::<BandwidthLimit>().copied().unwrap_or(self.limit);
+        let conn = ThrottledStream::new(conn, limit);
+        Ok(EstablishedClientConnection { ctx, req, conn })
+    }
+}
+
+/// A [`Layer`] that wraps a [`Service`]'s output IO [`Stream`] in a [`ThrottledStream`].
+///
+/// See [`OutgoingThrottleService`] for more information.
+///
+/// [`Layer`]: rama_core::Layer
+/// [`Service`]: rama_core::Service
+/// [`Stream`]: crate::stream::Stream
+#[derive(Debug, Clone, Default)]
+pub struct OutgoingThrottleLayer {
+    limit: BandwidthLimit,
+}
+
+impl OutgoingThrottleLayer {
+    /// Create a new [`OutgoingThrottleLayer`],
+    /// using the given [`BandwidthLimit`] unless overwritten in the [`Context`].
+    #[must_use]
+    pub const fn new(limit: BandwidthLimit) -> Self {
+        Self { limit }
+    }
+}
+
+impl<S> Layer<S> for OutgoingThrottleLayer {
+    type Service = OutgoingThrottleService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        OutgoingThrottleService {
+            inner,
+            limit: self.limit,
+        }
+    }
+}
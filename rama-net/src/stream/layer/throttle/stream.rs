@@ -0,0 +1,366 @@
+use pin_project_lite::pin_project;
+use std::{
+    fmt, io,
+    pin::Pin,
+    task::{Context, Poll, ready},
+    time::Duration,
+};
+use tokio::{
+    io::{AsyncRead, AsyncWrite, ReadBuf},
+    time::{Instant, Sleep},
+};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+/// The bandwidth limit enforced by a [`ThrottledStream`], per direction.
+///
+/// A limit is expressed in bytes per second. Each direction has its own
+/// token bucket, which can hold up to `burst` bytes (by default one second worth of traffic).
+///
+/// Insert it in the [`Context`] to overwrite the limit configured on
+/// the [`IncomingThrottleLayer`] or [`OutgoingThrottleLayer`] for a specific connection.
+///
+/// [`Context`]: rama_core::Context
+/// [`IncomingThrottleLayer`]: super::IncomingThrottleLayer
+/// [`OutgoingThrottleLayer`]: super::OutgoingThrottleLayer
+pub struct BandwidthLimit {
+    read: Option<u64>,
+    write: Option<u64>,
+    burst: Option<u64>,
+}
+
+impl BandwidthLimit {
+    /// Create a new [`BandwidthLimit`], which does not limit either direction.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            read: None,
+            write: None,
+            burst: None,
+        }
+    }
+
+    /// Create a new [`BandwidthLimit`], limiting both directions
+    /// to the given amount of bytes per second.
+    #[must_use]
+    pub const fn symmetric(bytes_per_second: u64) -> Self {
+        Self {
+            read: Some(bytes_per_second),
+            write: Some(bytes_per_second),
+            burst: None,
+        }
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Set the amount of bytes per second that can be read from the stream.
+        ///
+        /// A limit of `0` disables the limit.
+        pub fn read_limit(mut self, bytes_per_second: Option<u64>) -> Self {
+            self.read = bytes_per_second;
+            self
+        }
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Set the amount of bytes per second that can be written to the stream.
+        ///
+        /// A limit of `0` disables the limit.
+        pub fn write_limit(mut self, bytes_per_second: Option<u64>) -> Self {
+            self.write = bytes_per_second;
+            self
+        }
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Set the maximum amount of bytes that can be transferred at once
+        /// after a period of inactivity, for both directions.
+        ///
+        /// Defaults to the limit of the direction, i.e. one second worth of traffic.
+        pub fn burst(mut self, bytes: Option<u64>) -> Self {
+            self.burst = bytes;
+            self
+        }
+    }
+
+    /// Returns the amount of bytes per second that can be read, if limited.
+    #[must_use]
+    pub fn read_limit(&self) -> Option<u64> {
+        self.read.filter(|limit| *limit > 0)
+    }
+
+    /// Returns the amount of bytes per second that can be written, if limited.
+    #[must_use]
+    pub fn write_limit(&self) -> Option<u64> {
+        self.write.filter(|limit| *limit > 0)
+    }
+
+    fn bucket(&self, bytes_per_second: Option<u64>) -> Option<TokenBucket> {
+        let rate = bytes_per_second.filter(|limit| *limit > 0)?;
+        let burst = self.burst.filter(|burst| *burst > 0).unwrap_or(rate);
+        Some(TokenBucket::new(rate, burst))
+    }
+}
+
+/// Token bucket used to throttle a single direction of a [`ThrottledStream`].
+///
+/// Transferred bytes are consumed after the fact, which can make
+/// the bucket go into debt in case of reads. No bytes are transferred until that debt is repaid.
+struct TokenBucket {
+    rate: f64,
+    capacity: f64,
+    tokens: f64,
+    last_refill: Instant,
+    sleep: Option<Pin<Box<Sleep>>>,
+}
+
+impl fmt::Debug for TokenBucket {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TokenBucket")
+            .field("rate", &self.rate)
+            .field("capacity", &self.capacity)
+            .field("tokens", &self.tokens)
+            .field("last_refill", &self.last_refill)
+            .finish()
+    }
+}
+
+impl TokenBucket {
+    fn new(rate: u64, burst: u64) -> Self {
+        Self {
+            rate: rate as f64,
+            capacity: burst as f64,
+            tokens: burst as f64,
+            last_refill: Instant::now(),
+            sleep: None,
+        }
+    }
+
+    fn refill(&mut self) {
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.last_refill).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.rate).min(self.capacity);
+        self.last_refill = now;
+    }
+
+    /// Wait until (a chunk of) the wanted amount of bytes can be transferred,
+    /// returning the amount of bytes that can be transferred without going into debt.
+    ///
+    /// Waiting for a chunk, rather than a single byte, avoids
+    /// the stream from being woken up for every byte.
+    fn poll_available(&mut self, cx: &mut Context<'_>, wanted: usize) -> Poll<usize> {
+        let min_chunk = (self.rate / 100.0).max(1.0);
+        let threshold = (wanted as f64).min(self.capacity).min(min_chunk).max(1.0);
+        loop {
+            self.refill();
+            if self.tokens >= threshold {
+                self.sleep = None;
+                return Poll::Ready(self.tokens as usize);
+            }
+
+            let wait = Duration::from_secs_f64((threshold - self.tokens) / self.rate);
+            let deadline = self.last_refill + wait;
+            match self.sleep.as_mut() {
+                Some(sleep) => sleep.as_mut().reset(deadline),
+                None => self.sleep = Some(Box::pin(tokio::time::sleep_until(deadline))),
+            }
+            if let Some(sleep) = self.sleep.as_mut() {
+                ready!(sleep.as_mut().poll(cx));
+            }
+        }
+    }
+
+    fn consume(&mut self, bytes: usize) {
+        self.tokens -= bytes as f64;
+    }
+}
+
+pin_project! {
+    /// A wrapper around a [`AsyncRead`] and/or [`AsyncWrite`] that limits
+    /// the bandwidth used to read and/or write, as defined by a [`BandwidthLimit`].
+    ///
+    /// Each direction is throttled independently using its own token bucket.
+    ///
+    /// [`AsyncRead`]: crate::stream::AsyncRead
+    /// [`AsyncWrite`]: crate::stream::AsyncWrite
+    pub struct ThrottledStream<S> {
+        read: Option<TokenBucket>,
+        write: Option<TokenBucket>,
+        #[pin]
+        stream: S,
+    }
+}
+
+impl<S: fmt::Debug> fmt::Debug for ThrottledStream<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ThrottledStream")
+            .field("read", &self.read)
+            .field("write", &self.write)
+            .field("stream", &self.stream)
+            .finish()
+    }
+}
+
+impl<S> ThrottledStream<S> {
+    /// Create a new [`ThrottledStream`] that wraps the given [`AsyncRead`] and/or [`AsyncWrite`],
+    /// limiting its bandwidth as defined by the given [`BandwidthLimit`].
+    ///
+    /// [`AsyncRead`]: crate::stream::AsyncRead
+    /// [`AsyncWrite`]: crate::stream::AsyncWrite
+    pub fn new(stream: S, limit: BandwidthLimit) -> Self {
+        Self {
+            read: limit.bucket(limit.read),
+            write: limit.bucket(limit.write),
+            stream,
+        }
+    }
+
+    /// Get a reference to the inner stream.
+    pub fn get_ref(&self) -> &S {
+        &self.stream
+    }
+
+    /// Get a mutable reference to the inner stream.
+    pub fn get_mut(&mut self) -> &mut S {
+        &mut self.stream
+    }
+
+    /// Get the inner [`AsyncRead`] and/or [`AsyncWrite`] stream,
+    /// dropping the bandwidth limits for this stream.
+    ///
+    /// [`AsyncRead`]: crate::stream::AsyncRead
+    /// [`AsyncWrite`]: crate::stream::AsyncWrite
+    pub fn into_inner(self) -> S {
+        self.stream
+    }
+}
+
+impl<S> AsyncRead for ThrottledStream<S>
+where
+    S: AsyncRead,
+{
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let this = self.project();
+        let Some(bucket) = this.read else {
+            return this.stream.poll_read(cx, buf);
+        };
+
+        ready!(bucket.poll_available(cx, buf.remaining()));
+        let size = buf.filled().len();
+        ready!(this.stream.poll_read(cx, buf))?;
+        bucket.consume(buf.filled().len().saturating_sub(size));
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl<S> AsyncWrite for ThrottledStream<S>
+where
+    S: AsyncWrite,
+{
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<Result<usize, io::Error>> {
+        let this = self.project();
+        let Some(bucket) = this.write else {
+            return this.stream.poll_write(cx, buf);
+        };
+
+        let available = ready!(bucket.poll_available(cx, buf.len()));
+        let buf = &buf[..buf.len().min(available)];
+        let bytes_written = ready!(this.stream.poll_write(cx, buf))?;
+        bucket.consume(bytes_written);
+        Poll::Ready(Ok(bytes_written))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), io::Error>> {
+        self.project().stream.poll_flush(cx)
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), io::Error>> {
+        self.project().stream.poll_shutdown(cx)
+    }
+
+    fn poll_write_vectored(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        bufs: &[io::IoSlice<'_>],
+    ) -> Poll<Result<usize, io::Error>> {
+        let this = self.project();
+        let Some(bucket) = this.write else {
+            return this.stream.poll_write_vectored(cx, bufs);
+        };
+
+        // only write (part of) the first non-empty buffer,
+        // such that the amount of bytes written is bound by the available tokens
+        let Some(buf) = bufs.iter().find(|buf| !buf.is_empty()) else {
+            return this.stream.poll_write(cx, &[]);
+        };
+        let available = ready!(bucket.poll_available(cx, buf.len()));
+        let buf = &buf[..buf.len().min(available)];
+        let bytes_written = ready!(this.stream.poll_write(cx, buf))?;
+        bucket.consume(bytes_written);
+        Poll::Ready(Ok(bytes_written))
+    }
+
+    fn is_write_vectored(&self) -> bool {
+        self.write.is_none() && self.stream.is_write_vectored()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    #[tokio::test]
+    async fn test_throttled_write() {
+        let (client, mut server) = tokio::io::duplex(1024);
+        let mut stream = ThrottledStream::new(
+            client,
+            BandwidthLimit::new().with_write_limit(1000).with_burst(100),
+        );
+
+        let start = std::time::Instant::now();
+        stream.write_all(&[0u8; 300]).await.unwrap();
+        // the burst is available immediately, the remaining 200 bytes take ~200ms
+        assert!(start.elapsed() >= Duration::from_millis(150));
+
+        let mut buf = [0u8; 300];
+        server.read_exact(&mut buf).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_throttled_read() {
+        let (client, mut server) = tokio::io::duplex(1024);
+        let mut stream = ThrottledStream::new(
+            client,
+            BandwidthLimit::new().with_read_limit(1000).with_burst(100),
+        );
+
+        server.write_all(&[0u8; 300]).await.unwrap();
+
+        let start = std::time::Instant::now();
+        let mut buf = [0u8; 100];
+        for _ in 0..3 {
+            stream.read_exact(&mut buf).await.unwrap();
+        }
+        // the first read consumes the burst, the last one has to wait for the debt to be repaid
+        assert!(start.elapsed() >= Duration::from_millis(90));
+    }
+
+    #[tokio::test]
+    async fn test_unlimited_stream() {
+        let (client, mut server) = tokio::io::duplex(1024);
+        let mut stream = ThrottledStream::new(client, BandwidthLimit::new());
+
+        let start = std::time::Instant::now();
+        stream.write_all(&[0u8; 512]).await.unwrap();
+        let mut buf = [0u8; 512];
+        server.read_exact(&mut buf).await.unwrap();
+        assert!(start.elapsed() < Duration::from_millis(100));
+    }
+}
@@ -96,6 +96,7 @@ impl DerefMut for ClientSocketInfo {
 pub struct SocketInfo {
     local_addr: Option<SocketAddr>,
     peer_addr: SocketAddr,
+    multipath: bool,
 }
 
 impl SocketInfo {
@@ -105,6 +106,15 @@ impl SocketInfo {
         Self {
             local_addr,
             peer_addr,
+            multipath: false,
+        }
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Mark the socket as a Multipath TCP (MPTCP) socket.
+        pub fn multipath(mut self, multipath: bool) -> Self {
+            self.multipath = multipath;
+            self
         }
     }
 
@@ -119,4 +129,13 @@ impl SocketInfo {
     pub fn peer_addr(&self) -> &SocketAddr {
         &self.peer_addr
     }
+
+    /// Returns `true` in case the socket is a Multipath TCP (MPTCP) socket.
+    ///
+    /// Note that a MPTCP connection can still fall back to plain TCP
+    /// in case the peer does not support MPTCP.
+    #[must_use]
+    pub fn is_multipath(&self) -> bool {
+        self.multipath
+    }
 }
@@ -1,9 +1,8 @@
-use std::{convert::Infallible, fmt, net::Ipv4Addr};
+use std::{convert::Infallible, fmt};
 
 use rama_core::{Context, Service};
-use tokio::io::{AsyncRead, AsyncWrite, DuplexStream, duplex};
 
-use crate::{client::EstablishedClientConnection, stream::Socket};
+use crate::{client::EstablishedClientConnection, test_utils::stream::MockSocket};
 
 /// Mock connector can be used in tests to simulate connectors so we can test client and servers
 /// without opening actuall connections
@@ -55,9 +54,7 @@ where
     type Response = EstablishedClientConnection<MockSocket, Request>;
 
     async fn serve(&self, ctx: Context, req: Request) -> Result<Self::Response, Self::Error> {
-        let (client, server) = duplex(self.max_buffer_size);
-        let client_socket = MockSocket { stream: client };
-        let server_socket = MockSocket { stream: server };
+        let (client_socket, server_socket) = MockSocket::pair(self.max_buffer_size);
 
         let server = (self.create_server)();
         let server_ctx = ctx.clone();
@@ -73,70 +70,3 @@ where
         })
     }
 }
-
-#[derive(Debug)]
-pub struct MockSocket {
-    stream: DuplexStream,
-}
-
-impl AsyncRead for MockSocket {
-    fn poll_read(
-        mut self: std::pin::Pin<&mut Self>,
-        cx: &mut std::task::Context<'_>,
-        buf: &mut tokio::io::ReadBuf<'_>,
-    ) -> std::task::Poll<std::io::Result<()>> {
-        std::pin::Pin::new(&mut self.stream).poll_read(cx, buf)
-    }
-}
-
-impl AsyncWrite for MockSocket {
-    fn poll_write(
-        mut self: std::pin::Pin<&mut Self>,
-        cx: &mut std::task::Context<'_>,
-        buf: &[u8],
-    ) -> std::task::Poll<std::io::Result<usize>> {
-        std::pin::Pin::new(&mut self.stream).poll_write(cx, buf)
-    }
-
-    fn poll_flush(
-        mut self: std::pin::Pin<&mut Self>,
-        cx: &mut std::task::Context<'_>,
-    ) -> std::task::Poll<std::io::Result<()>> {
-        std::pin::Pin::new(&mut self.stream).poll_flush(cx)
-    }
-
-    fn poll_shutdown(
-        mut self: std::pin::Pin<&mut Self>,
-        cx: &mut std::task::Context<'_>,
-    ) -> std::task::Poll<std::io::Result<()>> {
-        std::pin::Pin::new(&mut self.stream).poll_shutdown(cx)
-    }
-
-    fn is_write_vectored(&self) -> bool {
-        self.stream.is_write_vectored()
-    }
-
-    fn poll_write_vectored(
-        mut self: std::pin::Pin<&mut Self>,
-        cx: &mut std::task::Context<'_>,
-        bufs: &[std::io::IoSlice<'_>],
-    ) -> std::task::Poll<Result<usize, std::io::Error>> {
-        std::pin::Pin::new(&mut self.stream).poll_write_vectored(cx, bufs)
-    }
-}
-
-impl Socket for MockSocket {
-    fn local_addr(&self) -> std::io::Result<std::net::SocketAddr> {
-        Ok(std::net::SocketAddr::V4(std::net::SocketAddrV4::new(
-            Ipv4Addr::new(127, 0, 0, 1),
-            0,
-        )))
-    }
-
-    fn peer_addr(&self) -> std::io::Result<std::net::SocketAddr> {
-        Ok(std::net::SocketAddr::V4(std::net::SocketAddrV4::new(
-            Ipv4Addr::new(127, 0, 0, 1),
-            0,
-        )))
-    }
-}
@@ -0,0 +1,385 @@
+use pin_project_lite::pin_project;
+use std::{
+    fmt, io,
+    pin::Pin,
+    task::{Context, Poll, ready},
+    time::Duration,
+};
+use tokio::{
+    io::{AsyncRead, AsyncWrite, ReadBuf},
+    time::Sleep,
+};
+
+#[derive(Debug, Clone)]
+/// The misbehavior injected by a [`ChaosStream`], drawn from a schedule seeded by [`ChaosConfig::new`].
+///
+/// All probabilities are per read or write operation, and default to `0.0`,
+/// such that a [`ChaosStream`] with a default config behaves as the stream it wraps.
+pub struct ChaosConfig {
+    seed: u64,
+    delay_probability: f64,
+    max_delay: Duration,
+    short_read_probability: f64,
+    short_write_probability: f64,
+    error_probability: f64,
+    error_kind: io::ErrorKind,
+}
+
+impl ChaosConfig {
+    /// Create a new [`ChaosConfig`], of which the schedule is generated using the given seed.
+    ///
+    /// The same seed results in the same misbehavior for the same sequence of operations.
+    #[must_use]
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            delay_probability: 0.0,
+            max_delay: Duration::from_millis(10),
+            short_read_probability: 0.0,
+            short_write_probability: 0.0,
+            error_probability: 0.0,
+            error_kind: io::ErrorKind::ConnectionReset,
+        }
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Set the probability that an operation is delayed, by at most [`Self::max_delay`].
+        pub fn delay_probability(mut self, probability: f64) -> Self {
+            self.delay_probability = probability;
+            self
+        }
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Set the maximum delay of a delayed operation, `10ms` by default.
+        pub fn max_delay(mut self, delay: Duration) -> Self {
+            self.max_delay = delay;
+            self
+        }
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Set the probability that a read returns less bytes than requested (but at least one).
+        pub fn short_read_probability(mut self, probability: f64) -> Self {
+            self.short_read_probability = probability;
+            self
+        }
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Set the probability that a write accepts less bytes than given (but at least one).
+        pub fn short_write_probability(mut self, probability: f64) -> Self {
+            self.short_write_probability = probability;
+            self
+        }
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Set the probability that an operation fails, after which all operations fail.
+        pub fn error_probability(mut self, probability: f64) -> Self {
+            self.error_probability = probability;
+            self
+        }
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Set the [`io::ErrorKind`] of injected errors, [`io::ErrorKind::ConnectionReset`] by default.
+        pub fn error_kind(mut self, kind: io::ErrorKind) -> Self {
+            self.error_kind = kind;
+            self
+        }
+    }
+}
+
+/// The misbehavior planned for a single read or write operation.
+struct Operation {
+    delay: Option<Pin<Box<Sleep>>>,
+    fraction: Option<f64>,
+    fail: bool,
+}
+
+impl Operation {
+    fn plan(config: &ChaosConfig, rng: &mut SplitMix64, short_probability: f64) -> Self {
+        // always draw the same amount of numbers, such that the schedule of
+        // an operation does not depend on the outcome of the previous ones
+        let delay_roll = rng.next_f64();
+        let delay_fraction = rng.next_f64();
+        let short_roll = rng.next_f64();
+        let short_fraction = rng.next_f64();
+        let fail_roll = rng.next_f64();
+
+        Self {
+            delay: (delay_roll < config.delay_probability)
+                .then(|| Box::pin(tokio::time::sleep(config.max_delay.mul_f64(delay_fraction)))),
+            fraction: (short_roll < short_probability).then_some(short_fraction),
+            fail: fail_roll < config.error_probability,
+        }
+    }
+
+    /// Returns the amount of bytes the operation is limited to.
+    fn limit(&self, len: usize) -> usize {
+        match self.fraction {
+            Some(fraction) => ((len as f64 * fraction) as usize).clamp(1, len.max(1)),
+            None => len,
+        }
+    }
+}
+
+pin_project! {
+    /// A test-support wrapper around a [`AsyncRead`] and/or [`AsyncWrite`]
+    /// which injects delays, short reads and writes, and errors, according to
+    /// the seeded schedule of its [`ChaosConfig`].
+    ///
+    /// This allows to test service stacks deterministically against a misbehaving network.
+    ///
+    /// [`AsyncRead`]: crate::stream::AsyncRead
+    /// [`AsyncWrite`]: crate::stream::AsyncWrite
+    pub struct ChaosStream<S> {
+        #[pin]
+        stream: S,
+        config: ChaosConfig,
+        rng: SplitMix64,
+        read_op: Option<Operation>,
+        write_op: Option<Operation>,
+        failed: bool,
+    }
+}
+
+impl<S: fmt::Debug> fmt::Debug for ChaosStream<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ChaosStream")
+            .field("stream", &self.stream)
+            .field("config", &self.config)
+            .field("failed", &self.failed)
+            .finish()
+    }
+}
+
+impl<S> ChaosStream<S> {
+    /// Create a new [`ChaosStream`] which wraps the given stream,
+    /// misbehaving as defined by the given [`ChaosConfig`].
+    pub fn new(stream: S, config: ChaosConfig) -> Self {
+        Self {
+            stream,
+            rng: SplitMix64(config.seed),
+            config,
+            read_op: None,
+            write_op: None,
+            failed: false,
+        }
+    }
+
+    /// Returns `true` in case an error was injected,
+    /// after which all operations fail.
+    #[must_use]
+    pub fn is_failed(&self) -> bool {
+        self.failed
+    }
+
+    /// Get a reference to the inner stream.
+    pub fn get_ref(&self) -> &S {
+        &self.stream
+    }
+
+    /// Get a mutable reference to the inner stream.
+    pub fn get_mut(&mut self) -> &mut S {
+        &mut self.stream
+    }
+
+    /// Consume the [`ChaosStream`], returning the inner stream.
+    pub fn into_inner(self) -> S {
+        self.stream
+    }
+}
+
+fn injected_error(kind: io::ErrorKind) -> io::Error {
+    io::Error::new(kind, "chaos stream: injected error")
+}
+
+/// Poll the delay of the operation, planning it first in case it is not yet started,
+/// returning the operation once it is ready to be executed.
+fn poll_operation<'a>(
+    op: &'a mut Option<Operation>,
+    cx: &mut Context<'_>,
+    config: &ChaosConfig,
+    rng: &mut SplitMix64,
+    short_probability: f64,
+) -> Poll<&'a mut Operation> {
+    let op = op.get_or_insert_with(|| Operation::plan(config, rng, short_probability));
+    if let Some(delay) = op.delay.as_mut() {
+        ready!(delay.as_mut().poll(cx));
+        op.delay = None;
+    }
+    Poll::Ready(op)
+}
+
+impl<S> AsyncRead for ChaosStream<S>
+where
+    S: AsyncRead,
+{
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let this = self.project();
+        if *this.failed {
+            return Poll::Ready(Err(injected_error(this.config.error_kind)));
+        }
+
+        let op = ready!(poll_operation(
+            this.read_op,
+            cx,
+            this.config,
+            this.rng,
+            this.config.short_read_probability,
+        ));
+        if op.fail {
+            *this.read_op = None;
+            *this.failed = true;
+            return Poll::Ready(Err(injected_error(this.config.error_kind)));
+        }
+
+        let limit = op.limit(buf.remaining());
+        let result = if limit < buf.remaining() {
+            let mut limited = ReadBuf::new(buf.initialize_unfilled_to(limit));
+            let result = ready!(this.stream.poll_read(cx, &mut limited));
+            let n = limited.filled().len();
+            buf.advance(n);
+            result
+        } else {
+            ready!(this.stream.poll_read(cx, buf))
+        };
+        *this.read_op = None;
+        Poll::Ready(result)
+    }
+}
+
+impl<S> AsyncWrite for ChaosStream<S>
+where
+    S: AsyncWrite,
+{
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<Result<usize, io::Error>> {
+        let this = self.project();
+        if *this.failed {
+            return Poll::Ready(Err(injected_error(this.config.error_kind)));
+        }
+
+        let op = ready!(poll_operation(
+            this.write_op,
+            cx,
+            this.config,
+            this.rng,
+            this.config.short_write_probability,
+        ));
+        if op.fail {
+            *this.write_op = None;
+            *this.failed = true;
+            return Poll::Ready(Err(injected_error(this.config.error_kind)));
+        }
+
+        let limit = op.limit(buf.len());
+        let result = ready!(this.stream.poll_write(cx, &buf[..limit]));
+        *this.write_op = None;
+        Poll::Ready(result)
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), io::Error>> {
+        let this = self.project();
+        if *this.failed {
+            return Poll::Ready(Err(injected_error(this.config.error_kind)));
+        }
+        this.stream.poll_flush(cx)
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), io::Error>> {
+        let this = self.project();
+        if *this.failed {
+            return Poll::Ready(Err(injected_error(this.config.error_kind)));
+        }
+        this.stream.poll_shutdown(cx)
+    }
+}
+
+/// Minimal deterministic pseudo random number generator,
+/// such that a seed results in the same schedule regardless of dependency versions.
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Returns a number in the range `[0, 1)`.
+    fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    async fn echo_through_chaos(config: ChaosConfig) -> (Vec<usize>, Vec<u8>) {
+        let (client, mut server) = tokio::io::duplex(1024);
+        let mut client = ChaosStream::new(client, config);
+
+        let data: Vec<u8> = (0..=255).collect();
+        let mut write_sizes = Vec::new();
+        let mut offset = 0;
+        while offset < data.len() {
+            let n = client.write(&data[offset..]).await.unwrap();
+            write_sizes.push(n);
+            offset += n;
+        }
+        client.shutdown().await.unwrap();
+
+        let mut received = Vec::new();
+        server.read_to_end(&mut received).await.unwrap();
+        (write_sizes, received)
+    }
+
+    #[tokio::test]
+    async fn test_chaos_stream_is_deterministic() {
+        let config = ChaosConfig::new(42)
+            .with_short_write_probability(0.5)
+            .with_delay_probability(0.5)
+            .with_max_delay(Duration::from_millis(1));
+
+        let (sizes_a, received_a) = echo_through_chaos(config.clone()).await;
+        let (sizes_b, received_b) = echo_through_chaos(config).await;
+
+        assert_eq!(sizes_a, sizes_b);
+        assert!(sizes_a.len() > 1, "{sizes_a:?}");
+        assert_eq!(received_a, (0..=255).collect::<Vec<u8>>());
+        assert_eq!(received_a, received_b);
+    }
+
+    #[tokio::test]
+    async fn test_chaos_stream_injects_errors() {
+        let (client, _server) = tokio::io::duplex(1024);
+        let mut client = ChaosStream::new(
+            client,
+            ChaosConfig::new(7)
+                .with_error_probability(1.0)
+                .with_error_kind(io::ErrorKind::BrokenPipe),
+        );
+
+        let err = client.write_all(b"hello").await.unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::BrokenPipe);
+        assert!(client.is_failed());
+
+        let mut buf = [0u8; 4];
+        let err = client.read(&mut buf).await.unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::BrokenPipe);
+    }
+}
@@ -0,0 +1,133 @@
+use std::{
+    io::{self, IoSlice},
+    net::{IpAddr, Ipv4Addr, SocketAddr},
+    pin::Pin,
+    task::{Context, Poll},
+};
+use tokio::io::{AsyncRead, AsyncWrite, DuplexStream, ReadBuf, duplex};
+
+use crate::stream::Socket;
+
+/// The local address of the client end of a [`MockSocket::pair`].
+pub const MOCK_CLIENT_ADDR: SocketAddr = SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), 40000);
+
+/// The local address of the server end of a [`MockSocket::pair`].
+pub const MOCK_SERVER_ADDR: SocketAddr = SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), 8080);
+
+#[derive(Debug)]
+/// An in-memory [`Stream`] with fake socket addresses,
+/// which can be used to test clients and servers without binding actual sockets.
+///
+/// # Example
+///
+/// ```
+/// use rama_net::test_utils::stream::MockSocket;
+/// use tokio::io::{AsyncReadExt, AsyncWriteExt};
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// let (mut client, mut server) = MockSocket::pair(1024);
+/// client.write_all(b"ping").await.unwrap();
+///
+/// let mut buf = [0; 4];
+/// server.read_exact(&mut buf).await.unwrap();
+/// assert_eq!(&buf, b"ping");
+/// # }
+/// ```
+///
+/// [`Stream`]: crate::stream::Stream
+pub struct MockSocket {
+    stream: DuplexStream,
+    local_addr: SocketAddr,
+    peer_addr: SocketAddr,
+}
+
+impl MockSocket {
+    /// Create a connected pair of [`MockSocket`]s, the client and server end,
+    /// of which the data written to one end can be read from the other.
+    ///
+    /// Each direction buffers at most `max_buffer_size` bytes.
+    /// The client end is bound to [`MOCK_CLIENT_ADDR`] and the server end to [`MOCK_SERVER_ADDR`].
+    #[must_use]
+    pub fn pair(max_buffer_size: usize) -> (Self, Self) {
+        let (client, server) = duplex(max_buffer_size);
+        (
+            Self {
+                stream: client,
+                local_addr: MOCK_CLIENT_ADDR,
+                peer_addr: MOCK_SERVER_ADDR,
+            },
+            Self {
+                stream: server,
+                local_addr: MOCK_SERVER_ADDR,
+                peer_addr: MOCK_CLIENT_ADDR,
+            },
+        )
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Set the local address of this end of the [`MockSocket`].
+        pub fn local_addr(mut self, addr: SocketAddr) -> Self {
+            self.local_addr = addr;
+            self
+        }
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Set the peer address of this end of the [`MockSocket`].
+        pub fn peer_addr(mut self, addr: SocketAddr) -> Self {
+            self.peer_addr = addr;
+            self
+        }
+    }
+}
+
+impl AsyncRead for MockSocket {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.stream).poll_read(cx, buf)
+    }
+}
+
+impl AsyncWrite for MockSocket {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.stream).poll_write(cx, buf)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.stream).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.stream).poll_shutdown(cx)
+    }
+
+    fn is_write_vectored(&self) -> bool {
+        self.stream.is_write_vectored()
+    }
+
+    fn poll_write_vectored(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        bufs: &[IoSlice<'_>],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.stream).poll_write_vectored(cx, bufs)
+    }
+}
+
+impl Socket for MockSocket {
+    fn local_addr(&self) -> io::Result<SocketAddr> {
+        Ok(self.local_addr)
+    }
+
+    fn peer_addr(&self) -> io::Result<SocketAddr> {
+        Ok(self.peer_addr)
+    }
+}
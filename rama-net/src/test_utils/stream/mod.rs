@@ -0,0 +1,5 @@
+mod chaos;
+pub use chaos::{ChaosConfig, ChaosStream};
+
+mod mock_socket;
+pub use mock_socket::{MOCK_CLIENT_ADDR, MOCK_SERVER_ADDR, MockSocket};
@@ -0,0 +1,177 @@
+//! QUIC client module for Rama.
+//!
+//! The [`QuicConnector`] establishes a QUIC connection to the server
+//! and opens a bidirectional [`QuicStream`] on it, which can be used by any
+//! rama stream service, e.g. an HTTP/3 or custom protocol client.
+//!
+//! The established [`quinn::Connection`] is inserted in the [`Context`]
+//! of the [`EstablishedClientConnection`], such that more streams can be opened on it.
+
+use rama_core::{
+    Context, Service,
+    error::{BoxError, ErrorContext, OpaqueError},
+    telemetry::tracing,
+};
+use rama_dns::{DnsResolver, GlobalDnsResolver};
+use rama_net::{
+    address::{Authority, Host},
+    client::EstablishedClientConnection,
+    stream::{ClientSocketInfo, SocketInfo},
+    transport::TryRefIntoTransportContext,
+};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+
+use crate::QuicStream;
+
+/// A connector which can be used to establish a QUIC connection to a server,
+/// returning the first bidirectional stream opened on it as a [`QuicStream`].
+pub struct QuicConnector<Dns = GlobalDnsResolver> {
+    endpoint: quinn::Endpoint,
+    dns: Dns,
+}
+
+impl<Dns: std::fmt::Debug> std::fmt::Debug for QuicConnector<Dns> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("QuicConnector")
+            .field("endpoint", &self.endpoint)
+            .field("dns", &self.dns)
+            .finish()
+    }
+}
+
+impl<Dns: Clone> Clone for QuicConnector<Dns> {
+    fn clone(&self) -> Self {
+        Self {
+            endpoint: self.endpoint.clone(),
+            dns: self.dns.clone(),
+        }
+    }
+}
+
+impl QuicConnector {
+    /// Create a new [`QuicConnector`], which is used to establish a connection to a server,
+    /// using the given [`quinn::ClientConfig`].
+    ///
+    /// The client endpoint is bound to an unspecified (IPv6) address
+    /// with an OS-assigned port, such that both IPv4 and IPv6 servers can be reached.
+    pub fn new(config: quinn::ClientConfig) -> Result<Self, OpaqueError> {
+        let mut endpoint =
+            quinn::Endpoint::client(SocketAddr::new(IpAddr::V6(Ipv6Addr::UNSPECIFIED), 0))
+                .or_else(|_| {
+                    quinn::Endpoint::client(SocketAddr::new(IpAddr::V4(Ipv4Addr::UNSPECIFIED), 0))
+                })
+                .context("quic connector: bind client endpoint")?;
+        endpoint.set_default_client_config(config);
+        Ok(Self::with_endpoint(endpoint))
+    }
+
+    /// Create a new [`QuicConnector`] using the given (client) [`quinn::Endpoint`],
+    /// which is expected to have a default client config.
+    #[must_use]
+    pub fn with_endpoint(endpoint: quinn::Endpoint) -> Self {
+        Self {
+            endpoint,
+            dns: GlobalDnsResolver::new(),
+        }
+    }
+}
+
+impl<Dns> QuicConnector<Dns> {
+    /// Consume `self` to attach the given `dns` (a [`DnsResolver`]) as a new [`QuicConnector`].
+    pub fn with_dns<OtherDns>(self, dns: OtherDns) -> QuicConnector<OtherDns>
+    where
+        OtherDns: DnsResolver + Clone,
+    {
+        QuicConnector {
+            endpoint: self.endpoint,
+            dns,
+        }
+    }
+
+    /// Returns a reference to the underlying (client) [`quinn::Endpoint`].
+    pub fn endpoint(&self) -> &quinn::Endpoint {
+        &self.endpoint
+    }
+}
+
+impl<Request, Dns> Service<Request> for QuicConnector<Dns>
+where
+    Request: TryRefIntoTransportContext + Send + 'static,
+    Request::Error: Into<BoxError> + Send + Sync + 'static,
+    Dns: DnsResolver + Clone,
+{
+    type Response = EstablishedClientConnection<QuicStream, Request>;
+    type Error = BoxError;
+
+    async fn serve(&self, mut ctx: Context, req: Request) -> Result<Self::Response, Self::Error> {
+        let transport_ctx = ctx
+            .get_or_try_insert_with_ctx(|ctx| req.try_ref_into_transport_ctx(ctx))
+            .map_err(|err| {
+                OpaqueError::from_boxed(err.into())
+                    .context("quic connector: compute transport context to get authority")
+            })?;
+
+        let authority = transport_ctx.authority.clone();
+        let (addr, server_name) = self
+            .resolve(authority)
+            .await
+            .context("quic connector: resolve server address")?;
+
+        let conn = self
+            .endpoint
+            .connect(addr, &server_name)
+            .context("quic connector: connect to server")?
+            .await
+            .context("quic connector: establish connection")?;
+        let (send, recv) = conn
+            .open_bi()
+            .await
+            .context("quic connector: open bidirectional stream")?;
+
+        ctx.insert(ClientSocketInfo(SocketInfo::new(
+            self.endpoint
+                .local_addr()
+                .inspect_err(|err| {
+                    tracing::debug!(
+                        "failed to receive local addr of established connection: {err:?}"
+                    )
+                })
+                .ok(),
+            conn.remote_address(),
+        )));
+        ctx.insert(conn);
+
+        Ok(EstablishedClientConnection {
+            ctx,
+            req,
+            conn: QuicStream::new(send, recv),
+        })
+    }
+}
+
+impl<Dns: DnsResolver> QuicConnector<Dns> {
+    async fn resolve(&self, authority: Authority) -> Result<(SocketAddr, String), OpaqueError> {
+        let (host, port) = authority.into_parts();
+        match host {
+            Host::Address(ip) => Ok((SocketAddr::new(ip, port), ip.to_string())),
+            Host::Name(domain) => {
+                let server_name = domain.as_str().to_owned();
+                let ip = match self.dns.ipv4_lookup(domain.clone()).await {
+                    Ok(ips) if !ips.is_empty() => IpAddr::V4(ips[0]),
+                    _ => self
+                        .dns
+                        .ipv6_lookup(domain)
+                        .await
+                        .map_err(|err| OpaqueError::from_boxed(err.into()))?
+                        .first()
+                        .copied()
+                        .map(IpAddr::V6)
+                        .ok_or_else(|| {
+                            OpaqueError::from_display("no ip address found for domain")
+                        })?,
+                };
+                Ok((SocketAddr::new(ip, port), server_name))
+            }
+        }
+    }
+}
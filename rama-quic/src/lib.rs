@@ -0,0 +1,133 @@
+//! QUIC module for Rama.
+//!
+//! QUIC support is provided by [`quinn`], exposing each bidirectional stream
+//! of a QUIC connection as a [`QuicStream`], which implements the rama
+//! [`Stream`] trait. This allows HTTP/3 and custom QUIC protocols
+//! to reuse the existing rama layers and services:
+//!
+//! - [`server::QuicListener`] serves each stream opened by a peer with a rama [`Service`];
+//! - [`client::QuicConnector`] is a connector service opening a stream to a server.
+//!
+//! # Rama
+//!
+//! Crate used by the end-user `rama` crate and `rama` crate authors alike.
+//!
+//! Learn more about `rama`:
+//!
+//! - Github: <https://github.com/plabayo/rama>
+//! - Book: <https://ramaproxy.org/book/>
+//!
+//! [`Stream`]: rama_net::stream::Stream
+//! [`Service`]: rama_core::Service
+
+#![doc(
+    html_favicon_url = "https://raw.githubusercontent.com/plabayo/rama/main/docs/img/old_logo.png"
+)]
+#![doc(html_logo_url = "https://raw.githubusercontent.com/plabayo/rama/main/docs/img/old_logo.png")]
+#![cfg_attr(docsrs, feature(doc_auto_cfg, doc_cfg))]
+#![cfg_attr(test, allow(clippy::float_cmp))]
+#![cfg_attr(not(test), warn(clippy::print_stdout, clippy::dbg_macro))]
+
+pub mod client;
+pub mod server;
+
+mod stream;
+#[doc(inline)]
+pub use stream::QuicStream;
+
+pub mod dep {
+    //! Dependencies for rama quic modules.
+    //!
+    //! Exported for your convenience.
+
+    pub use quinn;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rama_core::{Context, Service, service::service_fn};
+    use rama_net::{
+        address::Authority,
+        client::EstablishedClientConnection,
+        transport::{TransportContext, TransportProtocol, TryRefIntoTransportContext},
+    };
+    use std::{convert::Infallible, sync::Arc};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    struct Target(Authority);
+
+    impl TryRefIntoTransportContext for Target {
+        type Error = Infallible;
+
+        fn try_ref_into_transport_ctx(
+            &self,
+            _ctx: &Context,
+        ) -> Result<TransportContext, Self::Error> {
+            Ok(TransportContext {
+                protocol: TransportProtocol::Udp,
+                app_protocol: None,
+                http_version: None,
+                authority: self.0.clone(),
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_quic_echo() {
+        let provider = Arc::new(rustls::crypto::aws_lc_rs::default_provider());
+
+        let key_pair = rcgen::KeyPair::generate().unwrap();
+        let cert = rcgen::CertificateParams::new(vec!["127.0.0.1".to_owned()])
+            .unwrap()
+            .self_signed(&key_pair)
+            .unwrap();
+        let cert_der = cert.der().clone();
+        let key_der = rustls::pki_types::PrivatePkcs8KeyDer::from(key_pair.serialize_der());
+
+        let server_crypto = rustls::ServerConfig::builder_with_provider(provider.clone())
+            .with_protocol_versions(&[&rustls::version::TLS13])
+            .unwrap()
+            .with_no_client_auth()
+            .with_single_cert(vec![cert_der.clone()], key_der.into())
+            .unwrap();
+        let server_config = quinn::ServerConfig::with_crypto(Arc::new(
+            quinn::crypto::rustls::QuicServerConfig::try_from(server_crypto).unwrap(),
+        ));
+
+        let listener = server::QuicListener::bind("127.0.0.1:0", server_config).unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(listener.serve(service_fn(async |mut stream: QuicStream| {
+            let mut buf = Vec::new();
+            stream.read_to_end(&mut buf).await?;
+            stream.write_all(&buf).await?;
+            stream.shutdown().await
+        })));
+
+        let mut roots = rustls::RootCertStore::empty();
+        roots.add(cert_der).unwrap();
+        let client_crypto = rustls::ClientConfig::builder_with_provider(provider)
+            .with_protocol_versions(&[&rustls::version::TLS13])
+            .unwrap()
+            .with_root_certificates(roots)
+            .with_no_client_auth();
+        let client_config = quinn::ClientConfig::new(Arc::new(
+            quinn::crypto::rustls::QuicClientConfig::try_from(client_crypto).unwrap(),
+        ));
+        let mut endpoint = quinn::Endpoint::client("127.0.0.1:0".parse().unwrap()).unwrap();
+        endpoint.set_default_client_config(client_config);
+
+        let EstablishedClientConnection { ctx, mut conn, .. } =
+            client::QuicConnector::with_endpoint(endpoint)
+                .serve(Context::default(), Target(addr.into()))
+                .await
+                .unwrap();
+        assert!(ctx.contains::<quinn::Connection>());
+
+        conn.write_all(b"hello quic").await.unwrap();
+        conn.shutdown().await.unwrap();
+        let mut buf = Vec::new();
+        conn.read_to_end(&mut buf).await.unwrap();
+        assert_eq!(buf, b"hello quic");
+    }
+}
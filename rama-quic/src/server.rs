@@ -0,0 +1,239 @@
+//! QUIC server module for Rama.
+//!
+//! The QUIC server is used to create a [`QuicListener`] and accept incoming connections,
+//! serving each bidirectional stream opened by the peer as a [`QuicStream`].
+//!
+//! The established [`quinn::Connection`] is inserted in the [`Context`] of each stream,
+//! such that services can inspect it, open streams of their own or send datagrams.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use rama_core::service::service_fn;
+//! use rama_quic::{QuicStream, dep::quinn, server::QuicListener};
+//! use tokio::io::AsyncWriteExt;
+//!
+//! # async fn run(server_config: quinn::ServerConfig) {
+//! QuicListener::bind("127.0.0.1:4433", server_config)
+//!     .expect("bind QUIC Listener")
+//!     .serve(service_fn(async |mut stream: QuicStream| {
+//!         stream.write_all(b"hello").await?;
+//!         stream.shutdown().await
+//!     }))
+//!     .await;
+//! # }
+//! ```
+
+use rama_core::Context;
+use rama_core::Service;
+use rama_core::error::BoxError;
+use rama_core::graceful::ShutdownGuard;
+use rama_core::rt::Executor;
+use rama_core::telemetry::tracing::{self, Instrument, trace_root_span};
+use rama_net::address::SocketAddress;
+use rama_net::stream::SocketInfo;
+use std::pin::pin;
+use std::sync::Arc;
+use std::{io, net::SocketAddr};
+
+use crate::QuicStream;
+
+#[derive(Debug)]
+/// A QUIC server, listening for incoming connections once served
+/// using one of the `serve` methods such as [`QuicListener::serve`].
+pub struct QuicListener {
+    endpoint: quinn::Endpoint,
+}
+
+impl QuicListener {
+    /// Creates a new QuicListener, which will be bound to the specified (socket) address,
+    /// using the given [`quinn::ServerConfig`] to accept incoming connections.
+    ///
+    /// Binding with a port number of 0 will request that the OS assigns a port
+    /// to this listener. The port allocated can be queried via the `local_addr`
+    /// method.
+    pub fn bind<A: TryInto<SocketAddress, Error: Into<BoxError>>>(
+        addr: A,
+        config: quinn::ServerConfig,
+    ) -> Result<Self, BoxError> {
+        let socket_addr = addr.try_into().map_err(Into::<BoxError>::into)?;
+        let endpoint = quinn::Endpoint::server(config, socket_addr.into())?;
+        Ok(Self { endpoint })
+    }
+
+    /// Returns the local address that this listener is bound to.
+    ///
+    /// This can be useful, for example, when binding to port 0 to figure out
+    /// which port was actually bound.
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.endpoint.local_addr()
+    }
+
+    /// Returns a reference to the underlying [`quinn::Endpoint`].
+    #[must_use]
+    pub fn endpoint(&self) -> &quinn::Endpoint {
+        &self.endpoint
+    }
+
+    /// Accept a single connection from this listener,
+    /// what you can do with whatever you want.
+    ///
+    /// Returns `None` in case the endpoint was closed.
+    pub async fn accept(&self) -> Option<Result<quinn::Connection, quinn::ConnectionError>> {
+        let incoming = self.endpoint.accept().await?;
+        Some(incoming.await)
+    }
+}
+
+impl From<quinn::Endpoint> for QuicListener {
+    fn from(endpoint: quinn::Endpoint) -> Self {
+        Self { endpoint }
+    }
+}
+
+impl QuicListener {
+    /// Serve connections from this listener with the given service.
+    ///
+    /// Each connection is handled in its own task,
+    /// in which every bidirectional stream opened by the peer
+    /// is served by the given service in a task of its own.
+    pub async fn serve<S>(self, service: S)
+    where
+        S: Service<QuicStream>,
+    {
+        let ctx = Context::new(Executor::new());
+        let service = Arc::new(service);
+
+        while let Some(incoming) = self.endpoint.accept().await {
+            let service = service.clone();
+            let ctx = ctx.clone();
+            let local_addr = self.endpoint.local_addr().ok();
+            let trace_local_addr = local_addr
+                .map(Into::into)
+                .unwrap_or_else(|| SocketAddress::default_ipv4(0));
+            let peer_addr = incoming.remote_address();
+
+            let span = trace_root_span!(
+                "quic::serve",
+                otel.kind = "server",
+                network.local.port = %trace_local_addr.port(),
+                network.local.address = %trace_local_addr.ip_addr(),
+                network.peer.port = %peer_addr.port(),
+                network.peer.address = %peer_addr.ip(),
+                network.transport = "udp",
+                network.protocol.name = "quic",
+            );
+
+            tokio::spawn(
+                serve_connection(ctx, incoming, local_addr, service, None).instrument(span),
+            );
+        }
+    }
+
+    /// Serve gracefully connections from this listener with the given service.
+    ///
+    /// This method does the same as [`Self::serve`] but it
+    /// will respect the given [`rama_core::graceful::ShutdownGuard`], and also pass
+    /// it to the service.
+    pub async fn serve_graceful<S>(self, guard: ShutdownGuard, service: S)
+    where
+        S: Service<QuicStream>,
+    {
+        let ctx: Context = Context::new(Executor::graceful(guard.clone()));
+        let service = Arc::new(service);
+        let mut cancelled_fut = pin!(guard.cancelled());
+
+        loop {
+            tokio::select! {
+                _ = cancelled_fut.as_mut() => {
+                    tracing::trace!("signal received: initiate graceful shutdown");
+                    self.endpoint.close(0u32.into(), b"shutdown");
+                    break;
+                }
+                incoming = self.endpoint.accept() => {
+                    let Some(incoming) = incoming else {
+                        break;
+                    };
+
+                    let service = service.clone();
+                    let ctx = ctx.clone();
+                    let local_addr = self.endpoint.local_addr().ok();
+                    let trace_local_addr = local_addr
+                        .map(Into::into)
+                        .unwrap_or_else(|| SocketAddress::default_ipv4(0));
+                    let peer_addr = incoming.remote_address();
+
+                    let span = trace_root_span!(
+                        "quic::serve_graceful",
+                        otel.kind = "server",
+                        network.local.port = %trace_local_addr.port(),
+                        network.local.address = %trace_local_addr.ip_addr(),
+                        network.peer.port = %peer_addr.port(),
+                        network.peer.address = %peer_addr.ip(),
+                        network.transport = "udp",
+                        network.protocol.name = "quic",
+                    );
+
+                    guard.spawn_task(
+                        serve_connection(ctx, incoming, local_addr, service, Some(guard.clone()))
+                            .instrument(span),
+                    );
+                }
+            }
+        }
+    }
+}
+
+async fn serve_connection<S>(
+    ctx: Context,
+    incoming: quinn::Incoming,
+    local_addr: Option<SocketAddr>,
+    service: Arc<S>,
+    guard: Option<ShutdownGuard>,
+) where
+    S: Service<QuicStream>,
+{
+    let conn = match incoming.await {
+        Ok(conn) => conn,
+        Err(err) => {
+            tracing::debug!("QUIC accept error: failed to establish connection: {err:?}");
+            return;
+        }
+    };
+
+    loop {
+        let (send, recv) = match conn.accept_bi().await {
+            Ok(streams) => streams,
+            Err(
+                quinn::ConnectionError::ApplicationClosed(_)
+                | quinn::ConnectionError::LocallyClosed,
+            ) => {
+                tracing::trace!("QUIC connection closed");
+                return;
+            }
+            Err(err) => {
+                tracing::debug!("QUIC accept error: failed to accept stream: {err:?}");
+                return;
+            }
+        };
+
+        let service = service.clone();
+        let mut ctx = ctx.clone();
+        ctx.insert(SocketInfo::new(local_addr, conn.remote_address()));
+        ctx.insert(conn.clone());
+
+        let fut = async move {
+            let _ = service.serve(ctx, QuicStream::new(send, recv)).await;
+        }
+        .in_current_span();
+
+        match &guard {
+            Some(guard) => {
+                guard.spawn_task(fut);
+            }
+            None => {
+                tokio::spawn(fut);
+            }
+        }
+    }
+}
@@ -0,0 +1,105 @@
+use pin_project_lite::pin_project;
+use std::{
+    io,
+    pin::Pin,
+    task::{Context, Poll},
+};
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+
+pin_project! {
+    #[derive(Debug)]
+    /// A bidirectional QUIC stream, combining the send and receive half
+    /// of a single stream within a QUIC connection.
+    ///
+    /// It implements [`AsyncRead`] and [`AsyncWrite`],
+    /// and therefore also the rama [`Stream`] trait,
+    /// such that it can be served by any rama stream service.
+    ///
+    /// [`Stream`]: rama_net::stream::Stream
+    pub struct QuicStream {
+        #[pin]
+        send: quinn::SendStream,
+        #[pin]
+        recv: quinn::RecvStream,
+    }
+}
+
+impl QuicStream {
+    /// Create a new [`QuicStream`] from the send and receive half of a bidirectional stream.
+    #[must_use]
+    pub fn new(send: quinn::SendStream, recv: quinn::RecvStream) -> Self {
+        Self { send, recv }
+    }
+
+    /// Returns the identifier of this stream within its QUIC connection.
+    #[must_use]
+    pub fn id(&self) -> quinn::StreamId {
+        self.send.id()
+    }
+
+    /// Returns a reference to the send half of this stream.
+    #[must_use]
+    pub fn send_stream(&self) -> &quinn::SendStream {
+        &self.send
+    }
+
+    /// Returns a mutable reference to the send half of this stream.
+    pub fn send_stream_mut(&mut self) -> &mut quinn::SendStream {
+        &mut self.send
+    }
+
+    /// Returns a reference to the receive half of this stream.
+    #[must_use]
+    pub fn recv_stream(&self) -> &quinn::RecvStream {
+        &self.recv
+    }
+
+    /// Returns a mutable reference to the receive half of this stream.
+    pub fn recv_stream_mut(&mut self) -> &mut quinn::RecvStream {
+        &mut self.recv
+    }
+
+    /// Consume this stream into its send and receive half.
+    #[must_use]
+    pub fn into_parts(self) -> (quinn::SendStream, quinn::RecvStream) {
+        (self.send, self.recv)
+    }
+}
+
+impl From<(quinn::SendStream, quinn::RecvStream)> for QuicStream {
+    fn from((send, recv): (quinn::SendStream, quinn::RecvStream)) -> Self {
+        Self::new(send, recv)
+    }
+}
+
+impl AsyncRead for QuicStream {
+    #[inline]
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        AsyncRead::poll_read(self.project().recv, cx, buf)
+    }
+}
+
+impl AsyncWrite for QuicStream {
+    #[inline]
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        AsyncWrite::poll_write(self.project().send, cx, buf)
+    }
+
+    #[inline]
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        AsyncWrite::poll_flush(self.project().send, cx)
+    }
+
+    #[inline]
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        AsyncWrite::poll_shutdown(self.project().send, cx)
+    }
+}
@@ -2,7 +2,7 @@ use crate::{Socks5Client, client::proxy_error::Socks5ProxyError};
 use rama_core::telemetry::tracing;
 use rama_core::{
     Context, Layer, Service,
-    error::{BoxError, ErrorExt, OpaqueError},
+    error::{BoxError, ClassifiedError, ErrorExt, OpaqueError},
 };
 use rama_net::{
     address::ProxyAddress,
@@ -376,14 +376,16 @@ where
                 .connect(ctx, req)
                 .await
                 .map_err(|err| match address.as_ref() {
-                    Some(address) => OpaqueError::from_std(Socks5ProxyError::Transport(
-                        OpaqueError::from_boxed(err.into())
-                            .context(format!(
-                                "establish connection to proxy {} (protocol: {:?})",
-                                address.authority, address.protocol,
-                            ))
-                            .into_boxed(),
-                    )),
+                    Some(address) => {
+                        OpaqueError::from_std(ClassifiedError::from(Socks5ProxyError::Transport(
+                            OpaqueError::from_boxed(err.into())
+                                .context(format!(
+                                    "establish connection to proxy {} (protocol: {:?})",
+                                    address.authority, address.protocol,
+                                ))
+                                .into_boxed(),
+                        )))
+                    }
                     None => {
                         OpaqueError::from_boxed(err.into()).context("establish connection target")
                     }
@@ -469,7 +471,11 @@ where
                     "socks5 proxy connector: handshake complete",
                 )
             }
-            Err(err) => return Err(Box::new(Socks5ProxyError::Handshake(err))),
+            Err(err) => {
+                return Err(Box::new(ClassifiedError::from(
+                    Socks5ProxyError::Handshake(err),
+                )));
+            }
         }
 
         Ok(EstablishedClientConnection { ctx, req, conn })
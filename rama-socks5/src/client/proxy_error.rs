@@ -1,5 +1,5 @@
 use super::core::HandshakeError;
-use rama_core::error::BoxError;
+use rama_core::error::{BoxError, ClassifiedError};
 use std::fmt;
 
 #[derive(Debug)]
@@ -53,3 +53,15 @@ impl std::error::Error for Socks5ProxyError {
         }
     }
 }
+
+impl From<Socks5ProxyError> for ClassifiedError {
+    fn from(error: Socks5ProxyError) -> Self {
+        match &error {
+            Socks5ProxyError::Handshake(_) => Self::protocol(error),
+            Socks5ProxyError::Transport(err) => match Self::classify(err.as_ref()) {
+                Some(class) => Self::new(class.kind(), error).with_retryable(class.is_retryable()),
+                None => Self::connect(error),
+            },
+        }
+    }
+}
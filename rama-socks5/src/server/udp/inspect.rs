@@ -6,7 +6,7 @@ use rama_core::bytes::Bytes;
 use rama_core::telemetry::tracing;
 use rama_core::{Context, Service, error::BoxError};
 use rama_net::address::SocketAddress;
-use rama_udp::UdpSocket;
+use rama_udp::{UdpSessionTable, UdpSocket};
 
 #[cfg(feature = "dns")]
 use ::rama_dns::BoxDnsResolver;
@@ -21,6 +21,7 @@ pub(super) trait UdpPacketProxy: Send + Sync + 'static {
         north_read_buf_size: usize,
         south: UdpSocket,
         south_read_buf_size: usize,
+        sessions: UdpSessionTable<()>,
         #[cfg(feature = "dns")] dns_resolver: Option<BoxDnsResolver>,
     ) -> impl Future<Output = Result<Context, Error>> + Send;
 }
@@ -39,6 +40,7 @@ impl UdpPacketProxy for DirectUdpRelay {
         north_read_buf_size: usize,
         south: UdpSocket,
         south_read_buf_size: usize,
+        sessions: UdpSessionTable<()>,
         #[cfg(feature = "dns")] dns_resolver: Option<BoxDnsResolver>,
     ) -> Result<Context, Error> {
         let relay = UdpSocketRelay::new(
@@ -47,6 +49,7 @@ impl UdpPacketProxy for DirectUdpRelay {
             north_read_buf_size,
             south,
             south_read_buf_size,
+            sessions,
         );
 
         #[cfg(feature = "dns")]
@@ -125,6 +128,7 @@ where
         north_read_buf_size: usize,
         south: UdpSocket,
         south_read_buf_size: usize,
+        sessions: UdpSessionTable<()>,
         #[cfg(feature = "dns")] dns_resolver: Option<BoxDnsResolver>,
     ) -> Result<Context, Error> {
         let relay = UdpSocketRelay::new(
@@ -133,6 +137,7 @@ where
             north_read_buf_size,
             south,
             south_read_buf_size,
+            sessions,
         );
 
         #[cfg(feature = "dns")]
@@ -295,6 +300,7 @@ where
         north_read_buf_size: usize,
         south: UdpSocket,
         south_read_buf_size: usize,
+        sessions: UdpSessionTable<()>,
         #[cfg(feature = "dns")] dns_resolver: Option<BoxDnsResolver>,
     ) -> Result<Context, Error> {
         let relay = UdpSocketRelay::new(
@@ -303,6 +309,7 @@ where
             north_read_buf_size,
             south,
             south_read_buf_size,
+            sessions,
         );
 
         #[cfg(feature = "dns")]
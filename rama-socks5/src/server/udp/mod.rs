@@ -9,7 +9,7 @@ use rama_net::{
     socket::{Interface, SocketService},
     stream::Stream,
 };
-use rama_udp::UdpSocket;
+use rama_udp::{UdpSessionTable, UdpSocket};
 use rama_utils::macros::generate_field_setters;
 
 #[cfg(feature = "dns")]
@@ -128,6 +128,9 @@ pub struct UdpRelay<B, I> {
     south_buffer_size: usize,
 
     relay_timeout: Option<Duration>,
+
+    session_idle_timeout: Duration,
+    max_sessions: usize,
 }
 
 impl<B> UdpRelay<B, DirectUdpRelay> {
@@ -143,6 +146,8 @@ impl<B> UdpRelay<B, DirectUdpRelay> {
             north_buffer_size: 2048,
             south_buffer_size: 2048,
             relay_timeout: None,
+            session_idle_timeout: UdpSessionTable::<()>::DEFAULT_IDLE_TIMEOUT,
+            max_sessions: UdpSessionTable::<()>::DEFAULT_MAX_SESSIONS,
         }
     }
 
@@ -159,6 +164,8 @@ impl<B> UdpRelay<B, DirectUdpRelay> {
             north_buffer_size: self.north_buffer_size,
             south_buffer_size: self.south_buffer_size,
             relay_timeout: self.relay_timeout,
+            session_idle_timeout: self.session_idle_timeout,
+            max_sessions: self.max_sessions,
         }
     }
 
@@ -175,6 +182,8 @@ impl<B> UdpRelay<B, DirectUdpRelay> {
             north_buffer_size: self.north_buffer_size,
             south_buffer_size: self.south_buffer_size,
             relay_timeout: self.relay_timeout,
+            session_idle_timeout: self.session_idle_timeout,
+            max_sessions: self.max_sessions,
         }
     }
 }
@@ -194,6 +203,8 @@ impl<B, I> UdpRelay<B, I> {
             north_buffer_size: self.north_buffer_size,
             south_buffer_size: self.south_buffer_size,
             relay_timeout: self.relay_timeout,
+            session_idle_timeout: self.session_idle_timeout,
+            max_sessions: self.max_sessions,
         }
     }
 
@@ -342,6 +353,44 @@ impl<B, I> UdpRelay<B, I> {
     }
 
     generate_field_setters!(relay_timeout, Duration);
+
+    /// Set the duration after which the relay forgets an idle udp session,
+    /// after which replies of that server are no longer relayed.
+    ///
+    /// Defaults to [`UdpSessionTable::DEFAULT_IDLE_TIMEOUT`].
+    pub fn set_session_idle_timeout(&mut self, timeout: Duration) -> &mut Self {
+        self.session_idle_timeout = timeout;
+        self
+    }
+
+    /// Set the duration after which the relay forgets an idle udp session,
+    /// after which replies of that server are no longer relayed.
+    ///
+    /// Defaults to [`UdpSessionTable::DEFAULT_IDLE_TIMEOUT`].
+    #[must_use]
+    pub fn with_session_idle_timeout(mut self, timeout: Duration) -> Self {
+        self.session_idle_timeout = timeout;
+        self
+    }
+
+    /// Set the maximum amount of udp sessions (one per target server)
+    /// a single udp association can have at once.
+    ///
+    /// Defaults to [`UdpSessionTable::DEFAULT_MAX_SESSIONS`].
+    pub fn set_max_sessions(&mut self, max: usize) -> &mut Self {
+        self.max_sessions = max;
+        self
+    }
+
+    /// Set the maximum amount of udp sessions (one per target server)
+    /// a single udp association can have at once.
+    ///
+    /// Defaults to [`UdpSessionTable::DEFAULT_MAX_SESSIONS`].
+    #[must_use]
+    pub fn with_max_sessions(mut self, max: usize) -> Self {
+        self.max_sessions = max;
+        self
+    }
 }
 
 #[cfg(feature = "dns")]
@@ -402,6 +451,8 @@ impl<B: fmt::Debug, I: fmt::Debug> fmt::Debug for UdpRelay<B, I> {
             .field("bind_north_interface", &self.bind_north_interface)
             .field("bind_south_interface", &self.bind_south_interface)
             .field("relay_timeout", &self.relay_timeout)
+            .field("session_idle_timeout", &self.session_idle_timeout)
+            .field("max_sessions", &self.max_sessions)
             .finish()
     }
 }
@@ -418,6 +469,8 @@ impl<B: Clone, I: Clone> Clone for UdpRelay<B, I> {
             north_buffer_size: self.north_buffer_size,
             south_buffer_size: self.south_buffer_size,
             relay_timeout: self.relay_timeout,
+            session_idle_timeout: self.session_idle_timeout,
+            max_sessions: self.max_sessions,
         }
     }
 }
@@ -562,6 +615,9 @@ where
             self.north_buffer_size,
             socket_south,
             self.south_buffer_size,
+            UdpSessionTable::new()
+                .with_idle_timeout(self.session_idle_timeout)
+                .with_max_sessions(self.max_sessions),
             #[cfg(feature = "dns")]
             self.dns_resolver.clone(),
         );
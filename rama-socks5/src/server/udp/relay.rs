@@ -4,7 +4,7 @@ use rama_core::bytes::{Bytes, BytesMut};
 use rama_core::error::{BoxError, ErrorExt, OpaqueError};
 use rama_core::telemetry::tracing;
 use rama_net::address::{Authority, Host, SocketAddress};
-use rama_udp::UdpSocket;
+use rama_udp::{UdpFlow, UdpSessionStatus, UdpSessionTable, UdpSocket};
 
 use crate::proto::udp::UdpHeader;
 
@@ -32,6 +32,8 @@ pub(super) struct UdpSocketRelay {
 
     north_write_buf: BytesMut,
 
+    sessions: UdpSessionTable<()>,
+
     #[cfg(feature = "dns")]
     dns_resolve_mode: DnsResolveIpMode,
     #[cfg(feature = "dns")]
@@ -51,6 +53,7 @@ impl UdpSocketRelay {
         north_read_buf_size: usize,
         south: UdpSocket,
         south_read_buf_size: usize,
+        sessions: UdpSessionTable<()>,
     ) -> Self {
         Self {
             client_address,
@@ -72,6 +75,8 @@ impl UdpSocketRelay {
                 b
             },
 
+            sessions,
+
             #[cfg(feature = "dns")]
             dns_resolve_mode: DnsResolveIpMode::default(),
             #[cfg(feature = "dns")]
@@ -102,7 +107,7 @@ impl UdpSocketRelay {
                             "north socket: received packet (len = {len}; src = {src})",
                         );
 
-                        if !self.client_address.eq(&src) {
+                        if !self.is_client_ip(src) {
                             tracing::debug!(
                                 network.peer.address = %self.client_address.ip_addr(),
                                 network.peer.port = %self.client_address.port(),
@@ -147,6 +152,10 @@ impl UdpSocketRelay {
                             },
                         };
 
+                        if !self.track_north_session(src, server_address) {
+                            return Ok(None);
+                        }
+
                         // remove header from payload
                         let offset = len - buf.len();
                         self.north_read_buf.copy_within(offset.., 0);
@@ -172,6 +181,14 @@ impl UdpSocketRelay {
                         tracing::trace!(
                             "south socket: received packet (len = {len}; src = {src})",
                         );
+                        if self.sessions.track_reply(src).is_none() {
+                            tracing::debug!(
+                                network.peer.address = %self.client_address.ip_addr(),
+                                network.peer.port = %self.client_address.port(),
+                                "south socket: drop unsolicited packet (len = {len}; src = {src})",
+                            );
+                            return Ok(None);
+                        }
                         self.south_read_buf.truncate(len);
                         Ok(Some(UdpRelayState::ReadSouth(src)))
                     }
@@ -362,6 +379,65 @@ impl UdpSocketRelay {
     }
 }
 
+impl UdpSocketRelay {
+    /// Returns `true` in case the packet is received from the ip of the client,
+    /// which is any ip in case the client did not specify it.
+    fn is_client_ip(&self, src: SocketAddress) -> bool {
+        let client_ip = self.client_address.ip_addr();
+        client_ip.is_unspecified() || client_ip == src.ip_addr()
+    }
+
+    /// Track the session of a packet from the client to the given server,
+    /// returning `false` in case the packet is to be dropped.
+    ///
+    /// The client address is learned from its first packet, in case it was not
+    /// (fully) specified by the client, and updated when the client got rebound to a new port.
+    fn track_north_session(&mut self, src: SocketAddress, server_address: SocketAddress) -> bool {
+        let flow = UdpFlow::new(src, server_address);
+        let learn =
+            self.client_address.ip_addr().is_unspecified() || self.client_address.port() == 0;
+        if !learn && self.client_address != src && !self.sessions.is_rebinding(&flow) {
+            tracing::debug!(
+                network.peer.address = %self.client_address.ip_addr(),
+                network.peer.port = %self.client_address.port(),
+                "north socket: drop packet from non-client port (src = {src})",
+            );
+            return false;
+        }
+
+        match self.sessions.track(flow, || ()) {
+            Ok((UdpSessionStatus::Rebound { previous_source }, _)) => {
+                tracing::debug!(
+                    network.peer.address = %src.ip_addr(),
+                    network.peer.port = %src.port(),
+                    "north socket: client rebound from port {}",
+                    previous_source.port(),
+                );
+                self.client_address = src;
+            }
+            Ok(_) => {
+                if learn {
+                    tracing::trace!(
+                        network.peer.address = %src.ip_addr(),
+                        network.peer.port = %src.port(),
+                        "north socket: learned client address from first packet",
+                    );
+                    self.client_address = src;
+                }
+            }
+            Err(err) => {
+                tracing::debug!(
+                    network.peer.address = %self.client_address.ip_addr(),
+                    network.peer.port = %self.client_address.port(),
+                    "north socket: drop packet to {server_address}: {err}",
+                );
+                return false;
+            }
+        }
+        true
+    }
+}
+
 fn is_fatal_io_error(err: &std::io::Error) -> bool {
     matches!(
         err.kind(),
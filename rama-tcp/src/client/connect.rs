@@ -2,19 +2,23 @@ use rama_core::telemetry::tracing::{self, Instrument, trace_span};
 use rama_core::{
     Context,
     combinators::Either,
-    error::{BoxError, ErrorContext, OpaqueError},
+    context::{Deadline, DeadlineExceeded},
+    error::{BoxError, ClassifiedError, ErrorContext, ErrorExt, OpaqueError},
+};
+use rama_dns::{
+    DnsDiagnostics, DnsOverwrite, DnsResolver, DnsResolverOverwrite, GlobalDnsResolver,
 };
-use rama_dns::{DnsOverwrite, DnsResolver, GlobalDnsResolver};
 use rama_net::{
     address::{Authority, Domain, Host, SocketAddress},
     mode::{ConnectIpMode, DnsResolveIpMode},
     socket::SocketOptions,
 };
 use std::{
+    io,
     net::{IpAddr, SocketAddr},
     ops::Deref,
     sync::{
-        Arc,
+        Arc, Mutex,
         atomic::{AtomicBool, Ordering},
     },
     time::Duration,
@@ -24,6 +28,7 @@ use tokio::sync::{
     mpsc::{Sender, channel},
 };
 
+use super::{TcpConnectAttemptError, TcpConnectError};
 use crate::TcpStream;
 
 /// Trait used internally by [`tcp_connect`] and the `TcpConnector`
@@ -113,6 +118,61 @@ impl TcpStreamConnector for rama_net::socket::DeviceName {
     }
 }
 
+#[cfg(target_os = "linux")]
+#[derive(Debug, Clone, Default)]
+/// A [`TcpStreamConnector`] which establishes Multipath TCP (MPTCP) connections,
+/// e.g. for resilient proxy links across multiple uplinks.
+///
+/// It falls back to plain TCP in case MPTCP is not supported by the kernel,
+/// while a MPTCP connection itself falls back to plain TCP in case the peer
+/// does not support it. Whether or not a MPTCP socket is used is reported by
+/// the `ClientSocketInfo` inserted by the `TcpConnector`.
+pub struct MultipathTcpStreamConnector {
+    options: Option<Arc<SocketOptions>>,
+}
+
+#[cfg(target_os = "linux")]
+impl MultipathTcpStreamConnector {
+    /// Create a new [`MultipathTcpStreamConnector`].
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Set the [`SocketOptions`] used to create the sockets,
+        /// of which the protocol is replaced by MPTCP.
+        ///
+        /// Its domain has to match the one of the address connected to.
+        pub fn socket_options(mut self, options: Option<Arc<SocketOptions>>) -> Self {
+            self.options = options;
+            self
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+impl TcpStreamConnector for MultipathTcpStreamConnector {
+    type Error = OpaqueError;
+
+    async fn connect(&self, addr: SocketAddr) -> Result<TcpStream, Self::Error> {
+        let opts = match self.options.as_deref() {
+            Some(opts) => opts.clone(),
+            None if addr.is_ipv4() => SocketOptions::default_tcp(),
+            None => SocketOptions::default_tcp_v6(),
+        };
+        let socket = crate::mptcp::try_build_multipath_socket(&opts)
+            .context("build (multipath) TCP socket")?;
+        socket
+            .set_nonblocking(true)
+            .context("set socket non-blocking")?;
+        tokio::net::TcpSocket::from_std_stream(std::net::TcpStream::from(socket))
+            .connect(addr)
+            .await
+            .context("connect (multipath) TCP socket to the provided socket addr")
+    }
+}
+
 fn tcp_connect_with_socket_opts(
     opts: &SocketOptions,
     addr: SocketAddr,
@@ -172,6 +232,110 @@ macro_rules! impl_stream_connector_either {
 
 ::rama_core::combinators::impl_either!(impl_stream_connector_either);
 
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+/// Options used by [`tcp_connect`] for each connection attempt
+/// to one of the (resolved) addresses of the target.
+///
+/// Insert it in the [`Context`] to overwrite the (default) options used
+/// by [`tcp_connect`] and the `TcpConnector`.
+pub struct TcpConnectOptions {
+    attempt_timeout: Option<Duration>,
+    bind_retries: usize,
+}
+
+impl TcpConnectOptions {
+    /// Create new [`TcpConnectOptions`], without a per-attempt timeout and bind retries.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            attempt_timeout: None,
+            bind_retries: 0,
+        }
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Set the timeout of a single connection attempt,
+        /// after which the attempt is considered failed.
+        ///
+        /// The remaining addresses are still attempted in case a single attempt times out.
+        /// Use a [`Deadline`] in the [`Context`] to limit the time spent on all attempts together.
+        pub fn attempt_timeout(mut self, timeout: Option<Duration>) -> Self {
+            self.attempt_timeout = timeout;
+            self
+        }
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Set the amount of times an attempt to an address is retried in case
+        /// it failed to bind its local address, e.g. when running out of ephemeral ports.
+        pub fn bind_retries(mut self, retries: usize) -> Self {
+            self.bind_retries = retries;
+            self
+        }
+    }
+
+    /// Returns the timeout of a single connection attempt, if any.
+    #[must_use]
+    pub fn attempt_timeout(&self) -> Option<Duration> {
+        self.attempt_timeout
+    }
+
+    /// Returns the amount of times an attempt is retried in case it failed to bind.
+    #[must_use]
+    pub fn bind_retries(&self) -> usize {
+        self.bind_retries
+    }
+}
+
+const BIND_RETRY_DELAY: Duration = Duration::from_millis(10);
+
+async fn tcp_connect_attempt<Connector>(
+    connector: &Connector,
+    addr: SocketAddr,
+    options: &TcpConnectOptions,
+) -> Result<TcpStream, OpaqueError>
+where
+    Connector: TcpStreamConnector<Error: Into<BoxError> + Send + 'static>,
+{
+    let mut retries = 0;
+    loop {
+        let result = match options.attempt_timeout {
+            Some(timeout) => match tokio::time::timeout(timeout, connector.connect(addr)).await {
+                Ok(result) => result.map_err(|err| OpaqueError::from_boxed(err.into())),
+                Err(_) => Err(OpaqueError::from_display(format!(
+                    "connect attempt timed out after {timeout:?}"
+                ))),
+            },
+            None => connector
+                .connect(addr)
+                .await
+                .map_err(|err| OpaqueError::from_boxed(err.into())),
+        };
+        match result {
+            Err(err) if retries < options.bind_retries && is_bind_error(&err) => {
+                retries += 1;
+                tracing::trace!("retry tcp connect attempt to {addr} (#{retries}): {err:?}");
+                tokio::time::sleep(BIND_RETRY_DELAY * retries as u32).await;
+            }
+            result => return result,
+        }
+    }
+}
+
+fn is_bind_error(err: &OpaqueError) -> bool {
+    let mut source: Option<&(dyn std::error::Error + 'static)> = Some(err);
+    while let Some(err) = source {
+        if let Some(err) = err.downcast_ref::<io::Error>() {
+            return matches!(
+                err.kind(),
+                io::ErrorKind::AddrInUse | io::ErrorKind::AddrNotAvailable
+            );
+        }
+        source = err.source();
+    }
+    false
+}
+
 #[inline]
 /// Establish a [`TcpStream`] connection for the given [`Authority`],
 /// using the default settings and no custom state.
@@ -188,12 +352,69 @@ where
 }
 
 /// Establish a [`TcpStream`] connection for the given [`Authority`].
+///
+/// In case the [`Context`] contains a [`Deadline`], the attempt
+/// is aborted with a [`DeadlineExceeded`] error once it expires.
+///
+/// The given [`DnsResolver`] is replaced by the one of a [`DnsResolverOverwrite`]
+/// found in the [`Context`], and its lookups are recorded in the [`DnsDiagnostics`]
+/// found in the [`Context`], if any.
+///
+/// All resolved addresses are attempted until a connection is established,
+/// using the [`TcpConnectOptions`] found in the [`Context`] (or the default ones).
+/// In case all attempts fail, the returned error contains a [`TcpConnectError`]
+/// reporting the error of each attempted address, classified as
+/// [`ErrorKind::Connect`] so retry policies can recognise it.
+///
+/// [`ErrorKind::Connect`]: rama_core::error::ErrorKind::Connect
 pub async fn tcp_connect<Dns, Connector>(
     ctx: &Context,
     authority: Authority,
     dns: Dns,
     connector: Connector,
 ) -> Result<(TcpStream, SocketAddr), OpaqueError>
+where
+    Dns: DnsResolver + Clone,
+    Connector: TcpStreamConnector<Error: Into<BoxError> + Send + 'static> + Clone,
+{
+    let options = ctx.get::<TcpConnectOptions>().cloned().unwrap_or_default();
+    tcp_connect_with_options(ctx, authority, dns, connector, options).await
+}
+
+/// Establish a [`TcpStream`] connection for the given [`Authority`],
+/// using the given [`TcpConnectOptions`].
+///
+/// See [`tcp_connect`] for more information.
+pub async fn tcp_connect_with_options<Dns, Connector>(
+    ctx: &Context,
+    authority: Authority,
+    dns: Dns,
+    connector: Connector,
+    options: TcpConnectOptions,
+) -> Result<(TcpStream, SocketAddr), OpaqueError>
+where
+    Dns: DnsResolver + Clone,
+    Connector: TcpStreamConnector<Error: Into<BoxError> + Send + 'static> + Clone,
+{
+    let connect = tcp_connect_authority(ctx, authority, dns, connector, options);
+    match Deadline::from_ctx(ctx) {
+        Some(deadline) => tokio::select! {
+            result = connect => result,
+            _ = deadline.expired() => {
+                Err(DeadlineExceeded::new()).context("establish tcp client connection")
+            }
+        },
+        None => connect.await,
+    }
+}
+
+async fn tcp_connect_authority<Dns, Connector>(
+    ctx: &Context,
+    authority: Authority,
+    dns: Dns,
+    connector: Connector,
+    options: TcpConnectOptions,
+) -> Result<(TcpStream, SocketAddr), OpaqueError>
 where
     Dns: DnsResolver + Clone,
     Connector: TcpStreamConnector<Error: Into<BoxError> + Send + 'static> + Clone,
@@ -218,12 +439,14 @@ where
 
             // if the authority is already defined as an IP address, we can directly connect to it
             let addr = (ip, port).into();
-            let stream = connector
-                .connect(addr)
-                .await
-                .map_err(|err| OpaqueError::from_boxed(err.into()))
-                .context("establish tcp client connection")?;
-            return Ok((stream, addr));
+            return match tcp_connect_attempt(&connector, addr, &options).await {
+                Ok(stream) => Ok((stream, addr)),
+                Err(error) => {
+                    let mut err = TcpConnectError::new((ip, port).into());
+                    err.push_attempt(TcpConnectAttemptError::new(addr, error));
+                    Err(OpaqueError::from_std(ClassifiedError::connect(err)))
+                }
+            };
         }
     };
 
@@ -236,6 +459,7 @@ where
             dns_overwrite.deref().clone(), // Convert DnsOverwrite to a DnsResolver
             connector.clone(),
             ip_mode,
+            options.clone(),
         )
         .await
     {
@@ -245,9 +469,22 @@ where
     //... otherwise we'll try to establish a connection,
     // with dual-stack parallel connections...
 
-    tcp_connect_inner(ctx, domain, port, dns_mode, dns, connector, ip_mode).await
+    let dns = match ctx.get::<DnsResolverOverwrite>() {
+        Some(dns_overwrite) => Either::B(dns_overwrite.resolver().clone()),
+        None => Either::A(dns),
+    };
+    let dns = match ctx.get::<DnsDiagnostics>() {
+        Some(diagnostics) => Either::B(diagnostics.resolver(dns)),
+        None => Either::A(dns),
+    };
+
+    tcp_connect_inner(
+        ctx, domain, port, dns_mode, dns, connector, ip_mode, options,
+    )
+    .await
 }
 
+#[allow(clippy::too_many_arguments)]
 async fn tcp_connect_inner<Dns, Connector>(
     ctx: &Context,
     domain: Domain,
@@ -256,6 +493,7 @@ async fn tcp_connect_inner<Dns, Connector>(
     dns: Dns,
     connector: Connector,
     connect_mode: ConnectIpMode,
+    options: TcpConnectOptions,
 ) -> Result<(TcpStream, SocketAddr), OpaqueError>
 where
     Dns: DnsResolver + Clone,
@@ -264,9 +502,14 @@ where
     let (tx, mut rx) = channel(1);
     let connected = Arc::new(AtomicBool::new(false));
     let sem = Arc::new(Semaphore::new(3));
+    let options = Arc::new(options);
+    let errors = Arc::new(Mutex::new(TcpConnectError::new(
+        (domain.clone(), port).into(),
+    )));
 
     if dns_mode.ipv4_supported() {
-        ctx.spawn(
+        ctx.spawn_labeled(
+            "tcp connect",
             tcp_connect_inner_branch(
                 dns_mode,
                 dns.clone(),
@@ -278,6 +521,8 @@ where
                 tx.clone(),
                 connected.clone(),
                 sem.clone(),
+                options.clone(),
+                errors.clone(),
             )
             .instrument(tracing::trace_span!(
                 "tcp::connect::dns_v4",
@@ -288,7 +533,8 @@ where
     }
 
     if dns_mode.ipv6_supported() {
-        ctx.spawn(
+        ctx.spawn_labeled(
+            "tcp connect",
             tcp_connect_inner_branch(
                 dns_mode,
                 dns.clone(),
@@ -300,6 +546,8 @@ where
                 tx.clone(),
                 connected.clone(),
                 sem.clone(),
+                options.clone(),
+                errors.clone(),
             )
             .instrument(tracing::trace_span!(
                 "tcp::connect::dns_v6",
@@ -315,9 +563,12 @@ where
         return Ok((stream, addr));
     }
 
-    Err(OpaqueError::from_display(format!(
-        "failed to connect to any resolved IP address for {domain} (port {port})"
-    )))
+    // all senders are dropped, so all attempts finished
+    let err = std::mem::replace(
+        &mut *errors.lock().unwrap_or_else(|err| err.into_inner()),
+        TcpConnectError::new((domain, port).into()),
+    );
+    Err(OpaqueError::from_std(ClassifiedError::connect(err)))
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
@@ -338,6 +589,8 @@ async fn tcp_connect_inner_branch<Dns, Connector>(
     tx: Sender<(TcpStream, SocketAddr)>,
     connected: Arc<AtomicBool>,
     sem: Arc<Semaphore>,
+    options: Arc<TcpConnectOptions>,
+    errors: Arc<Mutex<TcpConnectError>>,
 ) where
     Dns: DnsResolver + Clone,
     Connector: TcpStreamConnector<Error: Into<BoxError> + Send + 'static> + Clone,
@@ -350,6 +603,7 @@ async fn tcp_connect_inner_branch<Dns, Connector>(
                 tracing::trace!(
                     "[{ip_kind:?}] failed to resolve domain to IPv4 addresses: {err:?}"
                 );
+                push_dns_error(&errors, err.context("resolve domain to IPv4 addresses"));
                 return;
             }
         },
@@ -360,6 +614,7 @@ async fn tcp_connect_inner_branch<Dns, Connector>(
                 tracing::trace!(
                     "[{ip_kind:?}] failed to resolve domain to IPv6 addresses: {err:?}"
                 );
+                push_dns_error(&errors, err.context("resolve domain to IPv6 addresses"));
                 return;
             }
         },
@@ -390,6 +645,8 @@ async fn tcp_connect_inner_branch<Dns, Connector>(
 
         let tx = tx.clone();
         let connected = connected.clone();
+        let options = options.clone();
+        let errors = errors.clone();
 
         // back off retries exponentially
         if index > 0 {
@@ -419,7 +676,7 @@ async fn tcp_connect_inner_branch<Dns, Connector>(
 
             tracing::trace!("[{ip_kind:?}] #{index}: tcp connect attempt to {addr}");
 
-            match connector.connect(addr).await {
+            match tcp_connect_attempt(&connector, addr, &options).await {
                 Ok(stream) => {
                     tracing::trace!("[{ip_kind:?}] #{index}: tcp connection stablished to {addr}");
                     if let Err(err) = tx.send((stream, addr)).await {
@@ -427,8 +684,11 @@ async fn tcp_connect_inner_branch<Dns, Connector>(
                     }
                 }
                 Err(err) => {
-                    let err = OpaqueError::from_boxed(err.into());
                     tracing::trace!("[{ip_kind:?}] #{index}: tcp connector failed to connect: {err:?}");
+                    errors
+                        .lock()
+                        .unwrap_or_else(|err| err.into_inner())
+                        .push_attempt(TcpConnectAttemptError::new(addr, err));
                 }
             };
         }.instrument(trace_span!(
@@ -440,3 +700,135 @@ async fn tcp_connect_inner_branch<Dns, Connector>(
         )));
     }
 }
+
+fn push_dns_error(errors: &Mutex<TcpConnectError>, err: OpaqueError) {
+    errors
+        .lock()
+        .unwrap_or_else(|err| err.into_inner())
+        .push_dns_error(err);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rama_core::error::ErrorKind;
+    use rama_dns::InMemoryDns;
+    use std::{
+        error::Error as _,
+        net::Ipv4Addr,
+        sync::atomic::{AtomicUsize, Ordering},
+    };
+
+    fn dns_for(domain: &Domain, ips: &[Ipv4Addr]) -> InMemoryDns {
+        let mut dns = InMemoryDns::new();
+        dns.insert(domain, ips.iter().copied().map(IpAddr::V4).collect());
+        dns
+    }
+
+    #[tokio::test]
+    async fn test_tcp_connect_reports_all_attempts() {
+        let domain: Domain = "example.com".parse().unwrap();
+        let ips = [
+            Ipv4Addr::new(127, 0, 0, 1),
+            Ipv4Addr::new(127, 0, 0, 2),
+            Ipv4Addr::new(127, 0, 0, 3),
+        ];
+
+        let attempts = Arc::new(AtomicUsize::new(0));
+        let connector = {
+            let attempts = attempts.clone();
+            move |_addr: SocketAddr| {
+                let attempts = attempts.clone();
+                async move {
+                    attempts.fetch_add(1, Ordering::SeqCst);
+                    Err::<TcpStream, _>(io::Error::from(io::ErrorKind::ConnectionRefused))
+                }
+            }
+        };
+
+        let err = tcp_connect(
+            &Context::default(),
+            (domain.clone(), 80).into(),
+            dns_for(&domain, &ips),
+            connector,
+        )
+        .await
+        .unwrap_err();
+
+        assert_eq!(attempts.load(Ordering::SeqCst), 3);
+        let err = connect_error(&err);
+        let mut addrs: Vec<_> = err.attempts().iter().map(|a| a.addr()).collect();
+        addrs.sort();
+        assert_eq!(
+            addrs,
+            ips.iter()
+                .map(|ip| SocketAddr::new((*ip).into(), 80))
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_tcp_connect_attempt_timeout() {
+        let domain: Domain = "example.com".parse().unwrap();
+        let connector = |_addr: SocketAddr| async {
+            // black hole: the attempt never completes
+            std::future::pending::<Result<TcpStream, io::Error>>().await
+        };
+
+        let mut ctx = Context::default();
+        ctx.insert(TcpConnectOptions::new().with_attempt_timeout(Duration::from_millis(50)));
+
+        let err = tcp_connect(
+            &ctx,
+            (domain.clone(), 80).into(),
+            dns_for(&domain, &[Ipv4Addr::new(127, 0, 0, 1)]),
+            connector,
+        )
+        .await
+        .unwrap_err();
+
+        let err = connect_error(&err);
+        assert_eq!(err.attempts().len(), 1);
+        assert!(err.attempts()[0].error().to_string().contains("timed out"));
+    }
+
+    #[tokio::test]
+    async fn test_tcp_connect_bind_retries() {
+        let attempts = Arc::new(AtomicUsize::new(0));
+        let connector = {
+            let attempts = attempts.clone();
+            move |_addr: SocketAddr| {
+                let attempts = attempts.clone();
+                async move {
+                    attempts.fetch_add(1, Ordering::SeqCst);
+                    Err::<TcpStream, _>(io::Error::from(io::ErrorKind::AddrInUse))
+                }
+            }
+        };
+
+        let mut ctx = Context::default();
+        ctx.insert(TcpConnectOptions::new().with_bind_retries(2));
+
+        let err = tcp_connect(
+            &ctx,
+            (IpAddr::V4(Ipv4Addr::LOCALHOST), 80).into(),
+            InMemoryDns::new(),
+            connector,
+        )
+        .await
+        .unwrap_err();
+
+        assert_eq!(attempts.load(Ordering::SeqCst), 3);
+        let err = connect_error(&err);
+        assert_eq!(err.attempts().len(), 1);
+    }
+
+    fn connect_error(err: &OpaqueError) -> &TcpConnectError {
+        let err = ClassifiedError::find(err).unwrap();
+        assert_eq!(err.kind(), ErrorKind::Connect);
+        err.source()
+            .unwrap()
+            .downcast_ref::<TcpConnectError>()
+            .unwrap()
+    }
+}
@@ -0,0 +1,258 @@
+use rama_core::error::{ErrorContext, OpaqueError};
+use rama_net::address::SocketAddress;
+use std::{
+    fmt,
+    hash::{BuildHasher, Hash},
+    net::{IpAddr, SocketAddr},
+    sync::{
+        Arc,
+        atomic::{AtomicUsize, Ordering},
+    },
+};
+
+use super::TcpStreamConnector;
+use crate::TcpStream;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+/// The strategy used by an [`EgressAddressRotation`] to select
+/// the local address to bind an outbound connection to.
+pub enum EgressAddressStrategy {
+    #[default]
+    /// Select the configured addresses one after the other.
+    RoundRobin,
+    /// Select the configured addresses one after the other,
+    /// each as many times in a row as its weight.
+    Weighted,
+    /// Select the same address for all connections of the same session,
+    /// identified by the [`EgressSessionKey`] passed to
+    /// [`EgressAddressRotation::for_session`].
+    ///
+    /// Connections without a session fall back to [`Self::RoundRobin`].
+    StickyPerSession,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+/// The key identifying a session, used by [`EgressAddressStrategy::StickyPerSession`]
+/// to select the same local address for all connections of that session.
+///
+/// Insert it in the [`Context`] to make the `TcpConnector` use it,
+/// or otherwise the [`UserId`] found in the [`Context`] is used as the key.
+///
+/// [`Context`]: rama_core::Context
+/// [`UserId`]: rama_net::user::UserId
+pub struct EgressSessionKey(u64);
+
+impl EgressSessionKey {
+    /// Create a new [`EgressSessionKey`] from a hash of the given value.
+    pub fn new(value: impl Hash) -> Self {
+        // hasher with fixed keys, such that a session keeps its address across restarts
+        Self(std::hash::BuildHasherDefault::<std::hash::DefaultHasher>::default().hash_one(value))
+    }
+}
+
+impl From<u64> for EgressSessionKey {
+    fn from(value: u64) -> Self {
+        Self(value)
+    }
+}
+
+#[derive(Clone)]
+/// A [`TcpStreamConnector`] which rotates the local (source) address of
+/// outbound connections over a configured set of addresses,
+/// such that a host with multiple IP addresses spreads its egress traffic over all of them.
+///
+/// For each connection only the addresses of the same family (IPv4 or IPv6)
+/// as the target are considered. The connection fails in case no such address is configured.
+///
+/// It can also be used as the connector factory of the `TcpConnector`,
+/// in which case the [`EgressSessionKey`] of each connection is taken from the [`Context`].
+///
+/// All clones share the same rotation.
+///
+/// [`Context`]: rama_core::Context
+pub struct EgressAddressRotation {
+    inner: Arc<RotationInner>,
+    session: Option<EgressSessionKey>,
+}
+
+struct RotationInner {
+    strategy: EgressAddressStrategy,
+    addresses: Vec<(IpAddr, usize)>,
+    counter: AtomicUsize,
+}
+
+impl EgressAddressRotation {
+    /// Create a new [`EgressAddressRotation`] using the given strategy,
+    /// over the given addresses, which all have a weight of one.
+    pub fn new(
+        strategy: EgressAddressStrategy,
+        addresses: impl IntoIterator<Item = IpAddr>,
+    ) -> Self {
+        Self::new_weighted(strategy, addresses.into_iter().map(|addr| (addr, 1)))
+    }
+
+    /// Create a new [`EgressAddressRotation`] using the given strategy,
+    /// over the given addresses and their weights.
+    ///
+    /// Addresses with a weight of zero are never selected.
+    pub fn new_weighted(
+        strategy: EgressAddressStrategy,
+        addresses: impl IntoIterator<Item = (IpAddr, usize)>,
+    ) -> Self {
+        Self {
+            inner: Arc::new(RotationInner {
+                strategy,
+                addresses: addresses
+                    .into_iter()
+                    .filter(|(_, weight)| *weight > 0)
+                    .collect(),
+                counter: AtomicUsize::new(0),
+            }),
+            session: None,
+        }
+    }
+
+    /// Returns the strategy used to select the local address.
+    #[must_use]
+    pub fn strategy(&self) -> EgressAddressStrategy {
+        self.inner.strategy
+    }
+
+    /// Returns a [`EgressAddressRotation`] sharing the rotation of this one,
+    /// which connects on behalf of the session identified by the given key.
+    #[must_use]
+    pub fn for_session(&self, key: EgressSessionKey) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            session: Some(key),
+        }
+    }
+
+    /// Select the local address to use for a connection to the given target,
+    /// or `None` in case no address of the same family is configured.
+    #[must_use]
+    pub fn select(&self, target: SocketAddr) -> Option<IpAddr> {
+        let candidates = || {
+            self.inner
+                .addresses
+                .iter()
+                .filter(|(addr, _)| addr.is_ipv4() == target.is_ipv4())
+        };
+
+        let count = candidates().count();
+        if count == 0 {
+            return None;
+        }
+
+        match (self.inner.strategy, self.session) {
+            (EgressAddressStrategy::StickyPerSession, Some(EgressSessionKey(key))) => {
+                candidates().nth((key % count as u64) as usize)
+            }
+            (EgressAddressStrategy::Weighted, _) => {
+                let total: usize = candidates().map(|(_, weight)| *weight).sum();
+                let mut index = self.next_index() % total;
+                candidates().find(|(_, weight)| {
+                    if index < *weight {
+                        return true;
+                    }
+                    index -= *weight;
+                    false
+                })
+            }
+            _ => candidates().nth(self.next_index() % count),
+        }
+        .map(|(addr, _)| *addr)
+    }
+
+    fn next_index(&self) -> usize {
+        self.inner.counter.fetch_add(1, Ordering::Relaxed)
+    }
+}
+
+impl fmt::Debug for EgressAddressRotation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EgressAddressRotation")
+            .field("strategy", &self.inner.strategy)
+            .field("addresses", &self.inner.addresses)
+            .field("session", &self.session)
+            .finish()
+    }
+}
+
+impl TcpStreamConnector for EgressAddressRotation {
+    type Error = OpaqueError;
+
+    async fn connect(&self, addr: SocketAddr) -> Result<TcpStream, Self::Error> {
+        let local_ip = self.select(addr).with_context(|| {
+            format!(
+                "select egress address: no local address configured for the IP family of {addr}"
+            )
+        })?;
+        SocketAddress::new(local_ip, 0).connect(addr).await
+    }
+}
+
+#[cfg(feature = "http")]
+impl super::service::TcpStreamConnectorFactory for EgressAddressRotation {
+    type Connector = Self;
+    type Error = std::convert::Infallible;
+
+    fn make_connector(
+        &self,
+        ctx: rama_core::Context,
+    ) -> impl Future<
+        Output = Result<super::service::CreatedTcpStreamConnector<Self::Connector>, Self::Error>,
+    > + Send
+    + '_ {
+        let session = ctx.get::<EgressSessionKey>().copied().or_else(|| {
+            ctx.get::<rama_net::user::UserId>()
+                .map(EgressSessionKey::new)
+        });
+        let connector = match session {
+            Some(key) => self.for_session(key),
+            None => self.clone(),
+        };
+        std::future::ready(Ok(super::service::CreatedTcpStreamConnector {
+            ctx,
+            connector,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::{Ipv4Addr, Ipv6Addr};
+
+    const A: IpAddr = IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1));
+    const B: IpAddr = IpAddr::V4(Ipv4Addr::new(10, 0, 0, 2));
+    const C: IpAddr = IpAddr::V6(Ipv6Addr::new(0xfd00, 0, 0, 0, 0, 0, 0, 1));
+
+    fn select_n(rotation: &EgressAddressRotation, target: SocketAddr, n: usize) -> Vec<IpAddr> {
+        (0..n).map(|_| rotation.select(target).unwrap()).collect()
+    }
+
+    #[test]
+    fn test_egress_address_rotation() {
+        let v4_target: SocketAddr = (Ipv4Addr::new(1, 1, 1, 1), 443).into();
+        let v6_target: SocketAddr = (Ipv6Addr::LOCALHOST, 443).into();
+
+        let round_robin = EgressAddressRotation::new(EgressAddressStrategy::RoundRobin, [A, B, C]);
+        assert_eq!(select_n(&round_robin, v4_target, 4), [A, B, A, B]);
+        assert_eq!(select_n(&round_robin, v6_target, 2), [C, C]);
+
+        let weighted = EgressAddressRotation::new_weighted(
+            EgressAddressStrategy::Weighted,
+            [(A, 2), (B, 1), (C, 0)],
+        );
+        assert_eq!(select_n(&weighted, v4_target, 6), [A, A, B, A, A, B]);
+        assert!(weighted.select(v6_target).is_none());
+
+        let sticky = EgressAddressRotation::new(EgressAddressStrategy::StickyPerSession, [A, B]);
+        let session = sticky.for_session(EgressSessionKey::from(1));
+        assert_eq!(select_n(&session, v4_target, 3), [B, B, B]);
+        let other = sticky.for_session(EgressSessionKey::from(2));
+        assert_eq!(select_n(&other, v4_target, 3), [A, A, A]);
+        assert_eq!(select_n(&sticky, v4_target, 2), [A, B]);
+    }
+}
@@ -0,0 +1,119 @@
+use rama_core::error::OpaqueError;
+use rama_net::address::Authority;
+use std::{fmt, net::SocketAddr};
+
+#[derive(Debug)]
+/// The error of a single failed connection attempt, as reported by a [`TcpConnectError`].
+pub struct TcpConnectAttemptError {
+    addr: SocketAddr,
+    error: OpaqueError,
+}
+
+impl TcpConnectAttemptError {
+    pub(crate) fn new(addr: SocketAddr, error: OpaqueError) -> Self {
+        Self { addr, error }
+    }
+
+    /// Returns the address the connection attempt was made to.
+    #[must_use]
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// Returns the error the connection attempt failed with.
+    #[must_use]
+    pub fn error(&self) -> &OpaqueError {
+        &self.error
+    }
+}
+
+impl fmt::Display for TcpConnectAttemptError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.addr, self.error)
+    }
+}
+
+#[derive(Debug)]
+/// Error returned by [`tcp_connect`] in case no connection could be established
+/// to any of the (resolved) addresses of the target [`Authority`].
+///
+/// It reports the error of every attempted address, as well as the errors
+/// of the dns lookups that failed, if any.
+///
+/// [`tcp_connect`]: super::tcp_connect
+pub struct TcpConnectError {
+    authority: Authority,
+    dns_errors: Vec<OpaqueError>,
+    attempts: Vec<TcpConnectAttemptError>,
+}
+
+impl TcpConnectError {
+    pub(crate) fn new(authority: Authority) -> Self {
+        Self {
+            authority,
+            dns_errors: Vec::new(),
+            attempts: Vec::new(),
+        }
+    }
+
+    pub(crate) fn push_dns_error(&mut self, error: OpaqueError) {
+        self.dns_errors.push(error);
+    }
+
+    pub(crate) fn push_attempt(&mut self, attempt: TcpConnectAttemptError) {
+        self.attempts.push(attempt);
+    }
+
+    /// Returns the [`Authority`] a connection was attempted to.
+    #[must_use]
+    pub fn authority(&self) -> &Authority {
+        &self.authority
+    }
+
+    /// Returns the errors of the failed dns lookups.
+    #[must_use]
+    pub fn dns_errors(&self) -> &[OpaqueError] {
+        &self.dns_errors
+    }
+
+    /// Returns the failed connection attempts, in the order they failed.
+    #[must_use]
+    pub fn attempts(&self) -> &[TcpConnectAttemptError] {
+        &self.attempts
+    }
+}
+
+impl fmt::Display for TcpConnectError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "failed to establish tcp connection to {}",
+            self.authority
+        )?;
+        if self.attempts.is_empty() {
+            f.write_str(": no address attempted")?;
+        } else {
+            write!(f, ": {} attempt(s) failed: [", self.attempts.len())?;
+            for (index, attempt) in self.attempts.iter().enumerate() {
+                if index > 0 {
+                    f.write_str("; ")?;
+                }
+                attempt.fmt(f)?;
+            }
+            f.write_str("]")?;
+        }
+        if !self.dns_errors.is_empty() {
+            f.write_str(" (dns: ")?;
+            for (index, error) in self.dns_errors.iter().enumerate() {
+                if index > 0 {
+                    f.write_str("; ")?;
+                }
+                error.fmt(f)?;
+            }
+            f.write_str(")")?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for TcpConnectError {}
@@ -5,7 +5,22 @@ pub mod service;
 
 mod connect;
 #[doc(inline)]
-pub use connect::{TcpStreamConnector, default_tcp_connect, tcp_connect};
+pub use connect::{
+    TcpConnectOptions, TcpStreamConnector, default_tcp_connect, tcp_connect,
+    tcp_connect_with_options,
+};
+
+#[cfg(target_os = "linux")]
+#[doc(inline)]
+pub use connect::MultipathTcpStreamConnector;
+
+mod egress;
+#[doc(inline)]
+pub use egress::{EgressAddressRotation, EgressAddressStrategy, EgressSessionKey};
+
+mod error;
+#[doc(inline)]
+pub use error::{TcpConnectAttemptError, TcpConnectError};
 
 #[cfg(feature = "http")]
 mod request;
@@ -7,12 +7,13 @@ use rama_dns::{DnsResolver, GlobalDnsResolver};
 use rama_net::{
     address::ProxyAddress,
     client::EstablishedClientConnection,
+    socket::{TcpStreamOptions, core::SockRef},
     stream::{ClientSocketInfo, SocketInfo},
     transport::{TransportProtocol, TryRefIntoTransportContext},
 };
 
 use crate::TcpStream;
-use crate::client::connect::TcpStreamConnector;
+use crate::client::{TcpConnectOptions, TcpStreamConnector, tcp_connect_with_options};
 
 use super::{CreatedTcpStreamConnector, TcpStreamConnectorCloneFactory, TcpStreamConnectorFactory};
 
@@ -20,6 +21,8 @@ use super::{CreatedTcpStreamConnector, TcpStreamConnectorCloneFactory, TcpStream
 pub struct TcpConnector<Dns = GlobalDnsResolver, ConnectorFactory = ()> {
     dns: Dns,
     connector_factory: ConnectorFactory,
+    stream_options: Option<TcpStreamOptions>,
+    connect_options: Option<TcpConnectOptions>,
 }
 
 impl<Dns: std::fmt::Debug, ConnectorFactory: std::fmt::Debug> std::fmt::Debug
@@ -29,6 +32,8 @@ impl<Dns: std::fmt::Debug, ConnectorFactory: std::fmt::Debug> std::fmt::Debug
         f.debug_struct("TcpConnector")
             .field("dns", &self.dns)
             .field("connector_factory", &self.connector_factory)
+            .field("stream_options", &self.stream_options)
+            .field("connect_options", &self.connect_options)
             .finish()
     }
 }
@@ -38,11 +43,33 @@ impl<Dns: Clone, ConnectorFactory: Clone> Clone for TcpConnector<Dns, ConnectorF
         Self {
             dns: self.dns.clone(),
             connector_factory: self.connector_factory.clone(),
+            stream_options: self.stream_options.clone(),
+            connect_options: self.connect_options.clone(),
         }
     }
 }
 
-impl<Dns, Connector> TcpConnector<Dns, Connector> {}
+impl<Dns, Connector> TcpConnector<Dns, Connector> {
+    rama_utils::macros::generate_set_and_with! {
+        /// Set the [`TcpStreamOptions`] to apply to each established [`TcpStream`],
+        /// e.g. to enable TCP keepalive probes for long-lived connections.
+        pub fn stream_options(mut self, options: Option<TcpStreamOptions>) -> Self {
+            self.stream_options = options;
+            self
+        }
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Set the [`TcpConnectOptions`] used for each connection attempt,
+        /// e.g. to define a timeout per attempted address.
+        ///
+        /// [`TcpConnectOptions`] found in the [`Context`] take precedence over these.
+        pub fn connect_options(mut self, options: Option<TcpConnectOptions>) -> Self {
+            self.connect_options = options;
+            self
+        }
+    }
+}
 
 impl TcpConnector {
     /// Create a new [`TcpConnector`], which is used to establish a connection to a server.
@@ -54,6 +81,8 @@ impl TcpConnector {
         Self {
             dns: GlobalDnsResolver::new(),
             connector_factory: (),
+            stream_options: None,
+            connect_options: None,
         }
     }
 }
@@ -67,6 +96,8 @@ impl<Dns, ConnectorFactory> TcpConnector<Dns, ConnectorFactory> {
         TcpConnector {
             dns,
             connector_factory: self.connector_factory,
+            stream_options: self.stream_options,
+            connect_options: self.connect_options,
         }
     }
 }
@@ -81,6 +112,8 @@ where {
         TcpConnector {
             dns: self.dns,
             connector_factory: TcpStreamConnectorCloneFactory(connector),
+            stream_options: self.stream_options,
+            connect_options: self.connect_options,
         }
     }
 
@@ -90,10 +123,30 @@ where {
         TcpConnector {
             dns: self.dns,
             connector_factory: factory,
+            stream_options: self.stream_options,
+            connect_options: self.connect_options,
         }
     }
 }
 
+impl<Dns, ConnectorFactory> TcpConnector<Dns, ConnectorFactory> {
+    fn connect_options(&self, ctx: &Context) -> TcpConnectOptions {
+        ctx.get::<TcpConnectOptions>()
+            .or(self.connect_options.as_ref())
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    fn apply_stream_options(&self, conn: &TcpStream) -> Result<(), OpaqueError> {
+        if let Some(options) = &self.stream_options {
+            options
+                .apply_to(&SockRef::from(conn))
+                .context("tcp connector: apply stream options")?;
+        }
+        Ok(())
+    }
+}
+
 impl Default for TcpConnector {
     fn default() -> Self {
         Self::new()
@@ -121,25 +174,28 @@ where
             .map_err(Into::into)?;
 
         if let Some(proxy) = ctx.get::<ProxyAddress>() {
-            let (conn, addr) = crate::client::tcp_connect(
+            let (conn, addr) = tcp_connect_with_options(
                 &ctx,
                 proxy.authority.clone(),
                 self.dns.clone(),
                 connector,
+                self.connect_options(&ctx),
             )
             .await
             .context("tcp connector: conncept to proxy")?;
+            self.apply_stream_options(&conn)?;
 
-            ctx.insert(ClientSocketInfo(SocketInfo::new(
-                conn.local_addr()
-                    .inspect_err(|err| {
-                        tracing::debug!(
-                            "failed to receive local addr of established connection to proxy: {err:?}"
-                        )
-                    })
-                    .ok(),
-                addr,
-            )));
+            let local_addr = conn
+                .local_addr()
+                .inspect_err(|err| {
+                    tracing::debug!(
+                        "failed to receive local addr of established connection to proxy: {err:?}"
+                    )
+                })
+                .ok();
+            ctx.insert(ClientSocketInfo(
+                SocketInfo::new(local_addr, addr).with_multipath(crate::mptcp::is_multipath(&conn)),
+            ));
 
             return Ok(EstablishedClientConnection { ctx, req, conn });
         }
@@ -163,20 +219,26 @@ where
         }
 
         let authority = transport_ctx.authority.clone();
-        let (conn, addr) = crate::client::tcp_connect(&ctx, authority, self.dns.clone(), connector)
-            .await
-            .context("tcp connector: connect to server")?;
-
-        ctx.insert(ClientSocketInfo(SocketInfo::new(
-            conn.local_addr()
-                .inspect_err(|err| {
-                    tracing::debug!(
-                        "failed to receive local addr of established connection: {err:?}"
-                    )
-                })
-                .ok(),
-            addr,
-        )));
+        let options = self.connect_options(&ctx);
+        let (conn, addr) =
+            tcp_connect_with_options(&ctx, authority, self.dns.clone(), connector, options)
+                .await
+                .context("tcp connector: connect to server")?;
+        self.apply_stream_options(&conn)?;
+
+        ctx.insert(ClientSocketInfo(
+            SocketInfo::new(
+                conn.local_addr()
+                    .inspect_err(|err| {
+                        tracing::debug!(
+                            "failed to receive local addr of established connection: {err:?}"
+                        )
+                    })
+                    .ok(),
+                addr,
+            )
+            .with_multipath(crate::mptcp::is_multipath(&conn)),
+        ));
 
         Ok(EstablishedClientConnection { ctx, req, conn })
     }
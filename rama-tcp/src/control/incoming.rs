@@ -0,0 +1,95 @@
+use super::ControlledTcpStream;
+use crate::TcpStream;
+use rama_core::{Context, Layer, Service};
+use rama_utils::macros::define_inner_service_accessors;
+use std::fmt;
+
+/// A [`Service`] that wraps an accepted [`TcpStream`] in a [`ControlledTcpStream`],
+/// inserting its [`TcpStreamControl`] into the [`Context`].
+///
+/// [`Service`]: rama_core::Service
+/// [`TcpStream`]: crate::TcpStream
+/// [`TcpStreamControl`]: super::TcpStreamControl
+pub struct IncomingTcpControlService<S> {
+    inner: S,
+}
+
+impl<S: fmt::Debug> fmt::Debug for IncomingTcpControlService<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("IncomingTcpControlService")
+            .field("inner", &self.inner)
+            .finish()
+    }
+}
+
+impl<S> IncomingTcpControlService<S> {
+    /// Create a new [`IncomingTcpControlService`].
+    ///
+    /// See [`IncomingTcpControlService`] for more information.
+    pub const fn new(inner: S) -> Self {
+        Self { inner }
+    }
+
+    define_inner_service_accessors!();
+}
+
+impl<S> Clone for IncomingTcpControlService<S>
+where
+    S: Clone,
+{
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl<S> Service<TcpStream> for IncomingTcpControlService<S>
+where
+    S: Service<ControlledTcpStream>,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+
+    fn serve(
+        &self,
+        mut ctx: Context,
+        stream: TcpStream,
+    ) -> impl Future<Output = Result<Self::Response, Self::Error>> + Send + '_ {
+        let stream = ControlledTcpStream::new(stream);
+        ctx.insert(stream.control());
+        self.inner.serve(ctx, stream)
+    }
+}
+
+/// A [`Layer`] that wraps an accepted [`TcpStream`] in a [`ControlledTcpStream`].
+///
+/// See [`IncomingTcpControlService`] for more information.
+///
+/// [`Layer`]: rama_core::Layer
+/// [`TcpStream`]: crate::TcpStream
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct IncomingTcpControlLayer;
+
+impl IncomingTcpControlLayer {
+    /// Create a new [`IncomingTcpControlLayer`].
+    #[must_use]
+    pub const fn new() -> Self {
+        Self
+    }
+}
+
+impl Default for IncomingTcpControlLayer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<S> Layer<S> for IncomingTcpControlLayer {
+    type Service = IncomingTcpControlService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        IncomingTcpControlService { inner }
+    }
+}
@@ -0,0 +1,44 @@
+//! Control over the socket of a [`TcpStream`] from within services.
+//!
+//! Wrap accepted streams using the [`IncomingTcpControlLayer`] or established
+//! connections using the [`OutgoingTcpControlLayer`] in order to insert a
+//! [`TcpStreamControl`] handle into the [`Context`]. It allows to toggle Nagle's algorithm
+//! (`TCP_NODELAY`) and cork (`TCP_CORK`) and to explicitly flush the data buffered by the kernel
+//! mid-stream, even though the stream itself is consumed by a protocol consumer (e.g. `TLS`).
+//!
+//! This is needed for latency-sensitive (tunneled) protocols which
+//! only become interactive after an initial buffered phase.
+//!
+//! # Example
+//!
+//! ```
+//! use rama_core::{Context, Layer, service::service_fn};
+//! use rama_tcp::control::{ControlledTcpStream, IncomingTcpControlLayer, TcpStreamControl};
+//! use std::convert::Infallible;
+//!
+//! let service = IncomingTcpControlLayer::new().into_layer(service_fn(
+//!     async |ctx: Context, _stream: ControlledTcpStream| {
+//!         let control = ctx.get::<TcpStreamControl>().unwrap();
+//!         // ... initial (buffered) phase
+//!         control.set_nodelay(true).unwrap();
+//!         // ... latency-sensitive phase
+//!         Ok::<_, Infallible>(())
+//!     },
+//! ));
+//! # let _ = service;
+//! ```
+//!
+//! [`TcpStream`]: crate::TcpStream
+//! [`Context`]: rama_core::Context
+
+mod stream;
+#[doc(inline)]
+pub use stream::{ControlledTcpStream, TcpStreamControl};
+
+mod incoming;
+#[doc(inline)]
+pub use incoming::{IncomingTcpControlLayer, IncomingTcpControlService};
+
+mod outgoing;
+#[doc(inline)]
+pub use outgoing::{OutgoingTcpControlLayer, OutgoingTcpControlService};
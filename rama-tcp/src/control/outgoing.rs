@@ -0,0 +1,95 @@
+use super::ControlledTcpStream;
+use crate::TcpStream;
+use rama_core::{Context, Layer, Service};
+use rama_net::client::{ConnectorService, EstablishedClientConnection};
+use rama_utils::macros::define_inner_service_accessors;
+use std::fmt;
+
+/// A [`Service`] that wraps an established [`TcpStream`] in a [`ControlledTcpStream`],
+/// inserting its [`TcpStreamControl`] into the [`Context`] of the established connection.
+///
+/// [`Service`]: rama_core::Service
+/// [`TcpStream`]: crate::TcpStream
+/// [`TcpStreamControl`]: super::TcpStreamControl
+pub struct OutgoingTcpControlService<S> {
+    inner: S,
+}
+
+impl<S: fmt::Debug> fmt::Debug for OutgoingTcpControlService<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("OutgoingTcpControlService")
+            .field("inner", &self.inner)
+            .finish()
+    }
+}
+
+impl<S> OutgoingTcpControlService<S> {
+    /// Create a new [`OutgoingTcpControlService`].
+    ///
+    /// See [`OutgoingTcpControlService`] for more information.
+    pub const fn new(inner: S) -> Self {
+        Self { inner }
+    }
+
+    define_inner_service_accessors!();
+}
+
+impl<S> Clone for OutgoingTcpControlService<S>
+where
+    S: Clone,
+{
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl<S, Request> Service<Request> for OutgoingTcpControlService<S>
+where
+    S: ConnectorService<Request, Connection = TcpStream, Error: Send + 'static>,
+    Request: Send + 'static,
+{
+    type Response = EstablishedClientConnection<ControlledTcpStream, Request>;
+    type Error = S::Error;
+
+    async fn serve(&self, ctx: Context, req: Request) -> Result<Self::Response, Self::Error> {
+        let EstablishedClientConnection { mut ctx, req, conn } =
+            self.inner.connect(ctx, req).await?;
+        let conn = ControlledTcpStream::new(conn);
+        ctx.insert(conn.control());
+        Ok(EstablishedClientConnection { ctx, req, conn })
+    }
+}
+
+/// A [`Layer`] that wraps an established [`TcpStream`] in a [`ControlledTcpStream`].
+///
+/// See [`OutgoingTcpControlService`] for more information.
+///
+/// [`Layer`]: rama_core::Layer
+/// [`TcpStream`]: crate::TcpStream
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct OutgoingTcpControlLayer;
+
+impl OutgoingTcpControlLayer {
+    /// Create a new [`OutgoingTcpControlLayer`].
+    #[must_use]
+    pub const fn new() -> Self {
+        Self
+    }
+}
+
+impl Default for OutgoingTcpControlLayer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<S> Layer<S> for OutgoingTcpControlLayer {
+    type Service = OutgoingTcpControlService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        OutgoingTcpControlService { inner }
+    }
+}
@@ -0,0 +1,254 @@
+use crate::TcpStream;
+use rama_net::socket::core::SockRef;
+use std::{
+    fmt, io,
+    net::{Shutdown, SocketAddr},
+    pin::Pin,
+    sync::{Arc, Weak},
+    task::{Context, Poll, ready},
+};
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+
+/// A [`TcpStream`] which can be controlled using a [`TcpStreamControl`] handle,
+/// obtained using [`ControlledTcpStream::control`].
+///
+/// [`TcpStream`]: crate::TcpStream
+pub struct ControlledTcpStream {
+    stream: Arc<TcpStream>,
+}
+
+impl fmt::Debug for ControlledTcpStream {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ControlledTcpStream")
+            .field("stream", &self.stream)
+            .finish()
+    }
+}
+
+impl ControlledTcpStream {
+    /// Create a new [`ControlledTcpStream`] wrapping the given [`TcpStream`].
+    ///
+    /// [`TcpStream`]: crate::TcpStream
+    #[must_use]
+    pub fn new(stream: TcpStream) -> Self {
+        Self {
+            stream: Arc::new(stream),
+        }
+    }
+
+    /// Get a [`TcpStreamControl`] handle to this stream.
+    #[must_use]
+    pub fn control(&self) -> TcpStreamControl {
+        TcpStreamControl(Arc::downgrade(&self.stream))
+    }
+
+    /// Get a reference to the inner [`TcpStream`].
+    ///
+    /// [`TcpStream`]: crate::TcpStream
+    #[must_use]
+    pub fn get_ref(&self) -> &TcpStream {
+        &self.stream
+    }
+
+    /// Returns the local address that this stream is bound to.
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.stream.local_addr()
+    }
+
+    /// Returns the remote address that this stream is connected to.
+    pub fn peer_addr(&self) -> io::Result<SocketAddr> {
+        self.stream.peer_addr()
+    }
+}
+
+impl AsyncRead for ControlledTcpStream {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        if buf.remaining() == 0 {
+            return Poll::Ready(Ok(()));
+        }
+        loop {
+            ready!(self.stream.poll_read_ready(cx))?;
+            match self.stream.try_read(buf.initialize_unfilled()) {
+                Ok(n) => {
+                    buf.advance(n);
+                    return Poll::Ready(Ok(()));
+                }
+                // readiness got cleared, poll again to register interest
+                Err(err) if err.kind() == io::ErrorKind::WouldBlock => continue,
+                Err(err) => return Poll::Ready(Err(err)),
+            }
+        }
+    }
+}
+
+impl AsyncWrite for ControlledTcpStream {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<Result<usize, io::Error>> {
+        loop {
+            ready!(self.stream.poll_write_ready(cx))?;
+            match self.stream.try_write(buf) {
+                Err(err) if err.kind() == io::ErrorKind::WouldBlock => continue,
+                result => return Poll::Ready(result),
+            }
+        }
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Result<(), io::Error>> {
+        // tcp streams are not buffered in user space
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Result<(), io::Error>> {
+        Poll::Ready(SockRef::from(&*self.stream).shutdown(Shutdown::Write))
+    }
+
+    fn poll_write_vectored(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        bufs: &[io::IoSlice<'_>],
+    ) -> Poll<Result<usize, io::Error>> {
+        loop {
+            ready!(self.stream.poll_write_ready(cx))?;
+            match self.stream.try_write_vectored(bufs) {
+                Err(err) if err.kind() == io::ErrorKind::WouldBlock => continue,
+                result => return Poll::Ready(result),
+            }
+        }
+    }
+
+    fn is_write_vectored(&self) -> bool {
+        true
+    }
+}
+
+/// A handle to control the socket of a [`ControlledTcpStream`],
+/// even though the stream itself is consumed by a protocol consumer.
+///
+/// Inserted into the [`Context`] by the [`IncomingTcpControlLayer`] and [`OutgoingTcpControlLayer`].
+///
+/// All methods return an error of kind [`io::ErrorKind::NotConnected`]
+/// once the [`ControlledTcpStream`] is dropped.
+///
+/// [`Context`]: rama_core::Context
+/// [`IncomingTcpControlLayer`]: super::IncomingTcpControlLayer
+/// [`OutgoingTcpControlLayer`]: super::OutgoingTcpControlLayer
+#[derive(Debug, Clone)]
+pub struct TcpStreamControl(Weak<TcpStream>);
+
+impl TcpStreamControl {
+    fn with_socket<T>(&self, f: impl FnOnce(SockRef<'_>) -> io::Result<T>) -> io::Result<T> {
+        let stream = self.0.upgrade().ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotConnected,
+                "controlled tcp stream is dropped",
+            )
+        })?;
+        f(SockRef::from(&*stream))
+    }
+
+    /// Gets the value of the `TCP_NODELAY` option on the stream.
+    pub fn nodelay(&self) -> io::Result<bool> {
+        self.with_socket(|socket| socket.tcp_nodelay())
+    }
+
+    /// Sets the value of the `TCP_NODELAY` option on the stream.
+    ///
+    /// If set, Nagle's algorithm is disabled, such that data is sent
+    /// as soon as possible, even if there is only a small amount of it.
+    pub fn set_nodelay(&self, nodelay: bool) -> io::Result<()> {
+        self.with_socket(|socket| socket.set_tcp_nodelay(nodelay))
+    }
+
+    #[cfg(any(target_os = "android", target_os = "fuchsia", target_os = "linux"))]
+    /// Gets the value of the `TCP_CORK` option on the stream.
+    pub fn cork(&self) -> io::Result<bool> {
+        self.with_socket(|socket| socket.tcp_cork())
+    }
+
+    #[cfg(any(target_os = "android", target_os = "fuchsia", target_os = "linux"))]
+    /// Sets the value of the `TCP_CORK` option on the stream.
+    ///
+    /// If set, partial frames are not sent until the cork is removed,
+    /// the stream is flushed using [`TcpStreamControl::flush`] or a ceiling of 200ms is reached.
+    pub fn set_cork(&self, cork: bool) -> io::Result<()> {
+        self.with_socket(|socket| socket.set_tcp_cork(cork))
+    }
+
+    /// Explicitly flush the data which is buffered by the kernel
+    /// because the stream is corked or because of Nagle's algorithm,
+    /// leaving the options of the stream as they were.
+    pub fn flush(&self) -> io::Result<()> {
+        self.with_socket(|socket| {
+            #[cfg(any(target_os = "android", target_os = "fuchsia", target_os = "linux"))]
+            if socket.tcp_cork()? {
+                // removing the cork pushes out all pending data
+                socket.set_tcp_cork(false)?;
+                return socket.set_tcp_cork(true);
+            }
+            if !socket.tcp_nodelay()? {
+                // (temporarily) disabling nagle pushes out all pending data
+                socket.set_tcp_nodelay(true)?;
+                socket.set_tcp_nodelay(false)?;
+            }
+            Ok(())
+        })
+    }
+
+    /// Returns `true` in case the [`ControlledTcpStream`] is no longer alive.
+    #[must_use]
+    pub fn is_closed(&self) -> bool {
+        self.0.strong_count() == 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    #[tokio::test]
+    async fn test_controlled_tcp_stream() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        let server = tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut buf = [0u8; 4];
+            stream.read_exact(&mut buf).await.unwrap();
+            stream.write_all(&buf).await.unwrap();
+        });
+
+        let stream = ControlledTcpStream::new(TcpStream::connect(addr).await.unwrap());
+        let control = stream.control();
+
+        control.set_nodelay(true).unwrap();
+        assert!(control.nodelay().unwrap());
+        control.set_nodelay(false).unwrap();
+        assert!(!control.nodelay().unwrap());
+
+        let mut stream = stream;
+        stream.write_all(b"ping").await.unwrap();
+        control.flush().unwrap();
+        assert!(!control.nodelay().unwrap());
+
+        let mut buf = [0u8; 4];
+        stream.read_exact(&mut buf).await.unwrap();
+        assert_eq!(&buf, b"ping");
+        server.await.unwrap();
+
+        assert!(!control.is_closed());
+        drop(stream);
+        assert!(control.is_closed());
+        assert_eq!(
+            control.set_nodelay(true).unwrap_err().kind(),
+            io::ErrorKind::NotConnected
+        );
+    }
+}
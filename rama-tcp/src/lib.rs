@@ -18,7 +18,10 @@
 #![cfg_attr(not(test), warn(clippy::print_stdout, clippy::dbg_macro))]
 
 pub mod client;
+pub mod control;
 pub mod pool;
 pub mod server;
 
+mod mptcp;
+
 pub use tokio::net::{TcpSocket, TcpStream};
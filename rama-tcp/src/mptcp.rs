@@ -0,0 +1,47 @@
+//! Multipath TCP (MPTCP) support, only available on Linux.
+//!
+//! Sockets are created as MPTCP sockets when requested, falling back
+//! to plain TCP sockets in case MPTCP is not supported (or disabled) by the kernel.
+//! A MPTCP socket itself falls back to plain TCP transparently
+//! in case the peer does not support MPTCP.
+
+#[cfg(target_os = "linux")]
+use rama_core::telemetry::tracing;
+#[cfg(target_os = "linux")]
+use rama_net::socket::{
+    SocketOptions,
+    core::{Protocol as SocketProtocol, SockRef, Socket},
+    opts::Protocol,
+};
+#[cfg(target_os = "linux")]
+use std::io;
+
+#[cfg(target_os = "linux")]
+/// Build a socket using the given [`SocketOptions`] as a MPTCP socket,
+/// falling back to the protocol of the given options in case
+/// MPTCP sockets cannot be created.
+pub(crate) fn try_build_multipath_socket(opts: &SocketOptions) -> io::Result<Socket> {
+    let mptcp_opts = SocketOptions {
+        protocol: Some(Protocol::MPTCP),
+        ..opts.clone()
+    };
+    match mptcp_opts.try_build_socket() {
+        Ok(socket) => Ok(socket),
+        Err(err) => {
+            tracing::debug!("failed to create MPTCP socket, fallback to plain TCP: {err:?}");
+            opts.try_build_socket()
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+/// Returns `true` in case the given socket is a MPTCP socket.
+pub(crate) fn is_multipath(socket: &impl std::os::fd::AsFd) -> bool {
+    SockRef::from(socket).protocol().ok().flatten() == Some(SocketProtocol::MPTCP)
+}
+
+#[cfg(not(target_os = "linux"))]
+/// Returns `true` in case the given socket is a MPTCP socket.
+pub(crate) fn is_multipath<S>(_socket: &S) -> bool {
+    false
+}
@@ -6,7 +6,7 @@ use rama_core::graceful::ShutdownGuard;
 use rama_core::rt::Executor;
 use rama_core::telemetry::tracing::{self, Instrument, trace_root_span};
 use rama_net::address::SocketAddress;
-use rama_net::socket::Interface;
+use rama_net::socket::{Interface, TcpStreamOptions, core::SockRef};
 use rama_net::stream::SocketInfo;
 use std::pin::pin;
 use std::sync::Arc;
@@ -16,19 +16,30 @@ use tokio::net::TcpListener as TokioTcpListener;
 #[cfg(any(target_os = "android", target_os = "fuchsia", target_os = "linux"))]
 use rama_net::socket::{DeviceName, SocketOptions};
 
+use super::throttle::{AcceptThrottle, AcceptThrottler};
 use crate::TcpStream;
 
 #[derive(Clone, Debug)]
 /// Builder for `TcpListener`.
 pub struct TcpListenerBuilder {
     ttl: Option<u32>,
+    stream_options: Option<TcpStreamOptions>,
+    accept_throttle: Option<AcceptThrottle>,
+    #[cfg(target_os = "linux")]
+    multipath: bool,
 }
 
 impl TcpListenerBuilder {
     /// Create a new `TcpListenerBuilder` without a state.
     #[must_use]
     pub fn new() -> Self {
-        Self { ttl: None }
+        Self {
+            ttl: None,
+            stream_options: None,
+            accept_throttle: None,
+            #[cfg(target_os = "linux")]
+            multipath: false,
+        }
     }
 }
 
@@ -49,6 +60,41 @@ impl TcpListenerBuilder {
             self
         }
     }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Set the [`TcpStreamOptions`] to apply to each accepted [`TcpStream`],
+        /// e.g. to enable TCP keepalive probes for long-lived connections.
+        pub fn stream_options(mut self, options: Option<TcpStreamOptions>) -> Self {
+            self.stream_options = options;
+            self
+        }
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Set the [`AcceptThrottle`] used to slow down or pause accepting connections
+        /// while under resource pressure, when served using [`TcpListener::serve`]
+        /// or [`TcpListener::serve_graceful`].
+        pub fn accept_throttle(mut self, throttle: Option<AcceptThrottle>) -> Self {
+            self.accept_throttle = throttle;
+            self
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    rama_utils::macros::generate_set_and_with! {
+        /// Create the listener as a Multipath TCP (MPTCP) socket,
+        /// falling back to a plain TCP socket in case MPTCP is not supported by the kernel.
+        ///
+        /// Accepted connections from peers that do not support MPTCP
+        /// fall back to plain TCP, see [`SocketInfo::is_multipath`].
+        ///
+        /// Only applies to listeners bound using [`Self::bind_address`] or [`Self::bind_device`],
+        /// use [`SocketOptions::protocol`] for sockets created using your own [`SocketOptions`].
+        pub fn multipath(mut self, multipath: bool) -> Self {
+            self.multipath = multipath;
+            self
+        }
+    }
 }
 
 impl TcpListenerBuilder {
@@ -64,6 +110,35 @@ impl TcpListenerBuilder {
         addr: A,
     ) -> Result<TcpListener, BoxError> {
         let socket_addr = addr.try_into().map_err(Into::<BoxError>::into)?;
+
+        #[cfg(target_os = "linux")]
+        if self.multipath {
+            let opts = SocketOptions {
+                address: Some(socket_addr),
+                reuse_address: Some(true),
+                ..match socket_addr.ip_addr() {
+                    std::net::IpAddr::V4(_) => SocketOptions::default_tcp(),
+                    std::net::IpAddr::V6(_) => SocketOptions::default_tcp_v6(),
+                }
+            };
+            let ttl = self.ttl;
+            let stream_options = self.stream_options;
+            let accept_throttle = self.accept_throttle;
+            return tokio::task::spawn_blocking(move || {
+                let socket = crate::mptcp::try_build_multipath_socket(&opts)
+                    .context("create (multipath) tcp socket")?;
+                if let Some(ttl) = ttl {
+                    socket.set_ttl(ttl).context("set ttl on tcp listener")?;
+                }
+                socket
+                    .listen(4096)
+                    .context("mark the socket as ready to accept incoming connection requests")?;
+                bind_socket_internal(socket, stream_options, accept_throttle)
+            })
+            .await
+            .context("await blocking bind socket task")?;
+        }
+
         let tokio_socket_addr: SocketAddr = socket_addr.into();
         let inner = TokioTcpListener::bind(tokio_socket_addr)
             .await
@@ -73,7 +148,12 @@ impl TcpListenerBuilder {
             inner.set_ttl(ttl).context("set ttl on tcp listener")?;
         }
 
-        Ok(TcpListener { inner })
+        Ok(TcpListener {
+            inner,
+            stream_options: self.stream_options,
+            accept_throttle: self.accept_throttle,
+            multipath: false,
+        })
     }
 
     #[cfg(any(windows, unix))]
@@ -84,9 +164,13 @@ impl TcpListenerBuilder {
         self,
         socket: rama_net::socket::core::Socket,
     ) -> Result<TcpListener, BoxError> {
-        tokio::task::spawn_blocking(|| bind_socket_internal(socket))
-            .await
-            .context("await blocking bind socket task")?
+        let stream_options = self.stream_options;
+        let accept_throttle = self.accept_throttle;
+        tokio::task::spawn_blocking(|| {
+            bind_socket_internal(socket, stream_options, accept_throttle)
+        })
+        .await
+        .context("await blocking bind socket task")?
     }
 
     #[cfg(any(target_os = "android", target_os = "fuchsia", target_os = "linux"))]
@@ -97,18 +181,29 @@ impl TcpListenerBuilder {
         self,
         name: N,
     ) -> Result<TcpListener, BoxError> {
-        tokio::task::spawn_blocking(|| {
+        let stream_options = self.stream_options;
+        let accept_throttle = self.accept_throttle;
+        #[cfg(target_os = "linux")]
+        let multipath = self.multipath;
+        tokio::task::spawn_blocking(move || {
             let name = name.try_into().map_err(Into::<BoxError>::into)?;
-            let socket = SocketOptions {
+            let opts = SocketOptions {
                 device: Some(name),
                 ..SocketOptions::default_tcp()
-            }
-            .try_build_socket()
-            .context("create tcp ipv4 socket attached to device")?;
+            };
+            #[cfg(target_os = "linux")]
+            let socket = if multipath {
+                crate::mptcp::try_build_multipath_socket(&opts)
+            } else {
+                opts.try_build_socket()
+            };
+            #[cfg(not(target_os = "linux"))]
+            let socket = opts.try_build_socket();
+            let socket = socket.context("create tcp ipv4 socket attached to device")?;
             socket
                 .listen(4096)
                 .context("mark the socket as ready to accept incoming connection requests")?;
-            bind_socket_internal(socket)
+            bind_socket_internal(socket, stream_options, accept_throttle)
         })
         .await
         .context("await blocking bind socket task")?
@@ -143,6 +238,9 @@ impl TcpListenerBuilder {
 /// using one of the `serve` methods such as [`TcpListener::serve`].
 pub struct TcpListener {
     inner: TokioTcpListener,
+    stream_options: Option<TcpStreamOptions>,
+    accept_throttle: Option<AcceptThrottle>,
+    multipath: bool,
 }
 
 impl TcpListener {
@@ -194,13 +292,21 @@ impl TcpListener {
     }
 }
 
-fn bind_socket_internal(socket: rama_net::socket::core::Socket) -> Result<TcpListener, BoxError> {
+fn bind_socket_internal(
+    socket: rama_net::socket::core::Socket,
+    stream_options: Option<TcpStreamOptions>,
+    accept_throttle: Option<AcceptThrottle>,
+) -> Result<TcpListener, BoxError> {
+    let multipath = crate::mptcp::is_multipath(&socket);
     let listener = std::net::TcpListener::from(socket);
     listener
         .set_nonblocking(true)
         .context("set socket as non-blocking")?;
     Ok(TcpListener {
         inner: TokioTcpListener::from_std(listener)?,
+        stream_options,
+        accept_throttle,
+        multipath,
     })
 }
 
@@ -221,11 +327,59 @@ impl TcpListener {
     pub fn ttl(&self) -> io::Result<u32> {
         self.inner.ttl()
     }
+
+    /// Returns the [`TcpStreamOptions`] applied to each accepted [`TcpStream`], if any.
+    ///
+    /// See [`TcpListenerBuilder::with_stream_options`] for more information.
+    #[must_use]
+    pub fn stream_options(&self) -> Option<&TcpStreamOptions> {
+        self.stream_options.as_ref()
+    }
+
+    /// Returns the [`AcceptThrottle`] used while serving this listener, if any.
+    ///
+    /// See [`TcpListenerBuilder::with_accept_throttle`] for more information.
+    #[must_use]
+    pub fn accept_throttle(&self) -> Option<&AcceptThrottle> {
+        self.accept_throttle.as_ref()
+    }
+
+    /// Returns `true` in case the listener is a Multipath TCP (MPTCP) socket.
+    ///
+    /// See [`TcpListenerBuilder::with_multipath`] for more information.
+    #[must_use]
+    pub fn is_multipath(&self) -> bool {
+        self.multipath
+    }
+
+    fn socket_info(
+        &self,
+        stream: &TcpStream,
+        local_addr: Option<SocketAddr>,
+        peer_addr: SocketAddr,
+    ) -> SocketInfo {
+        SocketInfo::new(local_addr, peer_addr)
+            .with_multipath(self.multipath && crate::mptcp::is_multipath(stream))
+    }
+
+    fn apply_stream_options(&self, stream: &TcpStream) {
+        if let Some(options) = &self.stream_options
+            && let Err(err) = options.apply_to(&SockRef::from(stream))
+        {
+            tracing::debug!("failed to apply stream options to accepted TCP stream: {err:?}");
+        }
+    }
 }
 
 impl From<TokioTcpListener> for TcpListener {
     fn from(value: TokioTcpListener) -> Self {
-        Self { inner: value }
+        let multipath = crate::mptcp::is_multipath(&value);
+        Self {
+            inner: value,
+            stream_options: None,
+            accept_throttle: None,
+            multipath,
+        }
     }
 }
 
@@ -245,8 +399,12 @@ impl TryFrom<std::net::TcpListener> for TcpListener {
 
     fn try_from(value: std::net::TcpListener) -> Result<Self, Self::Error> {
         value.set_nonblocking(true)?;
+        let multipath = crate::mptcp::is_multipath(&value);
         Ok(Self {
             inner: TokioTcpListener::from_std(value)?,
+            stream_options: None,
+            accept_throttle: None,
+            multipath,
         })
     }
 }
@@ -257,6 +415,7 @@ impl TcpListener {
     #[inline]
     pub async fn accept(&self) -> std::io::Result<(TcpStream, SocketAddress)> {
         let (stream, addr) = self.inner.accept().await?;
+        self.apply_stream_options(&stream);
         Ok((stream, addr.into()))
     }
 
@@ -270,8 +429,14 @@ impl TcpListener {
     {
         let ctx = Context::new(Executor::new());
         let service = Arc::new(service);
+        let mut throttler = self.accept_throttle.clone().map(AcceptThrottler::new);
 
         loop {
+            let permit = match throttler.as_mut() {
+                Some(throttler) => throttler.ready().await,
+                None => None,
+            };
+
             let (socket, peer_addr) = match self.inner.accept().await {
                 Ok(stream) => stream,
                 Err(err) => {
@@ -280,6 +445,8 @@ impl TcpListener {
                 }
             };
 
+            self.apply_stream_options(&socket);
+
             let service = service.clone();
             let mut ctx = ctx.clone();
 
@@ -287,6 +454,7 @@ impl TcpListener {
             let trace_local_addr = local_addr
                 .map(Into::into)
                 .unwrap_or_else(|| SocketAddress::default_ipv4(0));
+            let socket_info = self.socket_info(&socket, local_addr, peer_addr);
 
             let span = trace_root_span!(
                 "tcp::serve",
@@ -300,7 +468,8 @@ impl TcpListener {
 
             tokio::spawn(
                 async move {
-                    ctx.insert(SocketInfo::new(local_addr, peer_addr));
+                    let _permit = permit;
+                    ctx.insert(socket_info);
 
                     let _ = service.serve(ctx, socket).await;
                 }
@@ -321,8 +490,20 @@ impl TcpListener {
         let ctx: Context = Context::new(Executor::graceful(guard.clone()));
         let service = Arc::new(service);
         let mut cancelled_fut = pin!(guard.cancelled());
+        let mut throttler = self.accept_throttle.clone().map(AcceptThrottler::new);
 
         loop {
+            let permit = match throttler.as_mut() {
+                Some(throttler) => tokio::select! {
+                    _ = cancelled_fut.as_mut() => {
+                        tracing::trace!("signal received while throttled: initiate graceful shutdown");
+                        break;
+                    }
+                    permit = throttler.ready() => permit,
+                },
+                None => None,
+            };
+
             tokio::select! {
                 _ = cancelled_fut.as_mut() => {
                     tracing::trace!("signal received: initiate graceful shutdown");
@@ -331,6 +512,8 @@ impl TcpListener {
                 result = self.inner.accept() => {
                     match result {
                         Ok((socket, peer_addr)) => {
+                            self.apply_stream_options(&socket);
+
                             let service = service.clone();
                             let mut ctx = ctx.clone();
 
@@ -338,6 +521,7 @@ impl TcpListener {
                             let trace_local_addr = local_addr
                                 .map(Into::into)
                                 .unwrap_or_else(|| SocketAddress::default_ipv4(0));
+                            let socket_info = self.socket_info(&socket, local_addr, peer_addr);
 
                             let span = trace_root_span!(
                                 "tcp::serve_graceful",
@@ -350,7 +534,8 @@ impl TcpListener {
                             );
 
                             guard.spawn_task(async move {
-                                ctx.insert(SocketInfo::new(local_addr, peer_addr));
+                                let _permit = permit;
+                                ctx.insert(socket_info);
                                 let _ = service.serve(ctx, socket).await;
                             }.instrument(span));
                         }
@@ -364,7 +549,7 @@ impl TcpListener {
     }
 }
 
-async fn handle_accept_err(err: io::Error) {
+pub(super) async fn handle_accept_err(err: io::Error) {
     if rama_net::conn::is_connection_error(&err) {
         tracing::trace!("TCP accept error: connect error: {err:?}");
     } else {
@@ -383,3 +568,53 @@ async fn handle_accept_err(err: io::Error) {
         tokio::time::sleep(std::time::Duration::from_secs(1)).await;
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rama_net::socket::opts::TcpKeepAlive;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_accepted_stream_options() {
+        let listener = TcpListener::build()
+            .with_stream_options(TcpStreamOptions {
+                keep_alive: Some(TcpKeepAlive {
+                    time: Some(Duration::from_secs(30)),
+                    ..Default::default()
+                }),
+                no_delay: Some(true),
+                ..Default::default()
+            })
+            .bind_address("127.0.0.1:0")
+            .await
+            .unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        let _client = TcpStream::connect(addr).await.unwrap();
+        let (stream, _) = listener.accept().await.unwrap();
+
+        let socket = SockRef::from(&stream);
+        assert!(socket.keepalive().unwrap());
+        assert!(socket.tcp_nodelay().unwrap());
+    }
+
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn test_multipath_listener() {
+        let listener = TcpListener::build()
+            .with_multipath(true)
+            .bind_address("127.0.0.1:0")
+            .await
+            .unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        // the listener falls back to plain TCP in case MPTCP is not available,
+        // while connections of plain TCP clients are accepted regardless
+        let client = TcpStream::connect(addr).await.unwrap();
+        assert!(!crate::mptcp::is_multipath(&client));
+        let (stream, peer_addr) = listener.accept().await.unwrap();
+        let info = listener.socket_info(&stream, stream.local_addr().ok(), peer_addr.into());
+        assert_eq!(info.peer_addr(), &client.local_addr().unwrap());
+    }
+}
@@ -2,6 +2,9 @@
 //!
 //! The TCP server is used to create a [`TcpListener`] and accept incoming connections.
 //!
+//! On Linux the `io-uring` feature provides an `UringTcpListener` as an alternative,
+//! of which the accepted streams are driven by io_uring.
+//!
 //! # Example
 //!
 //! ```no_run
@@ -41,3 +44,13 @@
 mod listener;
 #[doc(inline)]
 pub use listener::{TcpListener, TcpListenerBuilder};
+
+mod throttle;
+#[doc(inline)]
+pub use throttle::AcceptThrottle;
+
+#[cfg(all(feature = "io-uring", target_os = "linux"))]
+mod uring;
+#[cfg(all(feature = "io-uring", target_os = "linux"))]
+#[doc(inline)]
+pub use uring::{UringTcpListener, UringTcpStream};
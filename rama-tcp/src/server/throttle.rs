@@ -0,0 +1,277 @@
+use rama_core::telemetry::tracing;
+use std::{
+    fmt,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+#[derive(Debug, Clone)]
+/// Throttle the accepting of new connections by a [`TcpListener`] while under resource pressure,
+/// such that it degrades gracefully instead of running into an `EMFILE` (too many open files) storm.
+///
+/// - Accepting is paused for as long as the maximum amount of in-flight connections
+///   (connections accepted and still being served) is reached;
+/// - Accepting is slowed down, by waiting for the configured delay prior to each accept,
+///   for as long as the process uses more than the configured fraction of its open file limit,
+///   or more resident memory than configured. These resources are only checked on Linux,
+///   and at most once per check interval.
+///
+/// A warning is logged each time the listener starts to be throttled.
+///
+/// [`TcpListener`]: super::TcpListener
+pub struct AcceptThrottle {
+    max_in_flight: Option<usize>,
+    max_open_files_ratio: Option<f64>,
+    max_memory: Option<u64>,
+    delay: Duration,
+    check_interval: Duration,
+}
+
+impl Default for AcceptThrottle {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl AcceptThrottle {
+    /// Create a new [`AcceptThrottle`], without any thresholds,
+    /// delaying accepts under pressure by 100ms and checking resources once per second.
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            max_in_flight: None,
+            max_open_files_ratio: None,
+            max_memory: None,
+            delay: Duration::from_millis(100),
+            check_interval: Duration::from_secs(1),
+        }
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Pause accepting connections while the given amount of accepted
+        /// connections is still being served.
+        pub fn max_in_flight(mut self, max: Option<usize>) -> Self {
+            self.max_in_flight = max;
+            self
+        }
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Slow down accepting connections while the process uses more than the given
+        /// fraction (e.g. `0.9`) of its (soft) open file limit. Only checked on Linux.
+        pub fn max_open_files_ratio(mut self, ratio: Option<f64>) -> Self {
+            self.max_open_files_ratio = ratio;
+            self
+        }
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Slow down accepting connections while the resident memory of the process
+        /// exceeds the given amount of bytes. Only checked on Linux.
+        pub fn max_memory(mut self, bytes: Option<u64>) -> Self {
+            self.max_memory = bytes;
+            self
+        }
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Set the delay to wait prior to accepting a connection while under resource pressure.
+        pub fn delay(mut self, delay: Duration) -> Self {
+            self.delay = delay;
+            self
+        }
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Set the interval at which the open files and memory usage are checked.
+        pub fn check_interval(mut self, interval: Duration) -> Self {
+            self.check_interval = interval;
+            self
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+enum ResourcePressure {
+    OpenFiles { open: u64, limit: u64 },
+    Memory { resident: u64 },
+}
+
+impl fmt::Display for ResourcePressure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::OpenFiles { open, limit } => write!(f, "{open} of {limit} files open"),
+            Self::Memory { resident } => write!(f, "{resident} bytes resident memory"),
+        }
+    }
+}
+
+/// The state of an [`AcceptThrottle`] for a single serve loop.
+pub(super) struct AcceptThrottler {
+    config: AcceptThrottle,
+    in_flight: Option<Arc<Semaphore>>,
+    last_check: Option<Instant>,
+    pressure: Option<ResourcePressure>,
+    throttled: bool,
+}
+
+impl AcceptThrottler {
+    pub(super) fn new(config: AcceptThrottle) -> Self {
+        Self {
+            in_flight: config
+                .max_in_flight
+                .map(|max| Arc::new(Semaphore::new(max))),
+            config,
+            last_check: None,
+            pressure: None,
+            throttled: false,
+        }
+    }
+
+    /// Wait until a new connection can be accepted, returning the permit
+    /// to hold while serving it in case the in-flight connections are limited.
+    pub(super) async fn ready(&mut self) -> Option<OwnedSemaphorePermit> {
+        if let Some(pressure) = self.check_pressure() {
+            if !self.throttled {
+                tracing::warn!(
+                    "TCP listener under resource pressure ({pressure}): slow down accepting connections"
+                );
+                self.throttled = true;
+            }
+            tokio::time::sleep(self.config.delay).await;
+        } else if self.throttled {
+            tracing::info!(
+                "TCP listener no longer under resource pressure: resume accepting connections"
+            );
+            self.throttled = false;
+        }
+
+        let in_flight = self.in_flight.clone()?;
+        match in_flight.clone().try_acquire_owned() {
+            Ok(permit) => Some(permit),
+            Err(_) => {
+                tracing::warn!(
+                    "TCP listener reached max in-flight connections ({}): pause accepting connections",
+                    self.config.max_in_flight.unwrap_or_default(),
+                );
+                in_flight.acquire_owned().await.ok()
+            }
+        }
+    }
+
+    fn check_pressure(&mut self) -> Option<ResourcePressure> {
+        if self.config.max_open_files_ratio.is_none() && self.config.max_memory.is_none() {
+            return None;
+        }
+        let now = Instant::now();
+        if self
+            .last_check
+            .is_none_or(|last| now.duration_since(last) >= self.config.check_interval)
+        {
+            self.last_check = Some(now);
+            self.pressure = self.measure_pressure();
+        }
+        self.pressure
+    }
+
+    #[cfg(target_os = "linux")]
+    fn measure_pressure(&self) -> Option<ResourcePressure> {
+        if let Some(ratio) = self.config.max_open_files_ratio
+            && let Some((open, limit)) = linux::open_files()
+            && open as f64 >= limit as f64 * ratio
+        {
+            return Some(ResourcePressure::OpenFiles { open, limit });
+        }
+        if let Some(max) = self.config.max_memory
+            && let Some(resident) = linux::resident_memory()
+            && resident >= max
+        {
+            return Some(ResourcePressure::Memory { resident });
+        }
+        None
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    #[allow(clippy::unused_self)]
+    fn measure_pressure(&self) -> Option<ResourcePressure> {
+        None
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod linux {
+    use rama_core::telemetry::tracing;
+
+    /// Returns the amount of open files of this process and its (soft) limit.
+    pub(super) fn open_files() -> Option<(u64, u64)> {
+        let open = match std::fs::read_dir("/proc/self/fd") {
+            Ok(dir) => dir.count() as u64,
+            Err(err) => {
+                tracing::debug!("failed to count open files of process: {err:?}");
+                return None;
+            }
+        };
+        let limits = std::fs::read_to_string("/proc/self/limits").ok()?;
+        let limit = limits
+            .lines()
+            .find_map(|line| line.strip_prefix("Max open files"))?
+            .split_whitespace()
+            .next()?
+            .parse()
+            .ok()?;
+        Some((open, limit))
+    }
+
+    /// Returns the resident memory of this process, in bytes.
+    pub(super) fn resident_memory() -> Option<u64> {
+        let status = std::fs::read_to_string("/proc/self/status").ok()?;
+        let kilobytes: u64 = status
+            .lines()
+            .find_map(|line| line.strip_prefix("VmRSS:"))?
+            .split_whitespace()
+            .next()?
+            .parse()
+            .ok()?;
+        Some(kilobytes * 1024)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_accept_throttle_max_in_flight() {
+        let mut throttler = AcceptThrottler::new(AcceptThrottle::new().with_max_in_flight(1));
+
+        let permit = throttler.ready().await.unwrap();
+        let mut next = std::pin::pin!(throttler.ready());
+        assert!(
+            tokio::time::timeout(Duration::from_millis(20), next.as_mut())
+                .await
+                .is_err()
+        );
+
+        drop(permit);
+        assert!(next.await.is_some());
+    }
+
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn test_accept_throttle_open_files_pressure() {
+        let mut throttler = AcceptThrottler::new(
+            AcceptThrottle::new()
+                .with_max_open_files_ratio(0.0)
+                .with_delay(Duration::from_millis(1)),
+        );
+
+        assert!(throttler.ready().await.is_none());
+        assert!(throttler.throttled);
+        assert!(matches!(
+            throttler.pressure,
+            Some(ResourcePressure::OpenFiles { .. })
+        ));
+    }
+}
@@ -0,0 +1,548 @@
+//! TCP listener backed by io_uring, see [`UringTcpListener`].
+
+use rama_core::Context;
+use rama_core::Service;
+use rama_core::bytes::{Bytes, BytesMut};
+use rama_core::error::{BoxError, ErrorContext};
+use rama_core::graceful::ShutdownGuard;
+use rama_core::rt::Executor;
+use rama_core::telemetry::tracing::{self, Instrument, trace_root_span};
+use rama_net::address::SocketAddress;
+use rama_net::socket::core::SockRef;
+use rama_net::stream::SocketInfo;
+use std::future::Future;
+use std::os::fd::{AsRawFd, BorrowedFd};
+use std::panic::AssertUnwindSafe;
+use std::pin::{Pin, pin};
+use std::rc::Rc;
+use std::sync::Arc;
+use std::task::{Context as TaskContext, Poll, ready};
+use std::{io, net::SocketAddr, thread};
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+use tokio::sync::{mpsc, oneshot};
+use tokio_util::sync::PollSender;
+use tokio_util::task::TaskTracker;
+
+use super::listener::handle_accept_err;
+
+/// The number of buffers which can be in flight between
+/// an [`UringTcpStream`] and the io_uring driver of its socket, per direction.
+const CHANNEL_CAPACITY: usize = 8;
+
+/// A TCP listener of which the accepted streams are driven by io_uring,
+/// reducing the number of syscalls at high connection counts.
+///
+/// Each worker thread runs a dedicated current-thread runtime,
+/// accepting connections from the shared listening socket.
+/// The io_uring sockets are neither `Send` nor `Sync`, and are therefore
+/// driven by a local task of that runtime, while the service is served
+/// an [`UringTcpStream`], implementing the regular [`Stream`] surface.
+///
+/// The worker threads are detached: the future returned by [`Self::serve`]
+/// (or [`Self::serve_graceful`]) only waits for them to finish, and can be
+/// awaited from any async runtime.
+///
+/// Only available on Linux, with a kernel supporting io_uring (5.11+).
+///
+/// [`Stream`]: rama_net::stream::Stream
+#[derive(Debug)]
+pub struct UringTcpListener {
+    inner: std::net::TcpListener,
+    workers: usize,
+    buffer_size: usize,
+}
+
+impl UringTcpListener {
+    /// The default size of the buffers used to read from accepted streams.
+    pub const DEFAULT_BUFFER_SIZE: usize = 16 * 1024;
+
+    /// Creates a new [`UringTcpListener`], which will be bound to the specified (socket) address.
+    ///
+    /// Binding with a port number of 0 will request that the OS assigns a port
+    /// to this listener. The port allocated can be queried via the `local_addr`
+    /// method.
+    pub fn bind_address<A: TryInto<SocketAddress, Error: Into<BoxError>>>(
+        addr: A,
+    ) -> Result<Self, BoxError> {
+        let socket_addr: SocketAddr = addr.try_into().map_err(Into::<BoxError>::into)?.into();
+        let listener = std::net::TcpListener::bind(socket_addr).context("bind tcp listener")?;
+        Self::try_from(listener).map_err(Into::into)
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Set the number of worker threads, each running its own io_uring runtime.
+        ///
+        /// Defaults to the available parallelism of the system.
+        pub fn workers(mut self, workers: usize) -> Self {
+            self.workers = workers.max(1);
+            self
+        }
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Set the size of the buffers used to read from accepted streams.
+        ///
+        /// Defaults to [`Self::DEFAULT_BUFFER_SIZE`].
+        pub fn buffer_size(mut self, size: usize) -> Self {
+            self.buffer_size = size.max(1);
+            self
+        }
+    }
+
+    /// Returns the local address that this listener is bound to.
+    ///
+    /// This can be useful, for example, when binding to port 0 to figure out
+    /// which port was actually bound.
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.inner.local_addr()
+    }
+
+    /// Serve connections from this listener with the given service.
+    ///
+    /// The connections are served by detached worker threads, which keep serving
+    /// when the returned future is dropped. Use [`Self::serve_graceful`]
+    /// in case the workers have to be stopped.
+    pub async fn serve<S>(self, service: S) -> io::Result<()>
+    where
+        S: Service<UringTcpStream>,
+    {
+        let workers = self.spawn_workers(None, service)?;
+        wait_for_workers(workers).await
+    }
+
+    /// Serve gracefully connections from this listener with the given service.
+    ///
+    /// This method does the same as [`Self::serve`] but it
+    /// will respect the given [`ShutdownGuard`], and also pass
+    /// it to the service. The worker threads stop accepting connections
+    /// once shutdown is initiated, and return when their connections are closed.
+    pub async fn serve_graceful<S>(self, guard: ShutdownGuard, service: S) -> io::Result<()>
+    where
+        S: Service<UringTcpStream>,
+    {
+        let workers = self.spawn_workers(Some(guard), service)?;
+        wait_for_workers(workers).await
+    }
+
+    /// Spawn the detached worker threads, returning a receiver per worker
+    /// which resolves to `false` in case the worker panicked.
+    fn spawn_workers<S>(
+        self,
+        guard: Option<ShutdownGuard>,
+        service: S,
+    ) -> io::Result<Vec<oneshot::Receiver<bool>>>
+    where
+        S: Service<UringTcpStream>,
+    {
+        let local_addr = self.inner.local_addr()?;
+        let service = Arc::new(service);
+
+        let mut workers = Vec::with_capacity(self.workers);
+        for index in 0..self.workers {
+            let listener = self.inner.try_clone()?;
+            let guard = guard.clone();
+            let service = service.clone();
+            let buffer_size = self.buffer_size;
+            let (done_tx, done_rx) = oneshot::channel();
+            thread::Builder::new()
+                .name(format!("rama-tcp-uring-{index}"))
+                .spawn(move || {
+                    let result = std::panic::catch_unwind(AssertUnwindSafe(|| {
+                        tokio_uring::start(serve_worker(
+                            listener,
+                            local_addr,
+                            buffer_size,
+                            guard,
+                            service,
+                        ));
+                    }));
+                    let _ = done_tx.send(result.is_ok());
+                })?;
+            workers.push(done_rx);
+        }
+        Ok(workers)
+    }
+}
+
+async fn wait_for_workers(workers: Vec<oneshot::Receiver<bool>>) -> io::Result<()> {
+    let mut panicked = false;
+    for worker in workers {
+        panicked |= !worker.await.unwrap_or(false);
+    }
+    if panicked {
+        return Err(io::Error::other("io_uring worker thread panicked"));
+    }
+    Ok(())
+}
+
+impl TryFrom<std::net::TcpListener> for UringTcpListener {
+    type Error = io::Error;
+
+    fn try_from(value: std::net::TcpListener) -> Result<Self, Self::Error> {
+        value.set_nonblocking(true)?;
+        Ok(Self {
+            inner: value,
+            workers: thread::available_parallelism().map_or(1, usize::from),
+            buffer_size: Self::DEFAULT_BUFFER_SIZE,
+        })
+    }
+}
+
+async fn serve_worker<S>(
+    listener: std::net::TcpListener,
+    local_addr: SocketAddr,
+    buffer_size: usize,
+    guard: Option<ShutdownGuard>,
+    service: Arc<S>,
+) where
+    S: Service<UringTcpStream>,
+{
+    let listener = tokio_uring::net::TcpListener::from_std(listener);
+    let ctx = Context::new(match &guard {
+        Some(guard) => Executor::graceful(guard.clone()),
+        None => Executor::new(),
+    });
+    // connections are tracked such that the runtime of this worker
+    // is only dropped once they are closed, after a graceful shutdown
+    let tracker = TaskTracker::new();
+    let listener_addr: SocketAddress = local_addr.into();
+    let mut cancelled_fut = pin!(async {
+        match &guard {
+            Some(guard) => guard.cancelled().await,
+            None => std::future::pending().await,
+        }
+    });
+
+    loop {
+        let (socket, peer_addr) = tokio::select! {
+            _ = cancelled_fut.as_mut() => {
+                tracing::trace!("signal received: initiate graceful shutdown");
+                break;
+            }
+            result = listener.accept() => match result {
+                Ok(accepted) => accepted,
+                Err(err) => {
+                    handle_accept_err(err).await;
+                    continue;
+                }
+            },
+        };
+
+        let local_addr = socket_local_addr(&socket);
+        let trace_local_addr = local_addr.map(Into::into).unwrap_or(listener_addr);
+
+        let (stream, driver) = UringTcpStream::new(socket, buffer_size);
+        tokio_uring::spawn(tracker.track_future(driver));
+
+        let service = service.clone();
+        let mut ctx = ctx.clone();
+        let span = trace_root_span!(
+            "tcp::serve_uring",
+            otel.kind = "server",
+            network.local.port = %trace_local_addr.port(),
+            network.local.address = %trace_local_addr.ip_addr(),
+            network.peer.port = %peer_addr.port(),
+            network.peer.address = %peer_addr.ip(),
+            network.protocol.name = "tcp",
+        );
+
+        tracker.spawn(
+            async move {
+                ctx.insert(SocketInfo::new(local_addr, peer_addr));
+                let _ = service.serve(ctx, stream).await;
+            }
+            .instrument(span),
+        );
+    }
+
+    tracker.close();
+    tracker.wait().await;
+}
+
+/// Returns the local address of an accepted io_uring socket,
+/// which the io_uring socket itself does not expose.
+fn socket_local_addr(socket: &tokio_uring::net::TcpStream) -> Option<SocketAddr> {
+    // SAFETY: the file descriptor is owned by the socket, which outlives this borrow
+    let fd = unsafe { BorrowedFd::borrow_raw(socket.as_raw_fd()) };
+    SockRef::from(&fd).local_addr().ok()?.as_socket()
+}
+
+/// A stream accepted by an [`UringTcpListener`].
+///
+/// The io_uring socket itself is driven by a local task of the worker runtime,
+/// exchanging owned buffers with this stream, such that it can be served
+/// by any service accepting a [`Stream`].
+///
+/// The buffers are handed over without copying, and their allocations are
+/// reclaimed by their owner once the other side is done with them,
+/// such that no buffer is allocated per read or write in the steady state.
+///
+/// Writes are handed over to the driver in order, and are flushed by it
+/// as soon as possible, which is why flushing this stream is a no-op.
+///
+/// [`Stream`]: rama_net::stream::Stream
+pub struct UringTcpStream {
+    reader: mpsc::Receiver<io::Result<Bytes>>,
+    pending: Bytes,
+    writer: PollSender<Bytes>,
+    write_buf: BytesMut,
+    buffer_size: usize,
+}
+
+impl std::fmt::Debug for UringTcpStream {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("UringTcpStream")
+            .field("pending", &self.pending.len())
+            .field("buffer_size", &self.buffer_size)
+            .field("closed", &self.writer.is_closed())
+            .finish()
+    }
+}
+
+impl UringTcpStream {
+    /// Create a new [`UringTcpStream`] for the given socket,
+    /// together with the (local) future driving the socket.
+    fn new(
+        socket: tokio_uring::net::TcpStream,
+        buffer_size: usize,
+    ) -> (Self, impl Future<Output = ()>) {
+        let (read_tx, read_rx) = mpsc::channel(CHANNEL_CAPACITY);
+        let (write_tx, write_rx) = mpsc::channel(CHANNEL_CAPACITY);
+        let stream = Self {
+            reader: read_rx,
+            pending: Bytes::new(),
+            writer: PollSender::new(write_tx),
+            write_buf: BytesMut::new(),
+            buffer_size,
+        };
+        (
+            stream,
+            drive(Rc::new(socket), buffer_size, read_tx, write_rx),
+        )
+    }
+}
+
+async fn drive(
+    socket: Rc<tokio_uring::net::TcpStream>,
+    buffer_size: usize,
+    read_tx: mpsc::Sender<io::Result<Bytes>>,
+    mut write_rx: mpsc::Receiver<Bytes>,
+) {
+    // moved into the read future, such that the stream sees EOF
+    // as soon as the peer closes its write half
+    let read_socket = socket.clone();
+    let read = async move {
+        let mut buf = BytesMut::with_capacity(buffer_size);
+        loop {
+            // reclaims the allocation of the previously read chunks once they are consumed
+            buf.reserve(buffer_size);
+            let (result, read_buf) = tokio::select! {
+                read = read_socket.read(buf) => read,
+                _ = read_tx.closed() => return,
+            };
+            buf = read_buf;
+            let item = match result {
+                Ok(0) => return,
+                Ok(_) => Ok(buf.split().freeze()),
+                Err(err) => Err(err),
+            };
+            let failed = item.is_err();
+            if read_tx.send(item).await.is_err() || failed {
+                return;
+            }
+        }
+    };
+
+    let write = async {
+        // buffered writes are still written once the stream is dropped
+        while let Some(buf) = write_rx.recv().await {
+            let (result, _) = socket.write_all(buf).await;
+            if let Err(err) = result {
+                tracing::debug!("failed to write to io_uring tcp stream: {err:?}");
+                return;
+            }
+        }
+        if let Err(err) = socket.shutdown(std::net::Shutdown::Write) {
+            tracing::trace!("failed to shutdown io_uring tcp stream: {err:?}");
+        }
+    };
+
+    tokio::join!(read, write);
+}
+
+impl AsyncRead for UringTcpStream {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut TaskContext<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        if self.pending.is_empty() {
+            match ready!(self.reader.poll_recv(cx)) {
+                Some(Ok(data)) => self.pending = data,
+                Some(Err(err)) => return Poll::Ready(Err(err)),
+                None => return Poll::Ready(Ok(())),
+            }
+        }
+        let n = self.pending.len().min(buf.remaining());
+        buf.put_slice(&self.pending.split_to(n));
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl AsyncWrite for UringTcpStream {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut TaskContext<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = &mut *self;
+        ready!(this.writer.poll_reserve(cx)).map_err(|_| io::ErrorKind::BrokenPipe)?;
+        let n = buf.len().min(this.buffer_size);
+        // reclaims the allocation of the previously written chunks once the driver is done with them
+        this.write_buf.reserve(n);
+        this.write_buf.extend_from_slice(&buf[..n]);
+        this.writer
+            .send_item(this.write_buf.split().freeze())
+            .map_err(|_| io::ErrorKind::BrokenPipe)?;
+        Poll::Ready(Ok(n))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut TaskContext<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, _cx: &mut TaskContext<'_>) -> Poll<io::Result<()>> {
+        self.writer.close();
+        Poll::Ready(Ok(()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rama_core::graceful::Shutdown;
+    use rama_core::service::service_fn;
+    use std::time::Duration;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpStream;
+
+    #[tokio::test]
+    async fn test_uring_echo() {
+        let listener = UringTcpListener::bind_address("127.0.0.1:0")
+            .unwrap()
+            .with_workers(1);
+        let addr = listener.local_addr().unwrap();
+
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        tokio::spawn(listener.serve(service_fn(
+            move |ctx: Context, mut stream: UringTcpStream| {
+                let tx = tx.clone();
+                async move {
+                    let _ = tx.send(ctx.get::<SocketInfo>().unwrap().local_addr().copied());
+                    let mut buf = [0; 5];
+                    stream.read_exact(&mut buf).await?;
+                    stream.write_all(&buf).await?;
+                    stream.shutdown().await?;
+                    Ok::<_, io::Error>(())
+                }
+            },
+        )));
+
+        let mut client = TcpStream::connect(addr).await.unwrap();
+        client.write_all(b"hello").await.unwrap();
+        let mut echoed = String::new();
+        client.read_to_string(&mut echoed).await.unwrap();
+        assert_eq!(echoed, "hello");
+        assert_eq!(rx.recv().await.unwrap(), Some(addr));
+    }
+
+    #[tokio::test]
+    async fn test_uring_peer_half_close() {
+        let listener = UringTcpListener::bind_address("127.0.0.1:0")
+            .unwrap()
+            .with_workers(1);
+        let addr = listener.local_addr().unwrap();
+
+        tokio::spawn(
+            listener.serve(service_fn(async |mut stream: UringTcpStream| {
+                let mut data = Vec::new();
+                stream.read_to_end(&mut data).await?;
+                stream.write_all(b"received: ").await?;
+                stream.write_all(&data).await?;
+                stream.shutdown().await?;
+                Ok::<_, io::Error>(())
+            })),
+        );
+
+        let mut client = TcpStream::connect(addr).await.unwrap();
+        client.write_all(b"hello").await.unwrap();
+        client.shutdown().await.unwrap();
+        let mut response = String::new();
+        client.read_to_string(&mut response).await.unwrap();
+        assert_eq!(response, "received: hello");
+    }
+
+    #[tokio::test]
+    async fn test_uring_large_write() {
+        const SIZE: usize = 1024 * 1024;
+
+        let listener = UringTcpListener::bind_address("127.0.0.1:0")
+            .unwrap()
+            .with_workers(1)
+            .with_buffer_size(1024);
+        let addr = listener.local_addr().unwrap();
+
+        let data: Vec<u8> = (0..SIZE).map(|i| (i % 251) as u8).collect();
+        let expected = data.clone();
+        tokio::spawn(
+            listener.serve(service_fn(move |mut stream: UringTcpStream| {
+                let data = data.clone();
+                async move {
+                    // far exceeds the buffers which can be in flight to the driver
+                    stream.write_all(&data).await?;
+                    stream.shutdown().await?;
+                    Ok::<_, io::Error>(())
+                }
+            })),
+        );
+
+        let mut client = TcpStream::connect(addr).await.unwrap();
+        let mut received = Vec::new();
+        client.read_to_end(&mut received).await.unwrap();
+        assert_eq!(received.len(), SIZE);
+        assert!(received == expected);
+    }
+
+    #[tokio::test]
+    async fn test_uring_graceful_shutdown() {
+        let listener = UringTcpListener::bind_address("127.0.0.1:0")
+            .unwrap()
+            .with_workers(2);
+        let addr = listener.local_addr().unwrap();
+
+        let shutdown = Shutdown::default();
+        let server = tokio::spawn(listener.serve_graceful(
+            shutdown.guard(),
+            service_fn(async |mut stream: UringTcpStream| {
+                stream.write_all(b"bye").await?;
+                stream.shutdown().await?;
+                Ok::<_, io::Error>(())
+            }),
+        ));
+
+        let mut client = TcpStream::connect(addr).await.unwrap();
+        let mut response = String::new();
+        client.read_to_string(&mut response).await.unwrap();
+        assert_eq!(response, "bye");
+
+        shutdown
+            .shutdown_with_limit(Duration::from_secs(5))
+            .await
+            .unwrap();
+        tokio::time::timeout(Duration::from_secs(5), server)
+            .await
+            .unwrap()
+            .unwrap()
+            .unwrap();
+    }
+}
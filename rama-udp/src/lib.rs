@@ -20,6 +20,10 @@
 mod socket;
 pub use socket::UdpSocket;
 
+mod session;
+#[doc(inline)]
+pub use session::{UdpFlow, UdpSession, UdpSessionLimitReached, UdpSessionStatus, UdpSessionTable};
+
 #[doc(inline)]
 pub use tokio_util::udp::UdpFramed;
 
@@ -0,0 +1,485 @@
+use rama_net::address::SocketAddress;
+use std::{
+    collections::HashMap,
+    fmt,
+    time::{Duration, Instant},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+/// The 5-tuple identifying a UDP flow: the (implicit) UDP protocol,
+/// together with the address and port of both its source and destination.
+pub struct UdpFlow {
+    source: SocketAddress,
+    destination: SocketAddress,
+}
+
+impl UdpFlow {
+    /// Create a new [`UdpFlow`] for the datagrams sent from `source` to `destination`.
+    #[must_use]
+    pub const fn new(source: SocketAddress, destination: SocketAddress) -> Self {
+        Self {
+            source,
+            destination,
+        }
+    }
+
+    /// Returns the address the datagrams of this flow are sent from.
+    #[must_use]
+    pub fn source(&self) -> SocketAddress {
+        self.source
+    }
+
+    /// Returns the address the datagrams of this flow are sent to.
+    #[must_use]
+    pub fn destination(&self) -> SocketAddress {
+        self.destination
+    }
+
+    /// Returns the [`UdpFlow`] of the replies to this flow.
+    #[must_use]
+    pub fn reversed(&self) -> Self {
+        Self::new(self.destination, self.source)
+    }
+}
+
+impl fmt::Display for UdpFlow {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "udp {} -> {}", self.source, self.destination)
+    }
+}
+
+#[derive(Debug)]
+/// A session tracked by a [`UdpSessionTable`], holding the state of a single [`UdpFlow`].
+pub struct UdpSession<T> {
+    flow: UdpFlow,
+    state: T,
+    created_at: Instant,
+    last_activity: Instant,
+    rebinds: usize,
+}
+
+impl<T> UdpSession<T> {
+    /// Returns the [`UdpFlow`] of this session.
+    ///
+    /// Its source changes when the client got rebound to a different port.
+    #[must_use]
+    pub fn flow(&self) -> UdpFlow {
+        self.flow
+    }
+
+    /// Returns a reference to the state of this session.
+    #[must_use]
+    pub fn state(&self) -> &T {
+        &self.state
+    }
+
+    /// Returns a mutable reference to the state of this session.
+    #[must_use]
+    pub fn state_mut(&mut self) -> &mut T {
+        &mut self.state
+    }
+
+    /// Consume the session, returning its state.
+    #[must_use]
+    pub fn into_state(self) -> T {
+        self.state
+    }
+
+    /// Returns the moment the session was created.
+    #[must_use]
+    pub fn created_at(&self) -> Instant {
+        self.created_at
+    }
+
+    /// Returns the moment a datagram was last tracked for this session, in either direction.
+    #[must_use]
+    pub fn last_activity(&self) -> Instant {
+        self.last_activity
+    }
+
+    /// Returns how long no datagram was tracked for this session.
+    #[must_use]
+    pub fn idle_duration(&self) -> Duration {
+        self.last_activity.elapsed()
+    }
+
+    /// Returns how many times the source of this session was rebound to a different port.
+    #[must_use]
+    pub fn rebind_count(&self) -> usize {
+        self.rebinds
+    }
+
+    fn is_expired(&self, now: Instant, idle_timeout: Duration) -> bool {
+        now.saturating_duration_since(self.last_activity) >= idle_timeout
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// The outcome of tracking a datagram using [`UdpSessionTable::track`].
+pub enum UdpSessionStatus {
+    /// A new session was created for the flow.
+    New,
+    /// The datagram belongs to an existing session.
+    Existing,
+    /// The datagram belongs to an existing session of which the source port changed,
+    /// e.g. because a NAT in front of the client rebound it.
+    Rebound {
+        /// The source address of the session prior to the rebinding.
+        previous_source: SocketAddress,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Error returned by [`UdpSessionTable::track`] in case a new session was required
+/// while the table already tracks the maximum amount of (active) sessions.
+pub struct UdpSessionLimitReached {
+    max_sessions: usize,
+}
+
+impl UdpSessionLimitReached {
+    /// Returns the maximum amount of sessions of the table.
+    #[must_use]
+    pub fn max_sessions(&self) -> usize {
+        self.max_sessions
+    }
+}
+
+impl fmt::Display for UdpSessionLimitReached {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "udp session limit reached: max {} sessions",
+            self.max_sessions
+        )
+    }
+}
+
+impl std::error::Error for UdpSessionLimitReached {}
+
+#[derive(Debug)]
+/// A NAT-like table tracking the sessions of a datagram proxy, one per [`UdpFlow`].
+///
+/// Outgoing datagrams are tracked using [`UdpSessionTable::track`], creating a session
+/// for new flows, while replies are matched to their session using [`UdpSessionTable::track_reply`],
+/// such that unsolicited datagrams can be dropped. Sessions expire once idle for longer
+/// than the idle timeout, and no new sessions are created beyond the maximum amount of sessions.
+///
+/// A datagram sent from the same ip address as an active session to the same destination,
+/// but from a different port, is detected as a rebinding of that session
+/// (e.g. by a NAT in front of the client), moving the session to the new source address.
+pub struct UdpSessionTable<T> {
+    sessions: HashMap<UdpFlow, UdpSession<T>>,
+    sources: HashMap<SocketAddress, Vec<SocketAddress>>,
+    idle_timeout: Duration,
+    max_sessions: usize,
+}
+
+impl<T> Default for UdpSessionTable<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> UdpSessionTable<T> {
+    /// The default idle timeout of a session,
+    /// matching the minimum UDP mapping timeout of RFC 4787.
+    pub const DEFAULT_IDLE_TIMEOUT: Duration = Duration::from_secs(120);
+
+    /// The default maximum amount of sessions.
+    pub const DEFAULT_MAX_SESSIONS: usize = 1024;
+
+    /// Create a new [`UdpSessionTable`], using the default idle timeout and maximum amount of sessions.
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            sessions: HashMap::new(),
+            sources: HashMap::new(),
+            idle_timeout: Self::DEFAULT_IDLE_TIMEOUT,
+            max_sessions: Self::DEFAULT_MAX_SESSIONS,
+        }
+    }
+
+    /// Returns the duration after which an idle session expires.
+    #[must_use]
+    pub fn idle_timeout(&self) -> Duration {
+        self.idle_timeout
+    }
+
+    /// Set the duration after which an idle session expires.
+    pub fn set_idle_timeout(&mut self, timeout: Duration) -> &mut Self {
+        self.idle_timeout = timeout;
+        self
+    }
+
+    /// Set the duration after which an idle session expires.
+    #[must_use]
+    pub fn with_idle_timeout(mut self, timeout: Duration) -> Self {
+        self.idle_timeout = timeout;
+        self
+    }
+
+    /// Returns the maximum amount of sessions tracked at once.
+    #[must_use]
+    pub fn max_sessions(&self) -> usize {
+        self.max_sessions
+    }
+
+    /// Set the maximum amount of sessions tracked at once.
+    pub fn set_max_sessions(&mut self, max: usize) -> &mut Self {
+        self.max_sessions = max;
+        self
+    }
+
+    /// Set the maximum amount of sessions tracked at once.
+    #[must_use]
+    pub fn with_max_sessions(mut self, max: usize) -> Self {
+        self.max_sessions = max;
+        self
+    }
+
+    /// Returns the amount of sessions tracked, including those expired but not yet evicted.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.sessions.len()
+    }
+
+    /// Returns `true` in case no sessions are tracked.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.sessions.is_empty()
+    }
+
+    /// Returns the active session of the given [`UdpFlow`], if any.
+    #[must_use]
+    pub fn get(&self, flow: &UdpFlow) -> Option<&UdpSession<T>> {
+        let now = Instant::now();
+        self.sessions
+            .get(flow)
+            .filter(|session| !session.is_expired(now, self.idle_timeout))
+    }
+
+    /// Returns an iterator over all active sessions.
+    pub fn iter(&self) -> impl Iterator<Item = &UdpSession<T>> {
+        let now = Instant::now();
+        self.sessions
+            .values()
+            .filter(move |session| !session.is_expired(now, self.idle_timeout))
+    }
+
+    /// Returns `true` in case tracking the given [`UdpFlow`] would rebind an active session.
+    #[must_use]
+    pub fn is_rebinding(&self, flow: &UdpFlow) -> bool {
+        !self.sessions.contains_key(flow) && self.rebind_candidate(flow, Instant::now()).is_some()
+    }
+
+    /// Track a datagram sent for the given [`UdpFlow`], returning its (refreshed) session.
+    ///
+    /// A new session is created, with the state returned by `create`, in case
+    /// the flow has no active session and is not a rebinding of another active session.
+    pub fn track(
+        &mut self,
+        flow: UdpFlow,
+        create: impl FnOnce() -> T,
+    ) -> Result<(UdpSessionStatus, &mut UdpSession<T>), UdpSessionLimitReached> {
+        self.track_at(flow, Instant::now(), create)
+    }
+
+    /// Track a reply datagram received from the given address,
+    /// returning the most recently active session sending to that address, if any.
+    pub fn track_reply(&mut self, from: SocketAddress) -> Option<&mut UdpSession<T>> {
+        self.track_reply_at(from, Instant::now())
+    }
+
+    /// Remove the session of the given [`UdpFlow`], returning it if it existed.
+    pub fn remove(&mut self, flow: &UdpFlow) -> Option<UdpSession<T>> {
+        let session = self.sessions.remove(flow)?;
+        if let Some(sources) = self.sources.get_mut(&flow.destination) {
+            sources.retain(|source| *source != flow.source);
+            if sources.is_empty() {
+                self.sources.remove(&flow.destination);
+            }
+        }
+        Some(session)
+    }
+
+    /// Remove all expired sessions, returning how many were removed.
+    pub fn evict_expired(&mut self) -> usize {
+        self.evict_expired_at(Instant::now())
+    }
+
+    fn track_at(
+        &mut self,
+        flow: UdpFlow,
+        now: Instant,
+        create: impl FnOnce() -> T,
+    ) -> Result<(UdpSessionStatus, &mut UdpSession<T>), UdpSessionLimitReached> {
+        if self
+            .sessions
+            .get(&flow)
+            .is_some_and(|session| session.is_expired(now, self.idle_timeout))
+        {
+            self.remove(&flow);
+        }
+
+        if self.sessions.contains_key(&flow) {
+            let session = self
+                .sessions
+                .get_mut(&flow)
+                .expect("udp session to exist for tracked flow");
+            session.last_activity = now;
+            return Ok((UdpSessionStatus::Existing, session));
+        }
+
+        if let Some(previous_source) = self.rebind_candidate(&flow, now)
+            && let Some(mut session) = self.remove(&UdpFlow::new(previous_source, flow.destination))
+        {
+            session.flow = flow;
+            session.last_activity = now;
+            session.rebinds += 1;
+            return Ok((
+                UdpSessionStatus::Rebound { previous_source },
+                self.insert(session),
+            ));
+        }
+
+        if self.sessions.len() >= self.max_sessions
+            && (self.evict_expired_at(now) == 0 || self.sessions.len() >= self.max_sessions)
+        {
+            return Err(UdpSessionLimitReached {
+                max_sessions: self.max_sessions,
+            });
+        }
+
+        let session = UdpSession {
+            flow,
+            state: create(),
+            created_at: now,
+            last_activity: now,
+            rebinds: 0,
+        };
+        Ok((UdpSessionStatus::New, self.insert(session)))
+    }
+
+    fn track_reply_at(&mut self, from: SocketAddress, now: Instant) -> Option<&mut UdpSession<T>> {
+        let flow = self
+            .sources
+            .get(&from)?
+            .iter()
+            .filter_map(|source| self.sessions.get(&UdpFlow::new(*source, from)))
+            .filter(|session| !session.is_expired(now, self.idle_timeout))
+            .max_by_key(|session| session.last_activity)?
+            .flow;
+        let session = self.sessions.get_mut(&flow)?;
+        session.last_activity = now;
+        Some(session)
+    }
+
+    fn evict_expired_at(&mut self, now: Instant) -> usize {
+        let expired: Vec<_> = self
+            .sessions
+            .values()
+            .filter(|session| session.is_expired(now, self.idle_timeout))
+            .map(|session| session.flow)
+            .collect();
+        for flow in &expired {
+            self.remove(flow);
+        }
+        expired.len()
+    }
+
+    fn rebind_candidate(&self, flow: &UdpFlow, now: Instant) -> Option<SocketAddress> {
+        self.sources
+            .get(&flow.destination)?
+            .iter()
+            .copied()
+            .filter(|source| {
+                source.ip_addr() == flow.source.ip_addr() && source.port() != flow.source.port()
+            })
+            .find(|source| {
+                self.sessions
+                    .get(&UdpFlow::new(*source, flow.destination))
+                    .is_some_and(|session| !session.is_expired(now, self.idle_timeout))
+            })
+    }
+
+    fn insert(&mut self, session: UdpSession<T>) -> &mut UdpSession<T> {
+        let flow = session.flow;
+        self.sources
+            .entry(flow.destination)
+            .or_default()
+            .push(flow.source);
+        self.sessions.entry(flow).insert_entry(session).into_mut()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn addr(s: &str) -> SocketAddress {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn test_udp_session_table() {
+        let mut table = UdpSessionTable::new()
+            .with_idle_timeout(Duration::from_secs(10))
+            .with_max_sessions(2);
+        let start = Instant::now();
+
+        let client = addr("10.0.0.1:5000");
+        let dns = addr("1.1.1.1:53");
+        let ntp = addr("2.2.2.2:123");
+
+        let (status, session) = table
+            .track_at(UdpFlow::new(client, dns), start, || 1)
+            .unwrap();
+        assert_eq!(status, UdpSessionStatus::New);
+        assert_eq!(*session.state(), 1);
+
+        let (status, _) = table
+            .track_at(UdpFlow::new(client, dns), start, || 2)
+            .unwrap();
+        assert_eq!(status, UdpSessionStatus::Existing);
+
+        table
+            .track_at(UdpFlow::new(client, ntp), start, || 3)
+            .unwrap();
+        let err = table
+            .track_at(UdpFlow::new(addr("10.0.0.2:5000"), dns), start, || 4)
+            .unwrap_err();
+        assert_eq!(err.max_sessions(), 2);
+
+        // unsolicited replies are not matched to any session
+        assert!(table.track_reply_at(addr("3.3.3.3:53"), start).is_none());
+        let session = table.track_reply_at(dns, start).unwrap();
+        assert_eq!(session.flow(), UdpFlow::new(client, dns));
+
+        // the client got rebound to a different port
+        let rebound = addr("10.0.0.1:6000");
+        let (status, session) = table
+            .track_at(UdpFlow::new(rebound, dns), start, || 5)
+            .unwrap();
+        assert_eq!(
+            status,
+            UdpSessionStatus::Rebound {
+                previous_source: client
+            }
+        );
+        assert_eq!(*session.state(), 1);
+        assert_eq!(session.rebind_count(), 1);
+        assert_eq!(
+            table.track_reply_at(dns, start).unwrap().flow().source(),
+            rebound
+        );
+
+        // the ntp session expires, making room for a new session
+        let later = start + Duration::from_secs(11);
+        assert!(table.track_reply_at(ntp, later).is_none());
+        let (status, _) = table
+            .track_at(UdpFlow::new(addr("10.0.0.2:5000"), dns), later, || 6)
+            .unwrap();
+        assert_eq!(status, UdpSessionStatus::New);
+        assert_eq!(table.len(), 1);
+    }
+}
@@ -1,14 +1,16 @@
 //! WebSocket server types and utilities
 
 use std::{
+    convert::Infallible,
     fmt,
     ops::{Deref, DerefMut},
+    sync::Arc,
 };
 
 use rama_core::{
     Context, Service,
     context::Extensions,
-    error::{ErrorContext, OpaqueError},
+    error::{BoxError, ErrorContext, OpaqueError},
     futures::{StreamExt, TryStreamExt},
     matcher::Matcher,
     telemetry::tracing::{self, Instrument},
@@ -947,6 +949,85 @@ impl Service<upgrade::Upgraded> for WebSocketEchoService {
     }
 }
 
+/// Create a [`WebSocketAcceptorService`], using a default [`WebSocketAcceptor`],
+/// which serves the upgraded [`ServerWebSocket`] using the given handler.
+///
+/// The handler receives the [`Context`] together with the socket,
+/// such that realtime endpoints can be served by a router alongside all other routes.
+/// An error returned by the handler is logged, as the response is already sent at that point.
+///
+/// # Example
+///
+/// ```
+/// use rama_core::{Context, error::{ErrorContext, OpaqueError}};
+/// use rama_http::service::web::Router;
+/// use rama_ws::handshake::server::{ServerWebSocket, websocket};
+///
+/// let router = Router::new().get(
+///     "/ws",
+///     websocket(async |_ctx: Context, mut ws: ServerWebSocket| {
+///         while let Ok(msg) = ws.recv_message().await {
+///             ws.send_message(msg).await.context("echo message")?;
+///         }
+///         Ok::<_, OpaqueError>(())
+///     }),
+/// );
+/// ```
+pub fn websocket<F, R, E>(handler: F) -> WebSocketAcceptorService<WebSocketHandler<F>>
+where
+    F: Fn(Context, ServerWebSocket) -> R + Send + Sync + 'static,
+    R: Future<Output = Result<(), E>> + Send + 'static,
+    E: Into<BoxError> + Send + 'static,
+{
+    WebSocketAcceptor::new().into_service(WebSocketHandler {
+        handler: Arc::new(handler),
+    })
+}
+
+/// The [`Service`] used by [`websocket`] to serve an upgraded [`ServerWebSocket`]
+/// using a handler function, logging the error it returns, if any.
+pub struct WebSocketHandler<F> {
+    handler: Arc<F>,
+}
+
+impl<F> fmt::Debug for WebSocketHandler<F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WebSocketHandler")
+            .field("handler", &std::any::type_name::<F>())
+            .finish()
+    }
+}
+
+impl<F> Clone for WebSocketHandler<F> {
+    fn clone(&self) -> Self {
+        Self {
+            handler: self.handler.clone(),
+        }
+    }
+}
+
+impl<F, R, E> Service<ServerWebSocket> for WebSocketHandler<F>
+where
+    F: Fn(Context, ServerWebSocket) -> R + Send + Sync + 'static,
+    R: Future<Output = Result<(), E>> + Send + 'static,
+    E: Into<BoxError> + Send + 'static,
+{
+    type Response = ();
+    type Error = Infallible;
+
+    async fn serve(
+        &self,
+        ctx: Context,
+        socket: ServerWebSocket,
+    ) -> Result<Self::Response, Self::Error> {
+        if let Err(err) = (self.handler)(ctx, socket).await {
+            let err = err.into();
+            tracing::debug!("WebSocketHandler: ws handler failed: {err}");
+        }
+        Ok(())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use headers::sec_websocket_protocol::AcceptedWebSocketProtocol;
@@ -1529,4 +1610,38 @@ mod tests {
         )
         .await;
     }
+
+    #[tokio::test]
+    async fn test_websocket_handler_route() {
+        let router = rama_http::service::web::Router::new().get(
+            "/ws",
+            websocket(async |_ctx: Context, _ws: ServerWebSocket| Ok::<_, OpaqueError>(())),
+        );
+
+        let resp = router
+            .serve(
+                Context::default(),
+                request! {
+                    "GET" "HTTP/1.1" "/ws"
+                    "Connection": "upgrade"
+                    "Upgrade": "websocket"
+                    "Sec-WebSocket-Version": "13"
+                    "Sec-WebSocket-Key": "dGhlIHNhbXBsZSBub25jZQ=="
+                },
+            )
+            .await
+            .unwrap();
+        assert_eq!(StatusCode::SWITCHING_PROTOCOLS, resp.status());
+
+        let resp = router
+            .serve(
+                Context::default(),
+                request! {
+                    "GET" "HTTP/1.1" "/ws"
+                },
+            )
+            .await
+            .unwrap();
+        assert_eq!(StatusCode::BAD_REQUEST, resp.status());
+    }
 }
@@ -75,3 +75,13 @@ impl FromStr for ForwardKind {
         s.try_into()
     }
 }
+
+impl<'de> serde::Deserialize<'de> for ForwardKind {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let s = <std::borrow::Cow<'de, str>>::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
@@ -6,6 +6,9 @@ pub mod args;
 #[cfg(all(feature = "http", feature = "net", feature = "haproxy"))]
 pub mod service;
 
+#[cfg(all(feature = "http-full", feature = "haproxy"))]
+pub mod config;
+
 mod forward;
 #[doc(inline)]
 pub use forward::ForwardKind;
@@ -8,7 +8,10 @@ use std::fmt;
 use crate::{
     Context, Service,
     error::{BoxError, ErrorContext, OpaqueError},
-    http::{Request, Response, dep::http_body},
+    http::{
+        Request, Response, StatusCode, dep::http_body,
+        layer::sanitize_headers::SanitizeHeadersLayer, service::web::response::IntoResponse,
+    },
     net::client::EstablishedClientConnection,
     service::BoxService,
     telemetry::tracing,
@@ -33,6 +36,11 @@ pub use proxy_connector::{MaybeProxiedConnection, ProxyConnector, ProxyConnector
 /// a common Http connector setup (tcp + proxy + tls + http) or bring your
 /// own http connector.
 ///
+/// The hop-by-hop headers of requests and responses are sanitized by default,
+/// as described in [`SanitizeHeadersLayer`], honoring upgrades. This makes the client
+/// safe to use as the forwarding service of a proxy. Use
+/// [`EasyHttpWebClient::without_header_sanitization`] to opt-out of this.
+///
 /// You can fork this http client in case you have use cases not possible with this service example.
 /// E.g. perhaps you wish to have middleware in into outbound requests, after they
 /// passed through your "connector" setup. All this and more is possible by defining your own
@@ -40,11 +48,14 @@ pub use proxy_connector::{MaybeProxiedConnection, ProxyConnector, ProxyConnector
 /// with your own service fork and use the full power of Rust at your fingertips ;)
 pub struct EasyHttpWebClient<BodyIn, ConnResponse> {
     connector: BoxService<Request<BodyIn>, ConnResponse, BoxError>,
+    header_sanitization: Option<SanitizeHeadersLayer>,
 }
 
 impl<BodyIn, ConnResponse> fmt::Debug for EasyHttpWebClient<BodyIn, ConnResponse> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        f.debug_struct("EasyHttpWebClient").finish()
+        f.debug_struct("EasyHttpWebClient")
+            .field("header_sanitization", &self.header_sanitization)
+            .finish()
     }
 }
 
@@ -52,6 +63,7 @@ impl<BodyIn, ConnResponse> Clone for EasyHttpWebClient<BodyIn, ConnResponse> {
     fn clone(&self) -> Self {
         Self {
             connector: self.connector.clone(),
+            header_sanitization: self.header_sanitization.clone(),
         }
     }
 }
@@ -110,7 +122,10 @@ impl<BodyIn, ConnResponse> EasyHttpWebClient<BodyIn, ConnResponse> {
     /// Create a new [`EasyHttpWebClient`] using the provided connector
     #[must_use]
     pub fn new(connector: BoxService<Request<BodyIn>, ConnResponse, BoxError>) -> Self {
-        Self { connector }
+        Self {
+            connector,
+            header_sanitization: Some(SanitizeHeadersLayer::new().with_honor_upgrade(true)),
+        }
     }
 
     /// Set the connector that this [`EasyHttpWebClient`] will use
@@ -119,7 +134,22 @@ impl<BodyIn, ConnResponse> EasyHttpWebClient<BodyIn, ConnResponse> {
         self,
         connector: BoxService<Request<BodyInNew>, ConnResponseNew, BoxError>,
     ) -> EasyHttpWebClient<BodyInNew, ConnResponseNew> {
-        EasyHttpWebClient { connector }
+        EasyHttpWebClient {
+            connector,
+            header_sanitization: self.header_sanitization,
+        }
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Set the [`SanitizeHeadersLayer`] config used to sanitize
+        /// the headers of requests and responses passing through this [`EasyHttpWebClient`].
+        ///
+        /// By default the headers are sanitized honoring upgrades,
+        /// use `without_header_sanitization` to forward all headers as-is.
+        pub fn header_sanitization(mut self, config: Option<SanitizeHeadersLayer>) -> Self {
+            self.header_sanitization = config;
+            self
+        }
     }
 }
 
@@ -135,21 +165,111 @@ where
 
     type Error = OpaqueError;
 
-    async fn serve(&self, ctx: Context, req: Request<Body>) -> Result<Self::Response, Self::Error> {
+    async fn serve(
+        &self,
+        ctx: Context,
+        mut req: Request<Body>,
+    ) -> Result<Self::Response, Self::Error> {
         let uri = req.uri().clone();
 
+        if let Some(sanitizer) = &self.header_sanitization
+            && let Err(name) = sanitizer.sanitize_request(&mut req)
+        {
+            tracing::debug!(
+                url.full = %uri,
+                http.header.name = %name,
+                "refuse request with header exceeding the size cap"
+            );
+            return Ok(StatusCode::REQUEST_HEADER_FIELDS_TOO_LARGE.into_response());
+        }
+
         let EstablishedClientConnection { ctx, req, conn } = self.connector.serve(ctx, req).await?;
         // NOTE: stack might change request version based on connector data,
         tracing::trace!(url.full = %uri, "send http req to connector stack");
 
         let result = conn.serve(ctx, req).await;
 
-        let resp = result
+        let mut resp = result
             .map_err(OpaqueError::from_boxed)
             .with_context(|| format!("http request failure for uri: {uri}"))?;
 
+        if let Some(sanitizer) = &self.header_sanitization
+            && let Err(name) = sanitizer.sanitize_response(&mut resp)
+        {
+            tracing::debug!(
+                url.full = %uri,
+                http.header.name = %name,
+                "refuse response with header exceeding the size cap"
+            );
+            return Ok(StatusCode::BAD_GATEWAY.into_response());
+        }
+
         tracing::trace!(url.full = %uri, "response received from connector stack");
 
         Ok(resp)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        http::{Body, header},
+        service::service_fn,
+    };
+
+    async fn send_hop_request(client: &EasyHttpWebClient<Body, MockConnection>) -> Response {
+        let req = Request::builder()
+            .uri("http://example.com")
+            .header(header::CONNECTION, "x-hop")
+            .header("x-hop", "1")
+            .body(Body::empty())
+            .unwrap();
+        client.serve(Context::default(), req).await.unwrap()
+    }
+
+    type MockConnection =
+        EstablishedClientConnection<BoxService<Request, Response, BoxError>, Request>;
+
+    fn mock_client() -> EasyHttpWebClient<Body, MockConnection> {
+        let conn = service_fn(async |req: Request| {
+            let req_hop = if req.headers().contains_key("x-hop") {
+                "forwarded"
+            } else {
+                "removed"
+            };
+            Ok::<_, BoxError>(
+                Response::builder()
+                    .header(header::CONNECTION, "x-hop")
+                    .header("x-hop", "1")
+                    .header("x-req-hop", req_hop)
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+        })
+        .boxed();
+        EasyHttpWebClient::new(
+            service_fn(move |ctx: Context, req: Request| {
+                let conn = conn.clone();
+                async move { Ok::<_, BoxError>(EstablishedClientConnection { ctx, req, conn }) }
+            })
+            .boxed(),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_client_sanitizes_headers_by_default() {
+        let resp = send_hop_request(&mock_client()).await;
+        assert_eq!(resp.headers()["x-req-hop"], "removed");
+        assert!(!resp.headers().contains_key("x-hop"));
+        assert!(!resp.headers().contains_key(header::CONNECTION));
+    }
+
+    #[tokio::test]
+    async fn test_client_without_header_sanitization() {
+        let resp = send_hop_request(&mock_client().without_header_sanitization()).await;
+        assert_eq!(resp.headers()["x-req-hop"], "forwarded");
+        assert_eq!(resp.headers()["x-hop"], "1");
+        assert_eq!(resp.headers()[header::CONNECTION], "x-hop");
+    }
+}
@@ -57,7 +57,7 @@
 //! | ✅ [proxy] protocols | ✅ [PROXY protocol](crate::proxy::haproxy) ⸱ ✅ [http proxy](https://github.com/plabayo/rama/blob/main/examples/http_connect_proxy.rs) ⸱ ✅ [https proxy](https://github.com/plabayo/rama/blob/main/examples/https_connect_proxy.rs) ⸱ ✅ [socks5(h) proxy](https://github.com/plabayo/rama/blob/main/examples/socks5_connect_proxy.rs) |
 //! | ✅ web protocols | ✅ [SSE](crate::http::sse) ⸱ ✅ [WS](crate::http::ws) ⸱ ❌ Web Transport <sup>(3)</sup> ⸱ ❌ gRPC <sup>(2)</sup> |
 //! | ✅ [async-method trait](https://blog.rust-lang.org/inside-rust/2023/05/03/stabilizing-async-fn-in-trait.html) services | ✅ [Service] ⸱ ✅ [Layer] ⸱ ✅ [context] ⸱ ✅ [dyn dispatch](crate::service::BoxService) ⸱ ✅ [middleware](crate::layer) |
-//! | ✅ [telemetry] | ✅ [tracing](https://tracing.rs/tracing/) ⸱ ✅ [opentelemetry][telemetry::opentelemetry] ⸱ ✅ [http metrics](crate::http::layer::opentelemetry) ⸱ ✅ [transport metrics](crate::net::stream::layer::opentelemetry) |
+//! | ✅ [telemetry] | ✅ [tracing](https://tracing.rs/tracing/) ⸱ ✅ [opentelemetry][telemetry::opentelemetry] ⸱ ✅ [http metrics](crate::http::layer::opentelemetry) ⸱ ✅ [transport metrics](crate::net::stream::layer::opentelemetry) ⸱ ✅ [OTLP exporters](telemetry::otlp) |
 //! | ✅ upstream [proxies](proxy) | ✅ [MemoryProxyDB](crate::proxy::MemoryProxyDB) ⸱ ✅ [Username Config] ⸱ ✅ [Proxy Filters](crate::proxy::ProxyFilter) |
 //! | ✅ [User Agent (UA)](https://ramaproxy.org/book/intro/user_agent) | ✅ [Http Emulation](crate::ua::profile::HttpProfile) ⸱ ✅ [Tls Emulation](crate::ua::profile::TlsProfile) ⸱ ✅ [UA Parsing](crate::ua::UserAgent) |
 //! | ✅ [Fingerprinting](crate::net::fingerprint) | ✅ [Ja3](crate::net::fingerprint::Ja3) ⸱ ✅ [Ja4](crate::net::fingerprint::Ja4) ⸱ ✅ [Ja4H](crate::net::fingerprint::Ja4H) ⸱ 🏗️ [Akamai passive h2](https://github.com/plabayo/rama/issues/517) <sup>(1)</sup> ⸱ ✅ [Peetprint (tls)](crate::net::fingerprint::PeetPrint) |
@@ -185,6 +185,7 @@
 //! - [`rama-unix`](https://crates.io/crates/rama-unix): Unix (domain) socket support for rama
 //! - [`rama-tcp`](https://crates.io/crates/rama-tcp): TCP support for rama
 //! - [`rama-udp`](https://crates.io/crates/rama-udp): UDP support for rama
+//! - [`rama-quic`](https://crates.io/crates/rama-quic): QUIC support for rama
 //! - [`rama-tls-acme`](https://crates.io/crates/rama-tls-acme): ACME support for rama
 //! - [`rama-tls-boring`](https://crates.io/crates/rama-tls-boring): [Boring](https://github.com/plabayo/rama-boring) tls support for rama
 //! - [`rama-tls-rustls`](https://crates.io/crates/rama-tls-rustls): [Rustls](https://github.com/rustls/rustls) support for rama
@@ -373,8 +374,8 @@
 
 #[doc(inline)]
 pub use ::rama_core::{
-    Context, Layer, Service, bytes, combinators, context, error, futures, graceful, inspect, layer,
-    matcher, rt, service, username,
+    Context, Layer, Service, buffer, bytes, combinators, context, error, futures, graceful,
+    inspect, layer, matcher, rt, service, username,
 };
 
 #[doc(inline)]
@@ -392,8 +393,14 @@ pub use ::rama_tcp as tcp;
 #[doc(inline)]
 pub use ::rama_udp as udp;
 
+#[cfg(feature = "quic")]
 #[doc(inline)]
-pub use ::rama_core::telemetry;
+pub use ::rama_quic as quic;
+
+#[cfg(feature = "tcp")]
+pub mod socket;
+
+pub mod telemetry;
 
 #[cfg(any(feature = "rustls", feature = "boring", feature = "acme"))]
 pub mod tls;
@@ -433,6 +440,8 @@ pub use ::rama_ua as ua;
 #[cfg(feature = "cli")]
 pub mod cli;
 
+pub mod test;
+
 pub mod utils {
     //! utilities for rama
 
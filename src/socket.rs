@@ -0,0 +1,322 @@
+//! Transport agnostic socket support for rama.
+//!
+//! A [`SocketTarget`] is either an ip socket address, served over TCP,
+//! or the path of a unix (domain) socket. The [`SocketListener`] and [`SocketConnector`]
+//! accept any such target and expose the established connections as a [`SocketStream`],
+//! such that a service can be written once and deployed over either transport,
+//! by changing the configured target only.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use rama::service::service_fn;
+//! use rama::socket::{SocketListener, SocketStream};
+//! use tokio::io::AsyncWriteExt;
+//!
+//! #[tokio::main]
+//! async fn main() {
+//!     // e.g. "127.0.0.1:8080" or "unix:/var/run/rama.sock"
+//!     let target = std::env::var("LISTEN").unwrap();
+//!
+//!     SocketListener::bind(target)
+//!         .await
+//!         .expect("bind socket listener")
+//!         .serve(service_fn(async |mut stream: SocketStream| {
+//!             stream.write_all(b"hello").await
+//!         }))
+//!         .await;
+//! }
+//! ```
+
+use crate::{
+    Context, Service,
+    error::{BoxError, ErrorContext, OpaqueError},
+    graceful::ShutdownGuard,
+    net::{
+        address::SocketTarget,
+        client::EstablishedClientConnection,
+        stream::{ClientSocketInfo, SocketInfo},
+    },
+    tcp::{TcpStream, server::TcpListener},
+    telemetry::tracing,
+};
+use std::{
+    io,
+    pin::Pin,
+    task::{self, Poll},
+};
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+
+#[cfg(unix)]
+use crate::unix::{UnixStream, client::UnixConnector, server::UnixListener};
+
+#[derive(Debug)]
+/// A connection established over one of the transports a [`SocketTarget`] can point to.
+pub enum SocketStream {
+    /// A TCP connection.
+    Tcp(TcpStream),
+    #[cfg(unix)]
+    /// A unix (domain) socket connection.
+    Unix(UnixStream),
+}
+
+impl From<TcpStream> for SocketStream {
+    fn from(stream: TcpStream) -> Self {
+        Self::Tcp(stream)
+    }
+}
+
+#[cfg(unix)]
+impl From<UnixStream> for SocketStream {
+    fn from(stream: UnixStream) -> Self {
+        Self::Unix(stream)
+    }
+}
+
+impl AsyncRead for SocketStream {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut task::Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        match self.get_mut() {
+            Self::Tcp(stream) => Pin::new(stream).poll_read(cx, buf),
+            #[cfg(unix)]
+            Self::Unix(stream) => Pin::new(stream).poll_read(cx, buf),
+        }
+    }
+}
+
+impl AsyncWrite for SocketStream {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut task::Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        match self.get_mut() {
+            Self::Tcp(stream) => Pin::new(stream).poll_write(cx, buf),
+            #[cfg(unix)]
+            Self::Unix(stream) => Pin::new(stream).poll_write(cx, buf),
+        }
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut task::Context<'_>) -> Poll<io::Result<()>> {
+        match self.get_mut() {
+            Self::Tcp(stream) => Pin::new(stream).poll_flush(cx),
+            #[cfg(unix)]
+            Self::Unix(stream) => Pin::new(stream).poll_flush(cx),
+        }
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut task::Context<'_>) -> Poll<io::Result<()>> {
+        match self.get_mut() {
+            Self::Tcp(stream) => Pin::new(stream).poll_shutdown(cx),
+            #[cfg(unix)]
+            Self::Unix(stream) => Pin::new(stream).poll_shutdown(cx),
+        }
+    }
+
+    fn poll_write_vectored(
+        self: Pin<&mut Self>,
+        cx: &mut task::Context<'_>,
+        bufs: &[io::IoSlice<'_>],
+    ) -> Poll<io::Result<usize>> {
+        match self.get_mut() {
+            Self::Tcp(stream) => Pin::new(stream).poll_write_vectored(cx, bufs),
+            #[cfg(unix)]
+            Self::Unix(stream) => Pin::new(stream).poll_write_vectored(cx, bufs),
+        }
+    }
+
+    fn is_write_vectored(&self) -> bool {
+        match self {
+            Self::Tcp(stream) => stream.is_write_vectored(),
+            #[cfg(unix)]
+            Self::Unix(stream) => stream.is_write_vectored(),
+        }
+    }
+}
+
+#[derive(Debug)]
+/// A server listening on a [`SocketTarget`], serving each accepted connection
+/// as a [`SocketStream`] using one of the `serve` methods such as [`SocketListener::serve`].
+///
+/// The [`Context`] contains the [`SocketInfo`] or [`UnixSocketInfo`]
+/// of the connection, as inserted by the underlying listener.
+///
+/// [`UnixSocketInfo`]: crate::unix::UnixSocketInfo
+pub enum SocketListener {
+    /// A listener bound to an ip socket address.
+    Tcp(TcpListener),
+    #[cfg(unix)]
+    /// A listener bound to a unix (domain) socket path.
+    Unix(UnixListener),
+}
+
+impl SocketListener {
+    /// Creates a new [`SocketListener`], which will be bound to the specified [`SocketTarget`].
+    ///
+    /// The returned listener is ready for accepting connections.
+    pub async fn bind<T: TryInto<SocketTarget, Error: Into<BoxError>>>(
+        target: T,
+    ) -> Result<Self, BoxError> {
+        match target.try_into().map_err(Into::into)? {
+            SocketTarget::Address(addr) => Ok(Self::Tcp(TcpListener::bind_address(addr).await?)),
+            #[cfg(unix)]
+            SocketTarget::Unix(path) => Ok(Self::Unix(
+                UnixListener::bind_path(path)
+                    .await
+                    .context("bind unix socket listener")?,
+            )),
+            target => Err(OpaqueError::from_display(format!(
+                "socket listener: unsupported target: {target}"
+            ))
+            .into()),
+        }
+    }
+
+    /// Returns the [`SocketTarget`] that this listener is bound to.
+    ///
+    /// This can be useful, for example, when binding to port 0 to figure out
+    /// which port was actually bound.
+    pub fn local_target(&self) -> io::Result<SocketTarget> {
+        match self {
+            Self::Tcp(listener) => listener.local_addr().map(Into::into),
+            #[cfg(unix)]
+            Self::Unix(listener) => listener
+                .local_addr()?
+                .as_pathname()
+                .map(Into::into)
+                .ok_or_else(|| io::Error::other("unix listener is not bound to a path")),
+        }
+    }
+
+    /// Serve connections from this listener with the given service.
+    ///
+    /// See [`TcpListener::serve`] and [`UnixListener::serve`] for more information.
+    pub async fn serve<S>(self, service: S)
+    where
+        S: Service<SocketStream>,
+    {
+        let service = SocketStreamService(service);
+        match self {
+            Self::Tcp(listener) => listener.serve(service).await,
+            #[cfg(unix)]
+            Self::Unix(listener) => listener.serve(service).await,
+        }
+    }
+
+    /// Serve gracefully connections from this listener with the given service.
+    ///
+    /// This method does the same as [`Self::serve`] but it
+    /// will respect the given [`ShutdownGuard`], and also pass
+    /// it to the service.
+    pub async fn serve_graceful<S>(self, guard: ShutdownGuard, service: S)
+    where
+        S: Service<SocketStream>,
+    {
+        let service = SocketStreamService(service);
+        match self {
+            Self::Tcp(listener) => listener.serve_graceful(guard, service).await,
+            #[cfg(unix)]
+            Self::Unix(listener) => listener.serve_graceful(guard, service).await,
+        }
+    }
+}
+
+/// Serves the transport specific stream of a listener as a [`SocketStream`].
+struct SocketStreamService<S>(S);
+
+impl<S, T> Service<T> for SocketStreamService<S>
+where
+    S: Service<SocketStream>,
+    T: Into<SocketStream> + Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+
+    fn serve(
+        &self,
+        ctx: Context,
+        stream: T,
+    ) -> impl Future<Output = Result<Self::Response, Self::Error>> + Send + '_ {
+        self.0.serve(ctx, stream.into())
+    }
+}
+
+#[derive(Debug, Clone)]
+/// A connector which establishes a [`SocketStream`] to a fixed [`SocketTarget`].
+///
+/// An ip socket address is connected to over TCP, in which case
+/// the [`ClientSocketInfo`] is inserted in the [`Context`],
+/// while a unix (domain) socket path results in a [`ClientUnixSocketInfo`].
+///
+/// [`ClientUnixSocketInfo`]: crate::unix::ClientUnixSocketInfo
+pub struct SocketConnector {
+    target: SocketTarget,
+}
+
+impl SocketConnector {
+    /// Create a new [`SocketConnector`], which is used to establish a connection
+    /// to the given fixed [`SocketTarget`].
+    pub fn fixed(target: impl Into<SocketTarget>) -> Self {
+        Self {
+            target: target.into(),
+        }
+    }
+
+    /// Returns the [`SocketTarget`] this connector connects to.
+    #[must_use]
+    pub fn target(&self) -> &SocketTarget {
+        &self.target
+    }
+}
+
+impl<Request> Service<Request> for SocketConnector
+where
+    Request: Send + 'static,
+{
+    type Response = EstablishedClientConnection<SocketStream, Request>;
+    type Error = BoxError;
+
+    async fn serve(&self, mut ctx: Context, req: Request) -> Result<Self::Response, Self::Error> {
+        match &self.target {
+            SocketTarget::Address(addr) => {
+                let (conn, addr) = crate::tcp::client::default_tcp_connect(&ctx, (*addr).into())
+                    .await
+                    .context("socket connector: connect to tcp target")?;
+
+                ctx.insert(ClientSocketInfo(SocketInfo::new(
+                    conn.local_addr()
+                        .inspect_err(|err| {
+                            tracing::debug!(
+                                "failed to receive local addr of established connection: {err:?}"
+                            )
+                        })
+                        .ok(),
+                    addr,
+                )));
+
+                Ok(EstablishedClientConnection {
+                    ctx,
+                    req,
+                    conn: conn.into(),
+                })
+            }
+            #[cfg(unix)]
+            SocketTarget::Unix(path) => {
+                let EstablishedClientConnection { ctx, req, conn } =
+                    UnixConnector::fixed(path.clone()).serve(ctx, req).await?;
+                Ok(EstablishedClientConnection {
+                    ctx,
+                    req,
+                    conn: conn.into(),
+                })
+            }
+            target => Err(OpaqueError::from_display(format!(
+                "socket connector: unsupported target: {target}"
+            ))
+            .into()),
+        }
+    }
+}
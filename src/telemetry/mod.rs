@@ -0,0 +1,9 @@
+//! rama telemetry support
+//!
+//! mostly contains re-exports from `rama-core::telemetry`.
+
+#[doc(inline)]
+pub use ::rama_core::telemetry::*;
+
+#[cfg(feature = "otlp")]
+pub mod otlp;
@@ -0,0 +1,765 @@
+//! First-party [OTLP] exporters for the trace spans and metrics produced by rama.
+//!
+//! [`OtlpConfig`] configures in one place how the trace spans (e.g. of the
+//! `tracing` instrumentation within rama) and the metrics (e.g. of the
+//! [http] and [transport] metric layers) are shipped to an OTLP collector,
+//! using either the `http/protobuf` or `grpc` protocol. The exports
+//! are sent using rama's own [`EasyHttpWebClient`], or the http client
+//! given to [`OtlpConfig::install_with_client`].
+//!
+//! Only trace spans are exported by default, the export of metrics
+//! has to be enabled explicitly, e.g. using `OTEL_METRICS_EXPORTER=otlp`.
+//!
+//! Installing the config registers its tracer and meter providers globally,
+//! such that the metric layers provided by rama export via it without any
+//! further setup. Trace spans are exported once the [`tracing layer`] is
+//! registered with the [`SdkTracer`] returned by [`OtlpTelemetry::tracer`].
+//!
+//! # Example
+//!
+//! ```no_run
+//! use rama::telemetry::{
+//!     otlp::{OtlpConfig, OtlpProtocol},
+//!     tracing::layer,
+//! };
+//! use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
+//!
+//! # #[tokio::main]
+//! # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let telemetry = OtlpConfig::from_env()
+//!     .with_service_name("my-proxy")
+//!     .with_protocol(OtlpProtocol::Grpc)
+//!     .install()?;
+//!
+//! tracing_subscriber::registry()
+//!     .with(layer().with_tracer(telemetry.tracer("my-proxy")))
+//!     .init();
+//!
+//! // ... serve your services
+//!
+//! telemetry.shutdown()?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [OTLP]: https://opentelemetry.io/docs/specs/otlp/
+//! [http]: crate::http::layer::opentelemetry
+//! [transport]: crate::net::stream::layer::opentelemetry
+//! [`tracing layer`]: crate::telemetry::tracing::layer
+
+use crate::{
+    Context, Service,
+    bytes::{Buf, BufMut, Bytes, BytesMut},
+    error::{BoxError, ErrorContext, OpaqueError},
+    http::{
+        Body, HeaderValue, Request, Response, StatusCode, Version,
+        client::EasyHttpWebClient,
+        dep::http_body_util::BodyExt,
+        header::{CONTENT_LENGTH, CONTENT_TYPE, TE},
+        service::opentelemetry::OtelExporter,
+    },
+    telemetry::{
+        opentelemetry::{
+            KeyValue, global,
+            sdk::{
+                Resource,
+                metrics::{PeriodicReader, SdkMeterProvider},
+                trace::{SdkTracer, SdkTracerProvider},
+            },
+            trace::TracerProvider as _,
+        },
+        tracing,
+    },
+};
+use opentelemetry_otlp::{MetricExporter, SpanExporter, WithExportConfig, WithHttpConfig};
+use rama_utils::macros::match_ignore_ascii_case_str;
+use std::{borrow::Cow, collections::HashMap, fmt, str::FromStr, time::Duration};
+
+/// Default timeout of a single export.
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Default interval in between two metric exports.
+pub const DEFAULT_METRICS_INTERVAL: Duration = Duration::from_secs(60);
+
+const GRPC_TRACES_PATH: &str = "/opentelemetry.proto.collector.trace.v1.TraceService/Export";
+const GRPC_METRICS_PATH: &str = "/opentelemetry.proto.collector.metrics.v1.MetricsService/Export";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+/// The protocol used to export telemetry to an OTLP collector.
+pub enum OtlpProtocol {
+    #[default]
+    /// OTLP over http using binary protobuf payloads (`http/protobuf`).
+    HttpProtobuf,
+    /// OTLP over gRPC (`grpc`), using http/2 with prior knowledge for plain-text endpoints.
+    Grpc,
+}
+
+impl OtlpProtocol {
+    /// The endpoint of a local collector listening on the default port of this protocol.
+    #[must_use]
+    pub fn default_endpoint(self) -> &'static str {
+        match self {
+            Self::HttpProtobuf => "http://localhost:4318",
+            Self::Grpc => "http://localhost:4317",
+        }
+    }
+}
+
+impl fmt::Display for OtlpProtocol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::HttpProtobuf => "http/protobuf",
+            Self::Grpc => "grpc",
+        }
+        .fmt(f)
+    }
+}
+
+impl FromStr for OtlpProtocol {
+    type Err = OpaqueError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match_ignore_ascii_case_str! {
+            match (s) {
+                "http/protobuf" | "http" => Ok(Self::HttpProtobuf),
+                "grpc" => Ok(Self::Grpc),
+                _ => Err(OpaqueError::from_display(format!(
+                    "unsupported OTLP protocol: {s}"
+                ))),
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+/// Central configuration of the OTLP exporters of the traces and metrics produced by rama.
+///
+/// See [the module docs](self) for more information.
+pub struct OtlpConfig {
+    endpoint: Option<String>,
+    traces_endpoint: Option<String>,
+    metrics_endpoint: Option<String>,
+    protocol: OtlpProtocol,
+    service_name: String,
+    service_version: Option<String>,
+    attributes: Vec<KeyValue>,
+    headers: HashMap<String, String>,
+    timeout: Duration,
+    traces: bool,
+    metrics: bool,
+    metrics_interval: Duration,
+}
+
+impl Default for OtlpConfig {
+    fn default() -> Self {
+        Self {
+            endpoint: None,
+            traces_endpoint: None,
+            metrics_endpoint: None,
+            protocol: OtlpProtocol::default(),
+            service_name: "rama".to_owned(),
+            service_version: None,
+            attributes: Vec::new(),
+            headers: HashMap::new(),
+            timeout: DEFAULT_TIMEOUT,
+            traces: true,
+            metrics: false,
+            metrics_interval: DEFAULT_METRICS_INTERVAL,
+        }
+    }
+}
+
+impl OtlpConfig {
+    /// Create a new [`OtlpConfig`] exporting traces
+    /// to a local collector using the `http/protobuf` protocol.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a new [`OtlpConfig`] using the standard OpenTelemetry environment variables.
+    ///
+    /// The following variables are supported, with invalid values being ignored:
+    ///
+    /// - `OTEL_EXPORTER_OTLP_ENDPOINT`;
+    /// - `OTEL_EXPORTER_OTLP_TRACES_ENDPOINT` and `OTEL_EXPORTER_OTLP_METRICS_ENDPOINT`:
+    ///   used as-is instead of an endpoint derived from the base endpoint;
+    /// - `OTEL_EXPORTER_OTLP_PROTOCOL`: `http/protobuf` or `grpc`;
+    /// - `OTEL_EXPORTER_OTLP_HEADERS`: comma-separated `key=value` pairs;
+    /// - `OTEL_EXPORTER_OTLP_TIMEOUT`: in milliseconds;
+    /// - `OTEL_SERVICE_NAME`;
+    /// - `OTEL_TRACES_EXPORTER` and `OTEL_METRICS_EXPORTER`: `otlp` or `none`;
+    /// - `OTEL_METRIC_EXPORT_INTERVAL`: in milliseconds.
+    #[must_use]
+    pub fn from_env() -> Self {
+        Self::from_vars(|key| std::env::var(key).ok())
+    }
+
+    fn from_vars(var: impl Fn(&str) -> Option<String>) -> Self {
+        let mut cfg = Self::default();
+        if let Some(endpoint) = var("OTEL_EXPORTER_OTLP_ENDPOINT") {
+            cfg.endpoint = Some(endpoint);
+        }
+        cfg.traces_endpoint = var("OTEL_EXPORTER_OTLP_TRACES_ENDPOINT");
+        cfg.metrics_endpoint = var("OTEL_EXPORTER_OTLP_METRICS_ENDPOINT");
+        if let Some(protocol) = var("OTEL_EXPORTER_OTLP_PROTOCOL") {
+            match protocol.parse() {
+                Ok(protocol) => cfg.protocol = protocol,
+                Err(err) => tracing::warn!("ignore OTEL_EXPORTER_OTLP_PROTOCOL: {err}"),
+            }
+        }
+        if let Some(headers) = var("OTEL_EXPORTER_OTLP_HEADERS") {
+            cfg.headers.extend(
+                headers
+                    .split(',')
+                    .filter_map(|pair| pair.split_once('='))
+                    .map(|(key, value)| (key.trim().to_owned(), value.trim().to_owned())),
+            );
+        }
+        if let Some(timeout) = var("OTEL_EXPORTER_OTLP_TIMEOUT") {
+            match timeout.parse() {
+                Ok(millis) => cfg.timeout = Duration::from_millis(millis),
+                Err(err) => tracing::warn!("ignore OTEL_EXPORTER_OTLP_TIMEOUT: {err}"),
+            }
+        }
+        if let Some(name) = var("OTEL_SERVICE_NAME") {
+            cfg.service_name = name;
+        }
+        if let Some(exporter) = var("OTEL_TRACES_EXPORTER") {
+            match parse_exporter(&exporter) {
+                Ok(enabled) => cfg.traces = enabled,
+                Err(err) => tracing::warn!("ignore OTEL_TRACES_EXPORTER: {err}"),
+            }
+        }
+        if let Some(exporter) = var("OTEL_METRICS_EXPORTER") {
+            match parse_exporter(&exporter) {
+                Ok(enabled) => cfg.metrics = enabled,
+                Err(err) => tracing::warn!("ignore OTEL_METRICS_EXPORTER: {err}"),
+            }
+        }
+        if let Some(interval) = var("OTEL_METRIC_EXPORT_INTERVAL") {
+            match interval.parse() {
+                Ok(millis) => cfg.metrics_interval = Duration::from_millis(millis),
+                Err(err) => tracing::warn!("ignore OTEL_METRIC_EXPORT_INTERVAL: {err}"),
+            }
+        }
+        cfg
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Set the base endpoint of the OTLP collector, e.g. `http://collector:4318`.
+        ///
+        /// By default the [`OtlpProtocol::default_endpoint`] is used.
+        pub fn endpoint(mut self, endpoint: impl Into<String>) -> Self {
+            self.endpoint = Some(endpoint.into());
+            self
+        }
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Set the endpoint to export the trace spans to, used as-is for `http/protobuf`.
+        ///
+        /// By default it is derived from the [base endpoint](Self::with_endpoint).
+        pub fn traces_endpoint(mut self, endpoint: impl Into<String>) -> Self {
+            self.traces_endpoint = Some(endpoint.into());
+            self
+        }
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Set the endpoint to export the metrics to, used as-is for `http/protobuf`.
+        ///
+        /// By default it is derived from the [base endpoint](Self::with_endpoint).
+        pub fn metrics_endpoint(mut self, endpoint: impl Into<String>) -> Self {
+            self.metrics_endpoint = Some(endpoint.into());
+            self
+        }
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Set the [`OtlpProtocol`] used to export the telemetry.
+        pub fn protocol(mut self, protocol: OtlpProtocol) -> Self {
+            self.protocol = protocol;
+            self
+        }
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Set the `service.name` of the exported telemetry, `rama` by default.
+        pub fn service_name(mut self, name: impl Into<String>) -> Self {
+            self.service_name = name.into();
+            self
+        }
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Set the `service.version` of the exported telemetry.
+        pub fn service_version(mut self, version: impl Into<String>) -> Self {
+            self.service_version = Some(version.into());
+            self
+        }
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Add an attribute to the resource of the exported telemetry.
+        pub fn attribute(mut self, attribute: KeyValue) -> Self {
+            self.attributes.push(attribute);
+            self
+        }
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Add a header to the export requests, e.g. to authenticate with the collector.
+        pub fn header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+            self.headers.insert(name.into(), value.into());
+            self
+        }
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Set the timeout of a single export, [`DEFAULT_TIMEOUT`] by default.
+        pub fn timeout(mut self, timeout: Duration) -> Self {
+            self.timeout = timeout;
+            self
+        }
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Enable or disable the export of trace spans, enabled by default.
+        pub fn traces(mut self, enabled: bool) -> Self {
+            self.traces = enabled;
+            self
+        }
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Enable or disable the export of metrics, disabled by default.
+        pub fn metrics(mut self, enabled: bool) -> Self {
+            self.metrics = enabled;
+            self
+        }
+    }
+
+    rama_utils::macros::generate_set_and_with! {
+        /// Set the interval in between two metric exports, [`DEFAULT_METRICS_INTERVAL`] by default.
+        pub fn metrics_interval(mut self, interval: Duration) -> Self {
+            self.metrics_interval = interval;
+            self
+        }
+    }
+
+    /// Build the configured exporters and register their providers globally.
+    ///
+    /// The exports are sent using the default [`EasyHttpWebClient`],
+    /// see [`Self::install_with_client`] to use a custom http client.
+    ///
+    /// This has to be called from within a tokio runtime,
+    /// which is used to send the exports.
+    pub fn install(&self) -> Result<OtlpTelemetry, OpaqueError> {
+        self.install_with_client(EasyHttpWebClient::default())
+    }
+
+    /// Build the configured exporters and register their providers globally,
+    /// sending the exports using the given http client.
+    ///
+    /// The client has to support http/2 in case the [`OtlpProtocol::Grpc`] is used.
+    ///
+    /// This has to be called from within a tokio runtime,
+    /// which is used to send the exports.
+    pub fn install_with_client<S>(&self, client: S) -> Result<OtlpTelemetry, OpaqueError>
+    where
+        S: fmt::Debug + Clone + Service<Request, Response = Response, Error: Into<BoxError>>,
+    {
+        let client = OtelExporter::new(OtlpClient {
+            inner: client,
+            grpc: self.protocol == OtlpProtocol::Grpc,
+        });
+        let resource = self.resource();
+
+        let mut tracer_provider = SdkTracerProvider::builder().with_resource(resource.clone());
+        if self.traces {
+            let exporter = SpanExporter::builder()
+                .with_http()
+                .with_http_client(client.clone())
+                .with_endpoint(self.signal_endpoint(
+                    self.traces_endpoint.as_deref(),
+                    "/v1/traces",
+                    GRPC_TRACES_PATH,
+                ))
+                .with_timeout(self.timeout)
+                .with_headers(self.headers.clone())
+                .build()
+                .context("build OTLP span exporter")?;
+            tracer_provider = tracer_provider.with_batch_exporter(exporter);
+        }
+        let tracer_provider = tracer_provider.build();
+
+        let mut meter_provider = SdkMeterProvider::builder().with_resource(resource);
+        if self.metrics {
+            let exporter = MetricExporter::builder()
+                .with_http()
+                .with_http_client(client)
+                .with_endpoint(self.signal_endpoint(
+                    self.metrics_endpoint.as_deref(),
+                    "/v1/metrics",
+                    GRPC_METRICS_PATH,
+                ))
+                .with_timeout(self.timeout)
+                .with_headers(self.headers.clone())
+                .build()
+                .context("build OTLP metric exporter")?;
+            meter_provider = meter_provider.with_reader(
+                PeriodicReader::builder(exporter)
+                    .with_interval(self.metrics_interval)
+                    .build(),
+            );
+        }
+        let meter_provider = meter_provider.build();
+
+        global::set_tracer_provider(tracer_provider.clone());
+        global::set_meter_provider(meter_provider.clone());
+
+        Ok(OtlpTelemetry {
+            tracer_provider,
+            meter_provider,
+        })
+    }
+
+    fn resource(&self) -> Resource {
+        let mut resource = Resource::builder()
+            .with_service_name(self.service_name.clone())
+            .with_attributes(self.attributes.iter().cloned());
+        if let Some(version) = &self.service_version {
+            resource = resource.with_attribute(KeyValue::new("service.version", version.clone()));
+        }
+        resource.build()
+    }
+
+    /// Returns the endpoint of a signal, which is the signal specific endpoint as-is,
+    /// if defined, or otherwise derived from the base endpoint.
+    ///
+    /// gRPC endpoints only define the collector, such that the path of the service is always added.
+    fn signal_endpoint(&self, signal: Option<&str>, http_path: &str, grpc_path: &str) -> String {
+        match (self.protocol, signal) {
+            (OtlpProtocol::HttpProtobuf, Some(endpoint)) => endpoint.to_owned(),
+            (OtlpProtocol::Grpc, Some(endpoint)) => {
+                format!("{}{grpc_path}", endpoint.trim_end_matches('/'))
+            }
+            (protocol, None) => {
+                let endpoint = self
+                    .endpoint
+                    .as_deref()
+                    .unwrap_or_else(|| protocol.default_endpoint())
+                    .trim_end_matches('/');
+                match protocol {
+                    OtlpProtocol::HttpProtobuf => format!("{endpoint}{http_path}"),
+                    OtlpProtocol::Grpc => format!("{endpoint}{grpc_path}"),
+                }
+            }
+        }
+    }
+}
+
+/// Parse the value of an `OTEL_*_EXPORTER` variable, returning whether the OTLP exporter is enabled.
+fn parse_exporter(exporter: &str) -> Result<bool, OpaqueError> {
+    match_ignore_ascii_case_str! {
+        match (exporter) {
+            "otlp" => Ok(true),
+            "none" => Ok(false),
+            _ => Err(OpaqueError::from_display(format!(
+                "unsupported exporter: {exporter}"
+            ))),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+/// The installed OTLP exporters, created using [`OtlpConfig::install`].
+///
+/// Call [`OtlpTelemetry::shutdown`] prior to exiting the process
+/// to export the telemetry not yet exported.
+pub struct OtlpTelemetry {
+    tracer_provider: SdkTracerProvider,
+    meter_provider: SdkMeterProvider,
+}
+
+impl OtlpTelemetry {
+    /// Create a [`SdkTracer`] exporting its spans to the configured collector,
+    /// e.g. to be used by the [`tracing layer`].
+    ///
+    /// [`tracing layer`]: crate::telemetry::tracing::layer
+    #[must_use]
+    pub fn tracer(&self, name: impl Into<Cow<'static, str>>) -> SdkTracer {
+        self.tracer_provider.tracer(name)
+    }
+
+    /// The [`SdkTracerProvider`] exporting the trace spans.
+    #[must_use]
+    pub fn tracer_provider(&self) -> &SdkTracerProvider {
+        &self.tracer_provider
+    }
+
+    /// The [`SdkMeterProvider`] exporting the metrics.
+    #[must_use]
+    pub fn meter_provider(&self) -> &SdkMeterProvider {
+        &self.meter_provider
+    }
+
+    /// Export all telemetry not yet exported.
+    pub fn force_flush(&self) -> Result<(), OpaqueError> {
+        self.tracer_provider
+            .force_flush()
+            .context("flush OTLP traces")?;
+        self.meter_provider
+            .force_flush()
+            .context("flush OTLP metrics")
+    }
+
+    /// Export all telemetry not yet exported and shut the exporters down.
+    pub fn shutdown(&self) -> Result<(), OpaqueError> {
+        self.tracer_provider
+            .shutdown()
+            .context("shutdown OTLP traces exporter")?;
+        self.meter_provider
+            .shutdown()
+            .context("shutdown OTLP metrics exporter")
+    }
+}
+
+#[derive(Debug, Clone)]
+/// Http [`Service`] sending the OTLP export requests,
+/// bridging them to gRPC calls if the [`OtlpProtocol::Grpc`] is used.
+struct OtlpClient<S> {
+    inner: S,
+    grpc: bool,
+}
+
+impl<S> Service<Request> for OtlpClient<S>
+where
+    S: Service<Request, Response = Response, Error: Into<BoxError>>,
+{
+    type Response = Response;
+    type Error = BoxError;
+
+    async fn serve(&self, ctx: Context, req: Request) -> Result<Self::Response, Self::Error> {
+        if !self.grpc {
+            return self.inner.serve(ctx, req).await.map_err(Into::into);
+        }
+
+        let (mut parts, body) = req.into_parts();
+        let message = body.collect().await?.to_bytes();
+        parts.version = Version::HTTP_2;
+        parts.headers.remove(CONTENT_LENGTH);
+        parts
+            .headers
+            .insert(CONTENT_TYPE, HeaderValue::from_static("application/grpc"));
+        parts
+            .headers
+            .insert(TE, HeaderValue::from_static("trailers"));
+        let req = Request::from_parts(parts, Body::from(encode_grpc_message(&message)?));
+
+        let resp = self.inner.serve(ctx, req).await.map_err(Into::into)?;
+        let (parts, body) = resp.into_parts();
+        if parts.status != StatusCode::OK {
+            return Ok(Response::from_parts(parts, body));
+        }
+
+        let body = body.collect().await?;
+        // a call failing immediately sends its status as headers (trailers-only)
+        let status = parts
+            .headers
+            .get("grpc-status")
+            .or_else(|| {
+                body.trailers()
+                    .and_then(|trailers| trailers.get("grpc-status"))
+            })
+            .cloned();
+        match status {
+            Some(status) if status == "0" => (),
+            Some(status) => {
+                let message = parts
+                    .headers
+                    .get("grpc-message")
+                    .or_else(|| {
+                        body.trailers()
+                            .and_then(|trailers| trailers.get("grpc-message"))
+                    })
+                    .and_then(|message| message.to_str().ok())
+                    .unwrap_or_default();
+                return Err(OpaqueError::from_display(format!(
+                    "OTLP gRPC export failed with status {status:?}: {message}"
+                ))
+                .into_boxed());
+            }
+            None => {
+                return Err(
+                    OpaqueError::from_display("OTLP gRPC export without status").into_boxed()
+                );
+            }
+        }
+
+        let message = decode_grpc_message(body.to_bytes())?;
+        Ok(Response::from_parts(parts, Body::from(message)))
+    }
+}
+
+/// Encode a protobuf message as an uncompressed length-prefixed gRPC message.
+fn encode_grpc_message(message: &[u8]) -> Result<Bytes, OpaqueError> {
+    let len = u32::try_from(message.len()).context("OTLP gRPC message too large")?;
+    let mut buf = BytesMut::with_capacity(message.len() + 5);
+    buf.put_u8(0);
+    buf.put_u32(len);
+    buf.put_slice(message);
+    Ok(buf.freeze())
+}
+
+/// Decode the protobuf message of an uncompressed length-prefixed gRPC message.
+fn decode_grpc_message(mut buf: Bytes) -> Result<Bytes, OpaqueError> {
+    if buf.is_empty() {
+        return Ok(buf);
+    }
+    if buf.len() < 5 {
+        return Err(OpaqueError::from_display("truncated OTLP gRPC message"));
+    }
+    if buf.get_u8() != 0 {
+        return Err(OpaqueError::from_display(
+            "compressed OTLP gRPC messages are not supported",
+        ));
+    }
+    let len = usize::try_from(buf.get_u32()).context("OTLP gRPC message length")?;
+    if buf.len() < len {
+        return Err(OpaqueError::from_display("truncated OTLP gRPC message"));
+    }
+    Ok(buf.split_to(len))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::service::service_fn;
+    use std::convert::Infallible;
+
+    #[test]
+    fn test_from_vars() {
+        let cfg = OtlpConfig::from_vars(|key| {
+            match key {
+                "OTEL_EXPORTER_OTLP_ENDPOINT" => Some("http://collector:4317/"),
+                "OTEL_EXPORTER_OTLP_PROTOCOL" => Some("grpc"),
+                "OTEL_EXPORTER_OTLP_HEADERS" => Some("x-api-key=secret, x-tenant = rama"),
+                "OTEL_EXPORTER_OTLP_TIMEOUT" => Some("500"),
+                "OTEL_EXPORTER_OTLP_METRICS_ENDPOINT" => Some("http://metrics:4317"),
+                "OTEL_SERVICE_NAME" => Some("proxy"),
+                "OTEL_METRICS_EXPORTER" => Some("otlp"),
+                "OTEL_METRIC_EXPORT_INTERVAL" => Some("not a number"),
+                _ => None,
+            }
+            .map(Into::into)
+        });
+
+        assert_eq!(cfg.protocol, OtlpProtocol::Grpc);
+        assert_eq!(cfg.headers["x-api-key"], "secret");
+        assert_eq!(cfg.headers["x-tenant"], "rama");
+        assert_eq!(cfg.timeout, Duration::from_millis(500));
+        assert_eq!(cfg.service_name, "proxy");
+        assert!(cfg.traces);
+        assert!(cfg.metrics);
+        assert_eq!(cfg.metrics_interval, DEFAULT_METRICS_INTERVAL);
+        assert_eq!(
+            cfg.signal_endpoint(
+                cfg.traces_endpoint.as_deref(),
+                "/v1/traces",
+                GRPC_TRACES_PATH
+            ),
+            "http://collector:4317/opentelemetry.proto.collector.trace.v1.TraceService/Export"
+        );
+        assert_eq!(
+            cfg.signal_endpoint(
+                cfg.metrics_endpoint.as_deref(),
+                "/v1/metrics",
+                GRPC_METRICS_PATH
+            ),
+            "http://metrics:4317/opentelemetry.proto.collector.metrics.v1.MetricsService/Export"
+        );
+
+        let cfg = OtlpConfig::from_vars(|_| None);
+        assert_eq!(cfg.protocol, OtlpProtocol::HttpProtobuf);
+        assert!(cfg.traces);
+        assert!(!cfg.metrics);
+        assert_eq!(
+            cfg.signal_endpoint(None, "/v1/metrics", GRPC_METRICS_PATH),
+            "http://localhost:4318/v1/metrics"
+        );
+
+        let cfg = OtlpConfig::from_vars(|key| {
+            match key {
+                "OTEL_EXPORTER_OTLP_ENDPOINT" => Some("http://collector:4318"),
+                "OTEL_EXPORTER_OTLP_TRACES_ENDPOINT" => Some("http://traces:4318/custom"),
+                "OTEL_TRACES_EXPORTER" => Some("none"),
+                _ => None,
+            }
+            .map(Into::into)
+        });
+        assert!(!cfg.traces);
+        assert_eq!(
+            cfg.signal_endpoint(
+                cfg.traces_endpoint.as_deref(),
+                "/v1/traces",
+                GRPC_TRACES_PATH
+            ),
+            "http://traces:4318/custom"
+        );
+        assert_eq!(
+            cfg.signal_endpoint(
+                cfg.metrics_endpoint.as_deref(),
+                "/v1/metrics",
+                GRPC_METRICS_PATH
+            ),
+            "http://collector:4318/v1/metrics"
+        );
+    }
+
+    #[test]
+    fn test_grpc_message() {
+        let message = encode_grpc_message(b"spans").unwrap();
+        assert_eq!(&message[..], b"\0\0\0\0\x05spans");
+        assert_eq!(&decode_grpc_message(message).unwrap()[..], b"spans");
+
+        assert!(decode_grpc_message(Bytes::from_static(b"\x01\0\0\0\0")).is_err());
+        assert!(decode_grpc_message(Bytes::from_static(b"\0\0\0\0\x05span")).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_grpc_client() {
+        let client = OtlpClient {
+            inner: service_fn(async |req: Request| {
+                assert_eq!(req.version(), Version::HTTP_2);
+                assert_eq!(req.headers()[CONTENT_TYPE], "application/grpc");
+                assert_eq!(req.headers()[TE], "trailers");
+                let message = req.into_body().collect().await.unwrap().to_bytes();
+                let status = if &message[5..] == b"spans" { "0" } else { "3" };
+                Ok::<_, Infallible>(
+                    Response::builder()
+                        .header("grpc-status", status)
+                        .header("grpc-message", "invalid spans")
+                        .body(Body::from(encode_grpc_message(b"ok").unwrap()))
+                        .unwrap(),
+                )
+            }),
+            grpc: true,
+        };
+
+        let resp = client
+            .serve(Context::default(), Request::new(Body::from("spans")))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let body = resp.into_body().collect().await.unwrap().to_bytes();
+        assert_eq!(&body[..], b"ok");
+
+        let err = client
+            .serve(Context::default(), Request::new(Body::from("metrics")))
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("invalid spans"));
+    }
+}
@@ -0,0 +1,318 @@
+use crate::{
+    Context, Service,
+    error::{BoxError, ErrorContext, OpaqueError},
+    http::{
+        Body, HeaderName, HeaderValue, Request, Response, StatusCode,
+        client::HttpConnector,
+        dep::http_body_util::BodyExt,
+        header::CONTENT_TYPE,
+        service::web::response::{IntoResponse, Json},
+    },
+    net::{
+        client::EstablishedClientConnection,
+        stream::SocketInfo,
+        test_utils::stream::{MOCK_CLIENT_ADDR, MOCK_SERVER_ADDR, MockSocket},
+    },
+};
+use std::{fmt, sync::Mutex};
+
+/// The maximum number of bytes buffered in each direction of the in-memory connections.
+const MAX_BUFFER_SIZE: usize = 64 * 1024;
+
+/// An http client which serves each request over a new in-memory connection,
+/// using the given server service (e.g. an [`HttpServer`] service)
+/// as the other end of the connection.
+///
+/// This allows to test a full server stack, including its transport layers,
+/// without binding actual sockets. The server is served with a [`SocketInfo`]
+/// of which the peer address is [`MOCK_CLIENT_ADDR`] (a loopback address).
+///
+/// Requests are most easily created using the [`HttpClientExt`] methods,
+/// which are implemented for this client. Their uri has to be absolute.
+///
+/// # Example
+///
+/// ```
+/// use rama::{
+///     Context,
+///     http::{Request, Response, server::HttpServer, service::client::HttpClientExt},
+///     rt::Executor,
+///     test::{MockService, TestClient, TestResponse},
+/// };
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// let service = MockService::<Request, Response>::new().with_response(TestResponse::ok().text("hello"));
+/// let client = TestClient::new(HttpServer::auto(Executor::default()).service(service.clone()));
+///
+/// let resp = client
+///     .get("http://example.com/greeting")
+///     .header("x-test", "1")
+///     .send(Context::default())
+///     .await
+///     .unwrap();
+/// assert!(resp.status().is_success());
+///
+/// let requests = service.take_requests();
+/// assert_eq!(requests[0].uri().path(), "/greeting");
+/// assert_eq!(requests[0].headers()["x-test"], "1");
+/// # }
+/// ```
+///
+/// [`HttpServer`]: crate::http::server::HttpServer
+/// [`HttpClientExt`]: crate::http::service::client::HttpClientExt
+pub struct TestClient<S> {
+    server: S,
+}
+
+impl<S> TestClient<S> {
+    /// Create a new [`TestClient`] serving its requests using the given server service.
+    pub const fn new(server: S) -> Self {
+        Self { server }
+    }
+}
+
+impl<S: fmt::Debug> fmt::Debug for TestClient<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TestClient")
+            .field("server", &self.server)
+            .finish()
+    }
+}
+
+impl<S: Clone> Clone for TestClient<S> {
+    fn clone(&self) -> Self {
+        Self {
+            server: self.server.clone(),
+        }
+    }
+}
+
+impl<S> Service<Request> for TestClient<S>
+where
+    S: Service<MockSocket, Response = (), Error: Into<BoxError>>,
+{
+    type Response = Response;
+    type Error = BoxError;
+
+    async fn serve(&self, ctx: Context, req: Request) -> Result<Self::Response, Self::Error> {
+        let (client_socket, server_socket) = MockSocket::pair(MAX_BUFFER_SIZE);
+
+        let mut server_ctx = Context::default();
+        server_ctx.insert(SocketInfo::new(Some(MOCK_SERVER_ADDR), MOCK_CLIENT_ADDR));
+        let server = async move {
+            self.server
+                .serve(server_ctx, server_socket)
+                .await
+                .map_err(Into::into)
+        };
+
+        let client = async move {
+            let connector = HttpConnector::new(OnceConnector(Mutex::new(Some(client_socket))));
+            let EstablishedClientConnection { ctx, req, conn } = connector.serve(ctx, req).await?;
+            let resp = conn.serve(ctx, req).await?;
+
+            // collect the body, such that the connection can be closed
+            // once the client connection is dropped
+            let (parts, body) = resp.into_parts();
+            let body = body.collect().await?.to_bytes();
+            Ok::<_, BoxError>(Response::from_parts(parts, Body::from(body)))
+        };
+
+        let (server_result, client_result) = tokio::join!(server, client);
+        client_result.map_err(|err| match server_result {
+            Err(server_err) => OpaqueError::from_display(format!(
+                "client failed: {err}; server failed: {server_err}"
+            ))
+            .into_boxed(),
+            Ok(()) => err,
+        })
+    }
+}
+
+/// Serve a single request over an in-memory connection, using the given server service.
+///
+/// See [`TestClient`] for more information.
+pub async fn serve_once<S>(server: S, req: Request) -> Result<Response, BoxError>
+where
+    S: Service<MockSocket, Response = (), Error: Into<BoxError>>,
+{
+    TestClient::new(server).serve(Context::default(), req).await
+}
+
+/// Connector handing out its [`MockSocket`] to the first request only.
+struct OnceConnector(Mutex<Option<MockSocket>>);
+
+impl Service<Request> for OnceConnector {
+    type Response = EstablishedClientConnection<MockSocket, Request>;
+    type Error = OpaqueError;
+
+    async fn serve(&self, ctx: Context, req: Request) -> Result<Self::Response, Self::Error> {
+        let conn = self
+            .0
+            .lock()
+            .ok()
+            .and_then(|mut socket| socket.take())
+            .context("test client connection already used")?;
+        Ok(EstablishedClientConnection { ctx, req, conn })
+    }
+}
+
+#[derive(Debug)]
+/// A builder of an http [`Response`], e.g. to be returned by a [`MockService`].
+///
+/// # Panics
+///
+/// The builder methods panic in case they are given an invalid header
+/// or a value which cannot be serialized as JSON, as is expected from a test utility.
+///
+/// [`MockService`]: super::MockService
+pub struct TestResponse {
+    response: Response,
+}
+
+impl TestResponse {
+    /// Create a new [`TestResponse`] with the given status and an empty body.
+    #[must_use]
+    pub fn new(status: StatusCode) -> Self {
+        Self {
+            response: status.into_response(),
+        }
+    }
+
+    /// Create a new `200 OK` [`TestResponse`] with an empty body.
+    #[must_use]
+    pub fn ok() -> Self {
+        Self::new(StatusCode::OK)
+    }
+
+    /// Set the status of the response.
+    #[must_use]
+    pub fn status(mut self, status: StatusCode) -> Self {
+        *self.response.status_mut() = status;
+        self
+    }
+
+    /// Append a header to the response.
+    #[must_use]
+    pub fn header<K, V>(mut self, name: K, value: V) -> Self
+    where
+        K: TryInto<HeaderName, Error: fmt::Debug>,
+        V: TryInto<HeaderValue, Error: fmt::Debug>,
+    {
+        self.response.headers_mut().append(
+            name.try_into().expect("valid header name"),
+            value.try_into().expect("valid header value"),
+        );
+        self
+    }
+
+    /// Set the body of the response.
+    #[must_use]
+    pub fn body(mut self, body: impl Into<Body>) -> Self {
+        *self.response.body_mut() = body.into();
+        self
+    }
+
+    /// Set a plain text body, together with its `Content-Type` header.
+    #[must_use]
+    pub fn text(mut self, text: impl Into<String>) -> Self {
+        self.response.headers_mut().insert(
+            CONTENT_TYPE,
+            HeaderValue::from_static("text/plain; charset=utf-8"),
+        );
+        self.body(text.into())
+    }
+
+    /// Set the value serialized as JSON body, together with its `Content-Type` header.
+    #[must_use]
+    pub fn json<T>(mut self, value: T) -> Self
+    where
+        Json<T>: IntoResponse,
+    {
+        let (parts, body) = Json(value).into_response().into_parts();
+        assert!(parts.status.is_success(), "serialize json body");
+        self.response.headers_mut().extend(parts.headers);
+        self.body(body)
+    }
+
+    /// Build the [`Response`].
+    #[must_use]
+    pub fn build(self) -> Response {
+        self.response
+    }
+}
+
+impl From<TestResponse> for Response {
+    fn from(response: TestResponse) -> Self {
+        response.response
+    }
+}
+
+impl IntoResponse for TestResponse {
+    fn into_response(self) -> Response {
+        self.response
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        Layer,
+        http::{
+            BodyExtractExt, StatusCode, layer::sanitize_headers::SanitizeHeadersLayer,
+            server::HttpServer, service::client::HttpClientExt,
+        },
+        rt::Executor,
+        test::MockService,
+    };
+
+    #[tokio::test]
+    async fn test_serve_once() {
+        let service = MockService::<Request, Response>::new()
+            .with_response(
+                TestResponse::new(StatusCode::CREATED).json(serde_json::json!({
+                    "id": 1,
+                })),
+            )
+            .with_response(
+                TestResponse::ok()
+                    .header("connection", "x-hop")
+                    .header("x-hop", "1"),
+            );
+        let server = HttpServer::auto(Executor::default())
+            .service(SanitizeHeadersLayer::new().into_layer(service.clone()));
+
+        let resp = serve_once(
+            server.clone(),
+            Request::builder()
+                .method("POST")
+                .uri("http://localhost/items")
+                .body(Body::from("item"))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(resp.status(), StatusCode::CREATED);
+        assert_eq!(resp.headers()[CONTENT_TYPE], "application/json");
+        let value: serde_json::Value = resp.into_body().try_into_json().await.unwrap();
+        assert_eq!(value["id"], 1);
+
+        let client = TestClient::new(server);
+        let resp = client
+            .get("http://localhost/hop")
+            .header("connection", "x-hop")
+            .header("x-hop", "1")
+            .send(Context::default())
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert!(!resp.headers().contains_key("x-hop"));
+
+        service.assert_called(2);
+        let requests = service.take_requests();
+        assert_eq!(requests[0].uri().path(), "/items");
+        assert!(!requests[1].headers().contains_key("x-hop"));
+    }
+}
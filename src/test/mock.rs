@@ -0,0 +1,210 @@
+use crate::{Context, Service};
+use std::{
+    collections::VecDeque,
+    convert::Infallible,
+    fmt,
+    sync::{Arc, Mutex, PoisonError},
+};
+
+/// A [`Service`] mock which returns canned responses
+/// and records the requests it served, such that they can be asserted.
+///
+/// Clones share the same canned responses and recorded requests,
+/// such that a clone can be moved into the service stack under test,
+/// while the original is kept to make assertions.
+///
+/// The canned responses (and errors) are returned in the order they were added,
+/// after which the response function is used, if any.
+///
+/// # Panics
+///
+/// Serving a request panics in case no canned response is left
+/// and no response function is set.
+///
+/// # Example
+///
+/// ```
+/// use rama::{Context, Layer, Service, layer::MapRequestLayer, test::MockService};
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// let mock = MockService::<String, &str>::new()
+///     .with_response("first")
+///     .with_response_fn(|req: &String| if req.is_empty() { "empty" } else { "other" });
+///
+/// let service = MapRequestLayer::new(|req: &str| req.to_uppercase()).into_layer(mock.clone());
+///
+/// assert_eq!(service.serve(Context::default(), "hello").await, Ok("first"));
+/// assert_eq!(service.serve(Context::default(), "").await, Ok("empty"));
+/// assert_eq!(service.serve(Context::default(), "world").await, Ok("other"));
+///
+/// mock.assert_called(3);
+/// assert_eq!(mock.take_requests(), ["HELLO", "", "WORLD"]);
+/// # }
+/// ```
+pub struct MockService<Request, Response, Error = Infallible> {
+    state: Arc<Mutex<MockState<Request, Response, Error>>>,
+}
+
+struct MockState<Request, Response, Error> {
+    responses: VecDeque<Result<Response, Error>>,
+    response_fn: Option<Arc<ResponseFn<Request, Response>>>,
+    requests: Vec<Request>,
+    calls: usize,
+}
+
+type ResponseFn<Request, Response> = dyn Fn(&Request) -> Response + Send + Sync;
+
+impl<Request, Response, Error> MockService<Request, Response, Error> {
+    /// Create a new [`MockService`] without any canned response.
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            state: Arc::new(Mutex::new(MockState {
+                responses: VecDeque::new(),
+                response_fn: None,
+                requests: Vec::new(),
+                calls: 0,
+            })),
+        }
+    }
+
+    fn with_state<T>(&self, f: impl FnOnce(&mut MockState<Request, Response, Error>) -> T) -> T {
+        // a poisoned lock only means an assertion failed while holding it
+        f(&mut self.state.lock().unwrap_or_else(PoisonError::into_inner))
+    }
+
+    /// Add a canned response, returned after the canned responses added before it.
+    #[must_use]
+    pub fn with_response(self, response: impl Into<Response>) -> Self {
+        self.push_response(response);
+        self
+    }
+
+    /// Add a canned response, returned after the canned responses added before it.
+    pub fn push_response(&self, response: impl Into<Response>) {
+        self.with_state(|state| state.responses.push_back(Ok(response.into())));
+    }
+
+    /// Add a canned error, returned after the canned responses added before it.
+    #[must_use]
+    pub fn with_error(self, error: impl Into<Error>) -> Self {
+        self.push_error(error);
+        self
+    }
+
+    /// Add a canned error, returned after the canned responses added before it.
+    pub fn push_error(&self, error: impl Into<Error>) {
+        self.with_state(|state| state.responses.push_back(Err(error.into())));
+    }
+
+    /// Set the function creating the response for a request,
+    /// used once no canned response is left.
+    #[must_use]
+    pub fn with_response_fn(
+        self,
+        f: impl Fn(&Request) -> Response + Send + Sync + 'static,
+    ) -> Self {
+        self.set_response_fn(f);
+        self
+    }
+
+    /// Set the function creating the response for a request,
+    /// used once no canned response is left.
+    pub fn set_response_fn(&self, f: impl Fn(&Request) -> Response + Send + Sync + 'static) {
+        self.with_state(|state| state.response_fn = Some(Arc::new(f)));
+    }
+
+    /// Returns the number of requests served so far.
+    #[must_use]
+    pub fn calls(&self) -> usize {
+        self.with_state(|state| state.calls)
+    }
+
+    /// Returns the number of canned responses and errors not yet returned.
+    #[must_use]
+    pub fn remaining(&self) -> usize {
+        self.with_state(|state| state.responses.len())
+    }
+
+    /// Take the requests recorded so far, in the order they were served.
+    #[must_use]
+    pub fn take_requests(&self) -> Vec<Request> {
+        self.with_state(|state| std::mem::take(&mut state.requests))
+    }
+
+    /// Assert that exactly the given number of requests were served so far.
+    ///
+    /// # Panics
+    ///
+    /// Panics in case a different number of requests was served.
+    #[track_caller]
+    pub fn assert_called(&self, times: usize) {
+        let calls = self.calls();
+        assert_eq!(
+            calls, times,
+            "expected MockService to be called {times} time(s), but it was called {calls} time(s)"
+        );
+    }
+
+    /// Assert that no request was served so far.
+    ///
+    /// # Panics
+    ///
+    /// Panics in case a request was served.
+    #[track_caller]
+    pub fn assert_not_called(&self) {
+        self.assert_called(0);
+    }
+}
+
+impl<Request, Response, Error> Default for MockService<Request, Response, Error> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<Request, Response, Error> Clone for MockService<Request, Response, Error> {
+    fn clone(&self) -> Self {
+        Self {
+            state: self.state.clone(),
+        }
+    }
+}
+
+impl<Request, Response, Error> fmt::Debug for MockService<Request, Response, Error> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MockService")
+            .field("calls", &self.calls())
+            .field("remaining", &self.remaining())
+            .finish()
+    }
+}
+
+impl<Request, Response, Error> Service<Request> for MockService<Request, Response, Error>
+where
+    Request: Send + 'static,
+    Response: Send + 'static,
+    Error: Send + 'static,
+{
+    type Response = Response;
+    type Error = Error;
+
+    async fn serve(&self, _ctx: Context, req: Request) -> Result<Self::Response, Self::Error> {
+        self.with_state(|state| {
+            state.calls += 1;
+            let result = match state.responses.pop_front() {
+                Some(result) => result,
+                None => match &state.response_fn {
+                    Some(f) => Ok(f(&req)),
+                    None => panic!(
+                        "MockService called {} time(s), without any canned response left",
+                        state.calls
+                    ),
+                },
+            };
+            state.requests.push(req);
+            result
+        })
+    }
+}
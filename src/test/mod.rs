@@ -0,0 +1,29 @@
+//! Utilities to test your own services and layers, without binding actual sockets.
+//!
+//! - [`MockService`]: a [`Service`] returning canned responses, recording the requests it served;
+//! - [`MockSocket`]: an in-memory duplex stream with fake socket addresses,
+//!   e.g. to serve transport services such as an [`HttpServer`];
+//! - [`TestClient`] and [`serve_once`]: serve http requests using your server stack
+//!   over in-memory connections, with the requests built using [`HttpClientExt`];
+//! - [`TestResponse`]: a builder of canned http responses.
+//!
+//! [`Service`]: crate::Service
+//! [`HttpServer`]: crate::http::server::HttpServer
+//! [`HttpClientExt`]: crate::http::service::client::HttpClientExt
+
+mod mock;
+#[doc(inline)]
+pub use mock::MockService;
+
+#[cfg(feature = "net")]
+#[doc(inline)]
+pub use ::rama_net::test_utils::{
+    client::MockConnectorService,
+    stream::{ChaosConfig, ChaosStream, MOCK_CLIENT_ADDR, MOCK_SERVER_ADDR, MockSocket},
+};
+
+#[cfg(feature = "http-full")]
+mod http;
+#[cfg(feature = "http-full")]
+#[doc(inline)]
+pub use http::{TestClient, TestResponse, serve_once};